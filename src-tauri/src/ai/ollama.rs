@@ -14,6 +14,8 @@ use std::io::{Read, Write};
 use std::net::TcpStream;
 use std::time::Duration;
 
+use crate::error::AppError;
+
 /// Provider identifiers accepted in the `ai_provider` setting. Matching is
 /// case-insensitive and tolerant of the common aliases so a user typing
 /// "OpenAI" or "openai-compatible" still lands on the compatibility path.
@@ -335,22 +337,24 @@ fn http_roundtrip(
 /// Sends a single non-streaming completion request to the configured local
 /// runtime and returns the model's text response. Routes to Ollama's
 /// `/api/generate` or an OpenAI-compatible `/v1/chat/completions` depending on
-/// `config.provider`.
-pub fn generate(config: &OllamaConfig, model: &str, prompt: &str, images_b64: &[String]) -> Result<String, String> {
+/// `config.provider`. Every failure is the runtime's, so it surfaces as an
+/// `external_service` error naming "ollama" whichever provider is configured.
+pub fn generate(config: &OllamaConfig, model: &str, prompt: &str, images_b64: &[String]) -> Result<String, AppError> {
+    let runtime = |message: String| AppError::external("ollama", message);
     let (path, body) = if config.uses_openai_api() {
         ("/v1/chat/completions", build_openai_chat_request(model, prompt, images_b64).to_string())
     } else {
         ("/api/generate", build_generate_request(model, prompt, images_b64).to_string())
     };
 
-    let (status, resp_body) = http_roundtrip(config, "POST", path, Some(&body))?;
+    let (status, resp_body) = http_roundtrip(config, "POST", path, Some(&body)).map_err(runtime)?;
     if status != 200 {
-        return Err(classify_status_error(&config.provider, status, &resp_body, model));
+        return Err(runtime(classify_status_error(&config.provider, status, &resp_body, model)));
     }
     if config.uses_openai_api() {
-        parse_openai_chat_response(&resp_body)
+        parse_openai_chat_response(&resp_body).map_err(runtime)
     } else {
-        parse_generate_response(&resp_body)
+        parse_generate_response(&resp_body).map_err(runtime)
     }
 }
 
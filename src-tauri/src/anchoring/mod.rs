@@ -28,6 +28,10 @@
 
 use serde::Serialize;
 
+use crate::error::AppError;
+
+pub mod node_rpc;
+pub mod spv;
 pub mod store;
 
 /// `OP_RETURN` opcode — marks a provably-unspendable, data-carrying output.
@@ -207,9 +211,9 @@ pub struct AnchorPayloadPreview {
 }
 
 /// Build the preview for a checkpoint's Merkle root.
-pub fn build_payload_preview(merkle_root_hex: &str, chain_name: &str) -> Result<AnchorPayloadPreview, String> {
-    let payload = build_anchor_payload(merkle_root_hex)?;
-    let script = build_op_return_script(merkle_root_hex)?;
+pub fn build_payload_preview(merkle_root_hex: &str, chain_name: &str) -> Result<AnchorPayloadPreview, AppError> {
+    let payload = build_anchor_payload(merkle_root_hex).map_err(|e| AppError::validation("merkle_root", e))?;
+    let script = build_op_return_script(merkle_root_hex).map_err(|e| AppError::validation("merkle_root", e))?;
     Ok(AnchorPayloadPreview {
         merkle_root: merkle_root_hex.to_lowercase(),
         payload_hex: hex_encode(&payload),
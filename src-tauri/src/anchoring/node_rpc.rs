@@ -0,0 +1,803 @@
+// WP-82: broadcast anchors through the lab's own bitcoind-compatible node.
+//
+// WP-66 stopped at preparing the `OP_RETURN` bytes; the operator broadcast them
+// with an external wallet and pasted the txid back. A lab that runs its own
+// Dogecoin or Bitcoin Core node can now let SteloPTC do both halves over the
+// node's JSON-RPC interface:
+//
+//   createrawtransaction [] {"data": <payload>}   → an unfunded tx with the anchor output
+//   fundrawtransaction                            → the node's wallet adds inputs + change
+//   signrawtransactionwithwallet                  → signed by the node's wallet
+//   sendrawtransaction                            → broadcast, returns the txid
+//
+// and later polls `gettransaction` until the anchor has `min_confirmations`,
+// at which point the committed root is re-extracted from the node's decoded
+// transaction and checked with the same `store::verify_anchor` a manual paste
+// uses. The keys never leave the node; SteloPTC only holds the RPC credentials.
+//
+// Once an anchor confirms, its SPV proof (WP-83) is captured from
+// `getblockheader <hash> false` and `getblock <hash> 1`, so the exported proof
+// no longer needs the node or an explorer to show the transaction is mined.
+//
+// Dogecoin Core 1.14 predates `signrawtransactionwithwallet`; when the node
+// answers "method not found" the older `signrawtransaction` is used instead.
+//
+// Like `ai::ollama`, the transport is a small HTTP/1.1 client over
+// `std::net::TcpStream` (bitcoind's RPC server speaks plain HTTP with Basic
+// auth). Everything above the socket goes through the `RpcTransport` trait, so
+// the broadcast and poll flows are unit-tested against a scripted node. A node
+// that is unreachable or answers with an error is an
+// `AppError::external("node_rpc", …)`.
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine as _;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::spv::{self, SpvVerification};
+use super::store::{self, now_iso, CheckpointAnchor};
+use super::{extract_root_from_hex, hex_decode, hex_encode, OP_RETURN};
+use crate::error::AppError;
+
+/// JSON-RPC "method not found" — how an older node says it lacks a call.
+pub const RPC_METHOD_NOT_FOUND: i64 = -32601;
+
+const RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Display form of `anchor_node_config`. Never carries the password.
+#[derive(Debug, Serialize)]
+pub struct NodeRpcConfig {
+    pub enabled: bool,
+    pub rpc_url: Option<String>,
+    pub username: Option<String>,
+    pub password_set: bool,
+    pub wallet: Option<String>,
+    pub min_confirmations: i64,
+}
+
+/// `password: None` keeps the stored password, as `set_smtp_config` does.
+#[derive(Debug, Deserialize)]
+pub struct SetNodeRpcConfigRequest {
+    pub enabled: bool,
+    pub rpc_url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub wallet: Option<String>,
+    pub min_confirmations: i64,
+}
+
+/// An error from the node. `code` is the JSON-RPC error code when the node
+/// returned one, so callers can tell "method not found" from a real failure.
+#[derive(Debug)]
+pub struct RpcError {
+    pub code: Option<i64>,
+    pub message: String,
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "Node RPC error {}: {}", code, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl From<RpcError> for String {
+    fn from(e: RpcError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<RpcError> for AppError {
+    fn from(e: RpcError) -> Self {
+        AppError::external("node_rpc", e.to_string())
+    }
+}
+
+fn transport_error(message: String) -> RpcError {
+    RpcError { code: None, message }
+}
+
+/// One JSON-RPC call against a node. Implemented over HTTP by [`HttpRpc`] and
+/// by a scripted node in the tests.
+pub trait RpcTransport {
+    fn call(&self, method: &str, params: Value) -> Result<Value, RpcError>;
+}
+
+/// Split `http://host:port[/]` into host and port. bitcoind's RPC server has no
+/// TLS, and there is no single default port across chains and networks
+/// (8332, 18443, 22555, …), so both the scheme and the port are required.
+pub fn parse_rpc_url(url: &str) -> Result<(String, u16), String> {
+    let rest = url
+        .trim()
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("Node RPC URL must start with http:// (got '{}')", url.trim()))?;
+    let rest = rest.trim_end_matches('/');
+    let (host, port) = rest
+        .rsplit_once(':')
+        .ok_or_else(|| format!("Node RPC URL must include a port, e.g. http://127.0.0.1:18443 (got '{}')", url.trim()))?;
+    if host.is_empty() || host.contains('/') {
+        return Err(format!("Invalid node RPC URL '{}'", url.trim()));
+    }
+    let port: u16 = port.parse().map_err(|_| format!("Invalid port in node RPC URL '{}'", url.trim()))?;
+    Ok((host.to_string(), port))
+}
+
+/// The HTTP path for a call: `/wallet/<name>` when a wallet is named (needed by
+/// multi-wallet nodes for the wallet calls), otherwise `/`.
+pub fn rpc_path(wallet: Option<&str>) -> String {
+    match wallet.map(str::trim).filter(|w| !w.is_empty()) {
+        Some(w) => format!("/wallet/{}", w),
+        None => "/".to_string(),
+    }
+}
+
+/// JSON-RPC 1.0 request body, the dialect bitcoind documents.
+pub fn build_rpc_body(method: &str, params: &Value) -> String {
+    json!({ "jsonrpc": "1.0", "id": "stelo-ptc", "method": method, "params": params }).to_string()
+}
+
+/// Interpret a node's HTTP response. bitcoind answers RPC errors with HTTP 500
+/// and a JSON body carrying `error`, and a bad password with a bare 401.
+pub fn parse_rpc_response(status: u16, body: &str) -> Result<Value, RpcError> {
+    if status == 401 || status == 403 {
+        return Err(transport_error("The node rejected the RPC username or password".to_string()));
+    }
+    let parsed: Value = serde_json::from_str(body).map_err(|_| {
+        transport_error(format!("Node returned HTTP {} with a non-JSON body: {}", status, body.trim()))
+    })?;
+    if let Some(err) = parsed.get("error").filter(|e| !e.is_null()) {
+        return Err(RpcError {
+            code: err.get("code").and_then(Value::as_i64),
+            message: err.get("message").and_then(Value::as_str).unwrap_or("unknown error").to_string(),
+        });
+    }
+    if status != 200 {
+        return Err(transport_error(format!("Node returned HTTP {}", status)));
+    }
+    Ok(parsed.get("result").cloned().unwrap_or(Value::Null))
+}
+
+/// JSON-RPC over a plain HTTP/1.1 socket to the configured node.
+pub struct HttpRpc {
+    host: String,
+    port: u16,
+    path: String,
+    authorization: Option<String>,
+}
+
+impl HttpRpc {
+    pub fn new(rpc_url: &str, username: Option<&str>, password: Option<&str>, wallet: Option<&str>) -> Result<Self, AppError> {
+        let (host, port) = parse_rpc_url(rpc_url).map_err(|e| AppError::validation("rpc_url", e))?;
+        let authorization = username
+            .filter(|u| !u.is_empty())
+            .map(|u| format!("Basic {}", B64.encode(format!("{}:{}", u, password.unwrap_or("")))));
+        Ok(Self { host, port, path: rpc_path(wallet), authorization })
+    }
+}
+
+impl RpcTransport for HttpRpc {
+    fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let body = build_rpc_body(method, &params);
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).map_err(|e| {
+            transport_error(format!("Could not reach the node at {}:{} — is it running? ({})", self.host, self.port, e))
+        })?;
+        stream.set_read_timeout(Some(RPC_TIMEOUT)).ok();
+        stream.set_write_timeout(Some(RPC_TIMEOUT)).ok();
+        let auth = self
+            .authorization
+            .as_ref()
+            .map(|a| format!("Authorization: {}\r\n", a))
+            .unwrap_or_default();
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            auth,
+            body.len(),
+            body
+        );
+        stream
+            .write_all(request.as_bytes())
+            .map_err(|e| transport_error(format!("Failed to send {} to the node: {}", method, e)))?;
+        let mut raw = Vec::new();
+        stream
+            .read_to_end(&mut raw)
+            .map_err(|e| transport_error(format!("Failed to read the node's reply to {}: {}", method, e)))?;
+        let (status, body) = crate::ai::ollama::parse_http_response(&raw).map_err(transport_error)?;
+        parse_rpc_response(status, &body)
+    }
+}
+
+pub fn get_config(conn: &Connection) -> Result<NodeRpcConfig, AppError> {
+    conn.query_row(
+        "SELECT enabled, rpc_url, username, password, wallet, min_confirmations FROM anchor_node_config WHERE id = 1",
+        [],
+        |r| {
+            let password: Option<String> = r.get(3)?;
+            Ok(NodeRpcConfig {
+                enabled: r.get::<_, i64>(0)? != 0,
+                rpc_url: r.get(1)?,
+                username: r.get(2)?,
+                password_set: password.is_some_and(|p| !p.is_empty()),
+                wallet: r.get(4)?,
+                min_confirmations: r.get(5)?,
+            })
+        },
+    )
+    .map_err(AppError::from)
+}
+
+/// Save the node settings. The URL is validated even when the feature is being
+/// disabled, so a stored URL is always usable once it is switched back on.
+pub fn set_config(conn: &Connection, req: &SetNodeRpcConfigRequest) -> Result<(), AppError> {
+    if !req.rpc_url.trim().is_empty() || req.enabled {
+        parse_rpc_url(&req.rpc_url).map_err(|e| AppError::validation("rpc_url", e))?;
+    }
+    if !(1..=100).contains(&req.min_confirmations) {
+        return Err(AppError::validation("min_confirmations", "Required confirmations must be between 1 and 100"));
+    }
+    let trimmed = |v: &Option<String>| v.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    let url = Some(req.rpc_url.trim().to_string()).filter(|u| !u.is_empty());
+    conn.execute(
+        "UPDATE anchor_node_config SET enabled = ?1, rpc_url = ?2, username = ?3, wallet = ?4, \
+         min_confirmations = ?5, updated_at = datetime('now') WHERE id = 1",
+        params![req.enabled as i64, url, trimmed(&req.username), trimmed(&req.wallet), req.min_confirmations],
+    )
+    .map_err(|e| AppError::internal(format!("Failed to save node settings: {}", e)))?;
+    if let Some(password) = &req.password {
+        conn.execute("UPDATE anchor_node_config SET password = ?1 WHERE id = 1", params![password])
+            .map_err(|e| AppError::internal(format!("Failed to save node settings: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Build the transport for the configured node. Errors when node broadcasting
+/// is switched off, so nothing reaches the network unless an admin enabled it.
+pub fn connect(conn: &Connection) -> Result<HttpRpc, AppError> {
+    let (enabled, url, username, password, wallet): (i64, Option<String>, Option<String>, Option<String>, Option<String>) = conn
+        .query_row(
+            "SELECT enabled, rpc_url, username, password, wallet FROM anchor_node_config WHERE id = 1",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+        )?;
+    if enabled == 0 {
+        return Err(AppError::invalid(
+            "Broadcasting through a node is not enabled — configure it under On-chain anchoring.",
+        ));
+    }
+    let url = url.ok_or_else(|| AppError::validation("rpc_url", "Node RPC URL is not configured"))?;
+    HttpRpc::new(&url, username.as_deref(), password.as_deref(), wallet.as_deref())
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodeInfo {
+    pub chain: String,
+    pub blocks: i64,
+    pub wallet_balance: Option<f64>,
+}
+
+/// Connection check for the settings panel: which chain the node is on, its
+/// height, and (if the wallet answers) the balance available for fees.
+pub fn node_info(rpc: &dyn RpcTransport) -> Result<NodeInfo, AppError> {
+    let info = rpc.call("getblockchaininfo", json!([]))?;
+    Ok(NodeInfo {
+        chain: info.get("chain").and_then(Value::as_str).unwrap_or("unknown").to_string(),
+        blocks: info.get("blocks").and_then(Value::as_i64).unwrap_or(0),
+        wallet_balance: rpc.call("getbalance", json!([])).ok().and_then(|b| b.as_f64()),
+    })
+}
+
+fn str_result(v: &Value, field: Option<&str>, method: &str) -> Result<String, AppError> {
+    let target = match field {
+        Some(f) => v.get(f),
+        None => Some(v),
+    };
+    target
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| AppError::external("node_rpc", format!("Unexpected reply from {}: {}", method, v)))
+}
+
+fn sign_with_wallet(rpc: &dyn RpcTransport, funded_hex: &str) -> Result<String, AppError> {
+    let signed = match rpc.call("signrawtransactionwithwallet", json!([funded_hex])) {
+        Err(RpcError { code: Some(RPC_METHOD_NOT_FOUND), .. }) => rpc.call("signrawtransaction", json!([funded_hex]))?,
+        other => other?,
+    };
+    if signed.get("complete").and_then(Value::as_bool) != Some(true) {
+        return Err(AppError::external(
+            "node_rpc",
+            format!(
+                "The node's wallet could not fully sign the anchor transaction: {}",
+                signed.get("errors").map(|e| e.to_string()).unwrap_or_default()
+            ),
+        ));
+    }
+    str_result(&signed, Some("hex"), "signrawtransactionwithwallet")
+}
+
+/// Broadcast a `prepared` anchor through the node, record its txid and move it
+/// to `submitted`. The signed transaction is checked to still carry the exact
+/// anchor script before it is sent, since it is the node's wallet that built
+/// the rest of it.
+pub fn broadcast_anchor(conn: &Connection, rpc: &dyn RpcTransport, anchor_id: &str) -> Result<CheckpointAnchor, AppError> {
+    let anchor = store::get_anchor(conn, anchor_id)?;
+    if anchor.status != "prepared" {
+        return Err(AppError::invalid(format!(
+            "Anchor is already {}; only a prepared anchor can be broadcast",
+            anchor.status
+        )));
+    }
+    if !extract_root_from_hex(&anchor.op_return_hex).map_err(AppError::integrity)?.eq_ignore_ascii_case(&anchor.merkle_root) {
+        return Err(AppError::integrity(
+            "The anchor's stored OP_RETURN does not commit to its Merkle root; prepare it again",
+        ));
+    }
+    let script = hex_decode(&anchor.op_return_hex).map_err(AppError::integrity)?;
+    if script.len() < 2 || script[0] != OP_RETURN {
+        return Err(AppError::integrity("The anchor's stored script is not an OP_RETURN output"));
+    }
+    let payload_hex = hex_encode(&script[2..]);
+
+    let raw = rpc.call("createrawtransaction", json!([[], { "data": payload_hex }]))?;
+    let raw = str_result(&raw, None, "createrawtransaction")?;
+    let funded = rpc.call("fundrawtransaction", json!([raw]))?;
+    let funded = str_result(&funded, Some("hex"), "fundrawtransaction")?;
+    let signed = sign_with_wallet(rpc, &funded)?;
+
+    // A serialized output script is preceded by its length (0x27 = 39 bytes).
+    let expected = format!("{:02x}{}", script.len(), anchor.op_return_hex.to_lowercase());
+    if !signed.to_lowercase().contains(&expected) {
+        return Err(AppError::external(
+            "node_rpc",
+            "The signed transaction does not carry the anchor OP_RETURN output; nothing was broadcast",
+        ));
+    }
+
+    let txid = rpc.call("sendrawtransaction", json!([signed]))?;
+    let txid = str_result(&txid, None, "sendrawtransaction")?;
+    store::record_anchor_txid(conn, anchor_id, &txid)?;
+    conn.execute(
+        "UPDATE checkpoint_anchors SET broadcast_via = 'node_rpc', confirmations = 0 WHERE id = ?1",
+        params![anchor_id],
+    )?;
+    store::get_anchor(conn, anchor_id)
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnchorPollResult {
+    pub anchor_id: String,
+    pub txid: Option<String>,
+    pub confirmations: Option<i64>,
+    pub block_hash: Option<String>,
+    /// True when this poll moved the anchor to `confirmed`.
+    pub confirmed: bool,
+    pub message: String,
+}
+
+/// Look the transaction up in the node's wallet, falling back to
+/// `getrawtransaction` (needs `-txindex`) for an anchor broadcast elsewhere.
+fn lookup_transaction(rpc: &dyn RpcTransport, txid: &str) -> Result<Value, AppError> {
+    match rpc.call("gettransaction", json!([txid])) {
+        Ok(tx) => Ok(tx),
+        Err(wallet_err) => rpc.call("getrawtransaction", json!([txid, true])).map_err(|e| {
+            AppError::external("node_rpc", format!("The node does not know transaction {} ({}; {})", txid, wallet_err, e))
+        }),
+    }
+}
+
+/// The first output script in a decoded transaction that is a SteloPTC anchor.
+fn anchor_output_script(decoded: &Value) -> Option<String> {
+    decoded.get("vout")?.as_array()?.iter().find_map(|out| {
+        let hex = out.get("scriptPubKey")?.get("hex")?.as_str()?;
+        extract_root_from_hex(hex).ok().map(|_| hex.to_string())
+    })
+}
+
+/// Poll one `submitted` anchor. Records the confirmation count and block hash;
+/// once the count reaches `min_confirmations`, decodes the transaction and
+/// confirms the anchor through `store::verify_anchor`.
+pub fn poll_anchor(
+    conn: &Connection,
+    rpc: &dyn RpcTransport,
+    anchor_id: &str,
+    min_confirmations: i64,
+) -> Result<AnchorPollResult, AppError> {
+    let anchor = store::get_anchor(conn, anchor_id)?;
+    let txid = match (&anchor.status[..], &anchor.txid) {
+        ("submitted", Some(txid)) => txid.clone(),
+        _ => {
+            return Err(AppError::invalid(format!(
+                "Anchor is {}; only a submitted anchor with a txid can be polled",
+                anchor.status
+            )))
+        }
+    };
+    let tx = lookup_transaction(rpc, &txid)?;
+    let confirmations = tx.get("confirmations").and_then(Value::as_i64).unwrap_or(0);
+    let block_hash = tx.get("blockhash").and_then(Value::as_str).map(str::to_string);
+    conn.execute(
+        "UPDATE checkpoint_anchors SET confirmations = ?1, block_hash = ?2, last_polled_at = ?3 WHERE id = ?4",
+        params![confirmations, block_hash, now_iso(), anchor_id],
+    )?;
+
+    let mut result = AnchorPollResult {
+        anchor_id: anchor_id.to_string(),
+        txid: Some(txid.clone()),
+        confirmations: Some(confirmations),
+        block_hash,
+        confirmed: false,
+        message: String::new(),
+    };
+    if confirmations < 0 {
+        result.message = format!("Transaction {} conflicts with the chain and will not confirm; prepare a new anchor", txid);
+        return Ok(result);
+    }
+    if confirmations < min_confirmations {
+        result.message = format!("{} of {} confirmations", confirmations, min_confirmations);
+        return Ok(result);
+    }
+
+    let raw_hex = str_result(&tx, Some("hex"), "gettransaction")?;
+    let decoded = rpc.call("decoderawtransaction", json!([raw_hex]))?;
+    let Some(script) = anchor_output_script(&decoded) else {
+        result.message = format!("Transaction {} carries no SteloPTC OP_RETURN output", txid);
+        return Ok(result);
+    };
+    let verdict = store::verify_anchor(conn, anchor_id, &script)?;
+    result.confirmed = verdict.ok;
+    result.message = verdict.message;
+    if verdict.ok {
+        // The anchor is confirmed either way; a missing proof can be captured later.
+        match capture_spv_proof(conn, rpc, anchor_id) {
+            Ok(v) if v.ok => result.message.push_str(" SPV proof stored."),
+            Ok(v) if v.pow_ok => result.message.push_str(&format!(" SPV proof stored. {}", v.message)),
+            Ok(v) => result.message.push_str(&format!(" SPV proof not stored: {}", v.message)),
+            Err(e) => result.message.push_str(&format!(" SPV proof not captured: {}", e)),
+        }
+    }
+    Ok(result)
+}
+
+/// Fetch the block header and transaction list for a confirmed anchor, build
+/// its SPV proof, and store it if it verifies against the anchored root. A
+/// proof short only of the chain's minimum work (a regtest or testnet lab) is
+/// stored too, and reads as unverified. The verification is returned either
+/// way so the caller can show why a proof was refused.
+pub fn capture_spv_proof(conn: &Connection, rpc: &dyn RpcTransport, anchor_id: &str) -> Result<SpvVerification, AppError> {
+    let anchor = store::get_anchor(conn, anchor_id)?;
+    let txid = match (&anchor.status[..], &anchor.txid) {
+        ("confirmed", Some(txid)) => txid.clone(),
+        _ => {
+            return Err(AppError::invalid(format!(
+                "Anchor is {}; an SPV proof needs a confirmed anchor with a txid",
+                anchor.status
+            )))
+        }
+    };
+    let tx = lookup_transaction(rpc, &txid)?;
+    let raw_hex = str_result(&tx, Some("hex"), "gettransaction")?;
+    let block_hash = tx
+        .get("blockhash")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or(anchor.block_hash)
+        .ok_or_else(|| AppError::external("node_rpc", format!("The node does not report a block for transaction {}", txid)))?;
+    let header_hex = str_result(&rpc.call("getblockheader", json!([block_hash, false]))?, None, "getblockheader")?;
+    let block = rpc.call("getblock", json!([block_hash, 1]))?;
+    let txids: Vec<String> = block
+        .get("tx")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .ok_or_else(|| AppError::external("node_rpc", format!("Unexpected reply from getblock: {}", block)))?;
+    let height = block.get("height").and_then(Value::as_i64);
+
+    let proof =
+        spv::build_spv_proof(anchor_id, &anchor.chain_name, &txid, &raw_hex, &block_hash, height, &header_hex, &txids)
+            .map_err(|e| AppError::external("node_rpc", e))?;
+    let verification = spv::verify_spv_proof(&proof, &anchor.merkle_root);
+    if verification.pow_ok {
+        spv::save_proof(conn, &proof)?;
+    }
+    Ok(verification)
+}
+
+/// Poll every `submitted` anchor. A failure on one anchor is reported in its
+/// result and does not stop the others.
+pub fn poll_submitted_anchors(conn: &Connection, rpc: &dyn RpcTransport) -> Result<Vec<AnchorPollResult>, AppError> {
+    let min_confirmations = get_config(conn)?.min_confirmations;
+    let pending: Vec<CheckpointAnchor> = store::list_anchors(conn, None)?
+        .into_iter()
+        .filter(|a| a.status == "submitted" && a.txid.is_some())
+        .collect();
+    Ok(pending
+        .into_iter()
+        .map(|a| {
+            poll_anchor(conn, rpc, &a.id, min_confirmations).unwrap_or_else(|e| AnchorPollResult {
+                anchor_id: a.id,
+                txid: a.txid,
+                confirmations: a.confirmations,
+                block_hash: a.block_hash,
+                confirmed: false,
+                message: e.to_string(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+    use crate::db::queries::build_merkle_root;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// A scripted node: each method returns a canned reply, and every call is
+    /// recorded so the tests can check the order of the broadcast steps.
+    struct ScriptedNode {
+        replies: HashMap<&'static str, Result<Value, (i64, &'static str)>>,
+        calls: RefCell<Vec<(String, Value)>>,
+    }
+
+    impl ScriptedNode {
+        fn new() -> Self {
+            Self { replies: HashMap::new(), calls: RefCell::new(Vec::new()) }
+        }
+        fn reply(mut self, method: &'static str, v: Value) -> Self {
+            self.replies.insert(method, Ok(v));
+            self
+        }
+        fn fail(mut self, method: &'static str, code: i64, msg: &'static str) -> Self {
+            self.replies.insert(method, Err((code, msg)));
+            self
+        }
+        fn methods(&self) -> Vec<String> {
+            self.calls.borrow().iter().map(|(m, _)| m.clone()).collect()
+        }
+    }
+
+    impl RpcTransport for ScriptedNode {
+        fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+            self.calls.borrow_mut().push((method.to_string(), params));
+            match self.replies.get(method) {
+                Some(Ok(v)) => Ok(v.clone()),
+                Some(Err((code, msg))) => Err(RpcError { code: Some(*code), message: msg.to_string() }),
+                None => Err(RpcError { code: Some(RPC_METHOD_NOT_FOUND), message: "Method not found".to_string() }),
+            }
+        }
+    }
+
+    const TXID: &str = "5e8d6c4a3b2f1e0d9c8b7a69584736251403f2e1d0c9b8a7968574635241302f";
+
+    fn test_db() -> (Connection, CheckpointAnchor) {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        let root = build_merkle_root(&["aa".repeat(32), "bb".repeat(32)]);
+        conn.execute_batch(&format!(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('user1', 'u1', 'x', 'U', 'admin');
+             INSERT INTO audit_checkpoints (id, lineage_id, start_seq, end_seq, entry_count, merkle_root, created_at, is_auto)
+             VALUES ('cp1', 'lin1', 0, 1, 2, '{}', '2026-01-01T00:00:00Z', 0);",
+            root
+        ))
+        .unwrap();
+        let anchor = store::prepare_anchor(&conn, "cp1", "dogecoin", "user1").unwrap();
+        (conn, anchor)
+    }
+
+    /// A stand-in signed transaction that embeds the anchor output as a real
+    /// serialization would: script length byte, then the script.
+    fn signed_tx(anchor: &CheckpointAnchor) -> String {
+        format!("0200000001{}0000000000000000{:02x}{}00000000", "ab".repeat(36), 39, anchor.op_return_hex)
+    }
+
+    fn happy_node(anchor: &CheckpointAnchor) -> ScriptedNode {
+        ScriptedNode::new()
+            .reply("createrawtransaction", json!("0200000000010000000000000000"))
+            .reply("fundrawtransaction", json!({ "hex": "02000000funded", "fee": 0.0001, "changepos": 1 }))
+            .reply("signrawtransactionwithwallet", json!({ "hex": signed_tx(anchor), "complete": true }))
+            .reply("sendrawtransaction", json!(TXID))
+    }
+
+    #[test]
+    fn parse_rpc_url_requires_http_and_a_port() {
+        assert_eq!(parse_rpc_url("http://127.0.0.1:18443/").unwrap(), ("127.0.0.1".to_string(), 18443));
+        assert!(parse_rpc_url("https://node:8332").is_err());
+        assert!(parse_rpc_url("http://node").is_err());
+        assert!(parse_rpc_url("http://node:notaport").is_err());
+        assert_eq!(rpc_path(Some("anchors")), "/wallet/anchors");
+        assert_eq!(rpc_path(Some("  ")), "/");
+    }
+
+    #[test]
+    fn parse_rpc_response_separates_results_errors_and_auth_failures() {
+        assert_eq!(parse_rpc_response(200, r#"{"result":"abc","error":null,"id":"x"}"#).unwrap(), json!("abc"));
+        let err = parse_rpc_response(500, r#"{"result":null,"error":{"code":-26,"message":"dust"},"id":"x"}"#).unwrap_err();
+        assert_eq!(err.code, Some(-26));
+        assert!(err.to_string().contains("dust"));
+        assert!(parse_rpc_response(401, "").unwrap_err().to_string().contains("username or password"));
+        assert!(parse_rpc_response(502, "<html>bad gateway</html>").is_err());
+    }
+
+    #[test]
+    fn broadcast_runs_the_four_steps_and_records_the_txid() {
+        let (conn, anchor) = test_db();
+        let node = happy_node(&anchor);
+        let updated = broadcast_anchor(&conn, &node, &anchor.id).unwrap();
+        assert_eq!(
+            node.methods(),
+            ["createrawtransaction", "fundrawtransaction", "signrawtransactionwithwallet", "sendrawtransaction"]
+        );
+        // The data output carries the bare 37-byte payload, not the script.
+        let create_params = &node.calls.borrow()[0].1;
+        assert_eq!(create_params[1]["data"].as_str().unwrap(), &anchor.op_return_hex[4..]);
+        assert_eq!(updated.status, "submitted");
+        assert_eq!(updated.txid.as_deref(), Some(TXID));
+        assert_eq!(updated.broadcast_via, "node_rpc");
+        assert!(broadcast_anchor(&conn, &node, &anchor.id).unwrap_err().message().contains("already submitted"));
+    }
+
+    #[test]
+    fn broadcast_falls_back_to_signrawtransaction_on_older_nodes() {
+        let (conn, anchor) = test_db();
+        let node = happy_node(&anchor)
+            .fail("signrawtransactionwithwallet", RPC_METHOD_NOT_FOUND, "Method not found")
+            .reply("signrawtransaction", json!({ "hex": signed_tx(&anchor), "complete": true }));
+        broadcast_anchor(&conn, &node, &anchor.id).unwrap();
+        assert!(node.methods().contains(&"signrawtransaction".to_string()));
+    }
+
+    #[test]
+    fn broadcast_refuses_incomplete_or_altered_transactions() {
+        let (conn, anchor) = test_db();
+        let incomplete = happy_node(&anchor)
+            .reply("signrawtransactionwithwallet", json!({ "hex": signed_tx(&anchor), "complete": false }));
+        assert!(broadcast_anchor(&conn, &incomplete, &anchor.id).unwrap_err().message().contains("could not fully sign"));
+
+        let altered = happy_node(&anchor)
+            .reply("signrawtransactionwithwallet", json!({ "hex": "0200000001deadbeef00000000", "complete": true }));
+        assert!(broadcast_anchor(&conn, &altered, &anchor.id).unwrap_err().message().contains("nothing was broadcast"));
+        assert!(!altered.methods().contains(&"sendrawtransaction".to_string()));
+        assert_eq!(store::get_anchor(&conn, &anchor.id).unwrap().status, "prepared");
+    }
+
+    #[test]
+    fn polling_confirms_only_at_the_threshold_and_after_decoding() {
+        let (conn, anchor) = test_db();
+        broadcast_anchor(&conn, &happy_node(&anchor), &anchor.id).unwrap();
+
+        let shallow = ScriptedNode::new().reply("gettransaction", json!({ "confirmations": 2, "blockhash": "00ff", "hex": "00" }));
+        let r = poll_anchor(&conn, &shallow, &anchor.id, 6).unwrap();
+        assert!(!r.confirmed);
+        let reloaded = store::get_anchor(&conn, &anchor.id).unwrap();
+        assert_eq!((reloaded.status.as_str(), reloaded.confirmations), ("submitted", Some(2)));
+
+        let deep = ScriptedNode::new()
+            .reply("gettransaction", json!({ "confirmations": 6, "blockhash": "00ff", "hex": signed_tx(&anchor) }))
+            .reply(
+                "decoderawtransaction",
+                json!({ "vout": [
+                    { "scriptPubKey": { "hex": "76a914" } },
+                    { "scriptPubKey": { "hex": anchor.op_return_hex } }
+                ] }),
+            );
+        let r = poll_anchor(&conn, &deep, &anchor.id, 6).unwrap();
+        assert!(r.confirmed);
+        let reloaded = store::get_anchor(&conn, &anchor.id).unwrap();
+        assert_eq!(reloaded.status, "confirmed");
+        assert_eq!(reloaded.block_hash.as_deref(), Some("00ff"));
+        assert!(reloaded.verified_at.is_some());
+    }
+
+    #[test]
+    fn confirmed_anchor_captures_a_verified_spv_proof() {
+        let (conn, anchor) = test_db();
+        let (block, txids) = spv::tests::mined_block(&anchor.merkle_root, "dogecoin");
+        store::record_anchor_txid(&conn, &anchor.id, &block.txid).unwrap();
+        let node = ScriptedNode::new()
+            .reply("gettransaction", json!({ "confirmations": 6, "blockhash": block.block_hash, "hex": block.raw_tx }))
+            .reply("decoderawtransaction", json!({ "vout": [{ "scriptPubKey": { "hex": anchor.op_return_hex } }] }))
+            .reply("getblockheader", json!(block.block_header))
+            .reply("getblock", json!({ "tx": txids, "height": 101 }));
+        let r = poll_anchor(&conn, &node, &anchor.id, 6).unwrap();
+        // Mined at regtest difficulty: stored, but not counted as verified.
+        assert!(r.confirmed && r.message.contains("SPV proof stored. Unverified: low work"), "{}", r.message);
+        let stored = spv::get_proof(&conn, &anchor.id).unwrap().unwrap();
+        assert_eq!(stored.merkle_branch, block.merkle_branch);
+        assert_eq!(stored.block_height, Some(101));
+        assert_eq!(spv::proofs_for_checkpoint(&conn, "cp1").unwrap().len(), 1);
+
+        // A header that does not contain the transaction is refused, not stored.
+        conn.execute("DELETE FROM anchor_spv_proofs", []).unwrap();
+        let other = spv::tests::mined_block(&"cd".repeat(32), "dogecoin").0;
+        let lying = ScriptedNode::new()
+            .reply("gettransaction", json!({ "confirmations": 6, "blockhash": block.block_hash, "hex": block.raw_tx }))
+            .reply("getblockheader", json!(other.block_header))
+            .reply("getblock", json!({ "tx": txids }));
+        let v = capture_spv_proof(&conn, &lying, &anchor.id).unwrap();
+        assert!(!v.ok && !v.merkle_ok);
+        assert!(spv::get_proof(&conn, &anchor.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn polling_does_not_confirm_a_transaction_without_the_anchor() {
+        let (conn, anchor) = test_db();
+        store::record_anchor_txid(&conn, &anchor.id, TXID).unwrap();
+        // Not in the wallet (broadcast manually), found through txindex instead.
+        let node = ScriptedNode::new()
+            .fail("gettransaction", -5, "Invalid or non-wallet transaction id")
+            .reply("getrawtransaction", json!({ "confirmations": 10, "hex": "00" }))
+            .reply("decoderawtransaction", json!({ "vout": [{ "scriptPubKey": { "hex": "6a0401020304" } }] }));
+        let results = poll_submitted_anchors(&conn, &node).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].confirmed);
+        assert!(results[0].message.contains("no SteloPTC OP_RETURN"));
+        assert_eq!(store::get_anchor(&conn, &anchor.id).unwrap().status, "submitted");
+    }
+
+    #[test]
+    fn an_unreachable_node_is_an_external_service_error() {
+        let rpc = HttpRpc::new("http://127.0.0.1:1", None, None, None).unwrap();
+        let err = node_info(&rpc).unwrap_err();
+        assert_eq!(err.code(), "external_service");
+        assert_eq!(serde_json::to_value(&err).unwrap()["params"]["service"], "node_rpc");
+        // A refusal from a live node is the node's failure too, not the caller's.
+        let (conn, anchor) = test_db();
+        let node = ScriptedNode::new().fail("createrawtransaction", -4, "Insufficient funds");
+        assert_eq!(broadcast_anchor(&conn, &node, &anchor.id).unwrap_err().code(), "external_service");
+    }
+
+    #[test]
+    fn config_keeps_the_password_and_connect_requires_enabled() {
+        let (conn, _) = test_db();
+        assert!(connect(&conn).is_err());
+        let mut req = SetNodeRpcConfigRequest {
+            enabled: true,
+            rpc_url: "http://127.0.0.1:18443".to_string(),
+            username: Some("rpcuser".to_string()),
+            password: Some("hunter2".to_string()),
+            wallet: Some("anchors".to_string()),
+            min_confirmations: 1,
+        };
+        set_config(&conn, &req).unwrap();
+        req.password = None;
+        set_config(&conn, &req).unwrap();
+        let cfg = get_config(&conn).unwrap();
+        assert!(cfg.enabled && cfg.password_set);
+        assert!(connect(&conn).is_ok());
+        req.rpc_url = "127.0.0.1:18443".to_string();
+        assert!(set_config(&conn, &req).is_err());
+    }
+
+    #[test]
+    fn http_transport_sends_basic_auth_to_the_wallet_path() {
+        use std::net::TcpListener;
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut request = String::new();
+            let mut buf = [0u8; 4096];
+            while !request.ends_with('}') {
+                let n = sock.read(&mut buf).unwrap();
+                assert!(n > 0, "client closed before sending the body");
+                request.push_str(&String::from_utf8_lossy(&buf[..n]));
+            }
+            let body = r#"{"result":{"chain":"regtest","blocks":101},"error":null,"id":"stelo-ptc"}"#;
+            write!(sock, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+            request
+        });
+        let rpc = HttpRpc::new(&format!("http://127.0.0.1:{}", port), Some("u"), Some("p"), Some("w")).unwrap();
+        let info = rpc.call("getblockchaininfo", json!([])).unwrap();
+        assert_eq!(info["chain"], "regtest");
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /wallet/w HTTP/1.1"));
+        assert!(request.contains(&format!("Authorization: Basic {}", B64.encode("u:p"))));
+        assert!(request.contains(r#""method":"getblockchaininfo""#));
+    }
+}
@@ -0,0 +1,872 @@
+// WP-83: SPV inclusion proofs for confirmed anchors.
+//
+// `op_return_matches_root` proves that some transaction's bytes commit to a
+// checkpoint root, but the auditor still had to take a block explorer's word
+// that the transaction is in a block at all. An SPV proof removes that last
+// third party. For each confirmed anchor we keep:
+//
+//   * the raw transaction — its double-SHA-256 is the txid, and its outputs
+//     carry the `OP_RETURN` commitment;
+//   * the transaction's Merkle branch and position in its block;
+//   * the block header (80 bytes, plus the AuxPoW data for a merge-mined
+//     Dogecoin block).
+//
+// `verify_spv_proof` then checks, with nothing but those bytes, that the txid
+// folds up the branch to the header's Merkle root, that the header hashes to the
+// recorded block hash, and that the header carries the proof-of-work its `bits`
+// field claims: double SHA-256 for Bitcoin, scrypt (N=1024, r=1, p=1) for
+// Dogecoin and Litecoin, and for merge-mined (AuxPoW) Dogecoin blocks, scrypt
+// over the parent block whose coinbase commits to this block's hash. The `bits`
+// themselves must be no easier than the chain's mainnet proof-of-work limit: a
+// header at regtest difficulty is mined in milliseconds, so a proof carrying
+// one is consistent but "unverified: low work".
+//
+// What SPV does not prove, stated plainly in the docs: that the header is on
+// the heaviest chain. The proof shows the work in one header; an auditor judges
+// that work (`bits`) or matches the block hash against any copy of the chain.
+//
+// Hashes are handled in internal byte order (as hashed); only txids and block
+// hashes are shown reversed, the way nodes and explorers display them.
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{extract_root_from_hex, hex_decode, hex_encode};
+use crate::error::AppError;
+
+pub type Hash = [u8; 32];
+
+/// Version bit marking a merge-mined block whose header is followed by AuxPoW.
+pub const AUXPOW_VERSION_FLAG: i32 = 0x100;
+/// Marker before the chain Merkle root in a merge-mining parent's coinbase.
+const MERGED_MINING_HEADER: [u8; 4] = [0xfa, 0xbe, b'm', b'm'];
+/// Dogecoin's AuxPoW chain id, carried in the top 16 bits of the block version.
+pub const DOGECOIN_CHAIN_ID: i32 = 0x62;
+const MAX_CHAIN_BRANCH: usize = 30;
+
+/// Everything needed to prove one anchor transaction is in a block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnchorSpvProof {
+    pub anchor_id: String,
+    pub chain_name: String,
+    /// Display (reversed) hex, as nodes and explorers show it.
+    pub txid: String,
+    pub raw_tx: String,
+    /// Display (reversed) hex.
+    pub block_hash: String,
+    pub block_height: Option<i64>,
+    /// 80-byte header hex, followed by AuxPoW data for a merge-mined block.
+    pub block_header: String,
+    pub tx_index: u32,
+    /// Sibling hashes from the transaction up to the root, internal byte order.
+    pub merkle_branch: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpvVerification {
+    pub anchor_id: String,
+    pub ok: bool,
+    pub txid_ok: bool,
+    /// The checkpoint root the raw transaction commits to, if it carries one.
+    pub anchored_root: Option<String>,
+    pub root_ok: bool,
+    pub merkle_ok: bool,
+    pub header_hash_ok: bool,
+    pub pow_ok: bool,
+    /// The header's target is no easier than the chain's [`min_work_bits`]. A
+    /// proof with `pow_ok` but not `work_ok` is unverified: low work.
+    pub work_ok: bool,
+    /// `sha256d` or `scrypt`.
+    pub pow_algorithm: String,
+    pub auxpow: bool,
+    /// Compact difficulty target from the header, as hex.
+    pub bits: String,
+    pub message: String,
+}
+
+pub fn sha256d(data: &[u8]) -> Hash {
+    let first = Sha256::digest(data);
+    Sha256::digest(first).into()
+}
+
+fn reversed_hex(h: &Hash) -> String {
+    let mut r = *h;
+    r.reverse();
+    hex_encode(&r)
+}
+
+fn hash_from_hex(s: &str, reversed: bool) -> Result<Hash, String> {
+    let bytes = hex_decode(s)?;
+    let mut h: Hash = bytes
+        .try_into()
+        .map_err(|_| format!("Expected a 32-byte hash, got '{}'", s))?;
+    if reversed {
+        h.reverse();
+    }
+    Ok(h)
+}
+
+/// Cursor over serialized Bitcoin-family data.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(n).filter(|&e| e <= self.buf.len()).ok_or_else(|| {
+            format!("Truncated data: needed {} bytes at offset {}, have {}", n, self.pos, self.buf.len() - self.pos)
+        })?;
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn hash(&mut self) -> Result<Hash, String> {
+        Ok(self.bytes(32)?.try_into().unwrap())
+    }
+
+    fn varint(&mut self) -> Result<usize, String> {
+        let n = match self.u8()? {
+            0xfd => u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()) as u64,
+            0xfe => self.u32()? as u64,
+            0xff => u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()),
+            b => b as u64,
+        };
+        // No count in a transaction or proof can exceed the bytes left.
+        usize::try_from(n)
+            .ok()
+            .filter(|&n| n <= self.buf.len())
+            .ok_or_else(|| format!("Implausible length {} at offset {}", n, self.pos))
+    }
+
+    fn hashes(&mut self) -> Result<Vec<Hash>, String> {
+        let n = self.varint()?;
+        (0..n).map(|_| self.hash()).collect()
+    }
+}
+
+/// The parts of a transaction the proofs need.
+pub struct ParsedTx {
+    /// Internal byte order.
+    pub txid: Hash,
+    /// scriptSig of the first input (the coinbase script of a parent block).
+    pub first_input_script: Vec<u8>,
+    pub output_scripts: Vec<Vec<u8>>,
+}
+
+/// Parse one transaction from the front of `r`. Handles the segwit
+/// serialization: the txid is taken over the transaction without its marker,
+/// flag and witnesses, as consensus defines it.
+fn read_transaction(r: &mut Reader) -> Result<ParsedTx, String> {
+    let start = r.pos;
+    r.bytes(4)?; // version
+    let mut segwit = false;
+    if r.buf.get(r.pos) == Some(&0x00) && r.buf.get(r.pos + 1) == Some(&0x01) {
+        segwit = true;
+        r.bytes(2)?;
+    }
+    let body_start = r.pos;
+    let n_in = r.varint()?;
+    let mut first_input_script = Vec::new();
+    for i in 0..n_in {
+        r.bytes(36)?; // previous outpoint
+        let len = r.varint()?;
+        let script = r.bytes(len)?;
+        if i == 0 {
+            first_input_script = script.to_vec();
+        }
+        r.bytes(4)?; // sequence
+    }
+    let n_out = r.varint()?;
+    let mut output_scripts = Vec::with_capacity(n_out);
+    for _ in 0..n_out {
+        r.bytes(8)?; // value
+        let len = r.varint()?;
+        output_scripts.push(r.bytes(len)?.to_vec());
+    }
+    let body_end = r.pos;
+    if segwit {
+        for _ in 0..n_in {
+            let items = r.varint()?;
+            for _ in 0..items {
+                let len = r.varint()?;
+                r.bytes(len)?;
+            }
+        }
+    }
+    let lock_start = r.pos;
+    r.bytes(4)?; // lock time
+
+    let mut stripped = Vec::with_capacity(r.pos - start);
+    stripped.extend_from_slice(&r.buf[start..start + 4]);
+    stripped.extend_from_slice(&r.buf[body_start..body_end]);
+    stripped.extend_from_slice(&r.buf[lock_start..r.pos]);
+    Ok(ParsedTx { txid: sha256d(&stripped), first_input_script, output_scripts })
+}
+
+/// Parse a complete raw transaction; trailing bytes are an error.
+pub fn parse_transaction(raw: &[u8]) -> Result<ParsedTx, String> {
+    let mut r = Reader::new(raw);
+    let tx = read_transaction(&mut r)?;
+    if r.pos != raw.len() {
+        return Err(format!("{} unexpected bytes after the transaction", raw.len() - r.pos));
+    }
+    Ok(tx)
+}
+
+pub struct BlockHeader {
+    pub version: i32,
+    pub merkle_root: Hash,
+    pub bits: u32,
+    pub raw: [u8; 80],
+}
+
+fn read_header(r: &mut Reader) -> Result<BlockHeader, String> {
+    let raw: [u8; 80] = r.bytes(80)?.try_into().unwrap();
+    Ok(BlockHeader {
+        version: i32::from_le_bytes(raw[0..4].try_into().unwrap()),
+        merkle_root: raw[36..68].try_into().unwrap(),
+        bits: u32::from_le_bytes(raw[72..76].try_into().unwrap()),
+        raw,
+    })
+}
+
+/// Fold a leaf up a Merkle branch. Bit `i` of `index` says whether the node
+/// at level `i` is a right child (sibling on the left).
+pub fn merkle_root_from_branch(leaf: Hash, branch: &[Hash], mut index: u32) -> Hash {
+    let mut h = leaf;
+    for sibling in branch {
+        let mut buf = [0u8; 64];
+        if index & 1 == 1 {
+            buf[..32].copy_from_slice(sibling);
+            buf[32..].copy_from_slice(&h);
+        } else {
+            buf[..32].copy_from_slice(&h);
+            buf[32..].copy_from_slice(sibling);
+        }
+        h = sha256d(&buf);
+        index >>= 1;
+    }
+    h
+}
+
+/// The branch for leaf `index` of a block's transaction tree, using Bitcoin's
+/// duplicate-the-last-node rule for odd levels.
+pub fn merkle_branch(leaves: &[Hash], mut index: usize) -> Vec<Hash> {
+    let mut level = leaves.to_vec();
+    let mut branch = Vec::new();
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(*level.last().unwrap());
+        }
+        branch.push(level[index ^ 1]);
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                let mut buf = [0u8; 64];
+                buf[..32].copy_from_slice(&pair[0]);
+                buf[32..].copy_from_slice(&pair[1]);
+                sha256d(&buf)
+            })
+            .collect();
+        index /= 2;
+    }
+    branch
+}
+
+/// Expand compact `bits` into a 256-bit big-endian target.
+pub fn target_from_bits(bits: u32) -> Result<[u8; 32], String> {
+    let exponent = (bits >> 24) as usize;
+    let mantissa = bits & 0x007f_ffff;
+    if bits & 0x0080_0000 != 0 || mantissa == 0 {
+        return Err(format!("Header bits 0x{:08x} encode a zero or negative target", bits));
+    }
+    let mut target = [0u8; 32];
+    let m = mantissa.to_be_bytes(); // [0, b1, b2, b3]
+    for (i, byte) in m[1..].iter().enumerate() {
+        // Byte i of the 3-byte mantissa lands at position exponent-1-i from the right.
+        let from_right = exponent as isize - 1 - i as isize;
+        if from_right < 0 {
+            continue;
+        }
+        if from_right >= 32 {
+            if *byte != 0 {
+                return Err(format!("Header bits 0x{:08x} overflow a 256-bit target", bits));
+            }
+            continue;
+        }
+        target[31 - from_right as usize] = *byte;
+    }
+    Ok(target)
+}
+
+/// True when a PoW hash (internal byte order, i.e. little-endian) is at or
+/// below the target.
+pub fn hash_meets_target(hash: &Hash, target_be: &[u8; 32]) -> bool {
+    let mut be = *hash;
+    be.reverse();
+    be <= *target_be
+}
+
+/// The easiest target a chain's mainnet accepts (its proof-of-work limit), as
+/// compact bits: `0x1d00ffff` for Bitcoin, `0x1e0fffff` for Dogecoin and
+/// Litecoin. Unknown chains get Bitcoin's, as they get its hash.
+pub fn min_work_bits(chain_name: &str) -> u32 {
+    match PowAlgorithm::for_chain(chain_name) {
+        PowAlgorithm::Sha256d => 0x1d00_ffff,
+        PowAlgorithm::Scrypt => 0x1e0f_ffff,
+    }
+}
+
+/// True when `bits` encode a target at or below the chain's [`min_work_bits`].
+pub fn meets_min_work(bits: u32, chain_name: &str) -> Result<bool, String> {
+    Ok(target_from_bits(bits)? <= target_from_bits(min_work_bits(chain_name))?)
+}
+
+/// The scrypt proof-of-work hash of an 80-byte header (Litecoin/Dogecoin).
+pub fn scrypt_pow_hash(header: &[u8; 80]) -> Hash {
+    let params = scrypt::Params::new(10, 1, 1, 32).expect("N=1024, r=1, p=1 are valid scrypt parameters");
+    let mut out = [0u8; 32];
+    scrypt::scrypt(header, header, &params, &mut out).expect("32 bytes is a valid scrypt output length");
+    out
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PowAlgorithm {
+    Sha256d,
+    Scrypt,
+}
+
+impl PowAlgorithm {
+    pub fn for_chain(chain_name: &str) -> Self {
+        match chain_name.trim().to_ascii_lowercase().as_str() {
+            "dogecoin" | "litecoin" => PowAlgorithm::Scrypt,
+            _ => PowAlgorithm::Sha256d,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PowAlgorithm::Sha256d => "sha256d",
+            PowAlgorithm::Scrypt => "scrypt",
+        }
+    }
+
+    fn pow_hash(self, header: &[u8; 80]) -> Hash {
+        match self {
+            PowAlgorithm::Sha256d => sha256d(header),
+            PowAlgorithm::Scrypt => scrypt_pow_hash(header),
+        }
+    }
+}
+
+/// The chain-tree slot a merge-mined chain must use, from the parent
+/// coinbase's nonce (the Namecoin/Dogecoin AuxPoW rule).
+pub fn expected_chain_index(nonce: u32, chain_id: i32, branch_len: usize) -> u32 {
+    let mut rand = nonce;
+    rand = rand.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+    rand = rand.wrapping_add(chain_id as u32);
+    rand = rand.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+    rand % (1u32 << branch_len)
+}
+
+/// Check the AuxPoW that follows a merge-mined header: the parent's coinbase
+/// is in the parent block, commits (through the chain Merkle tree) to this
+/// block's hash, and the parent header carries the work for this block's bits.
+/// Whether those bits are enough for the chain is checked by the caller, as for
+/// any header.
+fn verify_auxpow(block_hash: &Hash, header: &BlockHeader, auxpow: &[u8], algo: PowAlgorithm) -> Result<(), String> {
+    let mut r = Reader::new(auxpow);
+    let coinbase = read_transaction(&mut r)?;
+    r.hash()?; // parent block hash, recomputed from the parent header instead
+    let coinbase_branch = r.hashes()?;
+    if r.u32()? != 0 {
+        return Err("AuxPoW coinbase is not the first transaction of the parent block".to_string());
+    }
+    let chain_branch = r.hashes()?;
+    let chain_index = r.u32()?;
+    let parent = read_header(&mut r)?;
+    if r.pos != auxpow.len() {
+        return Err(format!("{} unexpected bytes after the AuxPoW parent header", auxpow.len() - r.pos));
+    }
+    if chain_branch.len() > MAX_CHAIN_BRANCH {
+        return Err("AuxPoW chain Merkle branch is too long".to_string());
+    }
+    if merkle_root_from_branch(coinbase.txid, &coinbase_branch, 0) != parent.merkle_root {
+        return Err("AuxPoW coinbase is not in the parent block's Merkle tree".to_string());
+    }
+
+    let mut chain_root = merkle_root_from_branch(*block_hash, &chain_branch, chain_index);
+    chain_root.reverse();
+    let script = &coinbase.first_input_script;
+    let root_pos = find(script, &chain_root).ok_or("AuxPoW parent coinbase does not commit to this block")?;
+    match find(script, &MERGED_MINING_HEADER) {
+        Some(head) => {
+            if find(&script[head + 1..], &MERGED_MINING_HEADER).is_some() {
+                return Err("AuxPoW parent coinbase has more than one merged-mining header".to_string());
+            }
+            if head + MERGED_MINING_HEADER.len() != root_pos {
+                return Err("AuxPoW chain root does not follow the merged-mining header".to_string());
+            }
+        }
+        None if root_pos > 20 => return Err("AuxPoW chain root starts too late in the parent coinbase".to_string()),
+        None => {}
+    }
+    let tail = script
+        .get(root_pos + 32..root_pos + 40)
+        .ok_or("AuxPoW parent coinbase is missing the chain tree size and nonce")?;
+    let size = u32::from_le_bytes(tail[0..4].try_into().unwrap());
+    let nonce = u32::from_le_bytes(tail[4..8].try_into().unwrap());
+    if size != 1u32 << chain_branch.len() {
+        return Err("AuxPoW chain tree size does not match its branch".to_string());
+    }
+    let chain_id = header.version >> 16;
+    if chain_index != expected_chain_index(nonce, chain_id, chain_branch.len()) {
+        return Err("AuxPoW chain index is not the slot for this chain id".to_string());
+    }
+
+    let target = target_from_bits(header.bits)?;
+    if !hash_meets_target(&algo.pow_hash(&parent.raw), &target) {
+        return Err("AuxPoW parent header does not meet the block's target".to_string());
+    }
+    Ok(())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Build a proof from what a node reports for a confirmed anchor. `block_txids`
+/// are the block's txids in block order, display hex.
+#[allow(clippy::too_many_arguments)]
+pub fn build_spv_proof(
+    anchor_id: &str,
+    chain_name: &str,
+    txid: &str,
+    raw_tx_hex: &str,
+    block_hash: &str,
+    block_height: Option<i64>,
+    block_header_hex: &str,
+    block_txids: &[String],
+) -> Result<AnchorSpvProof, String> {
+    let leaves = block_txids.iter().map(|t| hash_from_hex(t, true)).collect::<Result<Vec<_>, _>>()?;
+    let target = hash_from_hex(txid, true)?;
+    let tx_index = leaves
+        .iter()
+        .position(|h| *h == target)
+        .ok_or_else(|| format!("Transaction {} is not in block {}", txid, block_hash))?;
+    Ok(AnchorSpvProof {
+        anchor_id: anchor_id.to_string(),
+        chain_name: chain_name.to_string(),
+        txid: txid.to_lowercase(),
+        raw_tx: raw_tx_hex.to_lowercase(),
+        block_hash: block_hash.to_lowercase(),
+        block_height,
+        block_header: block_header_hex.to_lowercase(),
+        tx_index: tx_index as u32,
+        merkle_branch: merkle_branch(&leaves, tx_index).iter().map(|h| hex_encode(h)).collect(),
+    })
+}
+
+/// Verify a proof offline against the checkpoint root it should anchor.
+pub fn verify_spv_proof(proof: &AnchorSpvProof, expected_root: &str) -> SpvVerification {
+    let algo = PowAlgorithm::for_chain(&proof.chain_name);
+    let mut v = SpvVerification {
+        anchor_id: proof.anchor_id.clone(),
+        ok: false,
+        txid_ok: false,
+        anchored_root: None,
+        root_ok: false,
+        merkle_ok: false,
+        header_hash_ok: false,
+        pow_ok: false,
+        work_ok: false,
+        pow_algorithm: algo.as_str().to_string(),
+        auxpow: false,
+        bits: String::new(),
+        message: String::new(),
+    };
+    if let Err(e) = check_spv_proof(proof, expected_root, algo, &mut v) {
+        v.message = e;
+        return v;
+    }
+    v.ok = true;
+    v.message = format!(
+        "Transaction {} is in block {} — Merkle branch and {}{} proof-of-work verified offline.",
+        proof.txid,
+        proof.block_hash,
+        algo.as_str(),
+        if v.auxpow { " (merge-mined)" } else { "" }
+    );
+    v
+}
+
+fn check_spv_proof(proof: &AnchorSpvProof, expected_root: &str, algo: PowAlgorithm, v: &mut SpvVerification) -> Result<(), String> {
+    let tx = parse_transaction(&hex_decode(&proof.raw_tx)?)?;
+    v.txid_ok = reversed_hex(&tx.txid) == proof.txid.to_lowercase();
+    if !v.txid_ok {
+        return Err("The raw transaction does not hash to the recorded txid".to_string());
+    }
+    v.anchored_root = tx.output_scripts.iter().find_map(|s| extract_root_from_hex(&hex_encode(s)).ok());
+    v.root_ok = v.anchored_root.as_deref().is_some_and(|r| r.eq_ignore_ascii_case(expected_root.trim()));
+    if !v.root_ok {
+        return Err("The transaction does not commit to this checkpoint's Merkle root".to_string());
+    }
+
+    let header_bytes = hex_decode(&proof.block_header)?;
+    let mut r = Reader::new(&header_bytes);
+    let header = read_header(&mut r)?;
+    v.bits = format!("{:08x}", header.bits);
+    let branch = proof.merkle_branch.iter().map(|h| hash_from_hex(h, false)).collect::<Result<Vec<_>, _>>()?;
+    v.merkle_ok = merkle_root_from_branch(tx.txid, &branch, proof.tx_index) == header.merkle_root;
+    if !v.merkle_ok {
+        return Err("The Merkle branch does not lead to the header's Merkle root".to_string());
+    }
+    let block_hash = sha256d(&header.raw);
+    v.header_hash_ok = reversed_hex(&block_hash) == proof.block_hash.to_lowercase();
+    if !v.header_hash_ok {
+        return Err("The header does not hash to the recorded block hash".to_string());
+    }
+
+    let auxpow = &header_bytes[80..];
+    v.auxpow = header.version & AUXPOW_VERSION_FLAG != 0;
+    if v.auxpow {
+        if auxpow.is_empty() {
+            return Err("Merge-mined header is missing its AuxPoW data".to_string());
+        }
+        verify_auxpow(&block_hash, &header, auxpow, algo)?;
+    } else {
+        if !auxpow.is_empty() {
+            return Err("Unexpected data after a header that is not merge-mined".to_string());
+        }
+        let target = target_from_bits(header.bits)?;
+        if !hash_meets_target(&algo.pow_hash(&header.raw), &target) {
+            return Err(format!("The header's {} hash does not meet its own target", algo.as_str()));
+        }
+    }
+    v.pow_ok = true;
+
+    // Work a regtest node does in milliseconds proves nothing, however well
+    // the header meets it.
+    v.work_ok = meets_min_work(header.bits, &proof.chain_name)?;
+    if !v.work_ok {
+        return Err(format!(
+            "Unverified: low work — the header's bits 0x{:08x} are easier than the {} minimum 0x{:08x}",
+            header.bits,
+            proof.chain_name.trim(),
+            min_work_bits(&proof.chain_name)
+        ));
+    }
+    Ok(())
+}
+
+pub fn save_proof(conn: &Connection, proof: &AnchorSpvProof) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO anchor_spv_proofs \
+         (anchor_id, chain_name, txid, raw_tx, block_hash, block_height, block_header, tx_index, merkle_branch, captured_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10) \
+         ON CONFLICT(anchor_id) DO UPDATE SET chain_name = excluded.chain_name, txid = excluded.txid, \
+             raw_tx = excluded.raw_tx, block_hash = excluded.block_hash, block_height = excluded.block_height, \
+             block_header = excluded.block_header, tx_index = excluded.tx_index, \
+             merkle_branch = excluded.merkle_branch, captured_at = excluded.captured_at",
+        params![
+            proof.anchor_id,
+            proof.chain_name,
+            proof.txid,
+            proof.raw_tx,
+            proof.block_hash,
+            proof.block_height,
+            proof.block_header,
+            proof.tx_index,
+            serde_json::to_string(&proof.merkle_branch)?,
+            super::store::now_iso(),
+        ],
+    )
+    .map_err(|e| AppError::internal(format!("Failed to store the SPV proof: {}", e)))?;
+    Ok(())
+}
+
+const PROOF_COLUMNS: &str =
+    "p.anchor_id, p.chain_name, p.txid, p.raw_tx, p.block_hash, p.block_height, p.block_header, p.tx_index, p.merkle_branch";
+
+fn map_proof(r: &rusqlite::Row) -> rusqlite::Result<AnchorSpvProof> {
+    let branch: String = r.get(8)?;
+    Ok(AnchorSpvProof {
+        anchor_id: r.get(0)?,
+        chain_name: r.get(1)?,
+        txid: r.get(2)?,
+        raw_tx: r.get(3)?,
+        block_hash: r.get(4)?,
+        block_height: r.get(5)?,
+        block_header: r.get(6)?,
+        tx_index: r.get(7)?,
+        merkle_branch: serde_json::from_str(&branch).unwrap_or_default(),
+    })
+}
+
+pub fn get_proof(conn: &Connection, anchor_id: &str) -> Result<Option<AnchorSpvProof>, AppError> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM anchor_spv_proofs p WHERE p.anchor_id = ?1", PROOF_COLUMNS))?;
+    let mut rows = stmt.query_map(params![anchor_id], map_proof)?;
+    Ok(rows.next().transpose()?)
+}
+
+/// SPV proofs for every confirmed anchor of a checkpoint, oldest first. These
+/// travel inside the checkpoint's exported Merkle proof.
+pub fn proofs_for_checkpoint(conn: &Connection, checkpoint_id: &str) -> Result<Vec<AnchorSpvProof>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM anchor_spv_proofs p JOIN checkpoint_anchors a ON a.id = p.anchor_id \
+             WHERE a.checkpoint_id = ?1 AND a.status = 'confirmed' ORDER BY a.created_at ASC",
+            PROOF_COLUMNS
+        ))?;
+    let rows = stmt
+        .query_map(params![checkpoint_id], map_proof)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::anchoring::build_op_return_script;
+
+    const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+    const GENESIS_COINBASE: &str = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+    pub(crate) const ROOT: &str = "a3f1c09b8e7d6a5b4c3d2e1f00112233445566778899aabbccddeeff00112233";
+    /// Regtest's minimum difficulty: about one hash in two meets it.
+    const EASY_BITS: u32 = 0x207f_ffff;
+
+    fn varint(n: usize) -> Vec<u8> {
+        assert!(n < 0xfd);
+        vec![n as u8]
+    }
+
+    /// A one-input transaction with the given input script and output scripts.
+    fn tx(input_script: &[u8], outputs: &[Vec<u8>]) -> Vec<u8> {
+        let mut t = vec![2, 0, 0, 0];
+        t.extend(varint(1));
+        t.extend([0x11; 36]);
+        t.extend(varint(input_script.len()));
+        t.extend(input_script);
+        t.extend([0xff; 4]);
+        t.extend(varint(outputs.len()));
+        for s in outputs {
+            t.extend([0u8; 8]);
+            t.extend(varint(s.len()));
+            t.extend(s);
+        }
+        t.extend([0u8; 4]);
+        t
+    }
+
+    /// An 80-byte header mined (by nonce search) to `bits` under `algo`.
+    fn mine(version: i32, merkle_root: Hash, bits: u32, algo: PowAlgorithm) -> [u8; 80] {
+        let target = target_from_bits(bits).unwrap();
+        let mut h = [0u8; 80];
+        h[0..4].copy_from_slice(&version.to_le_bytes());
+        h[36..68].copy_from_slice(&merkle_root);
+        h[68..72].copy_from_slice(&1_760_000_000u32.to_le_bytes());
+        h[72..76].copy_from_slice(&bits.to_le_bytes());
+        for nonce in 0u32.. {
+            h[76..80].copy_from_slice(&nonce.to_le_bytes());
+            if hash_meets_target(&algo.pow_hash(&h), &target) {
+                return h;
+            }
+        }
+        unreachable!()
+    }
+
+    /// A mined block of three transactions whose middle one anchors `root`,
+    /// with the block's txids as a node lists them. It is mined at regtest
+    /// difficulty, so its proof is consistent but unverified: low work.
+    pub(crate) fn mined_block(root: &str, chain_name: &str) -> (AnchorSpvProof, Vec<String>) {
+        let anchor_tx = tx(&[0x51], &[build_op_return_script(root).unwrap()]);
+        let txs = [tx(&[0x01], &[vec![0x51]]), anchor_tx.clone(), tx(&[0x02], &[vec![0x52]])];
+        let leaves: Vec<Hash> = txs.iter().map(|t| sha256d(t)).collect();
+        let merkle_root = merkle_root_from_branch(leaves[1], &merkle_branch(&leaves, 1), 1);
+        let header = mine(0x2000_0000, merkle_root, EASY_BITS, PowAlgorithm::for_chain(chain_name));
+        let txids: Vec<String> = leaves.iter().map(reversed_hex).collect();
+        let proof = build_spv_proof(
+            "anchor1", chain_name, &txids[1], &hex_encode(&anchor_tx), &reversed_hex(&sha256d(&header)),
+            Some(101), &hex_encode(&header), &txids,
+        )
+        .unwrap();
+        (proof, txids)
+    }
+
+    pub(crate) fn anchored_block(chain_name: &str) -> AnchorSpvProof {
+        mined_block(ROOT, chain_name).0
+    }
+
+    #[test]
+    fn genesis_block_hashes_and_meets_its_target() {
+        let raw = hex_decode(GENESIS_HEADER).unwrap();
+        let header = read_header(&mut Reader::new(&raw)).unwrap();
+        assert_eq!(reversed_hex(&sha256d(&header.raw)), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+        assert!(hash_meets_target(&sha256d(&header.raw), &target_from_bits(header.bits).unwrap()));
+        // The genesis coinbase is the block's only transaction: its txid is the root.
+        let coinbase = parse_transaction(&hex_decode(GENESIS_COINBASE).unwrap()).unwrap();
+        assert_eq!(merkle_root_from_branch(coinbase.txid, &[], 0), header.merkle_root);
+        assert_eq!(reversed_hex(&coinbase.txid), "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b");
+    }
+
+    #[test]
+    fn target_from_bits_expands_the_compact_form() {
+        let t = target_from_bits(0x1d00_ffff).unwrap();
+        assert_eq!(hex_encode(&t), format!("00000000ffff{}", "0".repeat(52)));
+        assert!(target_from_bits(0x0480_0000).is_err()); // negative
+        assert_eq!(target_from_bits(0x2200_0001).unwrap()[0], 1);
+        assert!(target_from_bits(0x2300_0001).is_err()); // overflows 256 bits
+    }
+
+    #[test]
+    fn segwit_txid_excludes_the_witness() {
+        let legacy = tx(&[], &[vec![0x51]]);
+        let mut segwit = legacy[..4].to_vec();
+        segwit.extend([0x00, 0x01]);
+        segwit.extend(&legacy[4..legacy.len() - 4]);
+        segwit.extend([0x01, 0x02, 0xaa, 0xbb]); // one witness item of two bytes
+        segwit.extend(&legacy[legacy.len() - 4..]);
+        assert_eq!(parse_transaction(&segwit).unwrap().txid, sha256d(&legacy));
+        assert!(parse_transaction(&segwit[..segwit.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn sha256d_proof_verifies_and_catches_each_kind_of_tampering() {
+        let proof = anchored_block("bitcoin");
+        let v = verify_spv_proof(&proof, ROOT);
+        assert!(v.pow_ok, "{}", v.message);
+        assert_eq!(v.pow_algorithm, "sha256d");
+        assert_eq!(v.anchored_root.as_deref(), Some(ROOT));
+
+        assert!(!verify_spv_proof(&proof, &"ff".repeat(32)).root_ok);
+
+        let mut wrong_index = proof.clone();
+        wrong_index.tx_index = 0;
+        assert!(!verify_spv_proof(&wrong_index, ROOT).merkle_ok);
+
+        let mut wrong_block = proof.clone();
+        wrong_block.block_hash = "00".repeat(32);
+        let v = verify_spv_proof(&wrong_block, ROOT);
+        assert!(v.merkle_ok && !v.header_hash_ok);
+
+        let mut wrong_tx = proof.clone();
+        wrong_tx.raw_tx = hex_encode(&tx(&[0x51], &[build_op_return_script(ROOT).unwrap(), vec![0x51]]));
+        assert!(!verify_spv_proof(&wrong_tx, ROOT).txid_ok);
+    }
+
+    #[test]
+    fn a_header_without_the_work_fails_pow() {
+        let mut proof = anchored_block("bitcoin");
+        // Same block, but claim a target no cheaply mined header meets.
+        let mut header = hex_decode(&proof.block_header).unwrap();
+        header[72..76].copy_from_slice(&0x1d00_ffffu32.to_le_bytes());
+        proof.block_header = hex_encode(&header);
+        proof.block_hash = reversed_hex(&sha256d(&header));
+        let v = verify_spv_proof(&proof, ROOT);
+        assert!(v.merkle_ok && v.header_hash_ok && !v.pow_ok);
+    }
+
+    #[test]
+    fn dogecoin_proofs_use_scrypt() {
+        let proof = anchored_block("dogecoin");
+        let v = verify_spv_proof(&proof, ROOT);
+        assert!(v.pow_ok, "{}", v.message);
+        assert_eq!(v.pow_algorithm, "scrypt");
+    }
+
+    #[test]
+    fn a_header_mined_at_regtest_difficulty_is_not_verified() {
+        for chain in ["bitcoin", "dogecoin"] {
+            let v = verify_spv_proof(&anchored_block(chain), ROOT);
+            assert!(v.merkle_ok && v.header_hash_ok && v.pow_ok, "{}", v.message);
+            assert!(!v.work_ok && !v.ok);
+            assert!(v.message.starts_with("Unverified: low work"), "{}", v.message);
+        }
+        assert!(!meets_min_work(EASY_BITS, "bitcoin").unwrap());
+        assert!(!meets_min_work(EASY_BITS, "dogecoin").unwrap());
+        // The genesis block sits exactly at Bitcoin's limit; the scrypt chains'
+        // limit is easier than Bitcoin's.
+        assert!(meets_min_work(0x1d00_ffff, "bitcoin").unwrap());
+        assert!(!meets_min_work(0x1e0f_ffff, "bitcoin").unwrap());
+        assert!(meets_min_work(0x1e0f_ffff, "litecoin").unwrap());
+    }
+
+    #[test]
+    fn merge_mined_dogecoin_header_verifies_through_the_parent() {
+        let anchor_tx = tx(&[0x51], &[build_op_return_script(ROOT).unwrap()]);
+        let txid = sha256d(&anchor_tx);
+        let version = (DOGECOIN_CHAIN_ID << 16) | AUXPOW_VERSION_FLAG | 4;
+        // The Dogecoin header itself carries no work: a target of all ones in
+        // the leading bytes would be needed, so it is simply left unmined.
+        let mut header = [0u8; 80];
+        header[0..4].copy_from_slice(&version.to_le_bytes());
+        header[36..68].copy_from_slice(&txid);
+        header[72..76].copy_from_slice(&EASY_BITS.to_le_bytes());
+        let block_hash = sha256d(&header);
+
+        // Parent coinbase: merged-mining header, reversed chain root (a single
+        // chain, so the root is the block hash), tree size 1, nonce.
+        let mut chain_root = block_hash;
+        chain_root.reverse();
+        let mut script = vec![0x03, 0x01, 0x02, 0x03];
+        script.extend(MERGED_MINING_HEADER);
+        script.extend(chain_root);
+        script.extend(1u32.to_le_bytes());
+        script.extend(7u32.to_le_bytes());
+        let coinbase = tx(&script, &[vec![0x51]]);
+        let parent = mine(0x2000_0000, sha256d(&coinbase), EASY_BITS, PowAlgorithm::Scrypt);
+
+        let build = |chain_index: u32| {
+            let mut aux = coinbase.clone();
+            aux.extend(sha256d(&parent));
+            aux.extend(varint(0));
+            aux.extend(0u32.to_le_bytes());
+            aux.extend(varint(0));
+            aux.extend(chain_index.to_le_bytes());
+            aux.extend(parent);
+            let mut full = header.to_vec();
+            full.extend(aux);
+            AnchorSpvProof {
+                anchor_id: "a".into(),
+                chain_name: "dogecoin".into(),
+                txid: reversed_hex(&txid),
+                raw_tx: hex_encode(&anchor_tx),
+                block_hash: reversed_hex(&block_hash),
+                block_height: None,
+                block_header: hex_encode(&full),
+                tx_index: 0,
+                merkle_branch: vec![],
+            }
+        };
+        let v = verify_spv_proof(&build(0), ROOT);
+        assert!(v.pow_ok && v.auxpow, "{}", v.message);
+        assert!(!v.work_ok, "the parent was mined at regtest difficulty");
+
+        // With a one-leaf chain tree the only valid slot is 0.
+        assert!(!verify_spv_proof(&build(1), ROOT).pow_ok);
+
+        let mut bare = build(0);
+        bare.block_header = hex_encode(&header);
+        assert!(verify_spv_proof(&bare, ROOT).message.contains("missing its AuxPoW"));
+    }
+
+    #[test]
+    fn build_rejects_a_tx_not_in_the_block() {
+        let txids = vec!["11".repeat(32)];
+        assert!(build_spv_proof("a", "bitcoin", &"22".repeat(32), "00", &"33".repeat(32), None, "", &txids).is_err());
+    }
+}
@@ -7,6 +7,7 @@ use rusqlite::{params, Connection};
 use serde::Serialize;
 
 use super::{build_op_return_script_hex, extract_root_from_hex, op_return_matches_root};
+use crate::error::AppError;
 
 #[derive(Debug, Serialize)]
 pub struct CheckpointAnchor {
@@ -21,6 +22,12 @@ pub struct CheckpointAnchor {
     pub created_by: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// WP-82: `manual` (external wallet) or `node_rpc` (the lab's own node).
+    pub broadcast_via: String,
+    /// WP-82: confirmation count and block hash from the last node poll.
+    pub confirmations: Option<i64>,
+    pub block_hash: Option<String>,
+    pub last_polled_at: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -32,12 +39,13 @@ pub struct AnchorVerifyResult {
     pub message: String,
 }
 
-fn now_iso() -> String {
+pub(super) fn now_iso() -> String {
     chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
 }
 
 const ANCHOR_COLS: &str = "id, checkpoint_id, chain_name, merkle_root, op_return_hex, \
-                           txid, status, verified_at, created_by, created_at, updated_at";
+                           txid, status, verified_at, created_by, created_at, updated_at, \
+                           broadcast_via, confirmations, block_hash, last_polled_at";
 
 fn map_anchor(r: &rusqlite::Row) -> rusqlite::Result<CheckpointAnchor> {
     Ok(CheckpointAnchor {
@@ -52,43 +60,43 @@ fn map_anchor(r: &rusqlite::Row) -> rusqlite::Result<CheckpointAnchor> {
         created_by: r.get(8)?,
         created_at: r.get(9)?,
         updated_at: r.get(10)?,
+        broadcast_via: r.get(11)?,
+        confirmations: r.get(12)?,
+        block_hash: r.get(13)?,
+        last_polled_at: r.get(14)?,
     })
 }
 
 /// Load a single anchor by id.
-pub fn get_anchor(conn: &Connection, anchor_id: &str) -> Result<CheckpointAnchor, String> {
+pub fn get_anchor(conn: &Connection, anchor_id: &str) -> Result<CheckpointAnchor, AppError> {
     conn.query_row(
         &format!("SELECT {} FROM checkpoint_anchors WHERE id = ?1", ANCHOR_COLS),
         params![anchor_id],
         map_anchor,
     )
-    .map_err(|_| format!("Anchor '{}' not found", anchor_id))
+    .map_err(|_| AppError::not_found("checkpoint_anchor", format!("Anchor '{}' not found", anchor_id)).with_id(anchor_id))
 }
 
 /// List anchors, newest first, optionally scoped to one checkpoint.
-pub fn list_anchors(conn: &Connection, checkpoint_id: Option<&str>) -> Result<Vec<CheckpointAnchor>, String> {
+pub fn list_anchors(conn: &Connection, checkpoint_id: Option<&str>) -> Result<Vec<CheckpointAnchor>, AppError> {
     match checkpoint_id {
         Some(cid) => {
             let mut stmt = conn
                 .prepare(&format!(
                     "SELECT {} FROM checkpoint_anchors WHERE checkpoint_id = ?1 ORDER BY created_at DESC",
                     ANCHOR_COLS
-                ))
-                .map_err(|e| e.to_string())?;
+                ))?;
             let rows = stmt
-                .query_map(params![cid], map_anchor)
-                .map_err(|e| e.to_string())?
+                .query_map(params![cid], map_anchor)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
         }
         None => {
             let mut stmt = conn
-                .prepare(&format!("SELECT {} FROM checkpoint_anchors ORDER BY created_at DESC", ANCHOR_COLS))
-                .map_err(|e| e.to_string())?;
+                .prepare(&format!("SELECT {} FROM checkpoint_anchors ORDER BY created_at DESC", ANCHOR_COLS))?;
             let rows = stmt
-                .query_map([], map_anchor)
-                .map_err(|e| e.to_string())?
+                .query_map([], map_anchor)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
@@ -105,19 +113,21 @@ pub fn prepare_anchor(
     checkpoint_id: &str,
     chain_name: &str,
     user_id: &str,
-) -> Result<CheckpointAnchor, String> {
+) -> Result<CheckpointAnchor, AppError> {
     let merkle_root: String = conn
         .query_row(
             "SELECT merkle_root FROM audit_checkpoints WHERE id = ?1",
             params![checkpoint_id],
             |r| r.get(0),
         )
-        .map_err(|_| format!("Checkpoint '{}' not found", checkpoint_id))?;
+        .map_err(|_| {
+            AppError::not_found("audit_checkpoint", format!("Checkpoint '{}' not found", checkpoint_id)).with_id(checkpoint_id)
+        })?;
 
     // build_op_return_script_hex rejects an all-zero / malformed root, so an
     // un-anchorable checkpoint surfaces a clear error here rather than storing a
     // meaningless payload.
-    let op_return_hex = build_op_return_script_hex(&merkle_root)?;
+    let op_return_hex = build_op_return_script_hex(&merkle_root).map_err(AppError::invalid)?;
 
     let chain = if chain_name.trim().is_empty() { "dogecoin" } else { chain_name.trim() };
     let id = uuid::Uuid::new_v4().to_string();
@@ -127,8 +137,7 @@ pub fn prepare_anchor(
          (id, checkpoint_id, chain_name, merkle_root, op_return_hex, status, created_by, created_at, updated_at) \
          VALUES (?1, ?2, ?3, ?4, ?5, 'prepared', ?6, ?7, ?7)",
         params![id, checkpoint_id, chain, merkle_root, op_return_hex, user_id, now],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     get_anchor(conn, &id)
 }
@@ -141,27 +150,25 @@ fn is_hex64(s: &str) -> bool {
 /// prepared `OP_RETURN` externally. Advances the anchor to `submitted` and writes
 /// the txid back onto the covering checkpoint's `anchored_txid` column (the
 /// Phase-2 hook reserved since migration 013).
-pub fn record_anchor_txid(conn: &Connection, anchor_id: &str, txid: &str) -> Result<CheckpointAnchor, String> {
+pub fn record_anchor_txid(conn: &Connection, anchor_id: &str, txid: &str) -> Result<CheckpointAnchor, AppError> {
     let anchor = get_anchor(conn, anchor_id)?;
     if anchor.status == "confirmed" {
-        return Err("Anchor is already confirmed; its txid cannot be changed".to_string());
+        return Err(AppError::conflict("Anchor is already confirmed; its txid cannot be changed"));
     }
     let txid = txid.trim();
     if !is_hex64(txid) {
-        return Err("A Dogecoin transaction id must be 64 hexadecimal characters".to_string());
+        return Err(AppError::validation("txid", "A Dogecoin transaction id must be 64 hexadecimal characters"));
     }
     let now = now_iso();
     conn.execute(
         "UPDATE checkpoint_anchors SET txid = ?1, status = 'submitted', updated_at = ?2 WHERE id = ?3",
         params![txid, now, anchor_id],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     // Surface the anchor on the checkpoint itself.
     conn.execute(
         "UPDATE audit_checkpoints SET anchored_txid = ?1 WHERE id = ?2",
         params![txid, anchor.checkpoint_id],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     get_anchor(conn, anchor_id)
 }
 
@@ -169,18 +176,18 @@ pub fn record_anchor_txid(conn: &Connection, anchor_id: &str, txid: &str) -> Res
 /// explorer (for the recorded txid) commits to exactly this anchor's Merkle root.
 /// Trusts nothing but the two inputs. On a match, advances the anchor to
 /// `confirmed` and stamps `verified_at`.
-pub fn verify_anchor(conn: &Connection, anchor_id: &str, op_return_hex: &str) -> Result<AnchorVerifyResult, String> {
+pub fn verify_anchor(conn: &Connection, anchor_id: &str, op_return_hex: &str) -> Result<AnchorVerifyResult, AppError> {
     let anchor = get_anchor(conn, anchor_id)?;
     let found_root = extract_root_from_hex(op_return_hex).ok();
-    let matches = op_return_matches_root(op_return_hex, &anchor.merkle_root)?;
+    let matches =
+        op_return_matches_root(op_return_hex, &anchor.merkle_root).map_err(|e| AppError::validation("op_return_hex", e))?;
 
     if matches {
         let now = now_iso();
         conn.execute(
             "UPDATE checkpoint_anchors SET status = 'confirmed', verified_at = ?1, updated_at = ?1 WHERE id = ?2",
             params![now, anchor_id],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     }
 
     Ok(AnchorVerifyResult {
@@ -0,0 +1,295 @@
+// WP-90: HTTP/1.1 framing for the local API.
+//
+// One request per connection: the server reads a request line, headers and a
+// `Content-Length` body, writes one response with `Connection: close`, and
+// hangs up. That is all a script or the PWA's replay loop needs, and it keeps
+// the framing small enough to test exhaustively. Chunked request bodies are
+// refused with 411 rather than decoded.
+use crate::error::AppError;
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
+
+/// Request line plus headers.
+pub const MAX_HEAD_BYTES: usize = 16 * 1024;
+/// Bulk sensor payloads are the largest legitimate body.
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// The path without the query string, percent-decoded.
+    pub path: String,
+    pub query: HashMap<String, String>,
+    /// Header names lower-cased.
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// The token from `Authorization: Bearer <token>`.
+    pub fn bearer_token(&self) -> Option<&str> {
+        let value = self.header("authorization")?.trim();
+        let (scheme, token) = value.split_once(' ')?;
+        let token = token.trim();
+        (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+    }
+}
+
+/// A framing failure, answered with `status` before the request reaches the
+/// router.
+#[derive(Debug, PartialEq)]
+pub struct FramingError {
+    pub status: u16,
+    pub message: String,
+}
+
+fn framing(status: u16, message: impl Into<String>) -> FramingError {
+    FramingError { status, message: message.into() }
+}
+
+/// Decode `%XX` escapes, and `+` as a space when `plus_as_space` (query
+/// strings). Invalid escapes and non-UTF-8 results are errors.
+pub fn percent_decode(s: &str, plus_as_space: bool) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).ok_or("Truncated percent escape")?;
+                let hex = std::str::from_utf8(hex).map_err(|_| "Invalid percent escape")?;
+                out.push(u8::from_str_radix(hex, 16).map_err(|_| "Invalid percent escape")?);
+                i += 3;
+            }
+            b'+' if plus_as_space => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|_| "Percent-decoded text is not UTF-8".to_string())
+}
+
+fn parse_query(query: &str) -> Result<HashMap<String, String>, String> {
+    let mut out = HashMap::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+        out.insert(percent_decode(k, true)?, percent_decode(v, true)?);
+    }
+    Ok(out)
+}
+
+/// Read one request. The head is read line by line up to [`MAX_HEAD_BYTES`];
+/// the body is read to its declared length, at most [`MAX_BODY_BYTES`].
+pub fn read_request<R: BufRead>(reader: &mut R) -> Result<Request, FramingError> {
+    let mut head_bytes = 0usize;
+    let mut read_line = |reader: &mut R| -> Result<String, FramingError> {
+        let mut line = Vec::new();
+        let n = reader
+            .by_ref()
+            .take((MAX_HEAD_BYTES - head_bytes.min(MAX_HEAD_BYTES)) as u64 + 1)
+            .read_until(b'\n', &mut line)
+            .map_err(|e| framing(400, format!("Failed to read request: {}", e)))?;
+        head_bytes += n;
+        if head_bytes > MAX_HEAD_BYTES {
+            return Err(framing(431, "Request headers are too large"));
+        }
+        if n == 0 || line.last() != Some(&b'\n') {
+            return Err(framing(400, "Incomplete request"));
+        }
+        let line = String::from_utf8(line).map_err(|_| framing(400, "Request head is not UTF-8"))?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+
+    let request_line = read_line(reader)?;
+    let mut parts = request_line.split(' ');
+    let (method, target, version) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(m), Some(t), Some(v), None) if !m.is_empty() && t.starts_with('/') => (m, t, v),
+        _ => return Err(framing(400, "Malformed request line")),
+    };
+    if version != "HTTP/1.1" && version != "HTTP/1.0" {
+        return Err(framing(505, "Only HTTP/1.0 and HTTP/1.1 are supported"));
+    }
+    let (raw_path, raw_query) = target.split_once('?').unwrap_or((target, ""));
+    let path = percent_decode(raw_path, false).map_err(|e| framing(400, e))?;
+    let query = parse_query(raw_query).map_err(|e| framing(400, e))?;
+
+    let mut headers = HashMap::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').ok_or_else(|| framing(400, "Malformed header line"))?;
+        headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+
+    if headers.get("transfer-encoding").is_some_and(|te| !te.eq_ignore_ascii_case("identity")) {
+        return Err(framing(411, "Chunked request bodies are not supported; send Content-Length"));
+    }
+    let length = match headers.get("content-length") {
+        Some(v) => v.parse::<usize>().map_err(|_| framing(400, "Invalid Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(framing(413, format!("Request body is larger than {} bytes", MAX_BODY_BYTES)));
+    }
+    let mut body = vec![0u8; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| framing(400, "Request body is shorter than its Content-Length"))?;
+
+    Ok(Request { method: method.to_string(), path, query, headers, body })
+}
+
+pub fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        505 => "HTTP Version Not Supported",
+        _ => "Unknown",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    /// Extra headers; `Content-Type`, `Content-Length` and `Connection` are
+    /// always written.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(status: u16, value: &serde_json::Value) -> Self {
+        Response { status, headers: Vec::new(), body: value.to_string().into_bytes() }
+    }
+
+    /// An error body: the message under `error`, as before WP-93, with the
+    /// error's stable `code` and `params` beside it.
+    pub fn error(status: u16, error: &AppError) -> Self {
+        Self::json(
+            status,
+            &serde_json::json!({ "error": error.message(), "code": error.code(), "params": error.params() }),
+        )
+    }
+
+    pub fn no_content() -> Self {
+        Response { status: 204, headers: Vec::new(), body: Vec::new() }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+pub fn write_response<W: Write>(out: &mut W, response: &Response) -> std::io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason_phrase(response.status));
+    if !response.body.is_empty() {
+        head.push_str("Content-Type: application/json; charset=utf-8\r\n");
+    }
+    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n", response.body.len()));
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    out.write_all(head.as_bytes())?;
+    out.write_all(&response.body)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn parse(raw: &str) -> Result<Request, FramingError> {
+        read_request(&mut BufReader::new(raw.as_bytes()))
+    }
+
+    #[test]
+    fn parses_a_request_with_query_headers_and_body() {
+        let req = parse(
+            "POST /api/v1/specimens%2Fx?page=2&query=hello+world%21 HTTP/1.1\r\n\
+             Host: localhost\r\nAuthorization: Bearer abc123\r\nContent-Length: 7\r\n\r\n{\"a\":1}",
+        )
+        .unwrap();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/api/v1/specimens/x");
+        assert_eq!(req.query.get("page").map(String::as_str), Some("2"));
+        assert_eq!(req.query.get("query").map(String::as_str), Some("hello world!"));
+        assert_eq!(req.header("HOST"), Some("localhost"));
+        assert_eq!(req.bearer_token(), Some("abc123"));
+        assert_eq!(req.body, b"{\"a\":1}");
+    }
+
+    #[test]
+    fn refuses_bad_framing_with_the_right_status() {
+        let status = |raw: &str| parse(raw).unwrap_err().status;
+        assert_eq!(status("GET /x\r\n\r\n"), 400, "no version");
+        assert_eq!(status("GET x HTTP/1.1\r\n\r\n"), 400, "not origin-form");
+        assert_eq!(status("GET /x HTTP/2\r\n\r\n"), 505);
+        assert_eq!(status("GET /x HTTP/1.1\r\nHost: a"), 400, "truncated head");
+        assert_eq!(status("POST /x HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"), 411);
+        assert_eq!(status("POST /x HTTP/1.1\r\nContent-Length: 99999999\r\n\r\n"), 413);
+        assert_eq!(status("POST /x HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort"), 400);
+        assert_eq!(status("GET /%zz HTTP/1.1\r\n\r\n"), 400);
+        let huge = format!("GET /x HTTP/1.1\r\nX-Pad: {}\r\n\r\n", "a".repeat(MAX_HEAD_BYTES));
+        assert_eq!(status(&huge), 431);
+    }
+
+    #[test]
+    fn bearer_token_needs_the_scheme_and_a_value() {
+        let with = |auth: &str| {
+            let mut headers = HashMap::new();
+            headers.insert("authorization".to_string(), auth.to_string());
+            Request { method: "GET".into(), path: "/".into(), query: HashMap::new(), headers, body: vec![] }
+        };
+        assert_eq!(with("bearer t0k").bearer_token(), Some("t0k"));
+        assert_eq!(with("Basic dXNlcg==").bearer_token(), None);
+        assert_eq!(with("Bearer ").bearer_token(), None);
+    }
+
+    #[test]
+    fn writes_a_closed_json_response() {
+        let mut out = Vec::new();
+        let error = AppError::not_found("route", "No such route");
+        write_response(&mut out, &Response::error(404, &error).with_header("Allow", "GET")).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(text.contains("Content-Length: 72\r\nConnection: close\r\nAllow: GET\r\n\r\n"), "{}", text);
+        let body: serde_json::Value = serde_json::from_str(text.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({ "error": "No such route", "code": "not_found", "params": { "entity": "route" } }));
+    }
+
+    #[test]
+    fn an_external_service_failure_is_a_bad_gateway() {
+        let error = AppError::external("smtp", "SMTP send failed: connection refused");
+        let mut out = Vec::new();
+        write_response(&mut out, &Response::error(crate::api::status_for(&error), &error)).unwrap();
+        assert!(String::from_utf8(out).unwrap().starts_with("HTTP/1.1 502 Bad Gateway\r\n"));
+    }
+}
@@ -0,0 +1,256 @@
+// WP-90: optional local REST API.
+//
+// Everything the app does is a Tauri command, reachable only from the desktop
+// webview. Lab automation scripts and the PWA (WP-62) need the same commands
+// over the network. This module serves them as HTTP/JSON, off by default and
+// switched on under Settings → Local API, on loopback or on the LAN.
+//
+// No business logic lives here. Each route in [`routes::ROUTES`] names the
+// command it calls, and `commands::api` calls that very function, so the
+// session check (`auth::validate_session`), the capability check, field
+// masking and the audit entry are the command's own. The token is a normal
+// session token from `POST /api/v1/auth/login`, or a service account's API
+// token (WP-92), sent as `Authorization: Bearer`.
+//
+// Like the Ollama and node RPC clients, the server is hand-rolled over
+// `std::net` rather than a new HTTP dependency: one request per connection,
+// a thread per connection up to [`server::MAX_CONNECTIONS`]. Plain HTTP only;
+// on the LAN, put it behind a TLS-terminating proxy.
+pub mod http;
+pub mod routes;
+pub mod server;
+
+use crate::error::AppError;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const DEFAULT_PORT: u16 = 8470;
+pub const DEFAULT_PER_PAGE: u32 = 50;
+pub const MAX_PER_PAGE: u32 = 200;
+
+/// `api_config`, as shown and saved by Settings → Local API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    /// `loopback` (this computer only) or `lan` (every interface).
+    pub bind: String,
+    pub port: u16,
+    /// Origins allowed to call from a browser, e.g. the PWA's
+    /// `https://lab.example.org`. Empty means no cross-origin access.
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        ApiConfig { enabled: false, bind: "loopback".to_string(), port: DEFAULT_PORT, allowed_origins: Vec::new() }
+    }
+}
+
+impl ApiConfig {
+    pub fn socket_addr(&self) -> std::net::SocketAddr {
+        let ip = if self.bind == "lan" {
+            std::net::Ipv4Addr::UNSPECIFIED
+        } else {
+            std::net::Ipv4Addr::LOCALHOST
+        };
+        std::net::SocketAddr::from((ip, self.port))
+    }
+
+    /// One line for the audit entry.
+    pub fn describe(&self) -> String {
+        format!(
+            "enabled={} bind={} port={} origins={}",
+            self.enabled,
+            self.bind,
+            self.port,
+            if self.allowed_origins.is_empty() { "none".to_string() } else { self.allowed_origins.join(",") }
+        )
+    }
+}
+
+/// An origin is `scheme://host[:port]`, nothing after it.
+fn validate_origin(origin: &str) -> Result<(), AppError> {
+    let rest = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))
+        .ok_or_else(|| AppError::validation("allowed_origins", format!("Allowed origin '{}' must start with http:// or https://", origin)))?;
+    if rest.is_empty() || rest.contains(['/', '?', '#', ' ', '*']) {
+        return Err(AppError::validation("allowed_origins", format!("Allowed origin '{}' must be scheme://host[:port] with no path", origin)));
+    }
+    Ok(())
+}
+
+pub fn validate(config: &ApiConfig) -> Result<(), AppError> {
+    if config.bind != "loopback" && config.bind != "lan" {
+        return Err(AppError::validation("bind", "Listen on must be 'loopback' or 'lan'"));
+    }
+    if config.port < 1024 {
+        return Err(AppError::validation("port", "Port must be between 1024 and 65535"));
+    }
+    config.allowed_origins.iter().try_for_each(|o| validate_origin(o))
+}
+
+pub fn get_config(conn: &Connection) -> Result<ApiConfig, AppError> {
+    Ok(conn.query_row(
+        "SELECT enabled, bind, port, allowed_origins FROM api_config WHERE id = 1",
+        [],
+        |r| {
+            let origins: Option<String> = r.get(3)?;
+            Ok(ApiConfig {
+                enabled: r.get::<_, i64>(0)? != 0,
+                bind: r.get(1)?,
+                port: r.get(2)?,
+                allowed_origins: origins
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect(),
+            })
+        },
+    )?)
+}
+
+/// Save the settings. Origins are trimmed of a trailing slash, which browsers
+/// never send.
+pub fn set_config(conn: &Connection, config: &ApiConfig) -> Result<ApiConfig, AppError> {
+    let mut config = config.clone();
+    config.allowed_origins = config
+        .allowed_origins
+        .iter()
+        .map(|o| o.trim().trim_end_matches('/').to_string())
+        .filter(|o| !o.is_empty())
+        .collect();
+    validate(&config)?;
+    let origins = Some(config.allowed_origins.join(" ")).filter(|o| !o.is_empty());
+    conn.execute(
+        "UPDATE api_config SET enabled = ?1, bind = ?2, port = ?3, allowed_origins = ?4, \
+         updated_at = datetime('now') WHERE id = 1",
+        params![config.enabled as i64, config.bind, config.port, origins],
+    )
+    .map_err(|e| format!("Failed to save API settings: {}", e))?;
+    Ok(config)
+}
+
+/// The HTTP status for a command's error (WP-93: read from its code, no
+/// longer from its wording).
+pub fn status_for(error: &AppError) -> u16 {
+    match error {
+        AppError::Unauthenticated { .. } => 401,
+        AppError::Forbidden { .. } => 403,
+        AppError::NotFound { .. } => 404,
+        AppError::Validation { .. } => 400,
+        AppError::Conflict { .. } => 409,
+        AppError::ExternalService { .. } => 502,
+        AppError::Integrity { .. } | AppError::Internal { .. } => 500,
+    }
+}
+
+/// `page` / `perPage` from the query string, defaulting to the first page of
+/// [`DEFAULT_PER_PAGE`].
+pub fn page_params(query: &std::collections::HashMap<String, String>) -> Result<(u32, u32), String> {
+    let get = |name: &str, default: u32, max: u32| -> Result<u32, String> {
+        match query.get(name) {
+            None => Ok(default),
+            Some(v) => v
+                .parse::<u32>()
+                .ok()
+                .filter(|n| (1..=max).contains(n))
+                .ok_or_else(|| format!("'{}' must be a whole number from 1 to {}", name, max)),
+        }
+    };
+    Ok((get("page", 1, u32::MAX)?, get("perPage", DEFAULT_PER_PAGE, MAX_PER_PAGE)?))
+}
+
+/// One page of a whole list, in the same shape as the commands' own
+/// `PaginatedResponse`.
+pub fn slice_page(items: Value, page: u32, per_page: u32) -> Result<Value, String> {
+    let Value::Array(items) = items else {
+        return Err("Expected a list from the command".to_string());
+    };
+    let total = items.len();
+    let start = (page as usize - 1).saturating_mul(per_page as usize).min(total);
+    let end = start.saturating_add(per_page as usize).min(total);
+    Ok(json!({
+        "items": items[start..end],
+        "total": total,
+        "page": page,
+        "per_page": per_page,
+        "total_pages": total.div_ceil(per_page as usize),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+    use crate::error::reason;
+    use std::collections::HashMap;
+
+    #[test]
+    fn config_round_trips_and_is_validated() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        assert_eq!(get_config(&conn).unwrap(), ApiConfig::default());
+
+        let saved = set_config(
+            &conn,
+            &ApiConfig {
+                enabled: true,
+                bind: "lan".into(),
+                port: 9000,
+                allowed_origins: vec![" https://lab.example.org/ ".into(), "".into(), "http://10.0.0.5:5173".into()],
+            },
+        )
+        .unwrap();
+        assert_eq!(saved.allowed_origins, vec!["https://lab.example.org", "http://10.0.0.5:5173"]);
+        assert_eq!(get_config(&conn).unwrap(), saved);
+        assert_eq!(saved.socket_addr().to_string(), "0.0.0.0:9000");
+
+        let bad = |c: ApiConfig| set_config(&conn, &c).unwrap_err();
+        assert!(bad(ApiConfig { bind: "public".into(), ..Default::default() }).message().contains("loopback"));
+        assert!(bad(ApiConfig { port: 80, ..Default::default() }).message().contains("1024"));
+        for origin in ["*", "lab.example.org", "https://lab.example.org/app", "https://*.example.org"] {
+            assert!(
+                set_config(&conn, &ApiConfig { allowed_origins: vec![origin.into()], ..Default::default() }).is_err(),
+                "{}",
+                origin
+            );
+        }
+    }
+
+    #[test]
+    fn error_codes_map_to_a_status() {
+        assert_eq!(status_for(&AppError::unauthenticated(reason::SESSION_EXPIRED, "Session expired or invalid")), 401);
+        assert_eq!(status_for(&AppError::missing_capability("specimen.create", "Insufficient permissions")), 403);
+        assert_eq!(status_for(&AppError::gated(reason::PASSWORD_CHANGE_REQUIRED, "A password change is required")), 403);
+        assert_eq!(status_for(&AppError::not_found("specimen", "Specimen not found")), 404);
+        assert_eq!(status_for(&AppError::validation("stage", "Stage must be one of …")), 400);
+        assert_eq!(status_for(&AppError::conflict("The username 'x' is already taken.")), 409);
+        assert_eq!(status_for(&AppError::external("smtp", "SMTP refused")), 502);
+        assert_eq!(status_for(&AppError::internal("Failed to write")), 500);
+        // Text from a module that still returns `String` is classified as before.
+        assert_eq!(status_for(&"Session expired after 30 minute(s) of inactivity. Sign in again.".into()), 401);
+        assert_eq!(status_for(&"Specimen not found".to_string().into()), 404);
+    }
+
+    #[test]
+    fn paging_parameters_and_slices() {
+        let mut q = HashMap::new();
+        assert_eq!(page_params(&q).unwrap(), (1, DEFAULT_PER_PAGE));
+        q.insert("page".to_string(), "3".to_string());
+        q.insert("perPage".to_string(), "2".to_string());
+        assert_eq!(page_params(&q).unwrap(), (3, 2));
+        q.insert("perPage".to_string(), (MAX_PER_PAGE + 1).to_string());
+        assert!(page_params(&q).is_err());
+        q.insert("perPage".to_string(), "2".to_string());
+        q.insert("page".to_string(), "0".to_string());
+        assert!(page_params(&q).is_err());
+
+        let page = slice_page(json!([1, 2, 3, 4, 5]), 3, 2).unwrap();
+        assert_eq!(page, json!({ "items": [5], "total": 5, "page": 3, "per_page": 2, "total_pages": 3 }));
+        let past_end = slice_page(json!([1]), 9, 2).unwrap();
+        assert_eq!(past_end["items"], json!([]));
+        assert!(slice_page(json!({}), 1, 2).is_err());
+    }
+}
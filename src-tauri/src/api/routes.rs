@@ -0,0 +1,544 @@
+// WP-90: the route table. Each route names the Tauri command it calls
+// (`operation`), so the HTTP surface is the command surface under another
+// transport: the command still validates the session, checks the capability,
+// masks fields and writes the audit entry. The same table drives routing,
+// argument building and the OpenAPI document, so the three cannot drift.
+use serde_json::{json, Map, Value};
+
+/// Where a route's command arguments come from besides the path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Body {
+    None,
+    /// The JSON body is passed as the one named argument (e.g. `request`).
+    /// Path parameters are copied into it, so `PUT /specimens/{id}` fills
+    /// `request.id`.
+    Field(&'static str),
+    /// The JSON body's keys are the command's arguments, as with `invoke()`.
+    Merge,
+}
+
+/// How a list route pages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paging {
+    None,
+    /// The command takes `page` / `perPage` and returns a page.
+    Command,
+    /// The command returns the whole list; the server slices it.
+    Slice,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryKind {
+    Int,
+    Str,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Route {
+    pub method: &'static str,
+    /// `{name}` segments are path parameters, passed under that name.
+    pub path: &'static str,
+    /// The Tauri command this route calls.
+    pub operation: &'static str,
+    pub tag: &'static str,
+    pub summary: &'static str,
+    /// `false` only for sign-in; every other route needs a bearer token.
+    pub auth: bool,
+    /// The capability the command checks, for the documentation. `None` when
+    /// any signed-in user may call it; an API token then needs the `read`
+    /// scope (WP-92, [`needs_read_scope`]).
+    pub capability: Option<&'static str>,
+    pub query: &'static [(&'static str, QueryKind)],
+    pub body: Body,
+    /// The Rust type the body deserializes into, for the documentation.
+    pub body_type: Option<&'static str>,
+    pub paging: Paging,
+    /// 201 for routes that create a record.
+    pub status: u16,
+}
+
+const fn route(method: &'static str, path: &'static str, operation: &'static str, tag: &'static str, summary: &'static str) -> Route {
+    Route {
+        method,
+        path,
+        operation,
+        tag,
+        summary,
+        auth: true,
+        capability: None,
+        query: &[],
+        body: Body::None,
+        body_type: None,
+        paging: Paging::None,
+        status: 200,
+    }
+}
+
+impl Route {
+    const fn public(mut self) -> Self {
+        self.auth = false;
+        self
+    }
+    const fn needs(mut self, capability: &'static str) -> Self {
+        self.capability = Some(capability);
+        self
+    }
+    const fn query(mut self, query: &'static [(&'static str, QueryKind)]) -> Self {
+        self.query = query;
+        self
+    }
+    const fn body(mut self, field: &'static str, body_type: &'static str) -> Self {
+        self.body = Body::Field(field);
+        self.body_type = Some(body_type);
+        self
+    }
+    const fn args(mut self, body_type: &'static str) -> Self {
+        self.body = Body::Merge;
+        self.body_type = Some(body_type);
+        self
+    }
+    const fn paged(mut self, paging: Paging) -> Self {
+        self.paging = paging;
+        self
+    }
+    const fn creates(mut self) -> Self {
+        self.status = 201;
+        self
+    }
+}
+
+pub const PREFIX: &str = "/api/v1";
+
+/// Every route, matched in order: a literal segment listed before a
+/// parameter in the same position wins.
+pub const ROUTES: &[Route] = &[
+    route("POST", "/api/v1/auth/login", "login", "auth", "Sign in; returns a session token")
+        .public()
+        .args("{ username, password, device? }"),
+    route("POST", "/api/v1/auth/mfa", "verify_login_mfa", "auth", "Complete sign-in with an authenticator or recovery code")
+        .args("{ code }"),
+    route("POST", "/api/v1/auth/logout", "logout", "auth", "End this session"),
+    route("GET", "/api/v1/me", "get_current_user", "auth", "The signed-in user"),
+    route("GET", "/api/v1/specimens", "list_specimens", "specimens", "List specimens")
+        .paged(Paging::Command),
+    route("POST", "/api/v1/specimens/search", "search_specimens", "specimens", "Search specimens by text and filters")
+        .body("paramsInput", "SpecimenSearchParams")
+        .paged(Paging::Command),
+    route("POST", "/api/v1/specimens", "create_specimen", "specimens", "Create a specimen")
+        .needs("specimen.create")
+        .body("request", "CreateSpecimenRequest")
+        .creates(),
+    route("POST", "/api/v1/specimens/batch", "initiate_specimen_batch", "specimens", "Initiate a batch of specimens from a template; returns its labels")
+        .needs("specimen.create")
+        .body("request", "InitiateBatchRequest")
+        .creates(),
+    route("GET", "/api/v1/specimens/{id}", "get_specimen", "specimens", "One specimen"),
+    route("PUT", "/api/v1/specimens/{id}", "update_specimen", "specimens", "Update a specimen")
+        .needs("specimen.edit")
+        .body("request", "UpdateSpecimenRequest"),
+    route("GET", "/api/v1/specimens/{specimenId}/subcultures", "list_subcultures", "subcultures", "A specimen's passages")
+        .paged(Paging::Command),
+    route("GET", "/api/v1/specimens/{specimenId}/readings", "list_environmental_readings", "sensors", "A specimen's environmental readings, newest first")
+        .query(&[("limit", QueryKind::Int)]),
+    route("POST", "/api/v1/subcultures", "create_subculture", "subcultures", "Record a passage")
+        .needs("subculture.record")
+        .body("request", "CreateSubcultureRequest")
+        .creates(),
+    route("GET", "/api/v1/media", "list_media", "media", "List media batches")
+        .paged(Paging::Slice),
+    route("POST", "/api/v1/media", "create_media_batch", "media", "Create a media batch")
+        .needs("media.edit")
+        .body("request", "CreateMediaBatchRequest")
+        .creates(),
+    route("GET", "/api/v1/media/{id}", "get_media_batch", "media", "One media batch"),
+    route("GET", "/api/v1/reminders", "list_reminders", "reminders", "List reminders")
+        .paged(Paging::Slice),
+    route("POST", "/api/v1/reminders", "create_reminder", "reminders", "Create a reminder")
+        .needs("reminder.edit")
+        .body("request", "CreateReminderRequest")
+        .creates(),
+    route("POST", "/api/v1/reminders/{id}/dismiss", "dismiss_reminder", "reminders", "Complete or snooze a reminder")
+        .args("{ snooze, snoozeDays? }"),
+    route("POST", "/api/v1/readings", "create_environmental_reading", "sensors", "Record one environmental reading")
+        .needs("sensor.record")
+        .body("request", "CreateEnvironmentalReadingRequest")
+        .creates(),
+    route("POST", "/api/v1/sensors/ingest", "ingest_sensor_payload", "sensors", "Ingest a raw sensor payload (CSV or JSON)")
+        .needs("sensor.record")
+        .args("{ specimenId?, subcultureId?, source, rawPayload }")
+        .creates(),
+];
+
+/// Call any routed command by name with the same arguments `invoke()` takes,
+/// minus the token. The PWA's offline queue replays through this.
+pub const INVOKE_PATH: &str = "/api/v1/invoke/{command}";
+pub const HEALTH_PATH: &str = "/api/v1/health";
+pub const OPENAPI_PATH: &str = "/api/v1/openapi.json";
+
+#[derive(Debug, PartialEq)]
+pub enum Match<'a> {
+    Found(&'a Route, Map<String, Value>),
+    /// The path exists, but not for this method. Carries the allowed methods.
+    MethodNotAllowed(Vec<&'static str>),
+    NotFound,
+}
+
+/// Match `path` against a template, returning the path parameters.
+pub fn match_path(template: &str, path: &str) -> Option<Map<String, Value>> {
+    let mut params = Map::new();
+    let mut t = template.split('/');
+    let mut p = path.trim_end_matches('/').split('/');
+    loop {
+        match (t.next(), p.next()) {
+            (None, None) => return Some(params),
+            (Some(ts), Some(ps)) => {
+                if let Some(name) = ts.strip_prefix('{').and_then(|n| n.strip_suffix('}')) {
+                    if ps.is_empty() {
+                        return None;
+                    }
+                    params.insert(name.to_string(), Value::String(ps.to_string()));
+                } else if ts != ps {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+}
+
+pub fn find(method: &str, path: &str) -> Match<'static> {
+    let mut allowed = Vec::new();
+    for r in ROUTES {
+        if let Some(params) = match_path(r.path, path) {
+            if r.method == method {
+                return Match::Found(r, params);
+            }
+            allowed.push(r.method);
+        }
+    }
+    if allowed.is_empty() {
+        Match::NotFound
+    } else {
+        Match::MethodNotAllowed(allowed)
+    }
+}
+
+/// The route for an operation name, for `invoke`.
+pub fn by_operation(operation: &str) -> Option<&'static Route> {
+    ROUTES.iter().find(|r| r.operation == operation)
+}
+
+/// WP-92: calls about the caller's own sign-in. An API token needs no scope
+/// for them.
+const IDENTITY_OPERATIONS: &[&str] = &["login", "verify_login_mfa", "logout", "get_current_user"];
+
+/// Whether an API token needs the `read` scope for `operation`: every signed-in
+/// route that checks no capability, identity calls aside. Routes with a
+/// capability are scoped by the command's own `require_capability`.
+pub fn needs_read_scope(operation: &str) -> bool {
+    !IDENTITY_OPERATIONS.contains(&operation)
+        && by_operation(operation).is_some_and(|r| r.auth && r.capability.is_none())
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Build the command's arguments from path parameters, the query string and
+/// the JSON body. Paging arguments are added by the server.
+pub fn build_args(
+    route: &Route,
+    path_params: Map<String, Value>,
+    query: &std::collections::HashMap<String, String>,
+    body: &[u8],
+) -> Result<Map<String, Value>, String> {
+    let json_body = || -> Result<Value, String> {
+        if body.iter().all(u8::is_ascii_whitespace) {
+            return Err("This route needs a JSON body".to_string());
+        }
+        serde_json::from_slice(body).map_err(|e| format!("Request body is not valid JSON: {}", e))
+    };
+
+    let mut args = Map::new();
+    match route.body {
+        Body::None => {}
+        Body::Field(name) => {
+            let mut value = json_body()?;
+            let object = value.as_object_mut().ok_or("Request body must be a JSON object")?;
+            for (k, v) in &path_params {
+                let key = snake_case(k);
+                match object.get(&key) {
+                    Some(existing) if existing != v => {
+                        return Err(format!("'{}' in the body does not match the path", key));
+                    }
+                    _ => {
+                        object.insert(key, v.clone());
+                    }
+                }
+            }
+            args.insert(name.to_string(), value);
+        }
+        Body::Merge => {
+            if !body.iter().all(u8::is_ascii_whitespace) {
+                match json_body()? {
+                    Value::Object(object) => args.extend(object),
+                    _ => return Err("Request body must be a JSON object".to_string()),
+                }
+            }
+        }
+    }
+    args.extend(path_params);
+    for (name, kind) in route.query {
+        if let Some(raw) = query.get(*name) {
+            let value = match kind {
+                QueryKind::Int => raw
+                    .parse::<i64>()
+                    .map(Value::from)
+                    .map_err(|_| format!("Query parameter '{}' must be a whole number", name))?,
+                QueryKind::Str => Value::String(raw.clone()),
+            };
+            args.insert(name.to_string(), value);
+        }
+    }
+    Ok(args)
+}
+
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|s| s.strip_prefix('{').and_then(|n| n.strip_suffix('}')))
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+        .collect()
+}
+
+fn error_responses(auth: bool) -> Map<String, Value> {
+    let err = |d: &str| json!({ "description": d, "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } } });
+    let mut out = Map::new();
+    out.insert("400".into(), err("The request is invalid (`validation`); `params.field` names the field when there is one"));
+    if auth {
+        out.insert("401".into(), err("Missing, expired or revoked token (`unauthenticated`)"));
+        out.insert("403".into(), err("The role or token scope lacks the capability (`forbidden`)"));
+    }
+    out.insert("404".into(), err("No such record (`not_found`)"));
+    out.insert("409".into(), err("The request clashes with existing data (`conflict`)"));
+    out.insert("500".into(), err("An integrity or internal failure (`integrity`, `internal`)"));
+    out.insert("502".into(), err("A service outside the app failed (`external_service`)"));
+    out
+}
+
+/// The OpenAPI 3.0 description of [`ROUTES`]. Bodies are described by the
+/// Rust request type they deserialize into, whose fields are snake_case as in
+/// the Tauri commands.
+pub fn openapi(version: &str) -> Value {
+    let mut paths = Map::new();
+    for r in ROUTES {
+        let mut parameters = path_parameters(r.path);
+        for (name, kind) in r.query {
+            let ty = if *kind == QueryKind::Int { "integer" } else { "string" };
+            parameters.push(json!({ "name": name, "in": "query", "required": false, "schema": { "type": ty } }));
+        }
+        if r.paging != Paging::None {
+            parameters.push(json!({ "name": "page", "in": "query", "required": false, "schema": { "type": "integer", "minimum": 1, "default": 1 } }));
+            parameters.push(json!({ "name": "perPage", "in": "query", "required": false, "schema": { "type": "integer", "minimum": 1, "maximum": super::MAX_PER_PAGE, "default": super::DEFAULT_PER_PAGE } }));
+        }
+        let mut description = match r.capability {
+            Some(c) => format!("Calls `{}`. Needs the `{}` capability.", r.operation, c),
+            None if needs_read_scope(r.operation) => {
+                format!("Calls `{}`. Any signed-in user; API tokens need the `read` scope.", r.operation)
+            }
+            None if r.auth => format!("Calls `{}`. Any signed-in user.", r.operation),
+            None => format!("Calls `{}`. No token needed.", r.operation),
+        };
+        if let Some(t) = r.body_type {
+            description.push_str(&format!(" Body: `{}`.", t));
+        }
+        let ok = if r.paging == Paging::None {
+            json!({ "description": "Success" })
+        } else {
+            json!({ "description": "One page", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Page" } } } })
+        };
+        let mut responses = error_responses(r.auth);
+        responses.insert(r.status.to_string(), ok);
+        let mut op = json!({
+            "operationId": r.operation,
+            "tags": [r.tag],
+            "summary": r.summary,
+            "description": description,
+            "parameters": parameters,
+            "responses": responses,
+        });
+        if r.body != Body::None {
+            op["requestBody"] = json!({
+                "required": matches!(r.body, Body::Field(_)),
+                "content": { "application/json": { "schema": { "type": "object", "description": r.body_type } } },
+            });
+        }
+        if !r.auth {
+            op["security"] = json!([]);
+        }
+        if let Some(c) = r.capability {
+            op["x-capability"] = json!(c);
+        }
+        let openapi_path = r.path.strip_prefix(PREFIX).unwrap_or(r.path);
+        paths
+            .entry(openapi_path.to_string())
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("path item is an object")
+            .insert(r.method.to_ascii_lowercase(), op);
+    }
+    paths.insert(
+        INVOKE_PATH.strip_prefix(PREFIX).unwrap_or(INVOKE_PATH).to_string(),
+        json!({ "post": {
+            "operationId": "invoke",
+            "tags": ["invoke"],
+            "summary": "Call a routed command by name with its invoke() arguments",
+            "description": "`command` is the operationId of any route above. The body is the same argument object the desktop app passes to `invoke()`, without `token`.",
+            "parameters": path_parameters(INVOKE_PATH),
+            "requestBody": { "required": false, "content": { "application/json": { "schema": { "type": "object" } } } },
+            "responses": error_responses(true),
+        }}),
+    );
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "SteloPTC local API",
+            "version": version,
+            "description": "The desktop app's commands over HTTP. Sign in with POST /auth/login and send the token as `Authorization: Bearer <token>`, or send a service account's API token (`stk_…`) the same way. An API token can use the capabilities in its scope, and the routes without one only with the `read` scope.",
+        },
+        "servers": [{ "url": PREFIX }],
+        "security": [{ "bearer": [] }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": { "bearer": { "type": "http", "scheme": "bearer" } },
+            "schemas": {
+                "Error": {
+                    "type": "object",
+                    "required": ["error", "code", "params"],
+                    "properties": {
+                        "error": { "type": "string", "description": "The English message" },
+                        "code": {
+                            "type": "string",
+                            "enum": [
+                                "unauthenticated", "forbidden", "not_found", "validation",
+                                "conflict", "integrity", "external_service", "internal",
+                            ],
+                        },
+                        "params": { "type": "object", "additionalProperties": { "type": "string" } },
+                    },
+                },
+                "Page": {
+                    "type": "object",
+                    "properties": {
+                        "items": { "type": "array", "items": { "type": "object" } },
+                        "total": { "type": "integer" },
+                        "page": { "type": "integer" },
+                        "per_page": { "type": "integer" },
+                        "total_pages": { "type": "integer" },
+                    },
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn literal_segments_win_and_parameters_are_captured() {
+        match find("POST", "/api/v1/specimens/search") {
+            Match::Found(r, _) => assert_eq!(r.operation, "search_specimens"),
+            other => panic!("{:?}", other),
+        }
+        match find("GET", "/api/v1/specimens/abc/subcultures/") {
+            Match::Found(r, params) => {
+                assert_eq!(r.operation, "list_subcultures");
+                assert_eq!(params["specimenId"], "abc");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(find("DELETE", "/api/v1/specimens/abc"), Match::MethodNotAllowed(vec!["GET", "PUT"]));
+        assert_eq!(find("GET", "/api/v1/specimens//subcultures"), Match::NotFound);
+        assert_eq!(find("GET", "/api/v2/specimens"), Match::NotFound);
+    }
+
+    #[test]
+    fn operations_are_unique_and_the_table_is_well_formed() {
+        let mut seen = std::collections::HashSet::new();
+        for r in ROUTES {
+            assert!(seen.insert(r.operation), "{} is routed twice", r.operation);
+            assert!(r.path.starts_with(PREFIX), "{}", r.path);
+            assert!(["GET", "POST", "PUT"].contains(&r.method));
+            assert!(r.method != "GET" || r.body == Body::None, "{} reads a body on GET", r.operation);
+            assert!(r.body == Body::None || r.body_type.is_some(), "{} body is undocumented", r.operation);
+        }
+    }
+
+    #[test]
+    fn api_tokens_need_the_read_scope_only_for_uncapabilitied_data_routes() {
+        for op in ["list_specimens", "search_specimens", "get_media_batch", "list_environmental_readings", "dismiss_reminder"] {
+            assert!(needs_read_scope(op), "{op}");
+        }
+        for op in ["login", "verify_login_mfa", "logout", "get_current_user", "create_environmental_reading", "ingest_sensor_payload", "no_such_command"] {
+            assert!(!needs_read_scope(op), "{op}");
+        }
+    }
+
+    #[test]
+    fn path_parameters_fill_the_request_body() {
+        let r = by_operation("update_specimen").unwrap();
+        let params = match_path(r.path, "/api/v1/specimens/s1").unwrap();
+        let args = build_args(r, params.clone(), &HashMap::new(), br#"{"stage":"rooting"}"#).unwrap();
+        assert_eq!(args["request"], json!({ "stage": "rooting", "id": "s1" }));
+        assert_eq!(args["id"], "s1");
+
+        let err = build_args(r, params.clone(), &HashMap::new(), br#"{"id":"other"}"#).unwrap_err();
+        assert!(err.contains("does not match"), "{}", err);
+        assert!(build_args(r, params.clone(), &HashMap::new(), b"").is_err(), "body required");
+        assert!(build_args(r, params, &HashMap::new(), b"[1]").is_err(), "object required");
+    }
+
+    #[test]
+    fn merged_bodies_and_typed_query_parameters() {
+        let r = by_operation("list_environmental_readings").unwrap();
+        let params = match_path(r.path, "/api/v1/specimens/s1/readings").unwrap();
+        let mut query = HashMap::new();
+        query.insert("limit".to_string(), "20".to_string());
+        query.insert("ignored".to_string(), "x".to_string());
+        let args = build_args(r, params.clone(), &query, b"").unwrap();
+        assert_eq!(args.get("limit"), Some(&json!(20)));
+        assert!(!args.contains_key("ignored"));
+        query.insert("limit".to_string(), "many".to_string());
+        assert!(build_args(r, params, &query, b"").is_err());
+
+        let r = by_operation("dismiss_reminder").unwrap();
+        let params = match_path(r.path, "/api/v1/reminders/r1/dismiss").unwrap();
+        let args = build_args(r, params, &HashMap::new(), br#"{"snooze":true,"snoozeDays":3}"#).unwrap();
+        assert_eq!(Value::Object(args), json!({ "id": "r1", "snooze": true, "snoozeDays": 3 }));
+    }
+
+    #[test]
+    fn openapi_describes_every_route() {
+        let doc = openapi("1.2.3");
+        assert_eq!(doc["info"]["version"], "1.2.3");
+        for r in ROUTES {
+            let item = &doc["paths"][r.path.strip_prefix(PREFIX).unwrap()][r.method.to_ascii_lowercase()];
+            assert_eq!(item["operationId"], r.operation);
+            assert!(item["responses"].get(r.status.to_string()).is_some());
+        }
+        assert_eq!(doc["paths"]["/auth/login"]["post"]["security"], json!([]));
+        assert_eq!(doc["paths"]["/specimens"]["post"]["x-capability"], "specimen.create");
+        assert!(doc["paths"]["/invoke/{command}"]["post"].is_object());
+    }
+}
@@ -0,0 +1,405 @@
+// WP-90: the listener and the request pipeline.
+//
+// `handle` turns one parsed request into one response: CORS, the built-in
+// health and OpenAPI routes, routing, the bearer token, argument building,
+// paging, and the error-to-status mapping. The command call itself is the
+// `Dispatch` closure, which `commands::api` builds over the Tauri commands and
+// the tests replace with a stub.
+use std::io::BufReader;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use serde_json::{json, Map, Value};
+
+use super::http::{self, Request, Response};
+use crate::error::AppError;
+use super::routes::{self, Match, Paging, Route};
+
+/// Connections served at once. The database lock serializes the commands
+/// anyway; this only bounds the threads a burst of clients can start.
+pub const MAX_CONNECTIONS: usize = 16;
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Calls the named command with its arguments and returns its JSON result.
+pub type Dispatch = dyn Fn(&str, Map<String, Value>) -> Result<Value, AppError> + Send + Sync;
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub allowed_origins: Vec<String>,
+    /// Reported by `/health` and the OpenAPI document.
+    pub version: String,
+}
+
+fn cors(options: &Options, req: &Request, response: Response) -> Response {
+    match req.header("origin") {
+        Some(origin) if options.allowed_origins.iter().any(|o| o == origin) => response
+            .with_header("Access-Control-Allow-Origin", origin)
+            .with_header("Vary", "Origin"),
+        _ => response,
+    }
+}
+
+fn preflight(options: &Options, req: &Request) -> Response {
+    let allowed = req.header("origin").is_some_and(|o| options.allowed_origins.iter().any(|a| a == o));
+    if !allowed {
+        return Response::error(403, &AppError::forbidden("This origin is not allowed; add it under Settings → Local API"));
+    }
+    cors(
+        options,
+        req,
+        Response::no_content()
+            .with_header("Access-Control-Allow-Methods", "GET, POST, PUT, OPTIONS")
+            .with_header("Access-Control-Allow-Headers", "Authorization, Content-Type")
+            .with_header("Access-Control-Max-Age", "600"),
+    )
+}
+
+fn call(route: &Route, mut args: Map<String, Value>, req: &Request, dispatch: &Dispatch) -> Response {
+    if route.auth {
+        match req.bearer_token() {
+            Some(token) => {
+                args.insert("token".to_string(), Value::String(token.to_string()));
+            }
+            None => {
+                let error = AppError::Unauthenticated { message: "Missing bearer token".to_string(), reason: None };
+                return Response::error(401, &error).with_header("WWW-Authenticate", "Bearer");
+            }
+        }
+    }
+    let page = if route.paging == Paging::None {
+        None
+    } else {
+        match super::page_params(&req.query) {
+            Ok(p) => Some(p),
+            Err(e) => return Response::error(400, &AppError::invalid(e)),
+        }
+    };
+    if let (Paging::Command, Some((page, per_page))) = (route.paging, page) {
+        match route.body {
+            // Search carries its paging inside the body; the query wins when given.
+            routes::Body::Field(field) => {
+                if let Some(body) = args.get_mut(field).and_then(Value::as_object_mut) {
+                    if req.query.contains_key("page") || !body.contains_key("page") {
+                        body.insert("page".into(), json!(page));
+                    }
+                    if req.query.contains_key("perPage") || !body.contains_key("per_page") {
+                        body.insert("per_page".into(), json!(per_page));
+                    }
+                }
+            }
+            _ => {
+                args.insert("page".into(), json!(page));
+                args.insert("perPage".into(), json!(per_page));
+            }
+        }
+    }
+    let result = dispatch(route.operation, args).and_then(|value| match (route.paging, page) {
+        (Paging::Slice, Some((page, per_page))) => super::slice_page(value, page, per_page).map_err(AppError::internal),
+        _ => Ok(value),
+    });
+    match result {
+        Ok(Value::Null) => Response::no_content(),
+        Ok(value) => Response::json(route.status, &value),
+        Err(e) => {
+            let status = super::status_for(&e);
+            let response = Response::error(status, &e);
+            if status == 401 {
+                response.with_header("WWW-Authenticate", "Bearer")
+            } else {
+                response
+            }
+        }
+    }
+}
+
+/// One request, one response.
+pub fn handle(req: &Request, options: &Options, dispatch: &Dispatch) -> Response {
+    if req.method == "OPTIONS" {
+        return preflight(options, req);
+    }
+    let response = if req.method == "GET" && req.path == routes::HEALTH_PATH {
+        Response::json(200, &json!({ "status": "ok", "version": options.version }))
+    } else if req.method == "GET" && req.path == routes::OPENAPI_PATH {
+        Response::json(200, &routes::openapi(&options.version))
+    } else if let Some(params) = routes::match_path(routes::INVOKE_PATH, &req.path) {
+        invoke(req, params, dispatch)
+    } else {
+        match routes::find(&req.method, &req.path) {
+            Match::Found(route, params) => match routes::build_args(route, params, &req.query, &req.body) {
+                Ok(args) => call(route, args, req, dispatch),
+                Err(e) => Response::error(400, &AppError::invalid(e)),
+            },
+            Match::MethodNotAllowed(allowed) => {
+                Response::error(405, &AppError::invalid("Method not allowed")).with_header("Allow", &allowed.join(", "))
+            }
+            Match::NotFound => Response::error(404, &AppError::not_found("route", "No such route; see /api/v1/openapi.json")),
+        }
+    };
+    cors(options, req, response)
+}
+
+/// `POST /api/v1/invoke/{command}`: the body is the command's `invoke()`
+/// argument object. Only routed commands can be called, and paging is the
+/// caller's, as in the desktop app.
+fn invoke(req: &Request, params: Map<String, Value>, dispatch: &Dispatch) -> Response {
+    if req.method != "POST" {
+        return Response::error(405, &AppError::invalid("Method not allowed")).with_header("Allow", "POST");
+    }
+    let command = params.get("command").and_then(Value::as_str).unwrap_or_default();
+    let Some(route) = routes::by_operation(command) else {
+        let error = AppError::not_found("operation", format!("'{}' is not available over the API", command));
+        return Response::error(404, &error);
+    };
+    let args = if req.body.iter().all(u8::is_ascii_whitespace) {
+        Map::new()
+    } else {
+        match serde_json::from_slice::<Value>(&req.body) {
+            Ok(Value::Object(args)) => args,
+            Ok(_) => return Response::error(400, &AppError::invalid("Request body must be a JSON object")),
+            Err(e) => return Response::error(400, &AppError::invalid(format!("Request body is not valid JSON: {}", e))),
+        }
+    };
+    if args.contains_key("token") {
+        return Response::error(400, &AppError::invalid("Send the token in the Authorization header, not the body"));
+    }
+    let unpaged = Route { paging: Paging::None, ..*route };
+    call(&unpaged, args, req, dispatch)
+}
+
+fn serve_connection(stream: TcpStream, options: &Options, dispatch: &Dispatch) {
+    stream.set_read_timeout(Some(IO_TIMEOUT)).ok();
+    stream.set_write_timeout(Some(IO_TIMEOUT)).ok();
+    let mut writer = match stream.try_clone() {
+        Ok(w) => w,
+        Err(_) => return,
+    };
+    let response = match http::read_request(&mut BufReader::new(stream)) {
+        Ok(req) => handle(&req, options, dispatch),
+        Err(e) => Response::error(e.status, &AppError::invalid(e.message)),
+    };
+    http::write_response(&mut writer, &response).ok();
+}
+
+/// A running server. Dropping it stops the listener; requests already being
+/// served finish on their own threads.
+pub struct ApiServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ApiServer {
+    pub fn start(bind: SocketAddr, options: Options, dispatch: Arc<Dispatch>) -> Result<Self, String> {
+        let listener = TcpListener::bind(bind).map_err(|e| format!("Could not listen on {}: {}", bind, e))?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let active = Arc::new(AtomicUsize::new(0));
+        let options = Arc::new(options);
+        let stopping = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name("stelo-api".into())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if stopping.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(mut stream) = stream else { continue };
+                    if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        stream.set_write_timeout(Some(IO_TIMEOUT)).ok();
+                        let busy = AppError::internal("Too many connections; retry shortly");
+                        http::write_response(&mut stream, &Response::error(503, &busy)).ok();
+                        continue;
+                    }
+                    let (options, dispatch, done) = (options.clone(), dispatch.clone(), active.clone());
+                    let spawned = std::thread::Builder::new().name("stelo-api-conn".into()).spawn(move || {
+                        serve_connection(stream, &options, dispatch.as_ref());
+                        done.fetch_sub(1, Ordering::SeqCst);
+                    });
+                    if spawned.is_err() {
+                        active.fetch_sub(1, Ordering::SeqCst);
+                    }
+                }
+            })
+            .map_err(|e| format!("Could not start the API thread: {}", e))?;
+        Ok(ApiServer { addr, stop, thread: Some(thread) })
+    }
+
+    /// The bound address, with the real port when 0 was asked for.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the blocking accept so the loop sees the flag.
+        let wake = SocketAddr::from((std::net::Ipv4Addr::LOCALHOST, self.addr.port()));
+        TcpStream::connect_timeout(&wake, Duration::from_secs(1)).ok();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::sync::Mutex;
+
+    type Calls = Arc<Mutex<Vec<(String, Map<String, Value>)>>>;
+
+    /// A dispatcher that records its calls and answers by operation.
+    fn stub(calls: Calls) -> Arc<Dispatch> {
+        Arc::new(move |op: &str, args: Map<String, Value>| {
+            calls.lock().unwrap().push((op.to_string(), args.clone()));
+            match op {
+                "login" => Ok(json!({ "token": "t" })),
+                "list_reminders" => Ok(json!([{ "id": 1 }, { "id": 2 }, { "id": 3 }])),
+                "logout" => Ok(Value::Null),
+                "get_specimen" if args["id"] == "missing" => Err(AppError::not_found("specimen", "Specimen not found").with_id("missing")),
+                "create_specimen" => Err(AppError::missing_capability(
+                    "specimen.create",
+                    "Insufficient permissions — your role does not include \"Create specimens\" (specimen.create).",
+                )),
+                _ => Ok(json!({ "op": op })),
+            }
+        })
+    }
+
+    fn request(method: &str, target: &str, token: Option<&str>, body: &str) -> Request {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let mut headers = HashMap::new();
+        if let Some(t) = token {
+            headers.insert("authorization".to_string(), format!("Bearer {}", t));
+        }
+        Request {
+            method: method.into(),
+            path: path.into(),
+            query: query
+                .split('&')
+                .filter(|p| !p.is_empty())
+                .map(|p| {
+                    let (k, v) = p.split_once('=').unwrap();
+                    (k.to_string(), v.to_string())
+                })
+                .collect(),
+            headers,
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    fn body(r: &Response) -> Value {
+        serde_json::from_slice(&r.body).unwrap_or(Value::Null)
+    }
+
+    #[test]
+    fn tokens_paging_and_statuses() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let d = stub(calls.clone());
+        let opts = Options::default();
+
+        let r = handle(&request("POST", "/api/v1/auth/login", None, r#"{"username":"a","password":"b"}"#), &opts, d.as_ref());
+        assert_eq!(r.status, 200);
+        assert!(!calls.lock().unwrap()[0].1.contains_key("token"), "sign-in takes no token");
+
+        let r = handle(&request("GET", "/api/v1/reminders", None, ""), &opts, d.as_ref());
+        assert_eq!(r.status, 401);
+        assert_eq!(calls.lock().unwrap().len(), 1, "the command is not called without a token");
+
+        let r = handle(&request("GET", "/api/v1/reminders?page=2&perPage=2", Some("tok"), ""), &opts, d.as_ref());
+        assert_eq!(body(&r), json!({ "items": [{ "id": 3 }], "total": 3, "page": 2, "per_page": 2, "total_pages": 2 }));
+        assert_eq!(calls.lock().unwrap()[1].1["token"], "tok");
+
+        handle(&request("GET", "/api/v1/specimens?page=4", Some("tok"), ""), &opts, d.as_ref());
+        let args = calls.lock().unwrap()[2].1.clone();
+        assert_eq!((args["page"].clone(), args["perPage"].clone()), (json!(4), json!(50)));
+
+        handle(&request("POST", "/api/v1/specimens/search?perPage=5", Some("tok"), r#"{"query":"x","page":2}"#), &opts, d.as_ref());
+        let args = calls.lock().unwrap()[3].1.clone();
+        assert_eq!(args["paramsInput"], json!({ "query": "x", "page": 2, "per_page": 5 }));
+
+        let r = handle(&request("GET", "/api/v1/specimens/missing", Some("tok"), ""), &opts, d.as_ref());
+        assert_eq!(r.status, 404);
+        assert_eq!(
+            body(&r),
+            json!({ "error": "Specimen not found", "code": "not_found", "params": { "entity": "specimen", "id": "missing" } })
+        );
+        let r = handle(&request("POST", "/api/v1/specimens", Some("tok"), "{}"), &opts, d.as_ref());
+        assert_eq!((r.status, body(&r)["code"].clone()), (403, json!("forbidden")));
+        assert_eq!(body(&r)["params"]["capability"], "specimen.create");
+        assert_eq!(handle(&request("POST", "/api/v1/auth/logout", Some("tok"), ""), &opts, d.as_ref()).status, 204);
+        assert_eq!(handle(&request("GET", "/api/v1/reminders?perPage=0", Some("tok"), ""), &opts, d.as_ref()).status, 400);
+        assert_eq!(handle(&request("DELETE", "/api/v1/reminders", Some("tok"), ""), &opts, d.as_ref()).status, 405);
+        assert_eq!(handle(&request("GET", "/api/v1/nothing", Some("tok"), ""), &opts, d.as_ref()).status, 404);
+        let r = handle(&request("GET", "/api/v1/openapi.json", None, ""), &opts, d.as_ref());
+        assert_eq!(body(&r)["openapi"], "3.0.3");
+    }
+
+    #[test]
+    fn invoke_calls_only_routed_commands_with_their_own_arguments() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let d = stub(calls.clone());
+        let opts = Options::default();
+        let r = handle(&request("POST", "/api/v1/invoke/dismiss_reminder", Some("tok"), r#"{"id":"r1","snooze":false}"#), &opts, d.as_ref());
+        assert_eq!(r.status, 200);
+        assert_eq!(Value::Object(calls.lock().unwrap()[0].1.clone()), json!({ "id": "r1", "snooze": false, "token": "tok" }));
+
+        let r = handle(&request("POST", "/api/v1/invoke/list_reminders", Some("tok"), ""), &opts, d.as_ref());
+        assert!(body(&r).is_array(), "invoke returns what the command returns, unpaged");
+        assert_eq!(handle(&request("POST", "/api/v1/invoke/reset_database", Some("tok"), "{}"), &opts, d.as_ref()).status, 404);
+        assert_eq!(handle(&request("POST", "/api/v1/invoke/list_media", Some("tok"), r#"{"token":"x"}"#), &opts, d.as_ref()).status, 400);
+        assert_eq!(handle(&request("GET", "/api/v1/invoke/list_media", Some("tok"), ""), &opts, d.as_ref()).status, 405);
+    }
+
+    #[test]
+    fn cross_origin_access_is_limited_to_listed_origins() {
+        let d = stub(Arc::new(Mutex::new(Vec::new())));
+        let opts = Options { allowed_origins: vec!["https://pwa.lab".into()], ..Default::default() };
+        let mut pre = request("OPTIONS", "/api/v1/media", None, "");
+        pre.headers.insert("origin".into(), "https://pwa.lab".into());
+        let r = handle(&pre, &opts, d.as_ref());
+        assert_eq!(r.status, 204);
+        assert!(r.headers.contains(&("Access-Control-Allow-Origin".into(), "https://pwa.lab".into())));
+
+        pre.headers.insert("origin".into(), "https://evil.example".into());
+        assert_eq!(handle(&pre, &opts, d.as_ref()).status, 403);
+        let mut get = request("GET", "/api/v1/health", None, "");
+        get.headers.insert("origin".into(), "https://evil.example".into());
+        let r = handle(&get, &opts, d.as_ref());
+        assert!(r.headers.iter().all(|(k, _)| k != "Access-Control-Allow-Origin"));
+    }
+
+    #[test]
+    fn serves_over_a_socket_and_stops_when_dropped() {
+        let d = stub(Arc::new(Mutex::new(Vec::new())));
+        let server = ApiServer::start(
+            SocketAddr::from((std::net::Ipv4Addr::LOCALHOST, 0)),
+            Options { version: "9.9.9".into(), ..Default::default() },
+            d,
+        )
+        .unwrap();
+        let addr = server.local_addr();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /api/v1/health HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        assert!(reply.starts_with("HTTP/1.1 200 OK"), "{}", reply);
+        assert!(reply.ends_with(r#"{"status":"ok","version":"9.9.9"}"#), "{}", reply);
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"garbage\r\n\r\n").unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        assert!(reply.starts_with("HTTP/1.1 400"), "{}", reply);
+
+        drop(server);
+        assert!(TcpListener::bind(addr).is_ok(), "the port is released");
+    }
+}
@@ -0,0 +1,450 @@
+// WP-92: service accounts and scoped API tokens for machine clients.
+//
+// Sensors, label printers and scripts used to sign in as a person to reach
+// the local API or `stelo-cli`, with that person's full role and a session
+// that lapsed after a day. A **service account** is a `users` row with
+// `auth_source = 'service'`: it has a role like anyone else but no password,
+// so it can never sign in interactively. It authenticates with **API
+// tokens**, each of which carries a scope — `read` and/or capability keys —
+// and an optional expiry.
+//
+// A token is accepted wherever a session token is (`auth::validate_session`),
+// and the `User` it yields carries its `TokenScope`. What the caller may do is
+// then the intersection of the account's role and the token's scope:
+// `auth::require_capability` checks both, and `auth::require_read_scope`
+// guards the calls that need no capability at the remote interfaces (the local
+// API's read routes and the `stelo-cli` read commands).
+//
+// Tokens are stored as `auth::hash_token` digests, like sessions; the raw
+// value is shown once, when it is created. Revoking a token stamps
+// `revoked_at` rather than deleting the row, so the list keeps a record of
+// which clients had access.
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use super::roles::{self, Capability};
+use crate::error::{reason, AppError};
+use crate::models::user::{User, UserRole};
+
+/// Every API token starts with this, so `validate_session` can tell one from
+/// a session token without a lookup, and so a leaked one is recognisable in
+/// logs and secret scanners.
+pub const TOKEN_PREFIX: &str = "stk_";
+/// The scope for reading lab data, and for the few calls open to every
+/// signed-in user (dismissing a reminder).
+pub const READ_SCOPE: &str = "read";
+pub const MAX_NAME_LEN: usize = 80;
+/// Ten years: long enough for a sensor gateway, short enough that "never" is
+/// an explicit choice.
+pub const MAX_LIFETIME_DAYS: i64 = 3650;
+/// How many characters of the raw token the list shows, prefix included.
+const HINT_LEN: usize = 8;
+
+/// What a token may be used for, attached to the `User` it authenticates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenScope {
+    pub token_id: String,
+    pub read: bool,
+    pub capabilities: Vec<Capability>,
+}
+
+impl TokenScope {
+    fn parse(token_id: String, scopes: &str) -> Self {
+        let keys: Vec<&str> = scopes.split_whitespace().collect();
+        TokenScope {
+            token_id,
+            read: keys.contains(&READ_SCOPE),
+            // Keys dropped from the catalogue by a later release grant nothing.
+            capabilities: keys.iter().filter_map(|k| Capability::from_key(k)).collect(),
+        }
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub user_id: String,
+    pub username: String,
+    pub display_name: String,
+    pub role: String,
+    /// `read` first, then capability keys in catalogue order.
+    pub scopes: Vec<String>,
+    /// The first characters of the token, to tell tokens apart.
+    pub hint: String,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+    pub revoked_at: Option<String>,
+    /// Past `expires_at` and so no longer accepted.
+    pub expired: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateServiceAccountRequest {
+    pub username: String,
+    pub display_name: String,
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiTokenRequest {
+    pub service_account_id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// `None` for a token that does not expire.
+    pub expires_in_days: Option<i64>,
+}
+
+/// A new token. `token` is the only time the raw value is available.
+#[derive(Debug, Serialize)]
+pub struct CreatedApiToken {
+    pub token: String,
+    pub info: ApiTokenInfo,
+}
+
+pub fn is_api_token(token: &str) -> bool {
+    token.starts_with(TOKEN_PREFIX)
+}
+
+/// Check a requested scope against the account's role and put it in
+/// canonical order. A token cannot be scoped beyond its account's role: the
+/// role would refuse the extra capabilities anyway, and listing them would
+/// misstate what the token can do.
+pub fn normalize_scopes(conn: &Connection, role: &UserRole, requested: &[String]) -> Result<Vec<String>, AppError> {
+    let requested: Vec<&str> = requested.iter().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+    if requested.is_empty() {
+        return Err(AppError::validation("scopes", "Choose at least one scope for the token."));
+    }
+    for key in &requested {
+        if *key != READ_SCOPE && Capability::from_key(key).is_none() {
+            return Err(AppError::validation("scopes", format!("Unknown scope '{}'.", key)));
+        }
+    }
+    let mut scopes = Vec::new();
+    if requested.contains(&READ_SCOPE) {
+        scopes.push(READ_SCOPE.to_string());
+    }
+    for capability in Capability::ALL.iter().copied().filter(|c| requested.contains(&c.key())) {
+        if !roles::has_capability(conn, role, capability)? {
+            return Err(AppError::validation(
+                "scopes",
+                format!(
+                    "The {} role does not include \"{}\" ({}), so a token for this account cannot be scoped to it.",
+                    role.as_str(),
+                    capability.label(),
+                    capability.key()
+                ),
+            ));
+        }
+        scopes.push(capability.key().to_string());
+    }
+    Ok(scopes)
+}
+
+/// Create a service account. It gets no usable password: `!` is not a bcrypt
+/// hash, so every password check against it fails. The caller has already
+/// checked that it may grant `role`.
+pub fn create_service_account(conn: &Connection, request: &CreateServiceAccountRequest) -> Result<String, AppError> {
+    let username = request.username.trim();
+    let display_name = request.display_name.trim();
+    if username.is_empty() || display_name.is_empty() {
+        return Err(AppError::invalid("A service account needs a username and a display name."));
+    }
+    let role = roles::existing_role(conn, &request.role)?;
+    // Service accounts would count toward the last-admin guard, letting the
+    // last human administrator be demoted.
+    if role.is_admin() {
+        return Err(AppError::validation(
+            "role",
+            "Service accounts cannot hold the admin role. Give them a role with only what they need.",
+        ));
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO users (id, username, password_hash, display_name, role, auth_source) \
+         VALUES (?1, ?2, '!', ?3, ?4, 'service')",
+        params![id, username, display_name, role.as_str()],
+    )
+    .map_err(|e| {
+        if e.to_string().contains("UNIQUE") {
+            AppError::conflict(format!("The username '{}' is already taken.", username))
+        } else {
+            AppError::internal(format!("Failed to create service account: {}", e))
+        }
+    })?;
+    Ok(id)
+}
+
+/// The role of an active service account, or why a token cannot be issued
+/// for `user_id`.
+pub fn service_account_role(conn: &Connection, user_id: &str) -> Result<UserRole, AppError> {
+    let (source, role, active): (String, String, bool) = conn
+        .query_row(
+            "SELECT auth_source, role, is_active FROM users WHERE id = ?1",
+            params![user_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get::<_, i64>(2)? != 0)),
+        )
+        .optional()
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::not_found("user", "User not found").with_id(user_id))?;
+    if source != "service" {
+        return Err(AppError::validation(
+            "service_account_id",
+            "API tokens are issued to service accounts only. Create one for this client first.",
+        ));
+    }
+    if !active {
+        return Err(AppError::validation(
+            "service_account_id",
+            "This service account is deactivated. Reactivate it before issuing tokens.",
+        ));
+    }
+    Ok(role.parse().unwrap_or(UserRole::Guest))
+}
+
+/// Issue a token. The caller has checked its right to manage the account.
+pub fn create(conn: &Connection, request: &CreateApiTokenRequest, created_by: &str) -> Result<CreatedApiToken, AppError> {
+    let role = service_account_role(conn, &request.service_account_id)?;
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("name", "Give the token a name that says which client uses it."));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::validation("name", format!("Token names are limited to {} characters.", MAX_NAME_LEN)));
+    }
+    let scopes = normalize_scopes(conn, &role, &request.scopes)?;
+    let expires_at = match request.expires_in_days {
+        None => None,
+        Some(days) if (1..=MAX_LIFETIME_DAYS).contains(&days) => Some(super::expiry_after(chrono::Duration::days(days))),
+        Some(_) => {
+            return Err(AppError::validation(
+                "expires_in_days",
+                format!("Token lifetime must be 1–{} days, or no expiry.", MAX_LIFETIME_DAYS),
+            ))
+        }
+    };
+
+    let token = format!("{}{}", TOKEN_PREFIX, super::generate_token());
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO api_tokens (id, user_id, name, token_hash, hint, scopes, created_by, expires_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            id,
+            request.service_account_id,
+            name,
+            super::hash_token(&token),
+            &token[..HINT_LEN],
+            scopes.join(" "),
+            created_by,
+            expires_at
+        ],
+    )
+    .map_err(|e| AppError::internal(format!("Failed to create API token: {}", e)))?;
+    Ok(CreatedApiToken { token, info: get(conn, &id)? })
+}
+
+const INFO_SELECT: &str = "SELECT t.id, t.name, t.user_id, u.username, u.display_name, u.role, t.scopes, t.hint,
+                                  c.username, t.created_at, t.expires_at, t.last_used_at, t.revoked_at,
+                                  t.expires_at IS NOT NULL AND t.expires_at <= datetime('now')
+                           FROM api_tokens t JOIN users u ON t.user_id = u.id
+                           LEFT JOIN users c ON t.created_by = c.id";
+
+fn info_from_row(r: &rusqlite::Row) -> rusqlite::Result<ApiTokenInfo> {
+    Ok(ApiTokenInfo {
+        id: r.get(0)?,
+        name: r.get(1)?,
+        user_id: r.get(2)?,
+        username: r.get(3)?,
+        display_name: r.get(4)?,
+        role: r.get(5)?,
+        scopes: r.get::<_, String>(6)?.split_whitespace().map(str::to_string).collect(),
+        hint: r.get(7)?,
+        created_by: r.get(8)?,
+        created_at: r.get(9)?,
+        expires_at: r.get(10)?,
+        last_used_at: r.get(11)?,
+        revoked_at: r.get(12)?,
+        expired: r.get(13)?,
+    })
+}
+
+pub fn get(conn: &Connection, id: &str) -> Result<ApiTokenInfo, AppError> {
+    conn.query_row(&format!("{} WHERE t.id = ?1", INFO_SELECT), params![id], info_from_row)
+        .optional()
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::not_found("api_token", "API token not found").with_id(id))
+}
+
+/// Every token, live ones first, newest first.
+pub fn list(conn: &Connection) -> Result<Vec<ApiTokenInfo>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} ORDER BY t.revoked_at IS NOT NULL, t.created_at DESC, t.id",
+            INFO_SELECT
+        ))
+        ?;
+    let rows = stmt
+        .query_map([], info_from_row)
+        ?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        ?;
+    Ok(rows)
+}
+
+/// Stop a token working. Returns whether it was live.
+pub fn revoke(conn: &Connection, id: &str) -> Result<bool, AppError> {
+    conn.execute(
+        "UPDATE api_tokens SET revoked_at = datetime('now') WHERE id = ?1 AND revoked_at IS NULL",
+        params![id],
+    )
+    .map(|n| n > 0)
+    .map_err(|e| AppError::internal(format!("Failed to revoke API token: {}", e)))
+}
+
+/// The service account behind a live token, with the token's scope attached,
+/// recording the use. Unknown, revoked and expired tokens, and tokens of
+/// deactivated or time-boxed-out accounts, all get the same error as a dead
+/// session so clients need one "sign in again" path.
+pub fn authenticate(conn: &Connection, token: &str) -> Result<User, AppError> {
+    let token_hash = super::hash_token(token);
+    let (mut user, token_id, scopes): (User, String, String) = conn
+        .query_row(
+            "SELECT u.id, u.username, u.password_hash, u.display_name, u.email, u.role, u.is_active, u.must_change_password, u.created_at, u.updated_at, u.auth_source, u.access_expires_at, t.id, t.scopes
+             FROM api_tokens t JOIN users u ON t.user_id = u.id
+             WHERE t.token_hash = ?1 AND t.revoked_at IS NULL
+               AND (t.expires_at IS NULL OR t.expires_at > datetime('now'))
+               AND u.auth_source = 'service' AND u.is_active = 1
+               AND (u.access_expires_at IS NULL OR u.access_expires_at > datetime('now'))",
+            params![token_hash],
+            |row| Ok((super::user_from_row(row)?, row.get(12)?, row.get(13)?)),
+        )
+        .map_err(|_| AppError::unauthenticated(reason::SESSION_EXPIRED, "Session expired or invalid"))?;
+    conn.execute(
+        "UPDATE api_tokens SET last_used_at = datetime('now') WHERE id = ?1",
+        params![token_id],
+    )
+    ?;
+    user.token_scope = Some(TokenScope::parse(token_id, &scopes));
+    Ok(user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    /// A migrated database with an admin and one service account in `role`.
+    /// Returns the database, the account's id and the admin's id.
+    fn db_with_account(role: &str) -> (Database, String, String) {
+        let db = Database::new_in_memory().unwrap();
+        db.run_migrations().unwrap();
+        db.conn
+            .execute(
+                "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('a1', 'admin', 'x', 'Admin', 'admin')",
+                [],
+            )
+            .unwrap();
+        let admin = "a1".to_string();
+        let id = create_service_account(
+            &db.conn,
+            &CreateServiceAccountRequest {
+                username: "greenhouse-gw".into(),
+                display_name: "Greenhouse gateway".into(),
+                role: role.into(),
+            },
+        )
+        .unwrap();
+        (db, id, admin)
+    }
+
+    fn request(account: &str, scopes: &[&str], days: Option<i64>) -> CreateApiTokenRequest {
+        CreateApiTokenRequest {
+            service_account_id: account.to_string(),
+            name: "Bench 3 logger".into(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_in_days: days,
+        }
+    }
+
+    #[test]
+    fn a_token_authenticates_its_account_with_its_scope() {
+        let (db, account, admin) = db_with_account("tech");
+        let created = create(&db.conn, &request(&account, &["sensor.record", " read "], Some(90)), &admin).unwrap();
+        assert!(is_api_token(&created.token));
+        assert!(created.token.starts_with(&created.info.hint));
+        assert_eq!(created.info.scopes, vec!["read", "sensor.record"], "read first, then catalogue order");
+        assert!(created.info.expires_at.is_some() && created.info.last_used_at.is_none());
+
+        let stored: String = db.conn.query_row("SELECT token_hash FROM api_tokens", [], |r| r.get(0)).unwrap();
+        assert_ne!(stored, created.token, "only the digest is stored");
+
+        let user = authenticate(&db.conn, &created.token).unwrap();
+        assert_eq!(user.id, account);
+        let scope = user.token_scope.expect("token users carry their scope");
+        assert!(scope.read && scope.allows(Capability::SensorRecord));
+        assert!(!scope.allows(Capability::SpecimenCreate), "the tech role has it; the token does not");
+        assert!(get(&db.conn, &created.info.id).unwrap().last_used_at.is_some());
+    }
+
+    #[test]
+    fn scopes_are_limited_to_the_accounts_role() {
+        let (db, account, admin) = db_with_account("guest");
+        let err = create(&db.conn, &request(&account, &["sensor.record"], None), &admin).unwrap_err();
+        assert!(err.message().contains("guest role does not include"), "{err}");
+        assert_eq!(err.params()["field"], "scopes");
+        assert!(create(&db.conn, &request(&account, &["no.such"], None), &admin).unwrap_err().message().contains("Unknown scope"));
+        assert!(create(&db.conn, &request(&account, &[" "], None), &admin).is_err());
+        assert!(create(&db.conn, &request(&account, &["read"], Some(0)), &admin).is_err());
+        assert!(create(&db.conn, &request(&account, &["read"], Some(MAX_LIFETIME_DAYS + 1)), &admin).is_err());
+        assert!(create(&db.conn, &request(&account, &["read"], None), &admin).unwrap().info.expires_at.is_none());
+    }
+
+    #[test]
+    fn tokens_are_for_active_service_accounts_only() {
+        let (db, account, admin) = db_with_account("tech");
+        assert!(create(&db.conn, &request(&admin, &["read"], None), &admin).unwrap_err().message().contains("service accounts only"));
+
+        let err = create_service_account(
+            &db.conn,
+            &CreateServiceAccountRequest { username: "root-bot".into(), display_name: "Root".into(), role: "admin".into() },
+        )
+        .unwrap_err();
+        assert!(err.message().contains("cannot hold the admin role"));
+
+        // A service account has no password to sign in with.
+        assert!(crate::auth::authenticate(&db, "greenhouse-gw", "!").is_err());
+
+        let created = create(&db.conn, &request(&account, &["read"], None), &admin).unwrap();
+        db.conn.execute("UPDATE users SET is_active = 0 WHERE id = ?1", params![account]).unwrap();
+        assert_eq!(authenticate(&db.conn, &created.token).unwrap_err().code(), "unauthenticated");
+        assert!(create(&db.conn, &request(&account, &["read"], None), &admin).unwrap_err().message().contains("deactivated"));
+    }
+
+    #[test]
+    fn revoked_and_expired_tokens_stop_working() {
+        let (db, account, admin) = db_with_account("tech");
+        let a = create(&db.conn, &request(&account, &["read"], None), &admin).unwrap();
+        let b = create(&db.conn, &request(&account, &["read"], Some(30)), &admin).unwrap();
+
+        assert!(revoke(&db.conn, &a.info.id).unwrap());
+        assert!(!revoke(&db.conn, &a.info.id).unwrap(), "already revoked");
+        assert!(authenticate(&db.conn, &a.token).is_err());
+
+        db.conn
+            .execute("UPDATE api_tokens SET expires_at = datetime('now', '-1 minute') WHERE id = ?1", params![b.info.id])
+            .unwrap();
+        assert!(authenticate(&db.conn, &b.token).is_err());
+
+        let listed = list(&db.conn).unwrap();
+        assert_eq!(listed.len(), 2, "revoked tokens stay listed");
+        assert_eq!(listed[0].id, b.info.id, "live tokens first");
+        assert!(listed[0].expired && listed[1].revoked_at.is_some());
+    }
+}
@@ -0,0 +1,831 @@
+// WP-85: LDAP / Active Directory authentication.
+//
+// An institute that manages identities centrally does not want a second set of
+// passwords in SteloPTC. With a directory configured, `auth::authenticate`
+// sends every account except local administrators to the directory:
+//
+//   1. bind as the service account (or anonymously) and search `user_base_dn`
+//      with `user_filter`, `{username}` replaced by the escaped login name;
+//   2. refuse disabled entries (AD `userAccountControl` bit 2, OpenLDAP
+//      `pwdAccountLockedTime`, 389-DS `nsAccountLock`);
+//   3. bind as the entry's DN with the supplied password;
+//   4. map the entry's groups to a role through `ldap_group_roles`, taking the
+//      most privileged match, or `default_role` when nothing matches;
+//   5. create the `users` row on first login (just-in-time provisioning), or
+//      refresh its name, email, role and active flag.
+//
+// Local accounts with the admin role keep their bcrypt password. They are the
+// way back in when the directory is unreachable or misconfigured, which is why
+// the directory cannot be switched on without one.
+//
+// `sync_accounts` repeats steps 1, 2 and 4 for every directory account without
+// a password, so an account disabled or removed in the directory is
+// deactivated here on the next scheduler tick rather than at its next login.
+//
+// Everything above the socket goes through the `Directory` trait, as
+// `anchoring::node_rpc` does with `RpcTransport`, so the login and sync flows
+// are unit-tested against an in-memory directory. A directory that cannot be
+// reached or refuses a query is an `AppError::external("ldap", …)`.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::models::user::UserRole;
+
+/// Stored in `users.password_hash` for directory accounts. Not a bcrypt hash,
+/// so `bcrypt::verify` always fails and the account cannot sign in locally.
+pub const DIRECTORY_PASSWORD_MARKER: &str = "!directory";
+
+/// LDAP result code for a failed bind with a wrong password.
+const LDAP_INVALID_CREDENTIALS: u32 = 49;
+/// AD `userAccountControl` flag for a disabled account.
+const AD_ACCOUNTDISABLE: u64 = 0x2;
+
+/// One directory entry, reduced to what login and sync need.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DirectoryEntry {
+    pub dn: String,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    /// Group DNs from `memberOf`, plus any groups found under `group_base_dn`.
+    pub groups: Vec<String>,
+    pub disabled: bool,
+}
+
+/// The two questions SteloPTC asks a directory. Implemented over LDAP by
+/// [`LdapDirectory`] and in memory by the tests.
+pub trait Directory {
+    /// `Ok(None)` when no entry matches; `Err` when the directory cannot be
+    /// asked (unreachable, service bind refused, ambiguous filter).
+    fn find_user(&self, username: &str) -> Result<Option<DirectoryEntry>, AppError>;
+    /// `Ok(false)` for a wrong password.
+    fn verify_password(&self, dn: &str, password: &str) -> Result<bool, AppError>;
+}
+
+// ── Configuration ───────────────────────────────────────────────────────────
+
+/// Display form of `ldap_config`. Never carries the service password.
+#[derive(Debug, Clone, Serialize)]
+pub struct LdapConfig {
+    pub enabled: bool,
+    pub url: Option<String>,
+    pub starttls: bool,
+    pub bind_dn: Option<String>,
+    pub bind_password_set: bool,
+    pub user_base_dn: Option<String>,
+    pub user_filter: String,
+    pub group_base_dn: Option<String>,
+    pub display_name_attribute: String,
+    pub email_attribute: String,
+    pub default_role: Option<String>,
+    pub timeout_secs: i64,
+}
+
+/// `bind_password: None` keeps the stored password, as `set_smtp_config` does.
+#[derive(Debug, Deserialize)]
+pub struct SetLdapConfigRequest {
+    pub enabled: bool,
+    pub url: String,
+    pub starttls: bool,
+    pub bind_dn: Option<String>,
+    pub bind_password: Option<String>,
+    pub user_base_dn: String,
+    pub user_filter: String,
+    pub group_base_dn: Option<String>,
+    pub display_name_attribute: String,
+    pub email_attribute: String,
+    pub default_role: Option<String>,
+    pub timeout_secs: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupRoleMapping {
+    pub group_dn: String,
+    pub role: String,
+}
+
+pub fn get_config(conn: &Connection) -> Result<LdapConfig, AppError> {
+    conn.query_row(
+        "SELECT enabled, url, starttls, bind_dn, bind_password, user_base_dn, user_filter, group_base_dn, \
+                display_name_attribute, email_attribute, default_role, timeout_secs \
+         FROM ldap_config WHERE id = 1",
+        [],
+        |r| {
+            let password: Option<String> = r.get(4)?;
+            Ok(LdapConfig {
+                enabled: r.get::<_, i64>(0)? != 0,
+                url: r.get(1)?,
+                starttls: r.get::<_, i64>(2)? != 0,
+                bind_dn: r.get(3)?,
+                bind_password_set: password.is_some_and(|p| !p.is_empty()),
+                user_base_dn: r.get(5)?,
+                user_filter: r.get(6)?,
+                group_base_dn: r.get(7)?,
+                display_name_attribute: r.get(8)?,
+                email_attribute: r.get(9)?,
+                default_role: r.get(10)?,
+                timeout_secs: r.get(11)?,
+            })
+        },
+    )
+    .map_err(AppError::from)
+}
+
+/// Check a URL is `ldap://` or `ldaps://` with a host.
+pub fn validate_url(url: &str) -> Result<(), AppError> {
+    let rest = url
+        .trim()
+        .strip_prefix("ldaps://")
+        .or_else(|| url.trim().strip_prefix("ldap://"))
+        .ok_or_else(|| {
+            AppError::validation("url", format!("Directory URL must start with ldap:// or ldaps:// (got '{}')", url.trim()))
+        })?;
+    if rest.trim_end_matches('/').is_empty() {
+        return Err(AppError::validation("url", "Directory URL has no host"));
+    }
+    Ok(())
+}
+
+/// Save the directory settings. Enabling needs an active local admin, so the
+/// lab keeps a way in that does not depend on the directory.
+pub fn set_config(conn: &Connection, req: &SetLdapConfigRequest) -> Result<(), AppError> {
+    let trimmed = |v: &str| Some(v.trim().to_string()).filter(|s| !s.is_empty());
+    let opt = |v: &Option<String>| v.as_deref().and_then(trimmed);
+    if !req.url.trim().is_empty() || req.enabled {
+        validate_url(&req.url)?;
+    }
+    if req.url.trim().starts_with("ldaps://") && req.starttls {
+        return Err(AppError::validation(
+            "starttls",
+            "StartTLS cannot be combined with ldaps:// — the connection is already encrypted",
+        ));
+    }
+    if !req.user_filter.contains("{username}") {
+        return Err(AppError::validation("user_filter", "The user filter must contain {username}"));
+    }
+    if !(1..=120).contains(&req.timeout_secs) {
+        return Err(AppError::validation("timeout_secs", "Timeout must be between 1 and 120 seconds"));
+    }
+    if let Some(role) = opt(&req.default_role) {
+        super::roles::existing_role(conn, &role).map_err(|e| AppError::validation("default_role", format!("Default role: {}", e)))?;
+    }
+    if req.display_name_attribute.trim().is_empty() || req.email_attribute.trim().is_empty() {
+        return Err(AppError::invalid("Display name and email attributes are required"));
+    }
+    if req.enabled {
+        if req.user_base_dn.trim().is_empty() {
+            return Err(AppError::validation("user_base_dn", "A user base DN is required"));
+        }
+        if local_admin_count(conn)? == 0 {
+            return Err(AppError::invalid(
+                "Create an active local administrator account first. It is the only way to sign in if the \
+                 directory is unreachable.",
+            ));
+        }
+    }
+    conn.execute(
+        "UPDATE ldap_config SET enabled = ?1, url = ?2, starttls = ?3, bind_dn = ?4, user_base_dn = ?5, \
+         user_filter = ?6, group_base_dn = ?7, display_name_attribute = ?8, email_attribute = ?9, \
+         default_role = ?10, timeout_secs = ?11, updated_at = datetime('now') WHERE id = 1",
+        params![
+            req.enabled as i64,
+            trimmed(&req.url),
+            req.starttls as i64,
+            opt(&req.bind_dn),
+            trimmed(&req.user_base_dn),
+            req.user_filter.trim(),
+            opt(&req.group_base_dn),
+            req.display_name_attribute.trim(),
+            req.email_attribute.trim(),
+            opt(&req.default_role),
+            req.timeout_secs,
+        ],
+    )
+    .map_err(|e| AppError::internal(format!("Failed to save directory settings: {}", e)))?;
+    if let Some(password) = &req.bind_password {
+        conn.execute("UPDATE ldap_config SET bind_password = ?1 WHERE id = 1", params![password])
+            .map_err(|e| AppError::internal(format!("Failed to save directory settings: {}", e)))?;
+    }
+    Ok(())
+}
+
+fn local_admin_count(conn: &Connection) -> Result<i64, AppError> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FROM users WHERE role = 'admin' AND is_active = 1 AND auth_source = 'local'",
+        [],
+        |r| r.get(0),
+    )?)
+}
+
+pub fn list_group_mappings(conn: &Connection) -> Result<Vec<GroupRoleMapping>, AppError> {
+    let mut stmt = conn.prepare("SELECT group_dn, role FROM ldap_group_roles ORDER BY group_dn")?;
+    let rows = stmt
+        .query_map([], |r| Ok(GroupRoleMapping { group_dn: r.get(0)?, role: r.get(1)? }))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub fn set_group_mapping(conn: &Connection, group_dn: &str, role: &str) -> Result<(), AppError> {
+    if group_dn.trim().is_empty() {
+        return Err(AppError::validation("group_dn", "Group DN is required"));
+    }
+    super::roles::existing_role(conn, role).map_err(|e| AppError::validation("role", e))?;
+    conn.execute(
+        "INSERT INTO ldap_group_roles (group_dn, role) VALUES (?1, ?2) \
+         ON CONFLICT(group_dn) DO UPDATE SET role = excluded.role",
+        params![group_dn.trim(), role],
+    )?;
+    Ok(())
+}
+
+pub fn delete_group_mapping(conn: &Connection, group_dn: &str) -> Result<bool, AppError> {
+    Ok(conn.execute("DELETE FROM ldap_group_roles WHERE group_dn = ?1", params![group_dn])? > 0)
+}
+
+/// How privileged a role is, for picking between mapped groups: the number
+/// of capabilities it holds (WP-86), which orders the built-ins admin >
+/// supervisor > tech > guest and places custom roles among them.
+fn role_rank(conn: &Connection, role: &UserRole) -> Result<usize, AppError> {
+    Ok(super::roles::capabilities_of(conn, role)?.len() + usize::from(role.is_admin()))
+}
+
+/// The role for a set of group DNs: the most privileged mapped group, else
+/// `default_role`, else `None` (the account may not sign in).
+pub fn map_role(conn: &Connection, groups: &[String], default_role: Option<&str>) -> Result<Option<UserRole>, AppError> {
+    let mut best: Option<(usize, UserRole)> = None;
+    for m in list_group_mappings(conn)? {
+        if !groups.iter().any(|g| g.eq_ignore_ascii_case(&m.group_dn)) {
+            continue;
+        }
+        let Ok(role) = m.role.parse::<UserRole>() else { continue };
+        let rank = role_rank(conn, &role)?;
+        if best.as_ref().is_none_or(|(r, _)| rank > *r) {
+            best = Some((rank, role));
+        }
+    }
+    Ok(best.map(|(_, role)| role).or_else(|| default_role.and_then(|r| r.parse().ok())))
+}
+
+// ── The LDAP client ─────────────────────────────────────────────────────────
+
+/// Build the client for the configured directory, or `None` when directory
+/// login is switched off. Nothing is contacted until a method is called.
+pub fn configured_directory(conn: &Connection) -> Result<Option<LdapDirectory>, AppError> {
+    let dir = directory(conn)?;
+    Ok(Some(dir).filter(|d| d.cfg.enabled))
+}
+
+/// The client for the saved settings whether or not directory login is on,
+/// for the settings screen's connection test.
+pub fn directory(conn: &Connection) -> Result<LdapDirectory, AppError> {
+    let cfg = get_config(conn)?;
+    let bind_password: Option<String> =
+        conn.query_row("SELECT bind_password FROM ldap_config WHERE id = 1", [], |r| r.get(0))?;
+    Ok(LdapDirectory { cfg, bind_password })
+}
+
+pub struct LdapDirectory {
+    cfg: LdapConfig,
+    bind_password: Option<String>,
+}
+
+impl LdapDirectory {
+    pub fn config(&self) -> &LdapConfig {
+        &self.cfg
+    }
+
+    fn open(&self) -> Result<ldap3::LdapConn, AppError> {
+        let url = self.cfg.url.as_deref().ok_or_else(|| AppError::validation("url", "Directory URL is not configured"))?;
+        let settings = ldap3::LdapConnSettings::new()
+            .set_conn_timeout(Duration::from_secs(self.cfg.timeout_secs as u64))
+            .set_starttls(self.cfg.starttls);
+        let mut ldap = ldap3::LdapConn::with_settings(settings, url)
+            .map_err(|e| AppError::external("ldap", format!("Could not reach the directory at {}: {}", url, e)))?;
+        ldap.with_timeout(Duration::from_secs(self.cfg.timeout_secs as u64));
+        Ok(ldap)
+    }
+
+    fn service_bind(&self, ldap: &mut ldap3::LdapConn) -> Result<(), AppError> {
+        let dn = self.cfg.bind_dn.as_deref().unwrap_or("");
+        let pw = self.bind_password.as_deref().unwrap_or("");
+        ldap.simple_bind(dn, pw)
+            .and_then(|r| r.success())
+            .map(|_| ())
+            .map_err(|e| AppError::external("ldap", format!("The directory refused the service account bind: {}", e)))
+    }
+
+    fn search(&self, ldap: &mut ldap3::LdapConn, base: &str, filter: &str, attrs: &[&str]) -> Result<Vec<ldap3::SearchEntry>, AppError> {
+        let (entries, _) = ldap
+            .search(base, ldap3::Scope::Subtree, filter, attrs.to_vec())
+            .and_then(|r| r.success())
+            .map_err(|e| AppError::external("ldap", format!("Directory search failed: {}", e)))?;
+        Ok(entries.into_iter().map(ldap3::SearchEntry::construct).collect())
+    }
+}
+
+/// ldap3's sync client drives a private current-thread Tokio runtime, which
+/// panics if started on a thread that is already inside one (Tauri's async
+/// runtime, the scheduler loop). Each directory call therefore runs on its own
+/// short-lived thread.
+fn isolated<T: Send + 'static>(f: impl FnOnce() -> Result<T, AppError> + Send + 'static) -> Result<T, AppError> {
+    std::thread::spawn(f).join().map_err(|_| AppError::internal("The directory client panicked"))?
+}
+
+impl Directory for LdapDirectory {
+    fn find_user(&self, username: &str) -> Result<Option<DirectoryEntry>, AppError> {
+        let this = LdapDirectory { cfg: self.cfg.clone(), bind_password: self.bind_password.clone() };
+        let username = username.to_string();
+        isolated(move || {
+            let cfg = &this.cfg;
+            let base = cfg
+                .user_base_dn
+                .as_deref()
+                .ok_or_else(|| AppError::validation("user_base_dn", "User base DN is not configured"))?;
+            let filter = user_filter(&cfg.user_filter, &username);
+            let mut ldap = this.open()?;
+            this.service_bind(&mut ldap)?;
+            let attrs = [
+                cfg.display_name_attribute.as_str(),
+                cfg.email_attribute.as_str(),
+                "memberOf",
+                "userAccountControl",
+                "pwdAccountLockedTime",
+                "nsAccountLock",
+            ];
+            let mut found = this.search(&mut ldap, base, &filter, &attrs)?;
+            if found.len() > 1 {
+                return Err(AppError::validation(
+                    "user_filter",
+                    format!("The user filter matched {} directory entries for '{}'; it must match one", found.len(), username),
+                ));
+            }
+            let Some(entry) = found.pop() else {
+                ldap.unbind().ok();
+                return Ok(None);
+            };
+            let mut parsed = entry_from_attrs(&entry.dn, &entry.attrs, cfg);
+            if let Some(group_base) = cfg.group_base_dn.as_deref() {
+                let dn = ldap3::ldap_escape(entry.dn.as_str()).into_owned();
+                let filter = format!("(|(member={dn})(uniqueMember={dn}))");
+                for g in this.search(&mut ldap, group_base, &filter, &["1.1"])? {
+                    if !parsed.groups.iter().any(|x| x.eq_ignore_ascii_case(&g.dn)) {
+                        parsed.groups.push(g.dn);
+                    }
+                }
+            }
+            ldap.unbind().ok();
+            Ok(Some(parsed))
+        })
+    }
+
+    fn verify_password(&self, dn: &str, password: &str) -> Result<bool, AppError> {
+        // An LDAP simple bind with an empty password is an "unauthenticated
+        // bind" and succeeds on many servers without checking anything.
+        if password.is_empty() {
+            return Ok(false);
+        }
+        let this = LdapDirectory { cfg: self.cfg.clone(), bind_password: None };
+        let (dn, password) = (dn.to_string(), password.to_string());
+        isolated(move || {
+            let mut ldap = this.open()?;
+            let result = ldap
+                .simple_bind(&dn, &password)
+                .map_err(|e| AppError::external("ldap", format!("Directory bind failed: {}", e)))?;
+            ldap.unbind().ok();
+            match result.rc {
+                0 => Ok(true),
+                LDAP_INVALID_CREDENTIALS => Ok(false),
+                rc => Err(AppError::external("ldap", format!("Directory bind failed with result code {}: {}", rc, result.text))),
+            }
+        })
+    }
+}
+
+/// Substitute the escaped username into the configured filter.
+pub fn user_filter(template: &str, username: &str) -> String {
+    template.replace("{username}", &ldap3::ldap_escape(username))
+}
+
+fn attr<'a>(attrs: &'a HashMap<String, Vec<String>>, name: &str) -> Option<&'a Vec<String>> {
+    attrs.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v)
+}
+
+/// Read a search result into a [`DirectoryEntry`].
+pub fn entry_from_attrs(dn: &str, attrs: &HashMap<String, Vec<String>>, cfg: &LdapConfig) -> DirectoryEntry {
+    let first = |name: &str| attr(attrs, name).and_then(|v| v.first()).map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let ad_disabled = first("userAccountControl")
+        .and_then(|v| v.parse::<u64>().ok())
+        .is_some_and(|flags| flags & AD_ACCOUNTDISABLE != 0);
+    let locked = first("pwdAccountLockedTime").is_some();
+    let ns_locked = first("nsAccountLock").is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    DirectoryEntry {
+        dn: dn.to_string(),
+        display_name: first(&cfg.display_name_attribute),
+        email: first(&cfg.email_attribute),
+        groups: attr(attrs, "memberOf").cloned().unwrap_or_default(),
+        disabled: ad_disabled || locked || ns_locked,
+    }
+}
+
+// ── Login and provisioning ──────────────────────────────────────────────────
+
+/// What a directory login did to the local `users` row, for the audit log.
+#[derive(Debug, PartialEq)]
+pub enum Provisioning {
+    /// First login: the row was created.
+    Created,
+    /// A local account with this username now signs in through the directory.
+    Linked,
+    /// Existing directory account; `changes` lists what the directory changed.
+    Refreshed { changes: Vec<String> },
+}
+
+/// Create or refresh the local row for a directory account that has just
+/// authenticated. Returns the user id and what changed. A local
+/// administrator is never linked: it is the break-glass account.
+pub fn provision(conn: &Connection, username: &str, entry: &DirectoryEntry, role: &UserRole) -> Result<(String, Provisioning), AppError> {
+    let existing: Option<(String, String, String, Option<String>, String, bool)> = conn
+        .query_row(
+            "SELECT id, auth_source, display_name, email, role, is_active FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get::<_, i64>(5)? != 0)),
+        )
+        .optional()?;
+    let display = entry.display_name.clone().unwrap_or_else(|| username.to_string());
+
+    if let Some((_, source, _, _, old_role, _)) = &existing {
+        if source == "local" && old_role == "admin" {
+            return Err(AppError::conflict(format!(
+                "'{}' is a local administrator account and cannot sign in through the directory",
+                username
+            )));
+        }
+    }
+    let Some((id, source, old_display, old_email, old_role, was_active)) = existing else {
+        let id = uuid::Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, email, role, must_change_password, \
+                                auth_source, directory_dn, directory_synced_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, 'ldap', ?7, datetime('now'))",
+            params![id, username, DIRECTORY_PASSWORD_MARKER, display, entry.email, role.as_str(), entry.dn],
+        )
+        .map_err(|e| AppError::internal(format!("Failed to create the directory account: {}", e)))?;
+        return Ok((id, Provisioning::Created));
+    };
+
+    let mut changes = Vec::new();
+    if old_role != role.as_str() {
+        changes.push(format!("role {} → {}", old_role, role.as_str()));
+    }
+    if old_display != display {
+        changes.push("display name".to_string());
+    }
+    if old_email != entry.email {
+        changes.push("email".to_string());
+    }
+    if !was_active {
+        changes.push("reactivated".to_string());
+    }
+    conn.execute(
+        "UPDATE users SET password_hash = ?1, display_name = ?2, email = ?3, role = ?4, is_active = 1, \
+             must_change_password = 0, auth_source = 'ldap', directory_dn = ?5, directory_synced_at = datetime('now'), \
+             updated_at = CASE WHEN ?6 THEN datetime('now') ELSE updated_at END \
+         WHERE id = ?7",
+        params![DIRECTORY_PASSWORD_MARKER, display, entry.email, role.as_str(), entry.dn, !changes.is_empty() || source != "ldap", id],
+    )
+    .map_err(|e| AppError::internal(format!("Failed to update the directory account: {}", e)))?;
+    let outcome = if source == "ldap" { Provisioning::Refreshed { changes } } else { Provisioning::Linked };
+    Ok((id, outcome))
+}
+
+/// Deactivate a directory account and end its sessions.
+pub fn deactivate(conn: &Connection, user_id: &str) -> Result<bool, AppError> {
+    let n = conn.execute(
+        "UPDATE users SET is_active = 0, directory_synced_at = datetime('now'), updated_at = datetime('now') \
+         WHERE id = ?1 AND auth_source = 'ldap' AND is_active = 1",
+        params![user_id],
+    )?;
+    super::sessions::revoke_all_for_user(conn, user_id, None).ok();
+    Ok(n > 0)
+}
+
+// ── Disabled-account sync ───────────────────────────────────────────────────
+
+#[derive(Debug, Default, Serialize)]
+pub struct DirectorySyncReport {
+    pub checked: usize,
+    pub updated: usize,
+    pub deactivated: usize,
+    pub reactivated: usize,
+}
+
+/// Re-read every directory account. Accounts that are gone, disabled, or no
+/// longer in a mapped group are deactivated; the rest get the directory's
+/// current name, email and role. Each change is audited.
+///
+/// Aborts without changing anything when the directory cannot be asked, and
+/// when it finds none of several accounts — a wrong base DN should not read
+/// as "everyone left".
+pub fn sync_accounts(conn: &Connection, dir: &dyn Directory, default_role: Option<&str>, actor: Option<&str>) -> Result<DirectorySyncReport, AppError> {
+    let accounts: Vec<(String, String, bool)> = {
+        let mut stmt = conn.prepare("SELECT id, username, is_active FROM users WHERE auth_source = 'ldap' ORDER BY username")?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get::<_, i64>(2)? != 0)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows
+    };
+    let mut found = Vec::with_capacity(accounts.len());
+    for (_, username, _) in &accounts {
+        found.push(dir.find_user(username)?);
+    }
+    if accounts.len() > 1 && found.iter().all(Option::is_none) {
+        return Err(AppError::invalid(format!(
+            "None of the {} directory accounts were found — check the user base DN and filter. Nothing was changed.",
+            accounts.len()
+        )));
+    }
+
+    let mut report = DirectorySyncReport { checked: accounts.len(), ..Default::default() };
+    for ((id, username, was_active), entry) in accounts.iter().zip(found) {
+        let role = match &entry {
+            Some(e) if !e.disabled => map_role(conn, &e.groups, default_role)?,
+            _ => None,
+        };
+        match (entry, role) {
+            (Some(entry), Some(role)) => {
+                let (_, outcome) = provision(conn, username, &entry, &role)?;
+                if let Provisioning::Refreshed { changes } = outcome {
+                    if !changes.is_empty() {
+                        if !was_active {
+                            report.reactivated += 1;
+                        }
+                        report.updated += 1;
+                        crate::db::queries::log_audit(
+                            conn, actor, "directory_sync", "user", Some(id), None, Some(role.as_str()),
+                            Some(&format!("Updated from the directory: {}", changes.join(", "))),
+                        )
+                        .ok();
+                    }
+                }
+            }
+            (entry, _) => {
+                if *was_active && deactivate(conn, id)? {
+                    report.deactivated += 1;
+                    let reason = match entry {
+                        None => "no longer in the directory",
+                        Some(e) if e.disabled => "disabled in the directory",
+                        Some(_) => "no longer in a group mapped to a role",
+                    };
+                    crate::db::queries::log_audit(
+                        conn, actor, "directory_disabled", "user", Some(id), None, None,
+                        Some(&format!("Deactivated: {}", reason)),
+                    )
+                    .ok();
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+    use std::cell::RefCell;
+
+    /// An in-memory directory: username → (entry, password).
+    #[derive(Default)]
+    pub(crate) struct FakeDirectory {
+        pub users: RefCell<HashMap<String, (DirectoryEntry, String)>>,
+        pub down: std::cell::Cell<bool>,
+    }
+
+    impl FakeDirectory {
+        pub(crate) fn with_user(self, username: &str, password: &str, groups: &[&str]) -> Self {
+            self.users.borrow_mut().insert(
+                username.to_string(),
+                (
+                    DirectoryEntry {
+                        dn: format!("uid={},ou=people,dc=example,dc=org", username),
+                        display_name: Some(format!("{} (AD)", username)),
+                        email: Some(format!("{}@example.org", username)),
+                        groups: groups.iter().map(|g| g.to_string()).collect(),
+                        disabled: false,
+                    },
+                    password.to_string(),
+                ),
+            );
+            self
+        }
+    }
+
+    impl Directory for FakeDirectory {
+        fn find_user(&self, username: &str) -> Result<Option<DirectoryEntry>, AppError> {
+            if self.down.get() {
+                return Err(AppError::external("ldap", "Could not reach the directory"));
+            }
+            Ok(self.users.borrow().get(&username.to_lowercase()).map(|(e, _)| e.clone()))
+        }
+        fn verify_password(&self, dn: &str, password: &str) -> Result<bool, AppError> {
+            if self.down.get() {
+                return Err(AppError::external("ldap", "Could not reach the directory"));
+            }
+            Ok(!password.is_empty() && self.users.borrow().values().any(|(e, p)| e.dn == dn && p == password))
+        }
+    }
+
+    pub(crate) const LAB_GROUP: &str = "cn=lab,ou=groups,dc=example,dc=org";
+    pub(crate) const PI_GROUP: &str = "cn=pi,ou=groups,dc=example,dc=org";
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        set_group_mapping(&conn, LAB_GROUP, "tech").unwrap();
+        set_group_mapping(&conn, PI_GROUP, "supervisor").unwrap();
+        conn
+    }
+
+    fn cfg() -> LdapConfig {
+        LdapConfig {
+            enabled: true,
+            url: Some("ldap://localhost".into()),
+            starttls: false,
+            bind_dn: None,
+            bind_password_set: false,
+            user_base_dn: Some("dc=example,dc=org".into()),
+            user_filter: "(uid={username})".into(),
+            group_base_dn: None,
+            display_name_attribute: "displayName".into(),
+            email_attribute: "mail".into(),
+            default_role: None,
+            timeout_secs: 10,
+        }
+    }
+
+    #[test]
+    fn filters_escape_the_username() {
+        assert_eq!(user_filter("(uid={username})", "bob"), "(uid=bob)");
+        assert_eq!(user_filter("(uid={username})", "*)(uid=*"), "(uid=\\2a\\29\\28uid=\\2a)");
+    }
+
+    #[test]
+    fn entries_detect_disabled_accounts_across_servers() {
+        let attrs = |pairs: &[(&str, &str)]| -> HashMap<String, Vec<String>> {
+            pairs.iter().map(|(k, v)| (k.to_string(), vec![v.to_string()])).collect()
+        };
+        let e = entry_from_attrs("uid=a", &attrs(&[("displayname", "Ann"), ("MAIL", "a@x"), ("userAccountControl", "512")]), &cfg());
+        assert_eq!((e.display_name.as_deref(), e.email.as_deref(), e.disabled), (Some("Ann"), Some("a@x"), false));
+        assert!(entry_from_attrs("uid=a", &attrs(&[("userAccountControl", "514")]), &cfg()).disabled);
+        assert!(entry_from_attrs("uid=a", &attrs(&[("pwdAccountLockedTime", "000001010000Z")]), &cfg()).disabled);
+        assert!(entry_from_attrs("uid=a", &attrs(&[("nsAccountLock", "TRUE")]), &cfg()).disabled);
+    }
+
+    #[test]
+    fn roles_take_the_most_privileged_mapped_group() {
+        let conn = db();
+        let groups = |gs: &[&str]| gs.iter().map(|g| g.to_uppercase()).collect::<Vec<_>>();
+        assert_eq!(map_role(&conn, &groups(&[LAB_GROUP, PI_GROUP]), None).unwrap(), Some(UserRole::Supervisor));
+        assert_eq!(map_role(&conn, &groups(&[LAB_GROUP]), None).unwrap(), Some(UserRole::Tech));
+        assert_eq!(map_role(&conn, &groups(&["cn=other"]), None).unwrap(), None);
+        assert_eq!(map_role(&conn, &[], Some("guest")).unwrap(), Some(UserRole::Guest));
+    }
+
+    #[test]
+    fn config_cannot_be_enabled_without_a_local_admin() {
+        let conn = db();
+        let mut req = SetLdapConfigRequest {
+            enabled: true,
+            url: "ldap://dc1.example.org".into(),
+            starttls: true,
+            bind_dn: Some("cn=svc,dc=example,dc=org".into()),
+            bind_password: Some("secret".into()),
+            user_base_dn: "dc=example,dc=org".into(),
+            user_filter: "(sAMAccountName={username})".into(),
+            group_base_dn: None,
+            display_name_attribute: "displayName".into(),
+            email_attribute: "mail".into(),
+            default_role: None,
+            timeout_secs: 10,
+        };
+        assert!(set_config(&conn, &req).unwrap_err().message().contains("local administrator"));
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('a', 'admin', 'x', 'A', 'admin')",
+            [],
+        )
+        .unwrap();
+        set_config(&conn, &req).unwrap();
+        let stored = get_config(&conn).unwrap();
+        assert!(stored.enabled && stored.bind_password_set);
+
+        req.url = "http://dc1".into();
+        assert!(set_config(&conn, &req).is_err());
+        req.url = "ldaps://dc1".into();
+        assert!(set_config(&conn, &req).unwrap_err().message().contains("StartTLS"));
+        req.starttls = false;
+        req.user_filter = "(uid=bob)".into();
+        assert!(set_config(&conn, &req).is_err());
+    }
+
+    #[test]
+    fn provisioning_creates_links_and_refreshes_accounts() {
+        let conn = db();
+        let dir = FakeDirectory::default().with_user("ann", "pw", &[LAB_GROUP]);
+        let entry = dir.find_user("ann").unwrap().unwrap();
+        let (id, outcome) = provision(&conn, "ann", &entry, &UserRole::Tech).unwrap();
+        assert_eq!(outcome, Provisioning::Created);
+        let hash: String = conn.query_row("SELECT password_hash FROM users WHERE id = ?1", [&id], |r| r.get(0)).unwrap();
+        assert!(!bcrypt::verify("", &hash).unwrap_or(false));
+
+        let (same, outcome) = provision(&conn, "ANN", &entry, &UserRole::Supervisor).unwrap();
+        assert_eq!(same, id);
+        assert_eq!(outcome, Provisioning::Refreshed { changes: vec!["role tech → supervisor".to_string()] });
+
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('b', 'bob', 'x', 'Bob', 'tech')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(provision(&conn, "bob", &entry, &UserRole::Tech).unwrap().1, Provisioning::Linked);
+    }
+
+    #[test]
+    fn sync_deactivates_disabled_and_removed_accounts() {
+        let conn = db();
+        let dir = FakeDirectory::default()
+            .with_user("ann", "pw", &[LAB_GROUP])
+            .with_user("bob", "pw", &[LAB_GROUP])
+            .with_user("cat", "pw", &[LAB_GROUP]);
+        for u in ["ann", "bob", "cat"] {
+            let e = dir.find_user(u).unwrap().unwrap();
+            provision(&conn, u, &e, &UserRole::Tech).unwrap();
+        }
+        dir.users.borrow_mut().get_mut("ann").unwrap().0.disabled = true;
+        dir.users.borrow_mut().remove("bob");
+        dir.users.borrow_mut().get_mut("cat").unwrap().0.groups = vec![PI_GROUP.to_string()];
+
+        let report = sync_accounts(&conn, &dir, None, None).unwrap();
+        assert_eq!((report.checked, report.deactivated, report.updated), (3, 2, 1));
+        let active: Vec<String> = conn
+            .prepare("SELECT username || ':' || role FROM users WHERE is_active = 1")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(active, vec!["cat:supervisor"]);
+
+        // Re-enabled in the directory → reactivated on the next sync.
+        dir.users.borrow_mut().get_mut("ann").unwrap().0.disabled = false;
+        assert_eq!(sync_accounts(&conn, &dir, None, None).unwrap().reactivated, 1);
+    }
+
+    #[test]
+    fn sync_changes_nothing_when_the_directory_is_down_or_empty() {
+        let conn = db();
+        let dir = FakeDirectory::default().with_user("ann", "pw", &[LAB_GROUP]).with_user("bob", "pw", &[LAB_GROUP]);
+        for u in ["ann", "bob"] {
+            let e = dir.find_user(u).unwrap().unwrap();
+            provision(&conn, u, &e, &UserRole::Tech).unwrap();
+        }
+        dir.down.set(true);
+        assert!(sync_accounts(&conn, &dir, None, None).is_err());
+        dir.down.set(false);
+        dir.users.borrow_mut().clear();
+        assert!(sync_accounts(&conn, &dir, None, None).unwrap_err().message().contains("Nothing was changed"));
+        let active: i64 = conn.query_row("SELECT COUNT(*) FROM users WHERE is_active = 1", [], |r| r.get(0)).unwrap();
+        assert_eq!(active, 2);
+    }
+
+    /// Against a real OpenLDAP server seeded with
+    /// `tests/fixtures/ldap/seed.ldif` — see docs/ldap-authentication.md §6:
+    ///
+    /// ```text
+    /// STELO_LDAP_TEST_URL=ldap://localhost:1389 cargo test --no-default-features --lib -- --ignored openldap
+    /// ```
+    #[test]
+    #[ignore = "needs a local OpenLDAP container"]
+    fn openldap_container_login_and_groups() {
+        let url = std::env::var("STELO_LDAP_TEST_URL").unwrap_or_else(|_| "ldap://localhost:1389".into());
+        let dir = LdapDirectory {
+            cfg: LdapConfig {
+                url: Some(url),
+                bind_dn: Some("cn=admin,dc=example,dc=org".into()),
+                user_base_dn: Some("ou=people,dc=example,dc=org".into()),
+                group_base_dn: Some("ou=groups,dc=example,dc=org".into()),
+                display_name_attribute: "cn".into(),
+                ..cfg()
+            },
+            bind_password: Some("adminpassword".into()),
+        };
+        let ann = dir.find_user("ann").unwrap().expect("ann is seeded");
+        assert!(ann.groups.iter().any(|g| g.eq_ignore_ascii_case("cn=lab,ou=groups,dc=example,dc=org")));
+        assert!(dir.verify_password(&ann.dn, "ann-password").unwrap());
+        assert!(!dir.verify_password(&ann.dn, "wrong").unwrap());
+        assert!(!dir.verify_password(&ann.dn, "").unwrap());
+        assert!(dir.find_user("*").unwrap().is_none());
+    }
+}
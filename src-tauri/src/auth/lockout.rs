@@ -0,0 +1,302 @@
+// WP-89: persisted, policy-driven login lockout.
+//
+// `login` is an unauthenticated command that can be driven in a tight loop,
+// and bcrypt at cost 12 still allows several guesses per second per core —
+// slow for a remote attacker, ample for a local one working against a seeded
+// `admin` account. Each failed password (or second factor, or signature
+// re-authentication) is counted in `login_failures`; at the policy's threshold
+// the name is locked for the policy's duration.
+//
+// This replaces the in-memory `LoginThrottle`, which a restart cleared. The
+// write per failure is not a new cost: every failure already writes an audit
+// entry.
+//
+// Rows are keyed by the lower-cased username as typed, whether or not such an
+// account exists, and a locked name is refused with the same message as a
+// wrong password — a distinct "account locked" message would tell an attacker
+// which usernames exist. Usernames are attacker-supplied, so the table is
+// bounded: stale streaks are pruned, and once [`MAX_TRACKED`] names are live a
+// new name that matches no account is not tracked rather than evicting a live
+// lock an attacker could then reset at will. Names of real accounts are always
+// tracked, so a dictionary run cannot switch off lockout for them; those rows
+// are bounded by the number of accounts.
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use super::policy;
+use crate::error::{reason, AppError};
+
+pub const MAX_TRACKED: i64 = 1024;
+const INVALID: &str = "Invalid username or password";
+
+#[derive(Debug, Serialize)]
+pub struct LockedAccount {
+    pub username: String,
+    /// `None` when no account has this name.
+    pub user_id: Option<String>,
+    pub failures: i64,
+    pub last_failure_at: String,
+    pub locked_until: String,
+}
+
+fn key(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+/// `Err` when `username` is currently locked. The error text is identical to
+/// a bad-password failure.
+pub fn check(conn: &Connection, username: &str) -> Result<(), AppError> {
+    let locked: bool = conn
+        .query_row(
+            "SELECT locked_until > datetime('now') FROM login_failures WHERE username = ?1",
+            params![key(username)],
+            |r| r.get::<_, Option<bool>>(0),
+        )
+        .optional()?
+        .flatten()
+        .unwrap_or(false);
+    if locked {
+        Err(AppError::unauthenticated(reason::INVALID_CREDENTIALS, INVALID))
+    } else {
+        Ok(())
+    }
+}
+
+/// Whole minutes (rounded up) until `username` may try again; `None` when not
+/// locked. For the audit detail, never shown to the caller.
+pub fn lock_remaining_minutes(conn: &Connection, username: &str) -> Option<i64> {
+    conn.query_row(
+        "SELECT CAST((julianday(locked_until) - julianday('now')) * 1440 + 0.999 AS INTEGER) \
+         FROM login_failures WHERE username = ?1 AND locked_until > datetime('now')",
+        params![key(username)],
+        |r| r.get(0),
+    )
+    .ok()
+}
+
+/// Count a failed attempt. A failure more than the lockout duration after
+/// the previous one starts a fresh streak. Returns the lock's end when this
+/// failure locked the name, after writing the `account_locked` audit entry.
+pub fn record_failure(conn: &Connection, username: &str) -> Result<Option<String>, AppError> {
+    let p = policy::get(conn)?;
+    let name = key(username);
+    let window = format!("-{} minutes", p.lockout_minutes);
+    conn.execute(
+        "DELETE FROM login_failures WHERE last_failure_at <= datetime('now', ?1) \
+         AND (locked_until IS NULL OR locked_until <= datetime('now'))",
+        params![window],
+    )?;
+    let (tracked, known): (i64, bool) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(username = ?1), 0) > 0 FROM login_failures",
+            params![name],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+    let user_id: Option<String> = conn
+        .query_row("SELECT id FROM users WHERE username = ?1 COLLATE NOCASE", params![name], |r| r.get(0))
+        .optional()?;
+    if !known && user_id.is_none() && tracked >= MAX_TRACKED {
+        return Ok(None);
+    }
+
+    let failures: i64 = conn
+        .query_row(
+            "INSERT INTO login_failures (username, failures, last_failure_at) VALUES (?1, 1, datetime('now')) \
+             ON CONFLICT(username) DO UPDATE SET \
+                 failures = CASE WHEN last_failure_at <= datetime('now', ?2) THEN 1 ELSE failures + 1 END, \
+                 locked_until = CASE WHEN locked_until <= datetime('now') THEN NULL ELSE locked_until END, \
+                 last_failure_at = datetime('now') \
+             RETURNING failures",
+            params![name, window],
+            |r| r.get(0),
+        )?;
+    if failures < p.lockout_threshold {
+        return Ok(None);
+    }
+    let locked_until: Option<String> = conn
+        .query_row(
+            "UPDATE login_failures SET locked_until = datetime('now', ?2) \
+             WHERE username = ?1 AND locked_until IS NULL RETURNING locked_until",
+            params![name, format!("+{} minutes", p.lockout_minutes)],
+            |r| r.get(0),
+        )
+        .optional()?;
+    if let Some(until) = &locked_until {
+        crate::db::queries::log_audit(
+            conn, None, "account_locked", "user", user_id.as_deref(), None, Some(&name),
+            Some(&format!("Locked after {} failed attempts until {} UTC", failures, until)),
+        )
+        .ok();
+    }
+    Ok(locked_until)
+}
+
+/// Forget the failures after a successful login.
+pub fn clear(conn: &Connection, username: &str) {
+    conn.execute("DELETE FROM login_failures WHERE username = ?1", params![key(username)]).ok();
+}
+
+/// Lift a lock early. Returns whether the name was locked.
+pub fn unlock(conn: &Connection, username: &str) -> Result<bool, AppError> {
+    conn.execute(
+        "DELETE FROM login_failures WHERE username = ?1 AND locked_until > datetime('now')",
+        params![key(username)],
+    )
+    .map(|n| n > 0)
+    .map_err(AppError::from)
+}
+
+/// Names locked right now, with the account they belong to if any.
+pub fn list_locked(conn: &Connection) -> Result<Vec<LockedAccount>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT f.username, u.id, f.failures, f.last_failure_at, f.locked_until \
+             FROM login_failures f LEFT JOIN users u ON u.username = f.username COLLATE NOCASE \
+             WHERE f.locked_until > datetime('now') ORDER BY f.locked_until DESC",
+        )?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok(LockedAccount {
+                username: r.get(0)?,
+                user_id: r.get(1)?,
+                failures: r.get(2)?,
+                last_failure_at: r.get(3)?,
+                locked_until: r.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'Tech1', 'x', 'T', 'tech')",
+            [],
+        )
+        .unwrap();
+        policy::set(&conn, &policy::AuthPolicy { lockout_threshold: 3, ..Default::default() }, std::path::Path::new("")).unwrap();
+        conn
+    }
+
+    /// Move every failure and lock back by `minutes`, standing in for the
+    /// clock moving forward.
+    fn age(conn: &Connection, minutes: i64) {
+        let shift = format!("-{} minutes", minutes);
+        conn.execute(
+            "UPDATE login_failures SET last_failure_at = datetime(last_failure_at, ?1), \
+             locked_until = datetime(locked_until, ?1)",
+            params![shift],
+        )
+        .unwrap();
+    }
+
+    fn audit_count(conn: &Connection, action: &str) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM audit_log WHERE action = ?1", params![action], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn allows_attempts_below_the_threshold_and_locks_at_it() {
+        let conn = db();
+        for _ in 0..2 {
+            assert_eq!(record_failure(&conn, "tech1").unwrap(), None);
+            assert!(check(&conn, "tech1").is_ok());
+        }
+        assert!(record_failure(&conn, "tech1").unwrap().is_some());
+        assert!(check(&conn, "tech1").is_err());
+        assert!(check(&conn, "TECH1").is_err(), "case does not dodge the lock");
+        assert!(check(&conn, "someone-else").is_ok(), "locks are per name");
+        assert_eq!(audit_count(&conn, "account_locked"), 1);
+        let entity: Option<String> = conn
+            .query_row("SELECT entity_id FROM audit_log WHERE action = 'account_locked'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(entity.as_deref(), Some("u1"));
+    }
+
+    #[test]
+    fn lockout_message_matches_a_normal_failure() {
+        let conn = db();
+        for _ in 0..3 {
+            record_failure(&conn, "nobody").unwrap();
+        }
+        assert_eq!(check(&conn, "nobody").unwrap_err().message(), INVALID, "unknown names lock the same way");
+    }
+
+    #[test]
+    fn a_lock_survives_a_reconnect_and_expires_after_its_duration() {
+        let dir = std::env::temp_dir().join(format!("steloptc_lockout_test_{}.db", uuid::Uuid::new_v4()));
+        {
+            let conn = Connection::open(&dir).unwrap();
+            run_all(&conn).unwrap();
+            policy::set(&conn, &policy::AuthPolicy { lockout_threshold: 3, ..Default::default() }, std::path::Path::new("")).unwrap();
+            for _ in 0..3 {
+                record_failure(&conn, "tech1").unwrap();
+            }
+        }
+        let conn = Connection::open(&dir).unwrap();
+        assert!(check(&conn, "tech1").is_err(), "a restart does not clear the lock");
+        assert!(lock_remaining_minutes(&conn, "tech1").is_some_and(|m| (14..=15).contains(&m)));
+
+        age(&conn, 16);
+        assert!(check(&conn, "tech1").is_ok());
+        assert_eq!(lock_remaining_minutes(&conn, "tech1"), None);
+        // An expired lock starts a fresh streak rather than re-locking at once.
+        assert_eq!(record_failure(&conn, "tech1").unwrap(), None);
+        assert!(check(&conn, "tech1").is_ok());
+        drop(conn);
+        let _ = std::fs::remove_file(&dir);
+    }
+
+    #[test]
+    fn success_clears_and_an_admin_can_unlock() {
+        let conn = db();
+        record_failure(&conn, "tech1").unwrap();
+        record_failure(&conn, "tech1").unwrap();
+        clear(&conn, "tech1");
+        assert_eq!(record_failure(&conn, "tech1").unwrap(), None, "the streak restarted");
+
+        for _ in 0..2 {
+            record_failure(&conn, "tech1").unwrap();
+        }
+        let locked = list_locked(&conn).unwrap();
+        assert_eq!(locked.len(), 1);
+        assert_eq!(locked[0].user_id.as_deref(), Some("u1"));
+        assert!(unlock(&conn, "Tech1").unwrap());
+        assert!(!unlock(&conn, "Tech1").unwrap());
+        assert!(check(&conn, "tech1").is_ok());
+    }
+
+    #[test]
+    fn the_table_is_bounded_against_a_dictionary_run() {
+        let conn = db();
+        for i in 0..MAX_TRACKED {
+            conn.execute(
+                "INSERT INTO login_failures (username, failures, last_failure_at) VALUES (?1, 1, datetime('now'))",
+                params![format!("guess{}", i)],
+            )
+            .unwrap();
+        }
+        assert_eq!(record_failure(&conn, "one-more").unwrap(), None);
+        let tracked: i64 = conn.query_row("SELECT COUNT(*) FROM login_failures", [], |r| r.get(0)).unwrap();
+        assert_eq!(tracked, MAX_TRACKED, "a new name is not tracked once full");
+
+        // A real account still counts and locks, however full the table is.
+        assert_eq!(record_failure(&conn, "tech1").unwrap(), None);
+        assert_eq!(record_failure(&conn, "TECH1").unwrap(), None);
+        assert!(record_failure(&conn, "Tech1").unwrap().is_some());
+        assert!(check(&conn, "tech1").is_err());
+        assert_eq!(audit_count(&conn, "account_locked"), 1);
+
+        age(&conn, 16);
+        record_failure(&conn, "one-more").unwrap();
+        let tracked: i64 = conn.query_row("SELECT COUNT(*) FROM login_failures", [], |r| r.get(0)).unwrap();
+        assert_eq!(tracked, 1, "stale streaks are pruned first");
+    }
+}
@@ -1,5 +1,15 @@
+pub mod api_tokens;
+pub mod ldap;
+pub mod lockout;
+pub mod policy;
+pub mod roles;
+pub mod sessions;
+pub mod totp;
+
 use crate::db::Database;
+use crate::error::{reason, AppError};
 use crate::models::user::{User, UserRole};
+use roles::Capability;
 use rusqlite::params;
 
 // Simple token-based session management for local desktop app.
@@ -23,26 +33,99 @@ fn timing_equalizer_hash() -> &'static str {
     })
 }
 
-pub fn authenticate(db: &Database, username: &str, password: &str) -> Result<User, String> {
-    let user = db.conn.query_row(
-        "SELECT id, username, password_hash, display_name, email, role, is_active, must_change_password, created_at, updated_at
-         FROM users WHERE username = ?1 AND is_active = 1",
-        params![username],
-        |row| {
-            Ok(User {
-                id: row.get(0)?,
-                username: row.get(1)?,
-                password_hash: row.get(2)?,
-                display_name: row.get(3)?,
-                email: row.get(4)?,
-                role: row.get::<_, String>(5)?.parse().unwrap_or(UserRole::Guest),
-                is_active: row.get::<_, i32>(6)? != 0,
-                must_change_password: row.get::<_, i32>(7)? != 0,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        },
-    ).ok();
+/// Check a username and password.
+///
+/// WP-85: with a directory configured (`auth::ldap`), every account except a
+/// local administrator is checked against the directory instead of its bcrypt
+/// hash, and its local row is created or refreshed from the directory entry.
+pub fn authenticate(db: &Database, username: &str, password: &str) -> Result<User, AppError> {
+    let directory = ldap::configured_directory(&db.conn)?;
+    let default_role = directory.as_ref().and_then(|d| d.config().default_role.clone());
+    authenticate_with(
+        db,
+        directory.as_ref().map(|d| d as &dyn ldap::Directory),
+        default_role.as_deref(),
+        username,
+        password,
+    )
+}
+
+/// [`authenticate`] with the directory passed in, so tests can supply one.
+pub fn authenticate_with(
+    db: &Database,
+    directory: Option<&dyn ldap::Directory>,
+    default_role: Option<&str>,
+    username: &str,
+    password: &str,
+) -> Result<User, AppError> {
+    let Some(directory) = directory else {
+        return authenticate_local(db, username, password).and_then(|u| ensure_access_window(db, u));
+    };
+    // The break-glass path: local admins keep their password so the lab is
+    // never locked out by a directory outage. Matched the way the directory
+    // path normalises names, so "ADMIN" or " admin" cannot reach the directory
+    // and be linked over the local account.
+    let local_admin = find_user(
+        db,
+        "username = ?1 COLLATE NOCASE AND auth_source = 'local' AND role = 'admin'",
+        username.trim(),
+    )
+    .is_some();
+    if local_admin {
+        return authenticate_local(db, username, password).and_then(|u| ensure_access_window(db, u));
+    }
+    authenticate_directory(db, directory, default_role, username, password).and_then(|u| ensure_access_window(db, u))
+}
+
+/// WP-86: refuse a time-boxed account past its end date. Checked after the
+/// password, so the message reveals nothing to someone who does not know it.
+fn ensure_access_window(db: &Database, user: User) -> Result<User, AppError> {
+    let Some(expires) = user.access_expires_at.as_deref() else {
+        return Ok(user);
+    };
+    let expired: bool = db.conn.query_row("SELECT ?1 <= datetime('now')", params![expires], |r| r.get(0))?;
+    if expired {
+        return Err(AppError::invalid(format!(
+            "This account's access ended on {} UTC. Ask an administrator to extend it.",
+            expires
+        )));
+    }
+    Ok(user)
+}
+
+const USER_COLUMNS: &str = "id, username, password_hash, display_name, email, role, is_active, \
+                            must_change_password, created_at, updated_at, auth_source, access_expires_at";
+
+fn user_from_row(row: &rusqlite::Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        password_hash: row.get(2)?,
+        display_name: row.get(3)?,
+        email: row.get(4)?,
+        role: row.get::<_, String>(5)?.parse().unwrap_or(UserRole::Guest),
+        is_active: row.get::<_, i32>(6)? != 0,
+        must_change_password: row.get::<_, i32>(7)? != 0,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+        auth_source: row.get(10)?,
+        access_expires_at: row.get(11)?,
+        token_scope: None,
+    })
+}
+
+fn find_user(db: &Database, predicate: &str, value: &str) -> Option<User> {
+    db.conn
+        .query_row(
+            &format!("SELECT {} FROM users WHERE {}", USER_COLUMNS, predicate),
+            params![value],
+            user_from_row,
+        )
+        .ok()
+}
+
+fn authenticate_local(db: &Database, username: &str, password: &str) -> Result<User, AppError> {
+    let user = find_user(db, "username = ?1 AND is_active = 1", username);
 
     // Always pay the bcrypt cost, whether or not the account exists, so the
     // response time carries no information about which usernames are real.
@@ -59,10 +142,73 @@ pub fn authenticate(db: &Database, username: &str, password: &str) -> Result<Use
         Some(user) if password_ok => Ok(user),
         // One message for both failure modes: a distinct "no such user" would
         // undo the timing work above.
-        _ => Err("Invalid username or password".to_string()),
+        _ => Err(AppError::unauthenticated(reason::INVALID_CREDENTIALS, "Invalid username or password")),
     }
 }
 
+fn authenticate_directory(
+    db: &Database,
+    directory: &dyn ldap::Directory,
+    default_role: Option<&str>,
+    username: &str,
+    password: &str,
+) -> Result<User, AppError> {
+    let invalid = || AppError::unauthenticated(reason::INVALID_CREDENTIALS, "Invalid username or password");
+    // Directory logins are case-insensitive, like the directories themselves;
+    // provisioned rows use the lowercase form so "JDoe" and "jdoe" are one account.
+    let username = username.trim().to_lowercase();
+    if username.is_empty() || password.is_empty() {
+        return Err(invalid());
+    }
+    let entry = directory.find_user(&username)?.ok_or_else(invalid)?;
+    if !directory.verify_password(&entry.dn, password)? {
+        return Err(invalid());
+    }
+    // Checked after the bind so only the account's owner learns why.
+    let existing = find_user(db, "username = ?1 COLLATE NOCASE AND auth_source = 'ldap'", &username);
+    let refuse = |reason: &str, detail: &str| -> Result<User, AppError> {
+        if let Some(u) = &existing {
+            if ldap::deactivate(&db.conn, &u.id)? {
+                crate::db::queries::log_audit(
+                    &db.conn, Some(&u.id), "directory_disabled", "user", Some(&u.id), None, None,
+                    Some(&format!("Deactivated at login: {}", detail)),
+                ).ok();
+            }
+        }
+        Err(AppError::invalid(reason))
+    };
+    if entry.disabled {
+        return refuse("Your directory account is disabled.", "disabled in the directory");
+    }
+    let Some(role) = ldap::map_role(&db.conn, &entry.groups, default_role)? else {
+        return refuse(
+            "Your directory account is not in a group that grants access to SteloPTC. Ask an administrator.",
+            "no longer in a group mapped to a role",
+        );
+    };
+
+    let (id, outcome) = ldap::provision(&db.conn, &username, &entry, &role)?;
+    let conn = &db.conn;
+    let role_str = Some(role.as_str());
+    match &outcome {
+        ldap::Provisioning::Created => crate::db::queries::log_audit(
+            conn, Some(&id), "ldap_provisioned", "user", Some(&id), None, role_str,
+            Some(&format!("Created from directory entry {}", entry.dn)),
+        ),
+        ldap::Provisioning::Linked => crate::db::queries::log_audit(
+            conn, Some(&id), "ldap_linked", "user", Some(&id), None, role_str,
+            Some(&format!("Local account now signs in through directory entry {}", entry.dn)),
+        ),
+        ldap::Provisioning::Refreshed { changes } if !changes.is_empty() => crate::db::queries::log_audit(
+            conn, Some(&id), "directory_sync", "user", Some(&id), None, role_str,
+            Some(&format!("Updated from the directory at login: {}", changes.join(", "))),
+        ),
+        ldap::Provisioning::Refreshed { .. } => Ok(()),
+    }
+    .ok();
+    find_user(db, "id = ?1", &id).ok_or_else(|| AppError::internal("Directory account could not be loaded"))
+}
+
 /// Minimum password length, applied to every path that sets a password.
 ///
 /// Twelve rather than eight: this guards lab records subject to retention and
@@ -70,26 +216,102 @@ pub fn authenticate(db: &Database, username: &str, password: &str) -> Result<Use
 /// can guess at CPU speed rather than network speed).
 pub const MIN_PASSWORD_LEN: usize = 12;
 
-/// Single source of truth for password strength.
+/// The fixed floor for password strength.
 ///
-/// Both `create_user` and `change_password` call this. Previously only the
+/// Both `create_user` and `change_password` reach this through
+/// `policy::check_new_password`, which adds the lab's configurable rules
+/// (WP-89) on top. Previously only the
 /// change path enforced a minimum, so an admin could provision an account with
 /// a one-character password that the user was then unable to re-set to anything
 /// equally weak — the two rules disagreed, which is the failure mode a shared
 /// validator exists to prevent.
-pub fn validate_password(password: &str) -> Result<(), String> {
+pub fn validate_password(password: &str) -> Result<(), AppError> {
     if password.chars().count() < MIN_PASSWORD_LEN {
-        return Err(format!(
+        return Err(AppError::validation("password", format!(
             "Password must be at least {} characters",
             MIN_PASSWORD_LEN
-        ));
+        )));
     }
     if password.trim().is_empty() {
-        return Err("Password cannot be only whitespace".to_string());
+        return Err(AppError::validation("password", "Password cannot be only whitespace"));
     }
     Ok(())
 }
 
+/// A password check that succeeded: the new session, and whether it still
+/// waits for a second factor.
+pub struct SignIn {
+    pub user: User,
+    pub token: String,
+    pub mfa: totp::MfaStatus,
+}
+
+/// The password step of a login, shared by the `login` command and
+/// `stelo-cli`: the lockout, the password check, expiry (WP-89), a new session
+/// labelled `device` (WP-88), and the audit entries for each outcome.
+pub fn sign_in(db: &Database, username: &str, password: &str, device: Option<&str>) -> Result<SignIn, AppError> {
+    use crate::db::queries::log_audit;
+    // Check the lockout before hashing, so a guessing loop against a locked
+    // name cannot burn CPU on bcrypt.
+    if let Err(e) = lockout::check(&db.conn, username) {
+        let remaining = lockout::lock_remaining_minutes(&db.conn, username).unwrap_or(0);
+        log_audit(
+            &db.conn, None, "login_blocked", "user", None, None, Some(username),
+            Some(&format!("Locked out after repeated failures; ~{} minute(s) remaining", remaining)),
+        ).ok();
+        return Err(e);
+    }
+
+    let mut user = authenticate(db, username, password).inspect_err(|e| {
+        log_audit(&db.conn, None, "login_failed", "user", None, None, Some(username), Some(e.message())).ok();
+        lockout::record_failure(&db.conn, username).ok();
+    })?;
+    // WP-89: an expired password sends the user through the forced change.
+    if policy::expire_if_due(&db.conn, &mut user)? {
+        log_audit(
+            &db.conn, Some(&user.id), "password_expired", "user", Some(&user.id), None, None,
+            Some("Password older than the maximum age; change required at this login"),
+        ).ok();
+    }
+    let token = create_session(db, &user.id, device)?;
+    let mfa = totp::status(&db.conn, &user.id, user.role.as_str())?;
+
+    // With 2FA enabled the password is only half the login: the lockout keeps
+    // counting until the code is accepted, and the "login" entry is written by
+    // `sign_in_mfa`.
+    if mfa.enrolled {
+        log_audit(
+            &db.conn, Some(&user.id), "login_password_verified", "user", Some(&user.id),
+            None, None, Some("Awaiting second factor"),
+        ).ok();
+    } else {
+        lockout::clear(&db.conn, username);
+        log_audit(&db.conn, Some(&user.id), "login", "user", Some(&user.id), None, None, None).ok();
+    }
+    Ok(SignIn { user, token, mfa })
+}
+
+/// WP-84: the second step of a login for a user with TOTP enabled. `code` is
+/// the six-digit authenticator code or one of the user's recovery codes.
+/// Failures count against the same lockout as wrong passwords.
+pub fn sign_in_mfa(db: &Database, token: &str, code: &str) -> Result<User, AppError> {
+    use crate::db::queries::log_audit;
+    let pending_user = session_user_for_mfa(db, token)?;
+    lockout::check(&db.conn, &pending_user.username)?;
+
+    let key = totp::installation_key(db)?;
+    let (user, factor) = complete_mfa(db, &key, token, code).inspect_err(|e| {
+        log_audit(
+            &db.conn, Some(&pending_user.id), "mfa_failed", "user", Some(&pending_user.id),
+            None, None, Some(e.message()),
+        ).ok();
+        lockout::record_failure(&db.conn, &pending_user.username).ok();
+    })?;
+    lockout::clear(&db.conn, &user.username);
+    log_audit(&db.conn, Some(&user.id), "login", "user", Some(&user.id), None, None, Some(&factor.describe())).ok();
+    Ok(user)
+}
+
 /// Hashes a session token for storage.
 ///
 /// A session token is a bearer credential: whoever holds it *is* the user until
@@ -110,25 +332,68 @@ pub fn hash_token(token: &str) -> String {
     )
 }
 
-pub fn create_session(db: &Database, user_id: &str) -> Result<String, String> {
+/// Open a session for `user_id`. `device` is the client's description of
+/// where it is signing in from (WP-88), shown in the admin session list.
+pub fn create_session(db: &Database, user_id: &str, device: Option<&str>) -> Result<String, AppError> {
     let token = generate_token();
     let id = uuid::Uuid::new_v4().to_string();
-    let expires = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::hours(24))
-        .unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC)
-        .format("%Y-%m-%d %H:%M:%S")
-        .to_string();
+    // WP-84: a user with TOTP enabled gets a short-lived pending session that
+    // is good only for `complete_mfa`; the full 24h starts once the second
+    // factor checks out.
+    let mfa_pending = totp::is_enrolled(&db.conn, user_id)?;
+    let lifetime = if mfa_pending {
+        chrono::Duration::minutes(totp::PENDING_SESSION_MINUTES)
+    } else {
+        chrono::Duration::hours(24)
+    };
+    let expires = expiry_after(lifetime);
 
     // Only the digest is persisted. The raw token exists in this function's
     // return value and in the client's possession, never on disk.
     db.conn.execute(
-        "INSERT INTO sessions (id, user_id, token, expires_at) VALUES (?1, ?2, ?3, ?4)",
-        params![id, user_id, hash_token(&token), expires],
-    ).map_err(|e| format!("Failed to create session: {}", e))?;
+        "INSERT INTO sessions (id, user_id, token, expires_at, mfa_pending, device, last_seen_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'))",
+        params![id, user_id, hash_token(&token), expires, mfa_pending as i64, sessions::device_label(device)],
+    ).map_err(|e| AppError::internal(format!("Failed to create session: {}", e)))?;
 
     Ok(token)
 }
 
+fn expiry_after(lifetime: chrono::Duration) -> String {
+    chrono::Utc::now()
+        .checked_add_signed(lifetime)
+        .unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// The user behind a session that is still waiting for its second factor.
+/// `Err` for an unknown, expired, or already-complete session.
+pub fn session_user_for_mfa(db: &Database, token: &str) -> Result<User, AppError> {
+    match lookup_session(db, token)? {
+        (user, true) => Ok(user),
+        (_, false) => Err(AppError::invalid("This session does not need a second factor.")),
+    }
+}
+
+/// Finish a two-step login: check the TOTP or recovery code for the pending
+/// session's user, then promote the session to a normal 24h one. Returns the
+/// user and which factor was used.
+pub fn complete_mfa(
+    db: &Database,
+    key: &[u8; 32],
+    token: &str,
+    code: &str,
+) -> Result<(User, totp::SecondFactor), AppError> {
+    let user = session_user_for_mfa(db, token)?;
+    let factor = totp::verify_second_factor(&db.conn, key, &user.id, code, chrono::Utc::now().timestamp())?;
+    db.conn.execute(
+        "UPDATE sessions SET mfa_pending = 0, expires_at = ?1 WHERE token = ?2",
+        params![expiry_after(chrono::Duration::hours(24)), hash_token(token)],
+    ).map_err(|e| AppError::internal(format!("Failed to update session: {}", e)))?;
+    Ok((user, factor))
+}
+
 /// Validate a session token for a **normal** command.
 ///
 /// In addition to the token/expiry/active checks, this rejects any user who
@@ -138,22 +403,58 @@ pub fn create_session(db: &Database, user_id: &str) -> Result<String, String> {
 /// data. Enforcing it here (rather than only in the UI) means every command that
 /// calls `validate_session` is protected with no per-command change: the block
 /// is default-deny.
-pub fn validate_session(db: &Database, token: &str) -> Result<User, String> {
+///
+/// WP-84 adds a second gate of the same shape: a user whose role requires
+/// two-factor authentication (`mfa_policy`) but who has not enrolled is held
+/// to the enrollment commands until they do.
+///
+/// WP-92: an API token (`api_tokens::TOKEN_PREFIX`) is accepted here too. Its
+/// service account never has a password to change, and the two-factor gate
+/// does not apply: the token is the machine's only credential.
+pub fn validate_session(db: &Database, token: &str) -> Result<User, AppError> {
     let user = validate_session_allow_password_change(db, token)?;
     if user.must_change_password {
-        return Err("A password change is required before continuing.".to_string());
+        return Err(AppError::gated(
+            reason::PASSWORD_CHANGE_REQUIRED,
+            "A password change is required before continuing.",
+        ));
+    }
+    if user.token_scope.is_some() {
+        return Ok(user);
+    }
+    if totp::role_requires_mfa(&db.conn, user.role.as_str())? && !totp::is_enrolled(&db.conn, &user.id)? {
+        return Err(AppError::gated(
+            reason::MFA_ENROLLMENT_REQUIRED,
+            "Two-factor enrollment is required before continuing.",
+        ));
     }
     Ok(user)
 }
 
 /// Raw session lookup: returns the user for a valid, unexpired token belonging
-/// to an active account, **regardless of the `must_change_password` flag**.
+/// to an active account, **regardless of the `must_change_password` flag** or
+/// a pending MFA enrollment requirement.
 ///
-/// Only the two endpoints a locked-out user still needs may use this:
-/// `change_password` (to clear the flag) and `get_current_user` (so the forced-
-/// change screen can render who is logged in). Everything else must go through
+/// Only the endpoints a locked-out user still needs may use this:
+/// `change_password` (to clear the flag), `get_current_user` (so the forced-
+/// change screen can render who is logged in), and the TOTP enrollment
+/// commands (`get_mfa_status`, `begin_totp_enrollment`,
+/// `confirm_totp_enrollment`). Everything else must go through
 /// `validate_session`.
-pub fn validate_session_allow_password_change(db: &Database, token: &str) -> Result<User, String> {
+///
+/// A session still waiting for its second factor is rejected here too; only
+/// `complete_mfa` accepts it.
+pub fn validate_session_allow_password_change(db: &Database, token: &str) -> Result<User, AppError> {
+    if api_tokens::is_api_token(token) {
+        return api_tokens::authenticate(&db.conn, token);
+    }
+    match lookup_session(db, token)? {
+        (_, true) => Err(AppError::unauthenticated(reason::MFA_REQUIRED, "Two-factor verification required.")),
+        (user, false) => Ok(user),
+    }
+}
+
+fn lookup_session(db: &Database, token: &str) -> Result<(User, bool), AppError> {
     // Opportunistic reap. `invalidate_session` on explicit logout was previously
     // the only DELETE in the system, so on a shared terminal the sessions table
     // grew by one row per login forever. The predicate is the same one the
@@ -163,33 +464,80 @@ pub fn validate_session_allow_password_change(db: &Database, token: &str) -> Res
         .execute("DELETE FROM sessions WHERE expires_at <= datetime('now')", [])
         .ok();
 
-    let user = db.conn.query_row(
-        "SELECT u.id, u.username, u.password_hash, u.display_name, u.email, u.role, u.is_active, u.must_change_password, u.created_at, u.updated_at
+    let token_hash = hash_token(token);
+    // WP-88: the role's idle and absolute limits. A session past either is
+    // deleted, so it cannot be revived by a later policy change.
+    if let Some(timeout) = sessions::timeout_for(&db.conn, &token_hash)? {
+        db.conn.execute("DELETE FROM sessions WHERE token = ?1", params![token_hash]).ok();
+        return Err(AppError::unauthenticated(reason::SESSION_EXPIRED, timeout.message()));
+    }
+
+    let found = db.conn.query_row(
+        "SELECT u.id, u.username, u.password_hash, u.display_name, u.email, u.role, u.is_active, u.must_change_password, u.created_at, u.updated_at, u.auth_source, u.access_expires_at, s.mfa_pending
          FROM sessions s JOIN users u ON s.user_id = u.id
-         WHERE s.token = ?1 AND s.expires_at > datetime('now') AND u.is_active = 1",
-        params![hash_token(token)],
-        |row| {
-            Ok(User {
-                id: row.get(0)?,
-                username: row.get(1)?,
-                password_hash: row.get(2)?,
-                display_name: row.get(3)?,
-                email: row.get(4)?,
-                role: row.get::<_, String>(5)?.parse().unwrap_or(UserRole::Guest),
-                is_active: row.get::<_, i32>(6)? != 0,
-                must_change_password: row.get::<_, i32>(7)? != 0,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        },
-    ).map_err(|_| "Session expired or invalid".to_string())?;
+         WHERE s.token = ?1 AND s.expires_at > datetime('now') AND u.is_active = 1
+           AND (u.access_expires_at IS NULL OR u.access_expires_at > datetime('now'))",
+        params![token_hash],
+        |row| Ok((user_from_row(row)?, row.get::<_, i64>(12)? != 0)),
+    ).map_err(|_| AppError::unauthenticated(reason::SESSION_EXPIRED, "Session expired or invalid"))?;
+    sessions::touch(&db.conn, &token_hash)?;
+    Ok(found)
+}
 
-    Ok(user)
+/// WP-86: the one authorization check commands make, after
+/// `validate_session`. It replaces the old `can_write` / `can_manage` /
+/// `is_admin` role predicates; see `auth::roles` for the catalogue.
+///
+/// WP-92: a caller using an API token needs the capability in the token's
+/// scope as well as in its account's role.
+pub fn require_capability(db: &Database, user: &User, capability: Capability) -> Result<(), AppError> {
+    if let Some(scope) = &user.token_scope {
+        if !scope.allows(capability) {
+            return Err(AppError::Forbidden {
+                message: format!(
+                    "Insufficient permissions — this API token is not scoped for \"{}\" ({}).",
+                    capability.label(),
+                    capability.key()
+                ),
+                capability: Some(capability.key().to_string()),
+                reason: Some(reason::TOKEN_SCOPE),
+            });
+        }
+    }
+    if roles::has_capability(&db.conn, &user.role, capability)? {
+        Ok(())
+    } else {
+        Err(AppError::missing_capability(
+            capability.key(),
+            format!(
+                "Insufficient permissions — your role does not include \"{}\" ({}).",
+                capability.label(),
+                capability.key()
+            ),
+        ))
+    }
 }
 
-pub fn invalidate_session(db: &Database, token: &str) -> Result<(), String> {
+/// WP-92: the check for calls that need no capability — reading lab data —
+/// made by the remote interfaces (the local API and `stelo-cli`) before they
+/// run one. Sessions always pass; an API token needs the `read` scope.
+pub fn require_read_scope(user: &User) -> Result<(), AppError> {
+    match &user.token_scope {
+        Some(scope) if !scope.read => Err(AppError::Forbidden {
+            message: format!(
+                "Insufficient permissions — this API token is not scoped for reading lab data ({}).",
+                api_tokens::READ_SCOPE
+            ),
+            capability: Some(api_tokens::READ_SCOPE.to_string()),
+            reason: Some(reason::TOKEN_SCOPE),
+        }),
+        _ => Ok(()),
+    }
+}
+
+pub fn invalidate_session(db: &Database, token: &str) -> Result<(), AppError> {
     db.conn.execute("DELETE FROM sessions WHERE token = ?1", params![hash_token(token)])
-        .map_err(|e| format!("Failed to invalidate session: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Failed to invalidate session: {}", e)))?;
     Ok(())
 }
 
@@ -200,127 +548,6 @@ fn generate_token() -> String {
     base64::Engine::encode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, &bytes)
 }
 
-/// Per-username failed-login tracker with exponential backoff and a hard lock.
-///
-/// `login` is an unauthenticated IPC command that can be driven in a tight loop,
-/// and bcrypt at cost 12 still allows several guesses per second per core —
-/// slow for a remote attacker, ample for a local one working against a seeded
-/// `admin` account. Nothing else in the system rate-limits it.
-///
-/// State is in-memory and per-process. That is the right trade-off for a desktop
-/// app: it resets on restart (an attacker with the ability to restart the app
-/// already has the machine), and it avoids a write to the database on every
-/// failed guess, which would itself be an amplification vector.
-pub struct LoginThrottle {
-    failures: std::sync::Mutex<std::collections::HashMap<String, FailureRecord>>,
-    threshold: u32,
-    window: std::time::Duration,
-    capacity: usize,
-}
-
-#[derive(Clone, Copy)]
-struct FailureRecord {
-    count: u32,
-    last: std::time::Instant,
-}
-
-impl Default for LoginThrottle {
-    fn default() -> Self {
-        // 5 attempts, then a 15-minute lock — enough headroom for a genuine
-        // typo streak, low enough to make guessing impractical.
-        Self::new(5, std::time::Duration::from_secs(15 * 60), 1024)
-    }
-}
-
-impl LoginThrottle {
-    pub fn new(threshold: u32, window: std::time::Duration, capacity: usize) -> Self {
-        Self {
-            failures: std::sync::Mutex::new(std::collections::HashMap::new()),
-            threshold,
-            window,
-            capacity,
-        }
-    }
-
-    /// Returns `Err` when `username` is currently locked out.
-    ///
-    /// The error text is byte-identical to a bad-password failure: a distinct
-    /// "account locked" message would tell an attacker which usernames exist,
-    /// re-opening the enumeration hole that `timing_equalizer_hash` closes.
-    pub fn check(&self, username: &str) -> Result<(), String> {
-        let guard = match self.failures.lock() {
-            Ok(g) => g,
-            // A poisoned lock must not become a way to bypass the throttle, but
-            // it also must not lock everyone out. Recover and continue.
-            Err(poisoned) => poisoned.into_inner(),
-        };
-        match guard.get(username) {
-            Some(rec) if rec.count >= self.threshold && rec.last.elapsed() < self.window => {
-                Err("Invalid username or password".to_string())
-            }
-            _ => Ok(()),
-        }
-    }
-
-    /// How long `username` must wait before another attempt is accepted.
-    /// `None` when not currently locked. Used for the audit detail, never
-    /// returned to the caller.
-    pub fn lock_remaining(&self, username: &str) -> Option<std::time::Duration> {
-        let guard = self.failures.lock().ok()?;
-        let rec = guard.get(username)?;
-        if rec.count >= self.threshold {
-            self.window.checked_sub(rec.last.elapsed())
-        } else {
-            None
-        }
-    }
-
-    pub fn record_failure(&self, username: &str) {
-        let mut guard = match self.failures.lock() {
-            Ok(g) => g,
-            Err(poisoned) => poisoned.into_inner(),
-        };
-
-        // Usernames are attacker-supplied, so the map would otherwise grow
-        // without bound on a dictionary run. Drop entries whose window has
-        // elapsed, and if that is not enough, refuse to add new ones rather
-        // than evicting live lockouts an attacker could then reset at will.
-        if guard.len() >= self.capacity {
-            let window = self.window;
-            guard.retain(|_, rec| rec.last.elapsed() < window);
-            if guard.len() >= self.capacity && !guard.contains_key(username) {
-                return;
-            }
-        }
-
-        let entry = guard.entry(username.to_string()).or_insert(FailureRecord {
-            count: 0,
-            last: std::time::Instant::now(),
-        });
-        // A lockout that has fully expired starts a fresh streak rather than
-        // resuming at the old count.
-        if entry.count >= self.threshold && entry.last.elapsed() >= self.window {
-            entry.count = 0;
-        }
-        entry.count = entry.count.saturating_add(1);
-        entry.last = std::time::Instant::now();
-    }
-
-    /// Number of usernames currently being tracked. Exposed so the capacity
-    /// bound can be asserted — an unbounded map would itself be a
-    /// memory-exhaustion vector, since usernames are attacker-supplied.
-    pub fn entry_count(&self) -> usize {
-        self.failures.lock().map(|g| g.len()).unwrap_or(0)
-    }
-
-    /// Clears the counter after a successful login.
-    pub fn clear(&self, username: &str) {
-        if let Ok(mut guard) = self.failures.lock() {
-            guard.remove(username);
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -338,7 +565,7 @@ mod tests {
                 params![if must_change { 1 } else { 0 }],
             )
             .unwrap();
-        let token = create_session(&db, "u1").unwrap();
+        let token = create_session(&db, "u1", None).unwrap();
         (db, token)
     }
 
@@ -357,7 +584,8 @@ mod tests {
         // though their token is otherwise valid and unexpired.
         let (db, token) = db_with_session(true);
         let err = validate_session(&db, &token).expect_err("must be rejected");
-        assert!(err.to_lowercase().contains("password change"), "message was: {}", err);
+        assert!(err.message().to_lowercase().contains("password change"), "message was: {}", err);
+        assert_eq!(err.params()["reason"], crate::error::reason::PASSWORD_CHANGE_REQUIRED);
     }
 
     #[test]
@@ -419,8 +647,8 @@ mod tests {
              VALUES ('u1', 'tech1', 'x', 'Tech One', 'tech', 1)",
             [],
         ).unwrap();
-        let a = create_session(&db, "u1").unwrap();
-        let b = create_session(&db, "u1").unwrap();
+        let a = create_session(&db, "u1", None).unwrap();
+        let b = create_session(&db, "u1", None).unwrap();
         assert_ne!(a, b, "each login must mint a fresh token");
         assert!(validate_session(&db, &a).is_ok());
         assert!(validate_session(&db, &b).is_ok());
@@ -467,8 +695,9 @@ mod tests {
 
         let unknown = authenticate(&db, "nobody", "whatever").unwrap_err();
         let wrong = authenticate(&db, "tech1", "not-the-password").unwrap_err();
-        assert_eq!(unknown, wrong, "the two failures must be indistinguishable");
-        assert_eq!(unknown, "Invalid username or password");
+        assert_eq!(unknown.message(), wrong.message(), "the two failures must be indistinguishable");
+        assert_eq!(unknown.code(), "unauthenticated");
+        assert_eq!(unknown.message(), "Invalid username or password");
 
         assert!(authenticate(&db, "tech1", "the-real-password").is_ok());
     }
@@ -486,7 +715,25 @@ mod tests {
         // Correct password, inactive account — still the generic failure, so
         // deactivation is not observable either.
         let err = authenticate(&db, "gone", "the-real-password").unwrap_err();
-        assert_eq!(err, "Invalid username or password");
+        assert_eq!(err.message(), "Invalid username or password");
+    }
+
+    #[test]
+    fn a_time_boxed_account_stops_working_when_its_access_ends() {
+        let (db, token) = db_with_session(false);
+        let hash = bcrypt::hash("the-real-password", 4).unwrap();
+        db.conn.execute("UPDATE users SET password_hash = ?1, access_expires_at = datetime('now', '+1 day')", params![hash])
+            .unwrap();
+        assert!(validate_session(&db, &token).is_ok());
+        assert!(authenticate(&db, "tech1", "the-real-password").is_ok());
+
+        db.conn.execute("UPDATE users SET access_expires_at = datetime('now', '-1 minute')", []).unwrap();
+        assert!(validate_session(&db, &token).is_err(), "live sessions end with the access window");
+        let err = authenticate(&db, "tech1", "the-real-password").unwrap_err();
+        assert!(err.message().contains("access ended"), "{}", err);
+        // A wrong password still gets the generic failure, so expiry is only
+        // revealed to someone who knows the password.
+        assert_eq!(authenticate(&db, "tech1", "nope").unwrap_err().message(), "Invalid username or password");
     }
 
     // ── Expired-session reaping ───────────────────────────────────────────
@@ -507,96 +754,157 @@ mod tests {
         assert!(validate_session(&db, "stale-token").is_err());
     }
 
+    #[test]
+    fn validate_session_enforces_the_role_idle_limit_and_records_use() {
+        let (db, token) = db_with_session(false);
+        sessions::set_policy(
+            &db.conn,
+            &sessions::SessionPolicy { role: "tech".into(), idle_minutes: Some(10), absolute_hours: None },
+        )
+        .unwrap();
+        db.conn.execute("UPDATE sessions SET last_seen_at = datetime('now', '-5 minutes')", []).unwrap();
+        validate_session(&db, &token).expect("used five minutes ago");
+        let fresh: bool = db.conn
+            .query_row("SELECT last_seen_at > datetime('now', '-1 minute') FROM sessions", [], |r| r.get(0))
+            .unwrap();
+        assert!(fresh, "a validated session is marked as just used");
+
+        db.conn.execute("UPDATE sessions SET last_seen_at = datetime('now', '-11 minutes')", []).unwrap();
+        let err = validate_session(&db, &token).unwrap_err();
+        assert!(err.message().starts_with("Session expired") && err.message().contains("inactivity"), "{}", err);
+        assert_eq!(err.code(), "unauthenticated");
+        assert_eq!(session_count(&db), 0, "a timed-out session is deleted, not just refused");
+    }
+
     fn session_count(db: &Database) -> i64 {
         db.conn.query_row("SELECT COUNT(*) FROM sessions", [], |r| r.get(0)).unwrap()
     }
 
-    // ── Login throttle ────────────────────────────────────────────────────
+    #[test]
+    fn invalid_token_is_rejected_by_both_variants() {
+        let (db, _token) = db_with_session(false);
+        assert!(validate_session(&db, "not-a-real-token").is_err());
+        assert!(validate_session_allow_password_change(&db, "not-a-real-token").is_err());
+    }
 
-    fn fast_throttle() -> LoginThrottle {
-        LoginThrottle::new(3, std::time::Duration::from_millis(120), 8)
+    fn enroll(db: &Database, key: &[u8; 32]) {
+        let e = totp::begin_enrollment(&db.conn, key, "u1", "tech1").unwrap();
+        let secret = totp::base32_decode(&e.secret).unwrap();
+        let now = chrono::Utc::now().timestamp() - totp::STEP_SECS;
+        let code = format!("{:06}", totp::hotp(&secret, totp::step_at(now) as u64));
+        totp::confirm_enrollment(&db.conn, key, "u1", &code, now).unwrap();
     }
 
     #[test]
-    fn throttle_allows_attempts_below_the_threshold() {
-        let t = fast_throttle();
-        t.record_failure("tech1");
-        t.record_failure("tech1");
-        assert!(t.check("tech1").is_ok(), "2 of 3 failures must not lock");
+    fn enrolled_users_get_a_pending_session_until_the_second_factor() {
+        let (db, _) = db_with_session(false);
+        let key = [3u8; 32];
+        enroll(&db, &key);
+        let token = create_session(&db, "u1", None).unwrap();
+        assert_eq!(session_user_for_mfa(&db, &token).unwrap().id, "u1");
+        assert_eq!(
+            validate_session_allow_password_change(&db, &token).unwrap_err().to_string(),
+            "Two-factor verification required."
+        );
+        assert!(validate_session(&db, &token).is_err());
+
+        assert!(complete_mfa(&db, &key, &token, "not-a-code").is_err());
+        let codes = totp::regenerate_recovery_codes(&db.conn, "u1").unwrap();
+        let (user, factor) = complete_mfa(&db, &key, &token, &codes[0]).unwrap();
+        assert_eq!(user.id, "u1");
+        assert_eq!(factor, totp::SecondFactor::RecoveryCode { remaining: 9 });
+        assert!(validate_session(&db, &token).is_ok());
+        assert!(complete_mfa(&db, &key, &token, &codes[1]).is_err(), "a full session cannot be re-verified");
     }
 
     #[test]
-    fn throttle_locks_at_the_threshold() {
-        let t = fast_throttle();
-        for _ in 0..3 {
-            t.record_failure("tech1");
-        }
-        assert!(t.check("tech1").is_err());
-        assert!(t.check("someone_else").is_ok(), "the lock is per-username");
+    fn policy_holds_unenrolled_users_to_enrollment() {
+        let (db, token) = db_with_session(false);
+        totp::set_policy(&db.conn, "tech", true).unwrap();
+        assert_eq!(
+            validate_session(&db, &token).unwrap_err(),
+            AppError::gated(reason::MFA_ENROLLMENT_REQUIRED, "Two-factor enrollment is required before continuing.")
+        );
+        assert!(validate_session_allow_password_change(&db, &token).is_ok());
+        enroll(&db, &[3u8; 32]);
+        assert!(validate_session(&db, &token).is_ok(), "existing session is not demoted by enrolling");
     }
 
-    #[test]
-    fn throttle_lockout_message_matches_a_normal_failure() {
-        // A distinct "account locked" string would reveal which usernames
-        // exist, undoing the timing work in authenticate().
-        let t = fast_throttle();
-        for _ in 0..3 {
-            t.record_failure("tech1");
-        }
-        assert_eq!(t.check("tech1").unwrap_err(), "Invalid username or password");
+    fn directory_db() -> (Database, ldap::tests::FakeDirectory) {
+        let db = Database::new_in_memory().unwrap();
+        db.run_migrations().unwrap();
+        let hash = bcrypt::hash("local-admin-password", 4).unwrap();
+        db.conn
+            .execute(
+                "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('adm', 'admin', ?1, 'Admin', 'admin'), \
+                                                                                         ('loc', 'local1', ?1, 'Local', 'tech')",
+                params![hash],
+            )
+            .unwrap();
+        ldap::set_group_mapping(&db.conn, ldap::tests::LAB_GROUP, "tech").unwrap();
+        let dir = ldap::tests::FakeDirectory::default()
+            .with_user("ann", "ann-password", &[ldap::tests::LAB_GROUP])
+            .with_user("local1", "directory-password", &[ldap::tests::LAB_GROUP]);
+        (db, dir)
     }
 
     #[test]
-    fn throttle_expires_after_the_window_and_starts_a_fresh_streak() {
-        let t = fast_throttle();
-        for _ in 0..3 {
-            t.record_failure("tech1");
-        }
-        assert!(t.check("tech1").is_err());
-        std::thread::sleep(std::time::Duration::from_millis(160));
-        assert!(t.check("tech1").is_ok(), "the lock must lift once the window elapses");
+    fn directory_login_provisions_the_account_on_first_use() {
+        let (db, dir) = directory_db();
+        let user = authenticate_with(&db, Some(&dir), None, "Ann", "ann-password").unwrap();
+        assert_eq!((user.username.as_str(), user.role.as_str(), user.auth_source.as_str()), ("ann", "tech", "ldap"));
+        assert_eq!(user.display_name, "ann (AD)");
+        assert!(!user.must_change_password);
+        assert_eq!(authenticate_with(&db, Some(&dir), None, "ann", "ann-password").unwrap().id, user.id);
 
-        // One more failure must not immediately re-lock — the old streak is
-        // spent, so the counter restarts rather than resuming at 3.
-        t.record_failure("tech1");
-        assert!(t.check("tech1").is_ok());
+        assert!(authenticate_with(&db, Some(&dir), None, "ann", "wrong").is_err());
+        assert!(authenticate_with(&db, Some(&dir), None, "ann", "").is_err(), "no unauthenticated binds");
+        assert!(authenticate_with(&db, None, None, "ann", "ann-password").is_err(), "no local password to fall back on");
     }
 
     #[test]
-    fn throttle_clears_on_successful_login() {
-        let t = fast_throttle();
-        t.record_failure("tech1");
-        t.record_failure("tech1");
-        t.clear("tech1");
-        t.record_failure("tech1");
-        assert!(t.check("tech1").is_ok(), "the counter must have restarted from zero");
-    }
+    fn local_admins_bypass_the_directory_and_others_are_linked_to_it() {
+        let (db, dir) = directory_db();
+        dir.down.set(true);
+        assert!(authenticate_with(&db, Some(&dir), None, "admin", "local-admin-password").is_ok());
+        assert!(authenticate_with(&db, Some(&dir), None, "local1", "local-admin-password").is_err());
 
-    #[test]
-    fn throttle_map_is_bounded_against_a_dictionary_run() {
-        // Usernames are attacker-supplied, so an unbounded map would be a
-        // memory-exhaustion vector in its own right.
-        let t = LoginThrottle::new(3, std::time::Duration::from_secs(600), 8);
-        for i in 0..500 {
-            t.record_failure(&format!("user{}", i));
-        }
-        assert!(t.entry_count() <= 8, "tracked entries must stay within capacity");
+        dir.down.set(false);
+        assert!(authenticate_with(&db, Some(&dir), None, "local1", "local-admin-password").is_err());
+        let linked = authenticate_with(&db, Some(&dir), None, "local1", "directory-password").unwrap();
+        assert_eq!((linked.id.as_str(), linked.auth_source.as_str()), ("loc", "ldap"));
     }
 
     #[test]
-    fn throttle_lock_remaining_reports_time_only_while_locked() {
-        let t = fast_throttle();
-        assert!(t.lock_remaining("tech1").is_none());
-        for _ in 0..3 {
-            t.record_failure("tech1");
+    fn a_case_variant_directory_login_cannot_take_over_the_local_admin() {
+        let (db, dir) = directory_db();
+        let dir = dir.with_user("admin", "directory-password", &[ldap::tests::LAB_GROUP]);
+        for name in ["ADMIN", " admin", "Admin "] {
+            assert!(authenticate_with(&db, Some(&dir), None, name, "directory-password").is_err(), "{name:?}");
         }
-        assert!(t.lock_remaining("tech1").is_some());
+        let (source, role): (String, String) = db
+            .conn
+            .query_row("SELECT auth_source, role FROM users WHERE id = 'adm'", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert_eq!((source.as_str(), role.as_str()), ("local", "admin"));
+        assert!(authenticate_with(&db, Some(&dir), None, "admin", "local-admin-password").is_ok());
+
+        // Provisioning refuses the row even when reached directly.
+        let entry = ldap::Directory::find_user(&dir, "admin").unwrap().unwrap();
+        assert_eq!(ldap::provision(&db.conn, "admin", &entry, &UserRole::Tech).unwrap_err().code(), "conflict");
     }
 
     #[test]
-    fn invalid_token_is_rejected_by_both_variants() {
-        let (db, _token) = db_with_session(false);
-        assert!(validate_session(&db, "not-a-real-token").is_err());
-        assert!(validate_session_allow_password_change(&db, "not-a-real-token").is_err());
+    fn directory_login_refuses_disabled_and_unmapped_accounts() {
+        let (db, dir) = directory_db();
+        authenticate_with(&db, Some(&dir), None, "ann", "ann-password").unwrap();
+        dir.users.borrow_mut().get_mut("ann").unwrap().0.groups.clear();
+        assert!(authenticate_with(&db, Some(&dir), None, "ann", "ann-password").unwrap_err().message().contains("not in a group"));
+        assert!(authenticate_with(&db, Some(&dir), Some("guest"), "ann", "ann-password").is_ok(), "default role applies");
+
+        dir.users.borrow_mut().get_mut("ann").unwrap().0.disabled = true;
+        assert!(authenticate_with(&db, Some(&dir), Some("guest"), "ann", "ann-password").unwrap_err().message().contains("disabled"));
+        let active: i64 = db.conn.query_row("SELECT is_active FROM users WHERE username = 'ann'", [], |r| r.get(0)).unwrap();
+        assert_eq!(active, 0);
     }
 }
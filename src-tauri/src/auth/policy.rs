@@ -0,0 +1,438 @@
+// WP-89: the lab's sign-in and password policy.
+//
+// One row (`auth_policy`, id = 1) holds every knob an administrator can turn:
+//
+// * **Lockout** — how many failed attempts lock an account, and for how long
+//   (`auth::lockout` applies it).
+// * **Length** — a minimum above the fixed floor of `MIN_PASSWORD_LEN`.
+// * **History** — a new password may not match any of the user's last N.
+//   Every password set is kept as a bcrypt hash in `password_history`.
+// * **Expiry** — past the maximum age, a local account's next login is sent
+//   through the forced-change flow.
+// * **Breached passwords** — a new password is looked up in a local copy of a
+//   breached-password corpus in range format: one file per five-hex-digit
+//   SHA-1 prefix (`5BAA6.txt`), each line `SUFFIX:COUNT`. Only the file for
+//   the password's prefix is read, the same k-anonymity split the online
+//   range APIs use, so the lookup never loads the whole corpus. The check
+//   fails closed: with it switched on and the list missing, no password can
+//   be set until the list is restored or the check switched off.
+//
+// Directory accounts (WP-85) are governed by the directory and exempt from
+// everything here except lockout.
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::path::{Path, PathBuf};
+
+use super::MIN_PASSWORD_LEN;
+use crate::error::AppError;
+use crate::models::user::User;
+
+pub const MAX_PASSWORD_LEN: i64 = 128;
+/// Hashes kept per user; also the largest history an admin can require.
+pub const MAX_HISTORY: i64 = 24;
+pub const MAX_AGE_DAYS: i64 = 3650;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthPolicy {
+    /// Consecutive failures that lock an account.
+    pub lockout_threshold: i64,
+    /// How long a lock lasts; also how long a failure counts towards one.
+    pub lockout_minutes: i64,
+    pub password_min_length: i64,
+    /// Previous passwords a new one may not repeat; 0 only refuses the current one.
+    pub password_history: i64,
+    /// `None`: passwords never expire.
+    pub password_max_age_days: Option<i64>,
+    pub breach_check: bool,
+}
+
+impl Default for AuthPolicy {
+    fn default() -> Self {
+        AuthPolicy {
+            lockout_threshold: 5,
+            lockout_minutes: 15,
+            password_min_length: MIN_PASSWORD_LEN as i64,
+            password_history: 5,
+            password_max_age_days: None,
+            breach_check: false,
+        }
+    }
+}
+
+/// The policy plus where the breached-password list is expected, for the
+/// settings screen.
+#[derive(Debug, Serialize)]
+pub struct AuthPolicyView {
+    #[serde(flatten)]
+    pub policy: AuthPolicy,
+    pub breach_list_path: String,
+    pub breach_list_present: bool,
+}
+
+pub fn get(conn: &Connection) -> Result<AuthPolicy, AppError> {
+    conn.query_row(
+        "SELECT lockout_threshold, lockout_minutes, password_min_length, password_history, \
+                password_max_age_days, breach_check FROM auth_policy WHERE id = 1",
+        [],
+        |r| {
+            Ok(AuthPolicy {
+                lockout_threshold: r.get(0)?,
+                lockout_minutes: r.get(1)?,
+                password_min_length: r.get(2)?,
+                password_history: r.get(3)?,
+                password_max_age_days: r.get(4)?,
+                breach_check: r.get::<_, i64>(5)? != 0,
+            })
+        },
+    )
+    .optional()
+    .map(Option::unwrap_or_default)
+    .map_err(AppError::from)
+}
+
+pub fn view(conn: &Connection, dir: &Path) -> Result<AuthPolicyView, AppError> {
+    Ok(AuthPolicyView {
+        policy: get(conn)?,
+        breach_list_path: dir.display().to_string(),
+        breach_list_present: dir.is_dir(),
+    })
+}
+
+fn in_range(field: &'static str, name: &str, value: i64, min: i64, max: i64, unit: &str) -> Result<(), AppError> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(AppError::validation(field, format!("{} must be between {} and {} {}.", name, min, max, unit)))
+    }
+}
+
+pub fn validate(policy: &AuthPolicy) -> Result<(), AppError> {
+    in_range("lockout_threshold", "The lockout threshold", policy.lockout_threshold, 3, 20, "failed attempts")?;
+    in_range("lockout_minutes", "The lockout duration", policy.lockout_minutes, 1, 24 * 60, "minutes")?;
+    in_range(
+        "password_min_length",
+        "The minimum password length",
+        policy.password_min_length,
+        MIN_PASSWORD_LEN as i64,
+        MAX_PASSWORD_LEN,
+        "characters",
+    )?;
+    in_range("password_history", "The password history", policy.password_history, 0, MAX_HISTORY, "passwords")?;
+    if let Some(days) = policy.password_max_age_days {
+        in_range("password_max_age_days", "The maximum password age", days, 1, MAX_AGE_DAYS, "days")?;
+    }
+    Ok(())
+}
+
+/// Save the policy. Switching the breach check on requires the list to be in
+/// `breach_dir`, so nobody is locked out of changing their password by accident.
+pub fn set(conn: &Connection, policy: &AuthPolicy, breach_dir: &Path) -> Result<(), AppError> {
+    validate(policy)?;
+    if policy.breach_check && !breach_dir.is_dir() {
+        return Err(AppError::validation("breach_check", format!(
+            "Put the breached-password list in {} before switching the check on.",
+            breach_dir.display()
+        )));
+    }
+    conn.execute(
+        "INSERT INTO auth_policy (id, lockout_threshold, lockout_minutes, password_min_length, password_history, \
+                                  password_max_age_days, breach_check, updated_at) \
+         VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, datetime('now')) \
+         ON CONFLICT(id) DO UPDATE SET lockout_threshold = excluded.lockout_threshold, \
+             lockout_minutes = excluded.lockout_minutes, password_min_length = excluded.password_min_length, \
+             password_history = excluded.password_history, password_max_age_days = excluded.password_max_age_days, \
+             breach_check = excluded.breach_check, updated_at = excluded.updated_at",
+        params![
+            policy.lockout_threshold,
+            policy.lockout_minutes,
+            policy.password_min_length,
+            policy.password_history,
+            policy.password_max_age_days,
+            policy.breach_check as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// One line per setting, for the audit entry.
+pub fn describe(policy: &AuthPolicy) -> String {
+    format!(
+        "lock after {} failures for {} min; min length {}; history {}; max age {}; breach check {}",
+        policy.lockout_threshold,
+        policy.lockout_minutes,
+        policy.password_min_length,
+        policy.password_history,
+        policy.password_max_age_days.map_or("none".to_string(), |d| format!("{} days", d)),
+        if policy.breach_check { "on" } else { "off" },
+    )
+}
+
+// ── Breached passwords ──────────────────────────────────────────────────────
+
+/// `breached-passwords/` next to `db`'s file.
+pub fn breach_list_dir(db: &crate::db::Database) -> PathBuf {
+    db.beside("breached-passwords")
+}
+
+/// Upper-case hex SHA-1, the form breach corpora are published in.
+fn sha1_hex(password: &str) -> String {
+    crate::anchoring::hex_encode(&Sha1::digest(password.as_bytes())).to_uppercase()
+}
+
+/// Whether `password` appears in the range-format list under `dir`. A missing
+/// prefix file means no entry with that prefix; a missing directory is an
+/// error. Lines with a count of 0 are padding and do not match.
+pub fn is_breached_in(dir: &Path, password: &str) -> Result<bool, AppError> {
+    if !dir.is_dir() {
+        return Err(AppError::internal(format!(
+            "The breached-password list is missing ({}). Ask an administrator to restore it.",
+            dir.display()
+        )));
+    }
+    let digest = sha1_hex(password);
+    let (prefix, suffix) = digest.split_at(5);
+    let contents = match std::fs::read_to_string(dir.join(format!("{}.txt", prefix))) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(AppError::internal(format!("Failed to read the breached-password list: {}", e))),
+    };
+    Ok(contents.lines().any(|line| {
+        let (s, count) = line.trim().split_once(':').unwrap_or((line.trim(), "1"));
+        s.eq_ignore_ascii_case(suffix) && count.trim() != "0"
+    }))
+}
+
+// ── Setting a password ──────────────────────────────────────────────────────
+
+/// Every rule a new password must pass, cheapest first, against the breach
+/// list in `breach_dir` (see [`breach_list_dir`]). `user_id` is `None` for an
+/// account being created, which has no history yet.
+pub fn check_new_password(
+    conn: &Connection,
+    policy: &AuthPolicy,
+    breach_dir: &Path,
+    user_id: Option<&str>,
+    password: &str,
+) -> Result<(), AppError> {
+    super::validate_password(password)?;
+    let len = password.chars().count() as i64;
+    if len < policy.password_min_length {
+        return Err(AppError::validation("password", format!("Password must be at least {} characters", policy.password_min_length)));
+    }
+    if len > MAX_PASSWORD_LEN {
+        return Err(AppError::validation("password", format!("Password must be at most {} characters", MAX_PASSWORD_LEN)));
+    }
+    if policy.breach_check && is_breached_in(breach_dir, password)? {
+        return Err(AppError::validation(
+            "password",
+            "This password appears in a list of passwords exposed in data breaches. Choose a different one.",
+        ));
+    }
+    let Some(user_id) = user_id else {
+        return Ok(());
+    };
+    if policy.password_history > 0 {
+        let recent: Vec<String> = conn
+            .prepare(
+                "SELECT password_hash FROM password_history WHERE user_id = ?1 \
+                 ORDER BY created_at DESC, rowid DESC LIMIT ?2",
+            )
+            .and_then(|mut s| {
+                s.query_map(params![user_id, policy.password_history], |r| r.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()
+            })?;
+        if recent.iter().any(|h| bcrypt::verify(password, h).unwrap_or(false)) {
+            return Err(AppError::validation("password", format!(
+                "You have used this password recently. Choose one that is not among your last {}.",
+                policy.password_history
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Record that `user_id` now has the password hashed as `hash`: kept in the
+/// history (trimmed to [`MAX_HISTORY`]) and the age clock restarted.
+pub fn record_password_set(conn: &Connection, user_id: &str, hash: &str) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO password_history (id, user_id, password_hash) VALUES (?1, ?2, ?3)",
+        params![uuid::Uuid::new_v4().to_string(), user_id, hash],
+    )?;
+    conn.execute(
+        "DELETE FROM password_history WHERE user_id = ?1 AND rowid NOT IN \
+         (SELECT rowid FROM password_history WHERE user_id = ?1 ORDER BY created_at DESC, rowid DESC LIMIT ?2)",
+        params![user_id, MAX_HISTORY],
+    )?;
+    conn.execute(
+        "UPDATE users SET password_changed_at = datetime('now') WHERE id = ?1",
+        params![user_id],
+    )?;
+    Ok(())
+}
+
+// ── Expiry ──────────────────────────────────────────────────────────────────
+
+/// At login: when a local account's password is older than the policy allows,
+/// set `must_change_password` so the forced-change flow takes over. Returns
+/// whether it did.
+pub fn expire_if_due(conn: &Connection, user: &mut User) -> Result<bool, AppError> {
+    if user.auth_source != "local" || user.must_change_password {
+        return Ok(false);
+    }
+    let Some(days) = get(conn)?.password_max_age_days else {
+        return Ok(false);
+    };
+    let expired = conn
+        .execute(
+            "UPDATE users SET must_change_password = 1, updated_at = datetime('now') \
+             WHERE id = ?1 AND password_changed_at IS NOT NULL \
+               AND password_changed_at <= datetime('now', '-' || ?2 || ' days')",
+            params![user.id, days],
+        )?
+        > 0;
+    user.must_change_password = expired;
+    Ok(expired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'tech1', 'x', 'T', 'tech')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    /// Where no breach list is installed.
+    fn no_list() -> &'static Path {
+        Path::new("")
+    }
+
+    /// A range-format list in a fresh temp directory, removed on drop.
+    struct BreachList(PathBuf);
+
+    impl BreachList {
+        fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for BreachList {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn breach_dir(entries: &[(&str, &str)]) -> BreachList {
+        let dir = std::env::temp_dir().join(format!("steloptc_breach_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (password, count) in entries {
+            let digest = sha1_hex(password);
+            let path = dir.join(format!("{}.txt", &digest[..5]));
+            let mut body = std::fs::read_to_string(&path).unwrap_or_default();
+            body.push_str(&format!("{}:{}\r\n", &digest[5..], count));
+            std::fs::write(path, body).unwrap();
+        }
+        BreachList(dir)
+    }
+
+    #[test]
+    fn policy_defaults_round_trip_and_ranges_are_enforced() {
+        let conn = db();
+        assert_eq!(get(&conn).unwrap(), AuthPolicy::default());
+        let mut p = AuthPolicy { password_min_length: 16, password_max_age_days: Some(90), ..AuthPolicy::default() };
+        set(&conn, &p, no_list()).unwrap();
+        assert_eq!(get(&conn).unwrap(), p);
+
+        p.password_min_length = 8;
+        assert!(set(&conn, &p, no_list()).unwrap_err().message().contains("minimum password length"));
+        p.password_min_length = 12;
+        p.lockout_threshold = 1;
+        assert!(set(&conn, &p, no_list()).is_err());
+        p.lockout_threshold = 5;
+        p.password_history = MAX_HISTORY + 1;
+        assert!(set(&conn, &p, no_list()).is_err());
+    }
+
+    #[test]
+    fn breached_passwords_are_found_by_prefix_file_only() {
+        let dir = breach_dir(&[("correct horse battery", "42"), ("padding entry here", "0")]);
+        assert!(is_breached_in(dir.path(), "correct horse battery").unwrap());
+        assert!(!is_breached_in(dir.path(), "padding entry here").unwrap(), "count 0 is padding");
+        assert!(!is_breached_in(dir.path(), "a different passphrase").unwrap());
+        assert!(is_breached_in(&dir.path().join("missing"), "x").is_err(), "a missing list fails closed");
+
+        let conn = db();
+        let policy = AuthPolicy { breach_check: true, ..AuthPolicy::default() };
+        let err = check_new_password(&conn, &policy, dir.path(), Some("u1"), "correct horse battery").unwrap_err();
+        assert!(err.message().contains("data breaches"), "{}", err);
+        let off = AuthPolicy { breach_check: false, ..AuthPolicy::default() };
+        check_new_password(&conn, &off, dir.path(), Some("u1"), "correct horse battery").unwrap();
+    }
+
+    #[test]
+    fn history_refuses_the_last_n_passwords_only() {
+        let conn = db();
+        let dir = breach_dir(&[]);
+        for pw in ["first password one", "second password two", "third password three"] {
+            record_password_set(&conn, "u1", &bcrypt::hash(pw, 4).unwrap()).unwrap();
+        }
+        let policy = AuthPolicy { password_history: 2, ..AuthPolicy::default() };
+        let check = |pw: &str| check_new_password(&conn, &policy, dir.path(), Some("u1"), pw);
+        assert!(check("third password three").unwrap_err().message().contains("last 2"));
+        assert!(check("second password two").is_err());
+        check("first password one").expect("outside the last two");
+        check_new_password(&conn, &policy, dir.path(), None, "third password three")
+            .expect("a new account has no history");
+        assert!(check("short").is_err());
+    }
+
+    #[test]
+    fn history_is_trimmed_to_the_maximum() {
+        let conn = db();
+        for i in 0..(MAX_HISTORY + 3) {
+            record_password_set(&conn, "u1", &format!("hash-{}", i)).unwrap();
+        }
+        let kept: i64 = conn
+            .query_row("SELECT COUNT(*) FROM password_history WHERE user_id = 'u1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(kept, MAX_HISTORY);
+        let oldest_kept: i64 = conn
+            .query_row("SELECT COUNT(*) FROM password_history WHERE password_hash = 'hash-0'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(oldest_kept, 0, "the oldest hashes go first");
+    }
+
+    #[test]
+    fn an_old_local_password_forces_a_change_at_login() {
+        let conn = db();
+        let load = |conn: &Connection| {
+            conn.query_row(
+                "SELECT id, username, password_hash, display_name, email, role, is_active, must_change_password, \
+                        created_at, updated_at, auth_source, access_expires_at FROM users WHERE id = 'u1'",
+                [],
+                super::super::user_from_row,
+            )
+            .unwrap()
+        };
+        conn.execute("UPDATE users SET password_changed_at = datetime('now', '-100 days')", []).unwrap();
+        let mut user = load(&conn);
+        assert!(!expire_if_due(&conn, &mut user).unwrap(), "no maximum age by default");
+
+        set(&conn, &AuthPolicy { password_max_age_days: Some(90), ..AuthPolicy::default() }, no_list()).unwrap();
+        assert!(expire_if_due(&conn, &mut user).unwrap());
+        assert!(user.must_change_password && load(&conn).must_change_password);
+
+        conn.execute("UPDATE users SET must_change_password = 0, auth_source = 'ldap'", []).unwrap();
+        let mut directory_user = load(&conn);
+        assert!(!expire_if_due(&conn, &mut directory_user).unwrap(), "directory passwords are the directory's");
+    }
+}
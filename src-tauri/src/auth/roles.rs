@@ -0,0 +1,670 @@
+// WP-86: roles as named sets of capabilities.
+//
+// Commands used to ask the role enum directly — `can_write()`, `can_manage()`,
+// `is_admin()` — which fixed the lab to four roles and spread the rules for
+// each one across forty command files. Now every command names the single
+// capability it needs and calls `auth::require_capability`. A role is a row
+// in `roles` plus its rows in `role_capabilities`, and admins can define new
+// ones ("media prep", "cryo custodian", "external auditor").
+//
+// Migration 064 gave the built-in roles the capability sets that reproduce
+// their old checks exactly: each capability's `baseline` records which of the
+// three old predicates guarded the commands it now covers.
+//
+// `admin` is the exception. It holds every capability, including ones added
+// by later releases, and cannot be edited or deleted, so there is always a
+// role able to repair the others.
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::models::user::UserRole;
+
+/// Which of the pre-WP-86 role checks guarded a capability's commands. Used to
+/// seed the built-in roles and shown in the role editor as a hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Baseline {
+    /// `can_write()`: admin, supervisor and tech.
+    Write,
+    /// `can_manage()`: admin and supervisor.
+    Manage,
+    /// `is_admin()`: admin only.
+    Admin,
+}
+
+impl Baseline {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Baseline::Write => "tech",
+            Baseline::Manage => "supervisor",
+            Baseline::Admin => "admin",
+        }
+    }
+}
+
+/// Everything a command can be gated on. Add a variant, give it a row in
+/// `def`, and list it in `ALL`; a migration must then grant it to whichever
+/// non-admin roles should have it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    SpecimenCreate,
+    SpecimenEdit,
+    SpecimenSplit,
+    SpecimenArchive,
+    SpecimenDelete,
+    SpecimenMerge,
+    SpecimenRestore,
+    SubcultureRecord,
+    AttachmentManage,
+    FruitingRecord,
+    ReminderEdit,
+    CryoFreeze,
+    CryoThaw,
+    CryoDiscard,
+    StrainEdit,
+    StrainCrossSpeciesOverride,
+    BreedingEdit,
+    MediaEdit,
+    MediaDelete,
+    InventoryEdit,
+    InventoryDelete,
+    LocationEdit,
+    LocationDelete,
+    SensorRecord,
+    DataImport,
+    ComplianceEdit,
+    ComplianceWaive,
+    ComplianceExport,
+    SubmissionManage,
+    SignatureSign,
+    LedgerWitness,
+    LedgerWitnessPolicy,
+    SpeciesManage,
+    TaxonomyManage,
+    TaxonomyReanchor,
+    TaxonomyNcbi,
+    RegistryExchange,
+    PassportExchange,
+    PassportConfigure,
+    CoordinationExchange,
+    AiUse,
+    AiConfigure,
+    AuditView,
+    AuditCheckpoint,
+    AnchorManage,
+    AnchorNodeConfig,
+    IntegrityCheck,
+    ErrorLogClear,
+    AnalyticsTeam,
+    AnalyticsLayout,
+    TrialManage,
+    NotificationsManage,
+    BackupCreate,
+    BackupRestore,
+    SyncView,
+    SyncManage,
+    LabProfile,
+    AccessionConfigure,
+    CustomFieldsManage,
+    StagesConfigure,
+    SystemSettings,
+    SystemBackend,
+    SystemDemoData,
+    SystemReset,
+    PluginsManage,
+    UsersView,
+    UsersManage,
+    RolesManage,
+    DirectoryManage,
+}
+
+/// One capability as the role editor shows it.
+#[derive(Debug, Clone, Serialize)]
+pub struct CapabilityInfo {
+    pub key: &'static str,
+    pub group: &'static str,
+    pub label: &'static str,
+    /// The least privileged built-in role that held it when roles were fixed.
+    pub baseline: &'static str,
+}
+
+impl Capability {
+    pub const ALL: &'static [Capability] = &[
+        Capability::SpecimenCreate,
+        Capability::SpecimenEdit,
+        Capability::SpecimenSplit,
+        Capability::SpecimenArchive,
+        Capability::SpecimenDelete,
+        Capability::SpecimenMerge,
+        Capability::SpecimenRestore,
+        Capability::SubcultureRecord,
+        Capability::AttachmentManage,
+        Capability::FruitingRecord,
+        Capability::ReminderEdit,
+        Capability::CryoFreeze,
+        Capability::CryoThaw,
+        Capability::CryoDiscard,
+        Capability::StrainEdit,
+        Capability::StrainCrossSpeciesOverride,
+        Capability::BreedingEdit,
+        Capability::MediaEdit,
+        Capability::MediaDelete,
+        Capability::InventoryEdit,
+        Capability::InventoryDelete,
+        Capability::LocationEdit,
+        Capability::LocationDelete,
+        Capability::SensorRecord,
+        Capability::DataImport,
+        Capability::ComplianceEdit,
+        Capability::ComplianceWaive,
+        Capability::ComplianceExport,
+        Capability::SubmissionManage,
+        Capability::SignatureSign,
+        Capability::LedgerWitness,
+        Capability::LedgerWitnessPolicy,
+        Capability::SpeciesManage,
+        Capability::TaxonomyManage,
+        Capability::TaxonomyReanchor,
+        Capability::TaxonomyNcbi,
+        Capability::RegistryExchange,
+        Capability::PassportExchange,
+        Capability::PassportConfigure,
+        Capability::CoordinationExchange,
+        Capability::AiUse,
+        Capability::AiConfigure,
+        Capability::AuditView,
+        Capability::AuditCheckpoint,
+        Capability::AnchorManage,
+        Capability::AnchorNodeConfig,
+        Capability::IntegrityCheck,
+        Capability::ErrorLogClear,
+        Capability::AnalyticsTeam,
+        Capability::AnalyticsLayout,
+        Capability::TrialManage,
+        Capability::NotificationsManage,
+        Capability::BackupCreate,
+        Capability::BackupRestore,
+        Capability::SyncView,
+        Capability::SyncManage,
+        Capability::LabProfile,
+        Capability::AccessionConfigure,
+        Capability::CustomFieldsManage,
+        Capability::StagesConfigure,
+        Capability::SystemSettings,
+        Capability::SystemBackend,
+        Capability::SystemDemoData,
+        Capability::SystemReset,
+        Capability::PluginsManage,
+        Capability::UsersView,
+        Capability::UsersManage,
+        Capability::RolesManage,
+        Capability::DirectoryManage,
+    ];
+
+    /// Key, group, label and baseline.
+    fn def(self) -> (&'static str, &'static str, &'static str, Baseline) {
+        use Baseline::*;
+        use Capability::*;
+        match self {
+            SpecimenCreate => ("specimen.create", "Specimens", "Create specimens", Write),
+            SpecimenEdit => ("specimen.edit", "Specimens", "Edit specimens, stages and locations", Write),
+            SpecimenSplit => ("specimen.split", "Specimens", "Split specimens", Write),
+            SpecimenArchive => ("specimen.archive", "Specimens", "Archive specimens", Manage),
+            SpecimenDelete => ("specimen.delete", "Specimens", "Delete specimens", Manage),
+            SpecimenMerge => ("specimen.merge", "Specimens", "Merge duplicate specimens", Manage),
+            SpecimenRestore => ("specimen.restore", "Specimens", "Restore archived or deleted specimens", Manage),
+            SubcultureRecord => ("subculture.record", "Specimens", "Record subcultures and deaths", Write),
+            AttachmentManage => ("attachment.manage", "Specimens", "Upload and delete attachments", Write),
+            FruitingRecord => ("fruiting.record", "Specimens", "Record fruiting", Write),
+            ReminderEdit => ("reminder.edit", "Specimens", "Create and edit reminders", Write),
+            CryoFreeze => ("cryo.freeze", "Cryopreservation", "Freeze vials", Write),
+            CryoThaw => ("cryo.thaw", "Cryopreservation", "Thaw vials", Write),
+            CryoDiscard => ("cryo.discard", "Cryopreservation", "Discard vials", Write),
+            StrainEdit => ("strain.edit", "Strains & breeding", "Create and edit strains and crosses", Write),
+            StrainCrossSpeciesOverride => {
+                ("strain.cross_species_override", "Strains & breeding", "Override cross-species hybridization checks", Admin)
+            }
+            BreedingEdit => ("breeding.edit", "Strains & breeding", "Run breeding programs", Write),
+            MediaEdit => ("media.edit", "Media & inventory", "Prepare and edit media batches", Write),
+            MediaDelete => ("media.delete", "Media & inventory", "Delete media batches", Manage),
+            InventoryEdit => ("inventory.edit", "Media & inventory", "Edit inventory and prepared solutions", Write),
+            InventoryDelete => ("inventory.delete", "Media & inventory", "Delete inventory and prepared solutions", Manage),
+            LocationEdit => ("location.edit", "Media & inventory", "Create and edit locations", Write),
+            LocationDelete => ("location.delete", "Media & inventory", "Delete locations", Manage),
+            SensorRecord => ("sensor.record", "Media & inventory", "Record environmental readings", Write),
+            DataImport => ("data.import", "Media & inventory", "Import spreadsheets", Write),
+            ComplianceEdit => ("compliance.edit", "Compliance", "Create and edit compliance records", Write),
+            ComplianceWaive => ("compliance.waive", "Compliance", "Waive and revoke compliance flags", Write),
+            ComplianceExport => ("compliance.export", "Compliance", "Generate regulatory exports", Manage),
+            SubmissionManage => ("submission.manage", "Compliance", "Manage regulatory submissions", Manage),
+            SignatureSign => ("signature.sign", "Compliance", "Sign records", Write),
+            LedgerWitness => ("ledger.witness", "Compliance", "Countersign events as a witness", Write),
+            LedgerWitnessPolicy => ("ledger.witness_policy", "Compliance", "Change witness policies", Admin),
+            SpeciesManage => ("species.manage", "Taxonomy", "Manage species", Manage),
+            TaxonomyManage => ("taxonomy.manage", "Taxonomy", "Manage taxa and provisional taxa", Manage),
+            TaxonomyReanchor => ("taxonomy.reanchor", "Taxonomy", "Re-anchor a taxon's hash chain", Admin),
+            TaxonomyNcbi => ("taxonomy.ncbi", "Taxonomy", "Import and sync NCBI taxonomy", Admin),
+            RegistryExchange => ("registry.exchange", "Exchange", "Export and import taxonomy registries", Write),
+            PassportExchange => ("passport.exchange", "Exchange", "Issue and import specimen passports", Write),
+            PassportConfigure => ("passport.configure", "Exchange", "Set the lab name on passports", Manage),
+            CoordinationExchange => ("coordination.exchange", "Exchange", "Export and import coordination bundles", Write),
+            AiUse => ("ai.use", "AI", "Use AI suggestions", Write),
+            AiConfigure => ("ai.configure", "AI", "Configure the local AI runtime", Manage),
+            AuditView => ("audit.view", "Audit & integrity", "View the audit log", Manage),
+            AuditCheckpoint => ("audit.checkpoint", "Audit & integrity", "Create audit checkpoints", Manage),
+            AnchorManage => ("anchor.manage", "Audit & integrity", "Anchor checkpoints on-chain", Manage),
+            AnchorNodeConfig => ("anchor.node_config", "Audit & integrity", "Configure the anchoring node", Admin),
+            IntegrityCheck => ("integrity.check", "Audit & integrity", "Run the data-integrity check", Admin),
+            ErrorLogClear => ("error_log.clear", "Audit & integrity", "Clear error logs", Manage),
+            AnalyticsTeam => ("analytics.team", "Analytics", "View technician activity", Manage),
+            AnalyticsLayout => ("analytics.layout", "Analytics", "Change the shared analytics layout", Manage),
+            TrialManage => ("trial.manage", "Analytics", "Design treatment trials and assign specimens", Manage),
+            NotificationsManage => ("notifications.manage", "Administration", "Review and dispatch notifications", Manage),
+            BackupCreate => ("backup.create", "Administration", "Create backups and manage backup targets", Manage),
+            BackupRestore => ("backup.restore", "Administration", "Restore from a backup", Admin),
+            SyncView => ("sync.view", "Administration", "View sync peers and conflicts", Manage),
+            SyncManage => ("sync.manage", "Administration", "Register peers and resolve sync conflicts", Admin),
+            LabProfile => ("lab.profile", "Administration", "Change the lab profile", Admin),
+            AccessionConfigure => ("accession.configure", "Administration", "Configure accession number templates", Admin),
+            CustomFieldsManage => ("custom_fields.manage", "Administration", "Define custom specimen and subculture fields", Admin),
+            StagesConfigure => ("stages.configure", "Administration", "Define allowed stage transitions and their required fields", Admin),
+            SystemSettings => ("system.settings", "Administration", "Change email and pedigree settings", Admin),
+            SystemBackend => ("system.backend", "Administration", "Configure the database backend", Admin),
+            SystemDemoData => ("system.demo_data", "Administration", "Load demo data", Manage),
+            SystemReset => ("system.reset", "Administration", "Reset the database", Admin),
+            PluginsManage => ("plugins.manage", "Administration", "Install and remove plugins", Admin),
+            UsersView => ("users.view", "Users & roles", "View users, sessions and sign-in policies", Manage),
+            UsersManage => ("users.manage", "Users & roles", "Manage accounts, role assignments, sessions and sign-in policies", Admin),
+            RolesManage => ("roles.manage", "Users & roles", "Define roles and field visibility", Admin),
+            DirectoryManage => ("directory.manage", "Users & roles", "Configure directory sign-in", Admin),
+        }
+    }
+
+    pub fn info(self) -> CapabilityInfo {
+        let (key, group, label, baseline) = self.def();
+        CapabilityInfo { key, group, label, baseline: baseline.as_str() }
+    }
+
+    pub fn key(self) -> &'static str {
+        self.def().0
+    }
+
+    pub fn label(self) -> &'static str {
+        self.def().2
+    }
+
+    pub fn baseline(self) -> Baseline {
+        self.def().3
+    }
+
+    pub fn from_key(key: &str) -> Option<Capability> {
+        Capability::ALL.iter().copied().find(|c| c.key() == key)
+    }
+}
+
+/// The capabilities a built-in role holds by baseline: migration 064's grants
+/// plus those of the migrations adding capabilities since. `admin` is implicit.
+pub fn builtin_capabilities(role: &UserRole) -> Vec<Capability> {
+    let allowed: &[Baseline] = match role {
+        UserRole::Supervisor => &[Baseline::Write, Baseline::Manage],
+        UserRole::Tech => &[Baseline::Write],
+        _ => &[],
+    };
+    Capability::ALL.iter().copied().filter(|c| allowed.contains(&c.baseline())).collect()
+}
+
+// ── Checks ──────────────────────────────────────────────────────────────────
+
+pub fn has_capability(conn: &Connection, role: &UserRole, capability: Capability) -> Result<bool, AppError> {
+    if role.is_admin() {
+        return Ok(true);
+    }
+    conn.query_row(
+        "SELECT 1 FROM role_capabilities WHERE role = ?1 AND capability = ?2",
+        params![role.as_str(), capability.key()],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|r| r.is_some())
+    .map_err(AppError::from)
+}
+
+/// Every capability a role holds, in catalogue order. Keys no longer in the
+/// catalogue are ignored.
+pub fn capabilities_of(conn: &Connection, role: &UserRole) -> Result<Vec<Capability>, AppError> {
+    if role.is_admin() {
+        return Ok(Capability::ALL.to_vec());
+    }
+    let mut stmt = conn
+        .prepare("SELECT capability FROM role_capabilities WHERE role = ?1")?;
+    let keys = stmt
+        .query_map(params![role.as_str()], |r| r.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(Capability::ALL.iter().copied().filter(|c| keys.iter().any(|k| k == c.key())).collect())
+}
+
+pub fn role_exists(conn: &Connection, name: &str) -> Result<bool, AppError> {
+    conn.query_row("SELECT 1 FROM roles WHERE name = ?1", params![name], |_| Ok(()))
+        .optional()
+        .map(|r| r.is_some())
+        .map_err(AppError::from)
+}
+
+/// Parse a role name that must exist in `roles`.
+pub fn existing_role(conn: &Connection, name: &str) -> Result<UserRole, AppError> {
+    let role: UserRole = name.parse().map_err(|_| AppError::validation("role", format!("Invalid role '{}'", name)))?;
+    if !role_exists(conn, role.as_str())? {
+        return Err(AppError::validation("role", format!("Unknown role '{}'", name)));
+    }
+    Ok(role)
+}
+
+/// Refuse to let `actor` hand out `target` unless the actor already holds
+/// every capability in it. Without this, anyone trusted to assign roles could
+/// assign themselves `admin`.
+pub fn ensure_can_grant(conn: &Connection, actor: &UserRole, target: &UserRole) -> Result<(), AppError> {
+    if actor.is_admin() {
+        return Ok(());
+    }
+    if target.is_admin() {
+        return Err(AppError::forbidden("Only admins can grant the admin role"));
+    }
+    let held = capabilities_of(conn, actor)?;
+    let missing: Vec<&str> = capabilities_of(conn, target)?
+        .into_iter()
+        .filter(|c| !held.contains(c))
+        .map(|c| c.key())
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::forbidden(format!(
+            "Role '{}' includes capabilities you do not hold: {}",
+            target.as_str(),
+            missing.join(", ")
+        )))
+    }
+}
+
+// ── Role definitions ────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleSummary {
+    pub name: String,
+    pub label: String,
+    pub description: Option<String>,
+    pub builtin: bool,
+    /// Capability keys; every key for `admin`.
+    pub capabilities: Vec<String>,
+    pub user_count: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SaveRoleRequest {
+    pub name: String,
+    pub label: String,
+    pub description: Option<String>,
+    pub capabilities: Vec<String>,
+}
+
+pub fn list_roles(conn: &Connection) -> Result<Vec<RoleSummary>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.name, r.label, r.description, r.builtin,
+                    (SELECT COUNT(*) FROM users u WHERE u.role = r.name)
+             FROM roles r ORDER BY r.builtin DESC, r.label",
+        )?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, Option<String>>(2)?,
+                r.get::<_, i64>(3)? != 0,
+                r.get::<_, i64>(4)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    rows.into_iter()
+        .map(|(name, label, description, builtin, user_count)| {
+            let role: UserRole = name.parse().map_err(|_| AppError::internal(format!("Invalid role '{}' in roles table", name)))?;
+            let capabilities = capabilities_of(conn, &role)?.into_iter().map(|c| c.key().to_string()).collect();
+            Ok(RoleSummary { name, label, description, builtin, capabilities, user_count })
+        })
+        .collect()
+}
+
+fn parse_capabilities(keys: &[String]) -> Result<Vec<Capability>, AppError> {
+    let mut caps = Vec::new();
+    for key in keys {
+        let cap = Capability::from_key(key).ok_or_else(|| AppError::validation("capabilities", format!("Unknown capability '{}'", key)))?;
+        if !caps.contains(&cap) {
+            caps.push(cap);
+        }
+    }
+    Ok(caps)
+}
+
+fn write_capabilities(conn: &Connection, role: &str, caps: &[Capability]) -> Result<(), AppError> {
+    conn.execute("DELETE FROM role_capabilities WHERE role = ?1", params![role])?;
+    for cap in caps {
+        conn.execute(
+            "INSERT INTO role_capabilities (role, capability) VALUES (?1, ?2)",
+            params![role, cap.key()],
+        )?;
+    }
+    Ok(())
+}
+
+/// Reject capabilities the actor does not hold, for the same reason as
+/// [`ensure_can_grant`].
+fn ensure_holds(conn: &Connection, actor: &UserRole, caps: &[Capability]) -> Result<(), AppError> {
+    let held = capabilities_of(conn, actor)?;
+    match caps.iter().find(|c| !held.contains(c)) {
+        Some(c) => Err(AppError::forbidden(format!("You cannot grant '{}', which your own role does not include", c.key()))),
+        None => Ok(()),
+    }
+}
+
+pub fn create_role(conn: &Connection, actor: &UserRole, req: &SaveRoleRequest) -> Result<(), AppError> {
+    let name = req.name.trim();
+    let role: UserRole = name
+        .parse()
+        .map_err(|_| AppError::validation("name", "Role names are 2–32 lowercase letters, digits, '_' or '-', starting with a letter"))?;
+    if !matches!(role, UserRole::Custom(_)) || role_exists(conn, name)? {
+        return Err(AppError::conflict(format!("A role named '{}' already exists", name)));
+    }
+    if req.label.trim().is_empty() {
+        return Err(AppError::validation("label", "A role needs a label"));
+    }
+    let caps = parse_capabilities(&req.capabilities)?;
+    ensure_holds(conn, actor, &caps)?;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO roles (name, label, description, builtin) VALUES (?1, ?2, ?3, 0)",
+        params![name, req.label.trim(), req.description.as_deref().map(str::trim).filter(|d| !d.is_empty())],
+    )?;
+    write_capabilities(&tx, name, &caps)?;
+    tx.commit().map_err(AppError::from)
+}
+
+/// Replace a role's label, description and capabilities. Returns the
+/// capability keys it held before, for the audit entry.
+pub fn update_role(conn: &Connection, actor: &UserRole, req: &SaveRoleRequest) -> Result<Vec<String>, AppError> {
+    let role = existing_role(conn, req.name.trim())?;
+    if role.is_admin() {
+        return Err(AppError::invalid("The admin role always holds every capability and cannot be edited"));
+    }
+    if req.label.trim().is_empty() {
+        return Err(AppError::validation("label", "A role needs a label"));
+    }
+    if role == *actor {
+        return Err(AppError::forbidden("You cannot change the capabilities of your own role"));
+    }
+    let caps = parse_capabilities(&req.capabilities)?;
+    let before = capabilities_of(conn, &role)?;
+    // Removing a capability is a grant in reverse: only someone who holds it
+    // may take it away.
+    let changed: Vec<Capability> = caps
+        .iter()
+        .filter(|c| !before.contains(c))
+        .chain(before.iter().filter(|c| !caps.contains(c)))
+        .copied()
+        .collect();
+    ensure_holds(conn, actor, &changed)?;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE roles SET label = ?2, description = ?3, updated_at = datetime('now') WHERE name = ?1",
+        params![
+            role.as_str(),
+            req.label.trim(),
+            req.description.as_deref().map(str::trim).filter(|d| !d.is_empty())
+        ],
+    )?;
+    write_capabilities(&tx, role.as_str(), &caps)?;
+    tx.commit()?;
+    Ok(before.into_iter().map(|c| c.key().to_string()).collect())
+}
+
+/// Delete an admin-defined role that nobody holds. Its field-visibility rules
+/// and two-factor policy go with it.
+pub fn delete_role(conn: &Connection, actor: &UserRole, name: &str) -> Result<(), AppError> {
+    let role = existing_role(conn, name)?;
+    if !matches!(role, UserRole::Custom(_)) {
+        return Err(AppError::invalid("Built-in roles cannot be deleted"));
+    }
+    ensure_can_grant(conn, actor, &role)?;
+    let holders: i64 = conn
+        .query_row("SELECT COUNT(*) FROM users WHERE role = ?1", params![name], |r| r.get(0))?;
+    if holders > 0 {
+        return Err(AppError::conflict(format!("{} user(s) still have this role. Assign them another role first.", holders)));
+    }
+    let mapped: i64 = conn
+        .query_row("SELECT COUNT(*) FROM ldap_group_roles WHERE role = ?1", params![name], |r| r.get(0))?;
+    if mapped > 0 {
+        return Err(AppError::conflict("A directory group is mapped to this role. Remove the mapping first."));
+    }
+    let is_default: i64 = conn
+        .query_row("SELECT COUNT(*) FROM ldap_config WHERE default_role = ?1", params![name], |r| r.get(0))?;
+    if is_default > 0 {
+        return Err(AppError::conflict("This is the directory's default role. Choose another default first."));
+    }
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM mfa_policy WHERE role = ?1", params![name])?;
+    tx.execute("DELETE FROM roles WHERE name = ?1", params![name])?;
+    tx.commit().map_err(AppError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        run_all(&conn).unwrap();
+        conn
+    }
+
+    fn custom(name: &str) -> UserRole {
+        name.parse().unwrap()
+    }
+
+    fn request(name: &str, caps: &[&str]) -> SaveRoleRequest {
+        SaveRoleRequest {
+            name: name.to_string(),
+            label: name.to_string(),
+            description: None,
+            capabilities: caps.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn catalogue_keys_are_unique_and_round_trip() {
+        for (i, cap) in Capability::ALL.iter().enumerate() {
+            assert_eq!(Capability::from_key(cap.key()), Some(*cap));
+            assert!(Capability::ALL[i + 1..].iter().all(|c| c.key() != cap.key()), "duplicate key {}", cap.key());
+        }
+    }
+
+    #[test]
+    fn builtin_roles_reproduce_the_old_role_checks() {
+        // Migration 064's grants are frozen, so this also fails until a new
+        // capability's own migration grants it.
+        let conn = db();
+        for cap in Capability::ALL {
+            let (write, manage, admin) = match cap.baseline() {
+                Baseline::Write => (true, true, true),
+                Baseline::Manage => (false, true, true),
+                Baseline::Admin => (false, false, true),
+            };
+            assert_eq!(has_capability(&conn, &UserRole::Tech, *cap).unwrap(), write, "tech / {}", cap.key());
+            assert_eq!(has_capability(&conn, &UserRole::Supervisor, *cap).unwrap(), manage, "supervisor / {}", cap.key());
+            assert_eq!(has_capability(&conn, &UserRole::Admin, *cap).unwrap(), admin, "admin / {}", cap.key());
+            assert!(!has_capability(&conn, &UserRole::Guest, *cap).unwrap(), "guest / {}", cap.key());
+        }
+    }
+
+    #[test]
+    fn custom_roles_hold_exactly_their_capabilities() {
+        let conn = db();
+        create_role(&conn, &UserRole::Admin, &request("cryo_custodian", &["cryo.freeze", "cryo.thaw"])).unwrap();
+        let role = custom("cryo_custodian");
+        assert!(has_capability(&conn, &role, Capability::CryoThaw).unwrap());
+        assert!(!has_capability(&conn, &role, Capability::CryoDiscard).unwrap());
+        assert!(!has_capability(&conn, &role, Capability::SpecimenEdit).unwrap());
+
+        update_role(&conn, &UserRole::Admin, &request("cryo_custodian", &["cryo.discard"])).unwrap();
+        assert_eq!(capabilities_of(&conn, &role).unwrap(), vec![Capability::CryoDiscard]);
+
+        assert!(create_role(&conn, &UserRole::Admin, &request("cryo_custodian", &[])).is_err(), "duplicate");
+        assert!(create_role(&conn, &UserRole::Admin, &request("tech", &[])).is_err(), "built-in name");
+        assert!(create_role(&conn, &UserRole::Admin, &request("Bad Name", &[])).is_err());
+        assert!(create_role(&conn, &UserRole::Admin, &request("x_role", &["cryo.melt"])).is_err());
+        assert!(update_role(&conn, &UserRole::Admin, &request("admin", &[])).is_err());
+    }
+
+    #[test]
+    fn nobody_can_grant_what_they_do_not_hold() {
+        let conn = db();
+        create_role(&conn, &UserRole::Admin, &request("user_admin", &["users.manage", "roles.manage", "cryo.thaw"])).unwrap();
+        let actor = custom("user_admin");
+
+        assert!(ensure_can_grant(&conn, &actor, &UserRole::Admin).is_err());
+        assert!(ensure_can_grant(&conn, &actor, &UserRole::Tech).is_err(), "tech holds far more than cryo.thaw");
+        assert!(ensure_can_grant(&conn, &actor, &UserRole::Guest).is_ok());
+
+        assert!(create_role(&conn, &actor, &request("thawer", &["cryo.thaw"])).is_ok());
+        assert!(create_role(&conn, &actor, &request("splitter", &["specimen.split"])).is_err());
+        assert!(update_role(&conn, &actor, &request("thawer", &["cryo.thaw", "cryo.discard"])).is_err());
+        assert!(update_role(&conn, &actor, &request("user_admin", &[])).is_err(), "own role");
+        // Taking away a capability the actor lacks is refused too.
+        assert!(update_role(&conn, &actor, &request("supervisor", &[])).is_err());
+    }
+
+    #[test]
+    fn roles_in_use_cannot_be_deleted() {
+        let conn = db();
+        create_role(&conn, &UserRole::Admin, &request("auditor", &["audit.view"])).unwrap();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'aud', 'x', 'Aud', 'auditor')",
+            [],
+        )
+        .unwrap();
+        assert!(delete_role(&conn, &UserRole::Admin, "auditor").unwrap_err().message().contains("still have this role"));
+        assert!(delete_role(&conn, &UserRole::Admin, "tech").is_err());
+
+        conn.execute("UPDATE users SET role = 'guest' WHERE id = 'u1'", []).unwrap();
+        conn.execute("UPDATE ldap_config SET default_role = 'auditor'", []).unwrap();
+        assert!(delete_role(&conn, &UserRole::Admin, "auditor").unwrap_err().message().contains("default role"));
+
+        conn.execute("UPDATE ldap_config SET default_role = NULL", []).unwrap();
+        delete_role(&conn, &UserRole::Admin, "auditor").unwrap();
+        assert!(!role_exists(&conn, "auditor").unwrap());
+        let orphaned: i64 = conn
+            .query_row("SELECT COUNT(*) FROM role_capabilities WHERE role = 'auditor'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(orphaned, 0);
+    }
+}
@@ -0,0 +1,360 @@
+// WP-88: session metadata, remote revocation and per-role timeouts.
+//
+// Every session row now records the device it was opened on and when it was
+// last used, so an administrator can see who is signed in where and end a
+// single session — the one on the tablet left in the growth room — without
+// touching the account.
+//
+// `session_policy` adds two optional limits per role, enforced by
+// `auth::validate_session` on every command:
+//
+// * **idle** — the session ends when it has not been used for that many
+//   minutes;
+// * **absolute** — the session ends that many hours after sign-in, however
+//   busy it is. The 24-hour session lifetime remains the ceiling.
+//
+// A role without a row, or with a limit left empty, has no limit of that kind.
+// Revocation is a DELETE: a revoked token is indistinguishable from an expired
+// one, so the client's existing "session expired" handling signs it out.
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Longest device label kept; the rest is cut off.
+pub const MAX_DEVICE_LEN: usize = 120;
+pub const MIN_IDLE_MINUTES: i64 = 5;
+pub const MAX_IDLE_MINUTES: i64 = 24 * 60;
+pub const MIN_ABSOLUTE_HOURS: i64 = 1;
+/// Sessions are created with a 24-hour expiry, so a longer limit would never
+/// apply.
+pub const MAX_ABSOLUTE_HOURS: i64 = 24;
+
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub user_id: String,
+    pub username: String,
+    pub display_name: String,
+    pub role: String,
+    pub device: Option<String>,
+    pub created_at: String,
+    pub last_seen_at: Option<String>,
+    pub expires_at: String,
+    /// Password accepted, second factor not yet given.
+    pub mfa_pending: bool,
+    /// The session making the request.
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionPolicy {
+    pub role: String,
+    pub idle_minutes: Option<i64>,
+    pub absolute_hours: Option<i64>,
+}
+
+/// Why `auth::validate_session` ended a session that was otherwise valid.
+#[derive(Debug, PartialEq)]
+pub enum Timeout {
+    Idle { minutes: i64 },
+    Absolute { hours: i64 },
+}
+
+impl Timeout {
+    /// Returned as `unauthenticated` with reason `session_expired` (WP-93), so
+    /// the client treats it like any other expired token.
+    pub fn message(&self) -> String {
+        match self {
+            Timeout::Idle { minutes } => {
+                format!("Session expired after {} minute(s) of inactivity. Sign in again.", minutes)
+            }
+            Timeout::Absolute { hours } => {
+                format!("Session expired: your role allows {} hour(s) per sign-in. Sign in again.", hours)
+            }
+        }
+    }
+}
+
+/// Clean up a client-supplied device description: control characters
+/// removed, whitespace collapsed, cut to [`MAX_DEVICE_LEN`] characters.
+/// Blank input gives `None`.
+pub fn device_label(raw: Option<&str>) -> Option<String> {
+    let cleaned: String = raw?
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let cut: String = cleaned.chars().take(MAX_DEVICE_LEN).collect();
+    (!cut.is_empty()).then_some(cut)
+}
+
+/// The limit, if any, that the session with this token digest has run past.
+/// Pending (second-factor) sessions are covered too.
+pub fn timeout_for(conn: &Connection, token_hash: &str) -> Result<Option<Timeout>, AppError> {
+    let row: Option<(Option<i64>, Option<i64>, bool, bool)> = conn
+        .query_row(
+            "SELECT p.idle_minutes, p.absolute_hours,
+                    p.idle_minutes IS NOT NULL
+                        AND COALESCE(s.last_seen_at, s.created_at) <= datetime('now', '-' || p.idle_minutes || ' minutes'),
+                    p.absolute_hours IS NOT NULL
+                        AND s.created_at <= datetime('now', '-' || p.absolute_hours || ' hours')
+             FROM sessions s JOIN users u ON s.user_id = u.id
+             JOIN session_policy p ON p.role = u.role
+             WHERE s.token = ?1",
+            params![token_hash],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )
+        .optional()?;
+    Ok(match row {
+        Some((_, Some(hours), _, true)) => Some(Timeout::Absolute { hours }),
+        Some((Some(minutes), _, true, _)) => Some(Timeout::Idle { minutes }),
+        _ => None,
+    })
+}
+
+/// Record that the session was just used.
+pub fn touch(conn: &Connection, token_hash: &str) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE sessions SET last_seen_at = datetime('now') WHERE token = ?1",
+        params![token_hash],
+    )?;
+    Ok(())
+}
+
+/// Every unexpired session, most recently used first. `current_token_hash`
+/// marks the caller's own.
+pub fn list(conn: &Connection, current_token_hash: &str) -> Result<Vec<SessionInfo>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.user_id, u.username, u.display_name, u.role, s.device, s.created_at,
+                    s.last_seen_at, s.expires_at, s.mfa_pending, s.token = ?1
+             FROM sessions s JOIN users u ON s.user_id = u.id
+             WHERE s.expires_at > datetime('now')
+             ORDER BY COALESCE(s.last_seen_at, s.created_at) DESC, s.id",
+        )?;
+    let rows = stmt
+        .query_map(params![current_token_hash], |r| {
+            Ok(SessionInfo {
+                id: r.get(0)?,
+                user_id: r.get(1)?,
+                username: r.get(2)?,
+                display_name: r.get(3)?,
+                role: r.get(4)?,
+                device: r.get(5)?,
+                created_at: r.get(6)?,
+                last_seen_at: r.get(7)?,
+                expires_at: r.get(8)?,
+                mfa_pending: r.get::<_, i64>(9)? != 0,
+                current: r.get(10)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// The owner of a session and its device, for the checks and audit entry
+/// that precede a revocation.
+pub fn owner(conn: &Connection, session_id: &str) -> Result<(String, Option<String>), AppError> {
+    conn.query_row(
+        "SELECT user_id, device FROM sessions WHERE id = ?1",
+        params![session_id],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::not_found("session", "Session not found — it may already have ended.").with_id(session_id))
+}
+
+/// End one session. Returns whether it existed.
+pub fn revoke(conn: &Connection, session_id: &str) -> Result<bool, AppError> {
+    conn.execute("DELETE FROM sessions WHERE id = ?1", params![session_id])
+        .map(|n| n > 0)
+        .map_err(|e| AppError::internal(format!("Failed to revoke session: {}", e)))
+}
+
+/// End every session of a user, except the one whose token digest is
+/// `keep_token_hash`. Returns how many ended.
+pub fn revoke_all_for_user(conn: &Connection, user_id: &str, keep_token_hash: Option<&str>) -> Result<usize, AppError> {
+    conn.execute(
+        "DELETE FROM sessions WHERE user_id = ?1 AND (?2 IS NULL OR token <> ?2)",
+        params![user_id, keep_token_hash],
+    )
+    .map_err(|e| AppError::internal(format!("Failed to revoke sessions: {}", e)))
+}
+
+pub fn policy_for(conn: &Connection, role: &str) -> Result<SessionPolicy, AppError> {
+    conn.query_row(
+        "SELECT idle_minutes, absolute_hours FROM session_policy WHERE role = ?1",
+        params![role],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .optional()
+    .map(|row| {
+        let (idle_minutes, absolute_hours) = row.unwrap_or((None, None));
+        SessionPolicy { role: role.to_string(), idle_minutes, absolute_hours }
+    }).map_err(AppError::from)
+}
+
+pub fn list_policy(conn: &Connection) -> Result<Vec<SessionPolicy>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.name, p.idle_minutes, p.absolute_hours FROM roles r \
+             LEFT JOIN session_policy p ON p.role = r.name ORDER BY r.builtin DESC, r.name",
+        )?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok(SessionPolicy { role: r.get(0)?, idle_minutes: r.get(1)?, absolute_hours: r.get(2)? })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// Check both limits are in range. `None` means no limit.
+pub fn validate_policy(idle_minutes: Option<i64>, absolute_hours: Option<i64>) -> Result<(), AppError> {
+    if let Some(m) = idle_minutes {
+        if !(MIN_IDLE_MINUTES..=MAX_IDLE_MINUTES).contains(&m) {
+            return Err(AppError::validation("idle_minutes", format!(
+                "The idle timeout must be between {} and {} minutes.",
+                MIN_IDLE_MINUTES, MAX_IDLE_MINUTES
+            )));
+        }
+    }
+    if let Some(h) = absolute_hours {
+        if !(MIN_ABSOLUTE_HOURS..=MAX_ABSOLUTE_HOURS).contains(&h) {
+            return Err(AppError::validation("absolute_hours", format!(
+                "The absolute timeout must be between {} and {} hours.",
+                MIN_ABSOLUTE_HOURS, MAX_ABSOLUTE_HOURS
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn set_policy(conn: &Connection, policy: &SessionPolicy) -> Result<(), AppError> {
+    validate_policy(policy.idle_minutes, policy.absolute_hours)?;
+    conn.execute(
+        "INSERT INTO session_policy (role, idle_minutes, absolute_hours, updated_at) \
+         VALUES (?1, ?2, ?3, datetime('now')) \
+         ON CONFLICT(role) DO UPDATE SET idle_minutes = excluded.idle_minutes, \
+             absolute_hours = excluded.absolute_hours, updated_at = excluded.updated_at",
+        params![policy.role, policy.idle_minutes, policy.absolute_hours],
+    )?;
+    Ok(())
+}
+
+/// "30 min idle, 8 h absolute", for audit entries.
+pub fn describe_policy(policy: &SessionPolicy) -> String {
+    let idle = policy.idle_minutes.map_or("no idle limit".to_string(), |m| format!("{} min idle", m));
+    let absolute = policy
+        .absolute_hours
+        .map_or("no absolute limit".to_string(), |h| format!("{} h absolute", h));
+    format!("{}, {}", idle, absolute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        run_all(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'tech1', 'x', 'T', 'tech')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn session(conn: &Connection, id: &str, created: &str, last_seen: Option<&str>) {
+        conn.execute(
+            &format!(
+                "INSERT INTO sessions (id, user_id, token, created_at, last_seen_at, expires_at) \
+                 VALUES (?1, 'u1', ?1, datetime('now', '{}'), {}, datetime('now', '+1 day'))",
+                created,
+                last_seen.map_or("NULL".to_string(), |m| format!("datetime('now', '{}')", m)),
+            ),
+            params![id],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn device_labels_are_cleaned_and_bounded() {
+        assert_eq!(device_label(Some("  Lab   tablet\n(Android)\t")), Some("Lab tablet (Android)".to_string()));
+        assert_eq!(device_label(Some(" \u{7}  ")), None);
+        assert_eq!(device_label(None), None);
+        assert_eq!(device_label(Some(&"x".repeat(500))).unwrap().chars().count(), MAX_DEVICE_LEN);
+    }
+
+    #[test]
+    fn idle_and_absolute_limits_follow_the_role_policy() {
+        let conn = db();
+        session(&conn, "fresh", "-10 minutes", Some("-1 minute"));
+        session(&conn, "idle", "-2 hours", Some("-45 minutes"));
+        session(&conn, "old", "-9 hours", Some("-1 minute"));
+        session(&conn, "never_used", "-40 minutes", None);
+
+        // No policy row: nothing times out.
+        for id in ["fresh", "idle", "old", "never_used"] {
+            assert_eq!(timeout_for(&conn, id).unwrap(), None, "{}", id);
+        }
+
+        set_policy(&conn, &SessionPolicy { role: "tech".into(), idle_minutes: Some(30), absolute_hours: Some(8) })
+            .unwrap();
+        assert_eq!(timeout_for(&conn, "fresh").unwrap(), None);
+        assert_eq!(timeout_for(&conn, "idle").unwrap(), Some(Timeout::Idle { minutes: 30 }));
+        assert_eq!(timeout_for(&conn, "old").unwrap(), Some(Timeout::Absolute { hours: 8 }));
+        assert_eq!(
+            timeout_for(&conn, "never_used").unwrap(),
+            Some(Timeout::Idle { minutes: 30 }),
+            "an unused session is idle since sign-in"
+        );
+
+        touch(&conn, "idle").unwrap();
+        assert_eq!(timeout_for(&conn, "idle").unwrap(), None, "use resets the idle clock");
+        assert!(Timeout::Idle { minutes: 30 }.message().starts_with("Session expired"));
+        assert!(Timeout::Absolute { hours: 8 }.message().starts_with("Session expired"));
+    }
+
+    #[test]
+    fn policy_limits_are_range_checked_and_listed_for_every_role() {
+        let conn = db();
+        let bad = |idle, abs| set_policy(&conn, &SessionPolicy { role: "tech".into(), idle_minutes: idle, absolute_hours: abs });
+        assert!(bad(Some(1), None).is_err());
+        assert!(bad(None, Some(48)).is_err());
+        assert!(bad(None, Some(0)).is_err());
+        bad(Some(15), None).unwrap();
+
+        let all = list_policy(&conn).unwrap();
+        assert_eq!(all.len(), 4);
+        assert!(all.iter().any(|p| p.role == "tech" && p.idle_minutes == Some(15) && p.absolute_hours.is_none()));
+        assert!(all.iter().filter(|p| p.role != "tech").all(|p| p.idle_minutes.is_none() && p.absolute_hours.is_none()));
+        assert_eq!(policy_for(&conn, "guest").unwrap().idle_minutes, None);
+    }
+
+    #[test]
+    fn revocation_ends_one_session_or_all_but_the_kept_one() {
+        let conn = db();
+        for id in ["a", "b", "c"] {
+            session(&conn, id, "-1 minute", None);
+        }
+        let listed = list(&conn, "b").unwrap();
+        assert_eq!(listed.len(), 3);
+        assert!(listed.iter().all(|s| s.current == (s.id == "b") && s.username == "tech1"));
+
+        assert_eq!(owner(&conn, "a").unwrap().0, "u1");
+        assert!(revoke(&conn, "a").unwrap());
+        assert!(!revoke(&conn, "a").unwrap());
+        assert!(owner(&conn, "a").is_err());
+
+        assert_eq!(revoke_all_for_user(&conn, "u1", Some("b")).unwrap(), 1);
+        assert_eq!(list(&conn, "b").unwrap().len(), 1);
+        assert_eq!(revoke_all_for_user(&conn, "u1", None).unwrap(), 1);
+        assert!(list(&conn, "b").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,484 @@
+// WP-84: TOTP second factor (RFC 6238).
+//
+// A password alone is a weak proof on a shared bench machine: it is typed in
+// view of others and reused across systems. A user who enrolls an
+// authenticator app must also give the current six-digit code (HMAC-SHA1,
+// 30-second steps, one step of clock skew either way) or one of ten single-use
+// recovery codes.
+//
+// The TOTP secret is needed in the clear to check a code, so it cannot be
+// hashed like a password. It is encrypted with AES-256-GCM (`cloud::crypto`)
+// under a random per-installation key kept in `totp.key` beside the database,
+// not in it: a copy of the database file — a backup, a support bundle — does
+// not yield anyone's second factor. Recovery codes are 80-bit random strings
+// and are stored as SHA-256 digests, like session tokens.
+//
+// The login half lives in `auth`: `create_session` marks the session of an
+// enrolled user `mfa_pending` with a five-minute expiry, `validate_session`
+// refuses pending sessions, and `auth::complete_mfa` clears the flag once a
+// code checks out. A per-role policy (`mfa_policy`) can require enrollment;
+// an unenrolled user in such a role can then only reach the enrollment
+// commands.
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+
+pub const ISSUER: &str = "SteloPTC";
+pub const DIGITS: u32 = 6;
+pub const STEP_SECS: i64 = 30;
+/// Steps accepted either side of now, for clock drift between phone and PC.
+pub const SKEW_STEPS: i64 = 1;
+pub const SECRET_LEN: usize = 20;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+/// How long a password-verified session may wait for its second factor.
+pub const PENDING_SESSION_MINUTES: i64 = 5;
+
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Debug, Serialize)]
+pub struct MfaStatus {
+    pub enrolled: bool,
+    /// The user's role requires 2FA (`mfa_policy`).
+    pub required_by_policy: bool,
+    /// Required but not yet enrolled: only the enrollment commands will work.
+    pub enrollment_required: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpEnrollment {
+    /// Base32, for typing into an authenticator app by hand.
+    pub secret: String,
+    /// `otpauth://` URI, rendered as a QR code by the frontend.
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MfaPolicy {
+    pub role: String,
+    pub required: bool,
+}
+
+/// Which second factor a login used, for the audit entry.
+#[derive(Debug, PartialEq)]
+pub enum SecondFactor {
+    Totp,
+    RecoveryCode { remaining: i64 },
+}
+
+impl SecondFactor {
+    pub fn describe(&self) -> String {
+        match self {
+            SecondFactor::Totp => "Second factor: authenticator code".to_string(),
+            SecondFactor::RecoveryCode { remaining } => {
+                format!("Second factor: recovery code ({} remaining)", remaining)
+            }
+        }
+    }
+}
+
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for &b in bytes {
+        buffer = (buffer << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            out.push(BASE32[((buffer >> (bits - 5)) & 31) as usize] as char);
+            bits -= 5;
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+/// Decode unpadded base32, ignoring case, spaces and `=` padding.
+pub fn base32_decode(s: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in s.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let v = BASE32
+            .iter()
+            .position(|&b| b as char == c.to_ascii_uppercase())
+            .ok_or_else(|| format!("Invalid base32 character: '{}'", c))? as u32;
+        buffer = (buffer << 5) | v;
+        bits += 5;
+        if bits >= 8 {
+            out.push((buffer >> (bits - 8)) as u8);
+            bits -= 8;
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    Ok(out)
+}
+
+/// RFC 4226 HOTP value for one counter.
+pub fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes(digest[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+    binary % 10u32.pow(DIGITS)
+}
+
+pub fn step_at(unix_secs: i64) -> i64 {
+    unix_secs.div_euclid(STEP_SECS)
+}
+
+/// The step a code matches within the skew window, skipping steps at or
+/// before `last_used_step` so a code seen over someone's shoulder cannot be
+/// replayed.
+pub fn matching_step(secret: &[u8], code: &str, unix_secs: i64, last_used_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value: u32 = code.parse().ok()?;
+    let now = step_at(unix_secs);
+    (now - SKEW_STEPS..=now + SKEW_STEPS)
+        .filter(|s| *s >= 0 && last_used_step.is_none_or(|last| *s > last))
+        .find(|s| hotp(secret, *s as u64) == value)
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn provisioning_uri(username: &str, secret_b32: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{user}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECS}",
+        issuer = percent_encode(ISSUER),
+        user = percent_encode(username),
+        secret = secret_b32,
+    )
+}
+
+// ── Secret encryption ───────────────────────────────────────────────────────
+
+/// The per-installation key that encrypts TOTP secrets, created on first use
+/// as `totp.key` next to `db`'s file (owner-only on Unix). Losing it — e.g.
+/// restoring a backup onto a new machine — leaves enrolled users with their
+/// recovery codes; an admin can then reset their enrollment.
+pub fn installation_key(db: &crate::db::Database) -> Result<[u8; 32], AppError> {
+    let path = db.beside("totp.key");
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, existing.trim())
+            .map_err(|e| AppError::integrity(format!("{} is corrupt: {}", path.display(), e)))?;
+        return bytes.try_into().map_err(|_| AppError::integrity(format!("{} is not a 32-byte key", path.display())));
+    }
+    let mut key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    std::fs::write(&path, base64::Engine::encode(&base64::engine::general_purpose::STANDARD, key))
+        .map_err(|e| AppError::internal(format!("Failed to write {}: {}", path.display(), e)))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).ok();
+    }
+    Ok(key)
+}
+
+fn encrypt_secret(key: &[u8; 32], secret: &[u8]) -> Result<String, AppError> {
+    let blob = crate::cloud::crypto::encrypt(key, secret)?;
+    Ok(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, blob))
+}
+
+fn decrypt_secret(key: &[u8; 32], stored: &str) -> Result<Vec<u8>, AppError> {
+    let blob = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, stored)
+        .map_err(|e| AppError::integrity(format!("Stored TOTP secret is corrupt: {}", e)))?;
+    crate::cloud::crypto::decrypt(key, &blob).map_err(|_| {
+        AppError::integrity(
+            "This installation cannot decrypt your authenticator secret (was the database moved to another \
+             machine?). Sign in with a recovery code, or ask an admin to reset your two-factor enrollment.",
+        )
+    })
+}
+
+// ── Enrollment ──────────────────────────────────────────────────────────────
+
+pub fn is_enrolled(conn: &Connection, user_id: &str) -> Result<bool, AppError> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM user_totp WHERE user_id = ?1 AND enabled = 1",
+        params![user_id],
+        |r| r.get::<_, i64>(0),
+    )
+    .map(|n| n > 0)
+    .map_err(AppError::from)
+}
+
+/// Start (or restart) enrollment with a fresh secret. The row stays disabled
+/// until `confirm_enrollment` sees a valid code from it.
+pub fn begin_enrollment(conn: &Connection, key: &[u8; 32], user_id: &str, username: &str) -> Result<TotpEnrollment, AppError> {
+    if is_enrolled(conn, user_id)? {
+        return Err(AppError::conflict("Two-factor authentication is already enabled. Disable it first to enroll a new device."));
+    }
+    let mut secret = [0u8; SECRET_LEN];
+    rand::thread_rng().fill_bytes(&mut secret);
+    conn.execute(
+        "INSERT INTO user_totp (user_id, secret_enc, enabled) VALUES (?1, ?2, 0) \
+         ON CONFLICT(user_id) DO UPDATE SET secret_enc = excluded.secret_enc, enabled = 0, \
+             last_used_step = NULL, created_at = datetime('now'), confirmed_at = NULL",
+        params![user_id, encrypt_secret(key, &secret)?],
+    )?;
+    let b32 = base32_encode(&secret);
+    Ok(TotpEnrollment { provisioning_uri: provisioning_uri(username, &b32), secret: b32 })
+}
+
+/// Turn on 2FA once the user proves their app produces the right codes, and
+/// issue the first set of recovery codes.
+pub fn confirm_enrollment(conn: &Connection, key: &[u8; 32], user_id: &str, code: &str, unix_secs: i64) -> Result<Vec<String>, AppError> {
+    let (stored, enabled): (String, bool) = conn
+        .query_row(
+            "SELECT secret_enc, enabled FROM user_totp WHERE user_id = ?1",
+            params![user_id],
+            |r| Ok((r.get(0)?, r.get::<_, i64>(1)? != 0)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::invalid("Start enrollment first."))?;
+    if enabled {
+        return Err(AppError::conflict("Two-factor authentication is already enabled."));
+    }
+    let secret = decrypt_secret(key, &stored)?;
+    let step = matching_step(&secret, code, unix_secs, None)
+        .ok_or_else(|| AppError::validation("code", "That code does not match. Check the time on your phone and try the current code."))?;
+    conn.execute(
+        "UPDATE user_totp SET enabled = 1, last_used_step = ?1, confirmed_at = datetime('now') WHERE user_id = ?2",
+        params![step, user_id],
+    )?;
+    regenerate_recovery_codes(conn, user_id)
+}
+
+/// Remove a user's 2FA entirely (self-service disable, or an admin reset).
+pub fn remove(conn: &Connection, user_id: &str) -> Result<bool, AppError> {
+    conn.execute("DELETE FROM user_recovery_codes WHERE user_id = ?1", params![user_id])?;
+    let n = conn
+        .execute("DELETE FROM user_totp WHERE user_id = ?1", params![user_id])?;
+    Ok(n > 0)
+}
+
+// ── Recovery codes ──────────────────────────────────────────────────────────
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    crate::anchoring::hex_encode(&Sha256::digest(normalize_recovery_code(code).as_bytes()))
+}
+
+/// Replace all of a user's recovery codes with fresh ones, returned once in
+/// the clear as `xxxx-xxxx-xxxx-xxxx`.
+pub fn regenerate_recovery_codes(conn: &Connection, user_id: &str) -> Result<Vec<String>, AppError> {
+    conn.execute("DELETE FROM user_recovery_codes WHERE user_id = ?1", params![user_id])?;
+    let mut codes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+    for _ in 0..RECOVERY_CODE_COUNT {
+        let mut raw = [0u8; 10];
+        rand::thread_rng().fill_bytes(&mut raw);
+        let b32 = base32_encode(&raw).to_ascii_lowercase();
+        let code = format!("{}-{}-{}-{}", &b32[0..4], &b32[4..8], &b32[8..12], &b32[12..16]);
+        conn.execute(
+            "INSERT INTO user_recovery_codes (id, user_id, code_hash) VALUES (?1, ?2, ?3)",
+            params![uuid::Uuid::new_v4().to_string(), user_id, hash_recovery_code(&code)],
+        )?;
+        codes.push(code);
+    }
+    Ok(codes)
+}
+
+pub fn recovery_codes_remaining(conn: &Connection, user_id: &str) -> Result<i64, AppError> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM user_recovery_codes WHERE user_id = ?1 AND used_at IS NULL",
+        params![user_id],
+        |r| r.get(0),
+    ).map_err(AppError::from)
+}
+
+/// Check a second factor for an enrolled user: a six-digit code from the
+/// authenticator, or an unused recovery code (which is then spent).
+pub fn verify_second_factor(conn: &Connection, key: &[u8; 32], user_id: &str, code: &str, unix_secs: i64) -> Result<SecondFactor, AppError> {
+    const WRONG: &str = "Invalid authentication code";
+    let code = code.trim();
+    if code.len() == DIGITS as usize && code.bytes().all(|b| b.is_ascii_digit()) {
+        let (stored, last): (String, Option<i64>) = conn
+            .query_row(
+                "SELECT secret_enc, last_used_step FROM user_totp WHERE user_id = ?1 AND enabled = 1",
+                params![user_id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .map_err(|_| AppError::invalid("Two-factor authentication is not enabled for this account."))?;
+        let secret = decrypt_secret(key, &stored)?;
+        let step = matching_step(&secret, code, unix_secs, last).ok_or_else(|| AppError::validation("code", WRONG))?;
+        conn.execute("UPDATE user_totp SET last_used_step = ?1 WHERE user_id = ?2", params![step, user_id])?;
+        return Ok(SecondFactor::Totp);
+    }
+    let spent = conn
+        .execute(
+            "UPDATE user_recovery_codes SET used_at = datetime('now') \
+             WHERE user_id = ?1 AND code_hash = ?2 AND used_at IS NULL",
+            params![user_id, hash_recovery_code(code)],
+        )?;
+    if spent == 0 {
+        return Err(AppError::validation("code", WRONG));
+    }
+    Ok(SecondFactor::RecoveryCode { remaining: recovery_codes_remaining(conn, user_id)? })
+}
+
+// ── Policy ──────────────────────────────────────────────────────────────────
+
+pub fn role_requires_mfa(conn: &Connection, role: &str) -> Result<bool, AppError> {
+    conn.query_row("SELECT required FROM mfa_policy WHERE role = ?1", params![role], |r| r.get::<_, i64>(0))
+        .optional()
+        .map(|r| r.unwrap_or(0) != 0)
+        .map_err(AppError::from)
+}
+
+pub fn list_policy(conn: &Connection) -> Result<Vec<MfaPolicy>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.name, COALESCE(p.required, 0) FROM roles r \
+             LEFT JOIN mfa_policy p ON p.role = r.name ORDER BY r.builtin DESC, r.name",
+        )?;
+    let rows = stmt
+        .query_map([], |r| Ok(MfaPolicy { role: r.get(0)?, required: r.get::<_, i64>(1)? != 0 }))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub fn set_policy(conn: &Connection, role: &str, required: bool) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO mfa_policy (role, required, updated_at) VALUES (?1, ?2, datetime('now')) \
+         ON CONFLICT(role) DO UPDATE SET required = excluded.required, updated_at = excluded.updated_at",
+        params![role, required as i64],
+    )?;
+    Ok(())
+}
+
+pub fn status(conn: &Connection, user_id: &str, role: &str) -> Result<MfaStatus, AppError> {
+    let enrolled = is_enrolled(conn, user_id)?;
+    let required_by_policy = role_requires_mfa(conn, role)?;
+    Ok(MfaStatus {
+        enrolled,
+        required_by_policy,
+        enrollment_required: required_by_policy && !enrolled,
+        recovery_codes_remaining: recovery_codes_remaining(conn, user_id)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+
+    const KEY: [u8; 32] = [7u8; 32];
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'tech1', 'x', 'T', 'tech')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn code_at(secret_b32: &str, unix: i64) -> String {
+        format!("{:06}", hotp(&base32_decode(secret_b32).unwrap(), step_at(unix) as u64))
+    }
+
+    #[test]
+    fn rfc_6238_sha1_vectors() {
+        // RFC 6238 appendix B, truncated to six digits.
+        let secret = b"12345678901234567890";
+        for (t, expected) in [(59, 287082), (1111111109, 81804), (1234567890, 5924), (2000000000, 279037)] {
+            assert_eq!(hotp(secret, step_at(t) as u64), expected, "t = {}", t);
+        }
+        assert_eq!(matching_step(secret, "287082", 59, None), Some(1));
+        assert_eq!(matching_step(secret, "287082", 59 + 30, None), Some(1), "one step of skew is allowed");
+        assert_eq!(matching_step(secret, "287082", 59 + 60, None), None);
+        assert_eq!(matching_step(secret, "287082", 59, Some(1)), None, "a used step is refused");
+        assert_eq!(matching_step(secret, "28708", 59, None), None);
+    }
+
+    #[test]
+    fn base32_round_trips_and_matches_rfc_4648() {
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("mzxw 6ytb oi======").unwrap(), b"foobar");
+        assert_eq!(base32_decode(&base32_encode(&[0xff; 20])).unwrap(), vec![0xff; 20]);
+        assert!(base32_decode("MZ1").is_err());
+    }
+
+    #[test]
+    fn provisioning_uri_escapes_the_label() {
+        let uri = provisioning_uri("jane doe", "ABC");
+        assert_eq!(uri, "otpauth://totp/SteloPTC:jane%20doe?secret=ABC&issuer=SteloPTC&algorithm=SHA1&digits=6&period=30");
+    }
+
+    #[test]
+    fn enrollment_needs_a_valid_code_and_stores_the_secret_encrypted() {
+        let conn = db();
+        let e = begin_enrollment(&conn, &KEY, "u1", "tech1").unwrap();
+        assert!(!is_enrolled(&conn, "u1").unwrap());
+        let stored: String = conn.query_row("SELECT secret_enc FROM user_totp", [], |r| r.get(0)).unwrap();
+        assert!(!stored.contains(&e.secret));
+
+        let now = 1_760_000_000;
+        assert!(confirm_enrollment(&conn, &KEY, "u1", "000000", now).is_err() || code_at(&e.secret, now) == "000000");
+        assert!(confirm_enrollment(&conn, &[8u8; 32], "u1", &code_at(&e.secret, now), now).unwrap_err().message().contains("recovery code"));
+        let codes = confirm_enrollment(&conn, &KEY, "u1", &code_at(&e.secret, now), now).unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert!(is_enrolled(&conn, "u1").unwrap());
+        assert!(begin_enrollment(&conn, &KEY, "u1", "tech1").is_err());
+    }
+
+    #[test]
+    fn second_factor_accepts_codes_once_and_spends_recovery_codes() {
+        let conn = db();
+        let e = begin_enrollment(&conn, &KEY, "u1", "tech1").unwrap();
+        let t0 = 1_760_000_000;
+        let codes = confirm_enrollment(&conn, &KEY, "u1", &code_at(&e.secret, t0), t0).unwrap();
+
+        // The code used to confirm cannot log in again; the next step's can, once.
+        assert!(verify_second_factor(&conn, &KEY, "u1", &code_at(&e.secret, t0), t0).is_err());
+        let t1 = t0 + STEP_SECS;
+        assert_eq!(verify_second_factor(&conn, &KEY, "u1", &code_at(&e.secret, t1), t1).unwrap(), SecondFactor::Totp);
+        assert!(verify_second_factor(&conn, &KEY, "u1", &code_at(&e.secret, t1), t1).is_err());
+
+        let upper = codes[0].to_uppercase().replace('-', " ");
+        assert_eq!(
+            verify_second_factor(&conn, &KEY, "u1", &upper, t1).unwrap(),
+            SecondFactor::RecoveryCode { remaining: 9 }
+        );
+        assert!(verify_second_factor(&conn, &KEY, "u1", &codes[0], t1).is_err(), "recovery codes are single-use");
+        assert!(verify_second_factor(&conn, &KEY, "u1", "not-a-code", t1).is_err());
+
+        assert!(remove(&conn, "u1").unwrap());
+        assert_eq!(recovery_codes_remaining(&conn, "u1").unwrap(), 0);
+    }
+
+    #[test]
+    fn policy_flags_unenrolled_users_in_required_roles() {
+        let conn = db();
+        assert!(!status(&conn, "u1", "tech").unwrap().enrollment_required);
+        set_policy(&conn, "tech", true).unwrap();
+        let s = status(&conn, "u1", "tech").unwrap();
+        assert!(s.required_by_policy && s.enrollment_required);
+        assert!(list_policy(&conn).unwrap().iter().any(|p| p.role == "tech" && p.required));
+    }
+}
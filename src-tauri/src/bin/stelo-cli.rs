@@ -0,0 +1,5 @@
+// WP-91: the headless command line. All of it lives in `stelo_ptc_lib::cli`;
+// it needs no Tauri layer, so it also builds with `--no-default-features`.
+fn main() {
+    std::process::exit(stelo_ptc_lib::cli::main());
+}
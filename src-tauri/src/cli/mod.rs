@@ -0,0 +1,897 @@
+// WP-91: `stelo-cli`, the headless command line.
+//
+// Runs against the database file directly, so it needs neither the desktop
+// app nor its webview, and it builds with `--no-default-features`. Every
+// subcommand signs in first and calls the same functions the Tauri commands
+// do (`db::specimens`, `db::export`, `db::backup`, `integrity`, ...), with the
+// same capability checks and audit entries; this module only parses
+// arguments and prints results.
+pub mod xlsx;
+
+use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::import::ImportPayload;
+use crate::db::{queries, Database};
+use crate::models::specimen::SpecimenSearchParams;
+use crate::models::user::User;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+/// Exit status for success.
+pub const EXIT_OK: i32 = 0;
+/// Exit status when the command could not run: bad credentials, missing
+/// permission, unreadable file.
+pub const EXIT_ERROR: i32 = 1;
+/// Exit status for a malformed command line.
+pub const EXIT_USAGE: i32 = 2;
+/// Exit status when the command ran and found a problem: an integrity issue,
+/// a broken audit lineage or ledger, or import rows that were skipped.
+pub const EXIT_CHECK_FAILED: i32 = 3;
+
+/// The session label shown in Settings → Sessions (WP-88).
+const DEVICE: &str = "stelo-cli";
+
+pub const USAGE: &str = "\
+Usage: stelo-cli [options] <command> [arguments]
+
+Commands:
+  specimens list                 List specimens in the active lab
+  specimens search [TEXT]        Search specimens (--stage, --species-id,
+                                 --project-id, --quarantine, --archived,
+                                 --filter EXPR)
+  export csv|json|dwc            Export specimens as CSV or JSON (--filter
+                                 EXPR), or the taxonomy as Darwin Core
+                                 (--root TAXON_ID)
+  backup                         Write a database backup (--dest PATH)
+  integrity                      Run the data-integrity self-check
+  verify audit [LINEAGE...]      Verify audit lineages (all when none given)
+  verify ledger                  Verify the signed event ledger
+  checkpoint LINEAGE             Create an audit checkpoint (--start, --end)
+  checkpoint --all               Checkpoint every lineage with new entries
+  import FILE.xlsx               Import a SteloPTC workbook (--dry-run)
+
+Options:
+  --db PATH            Database file (default: $STELO_DB, then the app's)
+  --user NAME          Sign in as NAME (default: $STELO_USER)
+  --password-stdin     Read the password from the first line of stdin
+                       (default: $STELO_PASSWORD)
+  --code CODE          Authenticator or recovery code, for accounts with
+                       two-factor authentication
+  --token TOKEN        Use an existing session or API token instead
+                       (default: $STELO_TOKEN)
+  --json               Print results as JSON
+  --page N, --per-page N   Page through specimen lists (default 1, 50)
+  --out FILE           Write an export to FILE instead of stdout
+  -h, --help           Show this help
+  -V, --version        Show the version
+
+Exit status: 0 success, 1 error, 2 usage, 3 a check or import found problems.
+";
+
+/// How the caller proves who they are.
+#[derive(Debug, PartialEq)]
+pub enum Credentials {
+    /// A token from the desktop app, the local API (WP-90) or a previous
+    /// sign-in. It is used as-is and left open.
+    Token(String),
+    /// Username and password; `password` is `None` when it is to be read
+    /// from stdin. The session opened for the run is closed at the end.
+    Password { username: String, password: Option<String>, code: Option<String> },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    DarwinCore,
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Specimens(SpecimenSearchParams),
+    Export { format: ExportFormat, root: Option<String>, filter: Option<String>, out: Option<PathBuf> },
+    Backup { dest: Option<PathBuf> },
+    Integrity,
+    VerifyAudit { lineages: Vec<String> },
+    VerifyLedger,
+    Checkpoint { lineage: Option<String>, start: Option<i64>, end: Option<i64> },
+    Import { file: PathBuf, dry_run: bool },
+}
+
+#[derive(Debug)]
+pub struct Invocation {
+    pub db: PathBuf,
+    pub credentials: Credentials,
+    pub json: bool,
+    pub command: Command,
+}
+
+#[derive(Debug)]
+pub enum Parsed {
+    Help,
+    Version,
+    Run(Box<Invocation>),
+}
+
+/// Options that take a value; every other `--name` is a flag.
+const VALUE_OPTIONS: &[&str] = &[
+    "db", "user", "code", "token", "page", "per-page", "stage", "species-id", "project-id", "out", "root", "filter",
+    "dest", "start", "end",
+];
+
+/// The command line split into positionals and options, consumed as each
+/// part is recognised so that anything left over can be reported.
+struct Args {
+    positional: Vec<String>,
+    options: BTreeMap<String, Option<String>>,
+}
+
+impl Args {
+    fn split(raw: &[String]) -> Result<Args, String> {
+        let mut positional = Vec::new();
+        let mut options = BTreeMap::new();
+        let mut iter = raw.iter();
+        while let Some(arg) = iter.next() {
+            let name = match arg.as_str() {
+                "-h" => "help",
+                "-V" => "version",
+                a => match a.strip_prefix("--") {
+                    Some(name) if !name.is_empty() => name,
+                    _ => {
+                        positional.push(arg.clone());
+                        continue;
+                    }
+                },
+            };
+            if name == "password" || name.starts_with("password=") {
+                // Arguments are visible to every user in the process list.
+                return Err("There is no --password option; set STELO_PASSWORD or use --password-stdin".to_string());
+            }
+            let (name, inline) = match name.split_once('=') {
+                Some((n, v)) => (n, Some(v.to_string())),
+                None => (name, None),
+            };
+            let value = if VALUE_OPTIONS.contains(&name) {
+                match inline {
+                    Some(v) => Some(v),
+                    None => Some(iter.next().ok_or_else(|| format!("--{} needs a value", name))?.clone()),
+                }
+            } else if inline.is_some() {
+                return Err(format!("--{} does not take a value", name));
+            } else {
+                None
+            };
+            if options.insert(name.to_string(), value).is_some() {
+                return Err(format!("--{} given twice", name));
+            }
+        }
+        Ok(Args { positional, options })
+    }
+
+    fn flag(&mut self, name: &str) -> bool {
+        self.options.remove(name).is_some()
+    }
+
+    fn value(&mut self, name: &str) -> Option<String> {
+        self.options.remove(name).flatten()
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, name: &str) -> Result<Option<T>, String> {
+        self.value(name)
+            .map(|v| v.parse().map_err(|_| format!("--{} must be a number, not '{}'", name, v)))
+            .transpose()
+    }
+}
+
+/// Parse the command line. `env` looks up environment variables, so tests
+/// can supply their own.
+pub fn parse(raw: &[String], env: &dyn Fn(&str) -> Option<String>) -> Result<Parsed, String> {
+    let mut args = Args::split(raw)?;
+    if args.flag("help") {
+        return Ok(Parsed::Help);
+    }
+    if args.flag("version") {
+        return Ok(Parsed::Version);
+    }
+
+    let db = args
+        .value("db")
+        .or_else(|| env("STELO_DB"))
+        .map(PathBuf::from)
+        .unwrap_or_else(Database::db_path);
+    let json = args.flag("json");
+    // A flag beats the environment, so `--user` works in a shell that
+    // exports STELO_TOKEN and the other way round.
+    let (token, username) = match (args.value("token"), args.value("user")) {
+        (Some(_), Some(_)) => return Err("Give either --token or --user, not both".to_string()),
+        (Some(token), None) => (Some(token), None),
+        (None, Some(user)) => (None, Some(user)),
+        (None, None) => match env("STELO_TOKEN") {
+            Some(token) => (Some(token), None),
+            None => (None, env("STELO_USER")),
+        },
+    };
+    let password_stdin = args.flag("password-stdin");
+    let code = args.value("code");
+    let credentials = match (token, username) {
+        (Some(token), _) => {
+            if password_stdin || code.is_some() {
+                return Err("--password-stdin and --code go with --user, not --token".to_string());
+            }
+            Credentials::Token(token)
+        }
+        (None, Some(username)) => {
+            let password = if password_stdin {
+                None
+            } else {
+                Some(env("STELO_PASSWORD").ok_or(
+                    "No password: set STELO_PASSWORD or pass --password-stdin",
+                )?)
+            };
+            Credentials::Password { username, password, code }
+        }
+        (None, None) => {
+            return Err("Sign in with --user (or STELO_USER) or --token (or STELO_TOKEN)".to_string());
+        }
+    };
+
+    let command = parse_command(&mut args)?;
+    if let Some(extra) = args.positional.first() {
+        return Err(format!("Unexpected argument '{}'", extra));
+    }
+    if let Some(name) = args.options.keys().next() {
+        return Err(format!("--{} does not apply to this command", name));
+    }
+    Ok(Parsed::Run(Box::new(Invocation { db, credentials, json, command })))
+}
+
+fn parse_command(args: &mut Args) -> Result<Command, String> {
+    if args.positional.is_empty() {
+        return Err("No command given".to_string());
+    }
+    let words: Vec<String> = args.positional.drain(..).collect();
+    let word = |i: usize| words.get(i).map(String::as_str);
+    let rest = |from: usize| words[from.min(words.len())..].to_vec();
+
+    let (command, leftover) = match (word(0), word(1)) {
+        (Some("specimens"), Some(sub @ ("list" | "search"))) => {
+            let search = sub == "search";
+            let query = if search { word(2).map(str::to_string) } else { None };
+            let params = SpecimenSearchParams {
+                query,
+                species_id: if search { args.value("species-id") } else { None },
+                stage: if search { args.value("stage") } else { None },
+                project_id: if search { args.value("project-id") } else { None },
+                quarantine_only: Some(search && args.flag("quarantine")),
+                archived: Some(search && args.flag("archived")),
+                filter: if search { args.value("filter") } else { None },
+                page: args.number("page")?,
+                per_page: args.number("per-page")?,
+                ..Default::default()
+            };
+            (Command::Specimens(params), rest(if search { 3 } else { 2 }))
+        }
+        (Some("specimens"), _) => return Err("Use `specimens list` or `specimens search`".to_string()),
+        (Some("export"), Some(format)) => {
+            let format = match format {
+                "csv" => ExportFormat::Csv,
+                "json" => ExportFormat::Json,
+                "dwc" => ExportFormat::DarwinCore,
+                other => return Err(format!("Unknown export format '{}' (csv, json or dwc)", other)),
+            };
+            let root = if format == ExportFormat::DarwinCore { args.value("root") } else { None };
+            let filter = if format == ExportFormat::DarwinCore { None } else { args.value("filter") };
+            (Command::Export { format, root, filter, out: args.value("out").map(PathBuf::from) }, rest(2))
+        }
+        (Some("export"), None) => return Err("Use `export csv`, `export json` or `export dwc`".to_string()),
+        (Some("backup"), _) => (Command::Backup { dest: args.value("dest").map(PathBuf::from) }, rest(1)),
+        (Some("integrity"), _) => (Command::Integrity, rest(1)),
+        (Some("verify"), Some("audit")) => (Command::VerifyAudit { lineages: rest(2) }, Vec::new()),
+        (Some("verify"), Some("ledger")) => (Command::VerifyLedger, rest(2)),
+        (Some("verify"), _) => return Err("Use `verify audit` or `verify ledger`".to_string()),
+        (Some("checkpoint"), lineage) => {
+            let all = args.flag("all");
+            let lineage = match (all, lineage) {
+                (true, None) => None,
+                (false, Some(id)) => Some(id.to_string()),
+                _ => return Err("Use `checkpoint LINEAGE` or `checkpoint --all`".to_string()),
+            };
+            let (start, end) = if lineage.is_some() {
+                (args.number("start")?, args.number("end")?)
+            } else {
+                (None, None)
+            };
+            (Command::Checkpoint { lineage, start, end }, rest(2))
+        }
+        (Some("import"), Some(file)) => {
+            (Command::Import { file: PathBuf::from(file), dry_run: args.flag("dry-run") }, rest(2))
+        }
+        (Some("import"), None) => return Err("Use `import FILE.xlsx`".to_string()),
+        (Some(other), _) => return Err(format!("Unknown command '{}'", other)),
+        (None, _) => unreachable!("checked above"),
+    };
+    args.positional = leftover;
+    Ok(command)
+}
+
+/// Run a parsed invocation, writing results to `out`. Returns the exit
+/// status; `Err` is an error to print, exit status [`EXIT_ERROR`].
+pub fn run(invocation: Invocation, stdin: &mut dyn BufRead, out: &mut dyn Write) -> Result<i32, String> {
+    // Never create an empty database by mistake: a typo in --db must not
+    // look like a lab with no specimens.
+    if !invocation.db.is_file() {
+        return Err(format!("No database at {}", invocation.db.display()));
+    }
+    let db = Database::open(&invocation.db).map_err(|e| e.to_string())?;
+    // The same step the desktop app takes at launch, so a database last
+    // opened by an older version is brought up to this binary's schema.
+    db.run_migrations().map_err(|e| e.to_string())?;
+
+    let (user, opened) = sign_in(&db, invocation.credentials, stdin)?;
+    let result = execute(&db, &invocation.db, &user, invocation.json, invocation.command, out);
+    if let Some(token) = opened {
+        auth_service::invalidate_session(&db, &token).ok();
+    }
+    result
+}
+
+/// Resolve the credentials to a user. Returns the session token when this
+/// run opened one, so it can be closed afterwards.
+fn sign_in(db: &Database, credentials: Credentials, stdin: &mut dyn BufRead) -> Result<(User, Option<String>), String> {
+    match credentials {
+        Credentials::Token(token) => Ok((auth_service::validate_session(db, &token)?, None)),
+        Credentials::Password { username, password, code } => {
+            let password = match password {
+                Some(p) => p,
+                None => {
+                    let mut line = String::new();
+                    stdin.read_line(&mut line).map_err(|e| format!("Cannot read the password: {}", e))?;
+                    line.trim_end_matches(['\r', '\n']).to_string()
+                }
+            };
+            let signed_in = auth_service::sign_in(db, &username, &password, Some(DEVICE))?;
+            let token = signed_in.token;
+            if signed_in.mfa.enrolled {
+                let Some(code) = code else {
+                    auth_service::invalidate_session(db, &token).ok();
+                    return Err("Two-factor verification required: pass --code".to_string());
+                };
+                if let Err(e) = auth_service::sign_in_mfa(db, &token, &code) {
+                    auth_service::invalidate_session(db, &token).ok();
+                    return Err(e.into());
+                }
+            }
+            // The same gates every command applies: a forced password change
+            // or a missing 2FA enrollment must be dealt with in the app first.
+            match auth_service::validate_session(db, &token) {
+                Ok(user) => Ok((user, Some(token))),
+                Err(e) => {
+                    auth_service::invalidate_session(db, &token).ok();
+                    Err(e.into())
+                }
+            }
+        }
+    }
+}
+
+fn print_json<T: Serialize>(out: &mut dyn Write, value: &T) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    writeln!(out, "{}", text).map_err(|e| e.to_string())
+}
+
+fn line(out: &mut dyn Write, text: impl std::fmt::Display) -> Result<(), String> {
+    writeln!(out, "{}", text).map_err(|e| e.to_string())
+}
+
+/// A cell for tab-separated output: tabs and newlines would break the row.
+fn tsv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.replace(['\t', '\n', '\r'], " "),
+        other => other.to_string(),
+    }
+}
+
+fn execute(
+    db: &Database,
+    db_path: &std::path::Path,
+    user: &User,
+    json: bool,
+    command: Command,
+    out: &mut dyn Write,
+) -> Result<i32, String> {
+    let conn = &db.conn;
+    // WP-92: the subcommands that check no capability only read, which an
+    // API token may do only with the `read` scope.
+    if matches!(command, Command::Specimens(_) | Command::Export { .. } | Command::VerifyAudit { .. } | Command::VerifyLedger) {
+        auth_service::require_read_scope(user)?;
+    }
+    match command {
+        Command::Specimens(params) => {
+            let page = crate::db::specimens::search(conn, user.role.as_str(), &params).map_err(|e| e.to_string())?;
+            // Serialized, so the rows printed are the masked ones (WP-87).
+            let page = serde_json::to_value(&page).map_err(|e| e.to_string())?;
+            if json {
+                print_json(out, &page)?;
+                return Ok(EXIT_OK);
+            }
+            const COLUMNS: [&str; 6] =
+                ["accession_number", "species_name", "stage", "location", "health_status", "initiation_date"];
+            line(out, COLUMNS.join("\t"))?;
+            for item in page["items"].as_array().into_iter().flatten() {
+                let cells: Vec<String> = COLUMNS.iter().map(|c| tsv_cell(&item[*c])).collect();
+                line(out, cells.join("\t"))?;
+            }
+            eprintln!(
+                "Page {} of {} ({} specimens)",
+                page["page"], page["total_pages"], page["total"]
+            );
+            Ok(EXIT_OK)
+        }
+        Command::Export { format, root, filter, out: file } => {
+            let text = match format {
+                ExportFormat::Csv => {
+                    crate::db::export::masked_export_csv(conn, user.role.as_str(), filter.as_deref()).map_err(|e| e.to_string())?
+                }
+                ExportFormat::Json => {
+                    let rows = crate::db::export::masked_export_rows(conn, user.role.as_str(), filter.as_deref()).map_err(|e| e.to_string())?;
+                    serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())?
+                }
+                ExportFormat::DarwinCore => {
+                    let export = queries::export_darwin_core(conn, root.as_deref()).map_err(|e| e.to_string())?;
+                    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?
+                }
+            };
+            match file {
+                Some(path) => {
+                    std::fs::write(&path, &text).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+                    line(out, format_args!("Wrote {} bytes to {}", text.len(), path.display()))?;
+                }
+                None => write!(out, "{}", text).map_err(|e| e.to_string())?,
+            }
+            Ok(EXIT_OK)
+        }
+        Command::Backup { dest } => {
+            auth_service::require_capability(db, user, Capability::BackupCreate)?;
+            let path = crate::db::backup::create_backup_file(conn, db_path, dest.as_deref(), &user.id)?;
+            line(out, path.display())?;
+            Ok(EXIT_OK)
+        }
+        Command::Integrity => {
+            auth_service::require_capability(db, user, Capability::IntegrityCheck)?;
+            let report = crate::integrity::run_integrity_check(conn)?;
+            if json {
+                print_json(out, &report)?;
+            } else {
+                for issue in &report.issues {
+                    line(
+                        out,
+                        format_args!(
+                            "[{}] {}: {} ({}) e.g. {}",
+                            issue.severity,
+                            issue.check,
+                            issue.title,
+                            issue.count,
+                            issue.examples.join(", ")
+                        ),
+                    )?;
+                }
+                line(out, format_args!("{} checks run, {} issue(s) found", report.checks_run, report.issues.len()))?;
+            }
+            Ok(if report.ok { EXIT_OK } else { EXIT_CHECK_FAILED })
+        }
+        Command::VerifyAudit { lineages } => {
+            let lineages = if lineages.is_empty() {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT DISTINCT lineage_id FROM audit_log \
+                         WHERE entry_hash IS NOT NULL AND lineage_id IS NOT NULL ORDER BY lineage_id",
+                    )
+                    .map_err(|e| e.to_string())?;
+                let ids = stmt
+                    .query_map([], |r| r.get::<_, String>(0))
+                    .map_err(|e| e.to_string())?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+                    .map_err(|e| e.to_string());
+                ids?
+            } else {
+                lineages
+            };
+            let mut results = Vec::with_capacity(lineages.len());
+            for lineage in lineages {
+                results.push(queries::verify_audit_lineage(conn, lineage)?);
+            }
+            let broken = results.iter().filter(|r| !r.ok).count();
+            if json {
+                print_json(out, &results)?;
+            } else {
+                for r in results.iter().filter(|r| !r.ok) {
+                    line(out, format_args!("BROKEN {}: {}", r.lineage_id, r.message))?;
+                }
+                line(out, format_args!("{} lineage(s) verified, {} broken", results.len(), broken))?;
+            }
+            Ok(if broken == 0 { EXIT_OK } else { EXIT_CHECK_FAILED })
+        }
+        Command::VerifyLedger => {
+            let report = crate::signed_ledger::verify_ledger(conn)?;
+            if json {
+                print_json(out, &report)?;
+            } else {
+                line(out, &report.message)?;
+                if !report.pending_witness.is_empty() {
+                    line(out, format_args!("{} event(s) await witness countersignature", report.pending_witness.len()))?;
+                }
+            }
+            Ok(if report.verified { EXIT_OK } else { EXIT_CHECK_FAILED })
+        }
+        Command::Checkpoint { lineage, start, end } => {
+            auth_service::require_capability(db, user, Capability::AuditCheckpoint)?;
+            match lineage {
+                Some(lineage) => {
+                    let created = queries::create_audit_checkpoint(conn, &lineage, start, end, &user.id)?;
+                    if json {
+                        print_json(out, &created)?;
+                    } else {
+                        line(
+                            out,
+                            format_args!(
+                                "Checkpoint {} over {} seq {}–{} ({} entries), Merkle root {}",
+                                created.checkpoint_id, created.lineage_id, created.start_seq, created.end_seq,
+                                created.entry_count, created.merkle_root
+                            ),
+                        )?;
+                    }
+                }
+                None => {
+                    // Every lineage with any uncovered entry, like the
+                    // checkpoint taken before a backup.
+                    let ids = queries::auto_checkpoint_lineages(conn, &user.id, "cli", 0).map_err(|e| e.to_string())?;
+                    if json {
+                        print_json(out, &ids)?;
+                    } else {
+                        line(out, format_args!("{} checkpoint(s) created", ids.len()))?;
+                    }
+                }
+            }
+            Ok(EXIT_OK)
+        }
+        Command::Import { file, dry_run } => {
+            auth_service::require_capability(db, user, Capability::DataImport)?;
+            let payload = import_payload(&xlsx::read(&file)?)?;
+            let result = crate::db::import::import_workbook(conn, &user.id, &payload, dry_run)?;
+            if json {
+                print_json(out, &result)?;
+            } else {
+                let sheets = [
+                    ("Specimens", &result.specimens),
+                    ("Subcultures", &result.subcultures),
+                    ("Media Batches", &result.media),
+                    ("Prepared Solutions", &result.prepared_solutions),
+                    ("Inventory", &result.inventory),
+                    ("Compliance", &result.compliance),
+                ];
+                for (name, stats) in sheets {
+                    line(
+                        out,
+                        format_args!("{}: {} created, {} updated, {} skipped", name, stats.creates, stats.updates, stats.skips),
+                    )?;
+                }
+                for e in &result.errors {
+                    line(out, format_args!("{} row {}: {}", e.sheet, e.row, e.message))?;
+                }
+                if dry_run {
+                    line(out, "Dry run: nothing was written.")?;
+                }
+            }
+            Ok(if result.errors.is_empty() { EXIT_OK } else { EXIT_CHECK_FAILED })
+        }
+    }
+}
+
+/// The webview's `ImportManager` payload, built from the workbook: the six
+/// sheets an export writes, each without its header row.
+pub fn import_payload(book: &xlsx::Workbook) -> Result<ImportPayload, String> {
+    const SHEETS: [&str; 6] = ["Specimens", "Subcultures", "Media Batches", "Prepared Solutions", "Inventory", "Compliance"];
+    let missing: Vec<&str> = SHEETS.iter().copied().filter(|s| book.rows(s).is_none()).collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Missing sheet(s): {}. Make sure this file was exported by SteloPTC.",
+            missing.join(", ")
+        ));
+    }
+    let body = |name: &str| book.rows(name).map(|rows| rows.iter().skip(1).cloned().collect()).unwrap_or_default();
+    Ok(ImportPayload {
+        specimens: body("Specimens"),
+        subcultures: body("Subcultures"),
+        media: body("Media Batches"),
+        prepared_solutions: body("Prepared Solutions"),
+        inventory: body("Inventory"),
+        compliance: body("Compliance"),
+    })
+}
+
+/// The `stelo-cli` entry point; returns the process exit status.
+pub fn main() -> i32 {
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    let invocation = match parse(&raw, &|name| std::env::var(name).ok()) {
+        Ok(Parsed::Help) => {
+            print!("{}", USAGE);
+            return EXIT_OK;
+        }
+        Ok(Parsed::Version) => {
+            println!("stelo-cli {}", env!("CARGO_PKG_VERSION"));
+            return EXIT_OK;
+        }
+        Ok(Parsed::Run(invocation)) => *invocation,
+        Err(e) => {
+            eprintln!("stelo-cli: {}\nRun `stelo-cli --help` for usage.", e);
+            return EXIT_USAGE;
+        }
+    };
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    match run(invocation, &mut stdin.lock(), &mut stdout.lock()) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("stelo-cli: {}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    fn parsed(line: &str, env: &dyn Fn(&str) -> Option<String>) -> Result<Invocation, String> {
+        match parse(&args(line), env)? {
+            Parsed::Run(invocation) => Ok(*invocation),
+            other => panic!("expected a run, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn options_may_come_anywhere_and_fall_back_to_the_environment() {
+        let env = |name: &str| match name {
+            "STELO_USER" => Some("tech1".to_string()),
+            "STELO_PASSWORD" => Some("pw".to_string()),
+            _ => None,
+        };
+        let inv = parsed("specimens search shoot --stage=explant --db /tmp/x.db --per-page 10 --json", &env).unwrap();
+        assert_eq!(inv.db, PathBuf::from("/tmp/x.db"));
+        assert!(inv.json);
+        assert_eq!(
+            inv.credentials,
+            Credentials::Password { username: "tech1".into(), password: Some("pw".into()), code: None }
+        );
+        let Command::Specimens(params) = inv.command else { panic!("{:?}", inv.command) };
+        assert_eq!(params.query.as_deref(), Some("shoot"));
+        assert_eq!(params.stage.as_deref(), Some("explant"));
+        assert_eq!(params.per_page, Some(10));
+
+        let inv = parsed("--token abc checkpoint --all", &no_env).unwrap();
+        assert_eq!(inv.credentials, Credentials::Token("abc".into()));
+        assert!(matches!(inv.command, Command::Checkpoint { lineage: None, .. }));
+    }
+
+    #[test]
+    fn a_password_is_never_taken_from_the_command_line() {
+        let err = parsed("--user tech1 integrity", &no_env).unwrap_err();
+        assert!(err.contains("STELO_PASSWORD"), "{err}");
+        let err = parse(&args("--user tech1 --password hunter2 integrity"), &no_env).unwrap_err();
+        assert!(err.contains("no --password option") && !err.contains("hunter2"), "{err}");
+        let inv = parsed("--user tech1 --password-stdin integrity", &no_env).unwrap();
+        assert_eq!(inv.credentials, Credentials::Password { username: "tech1".into(), password: None, code: None });
+    }
+
+    #[test]
+    fn malformed_command_lines_are_refused() {
+        for (line, expected) in [
+            ("--token t", "No command given"),
+            ("--token t frobnicate", "Unknown command"),
+            ("--token t export xml", "Unknown export format"),
+            ("--token t integrity extra", "Unexpected argument 'extra'"),
+            ("--token t backup --stage x", "--stage does not apply"),
+            ("--token t checkpoint L1 --all", "checkpoint LINEAGE"),
+            ("--token t specimens list --page two", "--page must be a number"),
+            ("--token t --user u integrity", "not both"),
+            ("--token t --code 123456 integrity", "go with --user"),
+            ("integrity", "Sign in with"),
+        ] {
+            let err = parse(&args(line), &no_env).unwrap_err();
+            assert!(err.contains(expected), "{line}: {err}");
+        }
+        assert!(matches!(parse(&args("-h"), &no_env), Ok(Parsed::Help)));
+    }
+
+    fn lab_db() -> PathBuf {
+        let path = std::env::temp_dir().join(format!("stelo_cli_{}.db", uuid::Uuid::new_v4()));
+        lab_db_at(&path);
+        path
+    }
+
+    fn lab_db_at(path: &std::path::Path) {
+        let db = Database::open(path).unwrap();
+        db.run_migrations().unwrap();
+        let hash = bcrypt::hash("lab-password", 4).unwrap();
+        db.conn
+            .execute(
+                "INSERT INTO users (id, username, password_hash, display_name, role, is_active) \
+                 VALUES ('u1', 'curator', ?1, 'Curator', 'admin', 1), ('u2', 'guest1', ?1, 'Guest', 'guest', 1)",
+                rusqlite::params![hash],
+            )
+            .unwrap();
+        db.conn
+            .execute("INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT')", [])
+            .unwrap();
+        db.conn
+            .execute(
+                "INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, location) \
+                 VALUES ('s1', 'CIT-001', 'sp', 'explant', '2026-01-01', 'Room\t2')",
+                [],
+            )
+            .unwrap();
+    }
+
+    fn cleanup(path: &std::path::Path) {
+        for suffix in ["", "-wal", "-shm"] {
+            std::fs::remove_file(format!("{}{}", path.display(), suffix)).ok();
+        }
+    }
+
+    fn run_line(path: &std::path::Path, line: &str, stdin: &str) -> (Result<i32, String>, String) {
+        let env = |name: &str| (name == "STELO_PASSWORD").then(|| "lab-password".to_string());
+        let mut invocation = parsed(line, &env).unwrap();
+        invocation.db = path.to_path_buf();
+        let mut out = Vec::new();
+        let code = run(invocation, &mut stdin.as_bytes(), &mut out);
+        (code, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn signs_in_runs_and_closes_its_session() {
+        let path = lab_db();
+        let (code, out) = run_line(&path, "--user curator specimens list", "");
+        assert_eq!(code, Ok(EXIT_OK));
+        let mut lines = out.lines();
+        assert_eq!(lines.next(), Some("accession_number\tspecies_name\tstage\tlocation\thealth_status\tinitiation_date"));
+        assert_eq!(lines.next(), Some("CIT-001\tCitrus sinensis\texplant\tRoom 2\thealthy\t2026-01-01"));
+
+        let (code, out) = run_line(&path, "--user curator --password-stdin verify audit", "lab-password\n");
+        assert_eq!(code, Ok(EXIT_OK), "{out}");
+        assert!(out.ends_with("verified, 0 broken\n"), "{out}");
+
+        let db = Database::open(&path).unwrap();
+        let (sessions, logins): (i64, i64) = db
+            .conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM sessions), \
+                        (SELECT COUNT(*) FROM audit_log WHERE action = 'login' AND user_id = 'u1')",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(sessions, 0, "each run closes the session it opened");
+        assert_eq!(logins, 2, "CLI sign-ins are audited like app logins");
+        drop(db);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn the_callers_role_and_credentials_are_enforced() {
+        let path = lab_db();
+        let (code, _) = run_line(&path, "--user guest1 backup", "");
+        assert!(code.unwrap_err().contains("Insufficient permissions"));
+
+        let (code, _) = run_line(&path, "--user curator --password-stdin specimens list", "wrong\n");
+        assert_eq!(code, Err("Invalid username or password".to_string()));
+
+        let (code, _) = run_line(&path, "--token not-a-session specimens list", "");
+        assert!(code.unwrap_err().contains("Session expired"));
+
+        let (code, out) = run_line(&path, "--user curator integrity", "");
+        assert!(code.is_ok() && out.contains("checks run"), "{out}");
+        cleanup(&path);
+    }
+
+    #[test]
+    fn api_tokens_are_held_to_their_scope_and_left_open() {
+        use crate::auth::api_tokens;
+        let path = lab_db();
+        let db = Database::open(&path).unwrap();
+        let account = api_tokens::create_service_account(
+            &db.conn,
+            &api_tokens::CreateServiceAccountRequest {
+                username: "ci-bot".into(),
+                display_name: "CI".into(),
+                role: "supervisor".into(),
+            },
+        )
+        .unwrap();
+        let issue = |scopes: &[&str]| {
+            api_tokens::create(
+                &db.conn,
+                &api_tokens::CreateApiTokenRequest {
+                    service_account_id: account.clone(),
+                    name: "nightly".into(),
+                    scopes: scopes.iter().map(|s| s.to_string()).collect(),
+                    expires_in_days: Some(30),
+                },
+                "u1",
+            )
+            .unwrap()
+            .token
+        };
+        let reader = issue(&["read"]);
+        let backup_only = issue(&["backup.create"]);
+
+        let (code, out) = run_line(&path, &format!("--token {} specimens list", reader), "");
+        assert_eq!(code, Ok(EXIT_OK));
+        assert!(out.contains("CIT-001"));
+        let (code, _) = run_line(&path, &format!("--token {} backup --dest {}.bak", reader, path.display()), "");
+        assert!(code.unwrap_err().contains("not scoped for \"Create backups"));
+
+        let (code, _) = run_line(&path, &format!("--token {} export csv", backup_only), "");
+        assert!(code.unwrap_err().contains("not scoped for reading lab data"));
+
+        assert!(api_tokens::authenticate(&db.conn, &reader).is_ok(), "a token the caller brought stays valid");
+        drop(db);
+        cleanup(&path);
+    }
+
+    /// A server copy of the database keeps its own `totp.key` beside it; the
+    /// CLI must read that one, not the app's default location.
+    #[test]
+    fn an_enrolled_user_signs_in_against_a_database_elsewhere() {
+        use crate::auth::totp;
+        let dir = std::env::temp_dir().join(format!("stelo_cli_mfa_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lab.db");
+        lab_db_at(&path);
+        let default_key = Database::db_path().with_file_name("totp.key");
+        let default_key_existed = default_key.exists();
+
+        let secret = {
+            let db = Database::open(&path).unwrap();
+            let key = totp::installation_key(&db).unwrap();
+            let e = totp::begin_enrollment(&db.conn, &key, "u1", "curator").unwrap();
+            let secret = totp::base32_decode(&e.secret).unwrap();
+            let then = chrono::Utc::now().timestamp() - totp::STEP_SECS;
+            let code = format!("{:06}", totp::hotp(&secret, totp::step_at(then) as u64));
+            totp::confirm_enrollment(&db.conn, &key, "u1", &code, then).unwrap();
+            secret
+        };
+        assert!(dir.join("totp.key").is_file(), "the key is created beside the opened database");
+
+        let code = format!("{:06}", totp::hotp(&secret, totp::step_at(chrono::Utc::now().timestamp()) as u64));
+        let (status, out) = run_line(&path, &format!("--user curator --code {code} integrity"), "");
+        assert!(status.is_ok(), "{status:?} {out}");
+        assert_eq!(default_key.exists(), default_key_existed, "no stray key at the default location");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn a_missing_database_is_not_created() {
+        let path = std::env::temp_dir().join(format!("stelo_cli_missing_{}.db", uuid::Uuid::new_v4()));
+        let (code, _) = run_line(&path, "--user curator integrity", "");
+        assert!(code.unwrap_err().starts_with("No database at"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn import_needs_every_exported_sheet() {
+        let err = import_payload(&xlsx::Workbook::default()).unwrap_err();
+        assert!(err.contains("Specimens, Subcultures, Media Batches, Prepared Solutions, Inventory, Compliance"), "{err}");
+    }
+}
@@ -0,0 +1,425 @@
+// WP-91: the XLSX reader behind `stelo-cli import`.
+//
+// The desktop app parses workbooks in the webview with SheetJS and hands the
+// backend string rows; the CLI has no webview, so this reads the same rows
+// straight from the file. It understands exactly what a workbook needs to
+// carry cell values — the sheet list, shared strings, and each sheet's
+// `<c>` cells — and nothing else (no styles, no formulas beyond their cached
+// value). Like the HTTP code in `api::http`, the XML scanning is hand-rolled:
+// the subset in a SpreadsheetML part is small and regular.
+use std::io::Read;
+use std::path::Path;
+
+/// Upper bound on one decompressed part. A real lab workbook is a few MB; the
+/// cap keeps a hostile "zip bomb" from exhausting memory.
+const MAX_PART_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Every sheet of a workbook as rows of cell text, in workbook order.
+#[derive(Debug, Default)]
+pub struct Workbook {
+    sheets: Vec<(String, Vec<Vec<String>>)>,
+}
+
+impl Workbook {
+    pub fn sheet_names(&self) -> Vec<&str> {
+        self.sheets.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// The sheet's rows from row 1, header included. Missing rows and cells
+    /// read as empty, the way SheetJS's `sheet_to_json(ws, { header: 1,
+    /// defval: '' })` fills them.
+    pub fn rows(&self, name: &str) -> Option<&[Vec<String>]> {
+        self.sheets.iter().find(|(n, _)| n == name).map(|(_, rows)| rows.as_slice())
+    }
+}
+
+/// Read the `.xlsx` file at `path`.
+pub fn read(path: &Path) -> Result<Workbook, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    let mut zip = zip::ZipArchive::new(file)
+        .map_err(|e| format!("{} is not an .xlsx workbook: {}", path.display(), e))?;
+
+    let workbook = part(&mut zip, "xl/workbook.xml")?
+        .ok_or_else(|| format!("{} is not an .xlsx workbook (no xl/workbook.xml)", path.display()))?;
+    let rels = part(&mut zip, "xl/_rels/workbook.xml.rels")?.unwrap_or_default();
+    let shared = match part(&mut zip, "xl/sharedStrings.xml")? {
+        Some(xml) => shared_strings(&xml),
+        None => Vec::new(),
+    };
+
+    let targets = relationships(&rels);
+    let mut sheets = Vec::new();
+    for (name, rel_id) in sheet_entries(&workbook) {
+        let target = targets
+            .iter()
+            .find(|(id, _)| *id == rel_id)
+            .map(|(_, target)| resolve_target(target))
+            .ok_or_else(|| format!("Sheet '{}' has no worksheet part", name))?;
+        let xml = part(&mut zip, &target)?.ok_or_else(|| format!("Sheet '{}' is missing ({})", name, target))?;
+        sheets.push((name, sheet_rows(&xml, &shared)?));
+    }
+    Ok(Workbook { sheets })
+}
+
+fn part<R: Read + std::io::Seek>(zip: &mut zip::ZipArchive<R>, name: &str) -> Result<Option<String>, String> {
+    let entry = match zip.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Cannot read {}: {}", name, e)),
+    };
+    let mut text = String::new();
+    entry
+        .take(MAX_PART_BYTES + 1)
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Cannot read {}: {}", name, e))?;
+    if text.len() as u64 > MAX_PART_BYTES {
+        return Err(format!("{} is larger than {} MB", name, MAX_PART_BYTES / (1024 * 1024)));
+    }
+    Ok(Some(text))
+}
+
+/// Relationship targets are relative to `xl/`, or absolute from the package
+/// root.
+fn resolve_target(target: &str) -> String {
+    match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("xl/{}", target),
+    }
+}
+
+/// `(name, relationship id)` for each `<sheet>` in `xl/workbook.xml`.
+fn sheet_entries(xml: &str) -> Vec<(String, String)> {
+    Scanner::new(xml)
+        .filter_map(|event| match event {
+            Event::Open { name: "sheet", attrs, .. } => Some((attr(attrs, "name")?, attr(attrs, "id")?)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `(Id, Target)` for each `<Relationship>`.
+fn relationships(xml: &str) -> Vec<(String, String)> {
+    Scanner::new(xml)
+        .filter_map(|event| match event {
+            Event::Open { name: "Relationship", attrs, .. } => Some((attr(attrs, "Id")?, attr(attrs, "Target")?)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The shared-string table. A rich-text entry is the concatenation of its
+/// runs; phonetic hints (`<rPh>`) are not part of the text.
+fn shared_strings(xml: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut current: Option<String> = None;
+    let mut in_text = false;
+    let mut in_phonetic = false;
+    for event in Scanner::new(xml) {
+        match event {
+            Event::Open { name: "si", empty, .. } => {
+                if empty {
+                    strings.push(String::new());
+                } else {
+                    current = Some(String::new());
+                }
+            }
+            Event::Close("si") => strings.push(current.take().unwrap_or_default()),
+            Event::Open { name: "rPh", empty: false, .. } => in_phonetic = true,
+            Event::Close("rPh") => in_phonetic = false,
+            Event::Open { name: "t", empty: false, .. } => in_text = true,
+            Event::Close("t") => in_text = false,
+            Event::Text(text) if in_text && !in_phonetic => {
+                if let Some(s) = current.as_mut() {
+                    s.push_str(&unescape(text));
+                }
+            }
+            _ => {}
+        }
+    }
+    strings
+}
+
+/// Column index (0-based) from a cell reference such as `AB12`.
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: String = reference.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    if letters.is_empty() {
+        return None;
+    }
+    letters
+        .chars()
+        .try_fold(0usize, |acc, c| Some(acc * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1)))
+        .map(|n| n - 1)
+}
+
+/// Row number (1-based) from a cell reference such as `AB12`.
+fn row_number(reference: &str) -> Option<usize> {
+    reference.trim_start_matches(|c: char| c.is_ascii_alphabetic()).parse().ok()
+}
+
+/// A cell's text as the webview import sees it: `String(cell)` of the
+/// SheetJS value.
+fn cell_text(kind: &str, raw: &str, shared: &[String]) -> Result<String, String> {
+    Ok(match kind {
+        "s" => {
+            let index: usize = raw.trim().parse().map_err(|_| format!("Bad shared-string index '{}'", raw))?;
+            shared.get(index).cloned().ok_or_else(|| format!("Shared-string index {} out of range", index))?
+        }
+        "b" => if raw.trim() == "1" { "true" } else { "false" }.to_string(),
+        "n" | "" => match raw.trim().parse::<f64>() {
+            Ok(n) if n.is_finite() => n.to_string(),
+            _ => raw.to_string(),
+        },
+        _ => raw.to_string(),
+    })
+}
+
+/// Dense rows of one worksheet: row `n` of the sheet is `rows[n - 1]`.
+fn sheet_rows(xml: &str, shared: &[String]) -> Result<Vec<Vec<String>>, String> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row_index = 0usize;
+    let mut next_col = 0usize;
+    // The open cell: (column, type, text gathered so far).
+    let mut cell: Option<(usize, String, String)> = None;
+    let mut in_value = false;
+
+    for event in Scanner::new(xml) {
+        match event {
+            Event::Open { name: "row", attrs, .. } => {
+                row_index = attr(attrs, "r").and_then(|r| r.parse().ok()).unwrap_or(row_index + 1);
+                next_col = 0;
+            }
+            Event::Open { name: "c", attrs, empty } => {
+                let col = attr(attrs, "r").as_deref().and_then(column_index).unwrap_or(next_col);
+                if let Some(r) = attr(attrs, "r").as_deref().and_then(row_number) {
+                    row_index = r;
+                }
+                next_col = col + 1;
+                if !empty {
+                    cell = Some((col, attr(attrs, "t").unwrap_or_default(), String::new()));
+                }
+            }
+            Event::Open { name: "v" | "t", empty: false, .. } if cell.is_some() => in_value = true,
+            Event::Close("v" | "t") => in_value = false,
+            Event::Text(text) if in_value => {
+                if let Some((_, _, value)) = cell.as_mut() {
+                    value.push_str(&unescape(text));
+                }
+            }
+            Event::Close("c") => {
+                if let Some((col, kind, raw)) = cell.take() {
+                    if raw.is_empty() || row_index == 0 {
+                        continue;
+                    }
+                    if rows.len() < row_index {
+                        rows.resize(row_index, Vec::new());
+                    }
+                    let row = &mut rows[row_index - 1];
+                    if row.len() <= col {
+                        row.resize(col + 1, String::new());
+                    }
+                    row[col] = cell_text(&kind, &raw, shared)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    // SheetJS pads every row to the sheet's width.
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize(width, String::new());
+    }
+    Ok(rows)
+}
+
+// ── Minimal XML scanning ─────────────────────────────────────────────────────
+
+#[derive(Debug, PartialEq)]
+enum Event<'a> {
+    /// A start or empty-element tag. `name` has any namespace prefix removed.
+    Open { name: &'a str, attrs: &'a str, empty: bool },
+    Close(&'a str),
+    Text(&'a str),
+}
+
+struct Scanner<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(xml: &'a str) -> Self {
+        Scanner { rest: xml }
+    }
+}
+
+fn local(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Event<'a>> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+            if !self.rest.starts_with('<') {
+                let end = self.rest.find('<').unwrap_or(self.rest.len());
+                let (text, rest) = self.rest.split_at(end);
+                self.rest = rest;
+                return Some(Event::Text(text));
+            }
+            // Declarations, comments and processing instructions carry no cell data.
+            for (open, close) in [("<?", "?>"), ("<!--", "-->"), ("<!", ">")] {
+                if self.rest.starts_with(open) {
+                    let end = self.rest.find(close).map(|i| i + close.len()).unwrap_or(self.rest.len());
+                    self.rest = &self.rest[end..];
+                }
+            }
+            if !self.rest.starts_with('<') || self.rest.starts_with("<!") || self.rest.starts_with("<?") {
+                continue;
+            }
+            let end = self.rest.find('>')?;
+            let tag = &self.rest[1..end];
+            self.rest = &self.rest[end + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(Event::Close(local(name.trim())));
+            }
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(t) => (t, true),
+                None => (tag, false),
+            };
+            let split = tag.find(|c: char| c.is_ascii_whitespace()).unwrap_or(tag.len());
+            let (name, attrs) = tag.split_at(split);
+            return Some(Event::Open { name: local(name), attrs, empty });
+        }
+    }
+}
+
+/// The unescaped value of the attribute whose local name is `wanted`.
+fn attr(attrs: &str, wanted: &str) -> Option<String> {
+    let mut rest = attrs;
+    loop {
+        rest = rest.trim_start();
+        let eq = rest.find('=')?;
+        let name = rest[..eq].trim();
+        let after = rest[eq + 1..].trim_start();
+        let quote = after.chars().next().filter(|q| *q == '"' || *q == '\'')?;
+        let close = after[1..].find(quote)? + 1;
+        if local(name) == wanted {
+            return Some(unescape(&after[1..close]));
+        }
+        rest = &after[close + 1..];
+    }
+}
+
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_workbook(path: &Path, sheets: &[(&str, &str)], shared: &str) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default();
+        let mut workbook = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#,
+        );
+        let mut rels = String::from(r#"<?xml version="1.0"?><Relationships>"#);
+        for (i, (name, xml)) in sheets.iter().enumerate() {
+            workbook.push_str(&format!(r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#, name, i + 1, i + 1));
+            rels.push_str(&format!(r#"<Relationship Id="rId{}" Type="worksheet" Target="worksheets/sheet{}.xml"/>"#, i + 1, i + 1));
+            zip.start_file(format!("xl/worksheets/sheet{}.xml", i + 1), options).unwrap();
+            zip.write_all(xml.as_bytes()).unwrap();
+        }
+        workbook.push_str("</sheets></workbook>");
+        rels.push_str("</Relationships>");
+        zip.start_file("xl/workbook.xml", options).unwrap();
+        zip.write_all(workbook.as_bytes()).unwrap();
+        zip.start_file("xl/_rels/workbook.xml.rels", options).unwrap();
+        zip.write_all(rels.as_bytes()).unwrap();
+        zip.start_file("xl/sharedStrings.xml", options).unwrap();
+        zip.write_all(shared.as_bytes()).unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn reads_shared_inline_numeric_and_boolean_cells() {
+        let path = std::env::temp_dir().join(format!("stelo_cli_xlsx_{}.xlsx", uuid::Uuid::new_v4()));
+        let shared = r#"<sst><si><t>Accession</t></si><si><r><t>PTC</t></r><r><t xml:space="preserve">-001 &amp; co</t></r><rPh><t>ignored</t></rPh></si></sst>"#;
+        let sheet = r#"<worksheet><sheetData>
+            <row r="1"><c r="A1" t="s"><v>0</v></c><c r="C1" t="inlineStr"><is><t>Stage</t></is></c></row>
+            <row r="3"><c r="A3" t="s"><v>1</v></c><c r="B3"><v>5.8</v></c><c r="C3" t="b"><v>1</v></c><c r="D3"><v>12</v></c></row>
+        </sheetData></worksheet>"#;
+        write_workbook(&path, &[("Specimens", sheet), ("Notes", "<worksheet><sheetData/></worksheet>")], shared);
+
+        let book = read(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(book.sheet_names(), vec!["Specimens", "Notes"]);
+        let rows = book.rows("Specimens").unwrap();
+        assert_eq!(rows.len(), 3, "the blank row 2 is kept so row numbers line up");
+        assert_eq!(rows[0], vec!["Accession", "", "Stage", ""]);
+        assert!(rows[1].iter().all(String::is_empty));
+        assert_eq!(rows[2], vec!["PTC-001 & co", "5.8", "true", "12"]);
+        assert!(book.rows("Notes").unwrap().is_empty());
+        assert!(book.rows("Missing").is_none());
+    }
+
+    #[test]
+    fn a_file_that_is_not_a_workbook_is_refused() {
+        let path = std::env::temp_dir().join(format!("stelo_cli_xlsx_{}.xlsx", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"Accession,Stage\n").unwrap();
+        let err = read(&path).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(err.contains("is not an .xlsx workbook"), "{err}");
+    }
+
+    #[test]
+    fn cell_references_and_entities_decode() {
+        assert_eq!(column_index("A1"), Some(0));
+        assert_eq!(column_index("Z9"), Some(25));
+        assert_eq!(column_index("AB12"), Some(27));
+        assert_eq!(row_number("AB12"), Some(12));
+        assert_eq!(unescape("a &lt;b&gt; &#233;&#x41; &unknown; &"), "a <b> éA &unknown; &");
+        assert_eq!(attr(r#" name="Media Batches" r:id='rId3'"#, "id").as_deref(), Some("rId3"));
+    }
+}
@@ -9,6 +9,8 @@ use aes_gcm::{Aes256Gcm, Key, Nonce};
 use argon2::Argon2;
 use rand::RngCore;
 
+use crate::error::AppError;
+
 /// 4-byte magic + 1-byte format version, written at the start of every
 /// encrypted backup blob so `decrypt` can fail fast on a non-SteloPTC file
 /// instead of producing a confusing AEAD-tag-mismatch error.
@@ -32,12 +34,12 @@ fn argon2_params() -> argon2::Params {
 /// block at call time, and on a memory-constrained device that allocation can
 /// fail at runtime. A backup/restore command must surface that as a clean
 /// error, not unwind a panic across the Tauri command boundary.
-pub fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+pub fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], AppError> {
     let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, argon2_params());
     let mut key = [0u8; 32];
     argon2
         .hash_password_into(passphrase.as_bytes(), salt, &mut key)
-        .map_err(|e| format!("Key derivation failed (out of memory?): {}", e))?;
+        .map_err(|e| AppError::internal(format!("Key derivation failed (out of memory?): {}", e)))?;
     Ok(key)
 }
 
@@ -59,14 +61,14 @@ pub fn generate_salt() -> [u8; SALT_LEN] {
 /// practice AES-GCM encryption only fails if the plaintext exceeds GCM's
 /// ~64 GiB limit, which no SQLite backup will reach — but a Tauri command
 /// should surface even that as an error string, never a panic.
-pub fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+pub fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
     let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
     let mut nonce_bytes = [0u8; NONCE_LEN];
     rand::thread_rng().fill_bytes(&mut nonce_bytes);
     let nonce = Nonce::from_slice(&nonce_bytes);
     let ciphertext = cipher
         .encrypt(nonce, plaintext)
-        .map_err(|e| format!("Encryption failed: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Encryption failed: {}", e)))?;
 
     let mut out = Vec::with_capacity(4 + 1 + NONCE_LEN + ciphertext.len());
     out.extend_from_slice(MAGIC);
@@ -83,16 +85,16 @@ pub fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
 /// is the correct security property: a wrong-key error must look identical
 /// to a tamper error, or an attacker could use error messages to mount a
 /// key-guessing oracle).
-pub fn decrypt(key: &[u8; 32], blob: &[u8]) -> Result<Vec<u8>, String> {
+pub fn decrypt(key: &[u8; 32], blob: &[u8]) -> Result<Vec<u8>, AppError> {
     if blob.len() < 4 + 1 + NONCE_LEN {
-        return Err("Backup blob is too short to be valid".to_string());
+        return Err(AppError::integrity("Backup blob is too short to be valid"));
     }
     if &blob[0..4] != MAGIC {
-        return Err("Not a SteloPTC encrypted backup (bad magic header)".to_string());
+        return Err(AppError::integrity("Not a SteloPTC encrypted backup (bad magic header)"));
     }
     let version = blob[4];
     if version != FORMAT_VERSION {
-        return Err(format!("Unsupported backup format version {}", version));
+        return Err(AppError::integrity(format!("Unsupported backup format version {}", version)));
     }
     let nonce = Nonce::from_slice(&blob[5..5 + NONCE_LEN]);
     let ciphertext = &blob[5 + NONCE_LEN..];
@@ -100,7 +102,9 @@ pub fn decrypt(key: &[u8; 32], blob: &[u8]) -> Result<Vec<u8>, String> {
     let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
     cipher
         .decrypt(nonce, ciphertext)
-        .map_err(|_| "Decryption failed — wrong passphrase, or the backup is corrupted/tampered".to_string())
+        .map_err(|_| {
+            AppError::validation("passphrase", "Decryption failed — wrong passphrase, or the backup is corrupted/tampered")
+        })
 }
 
 #[cfg(test)]
@@ -124,7 +128,7 @@ mod tests {
         let wrong_key = derive_key("incorrect horse", &salt).unwrap();
         let blob = encrypt(&key, b"secret lab data").unwrap();
         let err = decrypt(&wrong_key, &blob).unwrap_err();
-        assert!(err.contains("wrong passphrase") || err.contains("corrupted"));
+        assert!(err.message().contains("wrong passphrase") || err.message().contains("corrupted"));
     }
 
     #[test]
@@ -153,7 +157,7 @@ mod tests {
         let mut blob = encrypt(&key, b"data").unwrap();
         blob[0] = b'X';
         let err = decrypt(&key, &blob).unwrap_err();
-        assert!(err.contains("bad magic"));
+        assert!(err.message().contains("bad magic"));
     }
 
     #[test]
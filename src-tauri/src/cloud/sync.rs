@@ -9,6 +9,7 @@
 // segment files that carry changes between devices via a shared cloud
 // target, since WP-51 assumed a live LAN connection rather than
 // asynchronous file-based exchange.
+use crate::error::AppError;
 use crate::models::sync::ChangeRecord;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -52,12 +53,12 @@ pub fn order_segments(mut segments: Vec<WalSegmentInfo>) -> Vec<WalSegmentInfo>
 /// (plain JSON — the file itself lives inside the already-encrypted cloud
 /// backup target's storage area, so a second layer of encryption here would
 /// be redundant; see ROADMAP.md WP-59 "As built").
-pub fn serialize_segment(changes: &[ChangeRecord]) -> Result<String, String> {
-    serde_json::to_string(changes).map_err(|e| e.to_string())
+pub fn serialize_segment(changes: &[ChangeRecord]) -> Result<String, AppError> {
+    Ok(serde_json::to_string(changes)?)
 }
 
-pub fn deserialize_segment(body: &str) -> Result<Vec<ChangeRecord>, String> {
-    serde_json::from_str(body).map_err(|e| format!("Malformed WAL segment: {}", e))
+pub fn deserialize_segment(body: &str) -> Result<Vec<ChangeRecord>, AppError> {
+    serde_json::from_str(body).map_err(|e| AppError::integrity(format!("Malformed WAL segment: {}", e)))
 }
 
 #[cfg(test)]
@@ -5,6 +5,7 @@ use base64::Engine as _;
 use serde::{Deserialize, Serialize};
 
 use super::crypto;
+use crate::error::AppError;
 
 /// The plaintext shape of a backup target's connection details. Only ever
 /// exists in memory (during encrypt/decrypt); the database only ever sees
@@ -22,8 +23,8 @@ pub struct TargetConfig {
 /// self-contained string safe to store directly in
 /// `backup_targets.config_encrypted`. The salt is not secret; it must travel
 /// with the ciphertext so the same key can be re-derived later.
-pub fn encrypt_target_config(passphrase: &str, config: &TargetConfig) -> Result<String, String> {
-    let json = serde_json::to_vec(config).map_err(|e| e.to_string())?;
+pub fn encrypt_target_config(passphrase: &str, config: &TargetConfig) -> Result<String, AppError> {
+    let json = serde_json::to_vec(config)?;
     let salt = crypto::generate_salt();
     let key = crypto::derive_key(passphrase, &salt)?;
     let blob = crypto::encrypt(&key, &json)?;
@@ -36,15 +37,15 @@ pub fn encrypt_target_config(passphrase: &str, config: &TargetConfig) -> Result<
 
 /// Reverses `encrypt_target_config`. Returns a clear error (never a panic)
 /// for a wrong passphrase or corrupted stored value.
-pub fn decrypt_target_config(passphrase: &str, stored_b64: &str) -> Result<TargetConfig, String> {
-    let combined = B64.decode(stored_b64).map_err(|e| format!("Corrupted stored config: {}", e))?;
+pub fn decrypt_target_config(passphrase: &str, stored_b64: &str) -> Result<TargetConfig, AppError> {
+    let combined = B64.decode(stored_b64).map_err(|e| AppError::integrity(format!("Corrupted stored config: {}", e)))?;
     if combined.len() < 16 {
-        return Err("Corrupted stored config: too short".to_string());
+        return Err(AppError::integrity("Corrupted stored config: too short"));
     }
     let (salt, blob) = combined.split_at(16);
     let key = crypto::derive_key(passphrase, salt)?;
     let json = crypto::decrypt(&key, blob)?;
-    serde_json::from_slice(&json).map_err(|e| format!("Corrupted stored config JSON: {}", e))
+    serde_json::from_slice(&json).map_err(|e| AppError::integrity(format!("Corrupted stored config JSON: {}", e)))
 }
 
 /// Validates a standard 5-field cron expression (`minute hour day month
@@ -0,0 +1,74 @@
+// WP-95: accession number templates. Anyone signed in can list them and
+// preview a pattern (the specimen form shows the scheme in use); saving and
+// deleting need `accession.configure`.
+use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::accession::{self, AccessionPreview, AccessionTemplate, SaveAccessionTemplateRequest, SequenceReset, SplitSuffix};
+use crate::db::queries;
+use crate::error::AppError;
+use crate::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_accession_templates(state: State<AppState>, token: String) -> Result<Vec<AccessionTemplate>, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    accession::list(&db.conn)
+}
+
+/// Create or replace the template for a lab profile, or for one species in it.
+#[tauri::command]
+pub fn save_accession_template(
+    state: State<AppState>,
+    token: String,
+    request: SaveAccessionTemplateRequest,
+) -> Result<AccessionTemplate, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::AccessionConfigure)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let template = accession::save(&db.conn, &user.id, &profile, &request)?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "save", "accession_template", Some(&template.id),
+        None, Some(&template.pattern),
+        Some(&format!(
+            "Accession template for {} / {}: reset {}, split suffix {}",
+            template.lab_profile,
+            template.species_code.as_deref().unwrap_or("all species"),
+            template.sequence_reset.as_str(),
+            template.split_suffix.as_str(),
+        )),
+    ).ok();
+    Ok(template)
+}
+
+/// Delete a template. New specimens fall back to the profile's template or
+/// the built-in scheme; existing accession numbers are untouched.
+#[tauri::command]
+pub fn delete_accession_template(state: State<AppState>, token: String, id: String) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::AccessionConfigure)?;
+    let template = accession::delete(&db.conn, &id)?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "delete", "accession_template", Some(&id),
+        Some(&template.pattern), None, Some("Accession template deleted"),
+    ).ok();
+    Ok(())
+}
+
+/// Sample numbers for a pattern, without saving it.
+#[tauri::command]
+pub fn preview_accession_template(
+    state: State<AppState>,
+    token: String,
+    pattern: String,
+    sequence_reset: SequenceReset,
+    split_suffix: SplitSuffix,
+    species_code: String,
+    date: String,
+) -> Result<AccessionPreview, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    accession::preview(&pattern, sequence_reset, split_suffix, &species_code, &date)
+}
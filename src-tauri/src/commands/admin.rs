@@ -1,5 +1,7 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::AppState;
 use rusqlite::{params, Connection};
 use tauri::State;
@@ -21,7 +23,7 @@ pub fn get_degraded_reason(state: State<AppState>) -> Option<String> {
 pub fn get_lab_profile(
     state: State<AppState>,
     token: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -40,20 +42,18 @@ pub fn set_lab_profile(
     token: String,
     profile: String,
     confirmation: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
 
-    if !user.role.is_admin() {
-        return Err("Only admins can change the lab profile".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::LabProfile)?;
 
     let allowed = ["plant_tissue_culture", "cell_culture", "mycology"];
     if !allowed.contains(&profile.as_str()) {
-        return Err(format!(
+        return Err(AppError::validation("profile", format!(
             "Invalid lab profile '{}'. Allowed values: plant_tissue_culture, cell_culture, mycology",
             profile
-        ));
+        )));
     }
 
     let specimen_count: i64 = db.conn
@@ -92,32 +92,30 @@ pub fn reset_database(
     state: State<AppState>,
     token: String,
     confirmation: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
 
-    if !user.role.is_admin() {
-        return Err("Only admins can reset the database".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SystemReset)?;
 
     if confirmation.trim() != "RESET DATABASE" {
-        return Err("Confirmation phrase did not match. Type exactly: RESET DATABASE".to_string());
+        return Err(AppError::validation("confirmation", "Confirmation phrase did not match. Type exactly: RESET DATABASE"));
     }
 
     // Delete operational data in dependency order
-    db.conn.execute("DELETE FROM media_hormones", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM subcultures", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM specimen_tags", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM compliance_records", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM reminders", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM attachments", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM specimens", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM media_batches", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM prepared_solutions", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM inventory_items", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM qr_scans", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM error_logs", []).map_err(|e| e.to_string())?;
-    db.conn.execute("DELETE FROM audit_log", []).map_err(|e| e.to_string())?;
+    db.conn.execute("DELETE FROM media_hormones", [])?;
+    db.conn.execute("DELETE FROM subcultures", [])?;
+    db.conn.execute("DELETE FROM specimen_tags", [])?;
+    db.conn.execute("DELETE FROM compliance_records", [])?;
+    db.conn.execute("DELETE FROM reminders", [])?;
+    db.conn.execute("DELETE FROM attachments", [])?;
+    db.conn.execute("DELETE FROM specimens", [])?;
+    db.conn.execute("DELETE FROM media_batches", [])?;
+    db.conn.execute("DELETE FROM prepared_solutions", [])?;
+    db.conn.execute("DELETE FROM inventory_items", [])?;
+    db.conn.execute("DELETE FROM qr_scans", [])?;
+    db.conn.execute("DELETE FROM error_logs", [])?;
+    db.conn.execute("DELETE FROM audit_log", [])?;
 
     // Log the reset itself (audit entry won't survive if audit_log was cleared,
     // but we log it here for completeness if any partial rollback occurs)
@@ -152,22 +150,19 @@ pub fn reset_database(
 pub fn load_demo_data(
     state: State<AppState>,
     token: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can load demo data".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SystemDemoData)?;
 
     let existing: i64 = db.conn
         .query_row("SELECT COUNT(*) FROM specimens", [], |r| r.get(0))
         .unwrap_or(0);
     if existing > 0 {
-        return Err(
+        return Err(AppError::conflict(
             "Demo data can only be loaded into an empty lab (no specimens exist yet). \
-             Use Admin → Reset Database first if you want to start over."
-                .to_string(),
-        );
+             Use Admin → Reset Database first if you want to start over.",
+        ));
     }
 
     // Demo cultures are filed under the lab that is active when they are loaded,
@@ -192,11 +187,10 @@ pub fn load_demo_data(
     }
 
     if roots.is_empty() {
-        return Err(
+        return Err(AppError::invalid(
             "No default species found in the registry. \
-             Add species before loading demo data."
-                .to_string(),
-        );
+             Add species before loading demo data.",
+        ));
     }
 
     let tx = db.conn
@@ -267,6 +261,11 @@ pub fn load_demo_data(
                 None, None,
                 Some(format!("Demo passage {} on {}", passage, date).as_str()),
             ).map_err(|e| format!("Failed to audit demo subculture: {}", e))?;
+            let (sev_type, sev_payload) =
+                crate::signed_ledger::lifecycle::passage(&sp_id, i64::from(passage), "passage");
+            crate::signed_ledger::try_append_signed_event(
+                conn, &user.id, sev_type, "specimen", Some(&sp_id), &sev_payload,
+            );
             total_subcultures += 1;
         }
 
@@ -336,6 +335,10 @@ pub fn load_demo_data(
                 conn, Some(&user.id), "subcultured", "specimen", Some(&child_id),
                 None, None, Some("Demo passage 1 on 2026-04-15"),
             ).map_err(|e| format!("Failed to audit demo split subculture: {}", e))?;
+            let (sev_type, sev_payload) = crate::signed_ledger::lifecycle::passage(&child_id, 1, "passage");
+            crate::signed_ledger::try_append_signed_event(
+                conn, &user.id, sev_type, "specimen", Some(&child_id), &sev_payload,
+            );
             total_subcultures += 1;
         }
     }
@@ -13,6 +13,8 @@ use tauri::State;
 
 use crate::ai::ollama::{self, OllamaConfig};
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::error::AppError;
 use crate::models::ai::{AiSuggestion, AnalyzePhotoRequest, SummarizeNotesRequest};
 use crate::AppState;
 
@@ -53,7 +55,7 @@ pub struct AiConfigResponse {
 }
 
 #[tauri::command]
-pub fn get_ai_config(state: State<AppState>, token: String) -> Result<AiConfigResponse, String> {
+pub fn get_ai_config(state: State<AppState>, token: String) -> Result<AiConfigResponse, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let cfg = load_config(&db.conn);
@@ -73,12 +75,10 @@ pub fn set_ai_config(
     base_url: String,
     text_model: String,
     vision_model: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can change the AI configuration".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AiConfigure)?;
     // Only two providers are supported, both fully local; reject anything else
     // so a typo can't silently disable AI assistance.
     let provider_value = if ollama::is_openai_compatible(&provider) {
@@ -96,8 +96,13 @@ pub fn set_ai_config(
             "INSERT INTO app_settings (key, value, updated_at) VALUES (?1, ?2, datetime('now')) \
              ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
             params![key, value],
-        ).map_err(|e| e.to_string())?;
+        )?;
     }
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "update", "app_settings", Some("ai_config"),
+        None, Some(&format!("{} @ {} (text: {}, vision: {})", provider_value, base_url, text_model, vision_model)),
+        Some("AI assistant configuration changed"),
+    ).ok();
     Ok(())
 }
 
@@ -132,7 +137,7 @@ fn model_present(installed: &[String], wanted: &str) -> bool {
 }
 
 #[tauri::command]
-pub fn get_ai_status(state: State<AppState>, token: String) -> Result<AiStatusResponse, String> {
+pub fn get_ai_status(state: State<AppState>, token: String) -> Result<AiStatusResponse, AppError> {
     // Read config under the lock, then RELEASE it before the network probe.
     // `ollama::list_models` is a blocking TcpStream call with a 120s timeout;
     // holding the app-wide DB mutex across it would freeze every other command
@@ -179,25 +184,24 @@ fn insert_suggestion(
     prompt: &str,
     suggestion: &str,
     created_by: &str,
-) -> Result<AiSuggestion, String> {
+) -> Result<AiSuggestion, AppError> {
     let id = uuid::Uuid::new_v4().to_string();
     conn.execute(
         "INSERT INTO ai_suggestions (id, entity_type, entity_id, kind, model_name, prompt, suggestion, status, created_by) \
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'pending', ?8)",
         params![id, entity_type, entity_id, kind, model_name, prompt, suggestion, created_by],
     ).map_err(|e| format!("Failed to store AI suggestion: {}", e))?;
-    conn.query_row("SELECT * FROM ai_suggestions WHERE id = ?1", [&id], row_to_suggestion)
-        .map_err(|e| e.to_string())
+    Ok(conn.query_row("SELECT * FROM ai_suggestions WHERE id = ?1", [&id], row_to_suggestion)?)
 }
 
-fn fetch_notes(conn: &rusqlite::Connection, entity_type: &str, entity_id: &str) -> Result<Option<String>, String> {
-    let table = match entity_type {
-        "specimen" => "specimens",
-        "subculture" => "subcultures",
-        other => return Err(format!("Unsupported entity_type '{}'", other)),
+fn fetch_notes(conn: &rusqlite::Connection, entity_type: &str, entity_id: &str) -> Result<Option<String>, AppError> {
+    let (table, entity) = match entity_type {
+        "specimen" => ("specimens", "specimen"),
+        "subculture" => ("subcultures", "subculture"),
+        other => return Err(AppError::validation("entity_type", format!("Unsupported entity_type '{}'", other))),
     };
     conn.query_row(&format!("SELECT notes FROM {} WHERE id = ?1", table), [entity_id], |r| r.get(0))
-        .map_err(|_| format!("{} not found", entity_type))
+        .map_err(|_| AppError::not_found(entity, format!("{} not found", entity_type)).with_id(entity_id))
 }
 
 /// "Summarize Notes" — feeds the entity's current free-text notes to the
@@ -207,19 +211,17 @@ pub fn summarize_notes(
     state: State<AppState>,
     token: String,
     request: SummarizeNotesRequest,
-) -> Result<AiSuggestion, String> {
+) -> Result<AiSuggestion, AppError> {
     // Gather everything the network call needs under the lock, then drop it
     // before calling Ollama (blocking, 120s timeout) so no other command blocks
     // on the app-wide DB mutex. Re-lock only to persist the suggestion.
     let (user_id, cfg, notes) = {
         let db = state.db();
         let user = auth_service::validate_session(&db, &token)?;
-        if !user.role.can_write() {
-            return Err("Insufficient permissions".to_string());
-        }
+        auth_service::require_capability(&db, &user, Capability::AiUse)?;
         let notes = fetch_notes(&db.conn, &request.entity_type, &request.entity_id)?
             .filter(|n| !n.trim().is_empty())
-            .ok_or_else(|| "There are no notes to summarize yet".to_string())?;
+            .ok_or_else(|| AppError::invalid("There are no notes to summarize yet"))?;
         (user.id, load_config(&db.conn), notes)
     };
 
@@ -239,20 +241,18 @@ pub fn summarize_notes(
 /// suggestion (to be attached to the specimen's notes, or copied into the
 /// next subculture's notes field manually).
 #[tauri::command]
-pub fn suggest_passage_comment(state: State<AppState>, token: String, specimen_id: String) -> Result<AiSuggestion, String> {
+pub fn suggest_passage_comment(state: State<AppState>, token: String, specimen_id: String) -> Result<AiSuggestion, AppError> {
     // Collect the passage history + config under the lock, drop it before the
     // Ollama call (blocking, 120s), then re-lock to persist. See get_ai_status.
     let (user_id, cfg, history) = {
         let db = state.db();
         let user = auth_service::validate_session(&db, &token)?;
-        if !user.role.can_write() {
-            return Err("Insufficient permissions".to_string());
-        }
+        auth_service::require_capability(&db, &user, Capability::AiUse)?;
 
         let mut stmt = db.conn.prepare(
             "SELECT passage_number, date, health_status, contamination_flag, notes, observations \
              FROM subcultures WHERE specimen_id = ?1 ORDER BY passage_number DESC LIMIT 5",
-        ).map_err(|e| e.to_string())?;
+        )?;
         let history: Vec<String> = stmt
             .query_map([&specimen_id], |r| {
                 let passage: i64 = r.get(0)?;
@@ -268,13 +268,12 @@ pub fn suggest_passage_comment(state: State<AppState>, token: String, specimen_i
                     notes.unwrap_or_default(),
                     observations.unwrap_or_default(),
                 ))
-            })
-            .map_err(|e| e.to_string())?
+            })?
             .filter_map(|r| r.ok())
             .collect();
 
         if history.is_empty() {
-            return Err("This specimen has no passage history to base a suggestion on".to_string());
+            return Err(AppError::invalid("This specimen has no passage history to base a suggestion on"));
         }
         (user.id, load_config(&db.conn), history)
     };
@@ -301,21 +300,19 @@ pub fn analyze_photo_for_contamination(
     state: State<AppState>,
     token: String,
     request: AnalyzePhotoRequest,
-) -> Result<AiSuggestion, String> {
+) -> Result<AiSuggestion, AppError> {
     // Resolve the attachment + config under the lock, drop it before the Ollama
     // vision call (blocking, 120s), then re-lock to persist. See get_ai_status.
     let (user_id, cfg, file_path, entity_type, entity_id) = {
         let db = state.db();
         let user = auth_service::validate_session(&db, &token)?;
-        if !user.role.can_write() {
-            return Err("Insufficient permissions".to_string());
-        }
+        auth_service::require_capability(&db, &user, Capability::AiUse)?;
 
         let (file_path, entity_type, entity_id): (String, String, String) = db.conn.query_row(
             "SELECT file_path, entity_type, entity_id FROM attachments WHERE id = ?1",
             [&request.attachment_id],
             |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
-        ).map_err(|_| "Attachment not found".to_string())?;
+        ).map_err(|_| AppError::not_found("attachment", "Attachment not found"))?;
         (user.id, load_config(&db.conn), file_path, entity_type, entity_id)
     };
 
@@ -339,15 +336,14 @@ pub fn list_ai_suggestions(
     token: String,
     entity_type: String,
     entity_id: String,
-) -> Result<Vec<AiSuggestion>, String> {
+) -> Result<Vec<AiSuggestion>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let mut stmt = db.conn.prepare(
         "SELECT * FROM ai_suggestions WHERE entity_type = ?1 AND entity_id = ?2 ORDER BY created_at DESC",
-    ).map_err(|e| e.to_string())?;
+    )?;
     let rows = stmt
-        .query_map(params![entity_type, entity_id], row_to_suggestion)
-        .map_err(|e| e.to_string())?
+        .query_map(params![entity_type, entity_id], row_to_suggestion)?
         .filter_map(|r| r.ok())
         .collect();
     Ok(rows)
@@ -359,27 +355,25 @@ pub fn list_ai_suggestions(
 /// suggestion `approved`. All manual note-editing workflows are completely
 /// unaffected — this is just another writer of the same column.
 #[tauri::command]
-pub fn approve_ai_suggestion(state: State<AppState>, token: String, suggestion_id: String) -> Result<(), String> {
+pub fn approve_ai_suggestion(state: State<AppState>, token: String, suggestion_id: String) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AiUse)?;
 
     let sug = db.conn.query_row("SELECT * FROM ai_suggestions WHERE id = ?1", [&suggestion_id], row_to_suggestion)
-        .map_err(|_| "AI suggestion not found".to_string())?;
+        .map_err(|_| AppError::not_found("ai_suggestion", "AI suggestion not found"))?;
     if sug.status != "pending" {
-        return Err(format!("This suggestion was already {}", sug.status));
+        return Err(AppError::conflict(format!("This suggestion was already {}", sug.status)));
     }
 
-    let table = match sug.entity_type.as_str() {
-        "specimen" => "specimens",
-        "subculture" => "subcultures",
-        other => return Err(format!("Cannot approve a suggestion for entity_type '{}'", other)),
+    let (table, entity) = match sug.entity_type.as_str() {
+        "specimen" => ("specimens", "specimen"),
+        "subculture" => ("subcultures", "subculture"),
+        other => return Err(AppError::invalid(format!("Cannot approve a suggestion for entity_type '{}'", other))),
     };
     let existing: Option<String> = db.conn.query_row(
         &format!("SELECT notes FROM {} WHERE id = ?1", table), [&sug.entity_id], |r| r.get(0),
-    ).map_err(|_| format!("{} not found", sug.entity_type))?;
+    ).map_err(|_| AppError::not_found(entity, format!("{} not found", sug.entity_type)).with_id(&sug.entity_id))?;
 
     let appended = format!(
         "{}[AI-assisted, approved by {}] {}",
@@ -401,26 +395,28 @@ pub fn approve_ai_suggestion(state: State<AppState>, token: String, suggestion_i
     db.conn.execute(
         "UPDATE ai_suggestions SET status = 'approved', reviewed_by = ?1, reviewed_at = datetime('now') WHERE id = ?2",
         params![user.id, suggestion_id],
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub fn reject_ai_suggestion(state: State<AppState>, token: String, suggestion_id: String) -> Result<(), String> {
+pub fn reject_ai_suggestion(state: State<AppState>, token: String, suggestion_id: String) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AiUse)?;
     let updated = db.conn.execute(
         "UPDATE ai_suggestions SET status = 'rejected', reviewed_by = ?1, reviewed_at = datetime('now') \
          WHERE id = ?2 AND status = 'pending'",
         params![user.id, suggestion_id],
-    ).map_err(|e| e.to_string())?;
+    )?;
     if updated == 0 {
-        return Err("Suggestion not found or already reviewed".to_string());
+        return Err(AppError::not_found("ai_suggestion", "Suggestion not found or already reviewed"));
     }
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "reject", "ai_suggestion", Some(&suggestion_id),
+        None, None, Some("AI suggestion rejected"),
+    ).ok();
     Ok(())
 }
 
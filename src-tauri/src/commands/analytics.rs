@@ -6,7 +6,10 @@
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::analytics::{self, TimeRange};
+use crate::db::contamination_sources::{self, Attribution, ContaminationSources};
+use crate::error::AppError;
 use crate::AppState;
 
 #[tauri::command]
@@ -14,10 +17,10 @@ pub fn get_specimen_growth_rate(
     state: State<AppState>,
     token: String,
     time_range: String,
-) -> Result<Vec<analytics::TimeSeriesPoint>, String> {
+) -> Result<Vec<analytics::TimeSeriesPoint>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    analytics::specimen_growth_rate(&db.conn, TimeRange::parse(&time_range)).map_err(|e| e.to_string())
+    analytics::specimen_growth_rate(&db.conn, TimeRange::parse(&time_range)).map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -26,11 +29,11 @@ pub fn get_subculture_frequency_trend(
     token: String,
     time_range: String,
     species_id: Option<String>,
-) -> Result<Vec<analytics::TimeSeriesPoint>, String> {
+) -> Result<Vec<analytics::TimeSeriesPoint>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     analytics::subculture_frequency_trend(&db.conn, TimeRange::parse(&time_range), species_id.as_deref())
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -38,10 +41,10 @@ pub fn get_contamination_rate_trend(
     state: State<AppState>,
     token: String,
     time_range: String,
-) -> Result<Vec<analytics::TimeSeriesPoint>, String> {
+) -> Result<Vec<analytics::TimeSeriesPoint>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    analytics::contamination_rate_trend(&db.conn, TimeRange::parse(&time_range)).map_err(|e| e.to_string())
+    analytics::contamination_rate_trend(&db.conn, TimeRange::parse(&time_range)).map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -49,10 +52,10 @@ pub fn get_passage_success_rate(
     state: State<AppState>,
     token: String,
     time_range: String,
-) -> Result<analytics::PassageSuccessRate, String> {
+) -> Result<analytics::PassageSuccessRate, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    analytics::passage_success_rate(&db.conn, TimeRange::parse(&time_range)).map_err(|e| e.to_string())
+    analytics::passage_success_rate(&db.conn, TimeRange::parse(&time_range)).map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -60,10 +63,10 @@ pub fn get_media_batch_efficiency(
     state: State<AppState>,
     token: String,
     time_range: String,
-) -> Result<Vec<analytics::MediaBatchEfficiency>, String> {
+) -> Result<Vec<analytics::MediaBatchEfficiency>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    analytics::media_batch_efficiency(&db.conn, TimeRange::parse(&time_range)).map_err(|e| e.to_string())
+    analytics::media_batch_efficiency(&db.conn, TimeRange::parse(&time_range)).map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -71,20 +74,20 @@ pub fn get_strain_performance(
     state: State<AppState>,
     token: String,
     species_id: String,
-) -> Result<Vec<analytics::StrainPerformance>, String> {
+) -> Result<Vec<analytics::StrainPerformance>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    analytics::strain_performance(&db.conn, &species_id).map_err(|e| e.to_string())
+    analytics::strain_performance(&db.conn, &species_id).map_err(AppError::from)
 }
 
 #[tauri::command]
 pub fn get_cryo_utilization(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<analytics::CryoUtilization>, String> {
+) -> Result<Vec<analytics::CryoUtilization>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    analytics::cryo_utilization(&db.conn).map_err(|e| e.to_string())
+    analytics::cryo_utilization(&db.conn).map_err(AppError::from)
 }
 
 /// Supervisor/admin only — see ROADMAP.md WP-58 on why this report is
@@ -94,13 +97,37 @@ pub fn get_technician_activity(
     state: State<AppState>,
     token: String,
     time_range: String,
-) -> Result<Vec<analytics::TechnicianActivity>, String> {
+) -> Result<Vec<analytics::TechnicianActivity>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can view technician activity".to_string());
-    }
-    analytics::technician_activity(&db.conn, TimeRange::parse(&time_range)).map_err(|e| e.to_string())
+    auth_service::require_capability(&db, &user, Capability::AnalyticsTeam)?;
+    analytics::technician_activity(&db.conn, TimeRange::parse(&time_range)).map_err(AppError::from)
+}
+
+/// WP-103: odds ratios of contamination per media batch, technician,
+/// location, vessel type, hood and day in the active lab, with the likely
+/// sources ranked. Technicians are only analysed for callers who may see the
+/// Technician Activity report; for everyone else the factor is withheld.
+#[tauri::command]
+pub fn get_contamination_sources(
+    state: State<AppState>,
+    token: String,
+    time_range: String,
+    contaminant_type: Option<String>,
+    attribution: Option<Attribution>,
+) -> Result<ContaminationSources, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    let include_technician = auth_service::require_capability(&db, &user, Capability::AnalyticsTeam).is_ok();
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    contamination_sources::analyse(
+        &db.conn,
+        &profile,
+        TimeRange::parse(&time_range),
+        attribution.unwrap_or_default(),
+        contaminant_type.as_deref(),
+        include_technician,
+    )
 }
 
 /// KPI summary strip: total active specimens, passages this week,
@@ -118,7 +145,7 @@ pub struct AnalyticsKpiSummary {
 }
 
 #[tauri::command]
-pub fn get_analytics_kpi_summary(state: State<AppState>, token: String) -> Result<AnalyticsKpiSummary, String> {
+pub fn get_analytics_kpi_summary(state: State<AppState>, token: String) -> Result<AnalyticsKpiSummary, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -211,7 +238,7 @@ pub fn get_analytics_kpi_summary(state: State<AppState>, token: String) -> Resul
 /// treats every panel as visible-by-default in that case. Readable by any
 /// authenticated user (the layout is shared, not per-user).
 #[tauri::command]
-pub fn get_analytics_panel_config(state: State<AppState>, token: String) -> Result<String, String> {
+pub fn get_analytics_panel_config(state: State<AppState>, token: String) -> Result<String, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     Ok(crate::db::queries::read_setting(&db.conn, "analytics_panel_config", "{}"))
@@ -223,19 +250,21 @@ pub fn get_analytics_panel_config(state: State<AppState>, token: String) -> Resu
 /// settings) prevents a read-only or technician account from silently
 /// reshaping the analytics view for the whole lab.
 #[tauri::command]
-pub fn set_analytics_panel_config(state: State<AppState>, token: String, config_json: String) -> Result<(), String> {
+pub fn set_analytics_panel_config(state: State<AppState>, token: String, config_json: String) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can change the shared analytics layout".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AnalyticsLayout)?;
     // Cheap validity check — reject non-JSON before persisting.
     serde_json::from_str::<serde_json::Value>(&config_json)
-        .map_err(|e| format!("Invalid panel config JSON: {}", e))?;
+        .map_err(|e| AppError::validation("config_json", format!("Invalid panel config JSON: {}", e)))?;
     db.conn.execute(
         "INSERT INTO app_settings (key, value, updated_at) VALUES ('analytics_panel_config', ?1, datetime('now')) \
          ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
-        [config_json],
-    ).map_err(|e| e.to_string())?;
+        [&config_json],
+    )?;
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "update", "app_settings", Some("analytics_panel_config"),
+        None, Some(&config_json), Some("Shared analytics layout changed"),
+    ).ok();
     Ok(())
 }
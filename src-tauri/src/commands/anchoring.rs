@@ -4,14 +4,23 @@
 // and recording/verifying its txid are supervisory trust-layer actions, so they
 // require the manage role (admin or supervisor), matching audit-checkpoint
 // creation. Listing anchors is read-only for any authenticated user.
+//
+// WP-82 adds broadcasting through the lab's own node (`anchoring::node_rpc`).
+// Its connection settings are admin-only, like the SMTP settings.
+//
+// WP-83 adds SPV inclusion proofs (`anchoring::spv`): captured from the node
+// once an anchor confirms, and checkable by any user without the node.
+use rusqlite::Connection;
 use tauri::State;
 
+use crate::anchoring::node_rpc::{self, AnchorPollResult, NodeInfo, NodeRpcConfig, RpcTransport, SetNodeRpcConfigRequest};
+use crate::anchoring::spv::{self, SpvVerification};
 use crate::anchoring::{build_payload_preview, store, AnchorPayloadPreview};
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::error::AppError;
 use crate::AppState;
 
-const MANAGE_ONLY: &str = "Insufficient permissions — admin or supervisor role required.";
-
 /// Preview the exact `OP_RETURN` bytes for a checkpoint's Merkle root without
 /// writing anything. Handy for inspecting what would be published before
 /// committing a `prepared` anchor row.
@@ -21,12 +30,10 @@ pub fn preview_checkpoint_anchor_payload(
     token: String,
     checkpoint_id: String,
     chain_name: Option<String>,
-) -> Result<AnchorPayloadPreview, String> {
+) -> Result<AnchorPayloadPreview, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AnchorManage)?;
     let merkle_root: String = db
         .conn
         .query_row(
@@ -34,7 +41,7 @@ pub fn preview_checkpoint_anchor_payload(
             rusqlite::params![&checkpoint_id],
             |r| r.get(0),
         )
-        .map_err(|_| format!("Checkpoint '{}' not found", checkpoint_id))?;
+        .map_err(|_| AppError::not_found("audit_checkpoint", format!("Checkpoint '{}' not found", checkpoint_id)).with_id(&checkpoint_id))?;
     build_payload_preview(&merkle_root, chain_name.as_deref().unwrap_or("dogecoin"))
 }
 
@@ -46,12 +53,10 @@ pub fn prepare_checkpoint_anchor(
     token: String,
     checkpoint_id: String,
     chain_name: Option<String>,
-) -> Result<store::CheckpointAnchor, String> {
+) -> Result<store::CheckpointAnchor, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AnchorManage)?;
     let anchor = store::prepare_anchor(
         &db.conn,
         &checkpoint_id,
@@ -79,12 +84,10 @@ pub fn record_checkpoint_anchor(
     token: String,
     anchor_id: String,
     txid: String,
-) -> Result<store::CheckpointAnchor, String> {
+) -> Result<store::CheckpointAnchor, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AnchorManage)?;
     let anchor = store::record_anchor_txid(&db.conn, &anchor_id, &txid)?;
     crate::db::queries::log_audit(
         &db.conn,
@@ -108,12 +111,10 @@ pub fn verify_checkpoint_anchor(
     token: String,
     anchor_id: String,
     op_return_hex: String,
-) -> Result<store::AnchorVerifyResult, String> {
+) -> Result<store::AnchorVerifyResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AnchorManage)?;
     let result = store::verify_anchor(&db.conn, &anchor_id, &op_return_hex)?;
     crate::db::queries::log_audit(
         &db.conn,
@@ -135,8 +136,172 @@ pub fn list_checkpoint_anchors(
     state: State<AppState>,
     token: String,
     checkpoint_id: Option<String>,
-) -> Result<Vec<store::CheckpointAnchor>, String> {
+) -> Result<Vec<store::CheckpointAnchor>, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::list_anchors(&db.conn, checkpoint_id.as_deref())
 }
+
+/// WP-82: the node RPC settings, without the password.
+#[tauri::command]
+pub fn get_anchor_node_config(state: State<AppState>, token: String) -> Result<NodeRpcConfig, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::AnchorManage)?;
+    node_rpc::get_config(&db.conn)
+}
+
+/// WP-82: save the node RPC settings. Admin only, since they carry the
+/// credentials of a wallet that spends the lab's coins on fees.
+#[tauri::command]
+pub fn set_anchor_node_config(
+    state: State<AppState>,
+    token: String,
+    request: SetNodeRpcConfigRequest,
+) -> Result<NodeRpcConfig, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::AnchorNodeConfig)?;
+    node_rpc::set_config(&db.conn, &request)?;
+    crate::db::queries::log_audit(
+        &db.conn,
+        Some(&user.id),
+        "update",
+        "anchor_node_config",
+        None,
+        None,
+        Some(&format!(
+            "enabled={} url={} wallet={} min_confirmations={}",
+            request.enabled,
+            request.rpc_url.trim(),
+            request.wallet.as_deref().unwrap_or(""),
+            request.min_confirmations
+        )),
+        Some("Anchoring node settings changed"),
+    )
+    .ok();
+    node_rpc::get_config(&db.conn)
+}
+
+/// WP-82: check the configured node answers, and report its chain and height.
+#[tauri::command]
+pub fn test_anchor_node(state: State<AppState>, token: String) -> Result<NodeInfo, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::AnchorManage)?;
+    let rpc = node_rpc::connect(&db.conn)?;
+    node_rpc::node_info(&rpc)
+}
+
+/// WP-82: fund, sign and broadcast a prepared anchor through the lab's node.
+#[tauri::command]
+pub fn broadcast_checkpoint_anchor(
+    state: State<AppState>,
+    token: String,
+    anchor_id: String,
+) -> Result<store::CheckpointAnchor, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::AnchorManage)?;
+    let rpc = node_rpc::connect(&db.conn)?;
+    let anchor = node_rpc::broadcast_anchor(&db.conn, &rpc, &anchor_id)?;
+    crate::db::queries::log_audit(
+        &db.conn,
+        Some(&user.id),
+        "anchor_submitted",
+        "checkpoint_anchor",
+        Some(&anchor.id),
+        None,
+        anchor.txid.as_deref(),
+        Some(&format!("Broadcast through the lab node for checkpoint {}", anchor.checkpoint_id)),
+    )
+    .ok();
+    Ok(anchor)
+}
+
+/// WP-82: poll every submitted anchor and confirm those with enough
+/// confirmations. Shared by the command and the background scheduler, which
+/// passes no user.
+pub fn poll_node_anchors(
+    conn: &Connection,
+    rpc: &dyn RpcTransport,
+    user_id: Option<&str>,
+) -> Result<Vec<AnchorPollResult>, AppError> {
+    let results = node_rpc::poll_submitted_anchors(conn, rpc)?;
+    for r in results.iter().filter(|r| r.confirmed) {
+        crate::db::queries::log_audit(
+            conn,
+            user_id,
+            "anchor_confirmed",
+            "checkpoint_anchor",
+            Some(&r.anchor_id),
+            None,
+            r.txid.as_deref(),
+            Some(&format!(
+                "Confirmed by the lab node at {} confirmations: {}",
+                r.confirmations.unwrap_or(0),
+                r.message
+            )),
+        )
+        .ok();
+    }
+    Ok(results)
+}
+
+/// WP-82: poll the node for the confirmations of every submitted anchor now,
+/// rather than waiting for the scheduler.
+#[tauri::command]
+pub fn poll_checkpoint_anchors(state: State<AppState>, token: String) -> Result<Vec<AnchorPollResult>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::AnchorManage)?;
+    let rpc = node_rpc::connect(&db.conn)?;
+    poll_node_anchors(&db.conn, &rpc, Some(&user.id))
+}
+
+/// WP-83: capture (or re-capture) the SPV proof of a confirmed anchor from the
+/// lab node — for anchors confirmed by a manual paste, or whose proof could not
+/// be fetched when the poll confirmed them. Refused proofs are not stored.
+#[tauri::command]
+pub fn capture_anchor_spv_proof(
+    state: State<AppState>,
+    token: String,
+    anchor_id: String,
+) -> Result<SpvVerification, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::AnchorManage)?;
+    let rpc = node_rpc::connect(&db.conn)?;
+    let verification = node_rpc::capture_spv_proof(&db.conn, &rpc, &anchor_id)?;
+    if verification.pow_ok {
+        let proof = spv::get_proof(&db.conn, &anchor_id)?;
+        crate::db::queries::log_audit(
+            &db.conn,
+            Some(&user.id),
+            "spv_proof_captured",
+            "checkpoint_anchor",
+            Some(&anchor_id),
+            None,
+            proof.as_ref().map(|p| p.block_hash.as_str()),
+            Some(&verification.message),
+        )
+        .ok();
+    }
+    Ok(verification)
+}
+
+/// WP-83: re-verify a stored SPV proof offline against the anchor's
+/// checkpoint root. Read-only, so any authenticated user may run it.
+#[tauri::command]
+pub fn verify_anchor_spv_proof(
+    state: State<AppState>,
+    token: String,
+    anchor_id: String,
+) -> Result<SpvVerification, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    let anchor = store::get_anchor(&db.conn, &anchor_id)?;
+    let proof = spv::get_proof(&db.conn, &anchor_id)?
+        .ok_or_else(|| AppError::not_found("spv_proof", "No SPV proof has been captured for this anchor yet.").with_id(&anchor_id))?;
+    Ok(spv::verify_spv_proof(&proof, &anchor.merkle_root))
+}
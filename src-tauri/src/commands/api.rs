@@ -0,0 +1,196 @@
+// WP-90: the local REST API's command bridge and its settings.
+//
+// `dispatch` calls the Tauri command a route names, with arguments decoded
+// from the JSON the server built. It is the only place the HTTP layer touches
+// the command layer, and it adds one check: an API token (WP-92) needs the
+// `read` scope for routes without a capability. Otherwise the command
+// validates the session, checks capabilities, masks and audits exactly as it
+// does for the webview.
+use crate::api::{self, server::ApiServer};
+use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::commands::{auth, media, reminders, sensors, specimens, subcultures};
+use crate::db::queries;
+use crate::error::AppError;
+use crate::AppState;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+
+/// One argument, in the camelCase the webview's `invoke()` uses. A missing
+/// argument decodes as `null`, so optional ones may be left out.
+fn arg<T: DeserializeOwned>(args: &Map<String, Value>, name: &'static str) -> Result<T, AppError> {
+    serde_json::from_value(args.get(name).cloned().unwrap_or(Value::Null))
+        .map_err(|e| AppError::validation(name, format!("Missing or invalid '{}': {}", name, e)))
+}
+
+fn out<T: Serialize>(result: Result<T, AppError>) -> Result<Value, AppError> {
+    Ok(serde_json::to_value(result?)?)
+}
+
+/// Call the command behind `operation`. Every operation in
+/// `api::routes::ROUTES` has an arm here; a test checks it.
+pub fn dispatch(app: &AppHandle, operation: &str, a: Map<String, Value>) -> Result<Value, AppError> {
+    let state = app.state::<AppState>();
+    let token = || arg::<String>(&a, "token");
+    if api::routes::needs_read_scope(operation) {
+        let token = token()?;
+        if auth_service::api_tokens::is_api_token(&token) {
+            let db = state.db();
+            auth_service::require_read_scope(&auth_service::validate_session(&db, &token)?)?;
+        }
+    }
+    match operation {
+        "login" => {
+            let device = arg::<Option<String>>(&a, "device")?.or_else(|| Some("Local API".to_string()));
+            out(auth::login(state, arg(&a, "username")?, arg(&a, "password")?, device))
+        }
+        "verify_login_mfa" => out(auth::verify_login_mfa(state, token()?, arg(&a, "code")?)),
+        "logout" => out(auth::logout(state, token()?)),
+        "get_current_user" => out(auth::get_current_user(state, token()?)),
+        "list_specimens" => out(specimens::list_specimens(state, token()?, arg(&a, "page")?, arg(&a, "perPage")?)),
+        "search_specimens" => out(specimens::search_specimens(state, token()?, arg(&a, "paramsInput")?)),
+        "create_specimen" => out(specimens::create_specimen(state, token()?, arg(&a, "request")?)),
+        "initiate_specimen_batch" => out(specimens::initiate_specimen_batch(state, token()?, arg(&a, "request")?)),
+        "get_specimen" => out(specimens::get_specimen(state, token()?, arg(&a, "id")?)),
+        "update_specimen" => out(specimens::update_specimen(state, token()?, arg(&a, "request")?)),
+        "list_subcultures" => out(subcultures::list_subcultures(
+            state, token()?, arg(&a, "specimenId")?, arg(&a, "page")?, arg(&a, "perPage")?,
+        )),
+        "list_environmental_readings" => out(sensors::list_environmental_readings(
+            state, token()?, arg(&a, "specimenId")?, arg(&a, "limit")?,
+        )),
+        "create_subculture" => out(subcultures::create_subculture(state, token()?, arg(&a, "request")?)),
+        "list_media" => out(media::list_media(state, token()?)),
+        "create_media_batch" => out(media::create_media_batch(state, token()?, arg(&a, "request")?)),
+        "get_media_batch" => out(media::get_media_batch(state, token()?, arg(&a, "id")?)),
+        "list_reminders" => out(reminders::list_reminders(state, token()?)),
+        "create_reminder" => out(reminders::create_reminder(state, token()?, arg(&a, "request")?)),
+        "dismiss_reminder" => out(reminders::dismiss_reminder(
+            state, token()?, arg(&a, "id")?, arg(&a, "snooze")?, arg(&a, "snoozeDays")?,
+        )),
+        "create_environmental_reading" => out(sensors::create_environmental_reading(state, token()?, arg(&a, "request")?)),
+        "ingest_sensor_payload" => out(sensors::ingest_sensor_payload(
+            state, token()?, arg(&a, "specimenId")?, arg(&a, "subcultureId")?, arg(&a, "source")?, arg(&a, "rawPayload")?,
+        )),
+        other => Err(AppError::not_found("operation", format!("'{}' is not available over the API", other))),
+    }
+}
+
+/// Stop the running server, if any, and start one for `config` when it is
+/// enabled. Returns the address it listens on.
+fn restart(app: &AppHandle, config: &api::ApiConfig) -> Result<Option<String>, AppError> {
+    let state = app.state::<AppState>();
+    let mut slot = state.api_server.lock().unwrap_or_else(|p| p.into_inner());
+    // Drop first: the new listener may want the same port.
+    slot.take();
+    if !config.enabled {
+        return Ok(None);
+    }
+    let handle = app.clone();
+    let server = ApiServer::start(
+        config.socket_addr(),
+        api::server::Options {
+            allowed_origins: config.allowed_origins.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+        Arc::new(move |operation: &str, args: Map<String, Value>| dispatch(&handle, operation, args)),
+    )?;
+    let addr = server.local_addr().to_string();
+    *slot = Some(server);
+    Ok(Some(addr))
+}
+
+/// Start the API at launch when it is switched on. A busy port is logged
+/// rather than failing startup; Settings → Local API shows it as stopped.
+pub fn start_configured(app: &AppHandle) {
+    let config = {
+        let state = app.state::<AppState>();
+        let db = state.db();
+        api::get_config(&db.conn)
+    };
+    match config.and_then(|c| restart(app, &c)) {
+        Ok(Some(addr)) => eprintln!("Local API listening on http://{}", addr),
+        Ok(None) => {}
+        Err(e) => eprintln!("Local API not started: {}", e),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiStatus {
+    #[serde(flatten)]
+    pub config: api::ApiConfig,
+    /// `host:port` while the server is running.
+    pub listening_on: Option<String>,
+}
+
+fn status(state: &AppState, config: api::ApiConfig) -> ApiStatus {
+    let listening_on = state
+        .api_server
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_ref()
+        .map(|s| s.local_addr().to_string());
+    ApiStatus { config, listening_on }
+}
+
+#[tauri::command]
+pub fn get_api_status(state: State<AppState>, token: String) -> Result<ApiStatus, AppError> {
+    let config = {
+        let db = state.db();
+        let user = auth_service::validate_session(&db, &token)?;
+        auth_service::require_capability(&db, &user, Capability::SystemSettings)?;
+        api::get_config(&db.conn)?
+    };
+    Ok(status(&state, config))
+}
+
+/// Save the API settings and apply them at once. When the new settings cannot
+/// be started (say the port is taken), nothing is saved and the previous
+/// server is put back.
+#[tauri::command]
+pub fn set_api_config(
+    app: AppHandle,
+    state: State<AppState>,
+    token: String,
+    config: api::ApiConfig,
+) -> Result<ApiStatus, AppError> {
+    let (user_id, old) = {
+        let db = state.db();
+        let user = auth_service::validate_session(&db, &token)?;
+        auth_service::require_capability(&db, &user, Capability::SystemSettings)?;
+        api::validate(&config)?;
+        (user.id, api::get_config(&db.conn)?)
+    };
+    // The database lock is not held here: requests in flight on the old
+    // server need it to finish.
+    if let Err(e) = restart(&app, &config) {
+        restart(&app, &old).ok();
+        return Err(AppError::conflict(e));
+    }
+    let db = state.db();
+    let saved = api::set_config(&db.conn, &config)?;
+    queries::log_audit(
+        &db.conn, Some(&user_id), "update", "api_config", None,
+        Some(&old.describe()), Some(&saved.describe()), None,
+    ).ok();
+    drop(db);
+    Ok(status(&state, saved))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn every_route_has_a_dispatch_arm() {
+        let source = include_str!("api.rs");
+        for route in crate::api::routes::ROUTES {
+            assert!(
+                source.contains(&format!("\"{}\" =>", route.operation)),
+                "{} is routed but not dispatched",
+                route.operation
+            );
+        }
+    }
+}
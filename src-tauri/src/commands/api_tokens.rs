@@ -0,0 +1,99 @@
+// WP-92: service accounts and their API tokens. Listing needs `users.view`;
+// creating accounts and issuing or revoking tokens needs `users.manage` and,
+// like every account change, is limited to roles the caller could grant.
+// Tokens cannot be used to mint more tokens: these commands refuse a caller
+// who is itself authenticated by one.
+use crate::auth as auth_service;
+use crate::auth::api_tokens::{self, ApiTokenInfo, CreateApiTokenRequest, CreateServiceAccountRequest, CreatedApiToken};
+use crate::auth::roles::Capability;
+use crate::db::queries;
+use crate::error::{reason, AppError};
+use crate::models::user::User;
+use crate::AppState;
+use tauri::State;
+
+/// A signed-in administrator with `users.manage`, not a machine client.
+fn token_manager(db: &crate::db::Database, token: &str) -> Result<User, AppError> {
+    let caller = auth_service::validate_session(db, token)?;
+    auth_service::require_capability(db, &caller, Capability::UsersManage)?;
+    if caller.token_scope.is_some() {
+        return Err(AppError::gated(
+            reason::TOKEN_SCOPE,
+            "Insufficient permissions — API tokens cannot manage service accounts or tokens.",
+        ));
+    }
+    Ok(caller)
+}
+
+#[tauri::command]
+pub fn list_api_tokens(state: State<AppState>, token: String) -> Result<Vec<ApiTokenInfo>, AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersView)?;
+    api_tokens::list(&db.conn)
+}
+
+/// Create a service account: a role but no password. Returns its id.
+#[tauri::command]
+pub fn create_service_account(
+    state: State<AppState>,
+    token: String,
+    request: CreateServiceAccountRequest,
+) -> Result<String, AppError> {
+    let db = state.db();
+    let caller = token_manager(&db, &token)?;
+    let role = auth_service::roles::existing_role(&db.conn, &request.role)?;
+    auth_service::roles::ensure_can_grant(&db.conn, &caller.role, &role)?;
+    let id = api_tokens::create_service_account(&db.conn, &request)?;
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "create", "user", Some(&id),
+        None, Some(request.username.trim()), Some(&format!("Service account created with role {}", role.as_str())),
+    ).ok();
+    Ok(id)
+}
+
+/// Issue a token for a service account. The raw token is in the result and
+/// nowhere else.
+#[tauri::command]
+pub fn create_api_token(
+    state: State<AppState>,
+    token: String,
+    request: CreateApiTokenRequest,
+) -> Result<CreatedApiToken, AppError> {
+    let db = state.db();
+    let caller = token_manager(&db, &token)?;
+    let role = api_tokens::service_account_role(&db.conn, &request.service_account_id)?;
+    auth_service::roles::ensure_can_grant(&db.conn, &caller.role, &role)?;
+    let created = api_tokens::create(&db.conn, &request, &caller.id)?;
+    let info = &created.info;
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "create", "api_token", Some(&info.id),
+        None, Some(&info.scopes.join(" ")),
+        Some(&format!(
+            "Token '{}' ({}…) issued to {}, {}",
+            info.name,
+            info.hint,
+            info.username,
+            info.expires_at.as_deref().map(|e| format!("expires {} UTC", e)).unwrap_or_else(|| "no expiry".to_string())
+        )),
+    ).ok();
+    Ok(created)
+}
+
+#[tauri::command]
+pub fn revoke_api_token(state: State<AppState>, token: String, token_id: String) -> Result<(), AppError> {
+    let db = state.db();
+    let caller = token_manager(&db, &token)?;
+    let info = api_tokens::get(&db.conn, &token_id)?;
+    auth_service::roles::ensure_can_grant(
+        &db.conn, &caller.role, &auth_service::roles::existing_role(&db.conn, &info.role)?,
+    )?;
+    if !api_tokens::revoke(&db.conn, &token_id)? {
+        return Ok(());
+    }
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "revoke", "api_token", Some(&token_id), None, None,
+        Some(&format!("Token '{}' ({}…) of {} revoked", info.name, info.hint, info.username)),
+    ).ok();
+    Ok(())
+}
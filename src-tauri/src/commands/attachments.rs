@@ -1,5 +1,7 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::AppState;
 use base64::engine::general_purpose::STANDARD as B64;
 use base64::Engine as _;
@@ -41,23 +43,23 @@ const ATTACHABLE_ENTITY_TYPES: &[&str] = &[
 ];
 
 /// Root directory for all attachment storage: `<db dir>/attachments`.
-fn attachments_root() -> Result<std::path::PathBuf, String> {
+fn attachments_root() -> Result<std::path::PathBuf, AppError> {
     let base = crate::db::Database::db_path();
     let parent = base.parent().ok_or_else(|| {
-        "Could not determine attachments directory: database path has no parent".to_string()
+        AppError::internal("Could not determine attachments directory: database path has no parent")
     })?;
     Ok(parent.join("attachments"))
 }
 
-fn attachments_dir(entity_type: &str, entity_id: &str) -> Result<std::path::PathBuf, String> {
+fn attachments_dir(entity_type: &str, entity_id: &str) -> Result<std::path::PathBuf, AppError> {
     if !ATTACHABLE_ENTITY_TYPES.contains(&entity_type) {
-        return Err(format!("Unknown attachment target type '{}'", entity_type));
+        return Err(AppError::validation("entity_type", format!("Unknown attachment target type '{}'", entity_type)));
     }
     // Every entity id in this schema is a UUID. Parsing it rejects `..`, path
     // separators, drive letters and absolute paths in a single step, and does
     // so by construction rather than by blocklist.
     uuid::Uuid::parse_str(entity_id)
-        .map_err(|_| format!("Attachment target id '{}' is not a valid id", entity_id))?;
+        .map_err(|_| AppError::validation("entity_id", format!("Attachment target id '{}' is not a valid id", entity_id)))?;
 
     let root = attachments_root()?;
     let dir = root.join(entity_type).join(entity_id);
@@ -66,7 +68,7 @@ fn attachments_dir(entity_type: &str, entity_id: &str) -> Result<std::path::Path
     // a containment assertion is cheap and survives someone later relaxing the
     // id format to something less strict than a UUID.
     if !dir.starts_with(&root) {
-        return Err("Refusing to write outside the attachments directory".to_string());
+        return Err(AppError::validation("entity_id", "Refusing to write outside the attachments directory"));
     }
     Ok(dir)
 }
@@ -78,12 +80,13 @@ fn attachments_dir(entity_type: &str, entity_id: &str) -> Result<std::path::Path
 /// disk, which would make `get_attachment_data` an arbitrary-file-read
 /// primitive. Validating on read as well as on write means those rows fail
 /// closed instead of being trusted because they are already stored.
-fn ensure_within_attachments_root(file_path: &str) -> Result<(), String> {
+fn ensure_within_attachments_root(file_path: &str) -> Result<(), AppError> {
     let root = attachments_root()?;
     if !std::path::Path::new(file_path).starts_with(&root) {
-        return Err("Attachment is stored outside the attachments directory and \
-                    will not be read. It may predate a security fix — re-upload it."
-            .to_string());
+        return Err(AppError::integrity(
+            "Attachment is stored outside the attachments directory and \
+             will not be read. It may predate a security fix — re-upload it.",
+        ));
     }
     Ok(())
 }
@@ -136,7 +139,7 @@ pub fn list_attachments(
     token: String,
     entity_type: String,
     entity_id: String,
-) -> Result<Vec<AttachmentMeta>, String> {
+) -> Result<Vec<AttachmentMeta>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -148,12 +151,10 @@ pub fn list_attachments(
              LEFT JOIN users u ON a.uploaded_by = u.id
              WHERE a.entity_type = ?1 AND a.entity_id = ?2
              ORDER BY a.created_at DESC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let items = stmt
-        .query_map(params![entity_type, entity_id], row_to_meta)
-        .map_err(|e| e.to_string())?
+        .query_map(params![entity_type, entity_id], row_to_meta)?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -171,24 +172,22 @@ pub fn upload_attachment(
     mime_type: String,
     data_b64: String,
     description: Option<String>,
-) -> Result<AttachmentMeta, String> {
+) -> Result<AttachmentMeta, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AttachmentManage)?;
 
     // Reject oversized uploads before decoding, so a bad payload never
     // allocates a second full-size copy in memory.
     if data_b64.len() > MAX_ATTACHMENT_B64_LEN {
-        return Err(format!(
+        return Err(AppError::validation("data_b64", format!(
             "Attachment is too large. The limit is {} MiB.",
             MAX_ATTACHMENT_BYTES / 1024 / 1024
-        ));
+        )));
     }
 
     // Decode bytes
-    let bytes = B64.decode(&data_b64).map_err(|e| format!("Base64 decode error: {}", e))?;
+    let bytes = B64.decode(&data_b64).map_err(|e| AppError::validation("data_b64", format!("Base64 decode error: {}", e)))?;
 
     // Build storage path
     let dir = attachments_dir(&entity_type, &entity_id)?;
@@ -244,7 +243,7 @@ pub fn upload_attachment(
             params![id],
             row_to_meta,
         )
-        .map_err(|e| format!("Failed to fetch created attachment: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to fetch created attachment: {}", e)))
 }
 
 #[tauri::command]
@@ -252,7 +251,7 @@ pub fn get_attachment_data(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -263,7 +262,7 @@ pub fn get_attachment_data(
             params![id],
             |r| r.get(0),
         )
-        .map_err(|_| "Attachment not found".to_string())?;
+        .map_err(|_| AppError::not_found("attachment", "Attachment not found"))?;
 
     ensure_within_attachments_root(&file_path)?;
 
@@ -276,12 +275,10 @@ pub fn delete_attachment(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AttachmentManage)?;
 
     let (file_path, file_name): (String, String) = db
         .conn
@@ -290,7 +287,7 @@ pub fn delete_attachment(
             params![id],
             |r| Ok((r.get(0)?, r.get(1)?)),
         )
-        .map_err(|_| "Attachment not found".to_string())?;
+        .map_err(|_| AppError::not_found("attachment", "Attachment not found"))?;
 
     // Remove from DB first; if file delete fails, the record is still gone — acceptable
     db.conn
@@ -345,7 +342,7 @@ mod tests {
         for evil in ["/etc/cron.d", "/tmp", "C:\\Windows\\Temp"] {
             let err = attachments_dir(evil, &id)
                 .expect_err("an absolute path must never be accepted as an entity type");
-            assert!(err.contains("Unknown attachment target type"), "got: {err}");
+            assert!(err.message().contains("Unknown attachment target type"), "got: {err}");
         }
     }
 
@@ -1,4 +1,7 @@
+use crate::anchoring::spv;
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::error::AppError;
 use crate::models::audit::*;
 use crate::models::specimen::PaginatedResponse;
 use crate::db::queries::{self, audit_canonical_bytes, compute_entry_hash, build_merkle_root};
@@ -16,12 +19,10 @@ pub fn get_audit_log(
     state: State<AppState>,
     token: String,
     search: AuditSearchParams,
-) -> Result<PaginatedResponse<AuditEntry>, String> {
+) -> Result<PaginatedResponse<AuditEntry>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AuditView)?;
 
     let pg = queries::PaginationParams {
         page: search.page.unwrap_or(1),
@@ -70,8 +71,7 @@ pub fn get_audit_log(
 
     let count_sql = format!("SELECT COUNT(*) FROM audit_log a {}", where_clause);
     let bind_refs: Vec<&dyn rusqlite::types::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
-    let total: i64 = db.conn.query_row(&count_sql, bind_refs.as_slice(), |r| r.get(0))
-        .map_err(|e| e.to_string())?;
+    let total: i64 = db.conn.query_row(&count_sql, bind_refs.as_slice(), |r| r.get(0))?;
 
     let query_sql = format!(
         "SELECT a.*, u.username
@@ -89,7 +89,7 @@ pub fn get_audit_log(
     bind_values.push(Box::new(pg.offset()));
 
     let bind_refs2: Vec<&dyn rusqlite::types::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
-    let mut stmt = db.conn.prepare(&query_sql).map_err(|e| e.to_string())?;
+    let mut stmt = db.conn.prepare(&query_sql)?;
 
     let entries = stmt.query_map(bind_refs2.as_slice(), |row| {
         Ok(AuditEntry {
@@ -108,7 +108,7 @@ pub fn get_audit_log(
             prev_hash: row.get("prev_hash")?,
             entry_hash: row.get("entry_hash")?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect::<Vec<_>>();
 
@@ -138,14 +138,12 @@ pub fn list_audit_entries_cursor(
     lineage_id: String,
     after_seq: Option<i64>,
     limit: i64,
-) -> Result<queries::CursorPage<AuditEntry>, String> {
+) -> Result<queries::CursorPage<AuditEntry>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AuditView)?;
     queries::list_audit_entries_by_cursor(&db.conn, &lineage_id, after_seq, limit)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// Verify a single audit entry by recomputing its hash from stored fields.
@@ -157,7 +155,7 @@ pub fn verify_audit_entry(
     state: State<AppState>,
     token: String,
     entry_id: String,
-) -> Result<VerifyEntryResult, String> {
+) -> Result<VerifyEntryResult, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
 
@@ -248,125 +246,10 @@ pub fn verify_audit_lineage(
     state: State<AppState>,
     token: String,
     lineage_id: String,
-) -> Result<VerifyChainResult, String> {
+) -> Result<VerifyChainResult, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
-
-    struct ChainRow {
-        chain_seq: i64,
-        user_id: Option<String>,
-        entity_type: String,
-        action: String,
-        entity_id: Option<String>,
-        created_at: String,
-        details: Option<String>,
-        prev_hash: String,
-        entry_hash: String,
-    }
-
-    let mut stmt = db.conn.prepare(
-        "SELECT chain_seq, user_id, entity_type, action, entity_id, created_at, details, prev_hash, entry_hash \
-         FROM audit_log \
-         WHERE lineage_id = ?1 AND entry_hash IS NOT NULL \
-         ORDER BY chain_seq ASC",
-    ).map_err(|e| e.to_string())?;
-
-    let rows: Vec<ChainRow> = stmt.query_map(rusqlite::params![lineage_id], |r| {
-        Ok(ChainRow {
-            chain_seq: r.get(0)?,
-            user_id: r.get(1)?,
-            entity_type: r.get(2)?,
-            action: r.get(3)?,
-            entity_id: r.get(4)?,
-            created_at: r.get(5)?,
-            details: r.get(6)?,
-            prev_hash: r.get(7)?,
-            entry_hash: r.get(8)?,
-        })
-    }).map_err(|e| e.to_string())?
-      // Strict: dropping a row here would shorten the chain, and the
-      // gap/hash checks would then report it as TAMPERING. A mapping
-      // failure must not masquerade as tamper evidence.
-      .collect::<rusqlite::Result<Vec<_>>>()
-      .map_err(|e| format!("Failed to read audit rows to verify the audit lineage: {}", e))?;
-
-    if rows.is_empty() {
-        return Ok(VerifyChainResult {
-            lineage_id,
-            ok: true,
-            checked: 0,
-            first_break_seq: None,
-            message: "No chained entries found for this lineage.".to_string(),
-        });
-    }
-
-    // For root lineages the first row's prev_hash is ZERO_HASH; for fork lineages
-    // (split specimens) it is the parent's last entry_hash. Either way, we anchor
-    // verification at the first row's own claimed prev_hash — we cannot independently
-    // verify the anchor without walking the parent lineage, but we verify every
-    // subsequent link and every entry_hash within this lineage.
-    //
-    // Previous (buggy) code used ZERO_HASH as the fixed anchor, which always
-    // reported "Chain broken at seq 1" for any forked lineage.
-    let mut prev_entry_hash = rows[0].prev_hash.clone();
-    // `idx` is the number of entries fully verified before the current row, which is
-    // the correct "checked" count on a break. (Deriving it from `chain_seq - 1`
-    // underflowed to usize::MAX when the break was on a genesis seq-0 entry.)
-    for (idx, row) in rows.iter().enumerate() {
-        // Verify the link: this row's prev_hash must equal the previous row's entry_hash
-        // (or the anchor for the very first row, which trivially passes).
-        if row.prev_hash != prev_entry_hash {
-            return Ok(VerifyChainResult {
-                lineage_id,
-                ok: false,
-                checked: idx,
-                first_break_seq: Some(row.chain_seq),
-                message: format!(
-                    "Chain broken at seq {} — prev_hash does not match the preceding entry's hash.",
-                    row.chain_seq
-                ),
-            });
-        }
-
-        // Verify the hash of this row's content.
-        let canonical = audit_canonical_bytes(
-            &lineage_id,
-            row.chain_seq,
-            &row.created_at,
-            row.user_id.as_deref().unwrap_or(""),
-            &row.entity_type,
-            row.entity_id.as_deref().unwrap_or(""),
-            &row.action,
-            row.details.as_deref().unwrap_or(""),
-        );
-        let computed = compute_entry_hash(&canonical, &row.prev_hash);
-        if computed != row.entry_hash {
-            return Ok(VerifyChainResult {
-                lineage_id,
-                ok: false,
-                checked: idx,
-                first_break_seq: Some(row.chain_seq),
-                message: format!(
-                    "Tamper detected at seq {} — stored hash does not match recomputed hash.",
-                    row.chain_seq
-                ),
-            });
-        }
-
-        prev_entry_hash = row.entry_hash.clone();
-    }
-
-    let checked = rows.len();
-    Ok(VerifyChainResult {
-        lineage_id,
-        ok: true,
-        checked,
-        first_break_seq: None,
-        message: format!(
-            "All {} entries verified — chain is intact.",
-            checked
-        ),
-    })
+    queries::verify_audit_lineage(&db.conn, lineage_id)
 }
 
 /// Create a Merkle checkpoint over a contiguous seq range of one lineage's audit chain.
@@ -381,86 +264,12 @@ pub fn create_audit_checkpoint(
     lineage_id: String,
     start_seq: Option<i64>,
     end_seq: Option<i64>,
-) -> Result<CreateCheckpointResult, String> {
+) -> Result<CreateCheckpointResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions — admin or supervisor role required.".to_string());
-    }
-
-    let actual_start: i64 = if let Some(s) = start_seq {
-        s
-    } else {
-        db.conn.query_row(
-            "SELECT MIN(chain_seq) FROM audit_log WHERE lineage_id = ?1 AND entry_hash IS NOT NULL",
-            rusqlite::params![&lineage_id],
-            |r| r.get::<_, Option<i64>>(0),
-        ).map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("No chained entries found in lineage '{}'.", lineage_id))?
-    };
-
-    let actual_end: i64 = if let Some(e) = end_seq {
-        e
-    } else {
-        db.conn.query_row(
-            "SELECT MAX(chain_seq) FROM audit_log WHERE lineage_id = ?1 AND entry_hash IS NOT NULL",
-            rusqlite::params![&lineage_id],
-            |r| r.get::<_, Option<i64>>(0),
-        ).map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("No chained entries found in lineage '{}'.", lineage_id))?
-    };
-
-    if actual_start > actual_end {
-        return Err(format!(
-            "start_seq ({}) must be ≤ end_seq ({}).", actual_start, actual_end
-        ));
-    }
-
-    let mut stmt = db.conn.prepare(
-        "SELECT entry_hash FROM audit_log \
-         WHERE lineage_id = ?1 AND chain_seq >= ?2 AND chain_seq <= ?3 AND entry_hash IS NOT NULL \
-         ORDER BY chain_seq ASC",
-    ).map_err(|e| e.to_string())?;
+    auth_service::require_capability(&db, &user, Capability::AuditCheckpoint)?;
 
-    let hashes: Vec<String> = stmt
-        .query_map(rusqlite::params![&lineage_id, actual_start, actual_end], |r| r.get::<_, String>(0))
-        .map_err(|e| e.to_string())?
-        // Strict: dropping a row here would shorten the chain, and the
-        // gap/hash checks would then report it as TAMPERING. A mapping
-        // failure must not masquerade as tamper evidence.
-        .collect::<rusqlite::Result<Vec<_>>>()
-        .map_err(|e| format!("Failed to read audit rows to build the checkpoint: {}", e))?;
-
-    if hashes.is_empty() {
-        return Err(format!(
-            "No chained entries found in lineage '{}' for seq range [{}, {}].",
-            lineage_id, actual_start, actual_end
-        ));
-    }
-
-    let entry_count = hashes.len() as i64;
-    let merkle_root = build_merkle_root(&hashes);
-    let checkpoint_id = uuid::Uuid::new_v4().to_string();
-    let created_at = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
-
-    db.conn.execute(
-        "INSERT INTO audit_checkpoints \
-         (id, lineage_id, start_seq, end_seq, entry_count, merkle_root, created_at, created_by, is_auto) \
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0)",
-        rusqlite::params![
-            &checkpoint_id, &lineage_id, actual_start, actual_end,
-            entry_count, &merkle_root, &created_at, &user.id
-        ],
-    ).map_err(|e| e.to_string())?;
-
-    Ok(CreateCheckpointResult {
-        checkpoint_id,
-        lineage_id,
-        start_seq: actual_start,
-        end_seq: actual_end,
-        entry_count,
-        merkle_root,
-    })
+    queries::create_audit_checkpoint(&db.conn, &lineage_id, start_seq, end_seq, &user.id)
 }
 
 /// Verify a stored checkpoint against the current state of the audit chain.
@@ -476,7 +285,7 @@ pub fn verify_against_checkpoint(
     state: State<AppState>,
     token: String,
     checkpoint_id: String,
-) -> Result<VerifyCheckpointResult, String> {
+) -> Result<VerifyCheckpointResult, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
 
@@ -499,7 +308,7 @@ pub fn verify_against_checkpoint(
             expected_count: r.get(3)?,
             stored_root: r.get(4)?,
         }),
-    ).map_err(|_| format!("Checkpoint '{}' not found.", checkpoint_id))?;
+    ).map_err(|_| AppError::not_found("audit_checkpoint", format!("Checkpoint '{}' not found.", checkpoint_id)).with_id(&checkpoint_id))?;
 
     struct EntryRow {
         chain_seq: i64,
@@ -518,7 +327,7 @@ pub fn verify_against_checkpoint(
          FROM audit_log \
          WHERE lineage_id = ?1 AND chain_seq >= ?2 AND chain_seq <= ?3 AND entry_hash IS NOT NULL \
          ORDER BY chain_seq ASC",
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let entries: Vec<EntryRow> = stmt
         .query_map(
@@ -534,7 +343,7 @@ pub fn verify_against_checkpoint(
                 prev_hash: r.get(7)?,
                 entry_hash: r.get(8)?,
             }),
-        ).map_err(|e| e.to_string())?
+        )?
         // Strict: dropping a row here would shorten the chain, and the
         // gap/hash checks would then report it as TAMPERING. A mapping
         // failure must not masquerade as tamper evidence.
@@ -648,7 +457,7 @@ pub fn list_audit_checkpoints(
     state: State<AppState>,
     token: String,
     lineage_id: Option<String>,
-) -> Result<Vec<AuditCheckpoint>, String> {
+) -> Result<Vec<AuditCheckpoint>, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
 
@@ -657,7 +466,7 @@ pub fn list_audit_checkpoints(
             "SELECT id, lineage_id, start_seq, end_seq, entry_count, merkle_root, \
                     created_at, created_by, anchored_txid, is_auto, auto_source \
              FROM audit_checkpoints WHERE lineage_id = ?1 ORDER BY created_at DESC",
-        ).map_err(|e| e.to_string())?;
+        )?;
         let collected: Vec<AuditCheckpoint> = stmt
             .query_map(rusqlite::params![lid], |r| Ok(AuditCheckpoint {
                 id: r.get(0)?, lineage_id: r.get(1)?, start_seq: r.get(2)?,
@@ -665,8 +474,7 @@ pub fn list_audit_checkpoints(
                 created_at: r.get(6)?, created_by: r.get(7)?, anchored_txid: r.get(8)?,
                 is_auto: r.get::<_, i64>(9).map(|v| v != 0).unwrap_or(false),
                 auto_source: r.get(10)?,
-            }))
-            .map_err(|e| e.to_string())?
+            }))?
             .filter_map(|r| r.ok())
             .collect();
         collected
@@ -675,7 +483,7 @@ pub fn list_audit_checkpoints(
             "SELECT id, lineage_id, start_seq, end_seq, entry_count, merkle_root, \
                     created_at, created_by, anchored_txid, is_auto, auto_source \
              FROM audit_checkpoints ORDER BY created_at DESC LIMIT 100",
-        ).map_err(|e| e.to_string())?;
+        )?;
         let collected: Vec<AuditCheckpoint> = stmt
             .query_map([], |r| Ok(AuditCheckpoint {
                 id: r.get(0)?, lineage_id: r.get(1)?, start_seq: r.get(2)?,
@@ -683,8 +491,7 @@ pub fn list_audit_checkpoints(
                 created_at: r.get(6)?, created_by: r.get(7)?, anchored_txid: r.get(8)?,
                 is_auto: r.get::<_, i64>(9).map(|v| v != 0).unwrap_or(false),
                 auto_source: r.get(10)?,
-            }))
-            .map_err(|e| e.to_string())?
+            }))?
             .filter_map(|r| r.ok())
             .collect();
         collected
@@ -708,7 +515,7 @@ pub fn export_audit_proof(
     state: State<AppState>,
     token: String,
     checkpoint_id: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
 
@@ -725,7 +532,7 @@ pub fn export_audit_proof(
             merkle_root: r.get(4)?,
             created_at: r.get(5)?,
         }),
-    ).map_err(|_| format!("Checkpoint '{}' not found.", checkpoint_id))?;
+    ).map_err(|_| AppError::not_found("audit_checkpoint", format!("Checkpoint '{}' not found.", checkpoint_id)).with_id(&checkpoint_id))?;
 
     struct EntryRow {
         chain_seq: i64,
@@ -744,7 +551,7 @@ pub fn export_audit_proof(
          FROM audit_log \
          WHERE lineage_id = ?1 AND chain_seq >= ?2 AND chain_seq <= ?3 AND entry_hash IS NOT NULL \
          ORDER BY chain_seq ASC",
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let rows: Vec<EntryRow> = stmt.query_map(
         rusqlite::params![&cp.lineage_id, cp.start_seq, cp.end_seq],
@@ -759,7 +566,7 @@ pub fn export_audit_proof(
             prev_hash: r.get(7)?,
             entry_hash: r.get(8)?,
         }),
-    ).map_err(|e| e.to_string())?
+    )?
     // Strict: dropping a row here would shorten the chain, and the
     // gap/hash checks would then report it as TAMPERING. A mapping
     // failure must not masquerade as tamper evidence.
@@ -767,10 +574,10 @@ pub fn export_audit_proof(
     .map_err(|e| format!("Failed to read audit rows to export the audit proof: {}", e))?;
 
     if rows.is_empty() {
-        return Err(format!(
+        return Err(AppError::integrity(format!(
             "No entries found for checkpoint '{}' — lineage '{}' seq [{}, {}].",
             checkpoint_id, cp.lineage_id, cp.start_seq, cp.end_seq
-        ));
+        )));
     }
 
     let leaf_hashes: Vec<String> = rows.iter().map(|r| r.entry_hash.clone()).collect();
@@ -803,11 +610,12 @@ pub fn export_audit_proof(
     let proof = PortableMerkleProof {
         version: "1".to_string(),
         exported_at: chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+        anchors: spv::proofs_for_checkpoint(&db.conn, &checkpoint_id)?,
         checkpoint: cp,
         entries,
     };
 
-    serde_json::to_string_pretty(&proof).map_err(|e| e.to_string())
+    serde_json::to_string_pretty(&proof).map_err(AppError::from)
 }
 
 /// Verify an exported proof JSON without requiring any database access.
@@ -821,12 +629,12 @@ pub fn verify_exported_proof(
     state: State<AppState>,
     token: String,
     proof_json: String,
-) -> Result<VerifyProofResult, String> {
+) -> Result<VerifyProofResult, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
 
     let proof: PortableMerkleProof = serde_json::from_str(&proof_json)
-        .map_err(|e| format!("Invalid proof JSON: {}", e))?;
+        .map_err(|e| AppError::validation("proof_json", format!("Invalid proof JSON: {}", e)))?;
 
     Ok(verify_proof_data(&proof))
 }
@@ -841,6 +649,7 @@ pub fn verify_proof_data(proof: &PortableMerkleProof) -> VerifyProofResult {
             merkle_root: proof.checkpoint.merkle_root.clone(),
             failure_reason: Some(format!("Version '{}' not supported; expected '1'.", proof.version)),
             failed_seq: None,
+            anchors_verified: 0,
         };
     }
 
@@ -856,6 +665,7 @@ pub fn verify_proof_data(proof: &PortableMerkleProof) -> VerifyProofResult {
             merkle_root: proof.checkpoint.merkle_root.clone(),
             failure_reason: Some("Entry count mismatch".to_string()),
             failed_seq: None,
+            anchors_verified: 0,
         };
     }
 
@@ -873,6 +683,7 @@ pub fn verify_proof_data(proof: &PortableMerkleProof) -> VerifyProofResult {
                 merkle_root: proof.checkpoint.merkle_root.clone(),
                 failure_reason: Some("Content hash mismatch".to_string()),
                 failed_seq: Some(entry.chain_seq),
+                anchors_verified: 0,
             };
         }
     }
@@ -894,6 +705,7 @@ pub fn verify_proof_data(proof: &PortableMerkleProof) -> VerifyProofResult {
                 merkle_root: proof.checkpoint.merkle_root.clone(),
                 failure_reason: Some("Entries out of order".to_string()),
                 failed_seq: Some(curr.chain_seq),
+                anchors_verified: 0,
             };
         }
         if curr.prev_hash != prev.entry_hash {
@@ -907,6 +719,7 @@ pub fn verify_proof_data(proof: &PortableMerkleProof) -> VerifyProofResult {
                 merkle_root: proof.checkpoint.merkle_root.clone(),
                 failure_reason: Some("Chain link broken".to_string()),
                 failed_seq: Some(curr.chain_seq),
+                anchors_verified: 0,
             };
         }
     }
@@ -922,19 +735,54 @@ pub fn verify_proof_data(proof: &PortableMerkleProof) -> VerifyProofResult {
             merkle_root: proof.checkpoint.merkle_root.clone(),
             failure_reason: Some("Merkle root mismatch".to_string()),
             failed_seq: None,
+            anchors_verified: 0,
         };
     }
 
+    // Stage 4: each anchor's transaction commits to this root and is in a
+    // block carrying real proof-of-work. A consistent proof with too little
+    // work is no sign of tampering, but it does not count as verified.
+    let mut low_work = 0;
+    for anchor in &proof.anchors {
+        let v = spv::verify_spv_proof(anchor, &computed_root);
+        if v.pow_ok && !v.work_ok {
+            low_work += 1;
+        } else if !v.ok {
+            return VerifyProofResult {
+                ok: false,
+                message: format!("On-chain anchor {} failed SPV verification — {}", anchor.txid, v.message),
+                entry_count: n,
+                merkle_root: computed_root,
+                failure_reason: Some("Anchor SPV proof invalid".to_string()),
+                failed_seq: None,
+                anchors_verified: 0,
+            };
+        }
+    }
+    let anchors = proof.anchors.len() as i64 - low_work;
+
     VerifyProofResult {
         ok: true,
         message: format!(
-            "Proof verified — all {} {} are intact and the Merkle root matches the checkpoint.",
-            n, if n == 1 { "entry" } else { "entries" }
+            "Proof verified — all {} {} are intact and the Merkle root matches the checkpoint.{}{}",
+            n,
+            if n == 1 { "entry" } else { "entries" },
+            match anchors {
+                0 => String::new(),
+                1 => " The on-chain anchor's SPV proof verifies.".to_string(),
+                _ => format!(" {} on-chain anchors' SPV proofs verify.", anchors),
+            },
+            match low_work {
+                0 => String::new(),
+                1 => " One anchor's header carries less than its chain's minimum work and is unverified: low work.".to_string(),
+                _ => format!(" {} anchors' headers carry less than their chain's minimum work and are unverified: low work.", low_work),
+            }
         ),
         entry_count: n,
         merkle_root: computed_root,
         failure_reason: None,
         failed_seq: None,
+        anchors_verified: anchors,
     }
 }
 
@@ -943,7 +791,7 @@ pub fn verify_proof_data(proof: &PortableMerkleProof) -> VerifyProofResult {
 pub fn get_auto_checkpoint_config(
     state: State<AppState>,
     token: String,
-) -> Result<AutoCheckpointConfig, String> {
+) -> Result<AutoCheckpointConfig, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
 
@@ -961,15 +809,13 @@ pub fn set_auto_checkpoint_config(
     state: State<AppState>,
     token: String,
     config: AutoCheckpointConfig,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions — admin or supervisor role required.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AuditCheckpoint)?;
 
     if config.interval < 0 {
-        return Err("interval must be non-negative".to_string());
+        return Err(AppError::validation("interval", "interval must be non-negative"));
     }
 
     let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
@@ -981,12 +827,19 @@ pub fn set_auto_checkpoint_config(
         db.conn.execute(
             "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES (?1, ?2, ?3)",
             rusqlite::params![key, val, &now],
-        ).map_err(|e| e.to_string())?;
+        )?;
     }
     db.conn.execute(
         "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES ('auto_checkpoint_interval', ?1, ?2)",
         rusqlite::params![config.interval.to_string(), &now],
-    ).map_err(|e| e.to_string())?;
+    )?;
+
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "update", "app_settings", Some("auto_checkpoint"),
+        None,
+        Some(&format!("enabled={} on_backup={} interval={}", config.enabled, config.on_backup, config.interval)),
+        Some("Auto-checkpoint configuration changed"),
+    ).ok();
 
     Ok(())
 }
@@ -999,12 +852,10 @@ pub fn set_auto_checkpoint_config(
 pub fn run_auto_checkpoint(
     state: State<AppState>,
     token: String,
-) -> Result<AutoCheckpointResult, String> {
+) -> Result<AutoCheckpointResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions — admin or supervisor role required.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::AuditCheckpoint)?;
 
     let enabled = queries::read_setting(&db.conn, "auto_checkpoint_enabled", "1") == "1";
     if !enabled {
@@ -1032,8 +883,7 @@ pub fn run_auto_checkpoint(
         |r| r.get(0),
     ).unwrap_or(0);
 
-    let created = queries::auto_checkpoint_lineages(&db.conn, &user.id, "entry_count", interval)
-        .map_err(|e| e.to_string())?;
+    let created = queries::auto_checkpoint_lineages(&db.conn, &user.id, "entry_count", interval)?;
 
     let checkpoints_created = created.len();
     let details = if created.is_empty() {
@@ -1105,6 +955,7 @@ mod tests {
                     }],
                 },
             ],
+            anchors: vec![],
         }
     }
 
@@ -1149,4 +1000,26 @@ mod tests {
         assert_eq!(result.failure_reason.as_deref(), Some("Merkle root mismatch"));
         assert_eq!(result.failed_seq, None);
     }
+
+    #[test]
+    fn proof_verify_checks_anchor_spv_proofs() {
+        let mut proof = make_valid_proof();
+        let root = proof.checkpoint.merkle_root.clone();
+        proof.anchors.push(crate::anchoring::spv::tests::mined_block(&root, "bitcoin").0);
+        let result = verify_proof_data(&proof);
+        assert!(result.ok, "{}", result.message);
+        // The fixture is mined at regtest difficulty: consistent, but not verified.
+        assert_eq!(result.anchors_verified, 0);
+        assert!(result.message.contains("unverified: low work"), "{}", result.message);
+
+        // An anchor for some other root does not vouch for this checkpoint.
+        proof.anchors.push(crate::anchoring::spv::tests::mined_block(&"ab".repeat(32), "bitcoin").0);
+        let result = verify_proof_data(&proof);
+        assert!(!result.ok);
+        assert_eq!(result.failure_reason.as_deref(), Some("Anchor SPV proof invalid"));
+
+        // Exports without anchors still omit the field.
+        proof.anchors.clear();
+        assert!(!serde_json::to_string(&proof).unwrap().contains("anchors"));
+    }
 }
@@ -1,45 +1,53 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::user::*;
 use crate::AppState;
 use tauri::State;
 
-/// The roles the system recognises. Module-level so `create_user` and
-/// `update_user_role` validate against the same list — they previously
-/// disagreed, with create_user passing an unchecked string straight to the DB.
-const VALID_ROLES: &[&str] = &["admin", "supervisor", "tech", "guest"];
 
 #[tauri::command]
-pub fn login(state: State<AppState>, username: String, password: String) -> Result<LoginResponse, String> {
-    // Check the lockout BEFORE taking the DB lock and before hashing, so a
-    // guessing loop cannot hold the global mutex or burn CPU on bcrypt.
-    if let Err(e) = state.login_throttle.check(&username) {
-        let db = state.db();
-        let remaining = state
-            .login_throttle
-            .lock_remaining(&username)
-            .map(|d| d.as_secs() / 60)
-            .unwrap_or(0);
-        queries::log_audit(
-            &db.conn, None, "login_blocked", "user", None, None, Some(&username),
-            Some(&format!("Locked out after repeated failures; ~{} minute(s) remaining", remaining)),
-        ).ok();
-        return Err(e);
-    }
+pub fn login(
+    state: State<AppState>,
+    username: String,
+    password: String,
+    device: Option<String>,
+) -> Result<LoginResponse, AppError> {
+    let db = state.db();
+    let auth_service::SignIn { user, token, mfa } =
+        auth_service::sign_in(&db, &username, &password, device.as_deref())?;
+
+    Ok(LoginResponse {
+        must_change_password: user.must_change_password,
+        mfa_required: mfa.enrolled,
+        mfa_enrollment_required: mfa.enrollment_required,
+        token,
+        user: UserPublic {
+            id: user.id,
+            username: user.username,
+            display_name: user.display_name,
+            email: user.email,
+            role: user.role.as_str().to_string(),
+            is_active: user.is_active,
+            auth_source: user.auth_source,
+            access_expires_at: user.access_expires_at,
+        },
+    })
+}
 
+/// WP-84: second step of a login for a user with TOTP enabled. `code` is the
+/// six-digit authenticator code or one of the user's recovery codes. Failures
+/// count against the same lockout as wrong passwords.
+#[tauri::command]
+pub fn verify_login_mfa(state: State<AppState>, token: String, code: String) -> Result<LoginResponse, AppError> {
     let db = state.db();
-    let user = auth_service::authenticate(&db, &username, &password).inspect_err(|e| {
-        state.login_throttle.record_failure(&username);
-        queries::log_audit(&db.conn, None, "login_failed", "user", None, None, Some(&username), Some(e.as_str())).ok();
-    })?;
-    state.login_throttle.clear(&username);
-    let token = auth_service::create_session(&db, &user.id)?;
-
-    queries::log_audit(&db.conn, Some(&user.id), "login", "user", Some(&user.id), None, None, None)
-        .ok();
+    let user = auth_service::sign_in_mfa(&db, &token, &code)?;
 
     Ok(LoginResponse {
         must_change_password: user.must_change_password,
+        mfa_required: false,
+        mfa_enrollment_required: false,
         token,
         user: UserPublic {
             id: user.id,
@@ -48,12 +56,14 @@ pub fn login(state: State<AppState>, username: String, password: String) -> Resu
             email: user.email,
             role: user.role.as_str().to_string(),
             is_active: user.is_active,
+            auth_source: user.auth_source,
+            access_expires_at: user.access_expires_at,
         },
     })
 }
 
 #[tauri::command]
-pub fn get_current_user(state: State<AppState>, token: String) -> Result<UserPublic, String> {
+pub fn get_current_user(state: State<AppState>, token: String) -> Result<UserPublic, AppError> {
     let db = state.db();
     // Allow-password-change variant: a user who still owes a forced change must
     // be able to fetch their own identity so the forced-change screen can render.
@@ -65,26 +75,26 @@ pub fn get_current_user(state: State<AppState>, token: String) -> Result<UserPub
         email: user.email,
         role: user.role.as_str().to_string(),
         is_active: user.is_active,
+        auth_source: user.auth_source,
+        access_expires_at: user.access_expires_at,
     })
 }
 
 #[tauri::command]
-pub fn logout(state: State<AppState>, token: String) -> Result<(), String> {
+pub fn logout(state: State<AppState>, token: String) -> Result<(), AppError> {
     let db = state.db();
     auth_service::invalidate_session(&db, &token)
 }
 
 #[tauri::command]
-pub fn list_users(state: State<AppState>, token: String) -> Result<Vec<UserPublic>, String> {
+pub fn list_users(state: State<AppState>, token: String) -> Result<Vec<UserPublic>, AppError> {
     let db = state.db();
     let caller = auth_service::validate_session(&db, &token)?;
-    if !caller.role.can_manage() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &caller, Capability::UsersView)?;
 
     let mut stmt = db.conn.prepare(
-        "SELECT id, username, display_name, email, role, is_active FROM users ORDER BY username"
-    ).map_err(|e| e.to_string())?;
+        "SELECT id, username, display_name, email, role, is_active, auth_source, access_expires_at FROM users ORDER BY username"
+    )?;
 
     let users = stmt.query_map([], |row| {
         Ok(UserPublic {
@@ -94,8 +104,10 @@ pub fn list_users(state: State<AppState>, token: String) -> Result<Vec<UserPubli
             email: row.get(3)?,
             role: row.get(4)?,
             is_active: row.get::<_, i32>(5)? != 0,
+            auth_source: row.get(6)?,
+            access_expires_at: row.get(7)?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect();
 
@@ -103,31 +115,34 @@ pub fn list_users(state: State<AppState>, token: String) -> Result<Vec<UserPubli
 }
 
 #[tauri::command]
-pub fn create_user(state: State<AppState>, token: String, request: CreateUserRequest) -> Result<UserPublic, String> {
+pub fn create_user(state: State<AppState>, token: String, request: CreateUserRequest) -> Result<UserPublic, AppError> {
     let db = state.db();
     let caller = auth_service::validate_session(&db, &token)?;
-    if !caller.role.is_admin() {
-        return Err("Only admins can create users".to_string());
-    }
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
 
     // Same policy as change_password — enforced here rather than left to the DB
     // CHECK constraint so the caller gets a readable message instead of a raw
     // SQL error, and so a weak provisioned password is impossible rather than
     // merely discouraged.
-    auth_service::validate_password(&request.password)?;
-    if !VALID_ROLES.contains(&request.role.as_str()) {
-        return Err(format!(
-            "Invalid role '{}'. Must be one of: admin, supervisor, tech, guest",
-            request.role
-        ));
-    }
+    let policy = auth_service::policy::get(&db.conn)?;
+    auth_service::policy::check_new_password(
+        &db.conn,
+        &policy,
+        &auth_service::policy::breach_list_dir(&db),
+        None,
+        &request.password,
+    )?;
+    // Checked here rather than left to the foreign key so the caller gets a
+    // readable message, and so nobody hands out a role stronger than their own.
+    let role = auth_service::roles::existing_role(&db.conn, &request.role)?;
+    auth_service::roles::ensure_can_grant(&db.conn, &caller.role, &role)?;
     if request.username.trim().is_empty() {
-        return Err("Username is required".to_string());
+        return Err(AppError::validation("username", "Username is required"));
     }
 
     let id = uuid::Uuid::new_v4().to_string();
     let hash = bcrypt::hash(&request.password, bcrypt::DEFAULT_COST)
-        .map_err(|e| format!("Password hashing failed: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Password hashing failed: {}", e)))?;
 
     // New accounts start under a forced password change: the admin who typed
     // the initial password knows it, so it is a shared secret until the user
@@ -137,6 +152,7 @@ pub fn create_user(state: State<AppState>, token: String, request: CreateUserReq
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1)",
         rusqlite::params![id, request.username, hash, request.display_name, request.email, request.role],
     ).map_err(|e| format!("Failed to create user: {}", e))?;
+    auth_service::policy::record_password_set(&db.conn, &id, &hash)?;
 
     queries::log_audit(
         &db.conn, Some(&caller.id), "create", "user", Some(&id),
@@ -150,6 +166,8 @@ pub fn create_user(state: State<AppState>, token: String, request: CreateUserReq
         email: request.email,
         role: request.role,
         is_active: true,
+        auth_source: "local".to_string(),
+        access_expires_at: None,
     })
 }
 
@@ -176,22 +194,28 @@ pub fn change_password(
     token: String,
     new_password: String,
     current_password: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     // Allow-password-change variant: this is the one endpoint a user under a
     // forced change must reach in order to clear the flag.
     let user = auth_service::validate_session_allow_password_change(&db, &token)?;
+    if user.auth_source == "ldap" {
+        return Err(AppError::invalid("Your password is managed by your organisation's directory. Change it there."));
+    }
+    if user.auth_source == "service" {
+        return Err(AppError::invalid("Service accounts have no password; they authenticate with API tokens."));
+    }
 
     if !user.must_change_password {
         let current = current_password
             .filter(|p| !p.is_empty())
-            .ok_or_else(|| "Your current password is required to change it.".to_string())?;
+            .ok_or_else(|| AppError::validation("current_password", "Your current password is required to change it."))?;
         if !bcrypt::verify(&current, &user.password_hash).unwrap_or(false) {
             queries::log_audit(
                 &db.conn, Some(&user.id), "change_password_denied", "user", Some(&user.id),
                 None, None, Some("Current password did not match"),
             ).ok();
-            return Err("Your current password is incorrect.".to_string());
+            return Err(AppError::validation("current_password", "Your current password is incorrect."));
         }
     }
 
@@ -201,16 +225,26 @@ pub fn change_password(
     // not happen — an audit entry claiming the password changed when it did not
     // is worse than no entry.
     if bcrypt::verify(&new_password, &user.password_hash).unwrap_or(false) {
-        return Err("Your new password must be different from your current one.".to_string());
+        return Err(AppError::validation("new_password", "Your new password must be different from your current one."));
     }
+    // WP-89: the lab's length, breached-password and history rules.
+    let policy = auth_service::policy::get(&db.conn)?;
+    auth_service::policy::check_new_password(
+        &db.conn,
+        &policy,
+        &auth_service::policy::breach_list_dir(&db),
+        Some(&user.id),
+        &new_password,
+    )?;
 
     let hash = bcrypt::hash(&new_password, bcrypt::DEFAULT_COST)
-        .map_err(|e| format!("Password hashing failed: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Password hashing failed: {}", e)))?;
 
     db.conn.execute(
         "UPDATE users SET password_hash = ?1, must_change_password = 0, updated_at = datetime('now') WHERE id = ?2",
         rusqlite::params![hash, user.id],
     ).map_err(|e| format!("Failed to update password: {}", e))?;
+    auth_service::policy::record_password_set(&db.conn, &user.id, &hash)?;
 
     // A password change is a revocation event. The caller's own token survives
     // so they are not logged out by their own action.
@@ -218,9 +252,8 @@ pub fn change_password(
     // session must be excluded by its digest too — comparing the raw value here
     // would match nothing and log the user out of their own session while
     // leaving every other one alive, the exact inverse of the intent.
-    let revoked = db.conn.execute(
-        "DELETE FROM sessions WHERE user_id = ?1 AND token <> ?2",
-        rusqlite::params![user.id, auth_service::hash_token(&token)],
+    let revoked = auth_service::sessions::revoke_all_for_user(
+        &db.conn, &user.id, Some(&auth_service::hash_token(&token)),
     ).unwrap_or(0);
 
     let detail = if user.must_change_password {
@@ -237,15 +270,28 @@ pub fn change_password(
 }
 
 #[tauri::command]
-pub fn update_user_role(state: State<AppState>, token: String, user_id: String, new_role: String) -> Result<(), String> {
-    if !VALID_ROLES.contains(&new_role.as_str()) {
-        return Err(format!("Invalid role '{}'. Must be one of: admin, supervisor, tech, guest", new_role));
-    }
-
+pub fn update_user_role(state: State<AppState>, token: String, user_id: String, new_role: String) -> Result<(), AppError> {
     let db = state.db();
     let caller = auth_service::validate_session(&db, &token)?;
-    if !caller.role.is_admin() {
-        return Err("Only admins can change roles".to_string());
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    let role = auth_service::roles::existing_role(&db.conn, &new_role)?;
+    let (source, old_role): (String, String) = db.conn.query_row(
+        "SELECT auth_source, role FROM users WHERE id = ?1",
+        rusqlite::params![user_id],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    ).map_err(|_| AppError::not_found("user", "User not found"))?;
+    // Both directions count as a grant: demoting someone whose role you could
+    // not have given them is as much an escalation as promoting past your own.
+    auth_service::roles::ensure_can_grant(&db.conn, &caller.role, &role)?;
+    auth_service::roles::ensure_can_grant(&db.conn, &caller.role, &auth_service::roles::existing_role(&db.conn, &old_role)?)?;
+    if source == "ldap" {
+        return Err(
+            AppError::invalid("This account's role comes from its directory groups. Change the group mapping under Settings → Directory instead."),
+        );
+    }
+    // WP-92: same reason `api_tokens::create_service_account` refuses it.
+    if source == "service" && role.is_admin() {
+        return Err(AppError::validation("role", "Service accounts cannot hold the admin role."));
     }
 
     // Refuse to remove the last administrator. Nothing else in the system can
@@ -260,21 +306,409 @@ pub fn update_user_role(state: State<AppState>, token: String, user_id: String,
         ).unwrap_or(0);
         if other_admins == 0 {
             return Err(
-                "This is the last active administrator. Promote another user to admin first."
-                    .to_string(),
+                AppError::conflict("This is the last active administrator. Promote another user to admin first."),
             );
         }
     }
 
     db.conn.execute(
         "UPDATE users SET role = ?1, updated_at = datetime('now') WHERE id = ?2",
-        rusqlite::params![new_role, user_id],
+        rusqlite::params![role.as_str(), user_id],
     ).map_err(|e| format!("Failed to update role: {}", e))?;
 
     queries::log_audit(
         &db.conn, Some(&caller.id), "update_role", "user", Some(&user_id),
-        None, Some(&new_role), None,
+        Some(&old_role), Some(role.as_str()), None,
+    ).ok();
+
+    Ok(())
+}
+
+// ── WP-84: TOTP enrollment and policy ──────────────────────────────────────
+
+/// Enrollment state for the caller. Allow-variant session: a user held to
+/// enrollment by policy needs this to render the enrollment screen.
+#[tauri::command]
+pub fn get_mfa_status(state: State<AppState>, token: String) -> Result<auth_service::totp::MfaStatus, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session_allow_password_change(&db, &token)?;
+    auth_service::totp::status(&db.conn, &user.id, user.role.as_str())
+}
+
+/// Generate a new secret for the caller. Nothing changes for their login
+/// until `confirm_totp_enrollment` sees a code from it.
+#[tauri::command]
+pub fn begin_totp_enrollment(state: State<AppState>, token: String) -> Result<auth_service::totp::TotpEnrollment, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session_allow_password_change(&db, &token)?;
+    let key = auth_service::totp::installation_key(&db)?;
+    auth_service::totp::begin_enrollment(&db.conn, &key, &user.id, &user.username)
+}
+
+/// Turn on 2FA for the caller and return their recovery codes. This is the
+/// only time the codes are shown.
+#[tauri::command]
+pub fn confirm_totp_enrollment(state: State<AppState>, token: String, code: String) -> Result<Vec<String>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session_allow_password_change(&db, &token)?;
+    let key = auth_service::totp::installation_key(&db)?;
+    let codes = auth_service::totp::confirm_enrollment(&db.conn, &key, &user.id, &code, chrono::Utc::now().timestamp())?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "totp_enrolled", "user", Some(&user.id), None, None,
+        Some(&format!("Authenticator app enrolled; {} recovery codes issued", codes.len())),
+    ).ok();
+    Ok(codes)
+}
+
+/// Turn off the caller's own 2FA. Needs a current code, so a borrowed
+/// unlocked session is not enough, and is refused while the caller's role
+/// requires 2FA.
+#[tauri::command]
+pub fn disable_totp(state: State<AppState>, token: String, code: String) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    if auth_service::totp::role_requires_mfa(&db.conn, user.role.as_str())? {
+        return Err(AppError::forbidden("Two-factor authentication is required for your role and cannot be turned off."));
+    }
+    let key = auth_service::totp::installation_key(&db)?;
+    let factor = auth_service::totp::verify_second_factor(&db.conn, &key, &user.id, &code, chrono::Utc::now().timestamp())?;
+    auth_service::totp::remove(&db.conn, &user.id)?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "totp_disabled", "user", Some(&user.id), None, None, Some(&factor.describe()),
+    ).ok();
+    Ok(())
+}
+
+/// Replace the caller's recovery codes, e.g. after using several. Needs a
+/// current code.
+#[tauri::command]
+pub fn regenerate_recovery_codes(state: State<AppState>, token: String, code: String) -> Result<Vec<String>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    let key = auth_service::totp::installation_key(&db)?;
+    auth_service::totp::verify_second_factor(&db.conn, &key, &user.id, &code, chrono::Utc::now().timestamp())?;
+    let codes = auth_service::totp::regenerate_recovery_codes(&db.conn, &user.id)?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "recovery_codes_regenerated", "user", Some(&user.id), None, None,
+        Some(&format!("{} new recovery codes issued; previous codes revoked", codes.len())),
+    ).ok();
+    Ok(codes)
+}
+
+/// WP-86: time-box an account. `expires_on` is a `YYYY-MM-DD` date — access
+/// ends at the end of that day, UTC — or `None` to remove the limit. Past the
+/// end the user can neither sign in nor use an existing session.
+#[tauri::command]
+pub fn set_user_access_expiry(
+    state: State<AppState>,
+    token: String,
+    user_id: String,
+    expires_on: Option<String>,
+) -> Result<(), AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    if user_id == caller.id {
+        return Err(AppError::forbidden("You cannot set an expiry on your own account."));
+    }
+    let (role, old): (String, Option<String>) = db.conn.query_row(
+        "SELECT role, access_expires_at FROM users WHERE id = ?1",
+        rusqlite::params![user_id],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    ).map_err(|_| AppError::not_found("user", "User not found"))?;
+    auth_service::roles::ensure_can_grant(&db.conn, &caller.role, &auth_service::roles::existing_role(&db.conn, &role)?)?;
+
+    let expires_at = match expires_on.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(date) => {
+            let day = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| AppError::validation("expires_on", format!("'{}' is not a date (expected YYYY-MM-DD)", date)))?;
+            Some(format!("{} 23:59:59", day.format("%Y-%m-%d")))
+        }
+        None => None,
+    };
+    db.conn.execute(
+        "UPDATE users SET access_expires_at = ?1, updated_at = datetime('now') WHERE id = ?2",
+        rusqlite::params![expires_at, user_id],
+    ).map_err(|e| format!("Failed to update access expiry: {}", e))?;
+
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "access_expiry", "user", Some(&user_id),
+        Some(old.as_deref().unwrap_or("none")), Some(expires_at.as_deref().unwrap_or("none")), None,
+    ).ok();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_mfa_policy(state: State<AppState>, token: String) -> Result<Vec<auth_service::totp::MfaPolicy>, AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersView)?;
+    auth_service::totp::list_policy(&db.conn)
+}
+
+/// Require (or stop requiring) 2FA for a role. Users in the role who have not
+/// enrolled are held to the enrollment screen from their next command.
+#[tauri::command]
+pub fn set_mfa_policy(state: State<AppState>, token: String, role: String, required: bool) -> Result<(), AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    auth_service::roles::existing_role(&db.conn, &role)?;
+    // An admin requiring 2FA for their own role without having enrolled would
+    // lock themselves to the enrollment screen mid-change; make them enroll
+    // first.
+    if required && role == caller.role.as_str() && !auth_service::totp::is_enrolled(&db.conn, &caller.id)? {
+        return Err(AppError::invalid("Enroll your own authenticator before requiring two-factor authentication for your role."));
+    }
+    let old = auth_service::totp::role_requires_mfa(&db.conn, &role)?;
+    auth_service::totp::set_policy(&db.conn, &role, required)?;
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "update", "mfa_policy", Some(&role),
+        Some(if old { "required" } else { "optional" }),
+        Some(if required { "required" } else { "optional" }),
+        None,
     ).ok();
+    Ok(())
+}
 
+/// Remove another user's 2FA enrollment, for a lost phone. If their role
+/// requires 2FA they are sent to the enrollment screen at their next login.
+#[tauri::command]
+pub fn reset_user_totp(state: State<AppState>, token: String, user_id: String) -> Result<(), AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    if !auth_service::totp::remove(&db.conn, &user_id)? {
+        return Err(AppError::invalid("That user has not enrolled in two-factor authentication."));
+    }
+    // Their pending logins were waiting on the factor just removed.
+    db.conn
+        .execute("DELETE FROM sessions WHERE user_id = ?1 AND mfa_pending = 1", rusqlite::params![user_id])
+        .ok();
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "totp_reset", "user", Some(&user_id), None, None,
+        Some("Two-factor enrollment and recovery codes removed by an administrator"),
+    ).ok();
+    Ok(())
+}
+
+// ── WP-88: sessions, deactivation and timeouts ─────────────────────────────
+
+/// Every live session across all accounts, with the device it was opened on
+/// and when it was last used.
+#[tauri::command]
+pub fn list_sessions(state: State<AppState>, token: String) -> Result<Vec<auth_service::sessions::SessionInfo>, AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersView)?;
+    auth_service::sessions::list(&db.conn, &auth_service::hash_token(&token))
+}
+
+/// The role of the account `user_id`, checked against what the caller could
+/// grant: ending someone's sessions or deactivating them is limited to the
+/// accounts whose role the caller could have assigned.
+fn manageable_target(db: &crate::db::Database, caller: &User, user_id: &str) -> Result<(String, String), AppError> {
+    let (username, role): (String, String) = db.conn.query_row(
+        "SELECT username, role FROM users WHERE id = ?1",
+        rusqlite::params![user_id],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    ).map_err(|_| AppError::not_found("user", "User not found"))?;
+    auth_service::roles::ensure_can_grant(&db.conn, &caller.role, &auth_service::roles::existing_role(&db.conn, &role)?)?;
+    Ok((username, role))
+}
+
+/// End one session, e.g. on a lost tablet. The device is told its session
+/// expired at its next command. Your own current session is ended by signing
+/// out instead.
+#[tauri::command]
+pub fn revoke_session(state: State<AppState>, token: String, session_id: String) -> Result<(), AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    let (user_id, device) = auth_service::sessions::owner(&db.conn, &session_id)?;
+    let current: bool = db.conn.query_row(
+        "SELECT token = ?1 FROM sessions WHERE id = ?2",
+        rusqlite::params![auth_service::hash_token(&token), session_id],
+        |r| r.get(0),
+    ).unwrap_or(false);
+    if current {
+        return Err(AppError::invalid("This is your current session. Sign out instead."));
+    }
+    let (username, _) = manageable_target(&db, &caller, &user_id)?;
+    auth_service::sessions::revoke(&db.conn, &session_id)?;
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "revoke", "session", Some(&session_id), None, None,
+        Some(&format!("Session of {} on {} ended", username, device.as_deref().unwrap_or("an unnamed device"))),
+    ).ok();
+    Ok(())
+}
+
+/// Sign a user out everywhere. Returns how many sessions ended. Your own
+/// current session is kept.
+#[tauri::command]
+pub fn revoke_user_sessions(state: State<AppState>, token: String, user_id: String) -> Result<usize, AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    manageable_target(&db, &caller, &user_id)?;
+    let ended = auth_service::sessions::revoke_all_for_user(
+        &db.conn, &user_id, Some(&auth_service::hash_token(&token)),
+    )?;
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "sessions_revoked", "user", Some(&user_id), None, None,
+        Some(&format!("{} session(s) ended by an administrator", ended)),
+    ).ok();
+    Ok(ended)
+}
+
+/// Deactivate or reactivate a local account. Deactivation ends all of the
+/// account's sessions at once. Directory accounts follow the directory.
+#[tauri::command]
+pub fn set_user_active(state: State<AppState>, token: String, user_id: String, active: bool) -> Result<(), AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    if user_id == caller.id {
+        return Err(AppError::forbidden("You cannot deactivate your own account."));
+    }
+    let (username, role) = manageable_target(&db, &caller, &user_id)?;
+    let (source, was_active): (String, bool) = db.conn.query_row(
+        "SELECT auth_source, is_active FROM users WHERE id = ?1",
+        rusqlite::params![user_id],
+        |r| Ok((r.get(0)?, r.get::<_, i64>(1)? != 0)),
+    ).map_err(|_| AppError::not_found("user", "User not found"))?;
+    if source == "ldap" {
+        return Err(
+            AppError::invalid("This account signs in through the directory. Disable or enable it there; the next directory sync follows."),
+        );
+    }
+    if was_active == active {
+        return Ok(());
+    }
+    // Same guard as `update_user_role`: nothing can restore the last admin.
+    if !active && role == "admin" {
+        let other_admins: i64 = db.conn.query_row(
+            "SELECT COUNT(*) FROM users WHERE role = 'admin' AND is_active = 1 AND id <> ?1",
+            rusqlite::params![user_id],
+            |r| r.get(0),
+        ).unwrap_or(0);
+        if other_admins == 0 {
+            return Err(AppError::conflict("This is the last active administrator. Promote another user to admin first."));
+        }
+    }
+
+    db.conn.execute(
+        "UPDATE users SET is_active = ?1, updated_at = datetime('now') WHERE id = ?2",
+        rusqlite::params![active as i64, user_id],
+    ).map_err(|e| format!("Failed to update account: {}", e))?;
+    let detail = if active {
+        format!("{} reactivated", username)
+    } else {
+        let ended = auth_service::sessions::revoke_all_for_user(&db.conn, &user_id, None)?;
+        format!("{} deactivated; {} session(s) ended", username, ended)
+    };
+    queries::log_audit(
+        &db.conn, Some(&caller.id), if active { "reactivate" } else { "deactivate" }, "user", Some(&user_id),
+        None, None, Some(&detail),
+    ).ok();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_session_policy(state: State<AppState>, token: String) -> Result<Vec<auth_service::sessions::SessionPolicy>, AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersView)?;
+    auth_service::sessions::list_policy(&db.conn)
+}
+
+/// Set a role's idle (minutes) and absolute (hours) session limits; `None`
+/// removes that limit. Applies to existing sessions from their next command.
+#[tauri::command]
+pub fn set_session_policy(
+    state: State<AppState>,
+    token: String,
+    role: String,
+    idle_minutes: Option<i64>,
+    absolute_hours: Option<i64>,
+) -> Result<(), AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    auth_service::roles::existing_role(&db.conn, &role)?;
+    let old = auth_service::sessions::policy_for(&db.conn, &role)?;
+    let new = auth_service::sessions::SessionPolicy { role: role.clone(), idle_minutes, absolute_hours };
+    auth_service::sessions::set_policy(&db.conn, &new)?;
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "update", "session_policy", Some(&role),
+        Some(&auth_service::sessions::describe_policy(&old)),
+        Some(&auth_service::sessions::describe_policy(&new)),
+        None,
+    ).ok();
+    Ok(())
+}
+
+/// WP-89: the lockout and password policy, with whether the breached-password
+/// list is installed.
+#[tauri::command]
+pub fn get_auth_policy(state: State<AppState>, token: String) -> Result<auth_service::policy::AuthPolicyView, AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersView)?;
+    auth_service::policy::view(&db.conn, &auth_service::policy::breach_list_dir(&db))
+}
+
+/// Replace the lockout and password policy. Password rules apply to the next
+/// password set; a shorter expiry applies at each account's next sign-in.
+#[tauri::command]
+pub fn set_auth_policy(
+    state: State<AppState>,
+    token: String,
+    policy: auth_service::policy::AuthPolicy,
+) -> Result<auth_service::policy::AuthPolicyView, AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    let old = auth_service::policy::get(&db.conn)?;
+    auth_service::policy::set(&db.conn, &policy, &auth_service::policy::breach_list_dir(&db))?;
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "update", "auth_policy", None,
+        Some(&auth_service::policy::describe(&old)),
+        Some(&auth_service::policy::describe(&policy)),
+        None,
+    ).ok();
+    auth_service::policy::view(&db.conn, &auth_service::policy::breach_list_dir(&db))
+}
+
+#[tauri::command]
+pub fn list_locked_accounts(state: State<AppState>, token: String) -> Result<Vec<auth_service::lockout::LockedAccount>, AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersView)?;
+    auth_service::lockout::list_locked(&db.conn)
+}
+
+/// Lift a lockout before it runs out. Unlocking an existing account needs
+/// every capability of its role, as for any other action on that account.
+#[tauri::command]
+pub fn unlock_account(state: State<AppState>, token: String, username: String) -> Result<(), AppError> {
+    let db = state.db();
+    let caller = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &caller, Capability::UsersManage)?;
+    let user_id: Option<String> = db.conn.query_row(
+        "SELECT id FROM users WHERE username = ?1 COLLATE NOCASE",
+        rusqlite::params![username.trim()],
+        |r| r.get(0),
+    ).ok();
+    if let Some(id) = &user_id {
+        manageable_target(&db, &caller, id)?;
+    }
+    if !auth_service::lockout::unlock(&db.conn, &username)? {
+        return Err(AppError::invalid(format!("{} is not locked", username.trim())));
+    }
+    queries::log_audit(
+        &db.conn, Some(&caller.id), "account_unlocked", "user", user_id.as_deref(),
+        None, Some(&username.trim().to_lowercase()), Some("Unlocked by an administrator"),
+    ).ok();
     Ok(())
 }
@@ -7,15 +7,17 @@
 //! string (see migration_035's doc comment for the rationale).
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::backend::{self, BackendKind};
 use crate::db::postgres;
+use crate::error::AppError;
 use crate::models::backend::BackendConfigInfo;
 use crate::AppState;
 use tauri::State;
 
 /// Any authenticated user may read the current backend configuration.
 #[tauri::command]
-pub fn get_backend_config(state: State<AppState>, token: String) -> Result<BackendConfigInfo, String> {
+pub fn get_backend_config(state: State<AppState>, token: String) -> Result<BackendConfigInfo, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -36,18 +38,16 @@ pub fn set_backend_type(
     token: String,
     backend_type: String,
     connection_string: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
 
-    if !user.role.is_admin() {
-        return Err("Only admins can change the backend configuration".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SystemBackend)?;
 
     let target = BackendKind::parse(&backend_type)?;
     backend::validate_backend_switch(target, cfg!(feature = "postgres"), connection_string.as_deref())?;
 
-    backend::set_backend_kind(&db.conn, target).map_err(|e| e.to_string())?;
+    backend::set_backend_kind(&db.conn, target)?;
 
     crate::db::queries::log_audit(
         &db.conn,
@@ -79,13 +79,11 @@ pub fn test_postgres_connection(
     state: State<AppState>,
     token: String,
     connection_string: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     {
         let db = state.db();
         let user = auth_service::validate_session(&db, &token)?;
-        if !user.role.is_admin() {
-            return Err("Only admins can test a PostgreSQL connection".to_string());
-        }
+        auth_service::require_capability(&db, &user, Capability::SystemBackend)?;
     }
     tauri::async_runtime::block_on(postgres::test_connection(&connection_string))
 }
@@ -97,13 +95,11 @@ pub fn bootstrap_postgres_schema(
     state: State<AppState>,
     token: String,
     connection_string: String,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, AppError> {
     {
         let db = state.db();
         let user = auth_service::validate_session(&db, &token)?;
-        if !user.role.is_admin() {
-            return Err("Only admins can bootstrap a PostgreSQL schema".to_string());
-        }
+        auth_service::require_capability(&db, &user, Capability::SystemBackend)?;
     }
     tauri::async_runtime::block_on(postgres::bootstrap_schema(&connection_string))
 }
@@ -1,5 +1,8 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::permissions::{mask_for_role, Masked};
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::breeding::{
     BreedingProgram, BreedingRecord, CreateBreedingProgramRequest, CreateBreedingRecordRequest,
     GenerationalSummary,
@@ -12,12 +15,10 @@ pub fn create_breeding_program(
     state: State<AppState>,
     token: String,
     request: CreateBreedingProgramRequest,
-) -> Result<BreedingProgram, String> {
+) -> Result<Masked<BreedingProgram>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::BreedingEdit)?;
     // WP-55 defense-in-depth: never let the literal "[RESTRICTED]" marker be
     // persisted into a masked field, even on create. There is no
     // update_breeding_program path today (so no read-masked value can
@@ -34,45 +35,21 @@ pub fn create_breeding_program(
         &db.conn, Some(&user.id), "create", "breeding_program", Some(&id),
         None, None, Some("Breeding program created"),
     ).ok();
-    queries::get_breeding_program(&db.conn, &id)
-        .map_err(|e| format!("Failed to retrieve breeding program: {}", e))
-}
-
-/// WP-55: masks `goal` and `target_traits` per the calling user's role.
-/// Takes a pre-loaded [`crate::db::permissions::FieldPermissionSet`] rather
-/// than querying per call — see the matching comment in `commands::strains`
-/// for why (fixes an N+1 query pattern across list results).
-///
-/// There is currently no `update_breeding_program` command, so unlike
-/// `strains::update_strain_status` there is no write path that could
-/// round-trip a masked "[RESTRICTED]" value back into these fields.
-/// `create_breeding_program` nonetheless already rejects the marker on both
-/// fields as defense-in-depth (see its body). If an update command is added
-/// later, it **must** apply the same
-/// `crate::db::permissions::reject_if_restricted_marker` guard before
-/// persisting — see the WP-55 write-path-guard doc comment in
-/// `db::permissions` for why.
-fn apply_field_permissions(perms: &crate::db::permissions::FieldPermissionSet, mut program: BreedingProgram) -> BreedingProgram {
-    program.goal = perms.mask_optional_field("breeding_program", "goal", program.goal);
-    program.target_traits = perms.mask_optional_field("breeding_program", "target_traits", program.target_traits);
-    program
+    let program = queries::get_breeding_program(&db.conn, &id)
+        .map_err(|e| format!("Failed to retrieve breeding program: {}", e))?;
+    mask_for_role(&db.conn, user.role.as_str(), program)
 }
 
 #[tauri::command]
 pub fn list_breeding_programs(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<BreedingProgram>, String> {
+) -> Result<Masked<Vec<BreedingProgram>>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
     let programs = queries::list_breeding_programs(&db.conn)
         .map_err(|e| format!("Failed to list breeding programs: {}", e))?;
-    let perms = crate::db::permissions::FieldPermissionSet::load(&db.conn, user.role.as_str())
-        .map_err(|e| e.to_string())?;
-    Ok(programs
-        .into_iter()
-        .map(|p| apply_field_permissions(&perms, p))
-        .collect())
+    mask_for_role(&db.conn, user.role.as_str(), programs)
 }
 
 #[tauri::command]
@@ -80,14 +57,12 @@ pub fn get_breeding_program(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<BreedingProgram, String> {
+) -> Result<Masked<BreedingProgram>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
     let program = queries::get_breeding_program(&db.conn, &id)
         .map_err(|e| format!("Failed to get breeding program: {}", e))?;
-    let perms = crate::db::permissions::FieldPermissionSet::load(&db.conn, user.role.as_str())
-        .map_err(|e| e.to_string())?;
-    Ok(apply_field_permissions(&perms, program))
+    mask_for_role(&db.conn, user.role.as_str(), program)
 }
 
 #[tauri::command]
@@ -95,12 +70,10 @@ pub fn add_breeding_record(
     state: State<AppState>,
     token: String,
     request: CreateBreedingRecordRequest,
-) -> Result<BreedingRecord, String> {
+) -> Result<BreedingRecord, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::BreedingEdit)?;
     let id = queries::add_breeding_record(&db.conn, &request, Some(&user.id))
         .map_err(|e| format!("Failed to add breeding record: {}", e))?;
     queries::log_audit(
@@ -108,7 +81,7 @@ pub fn add_breeding_record(
         None, None, Some("Breeding record added"),
     ).ok();
     queries::get_breeding_record(&db.conn, &id)
-        .map_err(|e| format!("Failed to retrieve breeding record: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to retrieve breeding record: {}", e)))
 }
 
 #[tauri::command]
@@ -116,11 +89,11 @@ pub fn list_breeding_records_for_program(
     state: State<AppState>,
     token: String,
     program_id: String,
-) -> Result<Vec<BreedingRecord>, String> {
+) -> Result<Vec<BreedingRecord>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     queries::list_breeding_records_for_program(&db.conn, &program_id)
-        .map_err(|e| format!("Failed to list breeding records: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to list breeding records: {}", e)))
 }
 
 #[tauri::command]
@@ -128,11 +101,11 @@ pub fn list_breeding_records_for_strain(
     state: State<AppState>,
     token: String,
     strain_id: String,
-) -> Result<Vec<BreedingRecord>, String> {
+) -> Result<Vec<BreedingRecord>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     queries::list_breeding_records_for_strain(&db.conn, &strain_id)
-        .map_err(|e| format!("Failed to list breeding records for strain: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to list breeding records for strain: {}", e)))
 }
 
 #[tauri::command]
@@ -140,9 +113,9 @@ pub fn get_generational_summary(
     state: State<AppState>,
     token: String,
     program_id: String,
-) -> Result<Vec<GenerationalSummary>, String> {
+) -> Result<Vec<GenerationalSummary>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     queries::get_generational_summary(&db.conn, &program_id)
-        .map_err(|e| format!("Failed to get generational summary: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to get generational summary: {}", e)))
 }
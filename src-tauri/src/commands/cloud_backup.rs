@@ -14,8 +14,10 @@ use rusqlite::params;
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::cloud::{crypto, targets};
 use crate::db::queries;
+use crate::error::AppError;
 use crate::AppState;
 
 #[derive(serde::Serialize)]
@@ -49,14 +51,12 @@ fn row_to_summary(row: &rusqlite::Row) -> rusqlite::Result<BackupTargetSummary>
 }
 
 #[tauri::command]
-pub fn list_backup_targets(state: State<AppState>, token: String) -> Result<Vec<BackupTargetSummary>, String> {
+pub fn list_backup_targets(state: State<AppState>, token: String) -> Result<Vec<BackupTargetSummary>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can view backup targets".to_string());
-    }
-    let mut stmt = db.conn.prepare("SELECT * FROM backup_targets ORDER BY name ASC").map_err(|e| e.to_string())?;
-    let rows = stmt.query_map([], row_to_summary).map_err(|e| e.to_string())?.filter_map(|r| r.ok()).collect();
+    auth_service::require_capability(&db, &user, Capability::BackupCreate)?;
+    let mut stmt = db.conn.prepare("SELECT * FROM backup_targets ORDER BY name ASC")?;
+    let rows = stmt.query_map([], row_to_summary)?.filter_map(|r| r.ok()).collect();
     Ok(rows)
 }
 
@@ -73,18 +73,16 @@ pub fn create_backup_target(
     access_key: Option<String>,
     secret_key: Option<String>,
     schedule_cron: Option<String>,
-) -> Result<BackupTargetSummary, String> {
+) -> Result<BackupTargetSummary, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can configure backup targets".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::BackupCreate)?;
     if passphrase.len() < 8 {
-        return Err("Passphrase must be at least 8 characters".to_string());
+        return Err(AppError::validation("passphrase", "Passphrase must be at least 8 characters"));
     }
     if let Some(ref cron) = schedule_cron {
         if !targets::is_valid_cron(cron) {
-            return Err(format!("'{}' is not a valid 5-field cron expression", cron));
+            return Err(AppError::validation("schedule_cron", format!("'{}' is not a valid 5-field cron expression", cron)));
         }
     }
 
@@ -103,17 +101,15 @@ pub fn create_backup_target(
         None, Some(&name), Some("Cloud backup target created"),
     ).ok();
 
-    db.conn.query_row("SELECT * FROM backup_targets WHERE id = ?1", [&id], row_to_summary).map_err(|e| e.to_string())
+    db.conn.query_row("SELECT * FROM backup_targets WHERE id = ?1", [&id], row_to_summary).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn delete_backup_target(state: State<AppState>, token: String, id: String) -> Result<(), String> {
+pub fn delete_backup_target(state: State<AppState>, token: String, id: String) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can delete backup targets".to_string());
-    }
-    db.conn.execute("DELETE FROM backup_targets WHERE id = ?1", [&id]).map_err(|e| e.to_string())?;
+    auth_service::require_capability(&db, &user, Capability::BackupCreate)?;
+    db.conn.execute("DELETE FROM backup_targets WHERE id = ?1", [&id])?;
     queries::log_audit(
         &db.conn, Some(&user.id), "delete", "backup_target", Some(&id),
         None, None, Some("Cloud backup target deleted"),
@@ -121,11 +117,11 @@ pub fn delete_backup_target(state: State<AppState>, token: String, id: String) -
     Ok(())
 }
 
-fn load_target(conn: &rusqlite::Connection, target_id: &str, passphrase: &str) -> Result<(String, targets::TargetConfig), String> {
+fn load_target(conn: &rusqlite::Connection, target_id: &str, passphrase: &str) -> Result<(String, targets::TargetConfig), AppError> {
     let (target_type, config_encrypted): (String, String) = conn.query_row(
         "SELECT type, config_encrypted FROM backup_targets WHERE id = ?1", [target_id],
         |r| Ok((r.get(0)?, r.get(1)?)),
-    ).map_err(|_| "Backup target not found".to_string())?;
+    ).map_err(|_| AppError::not_found("backup_target", "Backup target not found"))?;
     let config = targets::decrypt_target_config(passphrase, &config_encrypted)?;
     Ok((target_type, config))
 }
@@ -140,26 +136,24 @@ pub struct CloudBackupResult {
 }
 
 #[tauri::command]
-pub fn cloud_backup(state: State<AppState>, token: String, target_id: String, passphrase: String) -> Result<CloudBackupResult, String> {
+pub fn cloud_backup(state: State<AppState>, token: String, target_id: String, passphrase: String) -> Result<CloudBackupResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can run a cloud backup".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::BackupCreate)?;
 
     let started = std::time::Instant::now();
     let (target_type, config) = load_target(&db.conn, &target_id, &passphrase)?;
     if !matches!(target_type.as_str(), "local_nas" | "smb") {
-        return Err(format!(
+        return Err(AppError::invalid(format!(
             "Target type '{}' is configured but not yet connected — only local_nas/smb targets \
              (a filesystem path, e.g. a mounted network share) can complete a live backup today.",
             target_type
-        ));
+        )));
     }
 
     let db_path = crate::db::Database::db_path();
     if !db_path.exists() {
-        return Err("Database file not found (using in-memory database)".to_string());
+        return Err(AppError::internal("Database file not found (using in-memory database)"));
     }
 
     // Pre-checkpoint eligible lineages (same as the local WP-16 backup path)
@@ -170,10 +164,10 @@ pub fn cloud_backup(state: State<AppState>, token: String, target_id: String, pa
         .query_row("PRAGMA wal_checkpoint(TRUNCATE);", [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
         .map_err(|e| format!("Failed to checkpoint WAL: {}", e))?;
     if busy_frames > 0 {
-        return Err(format!(
+        return Err(AppError::conflict(format!(
             "WAL checkpoint incomplete: {} frame(s) held by active readers. Close other connections and retry.",
             busy_frames
-        ));
+        )));
     }
 
     // Security: like the local backup path (WP-16/WP-52), the cloud backup must
@@ -188,9 +182,9 @@ pub fn cloud_backup(state: State<AppState>, token: String, target_id: String, pa
     let backup_id = format!("stelo_cloud_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"));
     let temp_path = db_path.with_file_name(format!("{}.tmp", backup_id));
     std::fs::copy(&db_path, &temp_path).map_err(|e| format!("Failed to stage backup copy: {}", e))?;
-    if let Err(e) = crate::commands::backup::redact_smtp_password_in_backup(&temp_path) {
+    if let Err(e) = crate::db::backup::redact_smtp_password_in_backup(&temp_path) {
         let _ = std::fs::remove_file(&temp_path);
-        return Err(format!("Failed to redact SMTP credential from backup copy: {}", e));
+        return Err(AppError::internal(format!("Failed to redact SMTP credential from backup copy: {}", e)));
     }
     let read_result = std::fs::read(&temp_path).map_err(|e| format!("Failed to read staged backup: {}", e));
     let _ = std::fs::remove_file(&temp_path);
@@ -235,22 +229,20 @@ pub fn restore_from_cloud(
     target_id: String,
     passphrase: String,
     backup_file_name: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can restore from a cloud backup".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::BackupRestore)?;
 
     let (target_type, config) = load_target(&db.conn, &target_id, &passphrase)?;
     if !matches!(target_type.as_str(), "local_nas" | "smb") {
-        return Err(format!("Target type '{}' is not yet connected for restore", target_type));
+        return Err(AppError::invalid(format!("Target type '{}' is not yet connected for restore", target_type)));
     }
 
     let src_path = std::path::PathBuf::from(&config.bucket_or_path).join(&backup_file_name);
     let blob = std::fs::read(&src_path).map_err(|e| format!("Failed to read cloud backup file: {}", e))?;
     if blob.len() < 16 {
-        return Err("Backup file is too short to be valid".to_string());
+        return Err(AppError::integrity("Backup file is too short to be valid"));
     }
     let (salt, encrypted) = blob.split_at(16);
     let key = crypto::derive_key(&passphrase, salt)?;
@@ -299,16 +291,14 @@ pub fn reconcile_cloud_sync(
     target_id: String,
     passphrase: String,
     device_id: String,
-) -> Result<ReconcileSummary, String> {
+) -> Result<ReconcileSummary, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can sync with a cloud target".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::BackupCreate)?;
 
     let (target_type, config) = load_target(&db.conn, &target_id, &passphrase)?;
     if !matches!(target_type.as_str(), "local_nas" | "smb") {
-        return Err(format!("Target type '{}' is not yet connected for sync", target_type));
+        return Err(AppError::invalid(format!("Target type '{}' is not yet connected for sync", target_type)));
     }
     let shared_dir = std::path::PathBuf::from(&config.bucket_or_path).join("sync");
     std::fs::create_dir_all(&shared_dir).map_err(|e| format!("Failed to reach shared sync folder: {}", e))?;
@@ -376,5 +366,13 @@ pub fn reconcile_cloud_sync(
         }
     }
 
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "reconcile", "cloud_sync", Some(&target_id), None, None,
+        Some(&format!(
+            "Reconciled via device {}: published={}, peer segments={}, new={}, duplicates={}, conflicts={}",
+            device_id, segments_published, peer_segments_found, new_changes, duplicates, conflicts_recorded
+        )),
+    ).ok();
+
     Ok(ReconcileSummary { segments_published, peer_segments_found, new_changes, duplicates, conflicts_recorded })
 }
@@ -1,10 +1,14 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::commands::signed_events::verify_ceremony;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::compliance::{
     ComplianceFlag, ComplianceRecord, CreateComplianceRequest, MycoplasmaStatus,
     UpdateComplianceRequest,
 };
 use crate::models::specimen::PaginatedResponse;
+use crate::signed_ledger::esignature::{self, SignatureCeremony};
 use crate::AppState;
 use rusqlite::params;
 use tauri::State;
@@ -16,7 +20,7 @@ pub fn list_compliance_records(
     specimen_id: Option<String>,
     page: Option<u32>,
     per_page: Option<u32>,
-) -> Result<PaginatedResponse<ComplianceRecord>, String> {
+) -> Result<PaginatedResponse<ComplianceRecord>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -41,8 +45,7 @@ pub fn list_compliance_records(
     );
     let bind_refs: Vec<&dyn rusqlite::types::ToSql> = bind_vals.iter().map(|v| v.as_ref()).collect();
     let total: i64 = db.conn
-        .query_row(&count_sql, bind_refs.as_slice(), |r| r.get(0))
-        .map_err(|e| e.to_string())?;
+        .query_row(&count_sql, bind_refs.as_slice(), |r| r.get(0))?;
 
     let limit_idx = bind_vals.len() + 1;
     let offset_idx = bind_vals.len() + 2;
@@ -61,7 +64,7 @@ pub fn list_compliance_records(
     all_vals.push(Box::new(pg.offset()));
     let bind_refs2: Vec<&dyn rusqlite::types::ToSql> = all_vals.iter().map(|v| v.as_ref()).collect();
 
-    let mut stmt = db.conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut stmt = db.conn.prepare(&sql)?;
 
     let items = stmt.query_map(bind_refs2.as_slice(), |row| {
         Ok(ComplianceRecord {
@@ -86,7 +89,7 @@ pub fn list_compliance_records(
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect();
 
@@ -105,12 +108,10 @@ pub fn create_compliance_record(
     state: State<AppState>,
     token: String,
     request: CreateComplianceRequest,
-) -> Result<ComplianceRecord, String> {
+) -> Result<ComplianceRecord, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ComplianceEdit)?;
 
     let id = uuid::Uuid::new_v4().to_string();
 
@@ -163,7 +164,7 @@ pub fn create_compliance_record(
                 updated_at: row.get("updated_at")?,
             })
         },
-    ).map_err(|e| format!("Failed to fetch compliance record: {}", e))
+    ).map_err(|e| AppError::internal(format!("Failed to fetch compliance record: {}", e)))
 }
 
 #[tauri::command]
@@ -171,12 +172,10 @@ pub fn update_compliance_record(
     state: State<AppState>,
     token: String,
     request: UpdateComplianceRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ComplianceEdit)?;
 
     let mut updates = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -199,7 +198,7 @@ pub fn update_compliance_record(
     }
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
 
     updates.push("updated_at = datetime('now')".to_string());
@@ -223,7 +222,7 @@ pub fn update_compliance_record(
 }
 
 #[tauri::command]
-pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec<ComplianceFlag>, String> {
+pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec<ComplianceFlag>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -246,7 +245,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
              WHERE s.permit_expiry IS NOT NULL AND s.permit_expiry < date('now')
              AND s.is_archived = 0
              AND s.lab_profile = COALESCE((SELECT lab_profile FROM app_config WHERE id = 1), 'plant_tissue_culture')"
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         let expired: Vec<ComplianceFlag> = stmt.query_map([], |row| {
             Ok(ComplianceFlag {
@@ -258,7 +257,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
                 severity: "critical".to_string(),
                 last_test_date: None,
             })
-        }).map_err(|e| e.to_string())?.filter_map(|r| r.ok()).collect();
+        })?.filter_map(|r| r.ok()).collect();
         flags.extend(expired);
     }
 
@@ -276,7 +275,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
                  WHERE test_type = 'HLB' AND test_date >= date('now', '-12 months')
                  AND test_result IS NOT NULL
              )"
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         let missing_hlb: Vec<ComplianceFlag> = stmt.query_map([], |row| {
             Ok(ComplianceFlag {
@@ -288,7 +287,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
                 severity: "critical".to_string(),
                 last_test_date: None,
             })
-        }).map_err(|e| e.to_string())?.filter_map(|r| r.ok()).collect();
+        })?.filter_map(|r| r.ok()).collect();
         flags.extend(missing_hlb);
     }
 
@@ -301,7 +300,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
              WHERE s.quarantine_flag = 1 AND s.quarantine_release_date IS NULL
              AND s.is_archived = 0
              AND s.lab_profile = COALESCE((SELECT lab_profile FROM app_config WHERE id = 1), 'plant_tissue_culture')"
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         let quarantine: Vec<ComplianceFlag> = stmt.query_map([], |row| {
             Ok(ComplianceFlag {
@@ -313,7 +312,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
                 severity: "high".to_string(),
                 last_test_date: None,
             })
-        }).map_err(|e| e.to_string())?.filter_map(|r| r.ok()).collect();
+        })?.filter_map(|r| r.ok()).collect();
         flags.extend(quarantine);
     }
 
@@ -327,7 +326,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
              WHERE cr.test_result = 'positive' AND s.quarantine_flag = 0
              AND s.is_archived = 0
              AND s.lab_profile = COALESCE((SELECT lab_profile FROM app_config WHERE id = 1), 'plant_tissue_culture')"
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         let positive_no_quarantine: Vec<ComplianceFlag> = stmt.query_map([], |row| {
             Ok(ComplianceFlag {
@@ -339,7 +338,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
                 severity: "critical".to_string(),
                 last_test_date: None,
             })
-        }).map_err(|e| e.to_string())?.filter_map(|r| r.ok()).collect();
+        })?.filter_map(|r| r.ok()).collect();
         flags.extend(positive_no_quarantine);
     }
 
@@ -353,7 +352,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
             let slow_days: i64 = queries::read_setting(&db.conn, "myco_slow_colonization_days", "7")
                 .parse().unwrap_or(7);
             let myco_flags = queries::get_mycology_compliance_flags(&db.conn, transfer_days, slow_pct, slow_days)
-                .map_err(|e| format!("Mycology QC flags error: {}", e))?;
+                .map_err(|e| AppError::internal(format!("Mycology QC flags error: {}", e)))?;
             flags.extend(myco_flags);
         }
     }
@@ -382,7 +381,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
                  )",
                 interval_days
             );
-            let mut stmt = db.conn.prepare(&sql).map_err(|e| e.to_string())?;
+            let mut stmt = db.conn.prepare(&sql)?;
             let missing_myco: Vec<ComplianceFlag> = stmt.query_map([], |row| {
                 let last_date: Option<String> = row.get(3)?;
                 let msg = match &last_date {
@@ -404,7 +403,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
                     severity: "high".to_string(),
                     last_test_date: last_date,
                 })
-            }).map_err(|e| e.to_string())?.filter_map(|r| r.ok()).collect();
+            })?.filter_map(|r| r.ok()).collect();
             flags.extend(missing_myco);
         }
     }
@@ -424,7 +423,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
                    SELECT MAX(er2.recorded_at) FROM environmental_readings er2
                    WHERE er2.specimen_id = er.specimen_id AND er2.reading_type = er.reading_type
                )",
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         #[allow(clippy::type_complexity)]
         let rows: Vec<(String, String, String, String, f64, Option<String>)> = stmt
@@ -432,8 +431,7 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
                 Ok((
                     row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?,
                 ))
-            })
-            .map_err(|e| e.to_string())?
+            })?
             .filter_map(|r| r.ok())
             .collect();
 
@@ -470,10 +468,9 @@ pub fn get_compliance_flags(state: State<AppState>, token: String) -> Result<Vec
 /// by the suppression decision (WP-77).
 fn load_active_waivers(
     conn: &rusqlite::Connection,
-) -> Result<Vec<crate::compliance_rules::Waiver>, String> {
+) -> Result<Vec<crate::compliance_rules::Waiver>, AppError> {
     let mut stmt = conn
-        .prepare("SELECT flag_type, specimen_id, expires_at FROM compliance_flag_waivers WHERE revoked = 0")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT flag_type, specimen_id, expires_at FROM compliance_flag_waivers WHERE revoked = 0")?;
     let rows = stmt
         .query_map([], |r| {
             Ok(crate::compliance_rules::Waiver {
@@ -481,8 +478,7 @@ fn load_active_waivers(
                 specimen_id: r.get(1)?,
                 expires_at: r.get(2)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
     Ok(rows)
@@ -512,27 +508,31 @@ pub fn waive_compliance_flag(
     specimen_id: String,
     reason: String,
     expires_at: Option<String>,
-) -> Result<(), String> {
+    signature: SignatureCeremony,
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ComplianceWaive)?;
     if reason.trim().is_empty() {
-        return Err("A reason is required to waive a compliance flag".to_string());
+        return Err(AppError::validation("reason", "A reason is required to waive a compliance flag"));
     }
+    // WP-80: a waiver is approved with a Part 11 electronic signature.
+    let verified = verify_ceremony(&db, &user, &signature, esignature::WAIVER_APPROVAL)?;
 
+    let tx = db
+        .conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
     let id = uuid::Uuid::new_v4().to_string();
-    db.conn
-        .execute(
-            "INSERT INTO compliance_flag_waivers (id, flag_type, specimen_id, reason, waived_by, expires_at) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![id, flag_type, specimen_id, reason.trim(), user.id, expires_at],
-        )
-        .map_err(|e| format!("Failed to record waiver: {}", e))?;
+    tx.execute(
+        "INSERT INTO compliance_flag_waivers (id, flag_type, specimen_id, reason, waived_by, expires_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id, flag_type, specimen_id, reason.trim(), user.id, expires_at],
+    )
+    .map_err(|e| format!("Failed to record waiver: {}", e))?;
 
     queries::log_audit(
-        &db.conn,
+        &tx,
         Some(&user.id),
         "waive",
         "compliance_flag",
@@ -542,6 +542,8 @@ pub fn waive_compliance_flag(
         Some(&format!("Waived '{}': {}", flag_type, reason.trim())),
     )
     .ok();
+    esignature::record_signature(&tx, &user, verified, "compliance_flag_waiver", &id)?;
+    tx.commit().map_err(|e| format!("Failed to commit waiver: {}", e))?;
     Ok(())
 }
 
@@ -550,7 +552,7 @@ pub fn waive_compliance_flag(
 pub fn list_compliance_waivers(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<ComplianceWaiver>, String> {
+) -> Result<Vec<ComplianceWaiver>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
@@ -563,8 +565,7 @@ pub fn list_compliance_waivers(
              LEFT JOIN specimens s ON w.specimen_id = s.id \
              WHERE w.revoked = 0 AND (w.expires_at IS NULL OR w.expires_at >= ?1) \
              ORDER BY w.waived_at DESC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let rows = stmt
         .query_map(params![today], |r| {
             Ok(ComplianceWaiver {
@@ -577,8 +578,7 @@ pub fn list_compliance_waivers(
                 waived_at: r.get(6)?,
                 expires_at: r.get(7)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
     Ok(rows)
@@ -591,12 +591,10 @@ pub fn revoke_compliance_waiver(
     state: State<AppState>,
     token: String,
     waiver_id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ComplianceWaive)?;
     let affected = db
         .conn
         .execute(
@@ -605,7 +603,7 @@ pub fn revoke_compliance_waiver(
         )
         .map_err(|e| format!("Failed to revoke waiver: {}", e))?;
     if affected == 0 {
-        return Err("Waiver not found or already revoked".to_string());
+        return Err(AppError::not_found("compliance_waiver", "Waiver not found or already revoked"));
     }
     queries::log_audit(
         &db.conn,
@@ -638,7 +636,7 @@ pub struct ActiveComplianceRule {
 pub fn list_compliance_rules(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<ActiveComplianceRule>, String> {
+) -> Result<Vec<ActiveComplianceRule>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = queries::read_setting(&db.conn, "lab_profile", "plant_tissue_culture");
@@ -661,10 +659,10 @@ pub fn list_compliance_rules(
 pub fn get_mycoplasma_status(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<MycoplasmaStatus>, String> {
+) -> Result<Vec<MycoplasmaStatus>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    queries::list_mycoplasma_status(&db.conn).map_err(|e| e.to_string())
+    queries::list_mycoplasma_status(&db.conn).map_err(AppError::from)
 }
 
 #[cfg(test)]
@@ -5,35 +5,35 @@
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::compliance_export::{bundle, signing, zip_writer};
+use crate::error::AppError;
 use crate::AppState;
 
-pub(crate) fn exports_dir() -> Result<std::path::PathBuf, String> {
+pub(crate) fn exports_dir() -> Result<std::path::PathBuf, AppError> {
     let base = crate::db::Database::db_path();
-    let parent = base.parent().ok_or_else(|| "Could not determine exports directory".to_string())?;
+    let parent = base.parent().ok_or_else(|| AppError::internal("Could not determine exports directory"))?;
     let dir = parent.join("compliance_exports");
-    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir)?;
     Ok(dir)
 }
 
-pub(crate) fn load_or_create_signing_key(conn: &rusqlite::Connection) -> Result<(String, String), String> {
+pub(crate) fn load_or_create_signing_key(conn: &rusqlite::Connection) -> Result<(String, String), AppError> {
     // Delegates to the non-gated helper so this DB logic has a single source of
     // truth shared with `passport::store` (WP-70).
     crate::compliance_export::load_or_create_lab_signing_key(conn)
 }
 
 #[tauri::command]
-pub fn get_signing_public_key(state: State<AppState>, token: String) -> Result<String, String> {
+pub fn get_signing_public_key(state: State<AppState>, token: String) -> Result<String, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can view the signing key".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ComplianceExport)?;
     let (public_key, _) = load_or_create_signing_key(&db.conn)?;
     Ok(public_key)
 }
 
-pub(crate) fn sign_and_zip(private_key_b64: &str, public_key_b64: &str, documents: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>, String> {
+pub(crate) fn sign_and_zip(private_key_b64: &str, public_key_b64: &str, documents: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>, AppError> {
     let mut files = Vec::with_capacity(documents.len() * 2 + 1);
     for (name, contents) in documents {
         let signature = signing::sign(private_key_b64, &contents)?;
@@ -58,12 +58,10 @@ pub fn export_fda_part11_bundle(
     from_date: String,
     to_date: String,
     lab_name: String,
-) -> Result<ComplianceExportResult, String> {
+) -> Result<ComplianceExportResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can generate a Part 11 export".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ComplianceExport)?;
 
     let documents = bundle::build_part11_documents(&db.conn, &from_date, &to_date, &lab_name)?;
     let (public_key, private_key) = load_or_create_signing_key(&db.conn)?;
@@ -71,7 +69,7 @@ pub fn export_fda_part11_bundle(
 
     let file_name = format!("fda_part11_{}_{}_{}.zip", from_date, to_date, chrono::Local::now().format("%Y%m%d_%H%M%S"));
     let file_path = exports_dir()?.join(&file_name);
-    std::fs::write(&file_path, &zip_bytes).map_err(|e| e.to_string())?;
+    std::fs::write(&file_path, &zip_bytes)?;
 
     crate::db::queries::log_audit(
         &db.conn, Some(&user.id), "export", "compliance_bundle", None,
@@ -87,19 +85,17 @@ pub fn export_usda_permit(
     token: String,
     specimen_ids: Vec<String>,
     authorized_scientist: String,
-) -> Result<ComplianceExportResult, String> {
+) -> Result<ComplianceExportResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can generate a USDA APHIS export".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ComplianceExport)?;
 
     let prefill = bundle::build_usda_permit_prefill(&db.conn, &specimen_ids, &authorized_scientist)?;
-    let json_bytes = serde_json::to_vec_pretty(&prefill).map_err(|e| e.to_string())?;
+    let json_bytes = serde_json::to_vec_pretty(&prefill)?;
 
     let file_name = format!("usda_ppq526_{}.json", chrono::Local::now().format("%Y%m%d_%H%M%S"));
     let file_path = exports_dir()?.join(&file_name);
-    std::fs::write(&file_path, &json_bytes).map_err(|e| e.to_string())?;
+    std::fs::write(&file_path, &json_bytes)?;
 
     crate::db::queries::log_audit(
         &db.conn, Some(&user.id), "export", "compliance_bundle", None,
@@ -115,20 +111,18 @@ pub fn export_cites_dossier(
     token: String,
     root_specimen_id: String,
     cites_appendix: String,
-) -> Result<ComplianceExportResult, String> {
+) -> Result<ComplianceExportResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can generate a CITES dossier".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ComplianceExport)?;
 
     let dossier = bundle::build_cites_dossier(&db.conn, &root_specimen_id, &cites_appendix)?;
-    let json_bytes = serde_json::to_vec_pretty(&dossier).map_err(|e| e.to_string())?;
+    let json_bytes = serde_json::to_vec_pretty(&dossier)?;
     let zip_bytes = zip_writer::build_zip(&[("cites_dossier.json".to_string(), json_bytes)])?;
 
     let file_name = format!("cites_dossier_{}_{}.zip", root_specimen_id, chrono::Local::now().format("%Y%m%d_%H%M%S"));
     let file_path = exports_dir()?.join(&file_name);
-    std::fs::write(&file_path, &zip_bytes).map_err(|e| e.to_string())?;
+    std::fs::write(&file_path, &zip_bytes)?;
 
     crate::db::queries::log_audit(
         &db.conn, Some(&user.id), "export", "compliance_bundle", Some(&root_specimen_id),
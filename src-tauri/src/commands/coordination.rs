@@ -8,8 +8,10 @@
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::coordination::store::{self, SelectionDecision};
 use crate::coordination::{BundleVerification, CoordinationBundle};
+use crate::error::AppError;
 use crate::AppState;
 
 /// Export a signed coordination bundle for one breeding program and record it.
@@ -18,12 +20,10 @@ pub fn export_coordination_bundle(
     state: State<AppState>,
     token: String,
     program_id: String,
-) -> Result<CoordinationBundle, String> {
+) -> Result<CoordinationBundle, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions — a write-capable role is required to export a coordination bundle.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::CoordinationExchange)?;
     let bundle = store::export_bundle(&db.conn, &program_id, Some(&user.id))?;
     crate::db::queries::log_audit(
         &db.conn,
@@ -49,7 +49,7 @@ pub fn verify_coordination_bundle(
     state: State<AppState>,
     token: String,
     bundle_json: String,
-) -> Result<BundleVerification, String> {
+) -> Result<BundleVerification, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::verify_bundle_json(&bundle_json)
@@ -62,7 +62,7 @@ pub fn preview_coordination_import(
     state: State<AppState>,
     token: String,
     bundle_json: String,
-) -> Result<store::BundleImportPreview, String> {
+) -> Result<store::BundleImportPreview, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::preview_import(&db.conn, &bundle_json)
@@ -76,12 +76,10 @@ pub fn import_coordination_bundle(
     token: String,
     bundle_json: String,
     decisions: Option<Vec<SelectionDecision>>,
-) -> Result<store::BundleImportResult, String> {
+) -> Result<store::BundleImportResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions — a write-capable role is required to import a coordination bundle.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::CoordinationExchange)?;
     store::import_bundle(&db.conn, &bundle_json, &decisions.unwrap_or_default(), Some(&user.id))
 }
 
@@ -92,7 +90,7 @@ pub fn list_coordination_bundles(
     state: State<AppState>,
     token: String,
     direction: Option<String>,
-) -> Result<Vec<store::BundleRow>, String> {
+) -> Result<Vec<store::BundleRow>, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::list_bundles(&db.conn, direction.as_deref())
@@ -104,7 +102,7 @@ pub fn get_coordination_bundle_json(
     state: State<AppState>,
     token: String,
     row_id: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::get_bundle_json(&db.conn, &row_id)
@@ -116,7 +114,7 @@ pub fn list_coordination_dispositions(
     state: State<AppState>,
     token: String,
     bundle_row_id: String,
-) -> Result<Vec<store::AppliedSelection>, String> {
+) -> Result<Vec<store::AppliedSelection>, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::list_dispositions(&db.conn, &bundle_row_id)
@@ -1,5 +1,7 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::cryo::{
     CreateFrozenVialRequest, DiscardFrozenVialRequest, FrozenVial, ListFrozenVialsParams,
     ThawVialRequest, ThawVialResult,
@@ -13,12 +15,10 @@ pub fn create_frozen_vial(
     state: State<AppState>,
     token: String,
     request: CreateFrozenVialRequest,
-) -> Result<FrozenVial, String> {
+) -> Result<FrozenVial, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::CryoFreeze)?;
     let id = queries::create_frozen_vial(&db.conn, &request, Some(&user.id))
         .map_err(|e| format!("Failed to create frozen vial: {}", e))?;
 
@@ -28,7 +28,7 @@ pub fn create_frozen_vial(
     ).ok();
 
     queries::get_frozen_vial(&db.conn, &id)
-        .map_err(|e| format!("Failed to retrieve frozen vial: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to retrieve frozen vial: {}", e)))
 }
 
 #[tauri::command]
@@ -36,7 +36,7 @@ pub fn list_frozen_vials(
     state: State<AppState>,
     token: String,
     params: Option<ListFrozenVialsParams>,
-) -> Result<Vec<FrozenVial>, String> {
+) -> Result<Vec<FrozenVial>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let p = params.unwrap_or(ListFrozenVialsParams {
@@ -46,7 +46,7 @@ pub fn list_frozen_vials(
         location_freezer: None,
     });
     queries::list_frozen_vials(&db.conn, &p)
-        .map_err(|e| format!("Failed to list frozen vials: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to list frozen vials: {}", e)))
 }
 
 #[tauri::command]
@@ -54,11 +54,11 @@ pub fn get_frozen_vial(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<FrozenVial, String> {
+) -> Result<FrozenVial, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     queries::get_frozen_vial(&db.conn, &id)
-        .map_err(|e| format!("Frozen vial not found: {}", e))
+        .map_err(|e| AppError::not_found("frozen_vial", format!("Frozen vial not found: {}", e)))
 }
 
 #[tauri::command]
@@ -66,12 +66,10 @@ pub fn thaw_vial(
     state: State<AppState>,
     token: String,
     request: ThawVialRequest,
-) -> Result<ThawVialResult, String> {
+) -> Result<ThawVialResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::CryoThaw)?;
 
     let vials_to_thaw = request.vials_to_thaw.unwrap_or(1);
     let (specimen_id, accession) = queries::thaw_frozen_vial(
@@ -83,7 +81,7 @@ pub fn thaw_vial(
         request.notes.as_deref(),
         request.employee_id.as_deref(),
         Some(&user.id),
-    ).map_err(|e| format!("Thaw failed: {}", e))?;
+    )?;
 
     // WP-63: thawing a vial inserts a brand-new, non-archived specimen
     // (stage 'thaw_recovery'), which changes total/active/by-stage/by-species
@@ -108,14 +106,11 @@ pub fn discard_frozen_vial(
     state: State<AppState>,
     token: String,
     request: DiscardFrozenVialRequest,
-) -> Result<FrozenVial, String> {
+) -> Result<FrozenVial, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
-    queries::discard_frozen_vial(&db.conn, &request.vial_id, request.notes.as_deref())
-        .map_err(|e| format!("Discard failed: {}", e))?;
+    auth_service::require_capability(&db, &user, Capability::CryoDiscard)?;
+    queries::discard_frozen_vial(&db.conn, &request.vial_id, request.notes.as_deref())?;
 
     queries::log_audit(
         &db.conn, Some(&user.id), "discard", "frozen_vial", Some(&request.vial_id),
@@ -123,14 +118,14 @@ pub fn discard_frozen_vial(
     ).ok();
 
     queries::get_frozen_vial(&db.conn, &request.vial_id)
-        .map_err(|e| format!("Failed to retrieve vial: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to retrieve vial: {}", e)))
 }
 
 #[tauri::command]
 pub fn get_vial_summary_by_line(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<VialLineSummary>, String> {
+) -> Result<Vec<VialLineSummary>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     crate::db::dashboard::query_vial_summary_by_line(&db.conn)
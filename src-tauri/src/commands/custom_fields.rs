@@ -0,0 +1,62 @@
+// WP-96: custom specimen and subculture fields for the active lab profile.
+// Anyone signed in can list them (the forms render them); defining and
+// retiring need `custom_fields.manage`.
+use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::custom_fields::{self, CustomField, CustomFieldEntity, SaveCustomFieldRequest};
+use crate::db::queries;
+use crate::error::AppError;
+use crate::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_custom_fields(
+    state: State<AppState>,
+    token: String,
+    entity: Option<CustomFieldEntity>,
+    include_retired: Option<bool>,
+) -> Result<Vec<CustomField>, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    custom_fields::list(&db.conn, &profile, entity, include_retired.unwrap_or(false))
+}
+
+/// Define a field, or change the label, unit, required flag, order or
+/// options of an existing one. The type of a field cannot change.
+#[tauri::command]
+pub fn save_custom_field(
+    state: State<AppState>,
+    token: String,
+    request: SaveCustomFieldRequest,
+) -> Result<CustomField, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::CustomFieldsManage)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let field = custom_fields::save(&db.conn, &user.id, &profile, &request)?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "save", "custom_field", Some(&field.id),
+        None, serde_json::to_string(&request).ok().as_deref(),
+        Some(&format!(
+            "Custom {} field {} ({}) for {}",
+            field.entity.as_str(), field.key, field.field_type.as_str(), field.lab_profile,
+        )),
+    ).ok();
+    Ok(field)
+}
+
+/// Retire a field. Stored values stay on their records.
+#[tauri::command]
+pub fn retire_custom_field(state: State<AppState>, token: String, id: String) -> Result<CustomField, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::CustomFieldsManage)?;
+    let field = custom_fields::retire(&db.conn, &id)?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "retire", "custom_field", Some(&id),
+        Some(&field.key), None,
+        Some(&format!("Custom {} field {} retired", field.entity.as_str(), field.key)),
+    ).ok();
+    Ok(field)
+}
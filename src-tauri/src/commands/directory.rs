@@ -0,0 +1,137 @@
+// WP-85: LDAP / Active Directory settings, group-to-role mappings and account
+// sync. All admin-only: these decide who can sign in and with which role.
+use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::auth::ldap::{self, Directory, DirectoryEntry, DirectorySyncReport, GroupRoleMapping, LdapConfig, SetLdapConfigRequest};
+use crate::error::AppError;
+use crate::AppState;
+use serde::Serialize;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_ldap_config(state: State<AppState>, token: String) -> Result<LdapConfig, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::DirectoryManage)?;
+    ldap::get_config(&db.conn)
+}
+
+#[tauri::command]
+pub fn set_ldap_config(state: State<AppState>, token: String, request: SetLdapConfigRequest) -> Result<LdapConfig, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::DirectoryManage)?;
+    ldap::set_config(&db.conn, &request)?;
+    crate::db::queries::log_audit(
+        &db.conn,
+        Some(&user.id),
+        "update",
+        "ldap_config",
+        None,
+        None,
+        Some(&format!(
+            "enabled={} url={} base={} filter={} default_role={}",
+            request.enabled,
+            request.url.trim(),
+            request.user_base_dn.trim(),
+            request.user_filter.trim(),
+            request.default_role.as_deref().unwrap_or("none"),
+        )),
+        Some("Directory authentication settings changed"),
+    )
+    .ok();
+    ldap::get_config(&db.conn)
+}
+
+#[derive(Debug, Serialize)]
+pub struct LdapTestResult {
+    pub message: String,
+    /// The looked-up entry, when a username was given and found.
+    pub entry: Option<DirectoryEntry>,
+    /// The role that entry would sign in with, if any.
+    pub mapped_role: Option<String>,
+}
+
+/// Connect with the saved settings (enabled or not), bind as the service
+/// account and, given a username, show the entry and the role it maps to.
+/// Never checks a user's password.
+#[tauri::command]
+pub fn test_ldap_connection(state: State<AppState>, token: String, username: Option<String>) -> Result<LdapTestResult, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::DirectoryManage)?;
+    let dir = ldap::directory(&db.conn)?;
+    let username = username.map(|u| u.trim().to_lowercase()).filter(|u| !u.is_empty());
+    let Some(username) = username else {
+        dir.find_user("stelo-connection-test")?;
+        return Ok(LdapTestResult {
+            message: "Connected, bound and searched the user base successfully.".to_string(),
+            entry: None,
+            mapped_role: None,
+        });
+    };
+    let Some(entry) = dir.find_user(&username)? else {
+        return Ok(LdapTestResult {
+            message: format!("Connected, but no entry matched '{}'.", username),
+            entry: None,
+            mapped_role: None,
+        });
+    };
+    let role = ldap::map_role(&db.conn, &entry.groups, dir.config().default_role.as_deref())?;
+    let message = match (&role, entry.disabled) {
+        (_, true) => format!("Found {}, but the account is disabled in the directory.", entry.dn),
+        (Some(r), false) => format!("Found {}; they would sign in as {}.", entry.dn, r.as_str()),
+        (None, false) => format!("Found {}, but none of their groups is mapped to a role.", entry.dn),
+    };
+    Ok(LdapTestResult { message, mapped_role: role.map(|r| r.as_str().to_string()), entry: Some(entry) })
+}
+
+#[tauri::command]
+pub fn list_ldap_group_mappings(state: State<AppState>, token: String) -> Result<Vec<GroupRoleMapping>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::DirectoryManage)?;
+    ldap::list_group_mappings(&db.conn)
+}
+
+/// Map a directory group to a role, or change an existing mapping. Takes
+/// effect at each member's next login or the next account sync.
+#[tauri::command]
+pub fn set_ldap_group_mapping(state: State<AppState>, token: String, group_dn: String, role: String) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::DirectoryManage)?;
+    ldap::set_group_mapping(&db.conn, &group_dn, &role)?;
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "update", "ldap_group_role", Some(group_dn.trim()), None, Some(&role), None,
+    )
+    .ok();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_ldap_group_mapping(state: State<AppState>, token: String, group_dn: String) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::DirectoryManage)?;
+    if !ldap::delete_group_mapping(&db.conn, &group_dn)? {
+        return Err(AppError::not_found("group_mapping", "No mapping for that group"));
+    }
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "delete", "ldap_group_role", Some(&group_dn), None, None, None,
+    )
+    .ok();
+    Ok(())
+}
+
+/// Re-read every directory account now rather than on the next scheduler
+/// tick — e.g. straight after someone leaves.
+#[tauri::command]
+pub fn sync_directory_accounts(state: State<AppState>, token: String) -> Result<DirectorySyncReport, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::DirectoryManage)?;
+    let dir = ldap::configured_directory(&db.conn)?.ok_or_else(|| AppError::invalid("Directory authentication is not enabled"))?;
+    let default_role = dir.config().default_role.clone();
+    ldap::sync_accounts(&db.conn, &dir, default_role.as_deref(), Some(&user.id))
+}
@@ -1,4 +1,6 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::error::AppError;
 use crate::models::error_log::*;
 use crate::models::specimen::PaginatedResponse;
 use crate::db::queries;
@@ -11,7 +13,7 @@ pub fn log_error(
     state: State<AppState>,
     token: String,
     request: CreateErrorLogRequest,
-) -> Result<ErrorLog, String> {
+) -> Result<ErrorLog, AppError> {
     let db = state.db();
     // Allow any authenticated user to log errors; also allow unauthenticated
     // (token may be empty on startup errors) by soft-validating.
@@ -43,7 +45,7 @@ pub fn log_error(
         "SELECT * FROM error_logs WHERE id = ?1",
         params![id],
         map_row,
-    ).map_err(|e| format!("Failed to retrieve logged error: {}", e))
+    ).map_err(|e| AppError::internal(format!("Failed to retrieve logged error: {}", e)))
 }
 
 #[tauri::command]
@@ -51,7 +53,7 @@ pub fn list_error_logs(
     state: State<AppState>,
     token: String,
     search: ErrorLogSearchParams,
-) -> Result<PaginatedResponse<ErrorLog>, String> {
+) -> Result<PaginatedResponse<ErrorLog>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -85,8 +87,7 @@ pub fn list_error_logs(
 
     let count_sql = format!("SELECT COUNT(*) FROM error_logs {}", where_clause);
     let bind_refs: Vec<&dyn rusqlite::types::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
-    let total: i64 = db.conn.query_row(&count_sql, bind_refs.as_slice(), |r| r.get(0))
-        .map_err(|e| e.to_string())?;
+    let total: i64 = db.conn.query_row(&count_sql, bind_refs.as_slice(), |r| r.get(0))?;
 
     let query_sql = format!(
         "SELECT * FROM error_logs {} ORDER BY timestamp DESC LIMIT ?{} OFFSET ?{}",
@@ -99,10 +100,9 @@ pub fn list_error_logs(
     bind_values.push(Box::new(pg.offset()));
 
     let bind_refs2: Vec<&dyn rusqlite::types::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
-    let mut stmt = db.conn.prepare(&query_sql).map_err(|e| e.to_string())?;
+    let mut stmt = db.conn.prepare(&query_sql)?;
 
-    let entries = stmt.query_map(bind_refs2.as_slice(), map_row)
-        .map_err(|e| e.to_string())?
+    let entries = stmt.query_map(bind_refs2.as_slice(), map_row)?
         .filter_map(|r| r.ok())
         .collect::<Vec<_>>();
 
@@ -121,7 +121,7 @@ pub fn list_error_logs(
 pub fn get_unread_error_count(
     state: State<AppState>,
     token: String,
-) -> Result<i64, String> {
+) -> Result<i64, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -129,14 +129,14 @@ pub fn get_unread_error_count(
         "SELECT COUNT(*) FROM error_logs WHERE is_read = 0",
         [],
         |r| r.get(0),
-    ).map_err(|e| e.to_string())
+    ).map_err(AppError::from)
 }
 
 #[tauri::command]
 pub fn mark_errors_read(
     state: State<AppState>,
     token: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -152,12 +152,10 @@ pub fn mark_errors_read(
 pub fn clear_error_logs(
     state: State<AppState>,
     token: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can clear error logs".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ErrorLogClear)?;
 
     db.conn.execute("DELETE FROM error_logs", [])
         .map_err(|e| format!("Failed to clear error logs: {}", e))?;
@@ -1,5 +1,7 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::fruiting::{CreateFruitingRecordRequest, FruitingRecord, FruitingRecordWithSpecimen};
 use crate::AppState;
 use tauri::State;
@@ -9,12 +11,10 @@ pub fn create_fruiting_record(
     state: State<AppState>,
     token: String,
     request: CreateFruitingRecordRequest,
-) -> Result<FruitingRecord, String> {
+) -> Result<FruitingRecord, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::FruitingRecord)?;
     let id = queries::create_fruiting_record(&db.conn, &request, Some(&user.id))
         .map_err(|e| format!("Failed to create fruiting record: {}", e))?;
 
@@ -24,7 +24,7 @@ pub fn create_fruiting_record(
     ).ok();
 
     queries::get_fruiting_record(&db.conn, &id)
-        .map_err(|e| format!("Failed to retrieve fruiting record: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to retrieve fruiting record: {}", e)))
 }
 
 #[tauri::command]
@@ -32,11 +32,11 @@ pub fn list_fruiting_records(
     state: State<AppState>,
     token: String,
     specimen_id: String,
-) -> Result<Vec<FruitingRecord>, String> {
+) -> Result<Vec<FruitingRecord>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     queries::list_fruiting_records(&db.conn, &specimen_id)
-        .map_err(|e| format!("Failed to list fruiting records: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to list fruiting records: {}", e)))
 }
 
 /// Cross-specimen fruiting overview (mycology): every flush across all
@@ -45,9 +45,9 @@ pub fn list_fruiting_records(
 pub fn list_all_fruiting_records(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<FruitingRecordWithSpecimen>, String> {
+) -> Result<Vec<FruitingRecordWithSpecimen>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     queries::list_all_fruiting_records(&db.conn)
-        .map_err(|e| format!("Failed to list fruiting records: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to list fruiting records: {}", e)))
 }
@@ -6,6 +6,8 @@
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::error::AppError;
 use crate::integrity;
 use crate::AppState;
 
@@ -14,11 +16,9 @@ use crate::AppState;
 pub fn run_data_integrity_check(
     state: State<AppState>,
     token: String,
-) -> Result<integrity::IntegrityReport, String> {
+) -> Result<integrity::IntegrityReport, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Data-integrity checks are restricted to administrators".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::IntegrityCheck)?;
     integrity::run_integrity_check(&db.conn)
 }
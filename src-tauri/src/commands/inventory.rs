@@ -1,5 +1,8 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::permissions::{mask_for_role, reject_if_restricted_marker, Masked};
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::inventory::*;
 use crate::AppState;
 use rusqlite::params;
@@ -30,22 +33,20 @@ fn row_to_item(row: &rusqlite::Row) -> rusqlite::Result<InventoryItem> {
 }
 
 #[tauri::command]
-pub fn list_inventory(state: State<AppState>, token: String) -> Result<Vec<InventoryItem>, String> {
+pub fn list_inventory(state: State<AppState>, token: String) -> Result<Masked<Vec<InventoryItem>>, AppError> {
     let db = state.db();
-    let _user = auth_service::validate_session(&db, &token)?;
+    let user = auth_service::validate_session(&db, &token)?;
 
     let mut stmt = db
         .conn
-        .prepare("SELECT * FROM inventory_items ORDER BY category, name")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT * FROM inventory_items ORDER BY category, name")?;
 
-    let items = stmt
-        .query_map([], row_to_item)
-        .map_err(|e| e.to_string())?
+    let items: Vec<InventoryItem> = stmt
+        .query_map([], row_to_item)?
         .filter_map(|r| r.ok())
         .collect();
 
-    Ok(items)
+    mask_for_role(&db.conn, user.role.as_str(), items)
 }
 
 #[tauri::command]
@@ -53,12 +54,11 @@ pub fn create_inventory_item(
     state: State<AppState>,
     token: String,
     request: CreateInventoryItemRequest,
-) -> Result<InventoryItem, String> {
+) -> Result<Masked<InventoryItem>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::InventoryEdit)?;
+    reject_if_restricted_marker(request.supplier.as_deref(), "Supplier")?;
 
     let id = uuid::Uuid::new_v4().to_string();
     let current_stock = request.current_stock.unwrap_or(0.0);
@@ -88,9 +88,11 @@ pub fn create_inventory_item(
         None, Some(&request.name), Some("Inventory item created"),
     ).ok();
 
-    db.conn
+    let item = db
+        .conn
         .query_row("SELECT * FROM inventory_items WHERE id = ?1", params![id], row_to_item)
-        .map_err(|e| format!("Failed to retrieve created item: {}", e))
+        .map_err(|e| format!("Failed to retrieve created item: {}", e))?;
+    mask_for_role(&db.conn, user.role.as_str(), item)
 }
 
 #[tauri::command]
@@ -98,12 +100,12 @@ pub fn update_inventory_item(
     state: State<AppState>,
     token: String,
     request: UpdateInventoryItemRequest,
-) -> Result<InventoryItem, String> {
+) -> Result<Masked<InventoryItem>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::InventoryEdit)?;
+    // WP-87: the edit form is filled from a possibly masked read.
+    reject_if_restricted_marker(request.supplier.as_deref(), "Supplier")?;
 
     let mut updates = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -143,7 +145,7 @@ pub fn update_inventory_item(
     add_f64!(cost_per_unit, "cost_per_unit");
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
 
     updates.push("updated_at = datetime('now')".to_string());
@@ -164,9 +166,11 @@ pub fn update_inventory_item(
         None, None, Some("Inventory item updated"),
     ).ok();
 
-    db.conn
+    let item = db
+        .conn
         .query_row("SELECT * FROM inventory_items WHERE id = ?1", params![request.id], row_to_item)
-        .map_err(|e| format!("Failed to retrieve updated item: {}", e))
+        .map_err(|e| format!("Failed to retrieve updated item: {}", e))?;
+    mask_for_role(&db.conn, user.role.as_str(), item)
 }
 
 #[tauri::command]
@@ -174,12 +178,10 @@ pub fn delete_inventory_item(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can delete inventory items".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::InventoryDelete)?;
 
     db.conn
         .execute("DELETE FROM inventory_items WHERE id = ?1", params![id])
@@ -200,12 +202,10 @@ pub fn adjust_stock(
     id: String,
     adjustment: f64,
     reason: Option<String>,
-) -> Result<InventoryItem, String> {
+) -> Result<Masked<InventoryItem>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::InventoryEdit)?;
 
     let current: f64 = db
         .conn
@@ -214,11 +214,11 @@ pub fn adjust_stock(
             params![id],
             |row| row.get(0),
         )
-        .map_err(|_| "Inventory item not found".to_string())?;
+        .map_err(|_| AppError::not_found("inventory_item", "Inventory item not found"))?;
 
     let new_stock = current + adjustment;
     if new_stock < 0.0 {
-        return Err("Stock cannot go below zero".to_string());
+        return Err(AppError::validation("adjustment", "Stock cannot go below zero"));
     }
 
     db.conn
@@ -238,20 +238,22 @@ pub fn adjust_stock(
     );
 
     queries::log_audit(
-        &db.conn, Some(&user.id), "update", "inventory_item", Some(&id),
+        &db.conn, Some(&user.id), "adjust_stock", "inventory_item", Some(&id),
         Some(&current.to_string()), Some(&new_stock.to_string()), Some(&detail),
     ).ok();
 
-    db.conn
+    let item = db
+        .conn
         .query_row("SELECT * FROM inventory_items WHERE id = ?1", params![id], row_to_item)
-        .map_err(|e| format!("Failed to retrieve item: {}", e))
+        .map_err(|e| format!("Failed to retrieve item: {}", e))?;
+    mask_for_role(&db.conn, user.role.as_str(), item)
 }
 
 #[tauri::command]
 pub fn get_low_stock_alerts(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<LowStockAlert>, String> {
+) -> Result<Vec<LowStockAlert>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -263,8 +265,7 @@ pub fn get_low_stock_alerts(
              WHERE current_stock <= minimum_stock
                 OR (reorder_point IS NOT NULL AND current_stock <= reorder_point)
              ORDER BY (current_stock / CASE WHEN minimum_stock > 0 THEN minimum_stock ELSE 1 END) ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let alerts = stmt
         .query_map([], |row| {
@@ -278,8 +279,7 @@ pub fn get_low_stock_alerts(
                 unit: row.get(6)?,
                 physical_state: row.get(7)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -292,7 +292,7 @@ pub fn get_low_stock_alerts(
 pub fn list_prepared_solutions(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<PreparedSolution>, String> {
+) -> Result<Vec<PreparedSolution>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -301,7 +301,7 @@ pub fn list_prepared_solutions(
          FROM prepared_solutions ps
          LEFT JOIN inventory_items ii ON ps.source_item_id = ii.id
          ORDER BY ps.preparation_date DESC"
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let solutions = stmt.query_map([], |row| {
         Ok(PreparedSolution {
@@ -323,7 +323,7 @@ pub fn list_prepared_solutions(
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect();
 
@@ -335,12 +335,10 @@ pub fn create_prepared_solution(
     state: State<AppState>,
     token: String,
     request: CreatePreparedSolutionRequest,
-) -> Result<PreparedSolution, String> {
+) -> Result<PreparedSolution, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::InventoryEdit)?;
 
     let id = uuid::Uuid::new_v4().to_string();
 
@@ -375,7 +373,7 @@ pub fn create_prepared_solution(
                 params![new_stock, src_id],
             ).ok();
             queries::log_audit(
-                &db.conn, Some(&user.id), "update", "inventory_item", Some(src_id),
+                &db.conn, Some(&user.id), "consume", "inventory_item", Some(src_id),
                 Some(&cur.to_string()), Some(&new_stock.to_string()),
                 Some(&format!("Stock deducted for prepared solution: {}", request.name)),
             ).ok();
@@ -414,7 +412,7 @@ pub fn create_prepared_solution(
                 updated_at: row.get("updated_at")?,
             })
         },
-    ).map_err(|e| format!("Failed to fetch created solution: {}", e))
+    ).map_err(|e| AppError::internal(format!("Failed to fetch created solution: {}", e)))
 }
 
 #[tauri::command]
@@ -422,12 +420,10 @@ pub fn update_prepared_solution(
     state: State<AppState>,
     token: String,
     request: UpdatePreparedSolutionRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::InventoryEdit)?;
 
     let mut updates = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -450,7 +446,7 @@ pub fn update_prepared_solution(
     }
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
 
     updates.push("updated_at = datetime('now')".to_string());
@@ -478,12 +474,10 @@ pub fn delete_prepared_solution(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can delete prepared solutions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::InventoryDelete)?;
 
     db.conn.execute("DELETE FROM prepared_solutions WHERE id = ?1", params![id])
         .map_err(|e| format!("Failed to delete prepared solution: {}", e))?;
@@ -497,10 +491,10 @@ pub fn delete_prepared_solution(
 }
 
 /// Pure stock-level computation extracted for testability.
-pub fn apply_stock_adjustment(current: f64, adjustment: f64) -> Result<f64, String> {
+pub fn apply_stock_adjustment(current: f64, adjustment: f64) -> Result<f64, AppError> {
     let next = current + adjustment;
     if next < 0.0 {
-        Err("Stock cannot go below zero".to_string())
+        Err(AppError::validation("adjustment", "Stock cannot go below zero"))
     } else {
         Ok(next)
     }
@@ -534,7 +528,8 @@ mod tests {
     #[test]
     fn stock_adjustment_below_zero_is_error() {
         let err = apply_stock_adjustment(2.0, -3.0).unwrap_err();
-        assert!(err.contains("below zero"));
+        assert!(err.message().contains("below zero"));
+        assert_eq!(err.code(), "validation");
     }
 
     #[test]
@@ -0,0 +1,29 @@
+// WP-101: a specimen's lineage as a graph — metrics for the detail page, and
+// GraphML, DOT or Newick exports for papers and IP disputes. Read-only, so
+// any signed-in user of the specimen's lab can use them.
+use crate::auth as auth_service;
+use crate::db::lineage::{self, LineageFormat, LineageMetrics};
+use crate::db::specimen_merge;
+use crate::error::AppError;
+use crate::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_lineage_metrics(state: State<AppState>, token: String, id: String) -> Result<LineageMetrics, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    let id = specimen_merge::resolve(&db.conn, &id)?;
+    crate::db::vocabulary::require_active_lab_profile(&db.conn, &id)?;
+    Ok(lineage::load(&db.conn, &id)?.metrics())
+}
+
+/// The lineage of specimen `id` as `graphml`, `dot` or `newick` text.
+#[tauri::command]
+pub fn export_lineage(state: State<AppState>, token: String, id: String, format: String) -> Result<String, AppError> {
+    let format = LineageFormat::parse(&format)?;
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    let id = specimen_merge::resolve(&db.conn, &id)?;
+    crate::db::vocabulary::require_active_lab_profile(&db.conn, &id)?;
+    Ok(lineage::load(&db.conn, &id)?.export(format))
+}
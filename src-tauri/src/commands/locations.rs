@@ -3,6 +3,8 @@ use rusqlite::params;
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::error::AppError;
 use crate::models::location::{CreateLocationRequest, Location, LocationMapPoint, UpdateLocationRequest};
 use crate::AppState;
 
@@ -20,28 +22,26 @@ fn row_to_location(row: &rusqlite::Row) -> rusqlite::Result<Location> {
 }
 
 #[tauri::command]
-pub fn list_locations(state: State<AppState>, token: String) -> Result<Vec<Location>, String> {
+pub fn list_locations(state: State<AppState>, token: String) -> Result<Vec<Location>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let mut stmt = db
         .conn
-        .prepare("SELECT * FROM locations ORDER BY name ASC")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT * FROM locations ORDER BY name ASC")?;
     let rows = stmt
-        .query_map([], row_to_location)
-        .map_err(|e| e.to_string())?
+        .query_map([], row_to_location)?
         .filter_map(|r| r.ok())
         .collect();
     Ok(rows)
 }
 
 #[tauri::command]
-pub fn get_location(state: State<AppState>, token: String, id: String) -> Result<Location, String> {
+pub fn get_location(state: State<AppState>, token: String, id: String) -> Result<Location, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     db.conn
         .query_row("SELECT * FROM locations WHERE id = ?1", [id], row_to_location)
-        .map_err(|e| format!("Location not found: {}", e))
+        .map_err(|e| AppError::not_found("location", format!("Location not found: {}", e)))
 }
 
 #[tauri::command]
@@ -49,14 +49,12 @@ pub fn create_location(
     state: State<AppState>,
     token: String,
     request: CreateLocationRequest,
-) -> Result<Location, String> {
+) -> Result<Location, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::LocationEdit)?;
     if request.name.trim().is_empty() {
-        return Err("Location name is required".to_string());
+        return Err(AppError::validation("name", "Location name is required"));
     }
 
     let id = uuid::Uuid::new_v4().to_string();
@@ -78,7 +76,7 @@ pub fn create_location(
 
     db.conn
         .query_row("SELECT * FROM locations WHERE id = ?1", [&id], row_to_location)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -86,12 +84,10 @@ pub fn update_location(
     state: State<AppState>,
     token: String,
     request: UpdateLocationRequest,
-) -> Result<Location, String> {
+) -> Result<Location, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::LocationEdit)?;
 
     let mut updates = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -110,7 +106,7 @@ pub fn update_location(
     add_update!(floor_plan_y, "floor_plan_y");
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
     updates.push("updated_at = datetime('now')".to_string());
     let sql = format!("UPDATE locations SET {} WHERE id = ?{}", updates.join(", "), values.len() + 1);
@@ -125,29 +121,27 @@ pub fn update_location(
 
     db.conn
         .query_row("SELECT * FROM locations WHERE id = ?1", [&request.id], row_to_location)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn delete_location(state: State<AppState>, token: String, id: String) -> Result<(), String> {
+pub fn delete_location(state: State<AppState>, token: String, id: String) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can delete locations".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::LocationDelete)?;
 
     let pinned_count: i64 = db
         .conn
         .query_row("SELECT COUNT(*) FROM specimens WHERE location_id = ?1", [&id], |r| r.get(0))
         .unwrap_or(0);
     if pinned_count > 0 {
-        return Err(format!(
+        return Err(AppError::conflict(format!(
             "Cannot delete: {} specimen(s) are still pinned to this location. Unpin them first.",
             pinned_count
-        ));
+        )));
     }
 
-    db.conn.execute("DELETE FROM locations WHERE id = ?1", [&id]).map_err(|e| e.to_string())?;
+    db.conn.execute("DELETE FROM locations WHERE id = ?1", [&id])?;
 
     crate::db::queries::log_audit(
         &db.conn, Some(&user.id), "delete", "location", Some(&id),
@@ -168,12 +162,10 @@ pub fn set_specimen_location_pin(
     token: String,
     specimen_id: String,
     location_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::LocationEdit)?;
     crate::db::vocabulary::require_active_lab_profile(&db.conn, &specimen_id)?;
     db.conn
         .execute(
@@ -181,6 +173,10 @@ pub fn set_specimen_location_pin(
             params![location_id, specimen_id],
         )
         .map_err(|e| format!("Failed to set location pin: {}", e))?;
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "relocate", "specimen", Some(&specimen_id),
+        None, location_id.as_deref(), Some("Location pin set"),
+    ).ok();
     Ok(())
 }
 
@@ -189,7 +185,7 @@ pub fn set_specimen_location_pin(
 /// aggregates, computed server-side so the client never has to fetch every
 /// specimen just to render a heat-map.
 #[tauri::command]
-pub fn get_location_map_data(state: State<AppState>, token: String) -> Result<Vec<LocationMapPoint>, String> {
+pub fn get_location_map_data(state: State<AppState>, token: String) -> Result<Vec<LocationMapPoint>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let mut stmt = db
@@ -206,8 +202,7 @@ pub fn get_location_map_data(state: State<AppState>, token: String) -> Result<Ve
              LEFT JOIN specimens sp ON sp.location_id = l.id AND sp.is_archived = 0 \
              GROUP BY l.id \
              ORDER BY l.name ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let rows = stmt
         .query_map([], |r| {
             Ok(LocationMapPoint {
@@ -219,8 +214,7 @@ pub fn get_location_map_data(state: State<AppState>, token: String) -> Result<Ve
                 contaminated_count: r.get::<_, Option<i64>>(5)?.unwrap_or(0),
                 avg_age_days: r.get(6)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
     Ok(rows)
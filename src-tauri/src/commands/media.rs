@@ -1,18 +1,21 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::permissions::{mask_for_role, reject_if_restricted_marker, Masked};
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::media::*;
 use crate::AppState;
 use rusqlite::params;
 use tauri::State;
 
 #[tauri::command]
-pub fn list_media(state: State<AppState>, token: String) -> Result<Vec<MediaBatch>, String> {
+pub fn list_media(state: State<AppState>, token: String) -> Result<Masked<Vec<MediaBatch>>, AppError> {
     let db = state.db();
-    let _user = auth_service::validate_session(&db, &token)?;
+    let user = auth_service::validate_session(&db, &token)?;
 
     let mut stmt = db.conn.prepare(
         "SELECT * FROM media_batches ORDER BY preparation_date DESC"
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let batches: Vec<MediaBatch> = stmt.query_map([], |row| {
         Ok(MediaBatch {
@@ -49,7 +52,7 @@ pub fn list_media(state: State<AppState>, token: String) -> Result<Vec<MediaBatc
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect();
 
@@ -65,8 +68,7 @@ pub fn list_media(state: State<AppState>, token: String) -> Result<Vec<MediaBatc
     {
         let mut h_stmt = db
             .conn
-            .prepare("SELECT * FROM media_hormones")
-            .map_err(|e| e.to_string())?;
+            .prepare("SELECT * FROM media_hormones")?;
         let rows = h_stmt
             .query_map([], |row| {
                 Ok((
@@ -83,10 +85,9 @@ pub fn list_media(state: State<AppState>, token: String) -> Result<Vec<MediaBatc
                         amount_unit: row.get("amount_unit")?,
                     },
                 ))
-            })
-            .map_err(|e| e.to_string())?;
+            })?;
         for row in rows {
-            let (batch_id, hormone) = row.map_err(|e| e.to_string())?;
+            let (batch_id, hormone) = row?;
             by_batch.entry(batch_id).or_default().push(hormone);
         }
     }
@@ -96,13 +97,13 @@ pub fn list_media(state: State<AppState>, token: String) -> Result<Vec<MediaBatc
         batch.hormones = by_batch.remove(&batch.id).unwrap_or_default();
     }
 
-    Ok(result)
+    mask_for_role(&db.conn, user.role.as_str(), result)
 }
 
 #[tauri::command]
-pub fn get_media_batch(state: State<AppState>, token: String, id: String) -> Result<MediaBatch, String> {
+pub fn get_media_batch(state: State<AppState>, token: String, id: String) -> Result<Masked<MediaBatch>, AppError> {
     let db = state.db();
-    let _user = auth_service::validate_session(&db, &token)?;
+    let user = auth_service::validate_session(&db, &token)?;
 
     let mut batch = db.conn.query_row(
         "SELECT * FROM media_batches WHERE id = ?1",
@@ -143,11 +144,11 @@ pub fn get_media_batch(state: State<AppState>, token: String, id: String) -> Res
                 updated_at: row.get("updated_at")?,
             })
         },
-    ).map_err(|e| format!("Media batch not found: {}", e))?;
+    ).map_err(|_| AppError::not_found("media_batch", "Media batch not found").with_id(&id))?;
 
     let mut h_stmt = db.conn.prepare(
         "SELECT * FROM media_hormones WHERE media_batch_id = ?1"
-    ).map_err(|e| e.to_string())?;
+    )?;
     batch.hormones = h_stmt.query_map(params![batch.id], |row| {
         Ok(MediaHormone {
             id: row.get("id")?,
@@ -160,11 +161,11 @@ pub fn get_media_batch(state: State<AppState>, token: String, id: String) -> Res
             amount_used: row.get("amount_used")?,
             amount_unit: row.get("amount_unit")?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect();
 
-    Ok(batch)
+    mask_for_role(&db.conn, user.role.as_str(), batch)
 }
 
 #[tauri::command]
@@ -172,11 +173,14 @@ pub fn create_media_batch(
     state: State<AppState>,
     token: String,
     request: CreateMediaBatchRequest,
-) -> Result<MediaBatch, String> {
+) -> Result<Masked<MediaBatch>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
+    auth_service::require_capability(&db, &user, Capability::MediaEdit)?;
+    // WP-87: a batch copied from a masked one must not store the placeholder.
+    reject_if_restricted_marker(request.supplier_info.as_deref(), "Supplier info")?;
+    for hormone in request.hormones.iter().flatten() {
+        reject_if_restricted_marker(hormone.supplier.as_deref(), "Hormone supplier")?;
     }
 
     let id = uuid::Uuid::new_v4().to_string();
@@ -249,7 +253,7 @@ pub fn create_media_batch(
     // Audit entries written after commit (non-critical, best-effort)
     for (inv_id, old_stock, new_stock, hormone_name) in &inv_audit {
         crate::db::queries::log_audit(
-            &db.conn, Some(&user.id), "update", "inventory_item", Some(inv_id),
+            &db.conn, Some(&user.id), "consume", "inventory_item", Some(inv_id),
             Some(&old_stock.to_string()), Some(&new_stock.to_string()),
             Some(&format!("Used in media batch {} ({})", batch_id, hormone_name)),
         ).ok();
@@ -269,12 +273,10 @@ pub fn update_media_batch(
     state: State<AppState>,
     token: String,
     request: UpdateMediaBatchRequest,
-) -> Result<MediaBatch, String> {
+) -> Result<Masked<MediaBatch>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::MediaEdit)?;
 
     let mut updates = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -313,7 +315,7 @@ pub fn update_media_batch(
     }
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
 
     updates.push("updated_at = datetime('now')".to_string());
@@ -338,15 +340,12 @@ pub fn update_media_batch(
 }
 
 #[tauri::command]
-pub fn delete_media_batch(state: State<AppState>, token: String, id: String) -> Result<(), String> {
+pub fn delete_media_batch(state: State<AppState>, token: String, id: String) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can delete media batches".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::MediaDelete)?;
 
-    db.conn.execute("DELETE FROM media_hormones WHERE media_batch_id = ?1", params![id])
-        .map_err(|e| e.to_string())?;
+    db.conn.execute("DELETE FROM media_hormones WHERE media_batch_id = ?1", params![id])?;
     db.conn.execute("DELETE FROM media_batches WHERE id = ?1", params![id])
         .map_err(|e| format!("Failed to delete media batch: {}", e))?;
 
@@ -368,12 +367,10 @@ pub fn create_draft_media_batch(
     state: State<AppState>,
     token: String,
     name: String,
-) -> Result<MediaBatch, String> {
+) -> Result<Masked<MediaBatch>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::MediaEdit)?;
 
     let id = uuid::Uuid::new_v4().to_string();
     let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
@@ -392,7 +389,7 @@ pub fn create_draft_media_batch(
         None, Some(&batch_id), Some("Draft media batch created during split"),
     ).ok();
 
-    Ok(MediaBatch {
+    let draft = MediaBatch {
         id,
         batch_id,
         name: draft_name,
@@ -422,10 +419,11 @@ pub fn create_draft_media_batch(
         notes: Some("Draft batch — complete formulation in Media Management".to_string()),
         hormones: Vec::new(),
         employee_id: None,
-        created_by: Some(user.id),
+        created_by: Some(user.id.clone()),
         created_at: today.clone(),
         updated_at: today,
-    })
+    };
+    mask_for_role(&db.conn, user.role.as_str(), draft)
 }
 
 fn generate_batch_id(conn: &rusqlite::Connection) -> String {
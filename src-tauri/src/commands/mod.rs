@@ -40,3 +40,13 @@ pub mod passport;
 pub mod registry;
 pub mod coordination;
 pub mod integrity;
+pub mod directory;
+pub mod roles;
+pub mod api;
+pub mod api_tokens;
+pub mod accession;
+pub mod custom_fields;
+pub mod saved_searches;
+pub mod stage_transitions;
+pub mod lineage;
+pub mod trials;
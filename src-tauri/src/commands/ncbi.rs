@@ -1,5 +1,7 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::taxon::{
     ImportNcbiTaxonomyRequest, ImportNcbiTaxonomyResult, NcbiConflictSummary, NcbiSyncLog,
     NcbiTaxonRecord, ResolveNcbiConflictRequest,
@@ -31,12 +33,10 @@ pub fn import_ncbi_taxonomy(
     state: State<AppState>,
     token: String,
     request: ImportNcbiTaxonomyRequest,
-) -> Result<ImportNcbiTaxonomyResult, String> {
+) -> Result<ImportNcbiTaxonomyResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can import NCBI taxonomy data".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::TaxonomyNcbi)?;
 
     let now = chrono::Utc::now()
         .format("%Y-%m-%dT%H:%M:%S%.3fZ")
@@ -68,8 +68,7 @@ pub fn import_ncbi_taxonomy(
         };
 
         // Primary lookup: match by ncbi_taxon_id.
-        let by_ncbi_id = queries::find_taxon_by_ncbi_id(&db.conn, record.ncbi_taxon_id)
-            .map_err(|e| e.to_string())?;
+        let by_ncbi_id = queries::find_taxon_by_ncbi_id(&db.conn, record.ncbi_taxon_id)?;
 
         if let Some(local) = by_ncbi_id {
             if local.local_override {
@@ -107,8 +106,7 @@ pub fn import_ncbi_taxonomy(
         }
 
         // Secondary lookup: match by name + normalized rank.
-        let by_name = queries::find_taxon_by_name_rank(&db.conn, &record.name, rank)
-            .map_err(|e| e.to_string())?;
+        let by_name = queries::find_taxon_by_name_rank(&db.conn, &record.name, rank)?;
 
         if let Some(local) = by_name {
             if local.local_override {
@@ -189,8 +187,7 @@ pub fn import_ncbi_taxonomy(
                         Some(action.record.ncbi_taxon_id),
                         None,
                         &now,
-                    )
-                    .map_err(|e| e.to_string())?;
+                    )?;
                     imported += 1;
                 }
                 ActionKind::Update => {
@@ -209,8 +206,7 @@ pub fn import_ncbi_taxonomy(
                         Some(action.record.ncbi_taxon_id),
                         None,
                         &now,
-                    )
-                    .map_err(|e| e.to_string())?;
+                    )?;
                     updated += 1;
                 }
                 ActionKind::Conflict => {
@@ -223,8 +219,7 @@ pub fn import_ncbi_taxonomy(
                         Some(action.record.ncbi_taxon_id),
                         action.conflict_details.as_deref(),
                         &now,
-                    )
-                    .map_err(|e| e.to_string())?;
+                    )?;
                     conflicts.push(NcbiConflictSummary {
                         sync_log_id: Some(action.log_id.clone()),
                         taxon_id: action.taxon_id.clone(),
@@ -243,6 +238,14 @@ pub fn import_ncbi_taxonomy(
             }
         }
 
+        queries::log_audit(
+            &tx, Some(&user.id), "ncbi_import", "taxonomy", None, None, None,
+            Some(&format!(
+                "NCBI taxonomy import: {} imported, {} updated, {} conflicts, {} skipped (local override)",
+                imported, updated, conflicts.len(), skipped_overrides
+            )),
+        )
+        .ok();
         tx.commit().map_err(|e| format!("Failed to commit import: {}", e))?;
     } else {
         // Dry-run: tally without writing.
@@ -291,20 +294,18 @@ pub fn resolve_ncbi_conflict(
     state: State<AppState>,
     token: String,
     request: ResolveNcbiConflictRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can resolve NCBI taxonomy conflicts".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::TaxonomyNcbi)?;
 
     let valid_resolutions = ["kept_local", "accepted_ncbi", "merged"];
     if !valid_resolutions.contains(&request.resolution.as_str()) {
-        return Err(format!(
+        return Err(AppError::validation("resolution", format!(
             "Invalid resolution '{}'. Must be one of: {}",
             request.resolution,
             valid_resolutions.join(", ")
-        ));
+        )));
     }
 
     // Load the sync log entry.
@@ -315,7 +316,7 @@ pub fn resolve_ncbi_conflict(
             params![request.sync_log_id],
             |r| Ok((r.get(0)?, r.get(1)?)),
         )
-        .map_err(|e| format!("Sync log entry not found: {}", e))?;
+        .map_err(|_| AppError::not_found("ncbi_sync_log", "Sync log entry not found").with_id(&request.sync_log_id))?;
 
     // Check it isn't already resolved.
     let already_resolved: bool = db
@@ -327,7 +328,7 @@ pub fn resolve_ncbi_conflict(
         )
         .unwrap_or(false);
     if already_resolved {
-        return Err("This conflict has already been resolved".to_string());
+        return Err(AppError::conflict("This conflict has already been resolved"));
     }
 
     if request.resolution == "accepted_ncbi" {
@@ -348,6 +349,12 @@ pub fn resolve_ncbi_conflict(
             params![now, user.id, request.resolution, request.sync_log_id],
         )
         .map_err(|e| format!("Failed to update sync log: {}", e))?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "ncbi_resolve", "taxon",
+        Some(taxon_id.as_deref().unwrap_or(&request.sync_log_id)),
+        None, Some(&request.resolution), Some(&format!("NCBI conflict {} resolved", request.sync_log_id)),
+    )
+    .ok();
 
     Ok(())
 }
@@ -357,9 +364,9 @@ fn apply_ncbi_values(
     conn: &rusqlite::Connection,
     taxon_id: &str,
     conflict_details: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let details: serde_json::Value =
-        serde_json::from_str(conflict_details).map_err(|e| e.to_string())?;
+        serde_json::from_str(conflict_details)?;
 
     let mut updates: Vec<String> = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -412,23 +419,20 @@ pub fn sync_ncbi_taxon(
     state: State<AppState>,
     token: String,
     record: NcbiTaxonRecord,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can sync NCBI taxonomy data".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::TaxonomyNcbi)?;
 
     let rank = queries::normalize_ncbi_rank(&record.rank)
-        .ok_or_else(|| format!("Unsupported NCBI rank: '{}'", record.rank))?;
+        .ok_or_else(|| AppError::validation("rank", format!("Unsupported NCBI rank: '{}'", record.rank)))?;
 
     let now = chrono::Utc::now()
         .format("%Y-%m-%dT%H:%M:%S%.3fZ")
         .to_string();
 
     // Try to locate the existing local taxon.
-    let existing = queries::find_taxon_by_ncbi_id(&db.conn, record.ncbi_taxon_id)
-        .map_err(|e| e.to_string())?;
+    let existing = queries::find_taxon_by_ncbi_id(&db.conn, record.ncbi_taxon_id)?;
 
     let log_id = uuid::Uuid::new_v4().to_string();
 
@@ -451,8 +455,7 @@ pub fn sync_ncbi_taxon(
                 Some(record.ncbi_taxon_id),
                 Some(&details),
                 &now,
-            )
-            .map_err(|e| e.to_string())?;
+            )?;
             return Ok(format!(
                 "Conflict detected for taxon '{}' (NCBI ID {}) — logged as {}.",
                 local.name, record.ncbi_taxon_id, log_id
@@ -467,6 +470,11 @@ pub fn sync_ncbi_taxon(
                 params![now, local.id],
             )
             .map_err(|e| format!("Failed to update taxon '{}': {}", local.id, e))?;
+        queries::log_audit(
+            &db.conn, Some(&user.id), "ncbi_sync", "taxon", Some(&local.id),
+            None, None, Some(&format!("Synced with NCBI taxon {}", record.ncbi_taxon_id)),
+        )
+        .ok();
         queries::insert_ncbi_sync_log(
             &db.conn,
             &log_id,
@@ -475,8 +483,7 @@ pub fn sync_ncbi_taxon(
             Some(record.ncbi_taxon_id),
             None,
             &now,
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
         return Ok(format!(
             "Taxon '{}' synced successfully (no changes needed).",
             local.name
@@ -494,6 +501,11 @@ pub fn sync_ncbi_taxon(
             params![new_id, rank, record.name, record.ncbi_taxon_id, now, path],
         )
         .map_err(|e| format!("Failed to create taxon '{}': {}", record.name, e))?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "ncbi_sync", "taxon", Some(&new_id),
+        None, Some(&record.name), Some(&format!("Imported from NCBI taxon {}", record.ncbi_taxon_id)),
+    )
+    .ok();
     queries::insert_ncbi_sync_log(
         &db.conn,
         &log_id,
@@ -502,8 +514,7 @@ pub fn sync_ncbi_taxon(
         Some(record.ncbi_taxon_id),
         None,
         &now,
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     Ok(format!(
         "Taxon '{}' (NCBI ID {}) imported as new taxon {}.",
@@ -521,15 +532,15 @@ pub fn list_ncbi_sync_log(
     token: String,
     pending_only: bool,
     limit: Option<i64>,
-) -> Result<Vec<NcbiSyncLog>, String> {
+) -> Result<Vec<NcbiSyncLog>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
     let cap = limit.unwrap_or(200).clamp(1, 1000);
 
     if pending_only {
-        queries::list_pending_ncbi_conflicts(&db.conn).map_err(|e| e.to_string())
+        queries::list_pending_ncbi_conflicts(&db.conn).map_err(AppError::from)
     } else {
-        queries::list_ncbi_sync_log(&db.conn, cap).map_err(|e| e.to_string())
+        queries::list_ncbi_sync_log(&db.conn, cap).map_err(AppError::from)
     }
 }
@@ -8,7 +8,9 @@
 //! paging would be noisy at any nontrivial Work Queue size.
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::notifications as notif_queries;
+use crate::error::AppError;
 use crate::models::notifications::{
     DispatchNotificationsResult, NotificationPreference, SetNotificationPreferenceRequest,
     SetSmtpConfigRequest, SmtpConfig,
@@ -20,10 +22,10 @@ use tauri::State;
 pub fn get_notification_preferences(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<NotificationPreference>, String> {
+) -> Result<Vec<NotificationPreference>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    notif_queries::list_notification_preferences(&db.conn, &user.id).map_err(|e| e.to_string())
+    notif_queries::list_notification_preferences(&db.conn, &user.id).map_err(AppError::from)
 }
 
 /// Every user configures their own preferences — this is not admin-gated.
@@ -32,7 +34,7 @@ pub fn set_notification_preference(
     state: State<AppState>,
     token: String,
     request: SetNotificationPreferenceRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
     notif_queries::set_notification_preference(
@@ -42,17 +44,15 @@ pub fn set_notification_preference(
         request.enabled,
         &request.min_severity,
     )
-    .map_err(|e| e.to_string())
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn get_smtp_config(state: State<AppState>, token: String) -> Result<SmtpConfig, String> {
+pub fn get_smtp_config(state: State<AppState>, token: String) -> Result<SmtpConfig, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can view the SMTP configuration".to_string());
-    }
-    notif_queries::get_smtp_config_display(&db.conn).map_err(|e| e.to_string())
+    auth_service::require_capability(&db, &user, Capability::SystemSettings)?;
+    notif_queries::get_smtp_config_display(&db.conn).map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -60,13 +60,11 @@ pub fn set_smtp_config(
     state: State<AppState>,
     token: String,
     request: SetSmtpConfigRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can change the SMTP configuration".to_string());
-    }
-    notif_queries::set_smtp_config(&db.conn, &request).map_err(|e| e.to_string())?;
+    auth_service::require_capability(&db, &user, Capability::SystemSettings)?;
+    notif_queries::set_smtp_config(&db.conn, &request)?;
     crate::db::queries::log_audit(
         &db.conn, Some(&user.id), "update", "smtp_config", None, None, None,
         Some("SMTP configuration updated"),
@@ -81,7 +79,7 @@ pub fn send_test_desktop_notification(
     app: tauri::AppHandle,
     state: State<AppState>,
     token: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     drop(db);
@@ -92,7 +90,7 @@ pub fn send_test_desktop_notification(
         .title("SteloPTC")
         .body("This is a test notification. If you can see this, desktop notifications are working.")
         .show()
-        .map_err(|e| format!("Failed to show desktop notification: {}", e))
+        .map_err(|e| AppError::internal(format!("Failed to show desktop notification: {}", e)))
 }
 
 /// Admin-only: sends a real test email using the currently-saved SMTP config.
@@ -101,12 +99,10 @@ pub fn send_test_email(
     state: State<AppState>,
     token: String,
     to_address: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can send a test email".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SystemSettings)?;
     notif_queries::send_email(
         &db.conn,
         &to_address,
@@ -121,20 +117,17 @@ pub fn list_recent_notifications(
     state: State<AppState>,
     token: String,
     limit: Option<i64>,
-) -> Result<Vec<crate::models::audit::AuditEntry>, String> {
+) -> Result<Vec<crate::models::audit::AuditEntry>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::NotificationsManage)?;
     let limit = limit.unwrap_or(50).clamp(1, 500);
     let mut stmt = db
         .conn
         .prepare(
             "SELECT a.*, u.username FROM audit_log a LEFT JOIN users u ON a.user_id = u.id \
              WHERE a.entity_type = 'notification' ORDER BY a.created_at DESC LIMIT ?1",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let entries = stmt
         .query_map(rusqlite::params![limit], |row| {
             Ok(crate::models::audit::AuditEntry {
@@ -153,8 +146,7 @@ pub fn list_recent_notifications(
                 prev_hash: row.get("prev_hash")?,
                 entry_hash: row.get("entry_hash")?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
     Ok(entries)
@@ -167,13 +159,11 @@ pub fn dispatch_due_notifications_now(
     app: tauri::AppHandle,
     state: State<AppState>,
     token: String,
-) -> Result<DispatchNotificationsResult, String> {
+) -> Result<DispatchNotificationsResult, AppError> {
     {
         let db = state.db();
         let user = auth_service::validate_session(&db, &token)?;
-        if !user.role.can_manage() {
-            return Err("Insufficient permissions".to_string());
-        }
+        auth_service::require_capability(&db, &user, Capability::NotificationsManage)?;
     }
     dispatch_due_notifications(&app, &state)
 }
@@ -183,7 +173,7 @@ pub fn dispatch_due_notifications_now(
 pub fn dispatch_due_notifications(
     app: &tauri::AppHandle,
     state: &AppState,
-) -> Result<DispatchNotificationsResult, String> {
+) -> Result<DispatchNotificationsResult, AppError> {
     let db = state.db();
     let candidates = notif_queries::compute_due_notifications(&db.conn)?;
 
@@ -209,11 +199,9 @@ pub fn dispatch_due_notifications(
 
     let mut stmt = db
         .conn
-        .prepare("SELECT id, email FROM users WHERE role IN ('admin','supervisor') AND is_active = 1")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT id, email FROM users WHERE role IN ('admin','supervisor') AND is_active = 1")?;
     let recipients: Vec<(String, Option<String>)> = stmt
-        .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, Option<String>>(1)?)))
-        .map_err(|e| e.to_string())?
+        .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, Option<String>>(1)?)))?
         .filter_map(|r| r.ok())
         .collect();
     drop(stmt);
@@ -8,13 +8,17 @@
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::commands::signed_events::verify_ceremony;
+use crate::error::AppError;
 use crate::passport::{store, IssuerIdentity, PassportVerification, SpecimenPassport};
+use crate::signed_ledger::esignature::{self, SignatureCeremony};
 use crate::AppState;
 
 /// This lab's public issuer identity (name + Ed25519 public key). Shared
 /// out-of-band so partner labs can verify the passports this lab issues.
 #[tauri::command]
-pub fn get_lab_identity(state: State<AppState>, token: String) -> Result<IssuerIdentity, String> {
+pub fn get_lab_identity(state: State<AppState>, token: String) -> Result<IssuerIdentity, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::get_lab_identity(&db.conn)
@@ -22,12 +26,10 @@ pub fn get_lab_identity(state: State<AppState>, token: String) -> Result<IssuerI
 
 /// Set this lab's issuer name (appears in every passport it subsequently issues).
 #[tauri::command]
-pub fn set_lab_name(state: State<AppState>, token: String, name: String) -> Result<(), String> {
+pub fn set_lab_name(state: State<AppState>, token: String, name: String) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions — admin or supervisor role required.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::PassportConfigure)?;
     store::set_lab_name(&db.conn, &name)?;
     crate::db::queries::log_audit(
         &db.conn,
@@ -49,19 +51,26 @@ pub fn issue_specimen_passport(
     state: State<AppState>,
     token: String,
     specimen_id: String,
-) -> Result<SpecimenPassport, String> {
+    signature: SignatureCeremony,
+) -> Result<SpecimenPassport, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions — a write-capable role is required to issue a passport.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::PassportExchange)?;
     // A passport is an outward-facing attestation about a specific culture.
     // Issuing one for another lab's specimen would put this lab's name on
     // material it does not hold.
     crate::db::vocabulary::require_active_lab_profile(&db.conn, &specimen_id)?;
-    let passport = store::issue_passport(&db.conn, &specimen_id, Some(&user.id))?;
+    // WP-80: issuing is signed by the issuer under Part 11.
+    let verified = verify_ceremony(&db, &user, &signature, esignature::PASSPORT_ISSUE)?;
+    let tx = db
+        .conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let provenance_visible =
+        crate::db::permissions::is_field_visible(&tx, user.role.as_str(), "specimen", "provenance");
+    let passport = store::issue_passport(&tx, &specimen_id, Some(&user.id), provenance_visible)?;
     crate::db::queries::log_audit(
-        &db.conn,
+        &tx,
         Some(&user.id),
         "issue",
         "specimen_passport",
@@ -74,6 +83,8 @@ pub fn issue_specimen_passport(
         )),
     )
     .ok();
+    esignature::record_signature(&tx, &user, verified, "specimen_passport", &passport.passport_id)?;
+    tx.commit().map_err(|e| format!("Failed to commit passport: {}", e))?;
     Ok(passport)
 }
 
@@ -83,7 +94,7 @@ pub fn verify_specimen_passport(
     state: State<AppState>,
     token: String,
     passport_json: String,
-) -> Result<PassportVerification, String> {
+) -> Result<PassportVerification, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::verify_passport_json(&passport_json)
@@ -95,12 +106,10 @@ pub fn import_specimen_passport(
     state: State<AppState>,
     token: String,
     passport_json: String,
-) -> Result<store::ImportPassportResult, String> {
+) -> Result<store::ImportPassportResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions — a write-capable role is required to import a passport.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::PassportExchange)?;
     store::import_passport(&db.conn, &passport_json, Some(&user.id))
 }
 
@@ -111,7 +120,7 @@ pub fn list_specimen_passports(
     state: State<AppState>,
     token: String,
     direction: Option<String>,
-) -> Result<Vec<store::PassportRecord>, String> {
+) -> Result<Vec<store::PassportRecord>, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::list_passports(&db.conn, direction.as_deref())
@@ -123,8 +132,10 @@ pub fn get_specimen_passport_json(
     state: State<AppState>,
     token: String,
     row_id: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
-    auth_service::validate_session(&db, &token)?;
-    store::get_passport_json(&db.conn, &row_id)
+    let user = auth_service::validate_session(&db, &token)?;
+    let provenance_visible =
+        crate::db::permissions::is_field_visible(&db.conn, user.role.as_str(), "specimen", "provenance");
+    store::get_passport_json(&db.conn, &row_id, provenance_visible)
 }
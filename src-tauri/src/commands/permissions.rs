@@ -1,21 +1,24 @@
 //! WP-55 — Field-level permissions command surface.
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::permissions;
+use crate::error::AppError;
 use crate::models::permissions::{FieldPermission, SetFieldPermissionRequest};
 use crate::AppState;
 use tauri::State;
 
-/// Admin-only: full matrix for the PermissionsEditor UI.
+/// Full matrix for the PermissionsEditor UI. Needs `roles.manage` (WP-86).
 #[tauri::command]
-pub fn list_field_permissions(state: State<AppState>, token: String) -> Result<Vec<FieldPermission>, String> {
+pub fn list_field_permissions(state: State<AppState>, token: String) -> Result<Vec<FieldPermission>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    permissions::validate_admin_role(user.role.as_str())?;
-    permissions::list_field_permissions(&db.conn).map_err(|e| e.to_string())
+    auth_service::require_capability(&db, &user, Capability::RolesManage)?;
+    permissions::list_field_permissions(&db.conn).map_err(AppError::from)
 }
 
-/// Admin-only: toggle visibility for one (role, entity_type, field_name) triple.
+/// Toggle visibility for one (role, entity_type, field_name) triple. Needs
+/// `roles.manage` (WP-86).
 /// Takes effect immediately — every read command queries `field_permissions`
 /// live, there is nothing to invalidate or restart.
 #[tauri::command]
@@ -23,10 +26,10 @@ pub fn set_field_permission(
     state: State<AppState>,
     token: String,
     request: SetFieldPermissionRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    permissions::validate_admin_role(user.role.as_str())?;
+    auth_service::require_capability(&db, &user, Capability::RolesManage)?;
 
     permissions::set_field_permission(
         &db.conn,
@@ -34,8 +37,7 @@ pub fn set_field_permission(
         &request.entity_type,
         &request.field_name,
         request.visible,
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     crate::db::queries::log_audit(
         &db.conn,
@@ -8,6 +8,8 @@ use base64::Engine as _;
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::error::AppError;
 use crate::plugins::{loader, manifest};
 use crate::AppState;
 
@@ -22,13 +24,12 @@ pub struct InstalledPlugin {
 }
 
 #[tauri::command]
-pub fn list_installed_plugins(state: State<AppState>, token: String) -> Result<Vec<InstalledPlugin>, String> {
+pub fn list_installed_plugins(state: State<AppState>, token: String) -> Result<Vec<InstalledPlugin>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let mut stmt = db
         .conn
-        .prepare("SELECT id, plugin_name, version, profile, vocabulary_seeded, installed_at FROM installed_plugins ORDER BY plugin_name ASC")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT id, plugin_name, version, profile, vocabulary_seeded, installed_at FROM installed_plugins ORDER BY plugin_name ASC")?;
     let rows = stmt
         .query_map([], |r| {
             Ok(InstalledPlugin {
@@ -39,8 +40,7 @@ pub fn list_installed_plugins(state: State<AppState>, token: String) -> Result<V
                 vocabulary_seeded: r.get::<_, i64>(4)? != 0,
                 installed_at: r.get(5)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
     Ok(rows)
@@ -50,23 +50,25 @@ pub fn list_installed_plugins(state: State<AppState>, token: String) -> Result<V
 /// Manager UI to preview a `.steloplugin` file's contents before the admin
 /// confirms installation.
 #[tauri::command]
-pub fn validate_plugin_manifest(state: State<AppState>, token: String, manifest_json: String) -> Result<manifest::PluginManifest, String> {
+pub fn validate_plugin_manifest(state: State<AppState>, token: String, manifest_json: String) -> Result<manifest::PluginManifest, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     manifest::validate_manifest(&manifest_json)
 }
 
-fn install_from_manifest(db: &crate::db::Database, user: &crate::models::user::User, manifest_json: &str) -> Result<InstalledPlugin, String> {
+fn install_from_manifest(db: &crate::db::Database, user: &crate::models::user::User, manifest_json: &str) -> Result<InstalledPlugin, AppError> {
     let manifest = manifest::validate_manifest(manifest_json)?;
-    loader::apply_vocabulary_seed(&db.conn, &manifest).map_err(|e| e.to_string())?;
-    let id = loader::register_installed_plugin(&db.conn, &manifest, manifest_json).map_err(|e| e.to_string())?;
+    loader::apply_vocabulary_seed(&db.conn, &manifest)?;
+    loader::apply_custom_fields(&db.conn, &manifest)?;
+    loader::apply_stage_transitions(&db.conn, &manifest)?;
+    let id = loader::register_installed_plugin(&db.conn, &manifest, manifest_json)?;
 
     crate::db::queries::log_audit(
         &db.conn, Some(&user.id), "create", "plugin", Some(&id),
         None, Some(&manifest.name), Some("Plugin installed"),
     ).ok();
 
-    db.conn
+    Ok(db.conn
         .query_row(
             "SELECT id, plugin_name, version, profile, vocabulary_seeded, installed_at FROM installed_plugins WHERE id = ?1",
             [&id],
@@ -80,17 +82,14 @@ fn install_from_manifest(db: &crate::db::Database, user: &crate::models::user::U
                     installed_at: r.get(5)?,
                 })
             },
-        )
-        .map_err(|e| e.to_string())
+        )?)
 }
 
 #[tauri::command]
-pub fn install_plugin(state: State<AppState>, token: String, manifest_json: String) -> Result<InstalledPlugin, String> {
+pub fn install_plugin(state: State<AppState>, token: String, manifest_json: String) -> Result<InstalledPlugin, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can install plugins".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::PluginsManage)?;
     install_from_manifest(&db, &user, &manifest_json)
 }
 
@@ -100,36 +99,32 @@ pub fn install_plugin(state: State<AppState>, token: String, manifest_json: Stri
 /// references are extracted alongside it for future use but are not
 /// executed — see `plugins::loader`'s module doc comment.
 #[tauri::command]
-pub fn install_plugin_from_zip(state: State<AppState>, token: String, zip_b64: String) -> Result<InstalledPlugin, String> {
+pub fn install_plugin_from_zip(state: State<AppState>, token: String, zip_b64: String) -> Result<InstalledPlugin, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can install plugins".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::PluginsManage)?;
 
-    let zip_bytes = B64.decode(&zip_b64).map_err(|e| format!("Invalid .steloplugin file: {}", e))?;
+    let zip_bytes = B64.decode(&zip_b64).map_err(|e| AppError::validation("zip_b64", format!("Invalid .steloplugin file: {}", e)))?;
     let reader = std::io::Cursor::new(zip_bytes);
-    let mut archive = zip::ZipArchive::new(reader).map_err(|e| format!("Not a valid .steloplugin archive: {}", e))?;
+    let mut archive = zip::ZipArchive::new(reader).map_err(|e| AppError::validation("zip_b64", format!("Not a valid .steloplugin archive: {}", e)))?;
     let mut manifest_file = archive
         .by_name("manifest.json")
-        .map_err(|_| "'.steloplugin' archive must contain a top-level manifest.json".to_string())?;
+        .map_err(|_| AppError::validation("zip_b64", "'.steloplugin' archive must contain a top-level manifest.json"))?;
     let mut manifest_json = String::new();
     std::io::Read::read_to_string(&mut manifest_file, &mut manifest_json)
-        .map_err(|e| format!("Failed to read manifest.json: {}", e))?;
+        .map_err(|e| AppError::validation("zip_b64", format!("Failed to read manifest.json: {}", e)))?;
     drop(manifest_file);
 
     install_from_manifest(&db, &user, &manifest_json)
 }
 
 #[tauri::command]
-pub fn uninstall_plugin(state: State<AppState>, token: String, plugin_id: String) -> Result<(), String> {
+pub fn uninstall_plugin(state: State<AppState>, token: String, plugin_id: String) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can uninstall plugins".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::PluginsManage)?;
     let plugin_name: Option<String> = db.conn.query_row("SELECT plugin_name FROM installed_plugins WHERE id = ?1", [&plugin_id], |r| r.get(0)).ok();
-    loader::uninstall_plugin(&db.conn, &plugin_id).map_err(|e| e.to_string())?;
+    loader::uninstall_plugin(&db.conn, &plugin_id)?;
 
     crate::db::queries::log_audit(
         &db.conn, Some(&user.id), "delete", "plugin", Some(&plugin_id),
@@ -1,4 +1,5 @@
 use crate::auth as auth_service;
+use crate::error::AppError;
 use crate::AppState;
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
@@ -20,7 +21,7 @@ pub fn store_qr_scan(
     token: String,
     raw_data: String,
     accession_number: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
 
@@ -42,7 +43,7 @@ pub fn store_qr_scan(
 pub fn list_qr_scans(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<QrScan>, String> {
+) -> Result<Vec<QrScan>, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
 
@@ -53,8 +54,7 @@ pub fn list_qr_scans(
              FROM qr_scans
              ORDER BY scanned_at DESC
              LIMIT 200",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let scans = stmt
         .query_map([], |row| {
@@ -65,8 +65,7 @@ pub fn list_qr_scans(
                 scanned_by: row.get(3)?,
                 scanned_at: row.get(4)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
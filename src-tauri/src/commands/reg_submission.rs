@@ -8,16 +8,18 @@ use serde::Serialize;
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::commands::compliance_export as ce;
+use crate::commands::signed_events::verify_ceremony;
 use crate::compliance_export::{bundle, signing};
+use crate::error::AppError;
 use crate::reg_submission::{self, SubmissionKind};
+use crate::signed_ledger::esignature::{self, SignatureCeremony};
 use crate::AppState;
 
-const MANAGE_ONLY: &str = "Only supervisors and admins can manage regulatory submissions.";
-
-fn submissions_dir() -> Result<std::path::PathBuf, String> {
+fn submissions_dir() -> Result<std::path::PathBuf, AppError> {
     let dir = ce::exports_dir()?.join("submissions");
-    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir)?;
     Ok(dir)
 }
 
@@ -27,7 +29,7 @@ fn build_documents(
     conn: &rusqlite::Connection,
     kind: SubmissionKind,
     scope: &serde_json::Value,
-) -> Result<Vec<(String, Vec<u8>)>, String> {
+) -> Result<Vec<(String, Vec<u8>)>, AppError> {
     match kind {
         SubmissionKind::Part11 => {
             let from = scope.get("from_date").and_then(|v| v.as_str()).unwrap_or("");
@@ -43,26 +45,26 @@ fn build_documents(
                 .unwrap_or_default();
             let scientist = scope.get("authorized_scientist").and_then(|v| v.as_str()).unwrap_or("");
             let prefill = bundle::build_usda_permit_prefill(conn, &ids, scientist)?;
-            Ok(vec![("usda_ppq526_prefill.json".to_string(), serde_json::to_vec_pretty(&prefill).map_err(|e| e.to_string())?)])
+            Ok(vec![("usda_ppq526_prefill.json".to_string(), serde_json::to_vec_pretty(&prefill)?)])
         }
         SubmissionKind::Cites => {
             let root = scope.get("root_specimen_id").and_then(|v| v.as_str()).unwrap_or("");
             let appendix = scope.get("cites_appendix").and_then(|v| v.as_str()).unwrap_or("");
             let dossier = bundle::build_cites_dossier(conn, root, appendix)?;
-            Ok(vec![("cites_dossier.json".to_string(), serde_json::to_vec_pretty(&dossier).map_err(|e| e.to_string())?)])
+            Ok(vec![("cites_dossier.json".to_string(), serde_json::to_vec_pretty(&dossier)?)])
         }
     }
 }
 
 /// Generate + sign the package for a `ready` submission and advance it to
 /// `generated`. Shared by the explicit command and the monitor.
-fn generate_package(conn: &rusqlite::Connection, submission_id: &str) -> Result<reg_submission::Submission, String> {
+fn generate_package(conn: &rusqlite::Connection, submission_id: &str) -> Result<reg_submission::Submission, AppError> {
     let sub = reg_submission::get_submission(conn, submission_id)?;
     if sub.status != "ready" {
-        return Err(format!("Submission must be 'ready' to generate (currently '{}')", sub.status));
+        return Err(AppError::invalid(format!("Submission must be 'ready' to generate (currently '{}')", sub.status)));
     }
     let kind = SubmissionKind::from_code(&sub.kind)?;
-    let scope: serde_json::Value = serde_json::from_str(&sub.scope).map_err(|e| e.to_string())?;
+    let scope: serde_json::Value = serde_json::from_str(&sub.scope)?;
 
     let documents = build_documents(conn, kind, &scope)?;
     let (public_key, private_key) = ce::load_or_create_signing_key(conn)?;
@@ -77,7 +79,7 @@ fn generate_package(conn: &rusqlite::Connection, submission_id: &str) -> Result<
         chrono::Local::now().format("%Y%m%d_%H%M%S")
     );
     let file_path = submissions_dir()?.join(&file_name);
-    std::fs::write(&file_path, &zip_bytes).map_err(|e| e.to_string())?;
+    std::fs::write(&file_path, &zip_bytes)?;
 
     reg_submission::attach_package(conn, submission_id, &file_path.to_string_lossy(), &package_signature)
 }
@@ -88,12 +90,10 @@ pub fn evaluate_submission_readiness(
     token: String,
     kind: String,
     scope: serde_json::Value,
-) -> Result<reg_submission::Readiness, String> {
+) -> Result<reg_submission::Readiness, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SubmissionManage)?;
     let k = SubmissionKind::from_code(&kind)?;
     reg_submission::evaluate_readiness(&db.conn, k, &scope)
 }
@@ -106,12 +106,10 @@ pub fn create_submission(
     title: String,
     scope: serde_json::Value,
     auto_generate: Option<bool>,
-) -> Result<reg_submission::Submission, String> {
+) -> Result<reg_submission::Submission, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SubmissionManage)?;
     let sub = reg_submission::create_submission(&db.conn, &kind, &title, &scope, auto_generate.unwrap_or(false), &user.id)?;
     crate::db::queries::log_audit(
         &db.conn, Some(&user.id), "create", "regulatory_submission", Some(&sub.id),
@@ -126,12 +124,10 @@ pub fn reevaluate_submission(
     state: State<AppState>,
     token: String,
     submission_id: String,
-) -> Result<reg_submission::Submission, String> {
+) -> Result<reg_submission::Submission, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SubmissionManage)?;
     reg_submission::reevaluate_submission(&db.conn, &submission_id)
 }
 
@@ -140,24 +136,31 @@ pub fn generate_submission_package(
     state: State<AppState>,
     token: String,
     submission_id: String,
-) -> Result<reg_submission::Submission, String> {
+    signature: SignatureCeremony,
+) -> Result<reg_submission::Submission, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SubmissionManage)?;
     // Re-check readiness right before generating so a stale 'ready' can't slip a
     // no-longer-compliant package through.
     let refreshed = reg_submission::reevaluate_submission(&db.conn, &submission_id)?;
     if refreshed.status != "ready" {
-        return Err("Submission is not currently ready — resolve the blocking checks first.".to_string());
+        return Err(AppError::invalid("Submission is not currently ready — resolve the blocking checks first."));
     }
-    let sub = generate_package(&db.conn, &submission_id)?;
+    // WP-80: the person generating the package signs it under Part 11.
+    let verified = verify_ceremony(&db, &user, &signature, esignature::SUBMISSION_GENERATION)?;
+    let tx = db
+        .conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let sub = generate_package(&tx, &submission_id)?;
     crate::db::queries::log_audit(
-        &db.conn, Some(&user.id), "generate", "regulatory_submission", Some(&sub.id),
+        &tx, Some(&user.id), "generate", "regulatory_submission", Some(&sub.id),
         None, sub.package_path.as_deref(), Some("Generated & signed regulatory submission package"),
     )
     .ok();
+    esignature::record_signature(&tx, &user, verified, "regulatory_submission", &sub.id)?;
+    tx.commit().map_err(|e| format!("Failed to commit submission: {}", e))?;
     Ok(sub)
 }
 
@@ -167,12 +170,10 @@ pub fn mark_submission_submitted(
     token: String,
     submission_id: String,
     reference: String,
-) -> Result<reg_submission::Submission, String> {
+) -> Result<reg_submission::Submission, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SubmissionManage)?;
     let sub = reg_submission::mark_submitted(&db.conn, &submission_id, &reference)?;
     crate::db::queries::log_audit(
         &db.conn, Some(&user.id), "submit", "regulatory_submission", Some(&sub.id),
@@ -183,12 +184,10 @@ pub fn mark_submission_submitted(
 }
 
 #[tauri::command]
-pub fn list_submissions(state: State<AppState>, token: String) -> Result<Vec<reg_submission::Submission>, String> {
+pub fn list_submissions(state: State<AppState>, token: String) -> Result<Vec<reg_submission::Submission>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SubmissionManage)?;
     reg_submission::list_submissions(&db.conn)
 }
 
@@ -202,8 +201,10 @@ pub struct MonitorResult {
 
 /// Re-evaluate every non-terminal submission against current compliance state,
 /// and auto-generate the package for any that is now `ready` and flagged
-/// `auto_generate`. Callable on demand and from the background scheduler.
-pub fn monitor(conn: &rusqlite::Connection) -> Result<MonitorResult, String> {
+/// `auto_generate`. Callable on demand (`user_id` is the caller) and from the
+/// background scheduler (`None`). Auto-generated packages are audited like a
+/// manual `generate_submission_package`.
+pub fn monitor(conn: &rusqlite::Connection, user_id: Option<&str>) -> Result<MonitorResult, AppError> {
     let mut result = MonitorResult { evaluated: 0, became_ready: 0, auto_generated: 0, still_blocked: 0 };
     let submissions = reg_submission::list_submissions(conn)?;
     for sub in submissions {
@@ -214,8 +215,15 @@ pub fn monitor(conn: &rusqlite::Connection) -> Result<MonitorResult, String> {
         let refreshed = reg_submission::reevaluate_submission(conn, &sub.id)?;
         if refreshed.status == "ready" {
             result.became_ready += 1;
-            if refreshed.auto_generate && generate_package(conn, &refreshed.id).is_ok() {
-                result.auto_generated += 1;
+            if refreshed.auto_generate {
+                if let Ok(generated) = generate_package(conn, &refreshed.id) {
+                    crate::db::queries::log_audit(
+                        conn, user_id, "generate", "regulatory_submission", Some(&generated.id),
+                        None, generated.package_path.as_deref(), Some("Auto-generated regulatory submission package"),
+                    )
+                    .ok();
+                    result.auto_generated += 1;
+                }
             }
         } else if refreshed.status == "blocked" {
             result.still_blocked += 1;
@@ -225,11 +233,9 @@ pub fn monitor(conn: &rusqlite::Connection) -> Result<MonitorResult, String> {
 }
 
 #[tauri::command]
-pub fn run_submission_monitor(state: State<AppState>, token: String) -> Result<MonitorResult, String> {
+pub fn run_submission_monitor(state: State<AppState>, token: String) -> Result<MonitorResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err(MANAGE_ONLY.to_string());
-    }
-    monitor(&db.conn)
+    auth_service::require_capability(&db, &user, Capability::SubmissionManage)?;
+    monitor(&db.conn, Some(&user.id))
 }
@@ -7,18 +7,18 @@
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::error::AppError;
 use crate::registry::store::{self, RecordDecision};
 use crate::registry::{RegistryVerification, TaxonomyRegistry};
 use crate::AppState;
 
 /// Export a signed taxonomy registry for this lab and record it.
 #[tauri::command]
-pub fn export_taxonomy_registry(state: State<AppState>, token: String) -> Result<TaxonomyRegistry, String> {
+pub fn export_taxonomy_registry(state: State<AppState>, token: String) -> Result<TaxonomyRegistry, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions — a write-capable role is required to export a registry.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::RegistryExchange)?;
     let registry = store::export_registry(&db.conn, Some(&user.id))?;
     crate::db::queries::log_audit(
         &db.conn,
@@ -43,7 +43,7 @@ pub fn verify_taxonomy_registry(
     state: State<AppState>,
     token: String,
     registry_json: String,
-) -> Result<RegistryVerification, String> {
+) -> Result<RegistryVerification, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::verify_registry_json(&registry_json)
@@ -56,7 +56,7 @@ pub fn preview_taxonomy_registry_import(
     state: State<AppState>,
     token: String,
     registry_json: String,
-) -> Result<store::RegistryImportPreview, String> {
+) -> Result<store::RegistryImportPreview, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::preview_import(&db.conn, &registry_json)
@@ -70,12 +70,10 @@ pub fn import_taxonomy_registry(
     token: String,
     registry_json: String,
     decisions: Option<Vec<RecordDecision>>,
-) -> Result<store::RegistryImportResult, String> {
+) -> Result<store::RegistryImportResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions — a write-capable role is required to import a registry.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::RegistryExchange)?;
     store::import_registry(&db.conn, &registry_json, &decisions.unwrap_or_default(), Some(&user.id))
 }
 
@@ -86,7 +84,7 @@ pub fn list_taxonomy_registries(
     state: State<AppState>,
     token: String,
     direction: Option<String>,
-) -> Result<Vec<store::RegistryRecordRow>, String> {
+) -> Result<Vec<store::RegistryRecordRow>, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::list_registries(&db.conn, direction.as_deref())
@@ -98,7 +96,7 @@ pub fn get_taxonomy_registry_json(
     state: State<AppState>,
     token: String,
     row_id: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::get_registry_json(&db.conn, &row_id)
@@ -110,7 +108,7 @@ pub fn list_registry_dispositions(
     state: State<AppState>,
     token: String,
     registry_row_id: String,
-) -> Result<Vec<store::AppliedRecord>, String> {
+) -> Result<Vec<store::AppliedRecord>, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     store::list_dispositions(&db.conn, &registry_row_id)
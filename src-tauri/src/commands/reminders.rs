@@ -1,12 +1,14 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::reminder::*;
 use crate::AppState;
 use rusqlite::params;
 use tauri::State;
 
 #[tauri::command]
-pub fn list_reminders(state: State<AppState>, token: String) -> Result<Vec<Reminder>, String> {
+pub fn list_reminders(state: State<AppState>, token: String) -> Result<Vec<Reminder>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -16,7 +18,7 @@ pub fn list_reminders(state: State<AppState>, token: String) -> Result<Vec<Remin
          LEFT JOIN specimens s ON r.specimen_id = s.id
          LEFT JOIN users u ON r.assigned_to = u.id
          ORDER BY r.due_date ASC"
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let reminders = stmt.query_map([], |row| {
         Ok(Reminder {
@@ -39,7 +41,7 @@ pub fn list_reminders(state: State<AppState>, token: String) -> Result<Vec<Remin
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect();
 
@@ -47,7 +49,7 @@ pub fn list_reminders(state: State<AppState>, token: String) -> Result<Vec<Remin
 }
 
 #[tauri::command]
-pub fn get_active_reminders(state: State<AppState>, token: String) -> Result<Vec<Reminder>, String> {
+pub fn get_active_reminders(state: State<AppState>, token: String) -> Result<Vec<Reminder>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -58,7 +60,7 @@ pub fn get_active_reminders(state: State<AppState>, token: String) -> Result<Vec
          LEFT JOIN users u ON r.assigned_to = u.id
          WHERE r.status IN ('active', 'snoozed') AND r.due_date <= date('now', '+7 days')
          ORDER BY r.urgency DESC, r.due_date ASC"
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let reminders = stmt.query_map([], |row| {
         Ok(Reminder {
@@ -81,7 +83,7 @@ pub fn get_active_reminders(state: State<AppState>, token: String) -> Result<Vec
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect();
 
@@ -93,12 +95,10 @@ pub fn create_reminder(
     state: State<AppState>,
     token: String,
     request: CreateReminderRequest,
-) -> Result<Reminder, String> {
+) -> Result<Reminder, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ReminderEdit)?;
 
     let id = uuid::Uuid::new_v4().to_string();
 
@@ -149,7 +149,7 @@ pub fn create_reminder(
                 updated_at: row.get("updated_at")?,
             })
         },
-    ).map_err(|e| format!("Failed to fetch reminder: {}", e))
+    ).map_err(|e| AppError::internal(format!("Failed to fetch reminder: {}", e)))
 }
 
 #[tauri::command]
@@ -157,12 +157,10 @@ pub fn update_reminder(
     state: State<AppState>,
     token: String,
     request: UpdateReminderRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::ReminderEdit)?;
 
     let mut updates = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -193,7 +191,7 @@ pub fn update_reminder(
     }
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
 
     updates.push("updated_at = datetime('now')".to_string());
@@ -217,7 +215,7 @@ pub fn update_reminder(
 }
 
 #[tauri::command]
-pub fn dismiss_reminder(state: State<AppState>, token: String, id: String, snooze: bool, snooze_days: Option<u32>) -> Result<(), String> {
+pub fn dismiss_reminder(state: State<AppState>, token: String, id: String, snooze: bool, snooze_days: Option<u32>) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
 
@@ -231,7 +229,7 @@ pub fn dismiss_reminder(state: State<AppState>, token: String, id: String, snooz
                 days
             ),
             params![id],
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         // Check if snooze count >= 2 for escalation
         let snooze_count: i32 = db.conn.query_row(
@@ -250,12 +248,12 @@ pub fn dismiss_reminder(state: State<AppState>, token: String, id: String, snooz
         db.conn.execute(
             "UPDATE reminders SET status = 'dismissed', updated_at = datetime('now') WHERE id = ?1",
             params![id],
-        ).map_err(|e| e.to_string())?;
+        )?;
     }
 
-    let action = if snooze { "snooze" } else { "dismiss" };
     queries::log_audit(
-        &db.conn, Some(&user.id), action, "reminder", Some(&id), None, None, None,
+        &db.conn, Some(&user.id), if snooze { "snooze" } else { "dismiss" }, "reminder", Some(&id),
+        None, None, None,
     ).ok();
 
     Ok(())
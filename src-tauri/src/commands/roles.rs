@@ -0,0 +1,90 @@
+// WP-86: custom roles and the capability matrix. Reading the catalogue and
+// the role list needs `users.view`; changing roles needs `roles.manage`, and
+// nobody can put a capability in a role unless they hold it themselves.
+use crate::auth as auth_service;
+use crate::auth::roles::{self, Capability, CapabilityInfo, RoleSummary, SaveRoleRequest};
+use crate::error::AppError;
+use crate::AppState;
+use tauri::State;
+
+/// The capability keys the caller's role holds, so the frontend can hide what
+/// the backend would refuse. Every user may ask about themselves. With an API
+/// token (WP-92), only those in the token's scope.
+#[tauri::command]
+pub fn get_my_capabilities(state: State<AppState>, token: String) -> Result<Vec<String>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    Ok(roles::capabilities_of(&db.conn, &user.role)?
+        .into_iter()
+        .filter(|c| user.token_scope.as_ref().is_none_or(|scope| scope.allows(*c)))
+        .map(|c| c.key().to_string())
+        .collect())
+}
+
+#[tauri::command]
+pub fn list_capabilities(state: State<AppState>, token: String) -> Result<Vec<CapabilityInfo>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::UsersView)?;
+    Ok(Capability::ALL.iter().map(|c| c.info()).collect())
+}
+
+#[tauri::command]
+pub fn list_roles(state: State<AppState>, token: String) -> Result<Vec<RoleSummary>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::UsersView)?;
+    roles::list_roles(&db.conn)
+}
+
+#[tauri::command]
+pub fn create_role(state: State<AppState>, token: String, request: SaveRoleRequest) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::RolesManage)?;
+    roles::create_role(&db.conn, &user.role, &request)?;
+    crate::db::queries::log_audit(
+        &db.conn,
+        Some(&user.id),
+        "create",
+        "role",
+        Some(request.name.trim()),
+        None,
+        Some(&request.capabilities.join(", ")),
+        Some(&format!("Role '{}' created", request.label.trim())),
+    )
+    .ok();
+    Ok(())
+}
+
+/// Replace a role's label, description and capability set. Takes effect on
+/// its holders' next command.
+#[tauri::command]
+pub fn update_role(state: State<AppState>, token: String, request: SaveRoleRequest) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::RolesManage)?;
+    let before = roles::update_role(&db.conn, &user.role, &request)?;
+    crate::db::queries::log_audit(
+        &db.conn,
+        Some(&user.id),
+        "update",
+        "role",
+        Some(request.name.trim()),
+        Some(&before.join(", ")),
+        Some(&request.capabilities.join(", ")),
+        None,
+    )
+    .ok();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_role(state: State<AppState>, token: String, name: String) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::RolesManage)?;
+    roles::delete_role(&db.conn, &user.role, &name)?;
+    crate::db::queries::log_audit(&db.conn, Some(&user.id), "delete", "role", Some(&name), None, None, None).ok();
+    Ok(())
+}
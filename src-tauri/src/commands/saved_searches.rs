@@ -0,0 +1,75 @@
+// WP-97: saved specimen filters for the active lab profile. Any signed-in
+// user can keep their own and run the ones shared with the lab; only the
+// owner changes or deletes a search.
+use crate::auth as auth_service;
+use crate::db::saved_searches::{self, SaveSavedSearchRequest, SavedSearch};
+use crate::db::{queries, specimen_query};
+use crate::error::AppError;
+use crate::AppState;
+use serde::Serialize;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_saved_searches(state: State<AppState>, token: String) -> Result<Vec<SavedSearch>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    saved_searches::list(&db.conn, &user.id, &profile)
+}
+
+/// Create a search, or change one of the caller's own. The filter is
+/// compiled first, so a search that would not run is never saved.
+#[tauri::command]
+pub fn save_saved_search(
+    state: State<AppState>,
+    token: String,
+    request: SaveSavedSearchRequest,
+) -> Result<SavedSearch, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let search = saved_searches::save(
+        &db.conn, &user.id, user.role.as_str(), &profile, &request, chrono::Local::now().date_naive(),
+    )?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "save", "saved_search", Some(&search.id),
+        None, Some(&search.filter),
+        Some(&format!(
+            "Saved search '{}' {}{}",
+            search.name,
+            if request.id.is_some() { "updated" } else { "created" },
+            if search.shared { ", shared with the lab" } else { "" },
+        )),
+    ).ok();
+    Ok(search)
+}
+
+#[tauri::command]
+pub fn delete_saved_search(state: State<AppState>, token: String, id: String) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let search = saved_searches::delete(&db.conn, &user.id, &profile, &id)?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "delete", "saved_search", Some(&id),
+        Some(&search.filter), None,
+        Some(&format!("Saved search '{}' deleted", search.name)),
+    ).ok();
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct FilterFields {
+    pub specimen: Vec<&'static str>,
+    pub subculture: Vec<&'static str>,
+}
+
+/// The field names the filter language accepts, for the editor's hints.
+/// Custom fields (`custom.<key>`) come from `list_custom_fields`.
+#[tauri::command]
+pub fn specimen_filter_fields(state: State<AppState>, token: String) -> Result<FilterFields, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    let (specimen, subculture) = specimen_query::field_names();
+    Ok(FilterFields { specimen, subculture })
+}
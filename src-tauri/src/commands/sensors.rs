@@ -1,7 +1,9 @@
 //! WP-54 — Environmental sensor integration command surface.
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::sensors as sensor_queries;
+use crate::error::AppError;
 use crate::models::sensors::{CreateEnvironmentalReadingRequest, EnvironmentalAlert, EnvironmentalReading};
 use crate::AppState;
 use tauri::State;
@@ -11,12 +13,10 @@ pub fn create_environmental_reading(
     state: State<AppState>,
     token: String,
     request: CreateEnvironmentalReadingRequest,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SensorRecord)?;
 
     let id = sensor_queries::create_environmental_reading(&db.conn, &request, Some(&user.id))?;
 
@@ -59,12 +59,10 @@ pub fn ingest_sensor_payload(
     subculture_id: Option<String>,
     source: String,
     raw_payload: String,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SensorRecord)?;
 
     let parsed = sensor_queries::parse_sensor_payload(&raw_payload)?;
     let mut ids = Vec::with_capacity(parsed.len());
@@ -90,16 +88,16 @@ pub fn list_environmental_readings(
     token: String,
     specimen_id: String,
     limit: Option<i64>,
-) -> Result<Vec<EnvironmentalReading>, String> {
+) -> Result<Vec<EnvironmentalReading>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     sensor_queries::list_environmental_readings(&db.conn, Some(&specimen_id), limit.unwrap_or(100))
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn get_environmental_alerts(state: State<AppState>, token: String) -> Result<Vec<EnvironmentalAlert>, String> {
+pub fn get_environmental_alerts(state: State<AppState>, token: String) -> Result<Vec<EnvironmentalAlert>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    sensor_queries::get_environmental_alerts(&db.conn).map_err(|e| e.to_string())
+    sensor_queries::get_environmental_alerts(&db.conn).map_err(AppError::from)
 }
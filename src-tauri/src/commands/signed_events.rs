@@ -7,13 +7,152 @@
 use tauri::State;
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::user::User;
 use crate::signed_ledger;
+use crate::signed_ledger::esignature::{self, SignatureCeremony, VerifiedCeremony};
+use crate::signed_ledger::witness::{self, WitnessPolicy, WitnessStatus};
 use crate::AppState;
 
+/// WP-80: step one of a Part 11 signature ceremony, shared by every command that
+/// takes one. A wrong password counts against the same lockout
+/// (`auth::lockout`) as a failed login, so the dialog cannot be used to guess a password that the
+/// login screen would have locked out. Each failure is audited.
+pub(crate) fn verify_ceremony(
+    db: &Database,
+    user: &User,
+    ceremony: &SignatureCeremony,
+    action: &str,
+) -> Result<VerifiedCeremony, AppError> {
+    esignature::check_meaning(action, ceremony.meaning)?;
+    auth_service::lockout::check(&db.conn, &user.username)?;
+    esignature::reauthenticate(db, user, ceremony, action)
+        .inspect(|_| auth_service::lockout::clear(&db.conn, &user.username))
+        .inspect_err(|e| {
+            auth_service::lockout::record_failure(&db.conn, &user.username).ok();
+            crate::db::queries::log_audit(
+                &db.conn, Some(&user.id), "reauth_failed", "electronic_signature", None, None, None,
+                Some(&format!("{}: {}", action, e)),
+            )
+            .ok();
+        })
+}
+
+/// The signable actions and the meanings each allows, for the signature dialog.
+#[tauri::command]
+pub fn list_signable_actions(state: State<AppState>, token: String) -> Result<Vec<esignature::SignableAction>, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    Ok(esignature::SIGNABLE_ACTIONS.to_vec())
+}
+
+/// Apply an ad-hoc review or approval signature to any record (WP-80). The
+/// record's own critical actions take their signature inline. This is for
+/// signing a record after the fact, e.g. a supervisor reviewing a passage.
+#[tauri::command]
+pub fn sign_record(
+    state: State<AppState>,
+    token: String,
+    entity_type: String,
+    entity_id: String,
+    signature: SignatureCeremony,
+) -> Result<esignature::ElectronicSignature, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::SignatureSign)?;
+    if entity_type.trim().is_empty() || entity_id.trim().is_empty() {
+        return Err(AppError::invalid("A record type and id are required to sign a record."));
+    }
+    let verified = verify_ceremony(&db, &user, &signature, esignature::RECORD_REVIEW)?;
+    esignature::record_signature(&db.conn, &user, verified, entity_type.trim(), entity_id.trim())
+}
+
+/// Electronic signatures on one record, oldest first. Read-only.
+#[tauri::command]
+pub fn list_electronic_signatures(
+    state: State<AppState>,
+    token: String,
+    entity_type: String,
+    entity_id: String,
+) -> Result<Vec<esignature::ElectronicSignature>, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    esignature::list_signatures_for(&db.conn, &entity_type, &entity_id)
+}
+
+/// WP-81: ledger events still waiting on a witness countersignature. Read-only.
+#[tauri::command]
+pub fn list_pending_witness(state: State<AppState>, token: String) -> Result<Vec<WitnessStatus>, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    witness::pending_witness(&db.conn)
+}
+
+/// WP-81: countersign a witnessed ledger event. The witness rules (not the
+/// original signer, an allowed role, not already witnessed) are checked before
+/// the password is asked for, so a refused witness never burns a login attempt.
+#[tauri::command]
+pub fn countersign_event(
+    state: State<AppState>,
+    token: String,
+    event_id: String,
+    signature: SignatureCeremony,
+) -> Result<esignature::ElectronicSignature, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::LedgerWitness)?;
+    witness::check_can_countersign(&db.conn, &user, &event_id)?;
+    let verified = verify_ceremony(&db, &user, &signature, esignature::WITNESS_COUNTERSIGNATURE)?;
+    esignature::record_signature(&db.conn, &user, verified, "signed_event", &event_id)
+}
+
+/// WP-81: the witness policies, enabled or not. Read-only.
+#[tauri::command]
+pub fn list_witness_policies(state: State<AppState>, token: String) -> Result<Vec<WitnessPolicy>, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    witness::list_policies(&db.conn)
+}
+
+/// WP-81: create or update a witness policy (admin only). An empty id creates a
+/// new policy. Edits apply to future events only; each requirement already in
+/// the ledger carries its own snapshot of the policy.
+#[tauri::command]
+pub fn save_witness_policy(
+    state: State<AppState>,
+    token: String,
+    mut policy: WitnessPolicy,
+) -> Result<WitnessPolicy, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::LedgerWitnessPolicy)?;
+    if policy.id.trim().is_empty() {
+        policy.id = uuid::Uuid::new_v4().to_string();
+    }
+    let old = witness::list_policies(&db.conn)?.into_iter().find(|p| p.id == policy.id);
+    let created = witness::upsert_policy(&db.conn, &policy)?;
+    let old_json = old.and_then(|p| serde_json::to_string(&p).ok());
+    let new_json = serde_json::to_string(&policy).ok();
+    crate::db::queries::log_audit(
+        &db.conn,
+        Some(&user.id),
+        if created { "create" } else { "update" },
+        "witness_policy",
+        Some(&policy.id),
+        old_json.as_deref(),
+        new_json.as_deref(),
+        Some(&format!("Witness policy '{}' on {}", policy.label.trim(), policy.event_type)),
+    )
+    .ok();
+    Ok(policy)
+}
+
 /// The caller's Ed25519 public key (generating one on first use). Lets a user
 /// publish the key others verify their signed events against.
 #[tauri::command]
-pub fn get_user_signing_public_key(state: State<AppState>, token: String) -> Result<String, String> {
+pub fn get_user_signing_public_key(state: State<AppState>, token: String) -> Result<String, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
     let (public_key, _) = signed_ledger::load_or_create_user_signing_key(&db.conn, &user.id)?;
@@ -30,12 +169,10 @@ pub fn record_signed_event(
     entity_type: String,
     entity_id: Option<String>,
     payload: String,
-) -> Result<signed_ledger::SignedEvent, String> {
+) -> Result<signed_ledger::SignedEvent, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions — a write-capable role is required to sign an event.".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SignatureSign)?;
     let event = signed_ledger::append_signed_event(
         &db.conn,
         &user.id,
@@ -65,7 +202,7 @@ pub fn list_signed_events(
     token: String,
     entity_id: Option<String>,
     limit: Option<i64>,
-) -> Result<Vec<signed_ledger::SignedEvent>, String> {
+) -> Result<Vec<signed_ledger::SignedEvent>, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     signed_ledger::list_signed_events(&db.conn, entity_id.as_deref(), limit.unwrap_or(100))
@@ -77,7 +214,7 @@ pub fn list_signed_events(
 pub fn verify_signed_event_ledger(
     state: State<AppState>,
     token: String,
-) -> Result<signed_ledger::LedgerVerification, String> {
+) -> Result<signed_ledger::LedgerVerification, AppError> {
     let db = state.db();
     auth_service::validate_session(&db, &token)?;
     signed_ledger::verify_ledger(&db.conn)
@@ -1,5 +1,7 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::species::*;
 use crate::AppState;
 use rusqlite::params;
@@ -14,13 +16,13 @@ pub struct Project {
 }
 
 #[tauri::command]
-pub fn list_species(state: State<AppState>, token: String) -> Result<Vec<Species>, String> {
+pub fn list_species(state: State<AppState>, token: String) -> Result<Vec<Species>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
     let mut stmt = db.conn.prepare(
         "SELECT * FROM species ORDER BY genus, species_name"
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let species = stmt.query_map([], |row| {
         Ok(Species {
@@ -36,7 +38,7 @@ pub fn list_species(state: State<AppState>, token: String) -> Result<Vec<Species
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect();
 
@@ -48,12 +50,10 @@ pub fn create_species(
     state: State<AppState>,
     token: String,
     request: CreateSpeciesRequest,
-) -> Result<Species, String> {
+) -> Result<Species, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can manage species".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SpeciesManage)?;
 
     let id = uuid::Uuid::new_v4().to_string();
 
@@ -92,7 +92,7 @@ pub fn create_species(
                 updated_at: row.get("updated_at")?,
             })
         },
-    ).map_err(|e| format!("Failed to fetch species: {}", e))
+    ).map_err(|e| AppError::internal(format!("Failed to fetch species: {}", e)))
 }
 
 #[tauri::command]
@@ -100,12 +100,10 @@ pub fn update_species(
     state: State<AppState>,
     token: String,
     request: UpdateSpeciesRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can manage species".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SpeciesManage)?;
 
     let mut updates = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -136,7 +134,7 @@ pub fn update_species(
     }
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
 
     updates.push("updated_at = datetime('now')".to_string());
@@ -160,13 +158,13 @@ pub fn update_species(
 }
 
 #[tauri::command]
-pub fn list_projects(state: State<AppState>, token: String) -> Result<Vec<Project>, String> {
+pub fn list_projects(state: State<AppState>, token: String) -> Result<Vec<Project>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
     let mut stmt = db.conn.prepare(
         "SELECT id, name, status FROM projects WHERE status != 'archived' ORDER BY name"
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let projects = stmt.query_map([], |row| {
         Ok(Project {
@@ -174,7 +172,7 @@ pub fn list_projects(state: State<AppState>, token: String) -> Result<Vec<Projec
             name: row.get("name")?,
             status: row.get("status")?,
         })
-    }).map_err(|e| e.to_string())?
+    })?
       .filter_map(|r| r.ok())
       .collect();
 
@@ -1,12 +1,19 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::custom_fields::{self, CustomFieldEntity};
+use crate::db::permissions::{mask_for_role, reject_if_restricted_marker, Masked};
 use crate::db::queries;
+use crate::db::specimen_merge::{self, MergeSpecimensRequest, SpecimenMerge};
+use crate::db::specimen_restore::{self, RestorableSpecimen, RestoredSpecimen};
+use crate::error::AppError;
 use crate::models::specimen::{
-    CreateSpecimenRequest, FamilyMember, PaginatedResponse, Specimen, SpecimenSearchParams,
-    SpecimenStats, SplitChildResult, SplitResult, SplitSpecimenRequest,
+    CreateSpecimenRequest, FamilyMember, InitiateBatchRequest, InitiationBatch, PaginatedResponse,
+    Specimen, SpecimenSearchParams, SpecimenStats, SplitChildResult, SplitResult, SplitSpecimenRequest,
     UpdateSpecimenRequest,
 };
+use crate::db::specimens::{self, row_to_specimen};
 use crate::AppState;
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 use tauri::State;
 
 // Tuple returned by the parent-specimen query in split_specimen.
@@ -15,76 +22,15 @@ use tauri::State;
 //          contamination_flag, contamination_notes, origin_type)
 type ParentInfo = (String, String, String, Option<String>, Option<String>, Option<String>, i32, i32, i32, Option<String>, String, i32, Option<String>, Option<String>);
 
-/// Maps one row of the standard specimen SELECT into a `Specimen`.
-///
-/// All three read paths (`list_specimens`, `get_specimen`, `search_specimens`)
-/// select `s.*` plus the same four derived columns, so one mapper serves them
-/// all. It was previously written out three times, ~50 fields each: adding
-/// `lab_profile` meant editing three identical blocks, and missing one would
-/// have compiled cleanly while returning a wrong default on that path. A new
-/// column is now added in exactly one place.
-fn row_to_specimen(row: &rusqlite::Row) -> rusqlite::Result<Specimen> {
-    Ok(Specimen {
-        id: row.get("id")?,
-        accession_number: row.get("accession_number")?,
-        species_id: row.get("species_id")?,
-        species_code: row.get("species_code")?,
-        species_name: row.get("species_name")?,
-        project_id: row.get("project_id")?,
-        project_name: row.get("project_name")?,
-        stage: row.get("stage")?,
-        custom_stage: row.get("custom_stage")?,
-        provenance: row.get("provenance")?,
-        source_plant: row.get("source_plant")?,
-        initiation_date: row.get("initiation_date")?,
-        location: row.get("location")?,
-        location_details: row.get("location_details")?,
-        propagation_method: row.get("propagation_method")?,
-        acclimatization_status: row.get("acclimatization_status")?,
-        health_status: row.get("health_status")?,
-        disease_status: row.get("disease_status")?,
-        quarantine_flag: row.get::<_, i32>("quarantine_flag")? != 0,
-        quarantine_release_date: row.get("quarantine_release_date")?,
-        permit_number: row.get("permit_number")?,
-        permit_expiry: row.get("permit_expiry")?,
-        ip_flag: row.get::<_, i32>("ip_flag")? != 0,
-        ip_notes: row.get("ip_notes")?,
-        environmental_notes: row.get("environmental_notes")?,
-        subculture_count: row.get("subculture_count")?,
-        generation: row.get("generation")?,
-        lineage_passage_offset: row.get("lineage_passage_offset")?,
-        root_specimen_id: row.get("root_specimen_id")?,
-        parent_specimen_id: row.get("parent_specimen_id")?,
-        qr_code_data: row.get("qr_code_data")?,
-        notes: row.get("notes")?,
-        employee_id: row.get("employee_id")?,
-        is_archived: row.get::<_, i32>("is_archived")? != 0,
-        archived_at: row.get("archived_at")?,
-        contamination_flag: row.get::<_, i32>("contamination_flag")? != 0,
-        contamination_notes: row.get("contamination_notes")?,
-        created_by: row.get("created_by")?,
-        created_at: row.get("created_at")?,
-        updated_at: row.get("updated_at")?,
-        has_contamination: row.get::<_, i32>("has_contamination")? != 0,
-        strain_id: row.get("strain_id")?,
-        strain_chain_seq: row.get("strain_chain_seq")?,
-        cumulative_pdl: row.get("cumulative_pdl").unwrap_or(None),
-        biosafety_level: row.get("biosafety_level").unwrap_or(None),
-        origin_type: row.get("origin_type").unwrap_or(None),
-        is_best_performer: row.get::<_, i32>("is_best_performer").unwrap_or(0) != 0,
-        lab_profile: row.get("lab_profile")?,
-    })
-}
-
 #[tauri::command]
 pub fn list_specimens(
     state: State<AppState>,
     token: String,
     page: Option<u32>,
     per_page: Option<u32>,
-) -> Result<PaginatedResponse<Specimen>, String> {
+) -> Result<Masked<PaginatedResponse<Specimen>>, AppError> {
     let db = state.db();
-    let _user = auth_service::validate_session(&db, &token)?;
+    let user = auth_service::validate_session(&db, &token)?;
 
     let pg = queries::PaginationParams {
         page: page.unwrap_or(1),
@@ -99,7 +45,7 @@ pub fn list_specimens(
         "SELECT COUNT(*) FROM specimens WHERE is_archived = 0 AND lab_profile = ?1",
         params![profile],
         |r| r.get(0),
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let mut stmt = db.conn.prepare(
         "SELECT s.*, sp.species_code, sp.genus || ' ' || sp.species_name as species_name,
@@ -113,38 +59,40 @@ pub fn list_specimens(
          WHERE s.is_archived = 0 AND s.lab_profile = ?1
          ORDER BY s.created_at DESC
          LIMIT ?2 OFFSET ?3"
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     // Collected strictly: a row that fails to map is a schema drift or a type
     // bug, and silently dropping it makes specimens disappear from the list with
     // no error anywhere. In a lab whose records are subject to audit, showing 19
     // of 20 cultures and calling it 20 is worse than showing an error.
     let specimens = stmt
-        .query_map(params![profile, pg.limit(), pg.offset()], row_to_specimen)
-        .map_err(|e| e.to_string())?
+        .query_map(params![profile, pg.limit(), pg.offset()], row_to_specimen)?
         .collect::<rusqlite::Result<Vec<_>>>()
         .map_err(|e| format!("Failed to read specimen rows: {}", e))?;
 
     let total_pages = ((total as f64) / (pg.per_page as f64)).ceil() as u32;
 
-    Ok(PaginatedResponse {
+    let page = PaginatedResponse {
         items: specimens,
         total,
         page: pg.page,
         per_page: pg.per_page,
         total_pages,
-    })
+    };
+    mask_for_role(&db.conn, user.role.as_str(), page)
 }
 
 #[tauri::command]
-pub fn get_specimen(state: State<AppState>, token: String, id: String) -> Result<Specimen, String> {
+pub fn get_specimen(state: State<AppState>, token: String, id: String) -> Result<Masked<Specimen>, AppError> {
     let db = state.db();
-    let _user = auth_service::validate_session(&db, &token)?;
+    let user = auth_service::validate_session(&db, &token)?;
+    // WP-99: the id of a merged duplicate opens the record it was merged into.
+    let id = specimen_merge::resolve(&db.conn, &id)?;
     // A specimen ID that leaked across a profile switch (QR code, bookmark,
     // stale UI state) must not resolve under the wrong lab.
     crate::db::vocabulary::require_active_lab_profile(&db.conn, &id)?;
 
-    db.conn.query_row(
+    let specimen = db.conn.query_row(
         "SELECT s.*, sp.species_code, sp.genus || ' ' || sp.species_name as species_name,
                 p.name as project_name,
                 COALESCE(cf.has_contamination, 0) AS has_contamination
@@ -156,7 +104,8 @@ pub fn get_specimen(state: State<AppState>, token: String, id: String) -> Result
          WHERE s.id = ?1",
         params![id],
         row_to_specimen,
-    ).map_err(|e| format!("Specimen not found: {}", e))
+    ).map_err(|_| AppError::not_found("specimen", "Specimen not found").with_id(&id))?;
+    mask_for_role(&db.conn, user.role.as_str(), specimen)
 }
 
 #[tauri::command]
@@ -164,138 +113,61 @@ pub fn create_specimen(
     state: State<AppState>,
     token: String,
     request: CreateSpecimenRequest,
-) -> Result<Specimen, String> {
+) -> Result<Masked<Specimen>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SpecimenCreate)?;
 
-    // Validate the requested stage against the active profile's vocabulary, mirroring
-    // bulk_update_stage. Without this, a stale cross-profile stage left in the New
-    // Specimen form (e.g. an 'explant' default after switching to the mycology profile)
-    // would be written straight to the DB.
     let profile = crate::db::vocabulary::active_profile(&db.conn);
-    crate::db::vocabulary::require_selectable_stage(&db.conn, &profile, &request.stage)?;
-
-    let species_code: String = db.conn.query_row(
-        "SELECT species_code FROM species WHERE id = ?1",
-        params![request.species_id],
-        |row| row.get(0),
-    ).map_err(|_| "Species not found".to_string())?;
-
-    let accession = queries::generate_accession_number(&db.conn, &species_code, &request.initiation_date)
-        .map_err(|e| format!("Failed to generate accession: {}", e))?;
+    let species_code = specimens::check_create_request(&db.conn, &profile, &request)?;
 
     let id = uuid::Uuid::new_v4().to_string();
-    let qr_data = format!("STELO:{}", accession);
-
-    // Wrap the specimen INSERT and the audit entry in a single transaction.
-    // This guarantees two things:
-    //   1. A specimen without an audit entry can never be committed to the DB.
-    //   2. When a child is split from a parent, the parent's audit entry_hash
-    //      is visible to the child's log_audit_for_child query (same txn is
-    //      not needed here since the parent was committed in a prior request,
-    //      but the transaction still ensures our own write is atomic).
-    // Snapshot the strain's current chain_seq before opening the transaction
-    // so that strain_chain_seq records the strain state at the moment the
-    // specimen was created (not after any intra-transaction writes).
-    let strain_chain_seq: Option<i64> = if let Some(ref sid) = request.strain_id {
-        db.conn.query_row(
-            "SELECT COALESCE(MAX(chain_seq), 0) FROM audit_log \
-             WHERE lineage_id = ?1 AND entry_hash IS NOT NULL",
-            params![sid],
-            |r| r.get(0),
-        ).ok()
-    } else {
-        None
-    };
+    let strain_chain_seq = specimens::strain_chain_seq(&db.conn, request.strain_id.as_deref());
 
     let tx = db.conn
         .unchecked_transaction()
         .map_err(|e| format!("Failed to start transaction: {}", e))?;
-
-    tx.execute(
-        "INSERT INTO specimens (id, accession_number, species_id, project_id, stage, custom_stage,
-         provenance, source_plant, initiation_date, location, location_details,
-         propagation_method, acclimatization_status, health_status, disease_status,
-         quarantine_flag, permit_number, permit_expiry, ip_flag, ip_notes,
-         environmental_notes, parent_specimen_id, qr_code_data, notes, employee_id, created_by,
-         strain_id, strain_chain_seq, origin_type, lab_profile)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
-                 ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30)",
-        params![
-            id, accession, request.species_id, request.project_id, request.stage, request.custom_stage,
-            request.provenance, request.source_plant, request.initiation_date, request.location,
-            request.location_details, request.propagation_method, request.acclimatization_status,
-            request.health_status, request.disease_status, request.quarantine_flag.unwrap_or(false) as i32,
-            request.permit_number, request.permit_expiry, request.ip_flag.unwrap_or(false) as i32,
-            request.ip_notes, request.environmental_notes, request.parent_specimen_id, qr_data,
-            request.notes, request.employee_id, user.id,
-            request.strain_id, strain_chain_seq, request.origin_type,
-            // Stamp lab membership from the profile active right now. `profile`
-            // was already resolved above for the stage check, so the stage the
-            // specimen is created in and the lab it is filed under can never
-            // disagree.
-            profile,
-        ],
-    ).map_err(|e| format!("Failed to create specimen: {}", e))?;
-
-    // Link the audit chain.
-    // - Split/derived: fork from parent's last entry_hash (cryptographically visible fork).
-    // - Strain-seeded root: seed from strain's last entry_hash.
-    // - Plain root: seed from species' last entry_hash.
-    if let Some(ref parent_id) = request.parent_specimen_id {
-        queries::log_audit_for_child(
-            &tx, Some(&user.id), "create", "specimen", Some(&id),
-            None, Some(&accession), Some("Specimen created (split/derived)"),
-            parent_id,
-        ).map_err(|e| format!("Failed to write split audit entry: {}", e))?;
-    } else if let Some(ref strain_id) = request.strain_id {
-        queries::log_audit_seeded_by_strain(
-            &tx, Some(&user.id), "create", "specimen", Some(&id),
-            None, Some(&accession), Some("Specimen created (strain-seeded)"),
-            strain_id,
-        ).map_err(|e| format!("Failed to write strain audit entry: {}", e))?;
-    } else {
-        queries::log_audit_seeded_by_species(
-            &tx, Some(&user.id), "create", "specimen", Some(&id),
-            None, Some(&accession), Some("Specimen created"),
-            &request.species_id,
-        ).map_err(|e| format!("Failed to write audit entry: {}", e))?;
-    }
-
+    // Inside the transaction, so a failed insert does not use up a template's
+    // sequence number (WP-95).
+    let accession = crate::db::accession::allocate(
+        &tx, &profile, &request.species_id, &species_code, &request.initiation_date, 1,
+    )?.remove(0);
+    specimens::insert_specimen(&tx, &user.id, &profile, &request, &id, &accession, strain_chain_seq, None)?;
     tx.commit().map_err(|e| format!("Failed to commit specimen: {}", e))?;
     crate::db::dashboard::invalidate_dashboard_cache(&state.dashboard_cache);
 
-    // WP-67: record a signed genesis transaction for the new specimen, attributed
-    // to the creating user's Ed25519 key. Best-effort — a ledger hiccup must never
-    // fail specimen creation (mirrors the `log_audit(...).ok()` convention).
-    let signed_payload = serde_json::json!({
-        "event": "specimen_created",
-        "specimen_id": id,
-        "accession_number": accession,
-    })
-    .to_string();
-    crate::signed_ledger::try_append_signed_event(
-        &db.conn, &user.id, "specimen_created", "specimen", Some(&id), &signed_payload,
-    );
-
     drop(db);
     get_specimen(state, token, id)
 }
 
+/// Initiates a batch of specimens from one template (WP-94): a count or a plate
+/// layout, with per-row overrides, on one contiguous accession range. Returns
+/// the batch's labels for printing.
+#[tauri::command]
+pub fn initiate_specimen_batch(
+    state: State<AppState>,
+    token: String,
+    request: InitiateBatchRequest,
+) -> Result<InitiationBatch, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::SpecimenCreate)?;
+    let batch = crate::db::initiation::initiate_batch(&db.conn, &user.id, &request)?;
+    crate::db::dashboard::invalidate_dashboard_cache(&state.dashboard_cache);
+    Ok(batch)
+}
+
 #[tauri::command]
 pub fn update_specimen(
     state: State<AppState>,
     token: String,
     request: UpdateSpecimenRequest,
-) -> Result<Specimen, String> {
+) -> Result<Masked<Specimen>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SpecimenEdit)?;
+    reject_if_restricted_marker(request.permit_number.as_deref(), "Permit number")?;
+    reject_if_restricted_marker(request.ip_notes.as_deref(), "IP notes")?;
     crate::db::vocabulary::require_active_lab_profile(&db.conn, &request.id)?;
 
     let mut updates = Vec::new();
@@ -342,9 +214,25 @@ pub fn update_specimen(
         updates.push(format!("is_best_performer = ?{}", values.len() + 1));
         values.push(Box::new(ibp as i32));
     }
+    // WP-96: the patch is merged into the stored values and the result checked
+    // as a whole.
+    let mut custom_change = None;
+    if let Some(ref patch) = request.custom_fields {
+        let stored: Option<String> = db.conn.query_row(
+            "SELECT custom_fields FROM specimens WHERE id = ?1",
+            params![request.id],
+            |r| r.get(0),
+        )?;
+        let before = custom_fields::from_column(stored.as_deref());
+        let profile = crate::db::vocabulary::active_profile(&db.conn);
+        let after = custom_fields::apply(&db.conn, &profile, CustomFieldEntity::Specimen, &before, patch, false)?;
+        updates.push(format!("custom_fields = ?{}", values.len() + 1));
+        values.push(Box::new(custom_fields::to_column(&after)));
+        custom_change = Some((before, after));
+    }
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
 
     updates.push("updated_at = datetime('now')".to_string());
@@ -355,14 +243,34 @@ pub fn update_specimen(
     );
     values.push(Box::new(request.id.clone()));
 
+    // WP-98: a stage change is checked against the profile's transitions
+    // once the row is written, so fields set in the same request count.
+    let from_stage: String = db.conn.query_row(
+        "SELECT stage FROM specimens WHERE id = ?1",
+        params![request.id],
+        |r| r.get(0),
+    )?;
+    let tx = db.conn.unchecked_transaction()?;
     let params: Vec<&dyn rusqlite::types::ToSql> = values.iter().map(|v| v.as_ref()).collect();
-    db.conn.execute(&sql, params.as_slice())
+    tx.execute(&sql, params.as_slice())
         .map_err(|e| format!("Failed to update specimen: {}", e))?;
-
-    queries::log_audit(
-        &db.conn, Some(&user.id), "update", "specimen", Some(&request.id),
-        None, None, Some("Specimen updated"),
-    ).ok();
+    if request.stage.is_some() {
+        let profile = crate::db::vocabulary::active_profile(&tx);
+        crate::db::stage_transitions::enforce(&tx, &profile, &request.id, &from_stage)?;
+    }
+    tx.commit()?;
+
+    match custom_change {
+        Some((before, after)) => queries::log_audit(
+            &db.conn, Some(&user.id), "update", "specimen", Some(&request.id),
+            custom_fields::to_column(&before).as_deref(), custom_fields::to_column(&after).as_deref(),
+            Some(&format!("Specimen updated; {}", custom_fields::audit_note(&after).unwrap_or_else(|| "custom fields {}".into()))),
+        ),
+        None => queries::log_audit(
+            &db.conn, Some(&user.id), "update", "specimen", Some(&request.id),
+            None, None, Some("Specimen updated"),
+        ),
+    }.ok();
     crate::db::dashboard::invalidate_dashboard_cache(&state.dashboard_cache);
 
     drop(db);
@@ -370,14 +278,16 @@ pub fn update_specimen(
 }
 
 #[tauri::command]
-pub fn delete_specimen(state: State<AppState>, token: String, id: String) -> Result<(), String> {
+pub fn delete_specimen(state: State<AppState>, token: String, id: String) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can delete specimens".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SpecimenDelete)?;
     crate::db::vocabulary::require_active_lab_profile(&db.conn, &id)?;
 
+    // WP-100: the prior state goes into the audit entry so the archive can be undone.
+    let snapshot = specimen_restore::take(&db.conn, &id, None)?
+        .ok_or_else(|| AppError::not_found("specimen", "Specimen not found").with_id(&id))?;
+
     // Archive instead of hard delete
     db.conn.execute(
         "UPDATE specimens SET is_archived = 1, archived_at = datetime('now'), updated_at = datetime('now') WHERE id = ?1",
@@ -386,7 +296,7 @@ pub fn delete_specimen(state: State<AppState>, token: String, id: String) -> Res
 
     queries::log_audit(
         &db.conn, Some(&user.id), "archive", "specimen", Some(&id),
-        None, None, Some("Specimen archived"),
+        Some(&snapshot.json), None, Some(&snapshot.details("Specimen archived")),
     ).ok();
 
     // WP-75: signed archive event attributed to the acting user's key. Best-effort.
@@ -409,188 +319,26 @@ pub fn search_specimens(
     state: State<AppState>,
     token: String,
     params_input: SpecimenSearchParams,
-) -> Result<PaginatedResponse<Specimen>, String> {
+) -> Result<Masked<PaginatedResponse<Specimen>>, AppError> {
     let db = state.db();
-    let _user = auth_service::validate_session(&db, &token)?;
-
-    let pg = queries::PaginationParams {
-        page: params_input.page.unwrap_or(1),
-        per_page: params_input.per_page.unwrap_or(50),
-    };
-
-    let mut conditions = Vec::new();
-    let mut bind_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-
-    // Lab isolation is unconditional and is deliberately the first predicate —
-    // it is not a user-supplied filter and there is no search parameter that can
-    // widen it to another lab's cultures.
-    conditions.push("s.lab_profile = ?1".to_string());
-    bind_values.push(Box::new(crate::db::vocabulary::active_profile(&db.conn)));
-
-    let show_archived = params_input.archived.unwrap_or(false);
-    if !show_archived {
-        conditions.push("s.is_archived = 0".to_string());
-    }
-
-    // Free-text search is served either by the trigram FTS index (fast path,
-    // via a CTE that drives the query) or by the original LIKE scan (fallback).
-    // `cte` is empty when there is no text query, in which case both SQL
-    // statements below are shaped exactly as they were.
-    let mut cte = String::new();
-    let mut from_specimens = "specimens s".to_string();
-
-    if let Some(ref q) = params_input.query {
-        let trimmed = q.trim();
-        if !trimmed.is_empty() {
-            match queries::fts_match_query(trimmed) {
-                Some(fts_query) => {
-                    let fts_idx = bind_values.len() + 1;
-                    let like_idx = fts_idx + 1;
-                    // Shape matters as much as the index here. Expressing this
-                    // as `... WHERE s.rowid IN (fts) OR sp.genus LIKE ...`
-                    // leaves SQLite walking idx_specimens_archived_created and
-                    // probing the match set per row — measured at 100k rows,
-                    // that is 15.3ms versus 20.8ms for the plain LIKE scan, an
-                    // index that barely pays for itself.
-                    //
-                    // MATERIALIZED forces the match set to be computed once,
-                    // and CROSS JOIN pins it as the outer loop (SQLite honours
-                    // CROSS JOIN as a join-order constraint). Otherwise the
-                    // ORDER BY ... LIMIT tempts the planner into walking the
-                    // created_at index instead, which is only the right choice
-                    // when matches are dense. Same corpus, same results:
-                    // 2.6ms — 8x faster than the scan.
-                    //
-                    // `species` stays on LIKE: it is a small lookup table, so
-                    // indexing it would add maintenance for no measurable gain.
-                    cte = format!(
-                        "WITH matches(id) AS MATERIALIZED (
-                             SELECT fs.id FROM specimens_fts f
-                                 JOIN specimens fs ON fs.rowid = f.rowid
-                                 WHERE f.specimens_fts MATCH ?{f}
-                             UNION
-                             SELECT ss.id FROM specimens ss
-                                 WHERE ss.species_id IN (
-                                     SELECT id FROM species
-                                     WHERE genus LIKE ?{l} OR species_name LIKE ?{l})
-                         ) ",
-                        f = fts_idx,
-                        l = like_idx
-                    );
-                    from_specimens = "matches mt CROSS JOIN specimens s ON s.id = mt.id".to_string();
-                    bind_values.push(Box::new(fts_query));
-                    bind_values.push(Box::new(format!("%{}%", trimmed)));
-                }
-                // A trigram index cannot answer a query shorter than three
-                // characters, so those keep the original scan. They are rare,
-                // and a 1-2 character needle matches most of the lab anyway —
-                // the page limit bounds the work.
-                None => {
-                    let param_idx = bind_values.len() + 1;
-                    conditions.push(format!(
-                        "(s.accession_number LIKE ?{p} OR s.notes LIKE ?{p} OR s.location LIKE ?{p} \
-                         OR s.provenance LIKE ?{p} OR s.source_plant LIKE ?{p} \
-                         OR sp.genus LIKE ?{p} OR sp.species_name LIKE ?{p})",
-                        p = param_idx
-                    ));
-                    bind_values.push(Box::new(format!("%{}%", trimmed)));
-                }
-            }
-        }
-    }
-
-    if let Some(ref sid) = params_input.species_id {
-        let param_idx = bind_values.len() + 1;
-        conditions.push(format!("s.species_id = ?{}", param_idx));
-        bind_values.push(Box::new(sid.clone()));
-    }
-
-    if let Some(ref stage) = params_input.stage {
-        let param_idx = bind_values.len() + 1;
-        conditions.push(format!("s.stage = ?{}", param_idx));
-        bind_values.push(Box::new(stage.clone()));
-    }
-
-    if let Some(ref pid) = params_input.project_id {
-        let param_idx = bind_values.len() + 1;
-        conditions.push(format!("s.project_id = ?{}", param_idx));
-        bind_values.push(Box::new(pid.clone()));
-    }
-
-    if params_input.quarantine_only.unwrap_or(false) {
-        conditions.push("s.quarantine_flag = 1".to_string());
-    }
-
-    if params_input.best_performer_only.unwrap_or(false) {
-        conditions.push("s.is_best_performer = 1".to_string());
-    }
-
-    if let Some(ref stid) = params_input.strain_id {
-        let param_idx = bind_values.len() + 1;
-        conditions.push(format!("s.strain_id = ?{}", param_idx));
-        bind_values.push(Box::new(stid.clone()));
-    }
-
-    let where_clause = if conditions.is_empty() {
-        String::new()
-    } else {
-        format!("WHERE {}", conditions.join(" AND "))
-    };
-
-    let count_sql = format!(
-        "{}SELECT COUNT(*) FROM {} LEFT JOIN species sp ON s.species_id = sp.id {}",
-        cte, from_specimens, where_clause
-    );
-    let bind_refs: Vec<&dyn rusqlite::types::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
-    let total: i64 = db.conn.query_row(&count_sql, bind_refs.as_slice(), |r| r.get(0))
-        .map_err(|e| e.to_string())?;
-
-    let query_sql = format!(
-        "{}SELECT s.*, sp.species_code, sp.genus || ' ' || sp.species_name as species_name,
-                p.name as project_name,
-                COALESCE(cf.has_contamination, 0) AS has_contamination
-         FROM {}
-         LEFT JOIN species sp ON s.species_id = sp.id
-         LEFT JOIN projects p ON s.project_id = p.id
-         LEFT JOIN (SELECT specimen_id, MAX(contamination_flag) AS has_contamination
-                    FROM subcultures GROUP BY specimen_id) cf ON cf.specimen_id = s.id
-         {}
-         ORDER BY s.created_at DESC
-         LIMIT ?{} OFFSET ?{}",
-        cte,
-        from_specimens,
-        where_clause,
-        bind_values.len() + 1,
-        bind_values.len() + 2
-    );
-
-    bind_values.push(Box::new(pg.limit()));
-    bind_values.push(Box::new(pg.offset()));
-
-    let bind_refs2: Vec<&dyn rusqlite::types::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
-    let mut stmt = db.conn.prepare(&query_sql).map_err(|e| e.to_string())?;
-
-    // Strict for the same reason as list_specimens: a partially-populated search
-    // result is indistinguishable from a genuinely small one.
-    let specimens = stmt
-        .query_map(bind_refs2.as_slice(), row_to_specimen)
-        .map_err(|e| e.to_string())?
-        .collect::<rusqlite::Result<Vec<_>>>()
-        .map_err(|e| format!("Failed to read specimen rows: {}", e))?;
+    let user = auth_service::validate_session(&db, &token)?;
 
-    let total_pages = ((total as f64) / (pg.per_page as f64)).ceil() as u32;
+    crate::db::specimens::search(&db.conn, user.role.as_str(), &params_input)
+}
 
-    Ok(PaginatedResponse {
-        items: specimens,
-        total,
-        page: pg.page,
-        per_page: pg.per_page,
-        total_pages,
-    })
+/// IDs of every unarchived specimen in the active lab matching a filter
+/// expression (WP-97). The specimen list selects with this before a bulk
+/// archive, relocation or stage change.
+#[tauri::command]
+pub fn list_matching_specimen_ids(state: State<AppState>, token: String, filter: String) -> Result<Vec<String>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    let compiled = crate::db::specimen_query::compile_for_active_lab(&db.conn, user.role.as_str(), &filter)?;
+    crate::db::specimen_query::matching_ids(&db.conn, &compiled)
 }
 
 #[tauri::command]
-pub fn get_specimen_stats(state: State<AppState>, token: String) -> Result<SpecimenStats, String> {
+pub fn get_specimen_stats(state: State<AppState>, token: String) -> Result<SpecimenStats, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -611,32 +359,35 @@ pub fn bulk_archive_specimens(
     state: State<AppState>,
     token: String,
     ids: Vec<String>,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     if ids.is_empty() {
         return Ok(0);
     }
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can archive specimens".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SpecimenArchive)?;
     // Bulk operations take a caller-supplied ID list, so the lab predicate goes
     // into the UPDATE itself: an ID belonging to another lab matches no row,
     // contributes nothing to `count`, and produces no audit or signed event.
     let profile = crate::db::vocabulary::active_profile(&db.conn);
+    // WP-100: one batch id for the lot, so the whole archive can be undone together.
+    let batch = uuid::Uuid::new_v4().to_string();
     let mut count = 0usize;
     for id in &ids {
+        let Some(snapshot) = specimen_restore::take(&db.conn, id, Some(&batch))? else {
+            continue;
+        };
         let n = db.conn.execute(
             "UPDATE specimens SET is_archived = 1, archived_at = datetime('now'),
              updated_at = datetime('now')
              WHERE id = ?1 AND is_archived = 0 AND lab_profile = ?2",
             params![id, profile],
-        ).map_err(|e| e.to_string())?;
+        )?;
         count += n;
         if n > 0 {
             queries::log_audit(
                 &db.conn, Some(&user.id), "archive", "specimen", Some(id),
-                None, None, Some("Bulk archived"),
+                Some(&snapshot.json), None, Some(&snapshot.details("Bulk archived")),
             ).ok();
             // WP-75: one signed archive event per specimen actually archived.
             crate::signed_ledger::try_append_signed_event(
@@ -661,15 +412,13 @@ pub fn bulk_update_location(
     token: String,
     ids: Vec<String>,
     location: String,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     if ids.is_empty() {
         return Ok(0);
     }
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SpecimenEdit)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
     let mut count = 0usize;
     for id in &ids {
@@ -677,12 +426,12 @@ pub fn bulk_update_location(
             "UPDATE specimens SET location = ?1, updated_at = datetime('now')
              WHERE id = ?2 AND is_archived = 0 AND lab_profile = ?3",
             params![location, id, profile],
-        ).map_err(|e| e.to_string())?;
+        )?;
         count += n;
         if n > 0 {
             queries::log_audit(
-                &db.conn, Some(&user.id), "update", "specimen", Some(id),
-                None, None, Some(&format!("Bulk location transfer: {}", location)),
+                &db.conn, Some(&user.id), "relocate", "specimen", Some(id),
+                None, Some(&location), Some(&format!("Bulk location transfer: {}", location)),
             ).ok();
         }
     }
@@ -704,16 +453,14 @@ pub fn split_specimen(
     state: State<AppState>,
     token: String,
     request: SplitSpecimenRequest,
-) -> Result<SplitResult, String> {
+) -> Result<SplitResult, AppError> {
     if request.children.len() < 2 {
-        return Err("Split requires at least 2 children".to_string());
+        return Err(AppError::validation("children", "Split requires at least 2 children"));
     }
 
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SpecimenSplit)?;
     crate::db::vocabulary::require_active_lab_profile(&db.conn, &request.parent_specimen_id)?;
     // Children inherit the parent's lab rather than re-reading the active
     // profile. The guard above already proves the two agree, but reading it from
@@ -744,17 +491,17 @@ pub fn split_specimen(
             row.get(6)?, row.get(7)?, row.get(8)?, row.get(9)?, row.get(10)?,
             row.get(11)?, row.get(12)?, row.get(13)?,
         )),
-    ).map_err(|_| "Parent specimen not found or already archived".to_string())?;
+    ).map_err(|_| AppError::not_found("specimen", "Parent specimen not found or already archived"))?;
 
     // Pre-generate accession numbers for children that did not specify one.
-    // These use the parent's full accession string with a letter suffix (A, B, C…),
-    // skipping any letters already taken in the database.
+    // These use the parent's full accession string with the suffix rule of the
+    // species' accession template — a letter (A, B, C…) by default — skipping
+    // any already taken in the database (WP-95).
     let auto_count: usize = request.children.iter()
         .filter(|c| c.accession_number.as_deref().map(str::is_empty).unwrap_or(true))
         .count();
     let auto_generated: Vec<String> = if auto_count > 0 {
-        queries::generate_split_accession_numbers(&db.conn, &parent_accession, auto_count)
-            .map_err(|e| e.to_string())?
+        crate::db::accession::split_children(&db.conn, &parent_lab_profile, &parent_species_id, &parent_accession, auto_count)?
     } else {
         Vec::new()
     };
@@ -772,18 +519,18 @@ pub fn split_specimen(
                         "SELECT COUNT(*) FROM specimens WHERE accession_number = ?1",
                         params![provided],
                         |r| r.get::<_, i64>(0),
-                    ).map(|c| c > 0).map_err(|e| e.to_string())?;
+                    ).map(|c| c > 0)?;
                     if exists {
-                        return Err(format!(
+                        return Err(AppError::conflict(format!(
                             "Accession number '{}' for child {} is already in use",
                             provided, i + 1
-                        ));
+                        )));
                     }
                     provided.to_string()
                 }
                 None => {
                     let a = auto_generated.get(auto_idx).ok_or_else(|| {
-                        format!("Internal error: ran out of auto-generated accessions at child {}", i + 1)
+                        AppError::internal(format!("Internal error: ran out of auto-generated accessions at child {}", i + 1))
                     })?.clone();
                     auto_idx += 1;
                     a
@@ -791,22 +538,23 @@ pub fn split_specimen(
             };
 
             if seen.contains(&acc) {
-                return Err(format!(
+                return Err(AppError::validation("children", format!(
                     "Duplicate accession number '{}' at child {} — each child must have a unique accession",
                     acc, i + 1
-                ));
+                )));
             }
             seen.insert(acc.clone());
             child_accessions.push(acc);
         }
     }
 
-    // Fetch the parent's cumulative PDL so children can inherit it.
-    let parent_cumulative_pdl: Option<f64> = db.conn.query_row(
-        "SELECT cumulative_pdl FROM specimens WHERE id = ?1",
+    // Fetch the parent's cumulative PDL so children can inherit it, and its
+    // custom field values (WP-96), which children inherit as stored.
+    let (parent_cumulative_pdl, parent_custom_fields): (Option<f64>, Option<String>) = db.conn.query_row(
+        "SELECT cumulative_pdl, custom_fields FROM specimens WHERE id = ?1",
         params![request.parent_specimen_id],
-        |r| r.get(0),
-    ).ok().flatten();
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    ).unwrap_or((None, None));
 
     // Compute genealogy values for children:
     //   generation             = parent + 1
@@ -906,7 +654,8 @@ pub fn split_specimen(
             .unwrap_or(default_note.as_str());
 
         // Insert child specimen with genealogy fields, inherited contamination status,
-        // inherited cumulative PDL from the parent (WP-31), and inherited origin_type (WP-42).
+        // inherited cumulative PDL from the parent (WP-31), inherited origin_type (WP-42)
+        // and inherited custom field values (WP-96).
         // is_best_performer resets to 0 for every child — selection is re-evaluated per generation.
         tx.execute(
             "INSERT INTO specimens \
@@ -915,8 +664,8 @@ pub fn split_specimen(
               provenance, source_plant, notes, created_by, \
               generation, lineage_passage_offset, root_specimen_id, \
               contamination_flag, contamination_notes, cumulative_pdl, origin_type, \
-              lab_profile) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+              lab_profile, custom_fields) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
             params![
                 child_id, accession, parent_species_id, child_stage, request.date,
                 child_location, child_health, qr_data, request.parent_specimen_id,
@@ -924,13 +673,15 @@ pub fn split_specimen(
                 child_generation, child_passage_offset, child_root_id,
                 child_contamination_flag_i32, child_contamination_notes,
                 parent_cumulative_pdl, parent_origin_type,
-                parent_lab_profile,
+                parent_lab_profile, parent_custom_fields,
             ],
         ).map_err(|e| format!("Failed to create child specimen {}: {}", i + 1, e))?;
+        // WP-98: a child given its own stage must reach it from the parent's.
+        crate::db::stage_transitions::enforce(&tx, &parent_lab_profile, &child_id, &parent_stage)?;
 
         // Fork the audit chain from the parent (all children inherit the same
         // parent prev_hash — the split event logged above — making the fork visible)
-        let child_audit_detail = if child_contamination_flag_i32 != 0 {
+        let mut child_audit_detail = if child_contamination_flag_i32 != 0 {
             format!(
                 "Split from {} — container {} of {} [contamination inherited]",
                 request.parent_specimen_id, i + 1, request.children.len()
@@ -941,6 +692,9 @@ pub fn split_specimen(
                 request.parent_specimen_id, i + 1, request.children.len()
             )
         };
+        if let Some(ref custom) = parent_custom_fields {
+            child_audit_detail.push_str(&format!("; custom fields {}", custom));
+        }
         queries::log_audit_for_child(
             &tx, Some(&user.id), "create", "specimen", Some(&child_id),
             None, Some(accession.as_str()),
@@ -1012,21 +766,20 @@ pub fn preview_split_accessions(
     token: String,
     parent_id: String,
     count: u32,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, AppError> {
     if count == 0 || count > 26 {
-        return Err("Count must be between 1 and 26".to_string());
+        return Err(AppError::validation("count", "Count must be between 1 and 26"));
     }
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
-    let parent_accession: String = db.conn.query_row(
-        "SELECT accession_number FROM specimens WHERE id = ?1 AND is_archived = 0",
+    let (parent_accession, species_id, lab_profile): (String, String, String) = db.conn.query_row(
+        "SELECT accession_number, species_id, lab_profile FROM specimens WHERE id = ?1 AND is_archived = 0",
         params![parent_id],
-        |r| r.get(0),
-    ).map_err(|_| "Parent specimen not found or already archived".to_string())?;
+        |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+    ).map_err(|_| AppError::not_found("specimen", "Parent specimen not found or already archived"))?;
 
-    queries::generate_split_accession_numbers(&db.conn, &parent_accession, count as usize)
-        .map_err(|e| e.to_string())
+    crate::db::accession::split_children(&db.conn, &lab_profile, &species_id, &parent_accession, count as usize)
 }
 
 /// Return all specimens that share the same root as the given specimen.
@@ -1038,7 +791,7 @@ pub fn get_specimen_family(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<Vec<FamilyMember>, String> {
+) -> Result<Vec<FamilyMember>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     crate::db::vocabulary::require_active_lab_profile(&db.conn, &id)?;
@@ -1051,7 +804,7 @@ pub fn get_specimen_family(
             params![id],
             |r| r.get(0),
         )
-        .map_err(|_| "Specimen not found".to_string())?;
+        .map_err(|_| AppError::not_found("specimen", "Specimen not found"))?;
 
     // Fetch all members: the root itself + every specimen whose root_specimen_id = root.
     let mut stmt = db.conn
@@ -1063,8 +816,7 @@ pub fn get_specimen_family(
              LEFT JOIN species sp ON s.species_id = sp.id
              WHERE s.id = ?1 OR s.root_specimen_id = ?1
              ORDER BY s.generation ASC, s.created_at ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let members = stmt
         .query_map(params![root_id], |row| {
@@ -1081,8 +833,7 @@ pub fn get_specimen_family(
                 location:              row.get(9)?,
                 species_code:          row.get(10)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -1095,39 +846,156 @@ pub fn bulk_update_stage(
     token: String,
     ids: Vec<String>,
     stage: String,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     if ids.is_empty() {
         return Ok(0);
     }
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SpecimenEdit)?;
     // Validate against the vocabulary table; is_terminal = 0 prevents setting 'archived' in bulk.
     let profile = crate::db::vocabulary::active_profile(&db.conn);
     crate::db::vocabulary::require_selectable_stage(&db.conn, &profile, &stage)?;
+    // WP-98: all or nothing. One specimen that may not make the move (or is
+    // missing a required field) rolls the whole batch back, and the error
+    // names it.
+    let tx = db.conn.unchecked_transaction()?;
     let mut count = 0usize;
     for id in &ids {
-        let n = db.conn.execute(
-            "UPDATE specimens SET stage = ?1, updated_at = datetime('now')
-             WHERE id = ?2 AND is_archived = 0 AND lab_profile = ?3",
-            params![stage, id, profile],
-        ).map_err(|e| e.to_string())?;
+        let from_stage: Option<String> = tx.query_row(
+            "SELECT stage FROM specimens WHERE id = ?1 AND is_archived = 0 AND lab_profile = ?2",
+            params![id, profile],
+            |r| r.get(0),
+        ).optional()?;
+        let Some(from_stage) = from_stage else { continue };
+        let n = tx.execute(
+            "UPDATE specimens SET stage = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![stage, id],
+        )?;
+        crate::db::stage_transitions::enforce(&tx, &profile, id, &from_stage)?;
         count += n;
-        if n > 0 {
-            queries::log_audit(
-                &db.conn, Some(&user.id), "update", "specimen", Some(id),
-                None, None, Some(&format!("Bulk stage update: {}", stage)),
-            ).ok();
-        }
+        queries::log_audit(
+            &tx, Some(&user.id), "update", "specimen", Some(id),
+            Some(&from_stage), Some(&stage), Some(&format!("Bulk stage update: {}", stage)),
+        ).ok();
     }
+    tx.commit()?;
     if count > 0 {
         crate::db::dashboard::invalidate_dashboard_cache(&state.dashboard_cache);
     }
     Ok(count)
 }
 
+/// WP-99: merge a duplicate specimen into the one that stays. Its passages,
+/// attachments, reminders, readings, vials and children move to the
+/// survivor; the duplicate stays as an archived tombstone that redirects
+/// lookups. Both audit lineages record the merge, and it is signed once.
+#[tauri::command]
+pub fn merge_specimens(
+    state: State<AppState>,
+    token: String,
+    request: MergeSpecimensRequest,
+) -> Result<SpecimenMerge, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::SpecimenMerge)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+
+    let tx = db.conn.unchecked_transaction()?;
+    let merge = specimen_merge::merge(&tx, &user.id, &profile, &request)?;
+    let reason = merge.reason.as_deref().map(|r| format!(" Reason: {}", r)).unwrap_or_default();
+    queries::log_audit(
+        &tx, Some(&user.id), "merge", "specimen", Some(&merge.survivor_id),
+        None, Some(&merge.retired_id),
+        Some(&format!(
+            "Specimen merged: {} merged into this record; moved {}.{}",
+            merge.retired_accession, specimen_merge::describe(&merge.moved), reason,
+        )),
+    ).map_err(|e| format!("Failed to audit the merge: {}", e))?;
+    queries::log_audit(
+        &tx, Some(&user.id), "merge", "specimen", Some(&merge.retired_id),
+        None, Some(&merge.survivor_id),
+        Some(&format!("Specimen merged into {}; this record is kept as a tombstone.{}", merge.survivor_accession, reason)),
+    ).map_err(|e| format!("Failed to audit the merge: {}", e))?;
+    tx.commit()?;
+    crate::db::dashboard::invalidate_dashboard_cache(&state.dashboard_cache);
+
+    crate::signed_ledger::try_append_signed_event(
+        &db.conn,
+        &user.id,
+        crate::signed_ledger::lifecycle::SPECIMEN_MERGED,
+        "specimen",
+        Some(&merge.survivor_id),
+        &crate::signed_ledger::lifecycle::merged(
+            &merge.survivor_id, &merge.retired_id, &merge.retired_accession, &serde_json::to_value(&merge.moved)?,
+        ),
+    );
+    Ok(merge)
+}
+
+/// The duplicates merged into a specimen, newest first.
+#[tauri::command]
+pub fn list_specimen_merges(state: State<AppState>, token: String, id: String) -> Result<Vec<SpecimenMerge>, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    crate::db::vocabulary::require_active_lab_profile(&db.conn, &id)?;
+    specimen_merge::list_for(&db.conn, &id)
+}
+
+/// Archived specimens that can be restored, and deleted ones whose archive
+/// snapshot is in the audit trail (WP-100). Newest archive first.
+#[tauri::command]
+pub fn list_restorable_specimens(state: State<AppState>, token: String) -> Result<Vec<RestorableSpecimen>, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::SpecimenRestore)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    specimen_restore::list_restorable(&db.conn, &profile)
+}
+
+/// Undoes the archive of each specimen in `ids`, re-inserting deleted rows
+/// from the archive snapshot where the audit chain proves it (WP-100). All or
+/// nothing: one refusal rolls the batch back and names the specimen.
+#[tauri::command]
+pub fn restore_specimens(
+    state: State<AppState>,
+    token: String,
+    ids: Vec<String>,
+) -> Result<Vec<RestoredSpecimen>, AppError> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::SpecimenRestore)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+
+    let tx = db.conn.unchecked_transaction()?;
+    let restored = specimen_restore::restore(&tx, &profile, &ids)?;
+    for r in &restored {
+        queries::log_audit(
+            &tx, Some(&user.id), "restore", "specimen", Some(&r.specimen_id),
+            Some(&r.source_entry_id), None, Some(&specimen_restore::describe(r)),
+        ).map_err(|e| format!("Failed to audit the restore: {}", e))?;
+    }
+    tx.commit()?;
+    crate::db::dashboard::invalidate_dashboard_cache(&state.dashboard_cache);
+
+    for r in &restored {
+        crate::signed_ledger::try_append_signed_event(
+            &db.conn,
+            &user.id,
+            crate::signed_ledger::lifecycle::SPECIMEN_RESTORED,
+            "specimen",
+            Some(&r.specimen_id),
+            &crate::signed_ledger::lifecycle::restored(
+                &r.specimen_id, &r.source_entry_id, r.reinserted, r.subcultures_reinserted, r.children_relinked,
+            ),
+        );
+    }
+    Ok(restored)
+}
+
 #[cfg(test)]
 mod tests {
     use rusqlite::{Connection, params};
@@ -0,0 +1,76 @@
+// WP-98: the stage state machine of the active lab profile. Anyone signed in
+// can read it (the stage pickers offer only allowed moves); changing it needs
+// `stages.configure`. Enforcement lives with the specimen writes.
+use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::queries;
+use crate::db::stage_transitions::{self, RequirableField, SaveStageTransitionRequest, StageTransition};
+use crate::error::AppError;
+use crate::AppState;
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Serialize)]
+pub struct StageMachine {
+    pub lab_profile: String,
+    pub transitions: Vec<StageTransition>,
+    pub requirable_fields: Vec<RequirableField>,
+}
+
+#[tauri::command]
+pub fn list_stage_transitions(state: State<AppState>, token: String) -> Result<StageMachine, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    Ok(StageMachine {
+        transitions: stage_transitions::list(&db.conn, &profile)?,
+        requirable_fields: stage_transitions::requirable_fields(),
+        lab_profile: profile,
+    })
+}
+
+/// Allow a stage change, or change the fields it requires.
+#[tauri::command]
+pub fn save_stage_transition(
+    state: State<AppState>,
+    token: String,
+    request: SaveStageTransitionRequest,
+) -> Result<StageTransition, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::StagesConfigure)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let transition = stage_transitions::save(&db.conn, &user.id, &profile, &request)?;
+    let id = format!("{}:{}:{}", transition.lab_profile, transition.from_stage, transition.to_stage);
+    queries::log_audit(
+        &db.conn, Some(&user.id), "save", "stage_transition", Some(&id),
+        None, serde_json::to_string(&transition.required_fields).ok().as_deref(),
+        Some(&format!(
+            "Stage transition {} → {} allowed for {}",
+            transition.from_stage, transition.to_stage, transition.lab_profile,
+        )),
+    ).ok();
+    Ok(transition)
+}
+
+/// Forbid a stage change again.
+#[tauri::command]
+pub fn delete_stage_transition(
+    state: State<AppState>,
+    token: String,
+    from_stage: String,
+    to_stage: String,
+) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::StagesConfigure)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let removed = stage_transitions::delete(&db.conn, &profile, &from_stage, &to_stage)?;
+    let id = format!("{}:{}:{}", profile, from_stage, to_stage);
+    queries::log_audit(
+        &db.conn, Some(&user.id), "delete", "stage_transition", Some(&id),
+        serde_json::to_string(&removed.required_fields).ok().as_deref(), None,
+        Some(&format!("Stage transition {} → {} removed for {}", from_stage, to_stage, profile)),
+    ).ok();
+    Ok(())
+}
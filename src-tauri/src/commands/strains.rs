@@ -1,10 +1,15 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::commands::signed_events::verify_ceremony;
+use crate::db::permissions::{mask_for_role, Masked};
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::strain::{
     CreateHybridizationEventRequest, CreateStrainRequest, GenerationalStats, HybridizationResult,
     PedigreeExport, PedigreeNode, Strain, StrainSpecimenTree, SuggestGenerationLabelResponse,
     UpdateStrainRequest, UpdateStrainStatusRequest,
 };
+use crate::signed_ledger::esignature::{self, SignatureCeremony};
 use crate::AppState;
 use rusqlite::params;
 use tauri::State;
@@ -12,7 +17,7 @@ use tauri::State;
 
 // ── internal read helper ───────────────────────────────────────────────────────
 
-fn load_strain(conn: &rusqlite::Connection, id: &str) -> Result<Strain, String> {
+fn load_strain(conn: &rusqlite::Connection, id: &str) -> Result<Strain, AppError> {
     conn.query_row(
         "SELECT s.*, \
                 (SELECT COUNT(*) FROM specimens sp WHERE sp.strain_id = s.id AND sp.is_archived = 0 \
@@ -22,7 +27,7 @@ fn load_strain(conn: &rusqlite::Connection, id: &str) -> Result<Strain, String>
         params![id],
         row_to_strain,
     )
-    .map_err(|e| format!("Strain not found: {}", e))
+    .map_err(|_| AppError::not_found("strain", "Strain not found").with_id(id))
 }
 
 fn row_to_strain(row: &rusqlite::Row<'_>) -> rusqlite::Result<Strain> {
@@ -87,20 +92,20 @@ fn check_no_cycle(
     conn: &rusqlite::Connection,
     strain_a: &str,
     strain_b: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let mut visited = Vec::new();
     if is_ancestor(conn, strain_a, strain_b, &mut visited) {
-        return Err(format!(
+        return Err(AppError::conflict(format!(
             "Cycle detected: strain '{}' is an ancestor of '{}'",
             strain_a, strain_b
-        ));
+        )));
     }
     let mut visited = Vec::new();
     if is_ancestor(conn, strain_b, strain_a, &mut visited) {
-        return Err(format!(
+        return Err(AppError::conflict(format!(
             "Cycle detected: strain '{}' is an ancestor of '{}'",
             strain_b, strain_a
-        ));
+        )));
     }
     Ok(())
 }
@@ -112,12 +117,10 @@ pub fn create_strain(
     state: State<AppState>,
     token: String,
     request: CreateStrainRequest,
-) -> Result<Strain, String> {
+) -> Result<Masked<Strain>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::StrainEdit)?;
 
     // Verify species exists.
     let _: String = db
@@ -127,7 +130,7 @@ pub fn create_strain(
             params![request.species_id],
             |r| r.get(0),
         )
-        .map_err(|_| "Species not found".to_string())?;
+        .map_err(|_| AppError::not_found("species", "Species not found"))?;
 
     let id = uuid::Uuid::new_v4().to_string();
     let strain_type = request.strain_type.unwrap_or_else(|| "wildtype".to_string());
@@ -165,30 +168,16 @@ pub fn create_strain(
     get_strain(state, token, id)
 }
 
-/// WP-55: masks `genomic_fingerprint` per the calling user's role. Applied
-/// only here (the read-response construction), never at the DB/audit level.
-/// Takes a pre-loaded [`crate::db::permissions::FieldPermissionSet`] rather
-/// than querying per call — `list_strains_by_species` used to call this once
-/// per row, issuing one identical `field_permissions` query per strain in
-/// the list (an N+1 pattern fixed alongside the corruption bug below).
-fn apply_field_permissions(perms: &crate::db::permissions::FieldPermissionSet, mut strain: Strain) -> Strain {
-    strain.genomic_fingerprint =
-        perms.mask_optional_field("strain", "genomic_fingerprint", strain.genomic_fingerprint);
-    strain
-}
-
 #[tauri::command]
 pub fn get_strain(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<Strain, String> {
+) -> Result<Masked<Strain>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
     let strain = load_strain(&db.conn, &id)?;
-    let perms = crate::db::permissions::FieldPermissionSet::load(&db.conn, user.role.as_str())
-        .map_err(|e| e.to_string())?;
-    Ok(apply_field_permissions(&perms, strain))
+    mask_for_role(&db.conn, user.role.as_str(), strain)
 }
 
 #[tauri::command]
@@ -196,7 +185,7 @@ pub fn list_strains_by_species(
     state: State<AppState>,
     token: String,
     species_id: String,
-) -> Result<Vec<Strain>, String> {
+) -> Result<Masked<Vec<Strain>>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
 
@@ -210,22 +199,13 @@ pub fn list_strains_by_species(
              FROM strains s \
              WHERE s.species_id = ?1 AND s.is_archived = 0 \
              ORDER BY s.name ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let rows = stmt
-        .query_map(params![species_id], row_to_strain)
-        .map_err(|e| e.to_string())?;
-
-    // Loaded once for the whole list, not once per row — see the doc comment
-    // on `apply_field_permissions`.
-    let perms = crate::db::permissions::FieldPermissionSet::load(&db.conn, user.role.as_str())
-        .map_err(|e| e.to_string())?;
-    let strains: Vec<Strain> = rows
-        .filter_map(|r| r.ok())
-        .map(|s| apply_field_permissions(&perms, s))
-        .collect();
-    Ok(strains)
+        .query_map(params![species_id], row_to_strain)?;
+
+    let strains: Vec<Strain> = rows.filter_map(|r| r.ok()).collect();
+    mask_for_role(&db.conn, user.role.as_str(), strains)
 }
 
 #[tauri::command]
@@ -233,12 +213,10 @@ pub fn update_strain(
     state: State<AppState>,
     token: String,
     request: UpdateStrainRequest,
-) -> Result<Strain, String> {
+) -> Result<Masked<Strain>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::StrainEdit)?;
 
     let tx = db
         .conn
@@ -249,22 +227,19 @@ pub fn update_strain(
         tx.execute(
             "UPDATE strains SET name = ?1, updated_at = datetime('now') WHERE id = ?2",
             params![name, request.id],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     }
     if let Some(ref code) = request.code {
         tx.execute(
             "UPDATE strains SET code = ?1, updated_at = datetime('now') WHERE id = ?2",
             params![code, request.id],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     }
     if let Some(ref strain_type) = request.strain_type {
         tx.execute(
             "UPDATE strains SET strain_type = ?1, updated_at = datetime('now') WHERE id = ?2",
             params![strain_type, request.id],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     }
 
     queries::log_audit(
@@ -291,12 +266,10 @@ pub fn archive_strain(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::StrainEdit)?;
 
     db.conn
         .execute(
@@ -326,12 +299,23 @@ pub fn update_strain_status(
     state: State<AppState>,
     token: String,
     request: UpdateStrainStatusRequest,
-) -> Result<Strain, String> {
+    signature: Option<SignatureCeremony>,
+) -> Result<Masked<Strain>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::StrainEdit)?;
+
+    // WP-80: confirming a strain's identity is a Part 11 critical action, so it
+    // needs an electronic signature. The password is checked before anything is
+    // written.
+    let verified = if request.status.starts_with("confirmed_") {
+        let ceremony = signature.as_ref().ok_or_else(|| {
+            AppError::validation("signature", "Confirming a strain requires an electronic signature — re-enter your password to sign.")
+        })?;
+        Some(verify_ceremony(&db, &user, ceremony, esignature::STRAIN_CONFIRMATION)?)
+    } else {
+        None
+    };
 
     let current_status: String = db
         .conn
@@ -340,15 +324,20 @@ pub fn update_strain_status(
             params![request.id],
             |r| r.get(0),
         )
-        .map_err(|_| "Strain not found".to_string())?;
+        .map_err(|_| AppError::not_found("strain", "Strain not found"))?;
 
     // WP-55: validation, the write-path guard against a masked
     // "[RESTRICTED]" genomic_fingerprint round-tripping back into the
     // database, and the actual UPDATE all live in
     // `queries::apply_strain_status_update` so they can be unit-tested
     // directly. See that function's doc comment for details.
+    let tx = db
+        .conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
     queries::apply_strain_status_update(
-        &db.conn,
+        &tx,
         &request.id,
         &current_status,
         &request.status,
@@ -359,7 +348,7 @@ pub fn update_strain_status(
     )?;
 
     queries::log_audit(
-        &db.conn,
+        &tx,
         Some(&user.id),
         "status_change",
         "strain",
@@ -370,6 +359,12 @@ pub fn update_strain_status(
     )
     .map_err(|e| format!("Failed to write audit entry: {}", e))?;
 
+    if let Some(verified) = verified {
+        esignature::record_signature(&tx, &user, verified, "strain", &request.id)?;
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to commit strain status: {}", e))?;
+
     drop(db);
     get_strain(state, token, request.id)
 }
@@ -379,12 +374,10 @@ pub fn create_hybridization_event(
     state: State<AppState>,
     token: String,
     request: CreateHybridizationEventRequest,
-) -> Result<HybridizationResult, String> {
+) -> Result<HybridizationResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::StrainEdit)?;
 
     // Load both parent strains.
     let parent_a = load_strain(&db.conn, &request.parent_a_id)?;
@@ -396,16 +389,10 @@ pub fn create_hybridization_event(
         // Guard: cross-species hybridization requires an explicit admin override.
         if !request.admin_override_cross_species.unwrap_or(false) {
             return Err(
-                "Cross-species hybridization is not permitted: parent strains must belong to the same species"
-                    .to_string(),
-            );
-        }
-        if !user.role.is_admin() {
-            return Err(
-                "Cross-species hybridization override requires administrator privileges"
-                    .to_string(),
+                AppError::validation("admin_override_cross_species", "Cross-species hybridization is not permitted: parent strains must belong to the same species"),
             );
         }
+        auth_service::require_capability(&db, &user, Capability::StrainCrossSpeciesOverride)?;
         let reason = request
             .admin_override_reason
             .as_deref()
@@ -414,7 +401,7 @@ pub fn create_hybridization_event(
             .to_string();
         if reason.is_empty() {
             return Err(
-                "Cross-species override requires a documented reason".to_string(),
+                AppError::validation("admin_override_reason", "Cross-species override requires a documented reason"),
             );
         }
     }
@@ -649,7 +636,7 @@ pub fn suggest_generation_label(
     token: String,
     parent_a_id: String,
     parent_b_id: String,
-) -> Result<SuggestGenerationLabelResponse, String> {
+) -> Result<SuggestGenerationLabelResponse, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     Ok(queries::suggest_generation_label_for_parents(
@@ -666,10 +653,10 @@ pub fn get_generational_stats(
     state: State<AppState>,
     token: String,
     strain_id: String,
-) -> Result<Vec<GenerationalStats>, String> {
+) -> Result<Vec<GenerationalStats>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    queries::get_generational_stats(&db.conn, &strain_id).map_err(|e| e.to_string())
+    queries::get_generational_stats(&db.conn, &strain_id).map_err(AppError::from)
 }
 
 // ── Pedigree commands (WP-37) ─────────────────────────────────────────────────
@@ -682,12 +669,12 @@ pub fn get_strain_ancestry(
     token: String,
     strain_id: String,
     max_depth: Option<u32>,
-) -> Result<PedigreeNode, String> {
+) -> Result<PedigreeNode, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let cap = queries::configured_pedigree_max_depth(&db.conn);
     let depth = max_depth.unwrap_or(5).min(cap);
-    queries::get_strain_ancestry(&db.conn, &strain_id, depth).map_err(|e| e.to_string())
+    queries::get_strain_ancestry(&db.conn, &strain_id, depth).map_err(AppError::from)
 }
 
 /// Return the full descendant tree of a strain, walking downward through
@@ -698,12 +685,12 @@ pub fn get_strain_descendants(
     token: String,
     strain_id: String,
     max_depth: Option<u32>,
-) -> Result<PedigreeNode, String> {
+) -> Result<PedigreeNode, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let cap = queries::configured_pedigree_max_depth(&db.conn);
     let depth = max_depth.unwrap_or(5).min(cap);
-    queries::get_strain_descendants(&db.conn, &strain_id, depth).map_err(|e| e.to_string())
+    queries::get_strain_descendants(&db.conn, &strain_id, depth).map_err(AppError::from)
 }
 
 /// Return all live specimens bound to a strain.  When `include_descendants` is
@@ -714,11 +701,11 @@ pub fn get_strain_specimen_tree(
     token: String,
     strain_id: String,
     include_descendants: bool,
-) -> Result<StrainSpecimenTree, String> {
+) -> Result<StrainSpecimenTree, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     queries::get_strain_specimen_tree(&db.conn, &strain_id, include_descendants)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// Export the full pedigree of a strain as a portable JSON bundle containing
@@ -729,17 +716,17 @@ pub fn export_strain_pedigree(
     token: String,
     strain_id: String,
     max_depth: Option<u32>,
-) -> Result<PedigreeExport, String> {
+) -> Result<PedigreeExport, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let cap = queries::configured_pedigree_max_depth(&db.conn);
     let depth = max_depth.unwrap_or(5).min(cap);
-    queries::export_strain_pedigree(&db.conn, &strain_id, depth).map_err(|e| e.to_string())
+    queries::export_strain_pedigree(&db.conn, &strain_id, depth).map_err(AppError::from)
 }
 
 /// Returns the lab's configured pedigree traversal depth cap (1–20, default 10).
 #[tauri::command]
-pub fn get_pedigree_max_depth(state: State<AppState>, token: String) -> Result<u32, String> {
+pub fn get_pedigree_max_depth(state: State<AppState>, token: String) -> Result<u32, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     Ok(queries::configured_pedigree_max_depth(&db.conn))
@@ -752,18 +739,20 @@ pub fn set_pedigree_max_depth(
     state: State<AppState>,
     token: String,
     max_depth: u32,
-) -> Result<u32, String> {
+) -> Result<u32, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can change the pedigree depth limit".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SystemSettings)?;
     let clamped = max_depth.clamp(1, 20);
     db.conn.execute(
         "INSERT INTO app_settings (key, value, updated_at) VALUES ('pedigree_max_depth', ?1, datetime('now')) \
          ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
         rusqlite::params![clamped.to_string()],
-    ).map_err(|e| e.to_string())?;
+    )?;
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "update", "app_settings", Some("pedigree_max_depth"),
+        None, Some(&clamped.to_string()), Some("Pedigree depth limit changed"),
+    ).ok();
     Ok(clamped)
 }
 
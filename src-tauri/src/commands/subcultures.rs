@@ -1,5 +1,8 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::custom_fields::{self, CustomFieldEntity, CustomValues};
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::specimen::PaginatedResponse;
 use crate::models::subculture::*;
 use crate::AppState;
@@ -15,12 +18,10 @@ pub fn record_specimen_death(
     state: State<AppState>,
     token: String,
     request: RecordSpecimenDeathRequest,
-) -> Result<Subculture, String> {
+) -> Result<Subculture, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SubcultureRecord)?;
     // A passage is a physical act on a specific culture. Recording one against
     // a specimen belonging to another lab would write real bench history onto a
     // culture this operator cannot even see.
@@ -30,10 +31,10 @@ pub fn record_specimen_death(
         "SELECT subculture_count, is_archived FROM specimens WHERE id = ?1",
         params![request.specimen_id],
         |r| Ok((r.get(0)?, r.get(1)?)),
-    ).map_err(|_| "Specimen not found".to_string())?;
+    ).map_err(|_| AppError::not_found("specimen", "Specimen not found"))?;
 
     if is_archived != 0 {
-        return Err("Specimen is already archived — cannot record a death event".to_string());
+        return Err(AppError::invalid("Specimen is already archived — cannot record a death event"));
     }
 
     let id = uuid::Uuid::new_v4().to_string();
@@ -115,7 +116,7 @@ pub fn record_specimen_death(
          WHERE sc.id = ?1",
         params![id],
         row_to_subculture,
-    ).map_err(|e| format!("Failed to fetch death event: {}", e))
+    ).map_err(|e| AppError::internal(format!("Failed to fetch death event: {}", e)))
 }
 
 // ── helper: map a DB row to a Subculture ────────────────────────────────────
@@ -164,6 +165,8 @@ fn row_to_subculture(row: &rusqlite::Row) -> rusqlite::Result<Subculture> {
         doubling_time_hours: row.get("doubling_time_hours").unwrap_or(None),
         colonization_pct: row.get("colonization_pct").unwrap_or(None),
         contaminant_type: row.get("contaminant_type").unwrap_or(None),
+        custom_fields: custom_fields::from_column(row.get::<_, Option<String>>("custom_fields").unwrap_or(None).as_deref()),
+        hood: row.get("hood").unwrap_or(None),
     })
 }
 
@@ -174,7 +177,7 @@ pub fn list_subcultures(
     specimen_id: String,
     page: Option<u32>,
     per_page: Option<u32>,
-) -> Result<PaginatedResponse<Subculture>, String> {
+) -> Result<PaginatedResponse<Subculture>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -187,7 +190,7 @@ pub fn list_subcultures(
         "SELECT COUNT(*) FROM subcultures WHERE specimen_id = ?1",
         params![specimen_id],
         |r| r.get(0),
-    ).map_err(|e| e.to_string())?;
+    )?;
 
     let mut stmt = db.conn.prepare(
         "SELECT sc.*, u.display_name as performer_name, mb.name as media_batch_name
@@ -197,10 +200,9 @@ pub fn list_subcultures(
          WHERE sc.specimen_id = ?1
          ORDER BY sc.passage_number DESC
          LIMIT ?2 OFFSET ?3"
-    ).map_err(|e| e.to_string())?;
+    )?;
 
-    let subcultures = stmt.query_map(params![specimen_id, pg.limit(), pg.offset()], row_to_subculture)
-        .map_err(|e| e.to_string())?
+    let subcultures = stmt.query_map(params![specimen_id, pg.limit(), pg.offset()], row_to_subculture)?
         .filter_map(|r| r.ok())
         .collect::<Vec<_>>();
 
@@ -220,12 +222,10 @@ pub fn create_subculture(
     state: State<AppState>,
     token: String,
     request: CreateSubcultureRequest,
-) -> Result<Subculture, String> {
+) -> Result<Subculture, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SubcultureRecord)?;
     // A passage is a physical act on a specific culture. Recording one against
     // a specimen belonging to another lab would write real bench history onto a
     // culture this operator cannot even see.
@@ -235,15 +235,23 @@ pub fn create_subculture(
         "SELECT subculture_count, is_archived FROM specimens WHERE id = ?1",
         params![request.specimen_id],
         |r| Ok((r.get(0)?, r.get(1)?)),
-    ).map_err(|_| "Specimen not found".to_string())?;
+    ).map_err(|_| AppError::not_found("specimen", "Specimen not found"))?;
 
     if is_archived != 0 {
-        return Err("Cannot record a passage on an archived specimen".to_string());
+        return Err(AppError::invalid("Cannot record a passage on an archived specimen"));
     }
 
     let passage_number = current_count + 1;
     let id = uuid::Uuid::new_v4().to_string();
     let contamination_flag = request.contamination_flag.unwrap_or(false) as i32;
+    let custom = custom_fields::apply(
+        &db.conn,
+        &crate::db::vocabulary::active_profile(&db.conn),
+        CustomFieldEntity::Subculture,
+        &CustomValues::new(),
+        &request.custom_fields,
+        true,
+    )?;
 
     // ── WP-31: compute PDL gained and doubling time ──────────────────────────
     // Fetch the previous passage date to calculate elapsed hours for doubling time.
@@ -279,6 +287,14 @@ pub fn create_subculture(
         _ => None,
     };
 
+    // WP-102: a passage on a specimen in a running trial carries its treatment
+    // unless the form names one.
+    let experimental_treatment = match &request.experimental_treatment {
+        Some(t) if !t.trim().is_empty() => Some(t.clone()),
+        _ => crate::db::trials::treatment_label_for(&db.conn, &request.specimen_id)?
+            .or_else(|| request.experimental_treatment.clone()),
+    };
+
     let tx = db.conn.unchecked_transaction()
         .map_err(|e| format!("Failed to begin transaction: {}", e))?;
 
@@ -291,12 +307,12 @@ pub fn create_subculture(
          exposure_duration_hours, notes, observations, performed_by, employee_id,
          health_status, contamination_flag, contamination_notes,
          seed_cell_count, harvest_cell_count, split_ratio, pdl_gained, doubling_time_hours,
-         colonization_pct, contaminant_type)
-         VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17,?18,?19,?20,?21,?22,?23,?24,?25,?26,?27,?28,?29,?30,?31,?32,?33,?34,?35,?36,?37)",
+         colonization_pct, contaminant_type, custom_fields, hood)
+         VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17,?18,?19,?20,?21,?22,?23,?24,?25,?26,?27,?28,?29,?30,?31,?32,?33,?34,?35,?36,?37,?38,?39)",
         params![
             id, request.specimen_id, passage_number, request.date, request.media_batch_id,
             request.ph, request.temperature_c, request.light_cycle, request.light_intensity_lux,
-            request.experimental_treatment, request.vessel_type, request.vessel_size,
+            experimental_treatment, request.vessel_type, request.vessel_size,
             request.vessel_material, request.vessel_lid_type, request.location_from,
             request.location_to, request.temp_before, request.temp_after,
             request.humidity_before, request.humidity_after, request.light_before,
@@ -306,6 +322,8 @@ pub fn create_subculture(
             request.seed_cell_count, request.harvest_cell_count, request.split_ratio,
             pdl_gained, doubling_time_hours,
             request.colonization_pct, request.contaminant_type,
+            custom_fields::to_column(&custom),
+            request.hood.as_deref().map(str::trim).filter(|h| !h.is_empty()),
         ],
     ).map_err(|e| format!("Failed to create subculture: {}", e))?;
 
@@ -324,10 +342,15 @@ pub fn create_subculture(
         params![passage_number, request.location_to, request.health_status, request.specimen_id, pdl_gained],
     ).map_err(|e| format!("Failed to update specimen after passage: {}", e))?;
 
-    // Audit passage on the SPECIMEN's chain so chain_seq increments for the specimen
+    // Audit passage on the SPECIMEN's chain so chain_seq increments for the specimen.
+    // WP-96: the passage's custom values go in the hashed details.
+    let details = match custom_fields::audit_note(&custom) {
+        Some(note) => format!("Passage #{} recorded; {}", passage_number, note),
+        None => format!("Passage #{} recorded", passage_number),
+    };
     queries::log_audit(
         &tx,Some(&user.id), "subcultured", "specimen", Some(&request.specimen_id),
-        None, None, Some(&format!("Passage #{} recorded", passage_number)),
+        None, None, Some(&details),
     ).map_err(|e| format!("Failed to write passage audit: {}", e))?;
 
     tx.commit().map_err(|e| format!("Failed to commit subculture transaction: {}", e))?;
@@ -350,7 +373,7 @@ pub fn create_subculture(
          WHERE sc.id = ?1",
         params![id],
         row_to_subculture,
-    ).map_err(|e| format!("Failed to fetch created subculture: {}", e))
+    ).map_err(|e| AppError::internal(format!("Failed to fetch created subculture: {}", e)))
 }
 
 #[tauri::command]
@@ -358,12 +381,10 @@ pub fn update_subculture(
     state: State<AppState>,
     token: String,
     request: UpdateSubcultureRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_write() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SubcultureRecord)?;
 
     let mut updates = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -400,9 +421,25 @@ pub fn update_subculture(
         updates.push(format!("contaminant_type = ?{}", values.len() + 1));
         values.push(Box::new(ct.clone()));
     }
+    // WP-96: checked against the fields of the lab the passage's specimen
+    // belongs to.
+    let mut custom_change = None;
+    if let Some(ref patch) = request.custom_fields {
+        let (stored, profile): (Option<String>, String) = db.conn.query_row(
+            "SELECT sc.custom_fields, s.lab_profile FROM subcultures sc \
+             JOIN specimens s ON s.id = sc.specimen_id WHERE sc.id = ?1",
+            params![request.id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        ).map_err(|_| AppError::not_found("subculture", "Subculture not found").with_id(&request.id))?;
+        let before = custom_fields::from_column(stored.as_deref());
+        let after = custom_fields::apply(&db.conn, &profile, CustomFieldEntity::Subculture, &before, patch, false)?;
+        updates.push(format!("custom_fields = ?{}", values.len() + 1));
+        values.push(Box::new(custom_fields::to_column(&after)));
+        custom_change = Some((before, after));
+    }
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
 
     updates.push("updated_at = datetime('now')".to_string());
@@ -417,10 +454,17 @@ pub fn update_subculture(
     db.conn.execute(&sql, bind_refs.as_slice())
         .map_err(|e| format!("Failed to update subculture: {}", e))?;
 
-    queries::log_audit(
-        &db.conn, Some(&user.id), "update", "subculture", Some(&request.id),
-        None, None, Some("Subculture updated"),
-    ).ok();
+    match custom_change {
+        Some((before, after)) => queries::log_audit(
+            &db.conn, Some(&user.id), "update", "subculture", Some(&request.id),
+            custom_fields::to_column(&before).as_deref(), custom_fields::to_column(&after).as_deref(),
+            Some(&format!("Subculture updated; {}", custom_fields::audit_note(&after).unwrap_or_else(|| "custom fields {}".into()))),
+        ),
+        None => queries::log_audit(
+            &db.conn, Some(&user.id), "update", "subculture", Some(&request.id),
+            None, None, Some("Subculture updated"),
+        ),
+    }.ok();
     crate::db::dashboard::invalidate_dashboard_cache(&state.dashboard_cache);
 
     Ok(())
@@ -432,7 +476,7 @@ pub fn update_subculture(
 pub fn list_all_subcultures(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<Subculture>, String> {
+) -> Result<Vec<Subculture>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -448,10 +492,9 @@ pub fn list_all_subcultures(
          LEFT JOIN users u ON sc.performed_by = u.id
          LEFT JOIN media_batches mb ON sc.media_batch_id = mb.id
          ORDER BY sc.date DESC, sc.passage_number DESC"
-    ).map_err(|e| e.to_string())?;
+    )?;
 
-    let subcultures = stmt.query_map([], row_to_subculture)
-        .map_err(|e| e.to_string())?
+    let subcultures = stmt.query_map([], row_to_subculture)?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -465,7 +508,7 @@ pub fn get_colonization_history(
     state: State<AppState>,
     token: String,
     specimen_id: String,
-) -> Result<Vec<ColonizationEntry>, String> {
+) -> Result<Vec<ColonizationEntry>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let mut stmt = db.conn.prepare(
@@ -473,7 +516,7 @@ pub fn get_colonization_history(
          FROM subcultures
          WHERE specimen_id = ?1 AND colonization_pct IS NOT NULL
          ORDER BY date ASC, passage_number ASC",
-    ).map_err(|e| e.to_string())?;
+    )?;
     let rows = stmt.query_map(params![specimen_id], |row| {
         Ok(ColonizationEntry {
             subculture_id: row.get("id")?,
@@ -482,7 +525,7 @@ pub fn get_colonization_history(
             passage_number: row.get("passage_number")?,
             notes: row.get("notes")?,
         })
-    }).map_err(|e| e.to_string())?;
+    })?;
     Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
@@ -492,7 +535,7 @@ pub fn get_colonization_history(
 pub fn get_contamination_stats(
     state: State<AppState>,
     token: String,
-) -> Result<ContaminationStats, String> {
+) -> Result<ContaminationStats, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -513,7 +556,7 @@ pub fn get_contamination_stats(
 pub fn get_subculture_schedule(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<SubcultureScheduleEntry>, String> {
+) -> Result<Vec<SubcultureScheduleEntry>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -525,7 +568,7 @@ pub fn get_subculture_schedule(
 pub fn get_culture_maintenance_alerts(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<CultureMaintenanceAlert>, String> {
+) -> Result<Vec<CultureMaintenanceAlert>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
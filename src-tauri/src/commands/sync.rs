@@ -9,7 +9,9 @@
 //! etc. — see `ApplyChangesResult::pending_manual_apply` doc comment.
 
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::sync as sync_queries;
+use crate::error::AppError;
 use crate::models::sync::{
     ApplyChangesRequest, ApplyChangesResult, ChangeSetResponse, SyncConflict, SyncCursor,
     SyncPeer, SyncStatusResponse,
@@ -21,10 +23,10 @@ const DEFAULT_CHANGE_LIMIT: i64 = 500;
 
 /// Any authenticated user may see a summary of sync state.
 #[tauri::command]
-pub fn get_sync_status(state: State<AppState>, token: String) -> Result<SyncStatusResponse, String> {
+pub fn get_sync_status(state: State<AppState>, token: String) -> Result<SyncStatusResponse, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    sync_queries::get_sync_status(&db.conn).map_err(|e| e.to_string())
+    sync_queries::get_sync_status(&db.conn).map_err(AppError::from)
 }
 
 /// Supervisor+: returns audit-chain entries newer than the supplied cursors.
@@ -36,17 +38,14 @@ pub fn get_changes_since_cursor(
     token: String,
     cursors: Vec<SyncCursor>,
     limit: Option<i64>,
-) -> Result<ChangeSetResponse, String> {
+) -> Result<ChangeSetResponse, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SyncView)?;
 
     let limit = limit.unwrap_or(DEFAULT_CHANGE_LIMIT).clamp(1, 5000);
     // Ask for one extra row to cheaply detect whether more remain beyond this page.
-    let mut changes = sync_queries::get_changes_since(&db.conn, &cursors, limit + 1)
-        .map_err(|e| e.to_string())?;
+    let mut changes = sync_queries::get_changes_since(&db.conn, &cursors, limit + 1)?;
     let has_more = changes.len() as i64 > limit;
     changes.truncate(limit as usize);
 
@@ -66,23 +65,20 @@ pub fn apply_incoming_changes(
     state: State<AppState>,
     token: String,
     request: ApplyChangesRequest,
-) -> Result<ApplyChangesResult, String> {
+) -> Result<ApplyChangesResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can submit incoming sync changes".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SyncManage)?;
 
     let detection = sync_queries::detect_sync_conflicts(
         &db.conn,
         &request.changes,
         &request.source_device_id,
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     let mut recorded_conflicts = Vec::with_capacity(detection.conflicts.len());
     for conflict in &detection.conflicts {
-        sync_queries::record_sync_conflict(&db.conn, conflict).map_err(|e| e.to_string())?;
+        sync_queries::record_sync_conflict(&db.conn, conflict)?;
         recorded_conflicts.push(conflict.clone());
     }
 
@@ -121,14 +117,12 @@ pub fn list_sync_conflicts(
     state: State<AppState>,
     token: String,
     unresolved_only: Option<bool>,
-) -> Result<Vec<SyncConflict>, String> {
+) -> Result<Vec<SyncConflict>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SyncView)?;
     sync_queries::list_sync_conflicts(&db.conn, unresolved_only.unwrap_or(false))
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// Admin-only. Administratively closes a conflict record. Does not reconcile
@@ -140,17 +134,15 @@ pub fn resolve_sync_conflict(
     token: String,
     conflict_id: String,
     resolution_note: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can resolve sync conflicts".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SyncManage)?;
     if resolution_note.trim().is_empty() {
-        return Err("A resolution note is required".to_string());
+        return Err(AppError::validation("resolution_note", "A resolution note is required"));
     }
 
-    sync_queries::resolve_sync_conflict(&db.conn, &conflict_id, &user.id).map_err(|e| e.to_string())?;
+    sync_queries::resolve_sync_conflict(&db.conn, &conflict_id, &user.id)?;
 
     crate::db::queries::log_audit(
         &db.conn,
@@ -175,25 +167,27 @@ pub fn register_sync_peer(
     token: String,
     device_id: String,
     device_name: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can register sync peers".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::SyncManage)?;
     if device_id.trim().is_empty() || device_name.trim().is_empty() {
-        return Err("Both device_id and device_name are required".to_string());
+        return Err(AppError::invalid("Both device_id and device_name are required"));
     }
-    sync_queries::register_sync_peer(&db.conn, &device_id, &device_name).map_err(|e| e.to_string())
+    let peer_id = sync_queries::register_sync_peer(&db.conn, &device_id, &device_name)?;
+    crate::db::queries::log_audit(
+        &db.conn, Some(&user.id), "register", "sync_peer", Some(&peer_id),
+        None, Some(&device_id), Some(&format!("Registered trusted sync peer '{}'", device_name)),
+    )
+    .ok();
+    Ok(peer_id)
 }
 
 /// Supervisor+: lists known sync peers.
 #[tauri::command]
-pub fn list_sync_peers(state: State<AppState>, token: String) -> Result<Vec<SyncPeer>, String> {
+pub fn list_sync_peers(state: State<AppState>, token: String) -> Result<Vec<SyncPeer>, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Insufficient permissions".to_string());
-    }
-    sync_queries::list_sync_peers(&db.conn).map_err(|e| e.to_string())
+    auth_service::require_capability(&db, &user, Capability::SyncView)?;
+    sync_queries::list_sync_peers(&db.conn).map_err(AppError::from)
 }
@@ -1,5 +1,7 @@
 use crate::auth as auth_service;
+use crate::auth::roles::Capability;
 use crate::db::queries;
+use crate::error::AppError;
 use crate::models::taxon::{
     CreateProvisionalTaxonRequest, CreateTaxonMappingRequest, CreateTaxonRequest, DarwinCoreExport,
     SpeciesNodeSummary, Taxon, TaxonColumnItem, TaxonMapping, TaxonNode, TaxonomySearchResult,
@@ -9,11 +11,9 @@ use crate::AppState;
 use rusqlite::params;
 use tauri::State;
 
-fn build_taxon_node(conn: &rusqlite::Connection, taxon: Taxon) -> Result<TaxonNode, String> {
-    let species = queries::get_species_for_taxon(conn, &taxon.id)
-        .map_err(|e| e.to_string())?;
-    let children_taxa = queries::get_child_taxa(conn, &taxon.id)
-        .map_err(|e| e.to_string())?;
+fn build_taxon_node(conn: &rusqlite::Connection, taxon: Taxon) -> Result<TaxonNode, AppError> {
+    let species = queries::get_species_for_taxon(conn, &taxon.id)?;
+    let children_taxa = queries::get_child_taxa(conn, &taxon.id)?;
 
     let own_strain_count: i64 = species.iter().map(|s| s.strain_count).sum();
     let own_specimen_count: i64 = species.iter().map(|s| s.specimen_count).sum();
@@ -43,20 +43,18 @@ pub fn create_taxon(
     state: State<AppState>,
     token: String,
     request: CreateTaxonRequest,
-) -> Result<Taxon, String> {
+) -> Result<Taxon, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can manage taxonomy".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::TaxonomyManage)?;
 
     let valid_ranks = ["kingdom", "phylum", "class", "order", "family", "genus"];
     if !valid_ranks.contains(&request.rank.as_str()) {
-        return Err(format!(
+        return Err(AppError::validation("rank", format!(
             "Invalid rank '{}'. Must be one of: {}",
             request.rank,
             valid_ranks.join(", ")
-        ));
+        )));
     }
 
     let id = uuid::Uuid::new_v4().to_string();
@@ -70,14 +68,14 @@ pub fn create_taxon(
                 params![parent_id],
                 |r| r.get(0),
             )
-            .map_err(|e| format!("Parent taxon not found: {}", e))?;
+            .map_err(|_| AppError::not_found("taxon", "Parent taxon not found").with_id(parent_id))?;
 
         let mut arr: Vec<String> = parent_path
             .as_deref()
             .and_then(|p| serde_json::from_str::<Vec<String>>(p).ok())
             .unwrap_or_default();
         arr.push(id.clone());
-        serde_json::to_string(&arr).map_err(|e| e.to_string())?
+        serde_json::to_string(&arr)?
     } else {
         format!("[\"{}\"]", id)
     };
@@ -117,14 +115,14 @@ pub fn create_taxon(
     )
     .ok();
 
-    queries::load_taxon(&db.conn, &id).map_err(|e| e.to_string())
+    queries::load_taxon(&db.conn, &id).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn get_taxon(state: State<AppState>, token: String, id: String) -> Result<Taxon, String> {
+pub fn get_taxon(state: State<AppState>, token: String, id: String) -> Result<Taxon, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    queries::load_taxon(&db.conn, &id).map_err(|e| e.to_string())
+    queries::load_taxon(&db.conn, &id).map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -132,12 +130,10 @@ pub fn update_taxon(
     state: State<AppState>,
     token: String,
     request: UpdateTaxonRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can manage taxonomy".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::TaxonomyManage)?;
 
     let mut updates: Vec<String> = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -164,7 +160,7 @@ pub fn update_taxon(
     }
 
     if updates.is_empty() {
-        return Err("No fields to update".to_string());
+        return Err(AppError::invalid("No fields to update"));
     }
 
     updates.push("updated_at = datetime('now')".to_string());
@@ -207,7 +203,7 @@ pub fn list_taxa_by_rank(
     state: State<AppState>,
     token: String,
     rank: String,
-) -> Result<Vec<Taxon>, String> {
+) -> Result<Vec<Taxon>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
@@ -217,8 +213,7 @@ pub fn list_taxa_by_rank(
             "SELECT id, rank, name, parent_id, ncbi_taxon_id, ncbi_updated_at,
                     local_override, taxon_path, created_at, updated_at
              FROM taxa WHERE rank = ?1 ORDER BY name",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let rows = stmt
         .query_map(params![rank], |row| {
@@ -234,11 +229,10 @@ pub fn list_taxa_by_rank(
                 created_at: row.get("created_at")?,
                 updated_at: row.get("updated_at")?,
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
 
     let items: Result<Vec<_>, _> = rows.collect();
-    items.map_err(|e| e.to_string())
+    items.map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -246,11 +240,11 @@ pub fn get_taxon_descendants(
     state: State<AppState>,
     token: String,
     id: String,
-) -> Result<TaxonNode, String> {
+) -> Result<TaxonNode, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
 
-    let taxon = queries::load_taxon(&db.conn, &id).map_err(|e| e.to_string())?;
+    let taxon = queries::load_taxon(&db.conn, &id)?;
     build_taxon_node(&db.conn, taxon)
 }
 
@@ -261,11 +255,11 @@ pub fn get_taxon_column(
     state: State<AppState>,
     token: String,
     parent_id: Option<String>,
-) -> Result<Vec<TaxonColumnItem>, String> {
+) -> Result<Vec<TaxonColumnItem>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     queries::get_taxon_column_items(&db.conn, parent_id.as_deref())
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// WP-39: Return species whose most-specific ancestor is the given taxon.
@@ -277,10 +271,10 @@ pub fn list_species_for_taxon(
     state: State<AppState>,
     token: String,
     taxon_id: String,
-) -> Result<Vec<SpeciesNodeSummary>, String> {
+) -> Result<Vec<SpeciesNodeSummary>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    queries::get_species_for_taxon(&db.conn, &taxon_id).map_err(|e| e.to_string())
+    queries::get_species_for_taxon(&db.conn, &taxon_id).map_err(AppError::from)
 }
 
 /// WP-39: Search taxa, species, strains, and specimens by name / code /
@@ -291,13 +285,13 @@ pub fn search_taxonomy(
     state: State<AppState>,
     token: String,
     query: String,
-) -> Result<Vec<TaxonomySearchResult>, String> {
+) -> Result<Vec<TaxonomySearchResult>, AppError> {
     if query.len() < 2 {
         return Ok(vec![]);
     }
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    queries::search_taxonomy(&db.conn, &query).map_err(|e| e.to_string())
+    queries::search_taxonomy(&db.conn, &query).map_err(AppError::from)
 }
 
 // ── WP-49: Provisional taxa & Darwin Core export ──────────────────────────────
@@ -308,12 +302,10 @@ pub fn create_provisional_taxon(
     state: State<AppState>,
     token: String,
     request: CreateProvisionalTaxonRequest,
-) -> Result<Taxon, String> {
+) -> Result<Taxon, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can create provisional taxa".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::TaxonomyManage)?;
     let id = uuid::Uuid::new_v4().to_string();
     queries::create_provisional_taxon(
         &db.conn,
@@ -324,7 +316,7 @@ pub fn create_provisional_taxon(
         request.provisional_notes.as_deref(),
         Some(&user.id),
     )
-    .map_err(|e| e.to_string())
+    .map_err(AppError::from)
 }
 
 /// List all provisional taxa.
@@ -332,10 +324,10 @@ pub fn create_provisional_taxon(
 pub fn list_provisional_taxa(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<Taxon>, String> {
+) -> Result<Vec<Taxon>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    queries::list_provisional_taxa(&db.conn).map_err(|e| e.to_string())
+    queries::list_provisional_taxa(&db.conn).map_err(AppError::from)
 }
 
 /// Map a provisional taxon to an accepted NCBI taxon.  Requires supervisor or admin role.
@@ -344,14 +336,12 @@ pub fn map_provisional_taxon(
     state: State<AppState>,
     token: String,
     request: CreateTaxonMappingRequest,
-) -> Result<TaxonMapping, String> {
+) -> Result<TaxonMapping, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can map provisional taxa".to_string());
-    }
+    auth_service::require_capability(&db, &user, Capability::TaxonomyManage)?;
     let id = uuid::Uuid::new_v4().to_string();
-    queries::create_taxon_mapping(
+    let mapping = queries::create_taxon_mapping(
         &db.conn,
         &id,
         &request.provisional_taxon_id,
@@ -360,8 +350,14 @@ pub fn map_provisional_taxon(
         request.accepted_name.as_deref(),
         request.notes.as_deref(),
         Some(&user.id),
+    )?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "map", "taxon", Some(&request.provisional_taxon_id),
+        None, request.accepted_taxon_id.as_deref().or(request.accepted_name.as_deref()),
+        Some("Provisional taxon mapped to an accepted taxon"),
     )
-    .map_err(|e| e.to_string())
+    .ok();
+    Ok(mapping)
 }
 
 /// List all taxon mappings (provisional → accepted).
@@ -369,10 +365,10 @@ pub fn map_provisional_taxon(
 pub fn list_taxon_mappings(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<TaxonMapping>, String> {
+) -> Result<Vec<TaxonMapping>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    queries::list_taxon_mappings(&db.conn).map_err(|e| e.to_string())
+    queries::list_taxon_mappings(&db.conn).map_err(AppError::from)
 }
 
 /// Export a taxonomy subtree (or the full taxonomy) as Darwin Core JSON.
@@ -382,10 +378,10 @@ pub fn export_darwin_core(
     state: State<AppState>,
     token: String,
     root_id: Option<String>,
-) -> Result<DarwinCoreExport, String> {
+) -> Result<DarwinCoreExport, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
-    queries::export_darwin_core(&db.conn, root_id.as_deref()).map_err(|e| e.to_string())
+    queries::export_darwin_core(&db.conn, root_id.as_deref()).map_err(AppError::from)
 }
 
 /// WP-64: read-only pre-flight report for `reanchor_taxon_chain` — exactly
@@ -395,13 +391,11 @@ pub fn reanchor_taxon_chain_dry_run(
     state: State<AppState>,
     token: String,
     taxon_id: String,
-) -> Result<queries::ReanchorCounts, String> {
+) -> Result<queries::ReanchorCounts, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.can_manage() {
-        return Err("Only supervisors and admins can preview a taxon re-anchor".to_string());
-    }
-    queries::reanchor_taxon_chain_dry_run(&db.conn, &taxon_id).map_err(|e| e.to_string())
+    auth_service::require_capability(&db, &user, Capability::TaxonomyManage)?;
+    queries::reanchor_taxon_chain_dry_run(&db.conn, &taxon_id).map_err(AppError::from)
 }
 
 /// WP-64: atomically re-anchors the hash chain for `taxon_id` and every
@@ -414,11 +408,9 @@ pub fn reanchor_taxon_chain(
     token: String,
     taxon_id: String,
     reason: String,
-) -> Result<queries::ReanchorResult, String> {
+) -> Result<queries::ReanchorResult, AppError> {
     let db = state.db();
     let user = auth_service::validate_session(&db, &token)?;
-    if !user.role.is_admin() {
-        return Err("Only admins can re-anchor a taxon's hash chain".to_string());
-    }
-    queries::reanchor_taxon_chain(&db.conn, &taxon_id, &user.id, &reason).map_err(|e| e.to_string())
+    auth_service::require_capability(&db, &user, Capability::TaxonomyReanchor)?;
+    queries::reanchor_taxon_chain(&db.conn, &taxon_id, &user.id, &reason).map_err(AppError::from)
 }
@@ -0,0 +1,163 @@
+// WP-102: treatment trials in the active lab. Anyone signed in can read trials
+// and their analysis; designing, assigning and opening or closing them needs
+// `trial.manage`, and recording outcomes needs `subculture.record`, as it is
+// done at the bench alongside passages.
+use crate::auth as auth_service;
+use crate::auth::roles::Capability;
+use crate::db::queries;
+use crate::db::trials::{self, ObservationInput, SaveTrialRequest, Trial, TrialAnalysis, TrialDetail, TrialStatus, TrialUnit};
+use crate::error::AppError;
+use crate::AppState;
+use serde::Serialize;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_trials(state: State<AppState>, token: String) -> Result<Vec<Trial>, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    trials::list(&db.conn, &profile)
+}
+
+/// A trial with its assigned specimens and every observation.
+#[tauri::command]
+pub fn get_trial(state: State<AppState>, token: String, id: String) -> Result<TrialDetail, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    trials::detail(&db.conn, &profile, &id)
+}
+
+#[derive(Debug, Serialize)]
+pub struct SavedTrial {
+    pub trial: Trial,
+    /// A redesign cleared the draft's assignment.
+    pub assignment_cleared: bool,
+}
+
+/// Create a trial, or edit a draft one.
+#[tauri::command]
+pub fn save_trial(state: State<AppState>, token: String, request: SaveTrialRequest) -> Result<SavedTrial, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::TrialManage)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let tx = db.conn.unchecked_transaction()?;
+    let (trial, assignment_cleared) = trials::save(&tx, &user.id, &profile, &request)?;
+    tx.commit()?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), if request.id.is_some() { "update" } else { "create" }, "trial", Some(&trial.id),
+        None, serde_json::to_string(&request).ok().as_deref(),
+        Some(&format!(
+            "Trial {}: {} treatments × {} replicates{}",
+            trial.name, trial.treatments.len(), trial.replicates,
+            if assignment_cleared { "; assignment cleared" } else { "" },
+        )),
+    ).ok();
+    Ok(SavedTrial { trial, assignment_cleared })
+}
+
+/// Delete a draft trial.
+#[tauri::command]
+pub fn delete_trial(state: State<AppState>, token: String, id: String) -> Result<(), AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::TrialManage)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let tx = db.conn.unchecked_transaction()?;
+    let trial = trials::delete(&tx, &profile, &id)?;
+    tx.commit()?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "delete", "trial", Some(&id),
+        Some(&trial.name), None, Some(&format!("Draft trial {} deleted", trial.name)),
+    ).ok();
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrialAssignment {
+    pub units: Vec<TrialUnit>,
+    /// Assigning the same specimens with this seed reproduces the layout.
+    pub seed: u32,
+}
+
+/// Randomly assign specimens to a draft's treatments, replacing any earlier
+/// assignment. A seed reproduces an earlier layout; without one a new seed
+/// is drawn.
+#[tauri::command]
+pub fn assign_trial_specimens(
+    state: State<AppState>,
+    token: String,
+    id: String,
+    specimen_ids: Vec<String>,
+    seed: Option<u32>,
+) -> Result<TrialAssignment, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::TrialManage)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let tx = db.conn.unchecked_transaction()?;
+    let (units, seed) = trials::assign(&tx, &profile, &id, &specimen_ids, seed)?;
+    tx.commit()?;
+    queries::log_audit(
+        &db.conn, Some(&user.id), "assign", "trial", Some(&id),
+        None, serde_json::to_string(&specimen_ids).ok().as_deref(),
+        Some(&format!("{} specimens randomly assigned with seed {}", units.len(), seed)),
+    ).ok();
+    Ok(TrialAssignment { units, seed })
+}
+
+/// Start, close or reopen a trial.
+#[tauri::command]
+pub fn set_trial_status(state: State<AppState>, token: String, id: String, status: TrialStatus) -> Result<Trial, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::TrialManage)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let (trial, from) = trials::set_status(&db.conn, &profile, &id, status)?;
+    queries::log_audit(
+        &db.conn, Some(&user.id),
+        match (from, status) {
+            (TrialStatus::Draft, _) => "start",
+            (_, TrialStatus::Closed) => "close",
+            _ => "reopen",
+        },
+        "trial", Some(&id),
+        Some(from.as_str()), Some(status.as_str()), Some(&format!("Trial {} is now {}", trial.name, status.as_str())),
+    ).ok();
+    Ok(trial)
+}
+
+/// Record outcome values for specimens of a running trial, each at the
+/// specimen's current passage. Returns how many were recorded.
+#[tauri::command]
+pub fn record_trial_observations(
+    state: State<AppState>,
+    token: String,
+    id: String,
+    observations: Vec<ObservationInput>,
+) -> Result<usize, AppError> {
+    let db = state.db();
+    let user = auth_service::validate_session(&db, &token)?;
+    auth_service::require_capability(&db, &user, Capability::SubcultureRecord)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    let tx = db.conn.unchecked_transaction()?;
+    let count = trials::record(&tx, &user.id, &profile, &id, &observations)?;
+    tx.commit()?;
+    let specimens: std::collections::BTreeSet<&str> = observations.iter().map(|o| o.specimen_id.as_str()).collect();
+    queries::log_audit(
+        &db.conn, Some(&user.id), "record", "trial", Some(&id),
+        None, None, Some(&format!("{} values recorded for {} specimens", count, specimens.len())),
+    ).ok();
+    Ok(count)
+}
+
+/// Summaries, ANOVA and Tukey HSD per outcome, over each specimen's value at
+/// `passage` or, without one, its latest.
+#[tauri::command]
+pub fn get_trial_analysis(state: State<AppState>, token: String, id: String, passage: Option<i64>) -> Result<TrialAnalysis, AppError> {
+    let db = state.db();
+    auth_service::validate_session(&db, &token)?;
+    let profile = crate::db::vocabulary::active_profile(&db.conn);
+    trials::analyse(&db.conn, &profile, &id, passage)
+}
@@ -1,4 +1,5 @@
 use crate::auth as auth_service;
+use crate::error::AppError;
 use crate::AppState;
 use serde::{Deserialize, Serialize};
 use tauri::State;
@@ -24,8 +25,8 @@ fn query_vocab(
     conn: &rusqlite::Connection,
     sql: &str,
     profile: &str,
-) -> Result<Vec<VocabEntry>, String> {
-    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+) -> Result<Vec<VocabEntry>, AppError> {
+    let mut stmt = conn.prepare(sql)?;
     let result = stmt
         .query_map([profile], |row| {
             Ok(VocabEntry {
@@ -34,18 +35,16 @@ fn query_vocab(
                 label: row.get(2)?,
                 sort_order: row.get(3)?,
             })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string());
-    result
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(result)
 }
 
 #[tauri::command]
 pub fn list_stages(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<StageEntry>, String> {
+) -> Result<Vec<StageEntry>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -55,8 +54,7 @@ pub fn list_stages(
         .prepare(
             "SELECT id, code, label, sort_order, is_terminal \
              FROM stages WHERE profile = ?1 ORDER BY sort_order",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let result = stmt
         .query_map([&profile], |row| {
@@ -67,18 +65,16 @@ pub fn list_stages(
                 sort_order: row.get(3)?,
                 is_terminal: row.get::<_, i64>(4)? != 0,
             })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string());
-    result
+        })?
+        .collect::<Result<Vec<_>, _>>();
+    Ok(result?)
 }
 
 #[tauri::command]
 pub fn list_propagation_methods(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<VocabEntry>, String> {
+) -> Result<Vec<VocabEntry>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -87,14 +83,13 @@ pub fn list_propagation_methods(
         "SELECT id, code, label, sort_order \
          FROM propagation_methods WHERE profile = ?1 ORDER BY sort_order",
         &profile,
-    )
-}
+    )}
 
 #[tauri::command]
 pub fn list_hormone_types(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<VocabEntry>, String> {
+) -> Result<Vec<VocabEntry>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -103,14 +98,13 @@ pub fn list_hormone_types(
         "SELECT id, code, label, sort_order \
          FROM hormone_types WHERE profile = ?1 ORDER BY sort_order",
         &profile,
-    )
-}
+    )}
 
 #[tauri::command]
 pub fn list_compliance_record_types(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<VocabEntry>, String> {
+) -> Result<Vec<VocabEntry>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -119,14 +113,13 @@ pub fn list_compliance_record_types(
         "SELECT id, code, label, sort_order \
          FROM compliance_record_types WHERE profile = ?1 ORDER BY sort_order",
         &profile,
-    )
-}
+    )}
 
 #[tauri::command]
 pub fn list_compliance_agencies(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<VocabEntry>, String> {
+) -> Result<Vec<VocabEntry>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -135,14 +128,13 @@ pub fn list_compliance_agencies(
         "SELECT id, code, label, sort_order \
          FROM compliance_agencies WHERE profile = ?1 ORDER BY sort_order",
         &profile,
-    )
-}
+    )}
 
 #[tauri::command]
 pub fn list_inventory_categories(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<VocabEntry>, String> {
+) -> Result<Vec<VocabEntry>, AppError> {
     let db = state.db();
     let _user = auth_service::validate_session(&db, &token)?;
     let profile = crate::db::vocabulary::active_profile(&db.conn);
@@ -151,6 +143,5 @@ pub fn list_inventory_categories(
         "SELECT id, code, label, sort_order \
          FROM inventory_categories WHERE profile = ?1 ORDER BY sort_order",
         &profile,
-    )
-}
+    )}
 
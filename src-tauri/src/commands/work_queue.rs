@@ -1,16 +1,28 @@
 use crate::auth as auth_service;
+use crate::db::specimen_query;
 use crate::db::work_queue::compute_work_queue_items;
+use crate::error::AppError;
 use crate::AppState;
+use std::collections::HashSet;
 use tauri::State;
 
 pub use crate::db::work_queue::WorkQueueItem;
 
+/// `filter` keeps only the items of specimens matching a filter expression
+/// (WP-97), so a saved search can scope the queue to a bench or a project.
 #[tauri::command]
 pub fn get_work_queue(
     state: State<AppState>,
     token: String,
-) -> Result<Vec<WorkQueueItem>, String> {
+    filter: Option<String>,
+) -> Result<Vec<WorkQueueItem>, AppError> {
     let db = state.db();
-    let _user = auth_service::validate_session(&db, &token)?;
-    compute_work_queue_items(&db.conn)
+    let user = auth_service::validate_session(&db, &token)?;
+    let mut items = compute_work_queue_items(&db.conn)?;
+    if let Some(text) = filter.as_deref().filter(|t| !t.trim().is_empty()) {
+        let compiled = specimen_query::compile_for_active_lab(&db.conn, user.role.as_str(), text)?;
+        let ids: HashSet<String> = specimen_query::matching_ids(&db.conn, &compiled)?.into_iter().collect();
+        items.retain(|item| ids.contains(&item.specimen_id));
+    }
+    Ok(items)
 }
@@ -7,6 +7,8 @@ use serde::Serialize;
 use serde_json::json;
 
 use crate::db::queries;
+use crate::error::AppError;
+use crate::signed_ledger::{self, esignature};
 
 #[derive(Debug, Serialize)]
 pub struct AuditRangeVerification {
@@ -20,7 +22,7 @@ pub struct AuditRangeVerification {
 /// would per-lineage — reimplemented here as a pure, connection-only
 /// function (rather than calling the Tauri command) so it can run
 /// server-side during bundle assembly without a session token.
-pub fn verify_audit_range(conn: &Connection, from: &str, to: &str) -> Result<AuditRangeVerification, String> {
+pub fn verify_audit_range(conn: &Connection, from: &str, to: &str) -> Result<AuditRangeVerification, AppError> {
     let mut stmt = conn
         .prepare(
             "SELECT lineage_id, chain_seq, prev_hash, entry_hash, created_at, user_id, \
@@ -29,15 +31,13 @@ pub fn verify_audit_range(conn: &Connection, from: &str, to: &str) -> Result<Aud
              WHERE lineage_id IS NOT NULL AND chain_seq IS NOT NULL \
                AND date(created_at) >= ?1 AND date(created_at) <= ?2 \
              ORDER BY lineage_id ASC, chain_seq ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     #[allow(clippy::type_complexity)]
     let rows: Vec<(String, i64, Option<String>, String, String, Option<String>, String, Option<String>, String, Option<String>)> = stmt
         .query_map(rusqlite::params![from, to], |r| {
             Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?, r.get(7)?, r.get(8)?, r.get(9)?))
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .collect::<rusqlite::Result<Vec<_>>>()
         .map_err(|e| format!("Failed to read a row for the compliance export bundle: {}", e))?;
 
@@ -66,9 +66,9 @@ pub fn verify_audit_range(conn: &Connection, from: &str, to: &str) -> Result<Aud
 
 /// FDA 21 CFR Part 11 electronic-records attestation bundle: cover summary +
 /// full canonical audit trail export + verification result + a per-user
-/// activity report. Signing is applied by the caller (`commands::compliance_export`)
+/// activity report + the electronic signatures applied in range. Signing is applied by the caller (`commands::compliance_export`)
 /// after this function returns the plaintext documents.
-pub fn build_part11_documents(conn: &Connection, from: &str, to: &str, lab_name: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+pub fn build_part11_documents(conn: &Connection, from: &str, to: &str, lab_name: &str) -> Result<Vec<(String, Vec<u8>)>, AppError> {
     let verification = verify_audit_range(conn, from, to)?;
 
     let mut stmt = conn
@@ -77,8 +77,7 @@ pub fn build_part11_documents(conn: &Connection, from: &str, to: &str, lab_name:
                     created_at, lineage_id, chain_seq, prev_hash, entry_hash \
              FROM audit_log WHERE date(created_at) >= ?1 AND date(created_at) <= ?2 \
              ORDER BY created_at ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let entries: Vec<serde_json::Value> = stmt
         .query_map(rusqlite::params![from, to], |r| {
             Ok(json!({
@@ -96,8 +95,7 @@ pub fn build_part11_documents(conn: &Connection, from: &str, to: &str, lab_name:
                 "prev_hash": r.get::<_, Option<String>>(11)?,
                 "entry_hash": r.get::<_, Option<String>>(12)?,
             }))
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .collect::<rusqlite::Result<Vec<_>>>()
         .map_err(|e| format!("Failed to read a row for the compliance export bundle: {}", e))?;
 
@@ -107,8 +105,7 @@ pub fn build_part11_documents(conn: &Connection, from: &str, to: &str, lab_name:
              FROM users u LEFT JOIN audit_log a ON a.user_id = u.id \
                  AND date(a.created_at) >= ?1 AND date(a.created_at) <= ?2 \
              GROUP BY u.id ORDER BY u.username ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let user_report: Vec<serde_json::Value> = user_stmt
         .query_map(rusqlite::params![from, to], |r| {
             Ok(json!({
@@ -118,35 +115,65 @@ pub fn build_part11_documents(conn: &Connection, from: &str, to: &str, lab_name:
                 "last_updated": r.get::<_, String>(3)?,
                 "actions_in_range": r.get::<_, i64>(4)?,
             }))
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .collect::<rusqlite::Result<Vec<_>>>()
         .map_err(|e| format!("Failed to read a row for the compliance export bundle: {}", e))?;
 
+    // WP-80: the electronic signatures applied in range. Each one is checked
+    // against the ledger event that carries it, and the ledger as a whole is
+    // verified too, so a reviewer can tell whether the manifestation fields
+    // shown here are the ones the signer's key actually signed.
+    let ledger = signed_ledger::verify_ledger(conn)?;
+    let signatures: Vec<serde_json::Value> = esignature::list_signatures_in_range(conn, from, to)?
+        .into_iter()
+        .map(|sig| {
+            let check = esignature::signature_matches_ledger(conn, &sig);
+            json!({
+                "signature": sig,
+                "ledger_check": match check {
+                    Ok(()) => "matches signed ledger event".to_string(),
+                    Err(e) => format!("MISMATCH: {}", e),
+                },
+            })
+        })
+        .collect();
+    let signatures_doc = json!({
+        "manifestation_statement": esignature::SIGNATURE_STATEMENT,
+        "ledger_verification": ledger,
+        "signatures": signatures,
+    });
+
     let cover = json!({
         "lab_name": lab_name,
         "system_version": env!("CARGO_PKG_VERSION"),
         "export_range": { "from": from, "to": to },
         "total_audit_entries": entries.len(),
+        "total_electronic_signatures": signatures.len(),
         "attestation": "This system maintains an append-only, cryptographically hash-chained audit \
                          log (SHA-256, per-entry) with role-based access control and a forced \
                          password-change policy on first login, consistent with 21 CFR Part 11 \
-                         electronic-record requirements for trustworthy, tamper-evident records.",
+                         electronic-record requirements for trustworthy, tamper-evident records. \
+                         Critical actions carry electronic signatures applied after password \
+                         re-entry, each recording the signer's printed name, the date and time, \
+                         and the meaning of the signature, and each signed with the signer's \
+                         Ed25519 key in the signed-event ledger.",
         "chain_verification_verdict": if verification.verified { "verified" } else { "broken" },
+        "signature_ledger_verdict": if ledger.verified { "verified" } else { "broken" },
     });
 
     Ok(vec![
-        ("part11_cover.json".to_string(), serde_json::to_vec_pretty(&cover).map_err(|e| e.to_string())?),
-        ("part11_audit_trail.json".to_string(), serde_json::to_vec_pretty(&entries).map_err(|e| e.to_string())?),
-        ("part11_verification.json".to_string(), serde_json::to_vec_pretty(&verification).map_err(|e| e.to_string())?),
-        ("part11_user_activity.json".to_string(), serde_json::to_vec_pretty(&user_report).map_err(|e| e.to_string())?),
+        ("part11_cover.json".to_string(), serde_json::to_vec_pretty(&cover)?),
+        ("part11_audit_trail.json".to_string(), serde_json::to_vec_pretty(&entries)?),
+        ("part11_verification.json".to_string(), serde_json::to_vec_pretty(&verification)?),
+        ("part11_user_activity.json".to_string(), serde_json::to_vec_pretty(&user_report)?),
+        ("part11_electronic_signatures.json".to_string(), serde_json::to_vec_pretty(&signatures_doc)?),
     ])
 }
 
 /// USDA APHIS PPQ Form 526 pre-fill (plant tissue culture profile only) —
 /// auto-populates from live specimen/species records. SteloPTC does not
 /// submit to APHIS; this produces a ready-to-review, ready-to-submit package.
-pub fn build_usda_permit_prefill(conn: &Connection, specimen_ids: &[String], authorized_scientist: &str) -> Result<serde_json::Value, String> {
+pub fn build_usda_permit_prefill(conn: &Connection, specimen_ids: &[String], authorized_scientist: &str) -> Result<serde_json::Value, AppError> {
     // Refuse rather than filter. These ids are caller-supplied, and this
     // document becomes a signed permit application: silently dropping a
     // requested specimen because it belongs to another lab would produce an
@@ -176,7 +203,7 @@ pub fn build_usda_permit_prefill(conn: &Connection, specimen_ids: &[String], aut
                     }))
                 },
             )
-            .map_err(|e| format!("Specimen {} not found: {}", id, e))?;
+            .map_err(|_| AppError::not_found("specimen", format!("Specimen {} not found", id)).with_id(id))?;
         specimens.push(row);
     }
 
@@ -187,7 +214,7 @@ pub fn build_usda_permit_prefill(conn: &Connection, specimen_ids: &[String], aut
              FROM compliance_records WHERE record_type = 'quarantine' AND specimen_id IN ({})",
             placeholders.join(",")
         );
-        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(&sql)?;
         let param_refs: Vec<&dyn rusqlite::types::ToSql> = specimen_ids.iter().map(|s| s as &dyn rusqlite::types::ToSql).collect();
         let collected: Vec<serde_json::Value> = stmt
             .query_map(param_refs.as_slice(), |r| {
@@ -199,8 +226,7 @@ pub fn build_usda_permit_prefill(conn: &Connection, specimen_ids: &[String], aut
                     "notes": r.get::<_, Option<String>>(4)?,
                     "recorded_at": r.get::<_, String>(5)?,
                 }))
-            })
-            .map_err(|e| e.to_string())?
+            })?
             .collect::<rusqlite::Result<Vec<_>>>()
             .map_err(|e| format!("Failed to read a row for the compliance export bundle: {}", e))?;
         collected
@@ -219,7 +245,7 @@ pub fn build_usda_permit_prefill(conn: &Connection, specimen_ids: &[String], aut
 /// existing WP-49 Darwin Core export), full chain-of-custody from
 /// `parent_specimen_id`, every propagation (subculture) record in
 /// chronological order, and an audit-chain verification summary.
-pub fn build_cites_dossier(conn: &Connection, root_specimen_id: &str, cites_appendix: &str) -> Result<serde_json::Value, String> {
+pub fn build_cites_dossier(conn: &Connection, root_specimen_id: &str, cites_appendix: &str) -> Result<serde_json::Value, AppError> {
     // Same reasoning as build_usda_permit_prefill: a CITES dossier is an
     // outward-facing attestation about a specific lineage, so a root specimen
     // from another lab is an error, not something to quietly work around.
@@ -231,13 +257,13 @@ pub fn build_cites_dossier(conn: &Connection, root_specimen_id: &str, cites_appe
             [root_specimen_id],
             |r| Ok((r.get(0)?, r.get(1)?)),
         )
-        .map_err(|_| "Specimen not found".to_string())?;
+        .map_err(|_| AppError::not_found("specimen", "Specimen not found").with_id(root_specimen_id))?;
 
     let taxon_path: Option<String> = conn.query_row("SELECT taxon_path FROM species WHERE id = ?1", [&species_id], |r| r.get(0)).ok();
     let root_id = taxon_path
         .and_then(|p| serde_json::from_str::<Vec<String>>(&p).ok())
         .and_then(|v| v.first().cloned());
-    let darwin_core = queries::export_darwin_core(conn, root_id.as_deref()).map_err(|e| e.to_string())?;
+    let darwin_core = queries::export_darwin_core(conn, root_id.as_deref())?;
 
     // Chain of custody: walk the specimen family via parent_specimen_id in
     // both directions from the given root.
@@ -246,8 +272,7 @@ pub fn build_cites_dossier(conn: &Connection, root_specimen_id: &str, cites_appe
         .prepare(
             "SELECT id, accession_number, parent_specimen_id, initiation_date, location, created_by, created_at \
              FROM specimens WHERE id = ?1 OR parent_specimen_id = ?1 OR root_specimen_id = ?1",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let rows = stmt
         .query_map([root_specimen_id], |r| {
             Ok(json!({
@@ -259,8 +284,7 @@ pub fn build_cites_dossier(conn: &Connection, root_specimen_id: &str, cites_appe
                 "responsible_party": r.get::<_, Option<String>>(5)?,
                 "recorded_at": r.get::<_, String>(6)?,
             }))
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
     for row in rows {
         custody.push(row.map_err(|e| {
             format!("Failed to read a chain-of-custody row for the export bundle: {}", e)
@@ -268,13 +292,11 @@ pub fn build_cites_dossier(conn: &Connection, root_specimen_id: &str, cites_appe
     }
 
     let mut sub_stmt = conn
-        .prepare("SELECT passage_number, date, notes FROM subcultures WHERE specimen_id = ?1 ORDER BY passage_number ASC")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT passage_number, date, notes FROM subcultures WHERE specimen_id = ?1 ORDER BY passage_number ASC")?;
     let propagation: Vec<serde_json::Value> = sub_stmt
         .query_map([root_specimen_id], |r| {
             Ok(json!({ "passage_number": r.get::<_, i64>(0)?, "date": r.get::<_, String>(1)?, "notes": r.get::<_, Option<String>>(2)? }))
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .collect::<rusqlite::Result<Vec<_>>>()
         .map_err(|e| format!("Failed to read a row for the compliance export bundle: {}", e))?;
 
@@ -350,15 +372,57 @@ mod tests {
     }
 
     #[test]
-    fn part11_bundle_includes_all_four_documents() {
+    fn part11_bundle_includes_all_five_documents() {
         let conn = export_test_db();
         let docs = build_part11_documents(&conn, "2020-01-01", "2030-01-01", "Test Lab").unwrap();
         let names: Vec<&str> = docs.iter().map(|(n, _)| n.as_str()).collect();
-        for expected in ["part11_cover.json", "part11_audit_trail.json", "part11_verification.json", "part11_user_activity.json"] {
+        for expected in [
+            "part11_cover.json", "part11_audit_trail.json", "part11_verification.json",
+            "part11_user_activity.json", "part11_electronic_signatures.json",
+        ] {
             assert!(names.contains(&expected), "bundle must include {}", expected);
         }
     }
 
+    #[test]
+    fn part11_bundle_lists_electronic_signatures_with_their_manifestation() {
+        let conn = export_test_db();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) \
+             VALUES ('u1', 'qa1', 'x', 'Quinn Avery', 'supervisor')",
+            [],
+        )
+        .unwrap();
+        let event = signed_ledger::append_signed_event(
+            &conn, "u1", esignature::ELECTRONIC_SIGNATURE, "strain", Some("st1"),
+            r#"{"printed_name":"Quinn Avery","meaning":"approved","action":"strain_confirmation","entity_type":"strain","signed_at":"2026-03-01T10:00:00.000Z"}"#,
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO electronic_signatures \
+             (id, signed_event_id, user_id, printed_name, meaning, action, entity_type, entity_id, signed_at) \
+             VALUES ('es1', ?1, 'u1', 'Quinn Avery', 'approved', 'strain_confirmation', 'strain', 'st1', '2026-03-01T10:00:00.000Z')",
+            [&event.id],
+        )
+        .unwrap();
+
+        let docs = build_part11_documents(&conn, "2020-01-01", "2030-01-01", "Test Lab").unwrap();
+        let doc = docs.iter().find(|(name, _)| name == "part11_electronic_signatures.json").unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&doc.1).unwrap();
+        let sig = &v["signatures"][0];
+        assert_eq!(sig["signature"]["printed_name"], "Quinn Avery");
+        assert_eq!(sig["signature"]["meaning"], "approved");
+        assert_eq!(sig["ledger_check"], "matches signed ledger event");
+        assert_eq!(v["ledger_verification"]["verified"], true);
+
+        // An edited index row is flagged rather than silently exported.
+        conn.execute("UPDATE electronic_signatures SET printed_name = 'Someone Else'", []).unwrap();
+        let docs = build_part11_documents(&conn, "2020-01-01", "2030-01-01", "Test Lab").unwrap();
+        let doc = docs.iter().find(|(name, _)| name == "part11_electronic_signatures.json").unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&doc.1).unwrap();
+        assert!(v["signatures"][0]["ledger_check"].as_str().unwrap().starts_with("MISMATCH"));
+    }
+
     #[test]
     fn usda_permit_prefill_populates_fields_from_specimen_record() {
         let conn = export_test_db();
@@ -9,13 +9,15 @@ pub mod zip_writer;
 
 use rusqlite::{params, Connection};
 
+use crate::error::AppError;
+
 /// Load the single lab-wide Ed25519 signing keypair (WP-60), generating and
 /// persisting one on first use. Returns `(public_key_b64, private_key_b64)`.
 ///
 /// This lives here — not in the `tauri-commands`-gated command layer — so the DB
 /// helper is available to non-command code (e.g. `passport::store`) and to the
 /// `--no-default-features` unit-test build. The command wrapper delegates here.
-pub fn load_or_create_lab_signing_key(conn: &Connection) -> Result<(String, String), String> {
+pub fn load_or_create_lab_signing_key(conn: &Connection) -> Result<(String, String), AppError> {
     let existing: Option<(String, String)> = conn
         .query_row(
             "SELECT public_key_b64, private_key_b64 FROM signing_keys WHERE id = 1",
@@ -30,7 +32,6 @@ pub fn load_or_create_lab_signing_key(conn: &Connection) -> Result<(String, Stri
     conn.execute(
         "INSERT INTO signing_keys (id, public_key_b64, private_key_b64) VALUES (1, ?1, ?2)",
         params![keypair.public_key_b64, keypair.private_key_b64],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok((keypair.public_key_b64, keypair.private_key_b64))
 }
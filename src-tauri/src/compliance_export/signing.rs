@@ -10,6 +10,8 @@ use base64::Engine as _;
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::rngs::OsRng;
 
+use crate::error::AppError;
+
 pub struct SigningKeypair {
     pub public_key_b64: String,
     pub private_key_b64: String,
@@ -27,9 +29,9 @@ pub fn generate_keypair() -> SigningKeypair {
 
 /// Signs `data` with the private key, returning a base64-encoded detached
 /// signature (stored/exported as a `.sig` file alongside the signed document).
-pub fn sign(private_key_b64: &str, data: &[u8]) -> Result<String, String> {
-    let key_bytes = B64.decode(private_key_b64).map_err(|e| format!("Invalid private key: {}", e))?;
-    let key_array: [u8; 32] = key_bytes.try_into().map_err(|_| "Private key must be 32 bytes".to_string())?;
+pub fn sign(private_key_b64: &str, data: &[u8]) -> Result<String, AppError> {
+    let key_bytes = B64.decode(private_key_b64).map_err(|e| AppError::internal(format!("Invalid private key: {}", e)))?;
+    let key_array: [u8; 32] = key_bytes.try_into().map_err(|_| AppError::internal("Private key must be 32 bytes"))?;
     let signing_key = SigningKey::from_bytes(&key_array);
     let signature: Signature = signing_key.sign(data);
     Ok(B64.encode(signature.to_bytes()))
@@ -38,13 +40,13 @@ pub fn sign(private_key_b64: &str, data: &[u8]) -> Result<String, String> {
 /// Verifies a detached signature against `data` and the public key. Returns
 /// `Ok(true)`/`Ok(false)` for a well-formed check (never panics on
 /// attacker-controlled input); `Err` only for a malformed key/signature encoding.
-pub fn verify(public_key_b64: &str, data: &[u8], signature_b64: &str) -> Result<bool, String> {
-    let key_bytes = B64.decode(public_key_b64).map_err(|e| format!("Invalid public key: {}", e))?;
-    let key_array: [u8; 32] = key_bytes.try_into().map_err(|_| "Public key must be 32 bytes".to_string())?;
-    let verifying_key = VerifyingKey::from_bytes(&key_array).map_err(|e| format!("Invalid public key: {}", e))?;
+pub fn verify(public_key_b64: &str, data: &[u8], signature_b64: &str) -> Result<bool, AppError> {
+    let key_bytes = B64.decode(public_key_b64).map_err(|e| AppError::integrity(format!("Invalid public key: {}", e)))?;
+    let key_array: [u8; 32] = key_bytes.try_into().map_err(|_| AppError::integrity("Public key must be 32 bytes"))?;
+    let verifying_key = VerifyingKey::from_bytes(&key_array).map_err(|e| AppError::integrity(format!("Invalid public key: {}", e)))?;
 
-    let sig_bytes = B64.decode(signature_b64).map_err(|e| format!("Invalid signature encoding: {}", e))?;
-    let sig_array: [u8; 64] = sig_bytes.try_into().map_err(|_| "Signature must be 64 bytes".to_string())?;
+    let sig_bytes = B64.decode(signature_b64).map_err(|e| AppError::integrity(format!("Invalid signature encoding: {}", e)))?;
+    let sig_array: [u8; 64] = sig_bytes.try_into().map_err(|_| AppError::integrity("Signature must be 64 bytes"))?;
     let signature = Signature::from_bytes(&sig_array);
 
     Ok(verifying_key.verify(data, &signature).is_ok())
@@ -4,17 +4,19 @@
 use std::io::Write;
 use zip::write::SimpleFileOptions;
 
-pub fn build_zip(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+use crate::error::AppError;
+
+pub fn build_zip(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, AppError> {
     let mut buf = Vec::new();
     {
         let cursor = std::io::Cursor::new(&mut buf);
         let mut writer = zip::ZipWriter::new(cursor);
         let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
         for (name, contents) in files {
-            writer.start_file(name, options).map_err(|e| e.to_string())?;
-            writer.write_all(contents).map_err(|e| e.to_string())?;
+            writer.start_file(name, options).map_err(|e| AppError::internal(e.to_string()))?;
+            writer.write_all(contents)?;
         }
-        writer.finish().map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| AppError::internal(e.to_string()))?;
     }
     Ok(buf)
 }
@@ -58,6 +58,7 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::compliance_export::signing;
+use crate::error::AppError;
 // The self-attested issuer identity (lab name + Ed25519 public key) is the same
 // concept as WP-70's passport issuer and WP-71's registry issuer — reuse it.
 pub use crate::passport::IssuerIdentity;
@@ -292,7 +293,7 @@ pub fn assemble_and_sign(
     program: BundleProgram,
     mut records: Vec<SelectionRecord>,
     private_key_b64: &str,
-) -> Result<CoordinationBundle, String> {
+) -> Result<CoordinationBundle, AppError> {
     // Deterministic order → byte-stable re-exports.
     records.sort_by(|a, b| a.source_key.cmp(&b.source_key));
     for r in &mut records {
@@ -461,8 +462,9 @@ pub fn verify_bundle(b: &CoordinationBundle) -> BundleVerification {
 }
 
 /// Parse a bundle from JSON, returning a clear error on malformed input.
-pub fn parse_bundle(json: &str) -> Result<CoordinationBundle, String> {
-    serde_json::from_str(json).map_err(|e| format!("Invalid coordination bundle JSON: {}", e))
+pub fn parse_bundle(json: &str) -> Result<CoordinationBundle, AppError> {
+    serde_json::from_str(json)
+        .map_err(|e| AppError::validation("bundle_json", format!("Invalid coordination bundle JSON: {}", e)))
 }
 
 #[cfg(test)]
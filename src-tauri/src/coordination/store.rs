@@ -18,6 +18,7 @@ use super::{
     BundleProgram, BundleVerification, CoordinationBundle, IssuerIdentity, SelectionRecord,
 };
 use crate::db::queries::log_audit;
+use crate::error::AppError;
 use crate::passport::store::{get_lab_identity, read_lab_name};
 
 fn now_iso() -> String {
@@ -94,7 +95,7 @@ pub struct BundleImportResult {
 
 /// This lab's public issuer identity (name + Ed25519 public key) — the same WP-60
 /// lab key the passport and registry use.
-pub fn get_identity(conn: &Connection) -> Result<IssuerIdentity, String> {
+pub fn get_identity(conn: &Connection) -> Result<IssuerIdentity, AppError> {
     get_lab_identity(conn)
 }
 
@@ -116,7 +117,7 @@ struct ProgramRow {
     notes: Option<String>,
 }
 
-fn load_program(conn: &Connection, program_id: &str) -> Result<ProgramRow, String> {
+fn load_program(conn: &Connection, program_id: &str) -> Result<ProgramRow, AppError> {
     conn.query_row(
         "SELECT name, goal, target_traits, start_date, notes FROM breeding_programs WHERE id = ?1",
         params![program_id],
@@ -130,7 +131,7 @@ fn load_program(conn: &Connection, program_id: &str) -> Result<ProgramRow, Strin
             })
         },
     )
-    .map_err(|_| format!("Breeding program '{}' not found.", program_id))
+    .map_err(|_| AppError::not_found("breeding_program", format!("Breeding program '{}' not found.", program_id)).with_id(program_id))
 }
 
 /// Gather one program's selection records as unsigned bundle records (record hashes
@@ -139,7 +140,7 @@ fn load_program(conn: &Connection, program_id: &str) -> Result<ProgramRow, Strin
 /// is the lab that authored it — a locally-authored record carries this lab's name;
 /// a previously-imported record keeps its foreign origin (so a re-export preserves
 /// provenance).
-pub fn gather_records(conn: &Connection, program_id: &str, program_name: &str, lab_name: &str) -> Result<Vec<SelectionRecord>, String> {
+pub fn gather_records(conn: &Connection, program_id: &str, program_name: &str, lab_name: &str) -> Result<Vec<SelectionRecord>, AppError> {
     let mut stmt = conn
         .prepare(
             "SELECT sp.genus, sp.species_name, s.code, br.generation_number, br.selection_notes, \
@@ -149,8 +150,7 @@ pub fn gather_records(conn: &Connection, program_id: &str, program_name: &str, l
              JOIN species sp ON s.species_id = sp.id \
              WHERE br.program_id = ?1 \
              ORDER BY br.generation_number, br.created_at",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     struct Row {
         genus: String,
         species_name: String,
@@ -177,8 +177,7 @@ pub fn gather_records(conn: &Connection, program_id: &str, program_name: &str, l
                 notes: r.get(8)?,
                 origin_lab: r.get(9)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -221,7 +220,7 @@ pub fn gather_records(conn: &Connection, program_id: &str, program_name: &str, l
 
 /// Export a signed coordination bundle for one program, record it (direction
 /// `issued`), and return the full document.
-pub fn export_bundle(conn: &Connection, program_id: &str, created_by: Option<&str>) -> Result<CoordinationBundle, String> {
+pub fn export_bundle(conn: &Connection, program_id: &str, created_by: Option<&str>) -> Result<CoordinationBundle, AppError> {
     let (public_key, private_key) = crate::compliance_export::load_or_create_lab_signing_key(conn)?;
     let lab_name = read_lab_name(conn);
     let issuer = IssuerIdentity { lab_name: lab_name.clone(), public_key };
@@ -245,7 +244,7 @@ pub fn export_bundle(conn: &Connection, program_id: &str, created_by: Option<&st
         &private_key,
     )?;
 
-    let bundle_json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    let bundle_json = serde_json::to_string_pretty(&bundle)?;
     conn.execute(
         "INSERT INTO breeding_bundles \
          (id, bundle_id, direction, issuer_lab, issuer_public_key, program_name, content_hash, \
@@ -263,14 +262,13 @@ pub fn export_bundle(conn: &Connection, program_id: &str, created_by: Option<&st
             created_by,
             bundle.issued_at,
         ],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     Ok(bundle)
 }
 
 /// Verify a bundle JSON with no side effects — pure verification, no import.
-pub fn verify_bundle_json(json: &str) -> Result<BundleVerification, String> {
+pub fn verify_bundle_json(json: &str) -> Result<BundleVerification, AppError> {
     let bundle = parse_bundle(json)?;
     Ok(verify_bundle(&bundle))
 }
@@ -303,7 +301,7 @@ fn strain_local_id(conn: &Connection, scientific_name: &str, code: &str) -> Opti
 /// Recompute the set of natural keys already present in the local copy of a program
 /// — so an incoming record can be classified `new` vs `identical` without storing
 /// `source_key` locally.
-fn local_source_keys(conn: &Connection, program_id: &str, program_name: &str) -> Result<std::collections::HashSet<String>, String> {
+fn local_source_keys(conn: &Connection, program_id: &str, program_name: &str) -> Result<std::collections::HashSet<String>, AppError> {
     let mut stmt = conn
         .prepare(
             "SELECT sp.genus, sp.species_name, s.code, br.generation_number, br.selection_notes, \
@@ -312,8 +310,7 @@ fn local_source_keys(conn: &Connection, program_id: &str, program_name: &str) ->
              JOIN strains s ON br.strain_id = s.id \
              JOIN species sp ON s.species_id = sp.id \
              WHERE br.program_id = ?1",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let keys = stmt
         .query_map(params![program_id], |r| {
             let genus: String = r.get(0)?;
@@ -336,8 +333,7 @@ fn local_source_keys(conn: &Connection, program_id: &str, program_name: &str) ->
                 selected_by.as_deref(),
                 &disc,
             ))
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
     Ok(keys)
@@ -390,7 +386,7 @@ fn plan_for(
 
 /// Preview an import: verify the bundle and compute a per-record merge plan against
 /// the local copy of the program. No side effects.
-pub fn preview_import(conn: &Connection, json: &str) -> Result<BundleImportPreview, String> {
+pub fn preview_import(conn: &Connection, json: &str) -> Result<BundleImportPreview, AppError> {
     let bundle = parse_bundle(json)?;
     let verification = verify_bundle(&bundle);
     let local_program = local_program_id(conn, &bundle.program.name);
@@ -425,7 +421,7 @@ pub struct SelectionDecision {
 /// Create a local shell of the program (name + carried metadata) so imported
 /// selection records have somewhere to attach. Called only when no local program of
 /// this name exists — never overwrites an existing one.
-fn create_program_shell(conn: &Connection, program: &BundleProgram, created_by: Option<&str>) -> Result<String, String> {
+fn create_program_shell(conn: &Connection, program: &BundleProgram, created_by: Option<&str>) -> Result<String, AppError> {
     let id = uuid::Uuid::new_v4().to_string();
     let notes = format!(
         "Coordinated copy created from a bundle issued by {}.{}",
@@ -436,14 +432,13 @@ fn create_program_shell(conn: &Connection, program: &BundleProgram, created_by:
         "INSERT INTO breeding_programs (id, name, goal, start_date, target_traits, notes, created_by) \
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
         params![id, program.name, program.goal, program.start_date, program.target_traits, notes, created_by],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(id)
 }
 
 /// Insert an imported selection record into the local program. The record's
 /// `origin_lab` is preserved so its provenance is visible and a re-export keeps it.
-fn insert_selection(conn: &Connection, program_id: &str, strain_id: &str, r: &SelectionRecord) -> Result<String, String> {
+fn insert_selection(conn: &Connection, program_id: &str, strain_id: &str, r: &SelectionRecord) -> Result<String, AppError> {
     let id = uuid::Uuid::new_v4().to_string();
     conn.execute(
         "INSERT INTO breeding_records \
@@ -462,8 +457,7 @@ fn insert_selection(conn: &Connection, program_id: &str, strain_id: &str, r: &Se
             r.notes,
             r.origin_lab,
         ],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(id)
 }
 
@@ -478,11 +472,11 @@ pub fn import_bundle(
     json: &str,
     decisions: &[SelectionDecision],
     imported_by: Option<&str>,
-) -> Result<BundleImportResult, String> {
+) -> Result<BundleImportResult, AppError> {
     let bundle = parse_bundle(json)?;
     let verification = verify_bundle(&bundle);
     if !verification.verified {
-        return Err(format!("Refusing to import an unverifiable bundle: {}", verification.message));
+        return Err(AppError::integrity(format!("Refusing to import an unverifiable bundle: {}", verification.message)));
     }
 
     // Do not import the same bundle twice.
@@ -491,10 +485,9 @@ pub fn import_bundle(
             "SELECT COUNT(*) FROM breeding_bundles WHERE direction = 'imported' AND bundle_id = ?1",
             params![bundle.bundle_id],
             |r| r.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     if already > 0 {
-        return Err(format!("Bundle '{}' has already been imported.", bundle.bundle_id));
+        return Err(AppError::conflict(format!("Bundle '{}' has already been imported.", bundle.bundle_id)));
     }
 
     // All writes below run inside a single transaction so a mid-batch failure
@@ -503,7 +496,7 @@ pub fn import_bundle(
     // — including the program shell and the `breeding_bundles` register row.
     // Without it a partial merge would be committed and the duplicate-import
     // guard above would then reject every retry.
-    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction()?;
 
     // Ensure the local copy of the program (additive — create only if absent).
     let (program_id, program_created) = match local_program_id(&tx, &bundle.program.name) {
@@ -528,8 +521,7 @@ pub fn import_bundle(
         None,
         Some(&bundle.content_hash),
         Some(&details),
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     let audit_entry_id: Option<String> = tx
         .query_row(
@@ -559,8 +551,7 @@ pub fn import_bundle(
             imported_by,
             now_iso(),
         ],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     // Apply each record.
     let local_keys = local_source_keys(&tx, &program_id, &bundle.program.name)?;
@@ -610,7 +601,7 @@ pub fn import_bundle(
                 skipped += 1;
             }
             other => {
-                return Err(format!("Unknown disposition '{}'.", other));
+                return Err(AppError::validation("dispositions", format!("Unknown disposition '{}'.", other)));
             }
         }
 
@@ -627,8 +618,7 @@ pub fn import_bundle(
                 action_taken,
                 local_record_id,
             ],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
         applied.push(AppliedSelection {
             source_key: r.source_key.clone(),
@@ -639,7 +629,7 @@ pub fn import_bundle(
         });
     }
 
-    tx.commit().map_err(|e| e.to_string())?;
+    tx.commit()?;
 
     Ok(BundleImportResult {
         imported: true,
@@ -659,7 +649,7 @@ pub fn import_bundle(
 // ── Register queries ─────────────────────────────────────────────────────────
 
 /// List bundle register rows, newest first, optionally filtered by direction.
-pub fn list_bundles(conn: &Connection, direction: Option<&str>) -> Result<Vec<BundleRow>, String> {
+pub fn list_bundles(conn: &Connection, direction: Option<&str>) -> Result<Vec<BundleRow>, AppError> {
     let cols = "id, bundle_id, direction, issuer_lab, issuer_public_key, program_name, content_hash, \
                 record_count, verified, created_at";
     let map = |r: &rusqlite::Row| -> rusqlite::Result<BundleRow> {
@@ -682,22 +672,18 @@ pub fn list_bundles(conn: &Connection, direction: Option<&str>) -> Result<Vec<Bu
                 .prepare(&format!(
                     "SELECT {} FROM breeding_bundles WHERE direction = ?1 ORDER BY created_at DESC",
                     cols
-                ))
-                .map_err(|e| e.to_string())?;
+                ))?;
             let rows = stmt
-                .query_map(params![dir], map)
-                .map_err(|e| e.to_string())?
+                .query_map(params![dir], map)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
         }
         None => {
             let mut stmt = conn
-                .prepare(&format!("SELECT {} FROM breeding_bundles ORDER BY created_at DESC", cols))
-                .map_err(|e| e.to_string())?;
+                .prepare(&format!("SELECT {} FROM breeding_bundles ORDER BY created_at DESC", cols))?;
             let rows = stmt
-                .query_map([], map)
-                .map_err(|e| e.to_string())?
+                .query_map([], map)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
@@ -706,23 +692,22 @@ pub fn list_bundles(conn: &Connection, direction: Option<&str>) -> Result<Vec<Bu
 }
 
 /// Fetch the full stored bundle JSON for one register row (for re-export).
-pub fn get_bundle_json(conn: &Connection, row_id: &str) -> Result<String, String> {
+pub fn get_bundle_json(conn: &Connection, row_id: &str) -> Result<String, AppError> {
     conn.query_row(
         "SELECT bundle_json FROM breeding_bundles WHERE id = ?1",
         params![row_id],
         |r| r.get(0),
     )
-    .map_err(|_| format!("Bundle record '{}' not found.", row_id))
+    .map_err(|_| AppError::not_found("breeding_bundle", format!("Bundle record '{}' not found.", row_id)).with_id(row_id))
 }
 
 /// Fetch the recorded per-record dispositions for one imported bundle row.
-pub fn list_dispositions(conn: &Connection, bundle_row_id: &str) -> Result<Vec<AppliedSelection>, String> {
+pub fn list_dispositions(conn: &Connection, bundle_row_id: &str) -> Result<Vec<AppliedSelection>, AppError> {
     let mut stmt = conn
         .prepare(
             "SELECT source_key, local_status, disposition, action_taken, local_record_id \
              FROM breeding_bundle_dispositions WHERE bundle_row_id = ?1 ORDER BY source_key",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let rows = stmt
         .query_map(params![bundle_row_id], |r| {
             Ok(AppliedSelection {
@@ -732,8 +717,7 @@ pub fn list_dispositions(conn: &Connection, bundle_row_id: &str) -> Result<Vec<A
                 action_taken: r.get(3)?,
                 local_record_id: r.get(4)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
     Ok(rows)
@@ -0,0 +1,624 @@
+// WP-95: configurable accession number templates.
+//
+// Without a template, accession numbers follow the built-in scheme in
+// `queries::generate_accession_number` (`YYYY-MM-DD-CODE-NNN`, counted per
+// species and day) and split children get a letter suffix (`001` → `001A`).
+// Labs with a mandated format can set a template per lab profile, or per
+// species within a profile:
+//
+//   `{YYYY}` `{YY}` `{MM}` `{DD}`  initiation date parts
+//   `{SPECIES}`                    the species code
+//   `{SEQ}` / `{SEQ:n}`            the sequence, zero-padded to n digits (3)
+//   `{CHECK}`                      Luhn check digit over every digit before it
+//
+// Anything else is literal text. A template's sequence is one counter per
+// template and reset period (`accession_sequences`), so a number is never
+// handed out twice even after its specimen is deleted. Allocation still skips
+// numbers already present in `specimens`, which can happen after a template is
+// edited, and never writes a duplicate.
+use crate::db::queries;
+use crate::error::AppError;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+const MAX_PATTERN_LEN: usize = 64;
+const MAX_SEQ_WIDTH: usize = 9;
+/// How far past a taken number allocation searches before giving up.
+const MAX_SKIP: i64 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Literal(String),
+    Year4,
+    Year2,
+    Month,
+    Day,
+    Species,
+    Seq(usize),
+    Check,
+}
+
+/// When a template's sequence starts again at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceReset {
+    Never,
+    Yearly,
+    Monthly,
+    Daily,
+}
+
+/// How split children extend their parent's accession number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitSuffix {
+    /// `A`, `B`, … `Z` — the built-in rule.
+    Letter,
+    /// `-1`, `-2`, …
+    Number,
+    /// `.1`, `.2`, …
+    DotNumber,
+}
+
+impl SequenceReset {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SequenceReset::Never => "never",
+            SequenceReset::Yearly => "yearly",
+            SequenceReset::Monthly => "monthly",
+            SequenceReset::Daily => "daily",
+        }
+    }
+
+    fn parse(s: &str) -> SequenceReset {
+        match s {
+            "yearly" => SequenceReset::Yearly,
+            "monthly" => SequenceReset::Monthly,
+            "daily" => SequenceReset::Daily,
+            _ => SequenceReset::Never,
+        }
+    }
+
+    /// The counter key for `date` (`YYYY-MM-DD`).
+    fn period(self, date: &str) -> String {
+        match self {
+            SequenceReset::Never => String::new(),
+            SequenceReset::Yearly => date[..4].to_string(),
+            SequenceReset::Monthly => date[..7].to_string(),
+            SequenceReset::Daily => date[..10].to_string(),
+        }
+    }
+}
+
+impl SplitSuffix {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SplitSuffix::Letter => "letter",
+            SplitSuffix::Number => "number",
+            SplitSuffix::DotNumber => "dot_number",
+        }
+    }
+
+    fn parse(s: &str) -> SplitSuffix {
+        match s {
+            "number" => SplitSuffix::Number,
+            "dot_number" => SplitSuffix::DotNumber,
+            _ => SplitSuffix::Letter,
+        }
+    }
+
+    /// The `n`th suffix (0-based), or `None` past the last letter.
+    fn nth(self, n: usize) -> Option<String> {
+        match self {
+            SplitSuffix::Letter => (n < 26).then(|| char::from(b'A' + n as u8).to_string()),
+            SplitSuffix::Number => Some(format!("-{}", n + 1)),
+            SplitSuffix::DotNumber => Some(format!(".{}", n + 1)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessionTemplate {
+    pub id: String,
+    pub lab_profile: String,
+    /// `None` for the profile-wide template.
+    pub species_id: Option<String>,
+    pub species_code: Option<String>,
+    pub pattern: String,
+    pub sequence_reset: SequenceReset,
+    pub split_suffix: SplitSuffix,
+    pub updated_at: String,
+    pub updated_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveAccessionTemplateRequest {
+    /// Defaults to the active profile.
+    pub lab_profile: Option<String>,
+    pub species_id: Option<String>,
+    pub pattern: String,
+    pub sequence_reset: SequenceReset,
+    pub split_suffix: SplitSuffix,
+}
+
+/// Sample numbers a template would produce, for the settings screen.
+#[derive(Debug, Serialize)]
+pub struct AccessionPreview {
+    pub first: String,
+    pub second: String,
+    pub split_child: String,
+}
+
+/// Parses a template pattern. Errors name the `pattern` field.
+pub fn parse(pattern: &str) -> Result<Vec<Token>, AppError> {
+    let bad = |msg: String| AppError::validation("pattern", msg);
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        if c == '{' {
+            let end = rest.find('}').ok_or_else(|| bad("Unclosed '{' in the pattern".into()))?;
+            let name = &rest[1..end];
+            let token = match name {
+                "YYYY" => Token::Year4,
+                "YY" => Token::Year2,
+                "MM" => Token::Month,
+                "DD" => Token::Day,
+                "SPECIES" => Token::Species,
+                "SEQ" => Token::Seq(3),
+                "CHECK" => Token::Check,
+                _ => match name.strip_prefix("SEQ:").map(str::parse::<usize>) {
+                    Some(Ok(w)) if (1..=MAX_SEQ_WIDTH).contains(&w) => Token::Seq(w),
+                    Some(_) => return Err(bad(format!("{{{}}}: the sequence width must be 1–{}", name, MAX_SEQ_WIDTH))),
+                    None => return Err(bad(format!("Unknown token {{{}}}", name))),
+                },
+            };
+            if !literal.is_empty() {
+                tokens.push(Token::Literal(std::mem::take(&mut literal)));
+            }
+            tokens.push(token);
+            rest = &rest[end + 1..];
+        } else {
+            if !(c.is_ascii_alphanumeric() || "-_./".contains(c)) {
+                return Err(bad(format!("'{}' is not allowed; literal text may use letters, digits and - _ . /", c)));
+            }
+            literal.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    if !literal.is_empty() {
+        tokens.push(Token::Literal(literal));
+    }
+    Ok(tokens)
+}
+
+/// Parses `pattern` and checks it can never produce the same number twice
+/// under `reset`.
+pub fn validate(pattern: &str, reset: SequenceReset) -> Result<Vec<Token>, AppError> {
+    let bad = |msg: &str| AppError::validation("pattern", msg);
+    if pattern.trim().is_empty() {
+        return Err(bad("The pattern is required"));
+    }
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(bad("The pattern is too long"));
+    }
+    let tokens = parse(pattern)?;
+    let count = |f: fn(&Token) -> bool| tokens.iter().filter(|t| f(t)).count();
+    if count(|t| matches!(t, Token::Seq(_))) != 1 {
+        return Err(bad("The pattern needs exactly one {SEQ} token"));
+    }
+    match count(|t| *t == Token::Check) {
+        0 => {}
+        1 if tokens.last() == Some(&Token::Check) => {}
+        _ => return Err(bad("{CHECK} may appear once, at the end of the pattern")),
+    }
+    let has = |t: Token| tokens.contains(&t);
+    let year = has(Token::Year4) || has(Token::Year2);
+    let missing = match reset {
+        SequenceReset::Never => None,
+        SequenceReset::Yearly => (!year).then_some("a yearly reset needs {YYYY} or {YY}"),
+        SequenceReset::Monthly => (!(year && has(Token::Month))).then_some("a monthly reset needs the year and {MM}"),
+        SequenceReset::Daily => {
+            (!(year && has(Token::Month) && has(Token::Day))).then_some("a daily reset needs the year, {MM} and {DD}")
+        }
+    };
+    if let Some(m) = missing {
+        return Err(AppError::validation(
+            "sequence_reset",
+            format!("Numbers would repeat: {}", m),
+        ));
+    }
+    Ok(tokens)
+}
+
+/// Luhn check digit over the digits of `s`; letters and separators are skipped.
+pub fn luhn_digit(s: &str) -> char {
+    let sum: u32 = s
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { let x = d * 2; if x > 9 { x - 9 } else { x } } else { d })
+        .sum();
+    char::from_digit((10 - sum % 10) % 10, 10).unwrap_or('0')
+}
+
+/// Whether the last character of `s` is the Luhn check digit of the rest.
+fn has_valid_check(s: &str) -> bool {
+    match s.char_indices().last() {
+        Some((i, c)) if c.is_ascii_digit() => luhn_digit(&s[..i]) == c,
+        _ => false,
+    }
+}
+
+/// `date` as `YYYY-MM-DD`, or a validation error on `initiation_date`.
+fn check_date(date: &str) -> Result<(), AppError> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|_| ())
+        .map_err(|_| AppError::validation("initiation_date", format!("'{}' is not a YYYY-MM-DD date", date)))
+}
+
+/// Renders one accession number. `date` must already be checked.
+pub fn render(tokens: &[Token], species_code: &str, date: &str, seq: i64) -> String {
+    let mut out = String::new();
+    for t in tokens {
+        match t {
+            Token::Literal(s) => out.push_str(s),
+            Token::Year4 => out.push_str(&date[..4]),
+            Token::Year2 => out.push_str(&date[2..4]),
+            Token::Month => out.push_str(&date[5..7]),
+            Token::Day => out.push_str(&date[8..10]),
+            Token::Species => out.push_str(species_code),
+            Token::Seq(w) => out.push_str(&format!("{:0width$}", seq, width = *w)),
+            Token::Check => {
+                let digit = luhn_digit(&out);
+                out.push(digit);
+            }
+        }
+    }
+    out
+}
+
+fn row_to_template(row: &rusqlite::Row) -> rusqlite::Result<AccessionTemplate> {
+    Ok(AccessionTemplate {
+        id: row.get("id")?,
+        lab_profile: row.get("lab_profile")?,
+        species_id: row.get("species_id")?,
+        species_code: row.get("species_code")?,
+        pattern: row.get("pattern")?,
+        sequence_reset: SequenceReset::parse(&row.get::<_, String>("sequence_reset")?),
+        split_suffix: SplitSuffix::parse(&row.get::<_, String>("split_suffix")?),
+        updated_at: row.get("updated_at")?,
+        updated_by: row.get("updated_by")?,
+    })
+}
+
+const SELECT_TEMPLATE: &str = "SELECT t.*, sp.species_code FROM accession_templates t \
+                               LEFT JOIN species sp ON sp.id = t.species_id";
+
+/// Every template, profile-wide ones first.
+pub fn list(conn: &Connection) -> Result<Vec<AccessionTemplate>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "{} ORDER BY t.lab_profile, t.species_id IS NOT NULL, sp.species_code",
+        SELECT_TEMPLATE
+    ))?;
+    let rows = stmt.query_map([], row_to_template)?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// The template for `species_id` in `profile`: its own, else the profile's,
+/// else `None` for the built-in scheme.
+pub fn template_for(conn: &Connection, profile: &str, species_id: &str) -> Result<Option<AccessionTemplate>, AppError> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "{} WHERE t.lab_profile = ?1 AND (t.species_id = ?2 OR t.species_id IS NULL) \
+                 ORDER BY t.species_id IS NULL LIMIT 1",
+                SELECT_TEMPLATE
+            ),
+            params![profile, species_id],
+            row_to_template,
+        )
+        .optional()?)
+}
+
+/// Creates or replaces the template for `(lab_profile, species_id)`.
+pub fn save(conn: &Connection, user_id: &str, active_profile: &str, req: &SaveAccessionTemplateRequest) -> Result<AccessionTemplate, AppError> {
+    let profile = req.lab_profile.as_deref().unwrap_or(active_profile);
+    use crate::compliance_rules::{CELL_CULTURE, MYCOLOGY, PLANT_TISSUE_CULTURE};
+    if ![PLANT_TISSUE_CULTURE, CELL_CULTURE, MYCOLOGY].contains(&profile) {
+        return Err(AppError::validation("lab_profile", format!("Unknown lab profile '{}'", profile)));
+    }
+    let pattern = req.pattern.trim();
+    validate(pattern, req.sequence_reset)?;
+    if let Some(ref sid) = req.species_id {
+        let known: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM species WHERE id = ?1)", params![sid], |r| r.get(0))?;
+        if !known {
+            return Err(AppError::not_found("species", "Species not found").with_id(sid));
+        }
+    }
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM accession_templates WHERE lab_profile = ?1 AND species_id IS ?2",
+            params![profile, req.species_id],
+            |r| r.get(0),
+        )
+        .optional()?;
+    let id = match existing {
+        Some(id) => {
+            conn.execute(
+                "UPDATE accession_templates SET pattern = ?1, sequence_reset = ?2, split_suffix = ?3, \
+                 updated_at = datetime('now'), updated_by = ?4 WHERE id = ?5",
+                params![pattern, req.sequence_reset.as_str(), req.split_suffix.as_str(), user_id, id],
+            )?;
+            id
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO accession_templates (id, lab_profile, species_id, pattern, sequence_reset, split_suffix, updated_by) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![id, profile, req.species_id, pattern, req.sequence_reset.as_str(), req.split_suffix.as_str(), user_id],
+            )?;
+            id
+        }
+    };
+    Ok(conn.query_row(&format!("{} WHERE t.id = ?1", SELECT_TEMPLATE), params![id], row_to_template)?)
+}
+
+/// Deletes a template; its species falls back to the profile's template or
+/// the built-in scheme. The sequence counters go with it.
+pub fn delete(conn: &Connection, id: &str) -> Result<AccessionTemplate, AppError> {
+    let template = conn
+        .query_row(&format!("{} WHERE t.id = ?1", SELECT_TEMPLATE), params![id], row_to_template)
+        .map_err(|_| AppError::not_found("accession_template", "Accession template not found").with_id(id))?;
+    conn.execute("DELETE FROM accession_sequences WHERE template_id = ?1", params![id])?;
+    conn.execute("DELETE FROM accession_templates WHERE id = ?1", params![id])?;
+    Ok(template)
+}
+
+fn is_taken(conn: &Connection, accession: &str) -> Result<bool, AppError> {
+    Ok(conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM specimens WHERE accession_number = ?1)",
+        params![accession],
+        |r| r.get(0),
+    )?)
+}
+
+/// `count` consecutive accession numbers for a new specimen of `species_id`
+/// initiated on `date`. Run it inside the transaction that inserts the
+/// specimens, so a rolled-back insert does not use up its numbers.
+pub fn allocate(
+    conn: &Connection,
+    profile: &str,
+    species_id: &str,
+    species_code: &str,
+    date: &str,
+    count: usize,
+) -> Result<Vec<String>, AppError> {
+    let Some(template) = template_for(conn, profile, species_id)? else {
+        return Ok(if count == 1 {
+            vec![queries::generate_accession_number(conn, species_code, date)
+                .map_err(|e| AppError::internal(format!("Failed to generate accession: {}", e)))?]
+        } else {
+            queries::generate_accession_range(conn, species_code, date, count)?
+        });
+    };
+    check_date(date)?;
+    let tokens = parse(&template.pattern)?;
+    let period = template.sequence_reset.period(date);
+    let last: i64 = conn
+        .query_row(
+            "SELECT last_value FROM accession_sequences WHERE template_id = ?1 AND period = ?2",
+            params![template.id, period],
+            |r| r.get(0),
+        )
+        .optional()?
+        .unwrap_or(0);
+
+    // The first run of `count` free numbers after the counter.
+    let mut numbers = Vec::with_capacity(count);
+    let mut seq = last;
+    while numbers.len() < count {
+        seq += 1;
+        if seq - last > MAX_SKIP {
+            return Err(AppError::conflict(format!(
+                "No run of {} free accession numbers within {} of the template's counter",
+                count, MAX_SKIP
+            )));
+        }
+        let candidate = render(&tokens, species_code, date, seq);
+        if is_taken(conn, &candidate)? {
+            numbers.clear();
+        } else {
+            numbers.push(candidate);
+        }
+    }
+    conn.execute(
+        "INSERT INTO accession_sequences (template_id, period, last_value) VALUES (?1, ?2, ?3) \
+         ON CONFLICT(template_id, period) DO UPDATE SET last_value = excluded.last_value",
+        params![template.id, period, seq],
+    )?;
+    Ok(numbers)
+}
+
+/// Accession numbers for `count` split children of `parent_accession`,
+/// following the species' template. A parent number that ends in a valid
+/// check digit (from a `{CHECK}` template) has it replaced: the suffix goes
+/// before it and the digit is recomputed, so children validate too.
+pub fn split_children(
+    conn: &Connection,
+    profile: &str,
+    species_id: &str,
+    parent_accession: &str,
+    count: usize,
+) -> Result<Vec<String>, AppError> {
+    let Some(template) = template_for(conn, profile, species_id)? else {
+        return Ok(queries::generate_split_accession_numbers(conn, parent_accession, count)?);
+    };
+    let checked = template.pattern.ends_with("{CHECK}") && has_valid_check(parent_accession);
+    let base = if checked { &parent_accession[..parent_accession.len() - 1] } else { parent_accession };
+
+    let mut children = Vec::with_capacity(count);
+    let mut n = 0;
+    while children.len() < count {
+        let suffix = template.split_suffix.nth(n).ok_or_else(|| {
+            AppError::conflict(format!(
+                "Cannot generate {} split accession numbers from '{}': all 26 letter suffixes (A–Z) are already taken",
+                count, parent_accession
+            ))
+        })?;
+        n += 1;
+        let mut candidate = format!("{}{}", base, suffix);
+        if checked {
+            let digit = luhn_digit(&candidate);
+            candidate.push(digit);
+        }
+        if !is_taken(conn, &candidate)? {
+            children.push(candidate);
+        }
+    }
+    Ok(children)
+}
+
+/// What a pattern would produce for `species_code` on `date`: the first two
+/// numbers of a fresh counter and the first split child of the first.
+pub fn preview(pattern: &str, reset: SequenceReset, split_suffix: SplitSuffix, species_code: &str, date: &str) -> Result<AccessionPreview, AppError> {
+    let tokens = validate(pattern.trim(), reset)?;
+    check_date(date)?;
+    let first = render(&tokens, species_code, date, 1);
+    let second = render(&tokens, species_code, date, 2);
+    let checked = tokens.last() == Some(&Token::Check);
+    let base = if checked { &first[..first.len() - 1] } else { first.as_str() };
+    let mut split_child = format!("{}{}", base, split_suffix.nth(0).unwrap_or_default());
+    if checked {
+        let digit = luhn_digit(&split_child);
+        split_child.push(digit);
+    }
+    Ok(AccessionPreview { first, second, split_child })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+
+    fn migrated_db() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory DB");
+        run_all(&conn).expect("all migrations must succeed on a fresh in-memory DB");
+        conn.execute(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp1', 'Citrus', 'sinensis', 'CIT')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn save_template(conn: &Connection, species_id: Option<&str>, pattern: &str, reset: SequenceReset, split: SplitSuffix) -> AccessionTemplate {
+        let req = SaveAccessionTemplateRequest {
+            lab_profile: None,
+            species_id: species_id.map(String::from),
+            pattern: pattern.into(),
+            sequence_reset: reset,
+            split_suffix: split,
+        };
+        save(conn, "u1", "plant_tissue_culture", &req).unwrap()
+    }
+
+    fn add_specimen(conn: &Connection, accession: &str) {
+        conn.execute(
+            "INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, lab_profile) \
+             VALUES (?1, ?1, 'sp1', 'explant', '2026-03-02', 'plant_tissue_culture')",
+            params![accession],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn luhn_matches_the_reference_digits() {
+        // The standard worked example: 7992739871 → 3.
+        assert_eq!(luhn_digit("7992739871"), '3');
+        assert_eq!(luhn_digit("BOS-2026-CIT-00042-"), luhn_digit("202600042"));
+        assert!(has_valid_check("79927398713"));
+        assert!(!has_valid_check("79927398714"));
+    }
+
+    #[test]
+    fn patterns_are_parsed_and_rendered() {
+        let tokens = validate("BOS-{YY}{MM}-{SPECIES}-{SEQ:5}", SequenceReset::Monthly).unwrap();
+        assert_eq!(render(&tokens, "CIT", "2026-03-02", 42), "BOS-2603-CIT-00042");
+        let tokens = validate("{YYYY}{SEQ:4}{CHECK}", SequenceReset::Yearly).unwrap();
+        let n = render(&tokens, "CIT", "2026-03-02", 7);
+        assert_eq!(&n[..8], "20260007");
+        assert!(has_valid_check(&n));
+    }
+
+    #[test]
+    fn patterns_that_could_repeat_or_are_malformed_are_rejected() {
+        let field = |p: &str, r| validate(p, r).unwrap_err().params()["field"].clone();
+        assert_eq!(field("{SPECIES}-{YYYY}", SequenceReset::Never), "pattern");
+        assert_eq!(field("{SEQ}-{SEQ}", SequenceReset::Never), "pattern");
+        assert_eq!(field("{CHECK}{SEQ}", SequenceReset::Never), "pattern");
+        assert_eq!(field("{SEQ:0}", SequenceReset::Never), "pattern");
+        assert_eq!(field("{BOGUS}{SEQ}", SequenceReset::Never), "pattern");
+        assert_eq!(field("A B{SEQ}", SequenceReset::Never), "pattern");
+        assert_eq!(field("{SEQ", SequenceReset::Never), "pattern");
+        assert_eq!(field("{SPECIES}-{SEQ}", SequenceReset::Yearly), "sequence_reset");
+        assert_eq!(field("{YYYY}-{SEQ}", SequenceReset::Monthly), "sequence_reset");
+        assert_eq!(field("{YYYY}{MM}-{SEQ}", SequenceReset::Daily), "sequence_reset");
+    }
+
+    #[test]
+    fn no_template_keeps_the_built_in_scheme() {
+        let conn = migrated_db();
+        assert_eq!(allocate(&conn, "plant_tissue_culture", "sp1", "CIT", "2026-03-02", 1).unwrap(), ["2026-03-02-CIT-001"]);
+        add_specimen(&conn, "2026-03-02-CIT-001");
+        assert_eq!(split_children(&conn, "plant_tissue_culture", "sp1", "2026-03-02-CIT-001", 2).unwrap(), ["2026-03-02-CIT-001A", "2026-03-02-CIT-001B"]);
+    }
+
+    #[test]
+    fn a_template_counts_per_period_and_skips_taken_numbers() {
+        let conn = migrated_db();
+        save_template(&conn, None, "LAB-{YYYY}-{SEQ:4}", SequenceReset::Yearly, SplitSuffix::Letter);
+        add_specimen(&conn, "LAB-2026-0002");
+        let got = allocate(&conn, "plant_tissue_culture", "sp1", "CIT", "2026-03-02", 1).unwrap();
+        assert_eq!(got, ["LAB-2026-0001"]);
+        // A run of three cannot include the taken 0002, so it starts after it.
+        let got = allocate(&conn, "plant_tissue_culture", "sp1", "CIT", "2026-05-01", 3).unwrap();
+        assert_eq!(got, ["LAB-2026-0003", "LAB-2026-0004", "LAB-2026-0005"]);
+        // A new year starts again at 1.
+        let got = allocate(&conn, "plant_tissue_culture", "sp1", "CIT", "2027-01-04", 1).unwrap();
+        assert_eq!(got, ["LAB-2027-0001"]);
+    }
+
+    #[test]
+    fn a_species_template_wins_over_the_profile_template() {
+        let conn = migrated_db();
+        save_template(&conn, None, "LAB-{SEQ}", SequenceReset::Never, SplitSuffix::Letter);
+        save_template(&conn, Some("sp1"), "{SPECIES}{SEQ:2}", SequenceReset::Never, SplitSuffix::Letter);
+        assert_eq!(allocate(&conn, "plant_tissue_culture", "sp1", "CIT", "2026-03-02", 1).unwrap(), ["CIT01"]);
+        assert_eq!(allocate(&conn, "plant_tissue_culture", "other", "VAC", "2026-03-02", 1).unwrap(), ["LAB-001"]);
+        // Another lab profile has no template.
+        assert_eq!(allocate(&conn, "mycology", "sp1", "CIT", "2026-03-02", 1).unwrap(), ["2026-03-02-CIT-001"]);
+        // Saving again for the same scope replaces rather than duplicates.
+        save_template(&conn, Some("sp1"), "{SPECIES}-{SEQ:2}", SequenceReset::Never, SplitSuffix::Letter);
+        assert_eq!(list(&conn).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn split_children_follow_the_suffix_rule_and_recompute_the_check_digit() {
+        let conn = migrated_db();
+        save_template(&conn, None, "{YYYY}-{SEQ:4}{CHECK}", SequenceReset::Yearly, SplitSuffix::DotNumber);
+        let parent = allocate(&conn, "plant_tissue_culture", "sp1", "CIT", "2026-03-02", 1).unwrap().remove(0);
+        add_specimen(&conn, &parent);
+        let children = split_children(&conn, "plant_tissue_culture", "sp1", &parent, 2).unwrap();
+        let base = &parent[..parent.len() - 1];
+        assert!(children[0].starts_with(&format!("{}.1", base)));
+        assert!(children[1].starts_with(&format!("{}.2", base)));
+        assert!(children.iter().all(|c| has_valid_check(c)));
+
+        let preview = preview("{YYYY}-{SEQ:4}{CHECK}", SequenceReset::Yearly, SplitSuffix::DotNumber, "CIT", "2026-03-02").unwrap();
+        assert_eq!(preview.first, parent);
+        assert_eq!(preview.split_child, children[0]);
+    }
+}
@@ -29,7 +29,7 @@ impl TimeRange {
 
     /// The earliest date (`YYYY-MM-DD`) included in this range, or `None` for
     /// "all time" (in which case callers omit the lower-bound filter).
-    fn since(self, conn: &Connection) -> Option<String> {
+    pub(crate) fn since(self, conn: &Connection) -> Option<String> {
         let offset = match self {
             TimeRange::Days30 => "-30 days",
             TimeRange::Days90 => "-90 days",
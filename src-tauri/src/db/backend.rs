@@ -10,6 +10,8 @@
 
 use rusqlite::Connection;
 
+use crate::error::AppError;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BackendKind {
     Sqlite,
@@ -24,13 +26,13 @@ impl BackendKind {
         }
     }
 
-    pub fn parse(s: &str) -> Result<Self, String> {
+    pub fn parse(s: &str) -> Result<Self, AppError> {
         match s {
             "sqlite" => Ok(BackendKind::Sqlite),
             "postgres" => Ok(BackendKind::Postgres),
-            other => Err(format!(
-                "Unknown backend type '{}'. Allowed values: sqlite, postgres",
-                other
+            other => Err(AppError::validation(
+                "backend_type",
+                format!("Unknown backend type '{}'. Allowed values: sqlite, postgres", other),
             )),
         }
     }
@@ -64,15 +66,16 @@ pub fn set_backend_kind(conn: &Connection, kind: BackendKind) -> rusqlite::Resul
 /// Validates a PostgreSQL connection string well-formedly enough to catch
 /// obvious mistakes before attempting a network round-trip. Does not verify
 /// reachability — that is `db::postgres::test_connection`'s job.
-pub fn validate_connection_string(s: &str) -> Result<(), String> {
+pub fn validate_connection_string(s: &str) -> Result<(), AppError> {
     let trimmed = s.trim();
     if trimmed.is_empty() {
-        return Err("A PostgreSQL connection string is required.".to_string());
+        return Err(AppError::validation("connection_string", "A PostgreSQL connection string is required."));
     }
     if !(trimmed.starts_with("postgres://") || trimmed.starts_with("postgresql://")) {
-        return Err(
-            "Connection string must start with 'postgres://' or 'postgresql://'.".to_string(),
-        );
+        return Err(AppError::validation(
+            "connection_string",
+            "Connection string must start with 'postgres://' or 'postgresql://'.",
+        ));
     }
     Ok(())
 }
@@ -85,24 +88,23 @@ pub fn validate_backend_switch(
     target: BackendKind,
     postgres_feature_enabled: bool,
     connection_string: Option<&str>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     match target {
         BackendKind::Sqlite => Ok(()),
         BackendKind::Postgres => {
             if !postgres_feature_enabled {
-                return Err(
+                return Err(AppError::invalid(
                     "This build was not compiled with PostgreSQL support. Rebuild with \
-                     `--features postgres` to enable the PostgreSQL backend."
-                        .to_string(),
-                );
+                     `--features postgres` to enable the PostgreSQL backend.",
+                ));
             }
             match connection_string {
                 Some(cs) => validate_connection_string(cs),
-                None => Err(
+                None => Err(AppError::validation(
+                    "connection_string",
                     "A PostgreSQL connection string is required to switch to the \
-                     PostgreSQL backend."
-                        .to_string(),
-                ),
+                     PostgreSQL backend.",
+                )),
             }
         }
     }
@@ -191,7 +193,7 @@ mod tests {
             Some("postgres://host/db"),
         );
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("--features postgres"));
+        assert!(result.unwrap_err().message().contains("--features postgres"));
     }
 
     #[test]
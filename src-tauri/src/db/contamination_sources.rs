@@ -0,0 +1,427 @@
+// WP-103: contamination root-cause analysis. `get_contamination_stats` and
+// the analytics trend say how much contamination there is; this says where it
+// comes from. Every passage in the window is a 2 × 2 observation: exposed or
+// not to one level of a factor (a media batch, a technician, a location, a
+// vessel type, a hood, a day), contaminated or not. Per level we report the
+// odds ratio against the other passages with that factor recorded, its
+// confidence interval and Fisher's exact p-value; Benjamini–Hochberg q-values
+// correct for testing every level at once. Levels with raised odds are ranked
+// as likely sources, each with the contaminated passages behind it.
+//
+// A flag is attributed to the conditions recorded on the same passage, as in
+// the dashboard's breakdowns. `Attribution::Previous` attributes it to the
+// specimen's preceding passage instead — the one that set up the vessel the
+// contamination was found in.
+use crate::db::analytics::TimeRange;
+use crate::error::AppError;
+use crate::stats::{self, OddsRatio};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Confidence of the odds-ratio intervals; a level is flagged when its
+/// q-value is below one minus this.
+pub const CONFIDENCE: f64 = 0.95;
+/// A level needs this many contaminated passages to be ranked as a source.
+const MIN_CASES: u64 = 2;
+const MAX_RANKED: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceFactor {
+    MediaBatch,
+    Technician,
+    Location,
+    VesselType,
+    Hood,
+    Date,
+}
+
+impl SourceFactor {
+    pub const ALL: [SourceFactor; 6] = [
+        SourceFactor::MediaBatch,
+        SourceFactor::Technician,
+        SourceFactor::Location,
+        SourceFactor::VesselType,
+        SourceFactor::Hood,
+        SourceFactor::Date,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SourceFactor::MediaBatch => "Media batch",
+            SourceFactor::Technician => "Technician",
+            SourceFactor::Location => "Location",
+            SourceFactor::VesselType => "Vessel type",
+            SourceFactor::Hood => "Hood",
+            SourceFactor::Date => "Date",
+        }
+    }
+}
+
+/// Which passage's conditions a contamination flag is held against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Attribution {
+    /// The passage the flag is recorded on.
+    #[default]
+    Passage,
+    /// The specimen's passage before it.
+    Previous,
+}
+
+/// A contaminated passage, linking a level to the specimen it affected.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseRef {
+    pub subculture_id: String,
+    pub specimen_id: String,
+    pub accession_number: String,
+    pub date: String,
+    pub contaminant_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LevelOdds {
+    pub factor: SourceFactor,
+    /// The id or value the level is keyed by.
+    pub level: String,
+    pub label: String,
+    pub passages: u64,
+    pub contaminated: u64,
+    pub rate_pct: f64,
+    /// Contaminated passages expected at the factor's overall rate.
+    pub expected: f64,
+    /// `None` when there is nothing to compare against: a single level, or
+    /// no contamination (or only contamination) across the factor.
+    pub odds: Option<OddsRatio>,
+    pub p: Option<f64>,
+    pub q: Option<f64>,
+    pub significant: bool,
+    pub cases: Vec<CaseRef>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FactorOdds {
+    pub factor: SourceFactor,
+    pub label: &'static str,
+    /// Passages with the factor recorded, and without it.
+    pub recorded: u64,
+    pub unrecorded: u64,
+    pub levels: Vec<LevelOdds>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContaminationSources {
+    /// First day of the window; `None` for all time.
+    pub since: Option<String>,
+    pub attribution: Attribution,
+    /// When set, only this contaminant counts; passages flagged with another
+    /// are left out.
+    pub contaminant_type: Option<String>,
+    /// Contaminant types flagged in the window, for narrowing the analysis.
+    pub contaminant_types: Vec<String>,
+    pub passages: u64,
+    pub contaminated: u64,
+    pub rate_pct: f64,
+    pub confidence: f64,
+    pub factors: Vec<FactorOdds>,
+    /// Levels with raised odds and at least two contaminated passages, most
+    /// convincing first.
+    pub ranked: Vec<LevelOdds>,
+    /// Factors left out because the caller may not see them.
+    pub withheld: Vec<SourceFactor>,
+}
+
+struct Passage {
+    case: CaseRef,
+    contaminated: bool,
+    /// `(key, label)` per factor, in `SourceFactor::ALL` order.
+    levels: [Option<(String, String)>; 6],
+}
+
+fn load(conn: &Connection, profile: &str, since: Option<&str>, attribution: Attribution) -> Result<Vec<Passage>, AppError> {
+    let (e, previous) = match attribution {
+        Attribution::Passage => ("sc", ""),
+        Attribution::Previous => (
+            "pv",
+            "LEFT JOIN subcultures pv ON pv.id = (
+                 SELECT p.id FROM subcultures p
+                 WHERE p.specimen_id = sc.specimen_id AND p.event_type = 'passage' AND p.passage_number < sc.passage_number
+                 ORDER BY p.passage_number DESC LIMIT 1)",
+        ),
+    };
+    let sql = format!(
+        "SELECT sc.id, sc.specimen_id, sp.accession_number, sc.date, sc.contamination_flag, NULLIF(TRIM(sc.contaminant_type), ''),
+                {e}.media_batch_id, mb.batch_id, mb.name,
+                {e}.performed_by, u.display_name,
+                COALESCE(NULLIF(TRIM({e}.location_to), ''), NULLIF(TRIM({e}.location_from), '')),
+                NULLIF(TRIM({e}.vessel_type), ''),
+                NULLIF(TRIM({e}.hood), ''),
+                substr({e}.date, 1, 10)
+         FROM subcultures sc
+         JOIN specimens sp ON sp.id = sc.specimen_id
+         {previous}
+         LEFT JOIN media_batches mb ON mb.id = {e}.media_batch_id
+         LEFT JOIN users u ON u.id = {e}.performed_by
+         WHERE sp.lab_profile = ?1 AND sc.event_type = 'passage' AND (?2 IS NULL OR sc.date >= ?2)
+         ORDER BY sc.date, sc.id"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params![profile, since], |r| {
+        let same = |v: Option<String>| v.map(|v| (v.clone(), v));
+        let batch = r.get::<_, Option<String>>(6)?.map(|id| {
+            let label = match (r.get::<_, Option<String>>(7), r.get::<_, Option<String>>(8)) {
+                (Ok(Some(code)), Ok(Some(name))) => format!("{} ({})", code, name),
+                _ => id.clone(),
+            };
+            (id, label)
+        });
+        let technician = r.get::<_, Option<String>>(9)?.map(|id| {
+            let name = r.get::<_, Option<String>>(10).ok().flatten().unwrap_or_else(|| id.clone());
+            (id, name)
+        });
+        Ok(Passage {
+            case: CaseRef {
+                subculture_id: r.get(0)?,
+                specimen_id: r.get(1)?,
+                accession_number: r.get(2)?,
+                date: r.get(3)?,
+                contaminant_type: r.get(5)?,
+            },
+            contaminated: r.get::<_, i64>(4)? != 0,
+            levels: [batch, technician, same(r.get(11)?), same(r.get(12)?), same(r.get(13)?), same(r.get(14)?)],
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Odds ratios per level of every factor over the passages of `time_range`
+/// in `profile`. The technician factor is only analysed when
+/// `include_technician` is set.
+pub fn analyse(
+    conn: &Connection,
+    profile: &str,
+    time_range: TimeRange,
+    attribution: Attribution,
+    contaminant_type: Option<&str>,
+    include_technician: bool,
+) -> Result<ContaminationSources, AppError> {
+    let since = time_range.since(conn);
+    let contaminant_type = contaminant_type.map(str::trim).filter(|t| !t.is_empty());
+    let all = load(conn, profile, since.as_deref(), attribution)?;
+
+    let contaminant_types: Vec<String> = all
+        .iter()
+        .filter(|p| p.contaminated)
+        .filter_map(|p| p.case.contaminant_type.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    // Narrowed to one contaminant, the cases are the passages flagged with it
+    // and the controls the clean ones.
+    let passages: Vec<Passage> = all
+        .into_iter()
+        .filter_map(|mut p| {
+            if let Some(wanted) = contaminant_type {
+                let matches = p.case.contaminant_type.as_deref().is_some_and(|t| t.eq_ignore_ascii_case(wanted));
+                if p.contaminated && !matches {
+                    return None;
+                }
+                p.contaminated &= matches;
+            }
+            Some(p)
+        })
+        .collect();
+
+    let factors_shown: Vec<SourceFactor> =
+        SourceFactor::ALL.into_iter().filter(|f| include_technician || *f != SourceFactor::Technician).collect();
+    let mut factors: Vec<FactorOdds> = factors_shown.iter().map(|&f| factor_odds(&passages, f)).collect();
+
+    // One family of tests: every level compared, across every factor.
+    let tested: Vec<(usize, usize)> = factors
+        .iter()
+        .enumerate()
+        .flat_map(|(i, f)| f.levels.iter().enumerate().filter(|(_, l)| l.p.is_some()).map(move |(j, _)| (i, j)))
+        .collect();
+    let ps: Vec<f64> = tested.iter().map(|&(i, j)| factors[i].levels[j].p.unwrap_or(1.0)).collect();
+    for (&(i, j), q) in tested.iter().zip(stats::benjamini_hochberg(&ps)) {
+        let level = &mut factors[i].levels[j];
+        level.q = Some(q);
+        level.significant = q < 1.0 - CONFIDENCE;
+    }
+
+    let mut ranked: Vec<LevelOdds> = factors
+        .iter()
+        .flat_map(|f| &f.levels)
+        .filter(|l| l.contaminated >= MIN_CASES && l.odds.is_some_and(|o| o.odds_ratio > 1.0))
+        .cloned()
+        .collect();
+    ranked.sort_by(|a, b| {
+        let p = |l: &LevelOdds| l.p.unwrap_or(1.0);
+        let excess = |l: &LevelOdds| l.contaminated as f64 - l.expected;
+        p(a).total_cmp(&p(b)).then(excess(b).total_cmp(&excess(a)))
+    });
+    ranked.truncate(MAX_RANKED);
+
+    let contaminated = passages.iter().filter(|p| p.contaminated).count() as u64;
+    Ok(ContaminationSources {
+        since,
+        attribution,
+        contaminant_type: contaminant_type.map(str::to_string),
+        contaminant_types,
+        passages: passages.len() as u64,
+        contaminated,
+        rate_pct: pct(contaminated, passages.len() as u64),
+        confidence: CONFIDENCE,
+        factors,
+        ranked,
+        withheld: if include_technician { Vec::new() } else { vec![SourceFactor::Technician] },
+    })
+}
+
+fn pct(part: u64, whole: u64) -> f64 {
+    if whole == 0 { 0.0 } else { 100.0 * part as f64 / whole as f64 }
+}
+
+fn factor_odds(passages: &[Passage], factor: SourceFactor) -> FactorOdds {
+    let slot = SourceFactor::ALL.iter().position(|f| *f == factor).unwrap_or_default();
+    let mut by_level: BTreeMap<&str, (&str, u64, Vec<CaseRef>)> = BTreeMap::new();
+    let (mut recorded, mut cases_total) = (0u64, 0u64);
+    for p in passages {
+        let Some((key, label)) = &p.levels[slot] else { continue };
+        recorded += 1;
+        let entry = by_level.entry(key).or_insert((label, 0, Vec::new()));
+        entry.1 += 1;
+        if p.contaminated {
+            cases_total += 1;
+            entry.2.push(p.case.clone());
+        }
+    }
+    let comparable = by_level.len() >= 2 && cases_total > 0 && cases_total < recorded;
+    let mut levels: Vec<LevelOdds> = by_level
+        .into_iter()
+        .map(|(key, (label, n, cases))| {
+            let a = cases.len() as u64;
+            let (b, c) = (n - a, cases_total - a);
+            let d = (recorded - n) - c;
+            let tested = comparable && n < recorded;
+            LevelOdds {
+                factor,
+                level: key.to_string(),
+                label: label.to_string(),
+                passages: n,
+                contaminated: a,
+                rate_pct: pct(a, n),
+                expected: n as f64 * cases_total as f64 / recorded as f64,
+                odds: tested.then(|| stats::odds_ratio(a, b, c, d, CONFIDENCE)),
+                p: tested.then(|| stats::fisher_exact(a, b, c, d)),
+                q: None,
+                significant: false,
+                cases,
+            }
+        })
+        .collect();
+    levels.sort_by(|x, y| {
+        let or = |l: &LevelOdds| l.odds.map_or(f64::NEG_INFINITY, |o| o.odds_ratio);
+        or(y).total_cmp(&or(x)).then(y.passages.cmp(&x.passages))
+    });
+    FactorOdds { factor, label: factor.label(), recorded, unrecorded: passages.len() as u64 - recorded, levels }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+
+    const LAB: &str = "plant_tissue_culture";
+
+    /// Two media batches and two hoods over 40 passages of 20 specimens (two
+    /// passages each, ten days apart). Batch B2 contaminates 8 of its 10
+    /// passages; everything else, 1 in 30. The hood follows the batch only on
+    /// the first passage.
+    fn contamination_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT');
+             INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'ana', 'x', 'Ana', 'tech');
+             INSERT INTO media_batches (id, batch_id, name, preparation_date) VALUES
+                 ('b1', 'MB-1', 'MS full', '2026-01-01'), ('b2', 'MB-2', 'MS half', '2026-01-01');
+             INSERT INTO specimens (id, accession_number, species_id, initiation_date, lab_profile)
+                 VALUES ('other', 'MYC-1', 'sp', '2026-01-01', 'mycology');
+             INSERT INTO subcultures (id, specimen_id, passage_number, date, contamination_flag, event_type)
+                 VALUES ('x', 'other', 1, date('now'), 1, 'passage');",
+        )
+        .unwrap();
+        for s in 0..20 {
+            conn.execute(
+                "INSERT INTO specimens (id, accession_number, species_id, initiation_date) VALUES (?1, ?2, 'sp', '2026-01-01')",
+                params![format!("s{s}"), format!("CIT-{s:03}")],
+            )
+            .unwrap();
+            for passage in 1..=2 {
+                let i = (s * 2 + passage - 1) as usize;
+                let batch = if i < 10 { "b2" } else { "b1" };
+                let flagged = (i < 10 && i % 5 != 4) || i == 39;
+                let hood = if passage == 1 && i < 10 { "Hood A" } else { "Hood B" };
+                conn.execute(
+                    "INSERT INTO subcultures (id, specimen_id, passage_number, date, media_batch_id, hood, performed_by,
+                                              contamination_flag, contaminant_type, event_type)
+                     VALUES (?1, ?2, ?3, date('now', ?4), ?5, ?6, 'u1', ?7, ?8, 'passage')",
+                    params![
+                        format!("p{i}"), format!("s{s}"), passage, format!("-{} days", 30 - passage * 10),
+                        batch, hood, flagged, flagged.then_some(if i == 3 { "bacteria" } else { "fungus" }),
+                    ],
+                )
+                .unwrap();
+            }
+        }
+        conn
+    }
+
+    fn level<'a>(r: &'a ContaminationSources, factor: SourceFactor, key: &str) -> &'a LevelOdds {
+        r.factors.iter().find(|f| f.factor == factor).unwrap().levels.iter().find(|l| l.level == key).unwrap()
+    }
+
+    #[test]
+    fn the_contaminating_batch_ranks_first_with_its_specimens() {
+        let conn = contamination_db();
+        let r = analyse(&conn, LAB, TimeRange::All, Attribution::Passage, None, false).unwrap();
+        assert_eq!((r.passages, r.contaminated), (40, 9), "the mycology passage is another lab's");
+        assert_eq!(r.withheld, [SourceFactor::Technician]);
+        assert!(r.factors.iter().all(|f| f.factor != SourceFactor::Technician));
+
+        let b2 = level(&r, SourceFactor::MediaBatch, "b2");
+        assert_eq!((b2.label.as_str(), b2.passages, b2.contaminated), ("MB-2 (MS half)", 10, 8));
+        // 8/2 against 1/29: OR = 116.
+        assert!((b2.odds.unwrap().odds_ratio - 116.0).abs() < 1e-9);
+        assert!(b2.odds.unwrap().lower > 1.0 && b2.significant);
+        assert_eq!(r.ranked[0].level, "b2");
+        assert!(b2.cases.iter().any(|c| c.accession_number == "CIT-000" && c.subculture_id == "p0"));
+        // No vessel types or locations were recorded: nothing to compare.
+        let vessels = r.factors.iter().find(|f| f.factor == SourceFactor::VesselType).unwrap();
+        assert_eq!((vessels.recorded, vessels.unrecorded, vessels.levels.len()), (0, 40, 0));
+
+        let with_staff = analyse(&conn, LAB, TimeRange::All, Attribution::Passage, None, true).unwrap();
+        let ana = level(&with_staff, SourceFactor::Technician, "u1");
+        assert_eq!((ana.label.as_str(), ana.passages), ("Ana", 40));
+        assert!(ana.odds.is_none(), "a single technician has no one to compare against");
+    }
+
+    #[test]
+    fn previous_attribution_and_contaminant_filter() {
+        let conn = contamination_db();
+        // Held against the passage before, only second passages have an
+        // exposure, and the flags on p1 and p3 fall on Hood A.
+        let r = analyse(&conn, LAB, TimeRange::All, Attribution::Previous, None, false).unwrap();
+        let hoods = r.factors.iter().find(|f| f.factor == SourceFactor::Hood).unwrap();
+        assert_eq!((hoods.recorded, hoods.unrecorded), (20, 20));
+        let a = level(&r, SourceFactor::Hood, "Hood A");
+        assert_eq!((a.passages, a.contaminated), (5, 4));
+
+        let fungus = analyse(&conn, LAB, TimeRange::Days30, Attribution::Passage, Some("FUNGUS"), false).unwrap();
+        assert_eq!(fungus.contaminant_types, ["bacteria", "fungus"]);
+        assert_eq!((fungus.passages, fungus.contaminated), (39, 8), "the bacterial passage is left out");
+        assert_eq!(level(&fungus, SourceFactor::MediaBatch, "b2").contaminated, 7);
+    }
+}
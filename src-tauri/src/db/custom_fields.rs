@@ -0,0 +1,712 @@
+// WP-96: admin-defined custom fields per lab profile.
+//
+// A lab adds the handful of fields its work needs (explant type, ploidy, a
+// cell line's donor ID) without a schema change. A definition belongs to one
+// lab profile and one entity (`specimen` or `subculture`) and has a type:
+//
+//   `text`     trimmed string, at most 1000 characters
+//   `number`   finite number; the definition may carry a display unit
+//   `date`     `YYYY-MM-DD`
+//   `enum`     one code from the field's own option vocabulary
+//   `boolean`  true / false
+//
+// Values are stored on the record as one JSON object keyed by field key
+// (`specimens.custom_fields`, `subcultures.custom_fields`). The object is a
+// `BTreeMap`, so it serializes with sorted keys and the same values always
+// give the same text; that text is what goes into the audit `details`, and
+// so into the entry hash. Definitions are retired, never deleted, so stored
+// values keep their meaning.
+use crate::db::permissions::reject_if_restricted_marker;
+use crate::error::AppError;
+use rusqlite::types::ToSql;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// A record's custom values, keyed by field key.
+pub type CustomValues = BTreeMap<String, Value>;
+
+const MAX_KEY_LEN: usize = 40;
+const MAX_LABEL_LEN: usize = 80;
+const MAX_TEXT_LEN: usize = 1000;
+/// Enough for any real vocabulary; keeps a form's dropdown usable.
+const MAX_OPTIONS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldEntity {
+    Specimen,
+    Subculture,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldType {
+    Text,
+    Number,
+    Date,
+    Enum,
+    Boolean,
+}
+
+impl CustomFieldEntity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CustomFieldEntity::Specimen => "specimen",
+            CustomFieldEntity::Subculture => "subculture",
+        }
+    }
+
+    fn parse(s: &str) -> CustomFieldEntity {
+        match s {
+            "subculture" => CustomFieldEntity::Subculture,
+            _ => CustomFieldEntity::Specimen,
+        }
+    }
+}
+
+impl CustomFieldType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CustomFieldType::Text => "text",
+            CustomFieldType::Number => "number",
+            CustomFieldType::Date => "date",
+            CustomFieldType::Enum => "enum",
+            CustomFieldType::Boolean => "boolean",
+        }
+    }
+
+    fn parse(s: &str) -> CustomFieldType {
+        match s {
+            "number" => CustomFieldType::Number,
+            "date" => CustomFieldType::Date,
+            "enum" => CustomFieldType::Enum,
+            "boolean" => CustomFieldType::Boolean,
+            _ => CustomFieldType::Text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomFieldOption {
+    pub code: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CustomField {
+    pub id: String,
+    pub lab_profile: String,
+    pub entity: CustomFieldEntity,
+    pub key: String,
+    pub label: String,
+    pub field_type: CustomFieldType,
+    pub unit: Option<String>,
+    pub required: bool,
+    pub sort_order: i64,
+    pub options: Vec<CustomFieldOption>,
+    /// Name of the plugin that shipped the field, if any.
+    pub source_plugin: Option<String>,
+    pub retired: bool,
+    pub updated_at: String,
+    pub updated_by: Option<String>,
+}
+
+/// A field definition as sent by the settings screen or a plugin manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveCustomFieldRequest {
+    /// Defaults to the active profile (or, in a manifest, the plugin's).
+    #[serde(default)]
+    pub lab_profile: Option<String>,
+    pub entity: CustomFieldEntity,
+    pub key: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub field_type: CustomFieldType,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub sort_order: i64,
+    #[serde(default)]
+    pub options: Vec<CustomFieldOption>,
+}
+
+pub(crate) fn is_key(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_lowercase())
+        && s.len() <= MAX_KEY_LEN
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Checks a definition on its own, without the database. Shared by
+/// [`save`] and plugin manifest validation.
+pub fn check_definition(req: &SaveCustomFieldRequest) -> Result<(), AppError> {
+    if !is_key(&req.key) {
+        return Err(AppError::validation(
+            "key",
+            format!(
+                "'{}' is not a valid key: use 1–{} lowercase letters, digits and _, starting with a letter",
+                req.key, MAX_KEY_LEN
+            ),
+        ));
+    }
+    let label = req.label.trim();
+    if label.is_empty() || label.chars().count() > MAX_LABEL_LEN {
+        return Err(AppError::validation("label", format!("The label must be 1–{} characters", MAX_LABEL_LEN)));
+    }
+    if req.unit.as_deref().is_some_and(|u| !u.trim().is_empty()) && req.field_type != CustomFieldType::Number {
+        return Err(AppError::validation("unit", "Only number fields have a unit"));
+    }
+    if req.field_type == CustomFieldType::Enum {
+        if req.options.is_empty() || req.options.len() > MAX_OPTIONS {
+            return Err(AppError::validation("options", format!("An enum field needs 1–{} options", MAX_OPTIONS)));
+        }
+        let mut seen = std::collections::HashSet::new();
+        for o in &req.options {
+            if !is_key(&o.code) || o.label.trim().is_empty() {
+                return Err(AppError::validation(
+                    "options",
+                    format!("Option '{}' needs a code like a key (e.g. nodal_segment) and a label", o.code),
+                ));
+            }
+            if !seen.insert(o.code.as_str()) {
+                return Err(AppError::validation("options", format!("Option '{}' is listed twice", o.code)));
+            }
+        }
+    } else if !req.options.is_empty() {
+        return Err(AppError::validation("options", "Only enum fields have options"));
+    }
+    Ok(())
+}
+
+const SELECT_FIELD: &str = "SELECT id, lab_profile, entity, field_key, label, field_type, unit, required, \
+                            sort_order, source_plugin, retired_at, updated_at, updated_by FROM custom_fields";
+
+fn row_to_field(row: &rusqlite::Row) -> rusqlite::Result<CustomField> {
+    Ok(CustomField {
+        id: row.get(0)?,
+        lab_profile: row.get(1)?,
+        entity: CustomFieldEntity::parse(&row.get::<_, String>(2)?),
+        key: row.get(3)?,
+        label: row.get(4)?,
+        field_type: CustomFieldType::parse(&row.get::<_, String>(5)?),
+        unit: row.get(6)?,
+        required: row.get::<_, i64>(7)? != 0,
+        sort_order: row.get(8)?,
+        options: Vec::new(),
+        source_plugin: row.get(9)?,
+        retired: row.get::<_, Option<String>>(10)?.is_some(),
+        updated_at: row.get(11)?,
+        updated_by: row.get(12)?,
+    })
+}
+
+fn load_options(conn: &Connection, field: &mut CustomField) -> Result<(), AppError> {
+    if field.field_type != CustomFieldType::Enum {
+        return Ok(());
+    }
+    let mut stmt = conn.prepare(
+        "SELECT code, label FROM custom_field_options WHERE field_id = ?1 ORDER BY sort_order, code",
+    )?;
+    field.options = stmt
+        .query_map(params![field.id], |r| Ok(CustomFieldOption { code: r.get(0)?, label: r.get(1)? }))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(())
+}
+
+/// The profile's field definitions in form order, for one entity or both.
+pub fn list(
+    conn: &Connection,
+    profile: &str,
+    entity: Option<CustomFieldEntity>,
+    include_retired: bool,
+) -> Result<Vec<CustomField>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE lab_profile = ?1 AND (?2 IS NULL OR entity = ?2) AND (?3 OR retired_at IS NULL) \
+         ORDER BY entity, sort_order, label",
+        SELECT_FIELD
+    ))?;
+    let mut fields = stmt
+        .query_map(params![profile, entity.map(CustomFieldEntity::as_str), include_retired], row_to_field)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for f in &mut fields {
+        load_options(conn, f)?;
+    }
+    Ok(fields)
+}
+
+fn get(conn: &Connection, id: &str) -> Result<CustomField, AppError> {
+    let mut field = conn
+        .query_row(&format!("{} WHERE id = ?1", SELECT_FIELD), params![id], row_to_field)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("custom_field", "Custom field not found").with_id(id))?;
+    load_options(conn, &mut field)?;
+    Ok(field)
+}
+
+fn write_options(conn: &Connection, field_id: &str, options: &[CustomFieldOption]) -> Result<(), AppError> {
+    conn.execute("DELETE FROM custom_field_options WHERE field_id = ?1", params![field_id])?;
+    for (i, o) in options.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO custom_field_options (field_id, code, label, sort_order) VALUES (?1, ?2, ?3, ?4)",
+            params![field_id, o.code, o.label.trim(), i as i64],
+        )?;
+    }
+    Ok(())
+}
+
+/// Creates or updates the definition for `(lab_profile, entity, key)`. A
+/// field's type is fixed once created, because stored values were checked
+/// against it; saving a retired field brings it back.
+pub fn save(conn: &Connection, user_id: &str, active_profile: &str, req: &SaveCustomFieldRequest) -> Result<CustomField, AppError> {
+    check_definition(req)?;
+    let profile = req.lab_profile.as_deref().unwrap_or(active_profile);
+    let known: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM stages WHERE profile = ?1)", params![profile], |r| r.get(0))?;
+    if !known {
+        return Err(AppError::validation("lab_profile", format!("Unknown lab profile '{}'", profile)));
+    }
+    let unit = req.unit.as_deref().map(str::trim).filter(|u| !u.is_empty());
+    let existing: Option<(String, String)> = conn
+        .query_row(
+            "SELECT id, field_type FROM custom_fields WHERE lab_profile = ?1 AND entity = ?2 AND field_key = ?3",
+            params![profile, req.entity.as_str(), req.key],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?;
+    let tx = conn.unchecked_transaction()?;
+    let id = match existing {
+        Some((id, field_type)) => {
+            if field_type != req.field_type.as_str() {
+                return Err(AppError::validation(
+                    "type",
+                    format!("'{}' is a {} field; the type of an existing field cannot change", req.key, field_type),
+                ));
+            }
+            tx.execute(
+                "UPDATE custom_fields SET label = ?1, unit = ?2, required = ?3, sort_order = ?4, retired_at = NULL, \
+                 updated_at = datetime('now'), updated_by = ?5 WHERE id = ?6",
+                params![req.label.trim(), unit, req.required, req.sort_order, user_id, id],
+            )?;
+            id
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO custom_fields (id, lab_profile, entity, field_key, label, field_type, unit, required, sort_order, updated_by) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    id, profile, req.entity.as_str(), req.key, req.label.trim(), req.field_type.as_str(),
+                    unit, req.required, req.sort_order, user_id,
+                ],
+            )?;
+            id
+        }
+    };
+    write_options(&tx, &id, &req.options)?;
+    tx.commit()?;
+    get(conn, &id)
+}
+
+/// Retires a field: it leaves the forms and can no longer be set, but stored
+/// values are kept, exported and searchable.
+pub fn retire(conn: &Connection, id: &str) -> Result<CustomField, AppError> {
+    let field = get(conn, id)?;
+    conn.execute(
+        "UPDATE custom_fields SET retired_at = datetime('now'), updated_at = datetime('now') WHERE id = ?1",
+        params![id],
+    )?;
+    Ok(CustomField { retired: true, ..field })
+}
+
+/// Adds a plugin's field definitions that do not exist yet. Existing ones,
+/// including a lab's own edits to a plugin field, are left alone, as
+/// vocabulary seeds are (`plugins::loader`). Returns how many were added.
+pub fn seed(conn: &Connection, plugin: &str, profile: &str, fields: &[SaveCustomFieldRequest]) -> Result<usize, AppError> {
+    let mut added = 0;
+    for req in fields {
+        check_definition(req)?;
+        let profile = req.lab_profile.as_deref().unwrap_or(profile);
+        let id = uuid::Uuid::new_v4().to_string();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO custom_fields (id, lab_profile, entity, field_key, label, field_type, unit, required, sort_order, source_plugin) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id, profile, req.entity.as_str(), req.key, req.label.trim(), req.field_type.as_str(),
+                req.unit.as_deref().map(str::trim).filter(|u| !u.is_empty()), req.required, req.sort_order, plugin,
+            ],
+        )?;
+        if inserted > 0 {
+            write_options(conn, &id, &req.options)?;
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+/// Parses a stored `custom_fields` column; absent or unreadable is empty.
+pub fn from_column(text: Option<&str>) -> CustomValues {
+    text.and_then(|t| serde_json::from_str(t).ok()).unwrap_or_default()
+}
+
+/// The column value for `values`: NULL when there are none.
+pub fn to_column(values: &CustomValues) -> Option<String> {
+    (!values.is_empty()).then(|| serde_json::to_string(values).unwrap_or_default())
+}
+
+/// `custom fields {…}` for an audit entry's details, so the values are
+/// covered by the entry hash. `None` when there is nothing to record.
+pub fn audit_note(values: &CustomValues) -> Option<String> {
+    to_column(values).map(|json| format!("custom fields {}", json))
+}
+
+/// Checks one value against its definition and returns it as stored.
+/// `Ok(None)` means the value clears the field.
+fn normalize(field: &CustomField, value: &Value) -> Result<Option<Value>, AppError> {
+    let bad = |expected: &str| {
+        AppError::validation("custom_fields", format!("{} must be {}", field.label, expected))
+    };
+    if value.is_null() {
+        return Ok(None);
+    }
+    let value = match field.field_type {
+        CustomFieldType::Text => {
+            let text = value.as_str().ok_or_else(|| bad("text"))?.trim();
+            if text.is_empty() {
+                return Ok(None);
+            }
+            if text.chars().count() > MAX_TEXT_LEN {
+                return Err(bad(&format!("at most {} characters", MAX_TEXT_LEN)));
+            }
+            reject_if_restricted_marker(Some(text), &field.label)?;
+            Value::String(text.to_string())
+        }
+        CustomFieldType::Number => {
+            let n = match value {
+                Value::Number(n) => n.as_f64(),
+                Value::String(s) if s.trim().is_empty() => return Ok(None),
+                Value::String(s) => s.trim().parse::<f64>().ok(),
+                _ => None,
+            }
+            .filter(|n| n.is_finite())
+            .ok_or_else(|| bad("a number"))?;
+            // Always stored as a float, so `2` and `"2"` give the same text.
+            serde_json::Number::from_f64(n).map(Value::Number).ok_or_else(|| bad("a number"))?
+        }
+        CustomFieldType::Date => {
+            let text = value.as_str().ok_or_else(|| bad("a YYYY-MM-DD date"))?.trim();
+            if text.is_empty() {
+                return Ok(None);
+            }
+            chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").map_err(|_| bad("a YYYY-MM-DD date"))?;
+            Value::String(text.to_string())
+        }
+        CustomFieldType::Enum => {
+            let code = value.as_str().ok_or_else(|| bad("one of its options"))?;
+            if code.is_empty() {
+                return Ok(None);
+            }
+            if !field.options.iter().any(|o| o.code == code) {
+                let codes: Vec<&str> = field.options.iter().map(|o| o.code.as_str()).collect();
+                return Err(bad(&format!("one of: {}", codes.join(", "))));
+            }
+            Value::String(code.to_string())
+        }
+        CustomFieldType::Boolean => Value::Bool(value.as_bool().ok_or_else(|| bad("true or false"))?),
+    };
+    Ok(Some(value))
+}
+
+/// Applies `patch` to a record's `current` values and returns the result.
+/// Every key must be a field of `entity` in `profile`; a null (or blank)
+/// value clears the field. Creating a record (`creating`) also requires every
+/// required field; an update only refuses to clear one, so making a field
+/// required does not block edits to records that predate it.
+pub fn apply(
+    conn: &Connection,
+    profile: &str,
+    entity: CustomFieldEntity,
+    current: &CustomValues,
+    patch: &CustomValues,
+    creating: bool,
+) -> Result<CustomValues, AppError> {
+    let fields = list(conn, profile, Some(entity), true)?;
+    let mut values = current.clone();
+    for (key, value) in patch {
+        let field = fields.iter().find(|f| &f.key == key).ok_or_else(|| {
+            AppError::validation("custom_fields", format!("'{}' is not a {} field in this lab", key, entity.as_str()))
+        })?;
+        match normalize(field, value)? {
+            Some(_) if field.retired => {
+                return Err(AppError::validation(
+                    "custom_fields",
+                    format!("{} is retired and can no longer be set", field.label),
+                ));
+            }
+            Some(v) => {
+                values.insert(key.clone(), v);
+            }
+            None if field.required && !field.retired => {
+                return Err(AppError::validation("custom_fields", format!("{} is required", field.label)));
+            }
+            None => {
+                values.remove(key);
+            }
+        }
+    }
+    if creating {
+        if let Some(missing) = fields.iter().find(|f| f.required && !f.retired && !values.contains_key(&f.key)) {
+            return Err(AppError::validation("custom_fields", format!("{} is required", missing.label)));
+        }
+    }
+    Ok(values)
+}
+
+/// Adds search conditions on `alias.custom_fields` for each filter. A text
+/// filter matches a case-insensitive substring; number and date filters take
+/// a value or `{ "min": …, "max": … }`; enum and boolean filters match
+/// exactly (a `false` filter also matches records without the value).
+pub fn push_search_conditions(
+    conn: &Connection,
+    profile: &str,
+    entity: CustomFieldEntity,
+    alias: &str,
+    filters: &CustomValues,
+    conditions: &mut Vec<String>,
+    bind_values: &mut Vec<Box<dyn ToSql>>,
+) -> Result<(), AppError> {
+    if filters.is_empty() {
+        return Ok(());
+    }
+    let fields = list(conn, profile, Some(entity), true)?;
+    for (key, filter) in filters {
+        let field = fields.iter().find(|f| &f.key == key).ok_or_else(|| {
+            AppError::validation("custom_fields", format!("'{}' is not a {} field in this lab", key, entity.as_str()))
+        })?;
+        let bad = |expected: &str| AppError::validation("custom_fields", format!("Filter on {}: expected {}", field.label, expected));
+        // The key was checked by `is_key` when the field was defined, but the
+        // path is bound, not interpolated, all the same.
+        bind_values.push(Box::new(format!("$.{}", key)));
+        let column = format!("json_extract({}.custom_fields, ?{})", alias, bind_values.len());
+        let mut bind = |value: Box<dyn ToSql>| {
+            bind_values.push(value);
+            format!("?{}", bind_values.len())
+        };
+        match (field.field_type, filter) {
+            (CustomFieldType::Text, Value::String(s)) => {
+                let p = bind(Box::new(format!("%{}%", s.trim().to_lowercase())));
+                conditions.push(format!("LOWER({}) LIKE {}", column, p));
+            }
+            (CustomFieldType::Boolean, Value::Bool(b)) => {
+                let p = bind(Box::new(*b as i64));
+                conditions.push(format!("COALESCE({}, 0) = {}", column, p));
+            }
+            (CustomFieldType::Enum, Value::String(code)) => {
+                let p = bind(Box::new(code.clone()));
+                conditions.push(format!("{} = {}", column, p));
+            }
+            (CustomFieldType::Number, Value::Number(n)) => {
+                let p = bind(Box::new(n.as_f64().unwrap_or_default()));
+                conditions.push(format!("{} = {}", column, p));
+            }
+            (CustomFieldType::Date, Value::String(d)) => {
+                let p = bind(Box::new(d.clone()));
+                conditions.push(format!("{} = {}", column, p));
+            }
+            (CustomFieldType::Number | CustomFieldType::Date, Value::Object(range)) => {
+                let number = field.field_type == CustomFieldType::Number;
+                for (bound, op) in [("min", ">="), ("max", "<=")] {
+                    let p = match range.get(bound) {
+                        None | Some(Value::Null) => continue,
+                        Some(Value::Number(n)) if number => bind(Box::new(n.as_f64().unwrap_or_default())),
+                        Some(Value::String(d)) if !number => bind(Box::new(d.clone())),
+                        Some(_) => return Err(bad(if number { "numeric min/max" } else { "YYYY-MM-DD min/max" })),
+                    };
+                    conditions.push(format!("{} {} {}", column, op, p));
+                }
+            }
+            (t, _) => {
+                return Err(bad(match t {
+                    CustomFieldType::Text => "text",
+                    CustomFieldType::Number => "a number or { min, max }",
+                    CustomFieldType::Date => "a date or { min, max }",
+                    CustomFieldType::Enum => "an option code",
+                    CustomFieldType::Boolean => "true or false",
+                }));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+    use serde_json::json;
+
+    fn migrated_db() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory DB");
+        run_all(&conn).expect("all migrations must succeed on a fresh in-memory DB");
+        crate::db::migrations::seed_defaults(&conn).unwrap();
+        conn
+    }
+
+    fn field(key: &str, field_type: CustomFieldType) -> SaveCustomFieldRequest {
+        SaveCustomFieldRequest {
+            lab_profile: None,
+            entity: CustomFieldEntity::Specimen,
+            key: key.into(),
+            label: key.replace('_', " "),
+            field_type,
+            unit: None,
+            required: false,
+            sort_order: 0,
+            options: Vec::new(),
+        }
+    }
+
+    fn values(v: Value) -> CustomValues {
+        serde_json::from_value(v).unwrap()
+    }
+
+    fn lab(conn: &Connection) {
+        save(conn, "u1", "plant_tissue_culture", &SaveCustomFieldRequest {
+            options: vec![
+                CustomFieldOption { code: "nodal".into(), label: "Nodal segment".into() },
+                CustomFieldOption { code: "leaf_disc".into(), label: "Leaf disc".into() },
+            ],
+            required: true,
+            ..field("explant_type", CustomFieldType::Enum)
+        })
+        .unwrap();
+        save(conn, "u1", "plant_tissue_culture", &SaveCustomFieldRequest {
+            unit: Some("n".into()),
+            ..field("ploidy", CustomFieldType::Number)
+        })
+        .unwrap();
+        save(conn, "u1", "plant_tissue_culture", &field("collected", CustomFieldType::Date)).unwrap();
+        save(conn, "u1", "plant_tissue_culture", &field("virus_indexed", CustomFieldType::Boolean)).unwrap();
+        save(conn, "u1", "plant_tissue_culture", &field("donor", CustomFieldType::Text)).unwrap();
+    }
+
+    #[test]
+    fn definitions_are_checked() {
+        let field_of = |r: SaveCustomFieldRequest| check_definition(&r).unwrap_err().params()["field"].clone();
+        assert_eq!(field_of(field("Ploidy", CustomFieldType::Number)), "key");
+        assert_eq!(field_of(field("9lives", CustomFieldType::Number)), "key");
+        assert_eq!(field_of(SaveCustomFieldRequest { label: " ".into(), ..field("a", CustomFieldType::Text) }), "label");
+        assert_eq!(field_of(SaveCustomFieldRequest { unit: Some("mm".into()), ..field("a", CustomFieldType::Text) }), "unit");
+        assert_eq!(field_of(field("a", CustomFieldType::Enum)), "options");
+        let twice = vec![
+            CustomFieldOption { code: "x".into(), label: "X".into() },
+            CustomFieldOption { code: "x".into(), label: "Y".into() },
+        ];
+        assert_eq!(field_of(SaveCustomFieldRequest { options: twice, ..field("a", CustomFieldType::Enum) }), "options");
+    }
+
+    #[test]
+    fn save_keeps_the_type_and_retire_keeps_the_definition() {
+        let conn = migrated_db();
+        lab(&conn);
+        let err = save(&conn, "u1", "plant_tissue_culture", &field("ploidy", CustomFieldType::Text)).unwrap_err();
+        assert_eq!(err.params()["field"], "type");
+        assert_eq!(save(&conn, "u1", "no_such_lab", &field("x", CustomFieldType::Text)).unwrap_err().params()["field"], "lab_profile");
+
+        let fields = list(&conn, "plant_tissue_culture", Some(CustomFieldEntity::Specimen), false).unwrap();
+        // The five above and the two WP-98 seeds for acclimatization.
+        assert_eq!(fields.len(), 7);
+        let explant = fields.iter().find(|f| f.key == "explant_type").unwrap();
+        assert_eq!(explant.options.len(), 2);
+        let ploidy = fields.iter().find(|f| f.key == "ploidy").unwrap();
+        retire(&conn, &ploidy.id).unwrap();
+        assert_eq!(list(&conn, "plant_tissue_culture", None, false).unwrap().len(), 6);
+        assert_eq!(list(&conn, "plant_tissue_culture", None, true).unwrap().len(), 7);
+        assert!(list(&conn, "mycology", None, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn values_are_typed_required_and_canonical() {
+        let conn = migrated_db();
+        lab(&conn);
+        let create = |v: Value| apply(&conn, "plant_tissue_culture", CustomFieldEntity::Specimen, &CustomValues::new(), &values(v), true);
+
+        let err = create(json!({ "ploidy": 2 })).unwrap_err();
+        assert!(err.message().contains("explant type is required"), "{}", err.message());
+        let stored = create(json!({
+            "virus_indexed": true, "ploidy": "4", "explant_type": "nodal", "collected": "2026-02-01", "donor": "  D-17 "
+        }))
+        .unwrap();
+        assert_eq!(
+            to_column(&stored).unwrap(),
+            r#"{"collected":"2026-02-01","donor":"D-17","explant_type":"nodal","ploidy":4.0,"virus_indexed":true}"#
+        );
+        for bad in [
+            json!({ "explant_type": "root" }),
+            json!({ "explant_type": "nodal", "ploidy": "many" }),
+            json!({ "explant_type": "nodal", "collected": "01/02/2026" }),
+            json!({ "explant_type": "nodal", "virus_indexed": "yes" }),
+            json!({ "explant_type": "nodal", "height": 3 }),
+        ] {
+            assert_eq!(create(bad.clone()).unwrap_err().params()["field"], "custom_fields", "{bad}");
+        }
+
+        // An update clears with null but may not clear a required field.
+        let updated = apply(&conn, "plant_tissue_culture", CustomFieldEntity::Specimen, &stored, &values(json!({ "donor": null })), false).unwrap();
+        assert!(!updated.contains_key("donor"));
+        assert!(apply(&conn, "plant_tissue_culture", CustomFieldEntity::Specimen, &stored, &values(json!({ "explant_type": null })), false).is_err());
+    }
+
+    #[test]
+    fn search_conditions_filter_by_type() {
+        let conn = migrated_db();
+        lab(&conn);
+        conn.execute("INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp1', 'Citrus', 'sinensis', 'CIT')", []).unwrap();
+        for (id, custom) in [
+            ("a", r#"{"explant_type":"nodal","ploidy":2,"donor":"Orchard D-17","virus_indexed":true}"#),
+            ("b", r#"{"explant_type":"leaf_disc","ploidy":4,"collected":"2026-03-01"}"#),
+            ("c", r#"{"explant_type":"nodal","ploidy":6}"#),
+        ] {
+            conn.execute(
+                "INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, custom_fields) \
+                 VALUES (?1, ?1, 'sp1', 'explant', '2026-01-01', ?2)",
+                params![id, custom],
+            )
+            .unwrap();
+        }
+        let search = |filters: Value| -> Vec<String> {
+            let mut conditions = Vec::new();
+            let mut binds: Vec<Box<dyn ToSql>> = Vec::new();
+            push_search_conditions(&conn, "plant_tissue_culture", CustomFieldEntity::Specimen, "s", &values(filters), &mut conditions, &mut binds).unwrap();
+            let sql = format!("SELECT id FROM specimens s WHERE {} ORDER BY id", conditions.join(" AND "));
+            let refs: Vec<&dyn ToSql> = binds.iter().map(|b| b.as_ref()).collect();
+            let mut stmt = conn.prepare(&sql).unwrap();
+            stmt.query_map(refs.as_slice(), |r| r.get(0)).unwrap().map(Result::unwrap).collect()
+        };
+        assert_eq!(search(json!({ "explant_type": "nodal" })), ["a", "c"]);
+        assert_eq!(search(json!({ "ploidy": 4 })), ["b"]);
+        assert_eq!(search(json!({ "ploidy": { "min": 3 } })), ["b", "c"]);
+        assert_eq!(search(json!({ "donor": "d-17" })), ["a"]);
+        assert_eq!(search(json!({ "virus_indexed": false })), ["b", "c"]);
+        assert_eq!(search(json!({ "collected": { "min": "2026-02-01", "max": "2026-03-31" } })), ["b"]);
+        assert_eq!(search(json!({ "explant_type": "nodal", "ploidy": { "max": 2 } })), ["a"]);
+
+        let mut conditions = Vec::new();
+        let mut binds: Vec<Box<dyn ToSql>> = Vec::new();
+        let err = push_search_conditions(&conn, "plant_tissue_culture", CustomFieldEntity::Specimen, "s", &values(json!({ "ploidy": "four" })), &mut conditions, &mut binds);
+        assert_eq!(err.unwrap_err().code(), "validation");
+    }
+
+    #[test]
+    fn plugin_seed_adds_missing_fields_only() {
+        let conn = migrated_db();
+        let mut donor = field("donor_id", CustomFieldType::Text);
+        donor.entity = CustomFieldEntity::Specimen;
+        assert_eq!(seed(&conn, "Cell Line Donors", "cell_culture", std::slice::from_ref(&donor)).unwrap(), 1);
+        assert_eq!(seed(&conn, "Cell Line Donors", "cell_culture", &[donor]).unwrap(), 0);
+        let fields = list(&conn, "cell_culture", None, false).unwrap();
+        assert_eq!(fields[0].source_plugin.as_deref(), Some("Cell Line Donors"));
+    }
+}
@@ -7,6 +7,7 @@ use crate::models::subculture::{
     ContaminantTypeCount, ContaminationStats, CultureMaintenanceAlert, RecentContaminationEvent,
     SubcultureScheduleEntry, VesselContaminationCount, VialLineSummary,
 };
+use crate::error::AppError;
 
 /// WP-63: materialized dashboard cache. Holds the last-computed
 /// `SpecimenStats` + `ContaminationStats` for a given profile, in memory only
@@ -37,9 +38,9 @@ pub fn get_or_refresh_dashboard_cache(
     profile: &str,
     cache: &Mutex<Option<DashboardCacheEntry>>,
     ttl: Duration,
-) -> Result<(SpecimenStats, ContaminationStats), String> {
+) -> Result<(SpecimenStats, ContaminationStats), AppError> {
     {
-        let guard = cache.lock().map_err(|e| e.to_string())?;
+        let guard = cache.lock().map_err(|e| AppError::internal(e.to_string()))?;
         if let Some(entry) = guard.as_ref() {
             if entry.profile == profile && entry.computed_at.elapsed() < ttl {
                 return Ok((entry.specimen_stats.clone(), entry.contamination_stats.clone()));
@@ -50,7 +51,7 @@ pub fn get_or_refresh_dashboard_cache(
     let specimen_stats = query_specimen_stats(conn, profile)?;
     let contamination_stats = query_contamination_stats(conn, profile)?;
 
-    let mut guard = cache.lock().map_err(|e| e.to_string())?;
+    let mut guard = cache.lock().map_err(|e| AppError::internal(e.to_string()))?;
     *guard = Some(DashboardCacheEntry {
         profile: profile.to_string(),
         computed_at: Instant::now(),
@@ -85,38 +86,34 @@ pub fn invalidate_dashboard_cache(cache: &Mutex<Option<DashboardCacheEntry>>) {
 ///
 /// `by_stage` returns vocabulary **labels** (e.g. "Shoot Meristem") rather
 /// than raw stage codes.
-pub fn query_specimen_stats(conn: &Connection, profile: &str) -> Result<SpecimenStats, String> {
+pub fn query_specimen_stats(conn: &Connection, profile: &str) -> Result<SpecimenStats, AppError> {
     let total: i64 = conn
         .query_row(
             "SELECT COUNT(*) FROM specimens sp WHERE sp.lab_profile = ?1",
             [profile],
             |r| r.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let active: i64 = conn
         .query_row(
             "SELECT COUNT(*) FROM specimens sp \
              WHERE sp.lab_profile = ?1 AND sp.is_archived = 0",
             [profile],
             |r| r.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let quarantined: i64 = conn
         .query_row(
             "SELECT COUNT(*) FROM specimens sp \
              WHERE sp.lab_profile = ?1 AND sp.quarantine_flag = 1 AND sp.is_archived = 0",
             [profile],
             |r| r.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let archived: i64 = conn
         .query_row(
             "SELECT COUNT(*) FROM specimens sp \
              WHERE sp.lab_profile = ?1 AND sp.is_archived = 1",
             [profile],
             |r| r.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     // Aggregate first, then attach labels. Joining `stages` before the GROUP BY
     // ran the lookup once per specimen — 100k times to produce ~12 rows. Doing
@@ -136,16 +133,14 @@ pub fn query_specimen_stats(conn: &Connection, profile: &str) -> Result<Specimen
                    GROUP BY stage) g
              LEFT JOIN stages st ON st.code = g.stage AND st.profile = ?1
              ORDER BY g.cnt DESC, COALESCE(st.sort_order, 9999) ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let by_stage: Vec<StageCount> = stage_stmt
         .query_map([profile], |row| {
             Ok(StageCount {
                 stage: row.get(0)?,
                 count: row.get(1)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -164,16 +159,14 @@ pub fn query_specimen_stats(conn: &Connection, profile: &str) -> Result<Specimen
                    GROUP BY species_id) g \
              JOIN species sp_info ON g.species_id = sp_info.id \
              ORDER BY g.cnt DESC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let by_species: Vec<SpeciesCount> = species_stmt
         .query_map([profile], |row| {
             Ok(SpeciesCount {
                 species_code: row.get(0)?,
                 count: row.get(1)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -192,8 +185,7 @@ pub fn query_specimen_stats(conn: &Connection, profile: &str) -> Result<Specimen
              WHERE sc.date >= date('now', '-7 days') AND sp.lab_profile = ?1",
             [profile],
             |r| r.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     Ok(SpecimenStats {
         total_specimens: total,
@@ -213,7 +205,7 @@ pub fn query_specimen_stats(conn: &Connection, profile: &str) -> Result<Specimen
 pub fn query_contamination_stats(
     conn: &Connection,
     profile: &str,
-) -> Result<ContaminationStats, String> {
+) -> Result<ContaminationStats, AppError> {
     let total_specimens: i64 = conn
         .query_row(
             "SELECT COUNT(*) FROM specimens sp
@@ -260,16 +252,14 @@ pub fn query_contamination_stats(
              GROUP BY sc.vessel_type
              ORDER BY cnt DESC
              LIMIT 10",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let by_vessel_type: Vec<VesselContaminationCount> = stmt
         .query_map([profile], |row| {
             Ok(VesselContaminationCount {
                 vessel_type: row.get("vessel_type")?,
                 count: row.get("cnt")?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -283,16 +273,14 @@ pub fn query_contamination_stats(
              GROUP BY sc.contaminant_type
              ORDER BY cnt DESC
              LIMIT 10",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let by_ct_rows = stmt_ct
         .query_map([profile], |row| {
             Ok(ContaminantTypeCount {
                 contaminant_type: row.get("contaminant_type")?,
                 count: row.get("cnt")?,
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
     let by_contaminant_type: Vec<ContaminantTypeCount> =
         by_ct_rows.filter_map(|r| r.ok()).collect();
 
@@ -308,8 +296,7 @@ pub fn query_contamination_stats(
              WHERE sp.lab_profile = ?1 AND sc.contamination_flag = 1
              ORDER BY sc.date DESC
              LIMIT 10",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let stmt2_rows = stmt2
         .query_map([profile], |row| {
             Ok(RecentContaminationEvent {
@@ -323,8 +310,7 @@ pub fn query_contamination_stats(
                 contamination_notes: row.get("contamination_notes")?,
                 contaminant_type: row.get("contaminant_type")?,
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
     let recent_events: Vec<RecentContaminationEvent> =
         stmt2_rows.filter_map(|r| r.ok()).collect();
 
@@ -344,7 +330,7 @@ pub fn query_contamination_stats(
 pub fn query_subculture_schedule(
     conn: &Connection,
     profile: &str,
-) -> Result<Vec<SubcultureScheduleEntry>, String> {
+) -> Result<Vec<SubcultureScheduleEntry>, AppError> {
     let mut stmt = conn
         .prepare(
             "SELECT
@@ -377,8 +363,7 @@ pub fn query_subculture_schedule(
              WHERE sp.lab_profile = ?1 AND sp.is_archived = 0
              GROUP BY sp.id
              ORDER BY days_until_due ASC NULLS LAST",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let entries: Vec<SubcultureScheduleEntry> = stmt
         .query_map([profile], |row| {
@@ -396,8 +381,7 @@ pub fn query_subculture_schedule(
                 days_until_due,
                 is_overdue,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -407,7 +391,7 @@ pub fn query_subculture_schedule(
 /// Returns frozen-vial inventory aggregated by species/cell-line.
 /// Only `active` lots are counted; `depleted` and `discarded` lots are excluded.
 /// Results are ordered by total_vials ascending so low-stock lines appear first.
-pub fn query_vial_summary_by_line(conn: &Connection) -> Result<Vec<VialLineSummary>, String> {
+pub fn query_vial_summary_by_line(conn: &Connection) -> Result<Vec<VialLineSummary>, AppError> {
     let mut stmt = conn
         .prepare(
             "SELECT fv.species_id,
@@ -421,8 +405,7 @@ pub fn query_vial_summary_by_line(conn: &Connection) -> Result<Vec<VialLineSumma
              WHERE fv.status = 'active'
              GROUP BY fv.species_id
              ORDER BY total_vials ASC, sp.species_code ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let items: Vec<VialLineSummary> = stmt
         .query_map([], |row| {
@@ -434,8 +417,7 @@ pub fn query_vial_summary_by_line(conn: &Connection) -> Result<Vec<VialLineSumma
                 total_vials: row.get("total_vials")?,
                 min_vials_in_lot: row.get("min_vials_in_lot")?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -451,7 +433,7 @@ pub fn query_vial_summary_by_line(conn: &Connection) -> Result<Vec<VialLineSumma
 pub fn query_culture_maintenance_alerts(
     conn: &Connection,
     profile: &str,
-) -> Result<Vec<CultureMaintenanceAlert>, String> {
+) -> Result<Vec<CultureMaintenanceAlert>, AppError> {
     let mut stmt = conn
         .prepare(
             "SELECT
@@ -473,8 +455,7 @@ pub fn query_culture_maintenance_alerts(
                          AS INTEGER) >= 7
              ORDER BY days_since_passage DESC
              LIMIT 20",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     let items: Vec<CultureMaintenanceAlert> = stmt
         .query_map([profile], |row| {
@@ -487,8 +468,7 @@ pub fn query_culture_maintenance_alerts(
                 last_passage_date: row.get("last_passage_date")?,
                 days_since_passage: row.get("days_since_passage")?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
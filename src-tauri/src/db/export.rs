@@ -0,0 +1,400 @@
+// Specimen CSV / JSON export rows, shared by the export commands and
+// `stelo-cli export`.
+use crate::db::custom_fields::{self, CustomField, CustomFieldEntity, CustomFieldType, CustomValues};
+use crate::db::permissions::{mask_for_role, FieldPermissionSet, Maskable};
+use crate::db::specimen_query;
+use crate::error::AppError;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize)]
+struct ExportSpecimen {
+    accession_number: String,
+    species_code: String,
+    species_name: String,
+    stage: String,
+    custom_stage: Option<String>,
+    provenance: Option<String>,
+    source_plant: Option<String>,
+    initiation_date: String,
+    location: Option<String>,
+    location_details: Option<String>,
+    propagation_method: Option<String>,
+    acclimatization_status: Option<String>,
+    health_status: Option<String>,
+    disease_status: Option<String>,
+    quarantine_flag: bool,
+    quarantine_release_date: Option<String>,
+    permit_number: Option<String>,
+    permit_expiry: Option<String>,
+    ip_flag: bool,
+    ip_notes: Option<String>,
+    environmental_notes: Option<String>,
+    subculture_count: i32,
+    parent_specimen_id: Option<String>,
+    notes: Option<String>,
+    employee_id: Option<String>,
+    created_by: Option<String>,
+    created_at: String,
+    updated_at: String,
+    /// WP-96: keyed by field key; one CSV column per field.
+    custom_fields: CustomValues,
+}
+
+const EXPORT_SQL: &str =
+    "SELECT s.accession_number, sp.species_code,
+            sp.genus || ' ' || sp.species_name as species_name,
+            s.stage, s.custom_stage, s.provenance, s.source_plant,
+            s.initiation_date, s.location, s.location_details,
+            s.propagation_method, s.acclimatization_status,
+            s.health_status, s.disease_status,
+            s.quarantine_flag, s.quarantine_release_date,
+            s.permit_number, s.permit_expiry,
+            s.ip_flag, s.ip_notes, s.environmental_notes,
+            s.subculture_count, s.parent_specimen_id,
+            s.notes, s.employee_id, s.created_by, s.created_at, s.updated_at,
+            s.custom_fields
+     FROM specimens s
+     LEFT JOIN species sp ON s.species_id = sp.id
+     WHERE s.is_archived = 0 AND s.lab_profile = ?1";
+
+fn map_export_row(row: &rusqlite::Row) -> rusqlite::Result<ExportSpecimen> {
+    Ok(ExportSpecimen {
+        accession_number: row.get(0)?,
+        species_code: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+        species_name: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+        stage: row.get(3)?,
+        custom_stage: row.get(4)?,
+        provenance: row.get(5)?,
+        source_plant: row.get(6)?,
+        initiation_date: row.get(7)?,
+        location: row.get(8)?,
+        location_details: row.get(9)?,
+        propagation_method: row.get(10)?,
+        acclimatization_status: row.get(11)?,
+        health_status: row.get(12)?,
+        disease_status: row.get(13)?,
+        quarantine_flag: row.get::<_, i32>(14)? != 0,
+        quarantine_release_date: row.get(15)?,
+        permit_number: row.get(16)?,
+        permit_expiry: row.get(17)?,
+        ip_flag: row.get::<_, i32>(18)? != 0,
+        ip_notes: row.get(19)?,
+        environmental_notes: row.get(20)?,
+        subculture_count: row.get(21)?,
+        parent_specimen_id: row.get(22)?,
+        notes: row.get(23)?,
+        employee_id: row.get(24)?,
+        created_by: row.get(25)?,
+        created_at: row.get(26)?,
+        updated_at: row.get(27)?,
+        custom_fields: custom_fields::from_column(row.get::<_, Option<String>>(28)?.as_deref()),
+    })
+}
+
+impl Maskable for ExportSpecimen {
+    fn mask_json(perms: &FieldPermissionSet, value: &mut Value) {
+        perms.mask_object("specimen", value);
+    }
+}
+
+/// CSV header and the `ExportSpecimen` key each column reads, in file order.
+const CSV_COLUMNS: &[(&str, &str)] = &[
+    ("Accession", "accession_number"),
+    ("Species Code", "species_code"),
+    ("Species", "species_name"),
+    ("Stage", "stage"),
+    ("Custom Stage", "custom_stage"),
+    ("Provenance", "provenance"),
+    ("Source Plant", "source_plant"),
+    ("Initiation Date", "initiation_date"),
+    ("Location", "location"),
+    ("Location Details", "location_details"),
+    ("Propagation Method", "propagation_method"),
+    ("Acclimatization Status", "acclimatization_status"),
+    ("Health Status", "health_status"),
+    ("Disease Status", "disease_status"),
+    ("Quarantine", "quarantine_flag"),
+    ("Quarantine Release", "quarantine_release_date"),
+    ("Permit Number", "permit_number"),
+    ("Permit Expiry", "permit_expiry"),
+    ("IP Flag", "ip_flag"),
+    ("IP Notes", "ip_notes"),
+    ("Environmental Notes", "environmental_notes"),
+    ("Subculture Count", "subculture_count"),
+    ("Parent Specimen", "parent_specimen_id"),
+    ("Notes", "notes"),
+    ("Employee ID", "employee_id"),
+    ("Created By", "created_by"),
+    ("Created At", "created_at"),
+    ("Updated At", "updated_at"),
+];
+
+/// Loads the active lab's export rows and serializes them with the caller's
+/// field rules applied (WP-87), so the CSV and JSON exports mask exactly what
+/// the specimen screens do.
+///
+/// Exports carry the active lab only. Without this a mycology lab's CSV
+/// included every plant tissue culture and cell culture specimen in the
+/// database — data its operators cannot see anywhere else in the UI, being
+/// handed to whoever the file is sent to.
+///
+/// `filter` narrows the export with a specimen filter (WP-97), checked
+/// against `role` like a search.
+pub fn masked_export_rows(conn: &rusqlite::Connection, role: &str, filter: Option<&str>) -> Result<Value, AppError> {
+    let profile = crate::db::vocabulary::active_profile(conn);
+    let mut conditions = Vec::new();
+    let mut binds: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(profile)];
+    if let Some(text) = filter.filter(|t| !t.trim().is_empty()) {
+        specimen_query::compile_for_active_lab(conn, role, text)?.push_condition(&mut conditions, &mut binds);
+    }
+    let sql = conditions.iter().fold(EXPORT_SQL.to_string(), |sql, c| format!("{} AND {}", sql, c));
+    let refs: Vec<&dyn rusqlite::types::ToSql> = binds.iter().map(|b| b.as_ref()).collect();
+    let mut stmt = conn.prepare(&format!("{} ORDER BY s.accession_number", sql))?;
+    let specimens: Vec<ExportSpecimen> = stmt
+        .query_map(refs.as_slice(), map_export_row)?
+        .filter_map(|r| r.ok())
+        .collect();
+    serde_json::to_value(mask_for_role(conn, role, specimens)?).map_err(|e| AppError::internal(e.to_string()))
+}
+
+/// The active lab's export as CSV: [`masked_export_rows`] rendered by
+/// [`rows_to_csv`] with a column for each of the lab's custom specimen
+/// fields, retired ones included since their values are still stored.
+pub fn masked_export_csv(conn: &rusqlite::Connection, role: &str, filter: Option<&str>) -> Result<String, AppError> {
+    let rows = masked_export_rows(conn, role, filter)?;
+    let profile = crate::db::vocabulary::active_profile(conn);
+    let custom = custom_fields::list(conn, &profile, Some(CustomFieldEntity::Specimen), true)?;
+    Ok(rows_to_csv(&rows, &custom))
+}
+
+fn csv_cell(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::Bool(flag)) => if *flag { "Yes" } else { "No" }.to_string(),
+        Some(Value::String(text)) => escape_csv(text),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Renders serialized export rows as CSV, one [`CSV_COLUMNS`] entry per cell,
+/// then one column per `custom` field headed by its label (and unit).
+pub fn rows_to_csv(rows: &Value, custom: &[CustomField]) -> String {
+    let mut header: Vec<String> = CSV_COLUMNS.iter().map(|(title, _)| title.to_string()).collect();
+    header.extend(custom.iter().map(|f| {
+        let title = match &f.unit {
+            Some(unit) => format!("{} ({})", f.label, unit),
+            None => f.label.clone(),
+        };
+        escape_csv(&title)
+    }));
+    let mut csv = header.join(",");
+    csv.push('\n');
+    for row in rows.as_array().into_iter().flatten() {
+        let mut cells: Vec<String> = CSV_COLUMNS.iter().map(|(_, key)| csv_cell(row.get(*key))).collect();
+        cells.extend(custom.iter().map(|f| {
+            let value = row.get("custom_fields").and_then(|c| c.get(&f.key));
+            // An enum exports its label, as the forms show it.
+            match (f.field_type, value) {
+                (CustomFieldType::Enum, Some(Value::String(code))) => {
+                    let label = f.options.iter().find(|o| &o.code == code).map_or(code, |o| &o.label);
+                    escape_csv(label)
+                }
+                _ => csv_cell(value),
+            }
+        }));
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// RFC 4180 quoting **plus** spreadsheet formula neutralisation.
+///
+/// Excel, LibreOffice and Google Sheets treat a leading `=`, `+`, `-`, `@`, TAB
+/// or CR as the start of a formula and evaluate it when the file is opened.
+/// Quoting does **not** suppress this — `"=cmd|'/c calc'!A1"` is still executed.
+/// Since specimen notes, provenance and location are free text written by any
+/// user with write access, and exports are the artefact most likely to be sent
+/// outside the lab, the leading character has to be defused explicitly.
+///
+/// Prefixing an apostrophe forces text interpretation in every major
+/// spreadsheet and is not rendered in the cell. The cell must then also be
+/// quoted, or the apostrophe itself can perturb parsing.
+fn escape_csv(s: &str) -> String {
+    const FORMULA_LEAD: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+    let needs_defusing = s.starts_with(FORMULA_LEAD);
+    let needs_quoting = needs_defusing || s.contains(',') || s.contains('"') || s.contains('\n');
+
+    let body = if needs_defusing {
+        format!("'{}", s)
+    } else {
+        s.to_string()
+    };
+
+    if needs_quoting {
+        format!("\"{}\"", body.replace('"', "\"\""))
+    } else {
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_header_is_unchanged() {
+        let csv = rows_to_csv(&Value::Array(Vec::new()), &[]);
+        assert_eq!(
+            csv,
+            "Accession,Species Code,Species,Stage,Custom Stage,Provenance,Source Plant,\
+Initiation Date,Location,Location Details,Propagation Method,Acclimatization Status,\
+Health Status,Disease Status,Quarantine,Quarantine Release,Permit Number,Permit Expiry,\
+IP Flag,IP Notes,Environmental Notes,Subculture Count,Parent Specimen,\
+Notes,Employee ID,Created By,Created At,Updated At\n"
+        );
+    }
+
+    #[test]
+    fn csv_and_json_exports_mask_the_same_fields() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_all(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, provenance, \
+             permit_number, ip_flag, subculture_count) \
+             VALUES ('s1', 'CIT-001', 'sp', 'explant', '2026-01-01', 'Field site 7', 'P-42', 1, 3)",
+            [],
+        )
+        .unwrap();
+        crate::db::permissions::set_field_permission(&conn, "guest", "specimen", "provenance", false).unwrap();
+
+        let rows = masked_export_rows(&conn, "guest", None).unwrap();
+        assert_eq!(rows[0]["provenance"], crate::db::permissions::RESTRICTED_MARKER);
+        assert_eq!(rows[0]["permit_number"], "P-42");
+
+        let csv = rows_to_csv(&rows, &[]);
+        let line = csv.lines().nth(1).unwrap();
+        assert!(!csv.contains("Field site 7"), "the CSV must not carry a hidden value: {line}");
+        assert!(line.contains("[RESTRICTED]") && line.contains("P-42"), "{line}");
+        assert!(line.contains(",Yes,") && line.contains(",3,"), "flags and counts keep their format: {line}");
+
+        let admin = masked_export_rows(&conn, "admin", None).unwrap();
+        assert_eq!(admin[0]["provenance"], "Field site 7");
+    }
+
+    #[test]
+    fn custom_fields_export_as_json_and_as_csv_columns() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_all(&conn).unwrap();
+        crate::db::migrations::seed_defaults(&conn).unwrap();
+        let define = |v: Value| {
+            custom_fields::save(&conn, "u1", "plant_tissue_culture", &serde_json::from_value(v).unwrap()).unwrap()
+        };
+        define(serde_json::json!({ "entity": "specimen", "key": "ploidy", "label": "Ploidy", "type": "number", "unit": "n" }));
+        define(serde_json::json!({
+            "entity": "specimen", "key": "explant_type", "label": "Explant type", "type": "enum",
+            "options": [{ "code": "nodal", "label": "Nodal segment" }]
+        }));
+        conn.execute(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, custom_fields) \
+             VALUES ('s1', 'CIT-001', 'sp', 'explant', '2026-01-01', '{\"explant_type\":\"nodal\",\"ploidy\":4.0}')",
+            [],
+        )
+        .unwrap();
+
+        let rows = masked_export_rows(&conn, "admin", None).unwrap();
+        assert_eq!(rows[0]["custom_fields"]["ploidy"], 4.0);
+        let csv = masked_export_csv(&conn, "admin", None).unwrap();
+        let mut lines = csv.lines();
+        // The acclimatization fields are seeded for the plant profile (WP-98).
+        assert!(lines.next().unwrap().ends_with(",Updated At,Explant type,Ploidy (n),Acclimatization date,Survival count (plants)"));
+        assert!(lines.next().unwrap().ends_with(",Nodal segment,4.0,,"));
+    }
+
+    #[test]
+    fn a_filter_narrows_the_export() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_all(&conn).unwrap();
+        crate::db::migrations::seed_defaults(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT');
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, generation) VALUES
+                 ('s1', 'CIT-001', 'sp', 'explant', '2026-01-01', 2),
+                 ('s2', 'CIT-002', 'sp', 'explant', '2026-01-01', 6);",
+        )
+        .unwrap();
+
+        let rows = masked_export_rows(&conn, "admin", Some("generation >= 5")).unwrap();
+        assert_eq!(rows.as_array().unwrap().len(), 1);
+        assert_eq!(rows[0]["accession_number"], "CIT-002");
+        assert_eq!(masked_export_csv(&conn, "admin", Some(" ")).unwrap().lines().count(), 3);
+        let err = masked_export_rows(&conn, "admin", Some("generation >= 'x'")).unwrap_err();
+        assert_eq!(err.code(), "validation");
+    }
+
+    #[test]
+    fn plain_values_pass_through_unquoted() {
+        assert_eq!(escape_csv("PTC-001"), "PTC-001");
+        assert_eq!(escape_csv("Healthy shoot culture"), "Healthy shoot culture");
+        assert_eq!(escape_csv(""), "");
+    }
+
+    #[test]
+    fn rfc4180_quoting_is_preserved() {
+        assert_eq!(escape_csv("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv("line1\nline2"), "\"line1\nline2\"");
+    }
+
+    #[test]
+    fn formula_leads_are_defused() {
+        // The attack: a specimen note that Excel executes on open. Quoting alone
+        // does NOT stop this, which is why the apostrophe prefix is required.
+        assert_eq!(escape_csv("=cmd|'/c calc'!A1"), "\"'=cmd|'/c calc'!A1\"");
+        assert_eq!(escape_csv("=1+1"), "\"'=1+1\"");
+        assert_eq!(escape_csv("+1"), "\"'+1\"");
+        assert_eq!(escape_csv("-2+3"), "\"'-2+3\"");
+        assert_eq!(escape_csv("@SUM(A1:A9)"), "\"'@SUM(A1:A9)\"");
+        assert_eq!(escape_csv("\tTAB"), "\"'\tTAB\"");
+        assert_eq!(escape_csv("\rCR"), "\"'\rCR\"");
+    }
+
+    #[test]
+    fn every_defused_value_is_also_quoted() {
+        // A bare apostrophe prefix without quoting can perturb parsers that
+        // treat a leading quote character specially, so the two go together.
+        for evil in ["=x", "+x", "-x", "@x", "\tx", "\rx"] {
+            let out = escape_csv(evil);
+            assert!(out.starts_with("\"'"), "{evil:?} produced {out:?}");
+            assert!(out.ends_with('"'), "{evil:?} produced {out:?}");
+        }
+    }
+
+    #[test]
+    fn formula_characters_inside_a_value_are_left_alone() {
+        // Only the LEADING character triggers formula parsing, so a legitimate
+        // note containing an equals sign must not be mangled.
+        assert_eq!(escape_csv("pH=5.8"), "pH=5.8");
+        assert_eq!(escape_csv("2 - 3 days"), "2 - 3 days");
+        assert_eq!(escape_csv("stock@4C"), "stock@4C");
+    }
+
+    #[test]
+    fn a_negative_number_is_defused_but_still_readable() {
+        // Accepted trade-off: a genuine negative value gains a leading
+        // apostrophe. It renders identically in the cell, and treating "-" as
+        // safe would reopen the hole for "-2+3+cmd|..." style payloads.
+        assert_eq!(escape_csv("-5"), "\"'-5\"");
+    }
+}
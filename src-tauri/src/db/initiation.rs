@@ -0,0 +1,275 @@
+// WP-94: initiating a batch of specimens from one template.
+//
+// A new line is typically 50–200 explants from one source plant. The batch is
+// planned first (size, plate wells, per-row overrides), and every check runs
+// before anything is written. It is then inserted in one transaction through
+// `db::specimens::insert_specimen`, the insert `create_specimen` uses. Each
+// member therefore gets its own genesis audit entry and signed
+// `specimen_created` event, and a failure on row 137 leaves nothing behind.
+use crate::db::permissions::reject_if_restricted_marker;
+use crate::db::specimens;
+use crate::error::AppError;
+use crate::models::specimen::{BatchLabel, CreateSpecimenRequest, InitiateBatchRequest, InitiationBatch, PlateLayout};
+use rusqlite::Connection;
+use std::collections::HashSet;
+
+/// Upper bound on one batch. Large enough for two 96-well plates plus spares,
+/// small enough that one transaction stays short.
+pub const MAX_BATCH: u32 = 500;
+/// Rows are lettered A–Z.
+pub const MAX_PLATE_ROWS: u32 = 26;
+pub const MAX_PLATE_COLUMNS: u32 = 48;
+
+/// The well at 0-based `index` of a plate `columns` wide: 0 → `A1`, `columns` → `B1`.
+pub fn well_name(index: u32, columns: u32) -> String {
+    let row = char::from(b'A' + (index / columns) as u8);
+    format!("{}{}", row, index % columns + 1)
+}
+
+/// One specimen of a planned batch.
+#[derive(Debug)]
+pub struct PlannedRow {
+    /// 1-based, in accession order.
+    pub position: u32,
+    pub well: Option<String>,
+    pub request: CreateSpecimenRequest,
+}
+
+/// Expands a batch request into one create request per specimen: the template,
+/// the row's well appended to its location details, then the row's overrides.
+pub fn plan(request: &InitiateBatchRequest) -> Result<Vec<PlannedRow>, AppError> {
+    let size = match (request.count, request.plate) {
+        (None, None) => return Err(AppError::validation("count", "Give either a count or a plate layout")),
+        (Some(count), None) => count,
+        (count, Some(PlateLayout { rows, columns })) => {
+            if rows == 0 || rows > MAX_PLATE_ROWS || columns == 0 || columns > MAX_PLATE_COLUMNS {
+                return Err(AppError::validation(
+                    "plate",
+                    format!("A plate has 1–{} rows and 1–{} columns", MAX_PLATE_ROWS, MAX_PLATE_COLUMNS),
+                ));
+            }
+            let wells = rows * columns;
+            if count.is_some_and(|c| c != wells) {
+                return Err(AppError::validation(
+                    "count",
+                    format!("A {}×{} plate has {} wells; leave the count empty or set it to {}", rows, columns, wells, wells),
+                ));
+            }
+            wells
+        }
+    };
+    if size == 0 || size > MAX_BATCH {
+        return Err(AppError::validation("count", format!("A batch holds 1–{} specimens", MAX_BATCH)));
+    }
+
+    let mut seen = HashSet::new();
+    for o in &request.overrides {
+        if o.position == 0 || o.position > size {
+            return Err(AppError::validation(
+                "overrides",
+                format!("Override position {} is outside the batch (1–{})", o.position, size),
+            ));
+        }
+        if !seen.insert(o.position) {
+            return Err(AppError::validation("overrides", format!("Position {} is overridden twice", o.position)));
+        }
+        // WP-87: an override copied from a masked read must not store the placeholder.
+        reject_if_restricted_marker(o.provenance.as_deref(), "Provenance")?;
+        reject_if_restricted_marker(o.source_plant.as_deref(), "Source plant")?;
+    }
+
+    Ok((1..=size)
+        .map(|position| {
+            let mut row = request.template.clone();
+            let o = request.overrides.iter().find(|o| o.position == position);
+            if let Some(o) = o {
+                let keep = |value: &Option<String>, base: &mut Option<String>| {
+                    if value.is_some() {
+                        base.clone_from(value);
+                    }
+                };
+                keep(&o.location, &mut row.location);
+                keep(&o.location_details, &mut row.location_details);
+                keep(&o.provenance, &mut row.provenance);
+                keep(&o.source_plant, &mut row.source_plant);
+                keep(&o.health_status, &mut row.health_status);
+                keep(&o.environmental_notes, &mut row.environmental_notes);
+                keep(&o.notes, &mut row.notes);
+                keep(&o.employee_id, &mut row.employee_id);
+            }
+            let well = request.plate.map(|p| well_name(position - 1, p.columns));
+            if let Some(ref w) = well {
+                row.location_details = Some(match row.location_details.as_deref().map(str::trim) {
+                    Some(details) if !details.is_empty() => format!("{}, well {}", details, w),
+                    _ => format!("Well {}", w),
+                });
+            }
+            PlannedRow { position, well, request: row }
+        })
+        .collect())
+}
+
+/// Creates every specimen of the batch in one transaction, numbered with a
+/// contiguous accession range, and returns their labels in accession order.
+pub fn initiate_batch(conn: &Connection, user_id: &str, request: &InitiateBatchRequest) -> Result<InitiationBatch, AppError> {
+    let profile = crate::db::vocabulary::active_profile(conn);
+    let species_code = specimens::check_create_request(conn, &profile, &request.template)?;
+    let rows = plan(request)?;
+    let strain_chain_seq = specimens::strain_chain_seq(conn, request.template.strain_id.as_deref());
+
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| AppError::internal(format!("Failed to start transaction: {}", e)))?;
+    let accessions = crate::db::accession::allocate(
+        &tx, &profile, &request.template.species_id, &species_code, &request.template.initiation_date, rows.len(),
+    )?;
+    let batch_id = uuid::Uuid::new_v4().to_string();
+    let size = rows.len();
+
+    let mut labels = Vec::with_capacity(size);
+    for (row, accession) in rows.into_iter().zip(accessions) {
+        let id = uuid::Uuid::new_v4().to_string();
+        let note = format!("batch {} position {} of {}", batch_id, row.position, size);
+        specimens::insert_specimen(&tx, user_id, &profile, &row.request, &id, &accession, strain_chain_seq, Some(&note))?;
+        labels.push(BatchLabel {
+            position: row.position,
+            well: row.well,
+            specimen_id: id,
+            qr_code_data: format!("STELO:{}", accession),
+            accession_number: accession,
+            species_code: species_code.clone(),
+            stage: row.request.stage,
+            initiation_date: row.request.initiation_date,
+            location: row.request.location,
+        });
+    }
+    tx.commit().map_err(|e| AppError::internal(format!("Failed to commit batch: {}", e)))?;
+
+    Ok(InitiationBatch {
+        batch_id,
+        first_accession: labels[0].accession_number.clone(),
+        last_accession: labels[size - 1].accession_number.clone(),
+        labels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+    use crate::db::queries;
+    use crate::models::specimen::BatchRowOverride;
+
+    fn migrated_db() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory DB");
+        run_all(&conn).expect("all migrations must succeed on a fresh in-memory DB");
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'tech1', 'x', 'T', 'tech')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp1', 'Citrus', 'sinensis', 'CIT')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn template() -> CreateSpecimenRequest {
+        serde_json::from_value(serde_json::json!({
+            "species_id": "sp1",
+            "stage": "explant",
+            "initiation_date": "2026-03-02",
+            "location": "Hood 1",
+            "source_plant": "Mother tree 7",
+        }))
+        .unwrap()
+    }
+
+    fn batch(count: Option<u32>, plate: Option<PlateLayout>, overrides: Vec<BatchRowOverride>) -> InitiateBatchRequest {
+        InitiateBatchRequest { template: template(), count, plate, overrides }
+    }
+
+    #[test]
+    fn wells_fill_row_by_row() {
+        assert_eq!(well_name(0, 12), "A1");
+        assert_eq!(well_name(11, 12), "A12");
+        assert_eq!(well_name(12, 12), "B1");
+        assert_eq!(well_name(95, 12), "H12");
+    }
+
+    #[test]
+    fn plan_rejects_bad_sizes_and_overrides() {
+        let field = |r: Result<Vec<PlannedRow>, AppError>| r.unwrap_err().params()["field"].clone();
+        assert_eq!(field(plan(&batch(None, None, vec![]))), "count");
+        assert_eq!(field(plan(&batch(Some(0), None, vec![]))), "count");
+        assert_eq!(field(plan(&batch(Some(MAX_BATCH + 1), None, vec![]))), "count");
+        assert_eq!(field(plan(&batch(Some(10), Some(PlateLayout { rows: 8, columns: 12 }), vec![]))), "count");
+        assert_eq!(field(plan(&batch(None, Some(PlateLayout { rows: 27, columns: 1 }), vec![]))), "plate");
+        let at = |position| BatchRowOverride { position, ..Default::default() };
+        assert_eq!(field(plan(&batch(Some(3), None, vec![at(4)]))), "overrides");
+        assert_eq!(field(plan(&batch(Some(3), None, vec![at(2), at(2)]))), "overrides");
+    }
+
+    #[test]
+    fn plan_applies_wells_then_overrides() {
+        let mut request = batch(None, Some(PlateLayout { rows: 2, columns: 3 }), vec![BatchRowOverride {
+            position: 5,
+            location: Some("Hood 2".into()),
+            location_details: Some("Tray 4".into()),
+            ..Default::default()
+        }]);
+        request.template.location_details = Some("Tray 3".into());
+        let rows = plan(&request).unwrap();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0].well.as_deref(), Some("A1"));
+        assert_eq!(rows[0].request.location_details.as_deref(), Some("Tray 3, well A1"));
+        assert_eq!(rows[4].well.as_deref(), Some("B2"));
+        assert_eq!(rows[4].request.location.as_deref(), Some("Hood 2"));
+        assert_eq!(rows[4].request.location_details.as_deref(), Some("Tray 4, well B2"));
+        assert_eq!(rows[5].request.location.as_deref(), Some("Hood 1"));
+    }
+
+    #[test]
+    fn batch_gets_a_contiguous_range_and_a_genesis_entry_each() {
+        let conn = migrated_db();
+        let single = queries::generate_accession_number(&conn, "CIT", "2026-03-02").unwrap();
+        assert_eq!(single, "2026-03-02-CIT-001");
+
+        let result = initiate_batch(&conn, "u1", &batch(Some(4), None, vec![])).unwrap();
+        assert_eq!(result.first_accession, "2026-03-02-CIT-001");
+        assert_eq!(result.last_accession, "2026-03-02-CIT-004");
+        let accessions: Vec<_> = result.labels.iter().map(|l| l.accession_number.as_str()).collect();
+        assert_eq!(accessions, ["2026-03-02-CIT-001", "2026-03-02-CIT-002", "2026-03-02-CIT-003", "2026-03-02-CIT-004"]);
+        assert_eq!(result.labels[2].qr_code_data, "STELO:2026-03-02-CIT-003");
+
+        let genesis: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM audit_log WHERE entity_type = 'specimen' AND action = 'create' AND details LIKE ?1",
+                [format!("%batch {}%", result.batch_id)],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(genesis, 4);
+
+        // The next batch continues the range.
+        let next = initiate_batch(&conn, "u1", &batch(Some(2), None, vec![])).unwrap();
+        assert_eq!(next.first_accession, "2026-03-02-CIT-005");
+    }
+
+    #[test]
+    fn a_taken_number_in_the_range_writes_nothing() {
+        let conn = migrated_db();
+        conn.execute(
+            "INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, lab_profile) \
+             VALUES ('x', '2026-03-02-CIT-003', 'sp1', 'explant', '2026-03-02', 'plant_tissue_culture')",
+            [],
+        )
+        .unwrap();
+        let err = initiate_batch(&conn, "u1", &batch(Some(5), None, vec![])).unwrap_err();
+        assert_eq!(err.code(), "conflict");
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM specimens", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+}
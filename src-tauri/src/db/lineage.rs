@@ -0,0 +1,625 @@
+// WP-101: a specimen's lineage as a graph. `get_specimen_family` returns the
+// family as a flat list; for papers and IP disputes the lineage is exported
+// as GraphML, DOT or Newick with per-node attributes, and summarised with
+// lineage metrics: branching, survival per generation, time to loss, and
+// which branches still have living descendants.
+//
+// A node's status follows from the row: active specimens are `alive`, an
+// archived specimen with children was `split`, one with a death event or
+// health 0 is `dead`, and any other archived one is `archived`. Dead and
+// archived nodes are *lost*. Merge tombstones (WP-99) are left out: their
+// children already hang off the survivor.
+use crate::error::AppError;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+    Alive,
+    Split,
+    Dead,
+    Archived,
+}
+
+impl NodeStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            NodeStatus::Alive => "alive",
+            NodeStatus::Split => "split",
+            NodeStatus::Dead => "dead",
+            NodeStatus::Archived => "archived",
+        }
+    }
+
+    fn is_lost(self) -> bool {
+        matches!(self, NodeStatus::Dead | NodeStatus::Archived)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageNode {
+    pub id: String,
+    pub accession_number: String,
+    /// `None` for the root, and for a node whose parent is not in the family.
+    pub parent_id: Option<String>,
+    pub generation: i64,
+    pub passage_offset: i64,
+    pub subculture_count: i64,
+    pub health_status: Option<String>,
+    pub contaminated: bool,
+    pub stage: String,
+    pub species_code: Option<String>,
+    pub initiation_date: String,
+    pub status: NodeStatus,
+    /// Date of death, or of the archive, for lost nodes.
+    pub lost_on: Option<String>,
+    pub days_to_loss: Option<i64>,
+    /// Alive specimens below this one, not counting itself.
+    pub living_descendants: usize,
+}
+
+/// One family, parents before children.
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageGraph {
+    pub root_id: String,
+    pub nodes: Vec<LineageNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Branching {
+    /// Nodes with at least one child.
+    pub internal_nodes: usize,
+    pub mean_children: f64,
+    pub max_children: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationSurvival {
+    pub generation: i64,
+    pub total: usize,
+    pub alive: usize,
+    pub split: usize,
+    pub dead: usize,
+    pub archived: usize,
+    /// Share still growing or carried on by a split: (alive + split) / total.
+    pub survival_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeToLoss {
+    pub lost: usize,
+    pub mean_days: f64,
+    pub median_days: f64,
+    pub min_days: i64,
+    pub max_days: i64,
+}
+
+/// A subtree under the root.
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageBranch {
+    pub id: String,
+    pub accession_number: String,
+    pub nodes: usize,
+    /// Alive specimens in the branch, its head included.
+    pub living: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageMetrics {
+    pub root_id: String,
+    pub root_accession: String,
+    pub nodes: usize,
+    pub edges: usize,
+    /// Generations below the root.
+    pub depth: i64,
+    pub leaves: usize,
+    pub alive: usize,
+    pub branching: Branching,
+    pub generations: Vec<GenerationSurvival>,
+    /// `None` while nothing in the lineage has been lost.
+    pub time_to_loss: Option<TimeToLoss>,
+    pub branches: Vec<LineageBranch>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineageFormat {
+    GraphMl,
+    Dot,
+    Newick,
+}
+
+impl LineageFormat {
+    pub fn parse(s: &str) -> Result<LineageFormat, AppError> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "graphml" => Ok(LineageFormat::GraphMl),
+            "dot" => Ok(LineageFormat::Dot),
+            "newick" => Ok(LineageFormat::Newick),
+            other => Err(AppError::validation("format", format!("Unknown lineage format '{}'; use graphml, dot or newick", other))),
+        }
+    }
+}
+
+/// The family of specimen `id` (which must exist): its root and every
+/// specimen with that root.
+pub fn load(conn: &Connection, id: &str) -> Result<LineageGraph, AppError> {
+    let root_id: String = conn
+        .query_row("SELECT COALESCE(root_specimen_id, id) FROM specimens WHERE id = ?1", params![id], |r| r.get(0))
+        .map_err(|_| AppError::not_found("specimen", "Specimen not found").with_id(id))?;
+    let mut nodes = conn
+        .prepare(
+            "SELECT s.id, s.accession_number, s.parent_specimen_id, s.generation, s.lineage_passage_offset,
+                    s.subculture_count, s.health_status, s.contamination_flag, s.stage, sp.species_code,
+                    s.initiation_date, s.is_archived,
+                    (SELECT MIN(d.date) FROM subcultures d WHERE d.specimen_id = s.id AND d.event_type = 'death'),
+                    date(s.archived_at)
+             FROM specimens s
+             LEFT JOIN species sp ON sp.id = s.species_id
+             WHERE (s.id = ?1 OR s.root_specimen_id = ?1) AND s.merged_into IS NULL
+             ORDER BY s.generation, s.created_at, s.id",
+        )?
+        .query_map(params![root_id], |r| {
+            let health: Option<String> = r.get(6)?;
+            let archived: bool = r.get(11)?;
+            let died_on: Option<String> = r.get(12)?;
+            let archived_on: Option<String> = r.get(13)?;
+            let status = match (archived, died_on.is_some() || health.as_deref() == Some("0")) {
+                (false, _) => NodeStatus::Alive,
+                (true, true) => NodeStatus::Dead,
+                (true, false) => NodeStatus::Archived,
+            };
+            Ok(LineageNode {
+                id: r.get(0)?,
+                accession_number: r.get(1)?,
+                parent_id: r.get(2)?,
+                generation: r.get(3)?,
+                passage_offset: r.get(4)?,
+                subculture_count: r.get(5)?,
+                health_status: health,
+                contaminated: r.get(7)?,
+                stage: r.get(8)?,
+                species_code: r.get(9)?,
+                initiation_date: r.get(10)?,
+                status,
+                lost_on: if archived { died_on.or(archived_on) } else { None },
+                days_to_loss: None,
+                living_descendants: 0,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // A split parent is archived with children: it was carried on, not lost.
+    let index: HashMap<String, usize> = nodes.iter().enumerate().map(|(i, n)| (n.id.clone(), i)).collect();
+    let mut has_children = vec![false; nodes.len()];
+    for n in &mut nodes {
+        if n.parent_id.as_ref().is_some_and(|p| !index.contains_key(p)) {
+            n.parent_id = None;
+        }
+    }
+    for n in &nodes {
+        if let Some(p) = n.parent_id.as_ref().and_then(|p| index.get(p)) {
+            has_children[*p] = true;
+        }
+    }
+    for (i, n) in nodes.iter_mut().enumerate() {
+        if has_children[i] && n.status != NodeStatus::Alive {
+            n.status = NodeStatus::Split;
+            n.lost_on = None;
+        }
+        n.days_to_loss = n.lost_on.as_deref().and_then(|lost| days_between(&n.initiation_date, lost));
+    }
+
+    // Each living node counts once toward every ancestor. Walking up parent
+    // links does not rely on the stored generation numbers.
+    for i in 0..nodes.len() {
+        if nodes[i].status != NodeStatus::Alive {
+            continue;
+        }
+        let mut cursor = nodes[i].parent_id.clone();
+        let mut steps = 0;
+        while let Some(p) = cursor.and_then(|p| index.get(&p).copied()) {
+            nodes[p].living_descendants += 1;
+            cursor = nodes[p].parent_id.clone();
+            steps += 1;
+            if steps > nodes.len() {
+                break; // a cycle in bad data; counts stay finite
+            }
+        }
+    }
+    Ok(LineageGraph { root_id, nodes })
+}
+
+fn days_between(from: &str, to: &str) -> Option<i64> {
+    let parse = |s: &str| chrono::NaiveDate::parse_from_str(s.get(..10)?, "%Y-%m-%d").ok();
+    Some((parse(to)? - parse(from)?).num_days())
+}
+
+impl LineageGraph {
+    fn children(&self) -> HashMap<&str, Vec<&LineageNode>> {
+        let mut children: HashMap<&str, Vec<&LineageNode>> = HashMap::new();
+        for n in &self.nodes {
+            if let Some(p) = &n.parent_id {
+                children.entry(p.as_str()).or_default().push(n);
+            }
+        }
+        for list in children.values_mut() {
+            list.sort_by(|a, b| a.accession_number.cmp(&b.accession_number));
+        }
+        children
+    }
+
+    fn tops(&self) -> Vec<&LineageNode> {
+        self.nodes.iter().filter(|n| n.parent_id.is_none()).collect()
+    }
+
+    pub fn metrics(&self) -> LineageMetrics {
+        let children = self.children();
+        let root = self.nodes.iter().find(|n| n.id == self.root_id).or_else(|| self.nodes.first());
+        let base_generation = root.map(|r| r.generation).unwrap_or(0);
+
+        let counts: Vec<usize> = children.values().map(Vec::len).collect();
+        let branching = Branching {
+            internal_nodes: counts.len(),
+            mean_children: if counts.is_empty() { 0.0 } else { counts.iter().sum::<usize>() as f64 / counts.len() as f64 },
+            max_children: counts.iter().copied().max().unwrap_or(0),
+        };
+
+        let mut by_generation: std::collections::BTreeMap<i64, GenerationSurvival> = Default::default();
+        for n in &self.nodes {
+            let g = by_generation.entry(n.generation).or_insert(GenerationSurvival {
+                generation: n.generation,
+                total: 0,
+                alive: 0,
+                split: 0,
+                dead: 0,
+                archived: 0,
+                survival_rate: 0.0,
+            });
+            g.total += 1;
+            match n.status {
+                NodeStatus::Alive => g.alive += 1,
+                NodeStatus::Split => g.split += 1,
+                NodeStatus::Dead => g.dead += 1,
+                NodeStatus::Archived => g.archived += 1,
+            }
+        }
+        let generations: Vec<GenerationSurvival> = by_generation
+            .into_values()
+            .map(|mut g| {
+                g.survival_rate = (g.alive + g.split) as f64 / g.total as f64;
+                g
+            })
+            .collect();
+
+        let mut days: Vec<i64> = self.nodes.iter().filter(|n| n.status.is_lost()).filter_map(|n| n.days_to_loss).collect();
+        days.sort_unstable();
+        let time_to_loss = (!days.is_empty()).then(|| {
+            let mid = days.len() / 2;
+            TimeToLoss {
+                lost: days.len(),
+                mean_days: days.iter().sum::<i64>() as f64 / days.len() as f64,
+                median_days: if days.len().is_multiple_of(2) { (days[mid - 1] + days[mid]) as f64 / 2.0 } else { days[mid] as f64 },
+                min_days: days[0],
+                max_days: days[days.len() - 1],
+            }
+        });
+
+        let subtree_size = |id: &str| -> usize {
+            let mut stack = vec![id];
+            let mut size = 0;
+            while let Some(id) = stack.pop() {
+                size += 1;
+                stack.extend(children.get(id).into_iter().flatten().map(|c| c.id.as_str()));
+                if size > self.nodes.len() {
+                    break;
+                }
+            }
+            size
+        };
+        let branches = root
+            .and_then(|r| children.get(r.id.as_str()))
+            .into_iter()
+            .flatten()
+            .map(|head| LineageBranch {
+                id: head.id.clone(),
+                accession_number: head.accession_number.clone(),
+                nodes: subtree_size(&head.id),
+                living: head.living_descendants + usize::from(head.status == NodeStatus::Alive),
+            })
+            .collect();
+
+        LineageMetrics {
+            root_id: self.root_id.clone(),
+            root_accession: root.map(|r| r.accession_number.clone()).unwrap_or_default(),
+            nodes: self.nodes.len(),
+            edges: self.nodes.iter().filter(|n| n.parent_id.is_some()).count(),
+            depth: self.nodes.iter().map(|n| n.generation - base_generation).max().unwrap_or(0),
+            leaves: self.nodes.iter().filter(|n| !children.contains_key(n.id.as_str())).count(),
+            alive: self.nodes.iter().filter(|n| n.status == NodeStatus::Alive).count(),
+            branching,
+            generations,
+            time_to_loss,
+            branches,
+        }
+    }
+
+    pub fn export(&self, format: LineageFormat) -> String {
+        match format {
+            LineageFormat::GraphMl => self.to_graphml(),
+            LineageFormat::Dot => self.to_dot(),
+            LineageFormat::Newick => self.to_newick(),
+        }
+    }
+
+    /// Passages from the parent to `n`, the edge length.
+    fn passages_from_parent(&self, n: &LineageNode) -> Option<i64> {
+        let parent = self.nodes.iter().find(|p| Some(&p.id) == n.parent_id.as_ref())?;
+        Some(n.passage_offset - parent.passage_offset)
+    }
+
+    fn to_graphml(&self) -> String {
+        const KEYS: &[(&str, &str, &str)] = &[
+            ("accession", "node", "string"),
+            ("generation", "node", "int"),
+            ("passage_offset", "node", "int"),
+            ("subculture_count", "node", "int"),
+            ("health", "node", "string"),
+            ("contaminated", "node", "boolean"),
+            ("status", "node", "string"),
+            ("stage", "node", "string"),
+            ("species", "node", "string"),
+            ("initiation_date", "node", "string"),
+            ("days_to_loss", "node", "int"),
+            ("living_descendants", "node", "int"),
+            ("passages", "edge", "int"),
+        ];
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        );
+        for (name, scope, kind) in KEYS {
+            let _ = writeln!(out, "  <key id=\"{name}\" for=\"{scope}\" attr.name=\"{name}\" attr.type=\"{kind}\"/>");
+        }
+        let _ = writeln!(out, "  <graph id=\"{}\" edgedefault=\"directed\">", xml(&self.root_id));
+        for n in &self.nodes {
+            let _ = writeln!(out, "    <node id=\"{}\">", xml(&n.id));
+            let mut data = |key: &str, value: String| {
+                let _ = writeln!(out, "      <data key=\"{}\">{}</data>", key, xml(&value));
+            };
+            data("accession", n.accession_number.clone());
+            data("generation", n.generation.to_string());
+            data("passage_offset", n.passage_offset.to_string());
+            data("subculture_count", n.subculture_count.to_string());
+            if let Some(h) = &n.health_status {
+                data("health", h.clone());
+            }
+            data("contaminated", n.contaminated.to_string());
+            data("status", n.status.as_str().to_string());
+            data("stage", n.stage.clone());
+            if let Some(s) = &n.species_code {
+                data("species", s.clone());
+            }
+            data("initiation_date", n.initiation_date.clone());
+            if let Some(d) = n.days_to_loss {
+                data("days_to_loss", d.to_string());
+            }
+            data("living_descendants", n.living_descendants.to_string());
+            out.push_str("    </node>\n");
+        }
+        for n in &self.nodes {
+            if let Some(p) = &n.parent_id {
+                let _ = writeln!(out, "    <edge source=\"{}\" target=\"{}\">", xml(p), xml(&n.id));
+                if let Some(passages) = self.passages_from_parent(n) {
+                    let _ = writeln!(out, "      <data key=\"passages\">{}</data>", passages);
+                }
+                out.push_str("    </edge>\n");
+            }
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    fn to_dot(&self) -> String {
+        let mut out = format!(
+            "digraph \"{}\" {{\n  node [shape=box, style=rounded];\n",
+            dot(&format!("lineage {}", self.root_accession()))
+        );
+        for n in &self.nodes {
+            let color = match n.status {
+                NodeStatus::Alive => "#15803d",
+                NodeStatus::Split => "#6b7280",
+                NodeStatus::Dead => "#dc2626",
+                NodeStatus::Archived => "#9ca3af",
+            };
+            let _ = writeln!(
+                out,
+                "  \"{}\" [label=\"{}\\ngen {} · P{}\", accession=\"{}\", generation={}, passage_offset={}, subculture_count={}, health=\"{}\", contaminated={}, status=\"{}\", stage=\"{}\", living_descendants={}{}, color=\"{}\"];",
+                dot(&n.id),
+                dot(&n.accession_number),
+                n.generation,
+                n.passage_offset,
+                dot(&n.accession_number),
+                n.generation,
+                n.passage_offset,
+                n.subculture_count,
+                dot(n.health_status.as_deref().unwrap_or("")),
+                n.contaminated,
+                n.status.as_str(),
+                dot(&n.stage),
+                n.living_descendants,
+                n.days_to_loss.map(|d| format!(", days_to_loss={}", d)).unwrap_or_default(),
+                color,
+            );
+        }
+        for n in &self.nodes {
+            if let Some(p) = &n.parent_id {
+                let label = self.passages_from_parent(n).map(|d| format!(" [label=\"{}\"]", d)).unwrap_or_default();
+                let _ = writeln!(out, "  \"{}\" -> \"{}\"{};", dot(p), dot(&n.id), label);
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn root_accession(&self) -> &str {
+        self.nodes.iter().find(|n| n.id == self.root_id).map(|n| n.accession_number.as_str()).unwrap_or(&self.root_id)
+    }
+
+    /// One tree per line. Labels are accession numbers; edge lengths are the
+    /// passages from the parent; attributes ride along as NHX comments.
+    fn to_newick(&self) -> String {
+        let children = self.children();
+        let mut out = String::new();
+        for top in self.tops() {
+            self.newick_node(top, &children, &mut out, 0);
+            out.push_str(";\n");
+        }
+        out
+    }
+
+    fn newick_node(&self, n: &LineageNode, children: &HashMap<&str, Vec<&LineageNode>>, out: &mut String, depth: usize) {
+        if let Some(kids) = children.get(n.id.as_str()).filter(|_| depth <= self.nodes.len()) {
+            out.push('(');
+            for (i, kid) in kids.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                self.newick_node(kid, children, out, depth + 1);
+            }
+            out.push(')');
+        }
+        out.push_str(&newick_label(&n.accession_number));
+        if let Some(len) = self.passages_from_parent(n) {
+            let _ = write!(out, ":{}", len);
+        }
+        let _ = write!(
+            out,
+            "[&&NHX:generation={}:passage_offset={}:contaminated={}:status={}",
+            n.generation,
+            n.passage_offset,
+            u8::from(n.contaminated),
+            n.status.as_str()
+        );
+        if let Some(h) = n.health_status.as_deref().filter(|h| h.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')) {
+            let _ = write!(out, ":health={}", h);
+        }
+        if let Some(d) = n.days_to_loss {
+            let _ = write!(out, ":days_to_loss={}", d);
+        }
+        out.push(']');
+    }
+}
+
+fn xml(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+fn dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Quotes a Newick label that contains anything beyond plain characters.
+fn newick_label(s: &str) -> String {
+    if s.chars().any(|c| "()[]':;, \t\n_".contains(c)) {
+        format!("'{}'", s.replace('\'', "''"))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+
+    /// CIT-001 split into A (alive) and B; B split into B-A (died after 10
+    /// days) and B-B (archived after 30). A merge tombstone hangs off the root.
+    fn lineage_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT');
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, generation, lineage_passage_offset, subculture_count, health_status, is_archived, archived_at)
+             VALUES ('r', 'CIT-001', 'sp', 'shoot', '2026-01-01', 0, 0, 3, '3', 1, '2026-01-20 10:00:00');
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, generation, lineage_passage_offset, subculture_count, health_status, is_archived, archived_at, parent_specimen_id, root_specimen_id, contamination_flag)
+             VALUES ('a', 'CIT-001-A', 'sp', 'root', '2026-01-20', 1, 2, 1, '4', 0, NULL, 'r', 'r', 0),
+                    ('b', 'CIT-001-B', 'sp', 'shoot', '2026-01-20', 1, 3, 1, '2', 1, '2026-02-01 09:00:00', 'r', 'r', 1),
+                    ('t', 'CIT-001-T', 'sp', 'shoot', '2026-01-20', 1, 3, 0, '4', 1, '2026-01-25 09:00:00', NULL, 'r', 0);
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, generation, lineage_passage_offset, subculture_count, health_status, is_archived, archived_at, parent_specimen_id, root_specimen_id)
+             VALUES ('b1', 'CIT-001-B-A', 'sp', 'shoot', '2026-02-01', 2, 5, 0, '0', 1, '2026-02-12 08:00:00', 'b', 'r'),
+                    ('b2', 'CIT-001-B-B', 'sp', 'shoot', '2026-02-01', 2, 4, 0, '2', 1, '2026-03-03 08:00:00', 'b', 'r');
+             UPDATE specimens SET merged_into = 'a' WHERE id = 't';
+             INSERT INTO subcultures (id, specimen_id, passage_number, date, event_type) VALUES ('d1', 'b1', 1, '2026-02-11', 'death');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn node<'a>(g: &'a LineageGraph, id: &str) -> &'a LineageNode {
+        g.nodes.iter().find(|n| n.id == id).unwrap()
+    }
+
+    #[test]
+    fn nodes_carry_status_loss_and_living_descendants() {
+        let conn = lineage_db();
+        let g = load(&conn, "b1").unwrap();
+        assert_eq!(g.root_id, "r");
+        assert_eq!(g.nodes.len(), 5, "the merge tombstone is left out");
+        assert_eq!(node(&g, "r").status, NodeStatus::Split);
+        assert_eq!(node(&g, "a").status, NodeStatus::Alive);
+        assert_eq!(node(&g, "b").status, NodeStatus::Split);
+        assert_eq!((node(&g, "b1").status, node(&g, "b1").days_to_loss), (NodeStatus::Dead, Some(10)));
+        assert_eq!((node(&g, "b2").status, node(&g, "b2").days_to_loss), (NodeStatus::Archived, Some(30)));
+        assert_eq!((node(&g, "r").living_descendants, node(&g, "b").living_descendants), (1, 0));
+        assert_eq!(load(&conn, "nope").unwrap_err().code(), "not_found");
+    }
+
+    #[test]
+    fn metrics_summarise_branching_survival_and_loss() {
+        let conn = lineage_db();
+        let m = load(&conn, "r").unwrap().metrics();
+        assert_eq!((m.nodes, m.edges, m.depth, m.leaves, m.alive), (5, 4, 2, 3, 1));
+        assert_eq!((m.branching.internal_nodes, m.branching.max_children), (2, 2));
+        assert!((m.branching.mean_children - 2.0).abs() < 1e-9);
+
+        let rates: Vec<(i64, usize, f64)> = m.generations.iter().map(|g| (g.generation, g.total, g.survival_rate)).collect();
+        assert_eq!(rates, [(0, 1, 1.0), (1, 2, 1.0), (2, 2, 0.0)]);
+        assert_eq!((m.generations[2].dead, m.generations[2].archived), (1, 1));
+
+        let loss = m.time_to_loss.unwrap();
+        assert_eq!((loss.lost, loss.min_days, loss.max_days), (2, 10, 30));
+        assert!((loss.mean_days - 20.0).abs() < 1e-9 && (loss.median_days - 20.0).abs() < 1e-9);
+
+        let branches: Vec<(&str, usize, usize)> = m.branches.iter().map(|b| (b.accession_number.as_str(), b.nodes, b.living)).collect();
+        assert_eq!(branches, [("CIT-001-A", 1, 1), ("CIT-001-B", 3, 0)]);
+    }
+
+    #[test]
+    fn exports_carry_the_graph_and_node_attributes() {
+        let conn = lineage_db();
+        let g = load(&conn, "a").unwrap();
+
+        let graphml = g.export(LineageFormat::GraphMl);
+        assert!(graphml.contains("<edge source=\"r\" target=\"a\">\n      <data key=\"passages\">2</data>"), "{graphml}");
+        assert!(graphml.contains("<data key=\"status\">dead</data>"));
+        assert!(graphml.contains("<data key=\"contaminated\">true</data>"));
+        assert_eq!(graphml.matches("<node ").count(), 5);
+
+        let dot = g.export(LineageFormat::Dot);
+        assert!(dot.starts_with("digraph \"lineage CIT-001\" {"), "{dot}");
+        assert!(dot.contains("\"b\" -> \"b1\" [label=\"2\"];"));
+        assert!(dot.contains("status=\"dead\", stage=\"shoot\", living_descendants=0, days_to_loss=10"));
+
+        let newick = g.export(LineageFormat::Newick);
+        assert!(newick.starts_with("(CIT-001-A:2[&&NHX:generation=1:passage_offset=2:contaminated=0:status=alive:health=4],(CIT-001-B-A:2["), "{newick}");
+        assert!(newick.trim_end().ends_with(")CIT-001[&&NHX:generation=0:passage_offset=0:contaminated=0:status=split:health=3];"), "{newick}");
+        assert_eq!(newick.lines().count(), 1);
+
+        assert_eq!(newick_label("P_01 (b)"), "'P_01 (b)'");
+        assert_eq!(newick_label("O'Hara"), "'O''Hara'");
+        assert_eq!(xml("a<b & \"c\""), "a&lt;b &amp; &quot;c&quot;");
+        assert_eq!(LineageFormat::parse(" GraphML ").unwrap(), LineageFormat::GraphMl);
+        assert_eq!(LineageFormat::parse("gexf").unwrap_err().code(), "validation");
+    }
+}
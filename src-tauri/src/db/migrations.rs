@@ -1,4 +1,4 @@
-use rusqlite::Connection;
+use rusqlite::{Connection, OptionalExtension};
 use super::DbResult;
 
 /// Runs one migration and stamps its version **atomically**.
@@ -43,7 +43,8 @@ where
 /// These seven are therefore left as they were. They are all *additive or
 /// rebuild* migrations that predate the current schema, so the retry hazard
 /// `apply` closes is accepted here rather than traded for a correctness bug.
-/// New migrations should use `apply` and avoid both constructs.
+/// New migrations should use `apply` and avoid both constructs — or, for a
+/// table rebuild, `apply_rebuild`.
 fn apply_untransacted<F>(conn: &Connection, version: i64, migrate: F) -> DbResult<()>
 where
     F: FnOnce(&Connection) -> DbResult<()>,
@@ -53,6 +54,40 @@ where
     Ok(())
 }
 
+/// `apply` for a migration that rebuilds a table other tables reference.
+///
+/// This is SQLite's documented rebuild procedure: foreign-key enforcement is
+/// switched off *outside* the transaction, where the pragma takes effect, and
+/// the rebuild itself stays atomic inside `apply`. The migration must end with
+/// `check_foreign_keys`, since nothing else will notice a dangling reference.
+/// Enforcement is restored to whatever it was before.
+fn apply_rebuild<F>(conn: &Connection, version: i64, migrate: F) -> DbResult<()>
+where
+    F: FnOnce(&Connection) -> DbResult<()>,
+{
+    let enforced: bool = conn.query_row("PRAGMA foreign_keys", [], |r| r.get(0))?;
+    conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+    let result = apply(conn, version, migrate);
+    if enforced {
+        conn.execute_batch("PRAGMA foreign_keys = ON")?;
+    }
+    result
+}
+
+/// Fails with the first violation `PRAGMA foreign_key_check` reports.
+fn check_foreign_keys(conn: &Connection) -> DbResult<()> {
+    let violation: Option<(String, i64, String)> = conn
+        .query_row("PRAGMA foreign_key_check", [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+        .optional()?;
+    match violation {
+        Some((table, rowid, parent)) => Err(crate::db::DbError::Migration(format!(
+            "foreign key violation after rebuild: {} row {} references a missing {} row",
+            table, rowid, parent
+        ))),
+        None => Ok(()),
+    }
+}
+
 pub fn run_all(conn: &Connection) -> DbResult<()> {
     conn.execute_batch("
         CREATE TABLE IF NOT EXISTS schema_version (
@@ -300,6 +335,850 @@ pub fn run_all(conn: &Connection) -> DbResult<()> {
         apply(conn, 57, migration_057_media_hormones_batch_index)?;
     }
 
+    if current < 58 {
+        apply(conn, 58, migration_058_electronic_signatures)?;
+    }
+
+    if current < 59 {
+        apply(conn, 59, migration_059_witness_policies)?;
+    }
+
+    if current < 60 {
+        apply(conn, 60, migration_060_anchor_node_rpc)?;
+    }
+
+    if current < 61 {
+        apply(conn, 61, migration_061_anchor_spv_proofs)?;
+    }
+
+    if current < 62 {
+        apply(conn, 62, migration_062_totp_mfa)?;
+    }
+
+    if current < 63 {
+        apply(conn, 63, migration_063_ldap_directory)?;
+    }
+
+    if current < 64 {
+        apply_rebuild(conn, 64, migration_064_roles_and_capabilities)?;
+    }
+
+    if current < 65 {
+        apply(conn, 65, migration_065_declarative_field_masking)?;
+    }
+
+    if current < 66 {
+        apply(conn, 66, migration_066_session_management)?;
+    }
+
+    if current < 67 {
+        apply(conn, 67, migration_067_lockout_and_password_policy)?;
+    }
+
+    if current < 68 {
+        apply(conn, 68, migration_068_api_config)?;
+    }
+
+    if current < 69 {
+        apply_rebuild(conn, 69, migration_069_service_accounts_and_api_tokens)?;
+    }
+
+    if current < 70 {
+        apply(conn, 70, migration_070_accession_templates)?;
+    }
+    if current < 71 {
+        apply(conn, 71, migration_071_custom_fields)?;
+    }
+    if current < 72 {
+        apply(conn, 72, migration_072_saved_searches)?;
+    }
+    if current < 73 {
+        apply(conn, 73, migration_073_stage_transitions)?;
+    }
+    if current < 74 {
+        apply(conn, 74, migration_074_specimen_merges)?;
+    }
+    if current < 75 {
+        apply(conn, 75, migration_075_specimen_restore_capability)?;
+    }
+    if current < 76 {
+        apply(conn, 76, migration_076_trials)?;
+    }
+    if current < 77 {
+        apply(conn, 77, migration_077_subculture_hood)?;
+    }
+
+    Ok(())
+}
+
+/// WP-103: the hood or workstation a passage was done at, so contamination
+/// can be traced to it. Free text like the locations.
+fn migration_077_subculture_hood(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch("ALTER TABLE subcultures ADD COLUMN hood TEXT;")?;
+    Ok(())
+}
+
+/// WP-102: treatment trials. `trials` holds the design (factors and outcomes
+/// as JSON, since they are only ever read whole), `trial_units` the random
+/// assignment of specimens to treatments, and `trial_observations` one value
+/// per specimen, outcome and passage. Supervisors get `trial.manage`.
+fn migration_076_trials(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS trials (
+            id          TEXT PRIMARY KEY,
+            lab_profile TEXT NOT NULL,
+            name        TEXT NOT NULL,
+            objective   TEXT,
+            factors     TEXT NOT NULL,
+            outcomes    TEXT NOT NULL,
+            replicates  INTEGER NOT NULL CHECK (replicates >= 1),
+            status      TEXT NOT NULL DEFAULT 'draft' CHECK (status IN ('draft', 'running', 'closed')),
+            seed        INTEGER,
+            created_by  TEXT REFERENCES users(id) ON DELETE SET NULL,
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            started_at  TEXT,
+            closed_at   TEXT
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_trials_name ON trials(lab_profile, name COLLATE NOCASE);
+
+        CREATE TABLE IF NOT EXISTS trial_units (
+            id          TEXT PRIMARY KEY,
+            trial_id    TEXT NOT NULL REFERENCES trials(id) ON DELETE CASCADE,
+            specimen_id TEXT NOT NULL REFERENCES specimens(id),
+            treatment   INTEGER NOT NULL CHECK (treatment >= 0),
+            replicate   INTEGER NOT NULL CHECK (replicate >= 1),
+            UNIQUE (trial_id, specimen_id),
+            UNIQUE (trial_id, treatment, replicate)
+        );
+        CREATE INDEX IF NOT EXISTS idx_trial_units_specimen ON trial_units(specimen_id);
+
+        CREATE TABLE IF NOT EXISTS trial_observations (
+            id            TEXT PRIMARY KEY,
+            unit_id       TEXT NOT NULL REFERENCES trial_units(id) ON DELETE CASCADE,
+            outcome       TEXT NOT NULL,
+            passage       INTEGER NOT NULL,
+            subculture_id TEXT REFERENCES subcultures(id) ON DELETE SET NULL,
+            value         REAL NOT NULL,
+            recorded_by   TEXT REFERENCES users(id) ON DELETE SET NULL,
+            recorded_at   TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE (unit_id, outcome, passage)
+        );
+
+        INSERT OR IGNORE INTO role_capabilities (role, capability)
+        SELECT 'supervisor', 'trial.manage' WHERE EXISTS (SELECT 1 FROM roles WHERE name = 'supervisor');",
+    )?;
+    Ok(())
+}
+
+/// WP-100: supervisors get `specimen.restore`. Restoring needs no schema: the
+/// snapshots ride in the audit log's archive entries.
+fn migration_075_specimen_restore_capability(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "INSERT OR IGNORE INTO role_capabilities (role, capability)
+         SELECT 'supervisor', 'specimen.restore' WHERE EXISTS (SELECT 1 FROM roles WHERE name = 'supervisor');",
+    )?;
+    Ok(())
+}
+
+/// WP-99: merging duplicate specimens. The retired record stays as a
+/// tombstone, archived with `merged_into` pointing at the survivor, so its id
+/// and accession still resolve. `specimen_merges` keeps one row per merge with
+/// what was moved. Supervisors get `specimen.merge`.
+fn migration_074_specimen_merges(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "ALTER TABLE specimens ADD COLUMN merged_into TEXT REFERENCES specimens(id);
+        CREATE INDEX IF NOT EXISTS idx_specimens_merged_into ON specimens(merged_into);
+
+        CREATE TABLE IF NOT EXISTS specimen_merges (
+            id          TEXT PRIMARY KEY,
+            survivor_id TEXT NOT NULL REFERENCES specimens(id),
+            retired_id  TEXT NOT NULL UNIQUE REFERENCES specimens(id),
+            reason      TEXT,
+            moved       TEXT NOT NULL DEFAULT '{}',
+            merged_by   TEXT REFERENCES users(id) ON DELETE SET NULL,
+            merged_at   TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_specimen_merges_survivor ON specimen_merges(survivor_id);
+
+        INSERT OR IGNORE INTO role_capabilities (role, capability)
+        SELECT 'supervisor', 'specimen.merge' WHERE EXISTS (SELECT 1 FROM roles WHERE name = 'supervisor');",
+    )?;
+    Ok(())
+}
+
+/// Built-in stage transitions (WP-98), `(profile, from, [to…])`. Every
+/// non-terminal stage may also move to and from `custom`, and in mycology and
+/// cell culture to `contaminated` and `discarded`; those rows are added in SQL.
+const BUILTIN_STAGE_TRANSITIONS: &[(&str, &str, &[&str])] = &[
+    ("plant_tissue_culture", "explant", &["callus", "shoot", "shoot_meristem", "apical_meristem", "root", "embryogenic", "stock"]),
+    ("plant_tissue_culture", "callus", &["suspension", "protoplast", "shoot", "root", "embryogenic", "plantlet"]),
+    ("plant_tissue_culture", "suspension", &["callus", "protoplast", "embryogenic"]),
+    ("plant_tissue_culture", "protoplast", &["callus", "suspension"]),
+    ("plant_tissue_culture", "shoot", &["callus", "shoot_meristem", "apical_meristem", "root", "plantlet", "stock"]),
+    ("plant_tissue_culture", "shoot_meristem", &["shoot", "apical_meristem", "plantlet"]),
+    ("plant_tissue_culture", "apical_meristem", &["shoot", "shoot_meristem", "plantlet"]),
+    ("plant_tissue_culture", "root", &["root_meristem", "plantlet"]),
+    ("plant_tissue_culture", "root_meristem", &["root", "plantlet"]),
+    ("plant_tissue_culture", "embryogenic", &["callus", "suspension", "shoot", "plantlet"]),
+    ("plant_tissue_culture", "plantlet", &["shoot", "acclimatized", "stock"]),
+    ("plant_tissue_culture", "acclimatized", &["stock"]),
+    ("plant_tissue_culture", "stock", &["callus", "shoot"]),
+    ("mycology", "spore_clone", &["agar", "liquid_culture"]),
+    ("mycology", "agar", &["liquid_culture", "grain_spawn"]),
+    ("mycology", "liquid_culture", &["agar", "grain_spawn"]),
+    ("mycology", "grain_spawn", &["bulk_substrate", "colonizing"]),
+    ("mycology", "bulk_substrate", &["colonizing"]),
+    ("mycology", "colonizing", &["fruiting"]),
+    ("mycology", "fruiting", &["colonizing", "senescent"]),
+    ("cell_culture", "primary", &["subculture", "expansion", "characterization", "cryo_stock", "adherent", "suspension", "cryopreserved"]),
+    ("cell_culture", "subculture", &["expansion", "maintenance", "adherent", "suspension", "confluent", "passaged"]),
+    ("cell_culture", "expansion", &["maintenance", "differentiation", "characterization", "selection", "cryo_stock", "confluent", "passaged", "cryopreserved"]),
+    ("cell_culture", "maintenance", &["expansion", "differentiation", "characterization", "cryo_stock", "confluent", "passaged", "cryopreserved"]),
+    ("cell_culture", "differentiation", &["characterization"]),
+    ("cell_culture", "characterization", &["expansion", "maintenance", "selection", "stable_line", "cryo_stock", "cryopreserved"]),
+    ("cell_culture", "selection", &["expansion", "characterization", "stable_line"]),
+    ("cell_culture", "stable_line", &["expansion", "maintenance", "characterization", "cryo_stock", "cryopreserved"]),
+    ("cell_culture", "cryo_stock", &["thaw_recovery", "thawed"]),
+    ("cell_culture", "thaw_recovery", &["subculture", "expansion", "maintenance", "adherent", "suspension"]),
+    ("cell_culture", "thawed", &["thaw_recovery", "adherent", "suspension"]),
+    ("cell_culture", "adherent", &["expansion", "differentiation", "characterization", "cryo_stock", "confluent", "passaged", "cryopreserved"]),
+    ("cell_culture", "suspension", &["expansion", "differentiation", "characterization", "cryo_stock", "passaged", "cryopreserved"]),
+    ("cell_culture", "confluent", &["subculture", "differentiation", "cryo_stock", "passaged", "cryopreserved"]),
+    ("cell_culture", "passaged", &["expansion", "maintenance", "adherent", "suspension", "confluent"]),
+    ("cell_culture", "cryopreserved", &["thaw_recovery", "thawed"]),
+];
+
+/// WP-98: the stage state machine. `stage_transitions` lists the stage
+/// changes each profile allows and the specimen fields each one requires (a
+/// JSON array of field keys or `custom.<key>`). The three built-in profiles
+/// are seeded from [`BUILTIN_STAGE_TRANSITIONS`]; a profile with no rows is
+/// unrestricted. Plant tissue culture also gets two custom specimen fields,
+/// the acclimatization date and survival count, which plantlet →
+/// acclimatized requires. `INSERT OR IGNORE` keeps a lab's own field of the
+/// same key.
+fn migration_073_stage_transitions(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS stage_transitions (
+            lab_profile     TEXT NOT NULL,
+            from_stage      TEXT NOT NULL,
+            to_stage        TEXT NOT NULL,
+            required_fields TEXT NOT NULL DEFAULT '[]',
+            source_plugin   TEXT,
+            updated_at      TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_by      TEXT,
+            PRIMARY KEY (lab_profile, from_stage, to_stage),
+            CHECK (from_stage <> to_stage)
+        );
+
+        INSERT OR IGNORE INTO custom_fields (id, lab_profile, entity, field_key, label, field_type, unit, sort_order) VALUES
+            ('wp98-ptc-acclimatization-date', 'plant_tissue_culture', 'specimen', 'acclimatization_date', 'Acclimatization date', 'date', NULL, 100),
+            ('wp98-ptc-survival-count', 'plant_tissue_culture', 'specimen', 'survival_count', 'Survival count', 'number', 'plants', 101);
+
+        -- Any working stage can be marked custom, and a custom stage resolved.
+        INSERT OR IGNORE INTO stage_transitions (lab_profile, from_stage, to_stage)
+            SELECT a.profile, a.code, b.code FROM stages a JOIN stages b ON b.profile = a.profile
+            WHERE a.profile IN ('plant_tissue_culture', 'cell_culture') AND a.is_terminal = 0 AND b.is_terminal = 0
+              AND a.code <> b.code AND 'custom' IN (a.code, b.code);
+
+        -- A culture can be found contaminated or discarded at any working stage.
+        INSERT OR IGNORE INTO stage_transitions (lab_profile, from_stage, to_stage)
+            SELECT a.profile, a.code, b.code FROM stages a JOIN stages b ON b.profile = a.profile
+            WHERE a.profile IN ('mycology', 'cell_culture') AND a.is_terminal = 0
+              AND b.code IN ('contaminated', 'discarded');",
+    )?;
+    let mut insert = conn.prepare(
+        "INSERT OR IGNORE INTO stage_transitions (lab_profile, from_stage, to_stage) VALUES (?1, ?2, ?3)",
+    )?;
+    for (profile, from, targets) in BUILTIN_STAGE_TRANSITIONS {
+        for to in *targets {
+            insert.execute(rusqlite::params![profile, from, to])?;
+        }
+    }
+    conn.execute(
+        "UPDATE stage_transitions SET required_fields = '[\"custom.acclimatization_date\",\"custom.survival_count\"]'
+         WHERE lab_profile = 'plant_tissue_culture' AND from_stage = 'plantlet' AND to_stage = 'acclimatized'",
+        [],
+    )?;
+    Ok(())
+}
+
+/// WP-97: saved specimen filters. A search belongs to its owner and to the
+/// lab profile it was written for (field names differ between labs);
+/// `shared` lets everyone in that lab use it, but only the owner changes it.
+fn migration_072_saved_searches(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS saved_searches (
+            id          TEXT PRIMARY KEY,
+            owner_id    TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            lab_profile TEXT NOT NULL,
+            name        TEXT NOT NULL,
+            filter      TEXT NOT NULL,
+            shared      INTEGER NOT NULL DEFAULT 0,
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE (owner_id, lab_profile, name)
+        );
+        CREATE INDEX IF NOT EXISTS idx_saved_searches_profile ON saved_searches(lab_profile, shared);",
+    )?;
+    Ok(())
+}
+
+/// WP-96: admin-defined custom fields per lab profile. `custom_fields` holds
+/// the definitions (one per profile, entity and key; retired rather than
+/// deleted so stored values keep their meaning), `custom_field_options` the
+/// choices of `enum` fields. Values live on the record itself, as a JSON
+/// object keyed by field key in `specimens.custom_fields` and
+/// `subcultures.custom_fields`. `lab_profile` has no CHECK: a plugin's
+/// profile can define fields too.
+fn migration_071_custom_fields(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS custom_fields (
+            id            TEXT PRIMARY KEY,
+            lab_profile   TEXT NOT NULL,
+            entity        TEXT NOT NULL CHECK (entity IN ('specimen', 'subculture')),
+            field_key     TEXT NOT NULL,
+            label         TEXT NOT NULL,
+            field_type    TEXT NOT NULL CHECK (field_type IN ('text', 'number', 'date', 'enum', 'boolean')),
+            unit          TEXT,
+            required      INTEGER NOT NULL DEFAULT 0,
+            sort_order    INTEGER NOT NULL DEFAULT 0,
+            source_plugin TEXT,
+            retired_at    TEXT,
+            updated_at    TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_by    TEXT,
+            UNIQUE (lab_profile, entity, field_key)
+        );
+
+        CREATE TABLE IF NOT EXISTS custom_field_options (
+            field_id   TEXT NOT NULL REFERENCES custom_fields(id) ON DELETE CASCADE,
+            code       TEXT NOT NULL,
+            label      TEXT NOT NULL,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (field_id, code)
+        );
+
+        ALTER TABLE specimens ADD COLUMN custom_fields TEXT;
+        ALTER TABLE subcultures ADD COLUMN custom_fields TEXT;",
+    )?;
+    Ok(())
+}
+
+/// WP-95: accession number templates. At most one template per lab profile
+/// and species, plus one profile-wide template (`species_id` NULL); the unique
+/// index uses `COALESCE` because SQLite treats NULLs as distinct.
+/// `accession_sequences` holds each template's counter per reset period (`''`
+/// when it never resets).
+fn migration_070_accession_templates(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS accession_templates (
+            id             TEXT PRIMARY KEY,
+            lab_profile    TEXT NOT NULL CHECK (lab_profile IN ('plant_tissue_culture', 'cell_culture', 'mycology')),
+            species_id     TEXT REFERENCES species(id) ON DELETE CASCADE,
+            pattern        TEXT NOT NULL,
+            sequence_reset TEXT NOT NULL DEFAULT 'never' CHECK (sequence_reset IN ('never', 'yearly', 'monthly', 'daily')),
+            split_suffix   TEXT NOT NULL DEFAULT 'letter' CHECK (split_suffix IN ('letter', 'number', 'dot_number')),
+            updated_at     TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_by     TEXT
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_accession_templates_scope
+            ON accession_templates(lab_profile, COALESCE(species_id, ''));
+
+        CREATE TABLE IF NOT EXISTS accession_sequences (
+            template_id TEXT NOT NULL REFERENCES accession_templates(id) ON DELETE CASCADE,
+            period      TEXT NOT NULL,
+            last_value  INTEGER NOT NULL,
+            PRIMARY KEY (template_id, period)
+        );",
+    )?;
+    Ok(())
+}
+
+/// WP-92: service accounts and scoped API tokens. `users` is rebuilt so
+/// `auth_source` also admits `service`, an account that has no password and
+/// authenticates only with its tokens. `api_tokens` keeps a SHA-256 digest of
+/// each token (as `sessions` does since v1.48), its space-separated scopes,
+/// optional expiry, last use and revocation time; revoked tokens are kept so
+/// the list shows who had access when.
+fn migration_069_service_accounts_and_api_tokens(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE users_v69 (
+            id                   TEXT PRIMARY KEY,
+            username             TEXT NOT NULL UNIQUE,
+            password_hash        TEXT NOT NULL,
+            display_name         TEXT NOT NULL,
+            email                TEXT,
+            role                 TEXT NOT NULL DEFAULT 'tech' REFERENCES roles(name),
+            is_active            INTEGER NOT NULL DEFAULT 1,
+            created_at           TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at           TEXT NOT NULL DEFAULT (datetime('now')),
+            must_change_password INTEGER NOT NULL DEFAULT 0,
+            auth_source          TEXT NOT NULL DEFAULT 'local' CHECK (auth_source IN ('local', 'ldap', 'service')),
+            directory_dn         TEXT,
+            directory_synced_at  TEXT,
+            access_expires_at    TEXT,
+            password_changed_at  TEXT
+        );
+        INSERT INTO users_v69 (id, username, password_hash, display_name, email, role, is_active,
+                               created_at, updated_at, must_change_password, auth_source,
+                               directory_dn, directory_synced_at, access_expires_at, password_changed_at)
+            SELECT id, username, password_hash, display_name, email, role, is_active,
+                   created_at, updated_at, must_change_password, auth_source,
+                   directory_dn, directory_synced_at, access_expires_at, password_changed_at
+            FROM users;
+        DROP TABLE users;
+        ALTER TABLE users_v69 RENAME TO users;
+
+        CREATE TABLE IF NOT EXISTS api_tokens (
+            id           TEXT PRIMARY KEY,
+            user_id      TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name         TEXT NOT NULL,
+            token_hash   TEXT NOT NULL UNIQUE,
+            hint         TEXT NOT NULL,
+            scopes       TEXT NOT NULL,
+            created_by   TEXT REFERENCES users(id) ON DELETE SET NULL,
+            created_at   TEXT NOT NULL DEFAULT (datetime('now')),
+            expires_at   TEXT,
+            last_used_at TEXT,
+            revoked_at   TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_api_tokens_user ON api_tokens(user_id);",
+    )?;
+    check_foreign_keys(conn)
+}
+
+/// WP-90: local REST API settings. One row, off by default and bound to
+/// loopback, so nothing listens until an admin switches it on.
+fn migration_068_api_config(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS api_config (
+            id              INTEGER PRIMARY KEY CHECK (id = 1),
+            enabled         INTEGER NOT NULL DEFAULT 0,
+            bind            TEXT NOT NULL DEFAULT 'loopback' CHECK (bind IN ('loopback', 'lan')),
+            port            INTEGER NOT NULL DEFAULT 8470 CHECK (port BETWEEN 1024 AND 65535),
+            allowed_origins TEXT,
+            updated_at      TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        INSERT OR IGNORE INTO api_config (id) VALUES (1);",
+    )?;
+    Ok(())
+}
+
+/// WP-89: persistent lockout and password policy. `auth_policy` is a single
+/// row (id = 1) with the lockout threshold and duration and the password
+/// rules, seeded with the values the in-memory throttle used (5 failures,
+/// 15 minutes) and a history of 5. `login_failures` replaces that throttle.
+/// `password_history` keeps bcrypt hashes of each user's recent passwords.
+/// `users.password_changed_at` starts at `updated_at` for local accounts, the
+/// best available estimate; directory accounts keep NULL and never expire.
+fn migration_067_lockout_and_password_policy(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS auth_policy (
+            id                    INTEGER PRIMARY KEY CHECK (id = 1),
+            lockout_threshold     INTEGER NOT NULL DEFAULT 5,
+            lockout_minutes       INTEGER NOT NULL DEFAULT 15,
+            password_min_length   INTEGER NOT NULL DEFAULT 12,
+            password_history      INTEGER NOT NULL DEFAULT 5,
+            password_max_age_days INTEGER,
+            breach_check          INTEGER NOT NULL DEFAULT 0,
+            updated_at            TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        INSERT OR IGNORE INTO auth_policy (id) VALUES (1);
+
+        CREATE TABLE IF NOT EXISTS login_failures (
+            username        TEXT PRIMARY KEY,
+            failures        INTEGER NOT NULL,
+            last_failure_at TEXT NOT NULL,
+            locked_until    TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS password_history (
+            id            TEXT PRIMARY KEY,
+            user_id       TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            password_hash TEXT NOT NULL,
+            created_at    TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_password_history_user ON password_history(user_id, created_at);
+
+        ALTER TABLE users ADD COLUMN password_changed_at TEXT;
+        UPDATE users SET password_changed_at = updated_at WHERE auth_source = 'local';",
+    )?;
+    Ok(())
+}
+
+/// WP-88: session management. `sessions` gains the device a session was
+/// opened on and when it was last used. `session_policy` holds each role's
+/// optional idle (minutes) and absolute (hours) limits; it references
+/// `roles(name)` and is deleted with its role. No rows are seeded: a role
+/// without one has no limits beyond the 24-hour session lifetime.
+fn migration_066_session_management(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "ALTER TABLE sessions ADD COLUMN device TEXT;
+        ALTER TABLE sessions ADD COLUMN last_seen_at TEXT;
+        UPDATE sessions SET last_seen_at = created_at;
+        CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id);
+
+        CREATE TABLE IF NOT EXISTS session_policy (
+            role           TEXT PRIMARY KEY REFERENCES roles(name) ON DELETE CASCADE,
+            idle_minutes   INTEGER CHECK (idle_minutes IS NULL OR idle_minutes > 0),
+            absolute_hours INTEGER CHECK (absolute_hours IS NULL OR absolute_hours > 0),
+            updated_at     TEXT NOT NULL DEFAULT (datetime('now'))
+        );",
+    )?;
+    Ok(())
+}
+
+/// WP-87: declarative field masking. Seeds permissive (visible) rules for
+/// the fields added to `db::permissions::MASKABLE_FIELDS` — specimen
+/// provenance, source plant, permit number and IP notes, media supplier and
+/// cost, inventory supplier and cost — for every role, custom ones included,
+/// so the permissions editor lists them and nothing changes until an admin
+/// restricts a role.
+fn migration_065_declarative_field_masking(conn: &Connection) -> DbResult<()> {
+    let seeds = [
+        ("specimen", "provenance"),
+        ("specimen", "source_plant"),
+        ("specimen", "permit_number"),
+        ("specimen", "ip_notes"),
+        ("media_batch", "supplier_info"),
+        ("media_batch", "cost_per_batch"),
+        ("inventory_item", "supplier"),
+        ("inventory_item", "cost_per_unit"),
+    ];
+    let roles: Vec<String> = conn
+        .prepare("SELECT name FROM roles ORDER BY name")?
+        .query_map([], |r| r.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (entity_type, field_name) in seeds {
+        for role in &roles {
+            conn.execute(
+                "INSERT OR IGNORE INTO field_permissions (id, role, entity_type, field_name, visible) \
+                 VALUES (?1, ?2, ?3, ?4, 1)",
+                rusqlite::params![uuid::Uuid::new_v4().to_string(), role, entity_type, field_name],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// What migration 064 granted, frozen: the `Write` and `Manage` capabilities
+/// of the catalogue as it stood at WP-86. Seeding from the live catalogue
+/// would give fresh databases capabilities that upgraded ones only get from
+/// the migration adding them, so a capability added later is granted by that
+/// migration instead, on every database.
+const CAPABILITIES_064: &[(&str, &[&str])] = &[
+    (
+        "tech",
+        &[
+            "specimen.create", "specimen.edit", "specimen.split", "subculture.record",
+            "attachment.manage", "fruiting.record", "reminder.edit", "cryo.freeze", "cryo.thaw",
+            "cryo.discard", "strain.edit", "breeding.edit", "media.edit", "inventory.edit",
+            "location.edit", "sensor.record", "data.import", "compliance.edit", "compliance.waive",
+            "signature.sign", "ledger.witness", "registry.exchange", "passport.exchange",
+            "coordination.exchange", "ai.use",
+        ],
+    ),
+    (
+        "supervisor",
+        &[
+            "specimen.create", "specimen.edit", "specimen.split", "subculture.record",
+            "attachment.manage", "fruiting.record", "reminder.edit", "cryo.freeze", "cryo.thaw",
+            "cryo.discard", "strain.edit", "breeding.edit", "media.edit", "inventory.edit",
+            "location.edit", "sensor.record", "data.import", "compliance.edit", "compliance.waive",
+            "signature.sign", "ledger.witness", "registry.exchange", "passport.exchange",
+            "coordination.exchange", "ai.use", "specimen.archive", "specimen.delete",
+            "media.delete", "inventory.delete", "location.delete", "compliance.export",
+            "submission.manage", "species.manage", "taxonomy.manage", "passport.configure",
+            "ai.configure", "audit.view", "audit.checkpoint", "anchor.manage", "error_log.clear",
+            "analytics.team", "analytics.layout", "notifications.manage", "backup.create",
+            "sync.view", "system.demo_data", "users.view",
+        ],
+    ),
+];
+
+/// WP-86: custom roles and the capability matrix.
+///
+/// `roles` lists every role, built-in or admin-defined; `role_capabilities`
+/// holds what each may do. The built-ins are seeded from `CAPABILITIES_064`,
+/// each capability going to the roles that held it under the old fixed
+/// checks — so nobody gains or loses anything. `admin` gets no rows: it
+/// implicitly holds everything.
+///
+/// `users` and `field_permissions` are rebuilt because their `role` columns
+/// carried `CHECK (role IN (...))` for the four built-ins. Both now reference
+/// `roles(name)` instead. `users` also gains `access_expires_at` for
+/// time-boxed accounts.
+fn migration_064_roles_and_capabilities(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS roles (
+            name        TEXT PRIMARY KEY,
+            label       TEXT NOT NULL,
+            description TEXT,
+            builtin     INTEGER NOT NULL DEFAULT 0,
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS role_capabilities (
+            role       TEXT NOT NULL REFERENCES roles(name) ON DELETE CASCADE,
+            capability TEXT NOT NULL,
+            PRIMARY KEY (role, capability)
+        );
+        INSERT INTO roles (name, label, description, builtin) VALUES
+            ('admin', 'Admin', 'Every capability, including those added by future releases', 1),
+            ('supervisor', 'Supervisor', 'Lab management: deletions, exports, audit and taxonomy', 1),
+            ('tech', 'Tech', 'Day-to-day bench work', 1),
+            ('guest', 'Guest', 'Read-only access', 1);",
+    )?;
+    for (role, capabilities) in CAPABILITIES_064 {
+        for capability in *capabilities {
+            conn.execute(
+                "INSERT INTO role_capabilities (role, capability) VALUES (?1, ?2)",
+                rusqlite::params![role, capability],
+            )?;
+        }
+    }
+
+    conn.execute_batch(
+        "CREATE TABLE users_v64 (
+            id                   TEXT PRIMARY KEY,
+            username             TEXT NOT NULL UNIQUE,
+            password_hash        TEXT NOT NULL,
+            display_name         TEXT NOT NULL,
+            email                TEXT,
+            role                 TEXT NOT NULL DEFAULT 'tech' REFERENCES roles(name),
+            is_active            INTEGER NOT NULL DEFAULT 1,
+            created_at           TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at           TEXT NOT NULL DEFAULT (datetime('now')),
+            must_change_password INTEGER NOT NULL DEFAULT 0,
+            auth_source          TEXT NOT NULL DEFAULT 'local' CHECK (auth_source IN ('local', 'ldap')),
+            directory_dn         TEXT,
+            directory_synced_at  TEXT,
+            access_expires_at    TEXT
+        );
+        INSERT INTO users_v64 (id, username, password_hash, display_name, email, role, is_active,
+                               created_at, updated_at, must_change_password, auth_source,
+                               directory_dn, directory_synced_at)
+            SELECT id, username, password_hash, display_name, email, role, is_active,
+                   created_at, updated_at, must_change_password, auth_source,
+                   directory_dn, directory_synced_at
+            FROM users;
+        DROP TABLE users;
+        ALTER TABLE users_v64 RENAME TO users;
+
+        CREATE TABLE field_permissions_v64 (
+            id          TEXT PRIMARY KEY,
+            role        TEXT NOT NULL REFERENCES roles(name) ON DELETE CASCADE,
+            entity_type TEXT NOT NULL,
+            field_name  TEXT NOT NULL,
+            visible     INTEGER NOT NULL DEFAULT 1,
+            UNIQUE (role, entity_type, field_name)
+        );
+        INSERT INTO field_permissions_v64 SELECT id, role, entity_type, field_name, visible FROM field_permissions;
+        DROP TABLE field_permissions;
+        ALTER TABLE field_permissions_v64 RENAME TO field_permissions;
+        CREATE INDEX IF NOT EXISTS idx_field_permissions_entity ON field_permissions(entity_type, field_name);",
+    )?;
+    check_foreign_keys(conn)
+}
+
+/// WP-85: LDAP / Active Directory authentication.
+///
+/// `ldap_config` is a single row, like `anchor_node_config`; the service
+/// account password is never returned to the frontend. The default filter
+/// matches both OpenLDAP (`uid`) and AD (`sAMAccountName`) logins.
+/// `ldap_group_roles` maps directory group DNs to roles, compared without case
+/// as directories do. `users.auth_source` says which path checks an account's
+/// password; `directory_dn` and `directory_synced_at` record its entry.
+fn migration_063_ldap_directory(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS ldap_config (
+            id                     INTEGER PRIMARY KEY CHECK (id = 1),
+            enabled                INTEGER NOT NULL DEFAULT 0,
+            url                    TEXT,
+            starttls               INTEGER NOT NULL DEFAULT 0,
+            bind_dn                TEXT,
+            bind_password          TEXT,
+            user_base_dn           TEXT,
+            user_filter            TEXT NOT NULL DEFAULT '(&(objectClass=person)(|(uid={username})(sAMAccountName={username})))',
+            group_base_dn          TEXT,
+            display_name_attribute TEXT NOT NULL DEFAULT 'displayName',
+            email_attribute        TEXT NOT NULL DEFAULT 'mail',
+            default_role           TEXT,
+            timeout_secs           INTEGER NOT NULL DEFAULT 10,
+            updated_at             TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        INSERT OR IGNORE INTO ldap_config (id) VALUES (1);
+
+        CREATE TABLE IF NOT EXISTS ldap_group_roles (
+            group_dn   TEXT PRIMARY KEY COLLATE NOCASE,
+            role       TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        ALTER TABLE users ADD COLUMN auth_source TEXT NOT NULL DEFAULT 'local'
+            CHECK (auth_source IN ('local', 'ldap'));
+        ALTER TABLE users ADD COLUMN directory_dn TEXT;
+        ALTER TABLE users ADD COLUMN directory_synced_at TEXT;",
+    )?;
+    Ok(())
+}
+
+/// WP-84: TOTP second factor.
+///
+/// `user_totp` holds the AES-GCM-encrypted secret (the key lives outside the
+/// database) and the last accepted time step, so a code cannot be replayed.
+/// Recovery codes are stored as SHA-256 digests. `mfa_policy` marks roles that
+/// must enroll; it is keyed by role name without a CHECK so it can follow the
+/// role list. `sessions.mfa_pending` marks a password-verified session still
+/// waiting for its second factor.
+fn migration_062_totp_mfa(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS user_totp (
+            user_id        TEXT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+            secret_enc     TEXT NOT NULL,
+            enabled        INTEGER NOT NULL DEFAULT 0,
+            last_used_step INTEGER,
+            created_at     TEXT NOT NULL DEFAULT (datetime('now')),
+            confirmed_at   TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS user_recovery_codes (
+            id         TEXT PRIMARY KEY,
+            user_id    TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            code_hash  TEXT NOT NULL,
+            used_at    TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_recovery_codes_user ON user_recovery_codes(user_id);
+
+        CREATE TABLE IF NOT EXISTS mfa_policy (
+            role       TEXT PRIMARY KEY,
+            required   INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        INSERT OR IGNORE INTO mfa_policy (role) VALUES ('admin'), ('supervisor'), ('tech'), ('guest');
+
+        ALTER TABLE sessions ADD COLUMN mfa_pending INTEGER NOT NULL DEFAULT 0;",
+    )?;
+    Ok(())
+}
+
+/// WP-83: SPV inclusion proofs for confirmed anchors.
+///
+/// One row per anchor: the raw transaction, its Merkle branch and position, and
+/// the block header (with AuxPoW for merge-mined Dogecoin blocks). Together
+/// they let an auditor check the anchor is in a block without a block explorer.
+/// The branch is a JSON array of 32-byte hashes in internal byte order.
+fn migration_061_anchor_spv_proofs(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS anchor_spv_proofs (
+            anchor_id     TEXT PRIMARY KEY REFERENCES checkpoint_anchors(id) ON DELETE CASCADE,
+            chain_name    TEXT NOT NULL,
+            txid          TEXT NOT NULL,
+            raw_tx        TEXT NOT NULL,
+            block_hash    TEXT NOT NULL,
+            block_height  INTEGER,
+            block_header  TEXT NOT NULL,
+            tx_index      INTEGER NOT NULL CHECK (tx_index >= 0),
+            merkle_branch TEXT NOT NULL DEFAULT '[]',
+            captured_at   TEXT NOT NULL DEFAULT (datetime('now'))
+        );",
+    )?;
+    Ok(())
+}
+
+/// WP-82: broadcast anchors through the lab's own bitcoind-compatible node.
+///
+/// `anchor_node_config` is a single row, like `smtp_config`; the RPC password is
+/// never returned to the frontend. `checkpoint_anchors` gains how the anchor
+/// was broadcast (`manual` through an external wallet, or `node_rpc`) and the
+/// confirmation count and block hash from the last poll of the node.
+fn migration_060_anchor_node_rpc(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS anchor_node_config (
+            id                INTEGER PRIMARY KEY CHECK (id = 1),
+            enabled           INTEGER NOT NULL DEFAULT 0,
+            rpc_url           TEXT,
+            username          TEXT,
+            password          TEXT,
+            wallet            TEXT,
+            min_confirmations INTEGER NOT NULL DEFAULT 6 CHECK (min_confirmations >= 1),
+            updated_at        TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        INSERT OR IGNORE INTO anchor_node_config (id) VALUES (1);
+
+        ALTER TABLE checkpoint_anchors ADD COLUMN broadcast_via TEXT NOT NULL DEFAULT 'manual'
+            CHECK (broadcast_via IN ('manual', 'node_rpc'));
+        ALTER TABLE checkpoint_anchors ADD COLUMN confirmations INTEGER;
+        ALTER TABLE checkpoint_anchors ADD COLUMN block_hash TEXT;
+        ALTER TABLE checkpoint_anchors ADD COLUMN last_polled_at TEXT;",
+    )?;
+    Ok(())
+}
+
+/// WP-81: witness (countersignature) policies.
+///
+/// Each row names a signed ledger event type, optionally narrowed to one
+/// payload field value, that must be witnessed by `required_count` other users
+/// holding one of `allowed_roles` (comma-separated). The requirement itself is
+/// snapshotted into a `witness_required` ledger event when the covered event is
+/// appended, so editing a policy here only affects later events.
+///
+/// Seeded with the three operations our SOP template calls out. Vials have no
+/// master/working bank tier yet, so the thaw policy covers every thaw. Labs that
+/// only witness master-bank thaws can disable it.
+fn migration_059_witness_policies(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS witness_policies (
+            id             TEXT PRIMARY KEY,
+            event_type     TEXT NOT NULL,
+            match_field    TEXT,
+            match_value    TEXT,
+            label          TEXT NOT NULL,
+            required_count INTEGER NOT NULL DEFAULT 1 CHECK (required_count BETWEEN 1 AND 5),
+            allowed_roles  TEXT NOT NULL DEFAULT 'supervisor,admin',
+            enabled        INTEGER NOT NULL DEFAULT 1,
+            updated_at     TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_witness_policies_event_type
+            ON witness_policies(event_type);
+
+        INSERT OR IGNORE INTO witness_policies (id, event_type, match_field, match_value, label) VALUES
+            ('specimen_split',   'specimen_split',        NULL,        NULL,               'Split of a stock culture'),
+            ('vial_thaw',        'vial_thawed',           NULL,        NULL,               'Thaw of a banked vial'),
+            ('strain_confirmed', 'strain_status_changed', 'new_value', 'confirmed_manual', 'Manual strain confirmation');",
+    )?;
+    Ok(())
+}
+
+/// WP-80: 21 CFR Part 11 electronic signatures.
+///
+/// The signature itself is an `electronic_signature` event in `signed_events`.
+/// Its payload holds the printed name, timestamp and meaning, and the signer's
+/// key signs it. This table only indexes those events by record and date, so
+/// the Part 11 bundle and the record views can find them without parsing every
+/// ledger payload. Each row points at its ledger event, and
+/// `esignature::signature_matches_ledger` checks that the row still agrees with
+/// it, so editing a row here cannot change what was signed.
+fn migration_058_electronic_signatures(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS electronic_signatures (
+            id              TEXT PRIMARY KEY,
+            signed_event_id TEXT NOT NULL UNIQUE REFERENCES signed_events(id),
+            user_id         TEXT NOT NULL REFERENCES users(id),
+            printed_name    TEXT NOT NULL,
+            meaning         TEXT NOT NULL CHECK (meaning IN ('authored', 'reviewed', 'approved')),
+            action          TEXT NOT NULL,
+            entity_type     TEXT NOT NULL,
+            entity_id       TEXT NOT NULL,
+            reason          TEXT,
+            signed_at       TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_electronic_signatures_entity
+            ON electronic_signatures(entity_type, entity_id);
+        CREATE INDEX IF NOT EXISTS idx_electronic_signatures_signed_at
+            ON electronic_signatures(signed_at);",
+    )?;
     Ok(())
 }
 
@@ -900,7 +1779,7 @@ fn migration_038_notifications(conn: &Connection) -> DbResult<()> {
     // see the WP-52 "As built" note in ROADMAP.md for the disclosed
     // trade-off (no OS-keychain integration in this packet, unlike the
     // zero-knowledge design used for WP-59 cloud-backup credentials).
-    // `commands::backup::create_backup` redacts this column (to NULL) in the
+    // `db::backup::create_backup_file` redacts this column (to NULL) in the
     // backup file it produces, so the plaintext password lives only in the
     // live database, never in a copy that could leave the machine.
     conn.execute_batch(
@@ -4942,7 +5821,12 @@ mod tests {
     fn migration_036_seeds_twelve_permissive_rows() {
         let conn = migrated_db();
         let count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM field_permissions WHERE visible = 1", [], |r| r.get(0))
+            .query_row(
+                "SELECT COUNT(*) FROM field_permissions \
+                 WHERE visible = 1 AND entity_type IN ('strain', 'breeding_program')",
+                [],
+                |r| r.get(0),
+            )
             .unwrap();
         assert_eq!(count, 12, "4 roles x 3 seeded fields = 12 rows, all visible");
     }
@@ -4954,7 +5838,8 @@ mod tests {
             "INSERT INTO field_permissions (id, role, entity_type, field_name) VALUES ('x', 'superadmin', 'strain', 'foo')",
             [],
         );
-        assert!(result.is_err(), "CHECK constraint must reject roles outside admin/supervisor/tech/guest");
+        // The CHECK became a foreign key to `roles` in migration 064.
+        assert!(result.is_err(), "field permissions must reject roles that do not exist");
     }
 
     // ── Migration 037: environmental_readings ─────────────────────────────────
@@ -5069,6 +5954,426 @@ mod tests {
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn migration_058_electronic_signatures_reject_unknown_meanings() {
+        let conn = migrated_db();
+        // Only the CHECK constraint is under test, not the ledger reference.
+        conn.execute_batch("PRAGMA foreign_keys = OFF").unwrap();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1','u','x','U','tech')",
+            [],
+        ).unwrap();
+        let insert = |id: &str, meaning: &str| {
+            conn.execute(
+                "INSERT INTO electronic_signatures \
+                 (id, signed_event_id, user_id, printed_name, meaning, action, entity_type, entity_id, signed_at) \
+                 VALUES (?1, ?1, 'u1', 'U', ?2, 'record_review', 'specimen', 's1', '2026-01-01T00:00:00.000Z')",
+                rusqlite::params![id, meaning],
+            )
+        };
+        insert("e1", "approved").unwrap();
+        assert!(insert("e2", "witnessed").is_err(), "meaning is limited to the three Part 11 meanings");
+    }
+
+    #[test]
+    fn migration_059_seeds_the_three_sop_witness_policies() {
+        let conn = migrated_db();
+        let rows: Vec<(String, Option<String>, i64, String)> = conn
+            .prepare("SELECT event_type, match_value, required_count, allowed_roles FROM witness_policies ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|(_, _, n, roles)| *n == 1 && roles == "supervisor,admin"));
+        assert!(rows.iter().any(|(t, v, _, _)| t == "strain_status_changed" && v.as_deref() == Some("confirmed_manual")));
+    }
+
+    #[test]
+    fn migration_060_adds_node_config_and_anchor_poll_columns() {
+        let conn = migrated_db();
+        let (enabled, min_conf): (i64, i64) = conn
+            .query_row("SELECT enabled, min_confirmations FROM anchor_node_config WHERE id = 1", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((enabled, min_conf), (0, 6));
+        assert!(conn.execute("INSERT INTO anchor_node_config (id) VALUES (2)", []).is_err());
+        let cols: Vec<String> = conn
+            .prepare("PRAGMA table_info(checkpoint_anchors)")
+            .unwrap()
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        for c in ["broadcast_via", "confirmations", "block_hash", "last_polled_at"] {
+            assert!(cols.iter().any(|x| x == c), "missing column {}", c);
+        }
+    }
+
+    #[test]
+    fn migration_061_creates_spv_proof_table_keyed_by_anchor() {
+        let conn = migrated_db();
+        let cols: Vec<String> = conn
+            .prepare("PRAGMA table_info(anchor_spv_proofs)")
+            .unwrap()
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        for c in ["anchor_id", "raw_tx", "block_header", "tx_index", "merkle_branch"] {
+            assert!(cols.iter().any(|x| x == c), "missing column {}", c);
+        }
+        let insert = "INSERT INTO anchor_spv_proofs (anchor_id, chain_name, txid, raw_tx, block_hash, block_header, tx_index) \
+                      VALUES ('a1', 'dogecoin', 't', '00', 'b', '00', ?1)";
+        assert!(conn.execute(insert, [-1]).is_err());
+    }
+
+    #[test]
+    fn migration_062_adds_totp_tables_policy_and_pending_sessions() {
+        let conn = migrated_db();
+        let roles: i64 = conn
+            .query_row("SELECT COUNT(*) FROM mfa_policy WHERE required = 0", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(roles, 4, "every built-in role starts with 2FA optional");
+        let pending: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name = 'mfa_pending'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(pending, 1);
+        for t in ["user_totp", "user_recovery_codes"] {
+            let n: i64 = conn
+                .query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1", [t], |r| r.get(0))
+                .unwrap();
+            assert_eq!(n, 1, "missing table {}", t);
+        }
+    }
+
+    #[test]
+    fn migration_063_adds_directory_config_and_user_source() {
+        let conn = migrated_db();
+        let (enabled, filter): (i64, String) = conn
+            .query_row("SELECT enabled, user_filter FROM ldap_config WHERE id = 1", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert_eq!(enabled, 0);
+        assert!(filter.contains("{username}"));
+        conn.execute("INSERT INTO users (id, username, password_hash, display_name) VALUES ('u', 'u', 'x', 'U')", [])
+            .unwrap();
+        let source: String = conn.query_row("SELECT auth_source FROM users", [], |r| r.get(0)).unwrap();
+        assert_eq!(source, "local");
+        assert!(conn.execute("UPDATE users SET auth_source = 'kerberos'", []).is_err());
+        conn.execute("INSERT INTO ldap_group_roles (group_dn, role) VALUES ('CN=Lab', 'tech')", []).unwrap();
+        assert!(conn.execute("INSERT INTO ldap_group_roles (group_dn, role) VALUES ('cn=lab', 'guest')", []).is_err());
+    }
+
+    #[test]
+    fn migration_064_seeds_roles_and_rebuilds_users_against_them() {
+        let conn = migrated_db();
+        let builtins: i64 = conn.query_row("SELECT COUNT(*) FROM roles WHERE builtin = 1", [], |r| r.get(0)).unwrap();
+        assert_eq!(builtins, 4);
+        let admin_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM role_capabilities WHERE role = 'admin'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(admin_rows, 0, "admin holds everything implicitly");
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u', 'u', 'x', 'U', 'tech')",
+            [],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO sessions (id, token, user_id, expires_at) VALUES ('s', 't', 'u', datetime('now', '+1 day'))", [])
+            .unwrap();
+
+        // Rewind to 63 and migrate again: existing rows survive the rebuild.
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             DROP TABLE role_capabilities; DROP TABLE roles;
+             DELETE FROM schema_version WHERE version = 64;
+             PRAGMA foreign_keys = ON;",
+        )
+        .unwrap();
+        apply_rebuild(&conn, 64, migration_064_roles_and_capabilities).unwrap();
+        let (role, expires): (String, Option<String>) = conn
+            .query_row("SELECT role, access_expires_at FROM users WHERE id = 'u'", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert_eq!((role.as_str(), expires), ("tech", None));
+        let sessions: i64 = conn.query_row("SELECT COUNT(*) FROM sessions WHERE user_id = 'u'", [], |r| r.get(0)).unwrap();
+        assert_eq!(sessions, 1);
+
+        assert!(conn.execute("UPDATE users SET role = 'media_prep' WHERE id = 'u'", []).is_err());
+        conn.execute("INSERT INTO roles (name, label) VALUES ('media_prep', 'Media prep')", []).unwrap();
+        conn.execute("UPDATE users SET role = 'media_prep' WHERE id = 'u'", []).unwrap();
+    }
+
+    #[test]
+    fn migration_065_seeds_visible_rules_for_every_role() {
+        let conn = migrated_db();
+        conn.execute("INSERT INTO roles (name, label) VALUES ('media_prep', 'Media prep')", []).unwrap();
+        conn.execute("DELETE FROM schema_version WHERE version = 65", []).unwrap();
+        apply(&conn, 65, migration_065_declarative_field_masking).unwrap();
+        for role in ["admin", "supervisor", "tech", "guest", "media_prep"] {
+            let (rows, visible): (i64, i64) = conn
+                .query_row(
+                    "SELECT COUNT(*), SUM(visible) FROM field_permissions \
+                     WHERE role = ?1 AND entity_type IN ('specimen', 'media_batch', 'inventory_item')",
+                    [role],
+                    |r| Ok((r.get(0)?, r.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!((rows, visible), (8, 8), "role {role} must get every new field, visible");
+        }
+    }
+
+    #[test]
+    fn migration_066_adds_session_metadata_and_a_cascading_policy_table() {
+        let conn = migrated_db();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        for column in ["device", "last_seen_at"] {
+            assert!(column_exists(&conn, "sessions", column), "sessions.{column}");
+        }
+        let policies: i64 = conn.query_row("SELECT COUNT(*) FROM session_policy", [], |r| r.get(0)).unwrap();
+        assert_eq!(policies, 0, "no role is limited until an admin sets a policy");
+
+        conn.execute("INSERT INTO roles (name, label) VALUES ('night_shift', 'Night shift')", []).unwrap();
+        conn.execute("INSERT INTO session_policy (role, idle_minutes) VALUES ('night_shift', 15)", []).unwrap();
+        assert!(conn.execute("INSERT INTO session_policy (role, idle_minutes) VALUES ('nobody', 15)", []).is_err());
+        assert!(conn.execute("UPDATE session_policy SET idle_minutes = 0", []).is_err());
+        conn.execute("DELETE FROM roles WHERE name = 'night_shift'", []).unwrap();
+        let left: i64 = conn.query_row("SELECT COUNT(*) FROM session_policy", [], |r| r.get(0)).unwrap();
+        assert_eq!(left, 0, "the policy goes with its role");
+    }
+
+    #[test]
+    fn migration_067_seeds_the_policy_and_dates_local_passwords() {
+        let conn = migrated_db();
+        let (threshold, minutes, history, max_age): (i64, i64, i64, Option<i64>) = conn
+            .query_row(
+                "SELECT lockout_threshold, lockout_minutes, password_history, password_max_age_days FROM auth_policy",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!((threshold, minutes, history, max_age), (5, 15, 5, None));
+        assert!(conn.execute("INSERT INTO auth_policy (id) VALUES (2)", []).is_err(), "one row only");
+
+        // The seeded admin predates the column and is dated from its last update.
+        let undated: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM users WHERE auth_source = 'local' AND password_changed_at IS NULL",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(undated, 0);
+        assert!(column_exists(&conn, "users", "password_changed_at"));
+    }
+
+    #[test]
+    fn migration_068_seeds_a_disabled_loopback_api() {
+        let conn = migrated_db();
+        let (enabled, bind, port): (i64, String, i64) = conn
+            .query_row("SELECT enabled, bind, port FROM api_config", [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap();
+        assert_eq!((enabled, bind.as_str(), port), (0, "loopback", 8470));
+        assert!(conn.execute("UPDATE api_config SET bind = 'public'", []).is_err());
+        assert!(conn.execute("UPDATE api_config SET port = 80", []).is_err());
+    }
+
+    #[test]
+    fn migration_076_keeps_one_unit_per_specimen_and_slot() {
+        let conn = migrated_db();
+        conn.execute_batch(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT');
+             INSERT INTO specimens (id, accession_number, species_id, initiation_date) VALUES
+                 ('a', 'A', 'sp', '2026-01-01'), ('b', 'B', 'sp', '2026-01-01');
+             INSERT INTO trials (id, lab_profile, name, factors, outcomes, replicates)
+                 VALUES ('t', 'plant_tissue_culture', 'BAP', '[]', '[]', 1);
+             INSERT INTO trial_units (id, trial_id, specimen_id, treatment, replicate) VALUES ('u', 't', 'a', 0, 1);",
+        )
+        .unwrap();
+        let unit = |id: &str, specimen: &str, treatment: i64| {
+            conn.execute(
+                "INSERT INTO trial_units (id, trial_id, specimen_id, treatment, replicate) VALUES (?1, 't', ?2, ?3, 1)",
+                rusqlite::params![id, specimen, treatment],
+            )
+        };
+        assert!(unit("u2", "a", 1).is_err(), "a specimen appears once per trial");
+        assert!(unit("u3", "b", 0).is_err(), "one specimen per treatment and replicate");
+        unit("u4", "b", 1).unwrap();
+        assert!(conn
+            .execute("INSERT INTO trials (id, lab_profile, name, factors, outcomes, replicates) VALUES ('t2', 'plant_tissue_culture', 'bap', '[]', '[]', 1)", [])
+            .is_err());
+        let observe = |id: &str| {
+            conn.execute("INSERT INTO trial_observations (id, unit_id, outcome, passage, value) VALUES (?1, 'u', 'shoots', 3, 4.0)", [id])
+        };
+        observe("o1").unwrap();
+        assert!(observe("o2").is_err(), "one value per outcome and passage");
+        conn.execute("DELETE FROM trials WHERE id = 't'", []).unwrap();
+        let left: i64 = conn.query_row("SELECT COUNT(*) FROM trial_observations", [], |r| r.get(0)).unwrap();
+        assert_eq!(left, 0);
+    }
+
+    #[test]
+    fn migration_074_records_one_merge_per_retired_specimen() {
+        let conn = migrated_db();
+        assert!(column_exists(&conn, "specimens", "merged_into"));
+        conn.execute_batch(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT');
+             INSERT INTO specimens (id, accession_number, species_id, initiation_date) VALUES
+                 ('a', 'A', 'sp', '2026-01-01'), ('b', 'B', 'sp', '2026-01-01'), ('c', 'C', 'sp', '2026-01-01');",
+        )
+        .unwrap();
+        let merge = |id: &str, survivor: &str, retired: &str| {
+            conn.execute(
+                "INSERT INTO specimen_merges (id, survivor_id, retired_id) VALUES (?1, ?2, ?3)",
+                rusqlite::params![id, survivor, retired],
+            )
+        };
+        merge("m1", "a", "b").unwrap();
+        assert!(merge("m2", "c", "b").is_err(), "a specimen is retired by one merge only");
+        merge("m3", "a", "c").unwrap();
+        let moved: String = conn.query_row("SELECT moved FROM specimen_merges WHERE id = 'm1'", [], |r| r.get(0)).unwrap();
+        assert_eq!(moved, "{}");
+    }
+
+    #[test]
+    fn migration_073_seeds_a_stage_machine_for_each_built_in_profile() {
+        let conn = migrated_db();
+        let allowed = |profile: &str, from: &str, to: &str| -> bool {
+            conn.query_row(
+                "SELECT COUNT(*) FROM stage_transitions WHERE lab_profile = ?1 AND from_stage = ?2 AND to_stage = ?3",
+                rusqlite::params![profile, from, to],
+                |r| r.get::<_, i64>(0),
+            )
+            .unwrap()
+                == 1
+        };
+        assert!(allowed("plant_tissue_culture", "root", "plantlet"));
+        assert!(!allowed("plant_tissue_culture", "root", "explant"), "no going back to initiation");
+        assert!(allowed("plant_tissue_culture", "shoot", "custom") && allowed("plant_tissue_culture", "custom", "shoot"));
+        assert!(allowed("mycology", "agar", "contaminated") && !allowed("mycology", "contaminated", "agar"));
+        assert!(allowed("cell_culture", "cryo_stock", "thawed"));
+        // Every seeded row names real stages of its profile and leaves a non-terminal one.
+        let strays: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM stage_transitions t
+                 LEFT JOIN stages a ON a.profile = t.lab_profile AND a.code = t.from_stage AND a.is_terminal = 0
+                 LEFT JOIN stages b ON b.profile = t.lab_profile AND b.code = t.to_stage
+                 WHERE a.id IS NULL OR b.id IS NULL",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(strays, 0);
+        let required: String = conn
+            .query_row(
+                "SELECT required_fields FROM stage_transitions
+                 WHERE lab_profile = 'plant_tissue_culture' AND from_stage = 'plantlet' AND to_stage = 'acclimatized'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(required, r#"["custom.acclimatization_date","custom.survival_count"]"#);
+        let seeded: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM custom_fields WHERE lab_profile = 'plant_tissue_culture'
+                 AND field_key IN ('acclimatization_date', 'survival_count')",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(seeded, 2);
+    }
+
+    #[test]
+    fn migration_072_keeps_search_names_unique_per_owner_and_lab() {
+        let conn = migrated_db();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        conn.execute("INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'ann', 'x', 'Ann', 'tech')", []).unwrap();
+        let add = |id: &str, profile: &str| {
+            conn.execute(
+                "INSERT INTO saved_searches (id, owner_id, lab_profile, name, filter) VALUES (?1, 'u1', ?2, 'Stale', 'generation > 1')",
+                rusqlite::params![id, profile],
+            )
+        };
+        add("s1", "mycology").unwrap();
+        assert!(add("s2", "mycology").is_err());
+        add("s3", "cell_culture").unwrap();
+        conn.execute("DELETE FROM users WHERE id = 'u1'", []).unwrap();
+        let left: i64 = conn.query_row("SELECT COUNT(*) FROM saved_searches", [], |r| r.get(0)).unwrap();
+        assert_eq!(left, 0, "a user's searches go with the account");
+    }
+
+    #[test]
+    fn migration_071_adds_custom_field_tables_and_value_columns() {
+        let conn = migrated_db();
+        assert!(column_exists(&conn, "specimens", "custom_fields"));
+        assert!(column_exists(&conn, "subcultures", "custom_fields"));
+        let add = |id: &str, key: &str, field_type: &str| {
+            conn.execute(
+                "INSERT INTO custom_fields (id, lab_profile, entity, field_key, label, field_type) \
+                 VALUES (?1, 'cell_culture', 'specimen', ?2, 'L', ?3)",
+                rusqlite::params![id, key, field_type],
+            )
+        };
+        add("f1", "donor_id", "text").unwrap();
+        assert!(add("f2", "donor_id", "text").is_err(), "one definition per profile, entity and key");
+        assert!(add("f3", "ploidy", "integer").is_err(), "unknown field type");
+        conn.execute("INSERT INTO custom_field_options (field_id, code, label) VALUES ('f1', 'a', 'A')", []).unwrap();
+        assert!(conn.execute("INSERT INTO custom_field_options (field_id, code, label) VALUES ('f1', 'a', 'B')", []).is_err());
+    }
+
+    #[test]
+    fn migration_070_allows_one_template_per_profile_and_species() {
+        let conn = migrated_db();
+        conn.execute("INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp1', 'G', 's', 'GS')", []).unwrap();
+        let add = |id: &str, species: Option<&str>| {
+            conn.execute(
+                "INSERT INTO accession_templates (id, lab_profile, species_id, pattern) VALUES (?1, 'mycology', ?2, '{SEQ}')",
+                rusqlite::params![id, species],
+            )
+        };
+        add("t1", None).unwrap();
+        add("t2", Some("sp1")).unwrap();
+        assert!(add("t3", None).is_err(), "a second profile-wide template");
+        assert!(add("t4", Some("sp1")).is_err(), "a second template for the species");
+        assert!(conn.execute("UPDATE accession_templates SET split_suffix = 'roman' WHERE id = 't1'", []).is_err());
+    }
+
+    #[test]
+    fn migration_069_admits_service_accounts_and_keeps_every_user_column() {
+        let conn = migrated_db();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        for column in ["access_expires_at", "password_changed_at", "directory_dn"] {
+            assert!(column_exists(&conn, "users", column), "users.{column}");
+        }
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role, auth_source) \
+             VALUES ('svc', 'sensor-gw', '!', 'Sensor gateway', 'tech', 'service')",
+            [],
+        )
+        .unwrap();
+        assert!(conn.execute("UPDATE users SET auth_source = 'kerberos' WHERE id = 'svc'", []).is_err());
+
+        conn.execute(
+            "INSERT INTO api_tokens (id, user_id, name, token_hash, hint, scopes) \
+             VALUES ('t1', 'svc', 'Greenhouse', 'h1', 'stk_abcd', 'sensor.record')",
+            [],
+        )
+        .unwrap();
+        assert!(
+            conn.execute(
+                "INSERT INTO api_tokens (id, user_id, name, token_hash, hint, scopes) \
+                 VALUES ('t2', 'svc', 'Copy', 'h1', 'stk_abcd', 'read')",
+                [],
+            )
+            .is_err(),
+            "digests are unique"
+        );
+        conn.execute("DELETE FROM users WHERE id = 'svc'", []).unwrap();
+        let left: i64 = conn.query_row("SELECT COUNT(*) FROM api_tokens", [], |r| r.get(0)).unwrap();
+        assert_eq!(left, 0, "tokens go with their account");
+    }
+
     // ── Migration harness atomicity ───────────────────────────────────────────
 
     #[test]
@@ -5430,6 +6735,30 @@ mod tests {
         assert_eq!(fts_match_query("say \"hi\"").unwrap(), "\"say \"\"hi\"\"\"");
     }
 
+    #[test]
+    fn search_restricted_to_visible_columns_cannot_match_a_hidden_one() {
+        use crate::db::queries::{fts_match_query, fts_restrict_columns, SPECIMEN_SEARCH_COLUMNS};
+        let conn = search_fixture_db();
+        conn.execute("UPDATE specimens SET provenance = 'Secret Valley collection' WHERE id = 's4'", [])
+            .unwrap();
+        let hits = |columns: &[&str]| -> Vec<String> {
+            let expr = fts_restrict_columns(columns, &fts_match_query("Secret Valley").unwrap());
+            conn.prepare(
+                "SELECT fs.id FROM specimens_fts f JOIN specimens fs ON fs.rowid = f.rowid \
+                 WHERE f.specimens_fts MATCH ?1",
+            )
+            .unwrap()
+            .query_map([expr], |r| r.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+        };
+        assert_eq!(hits(SPECIMEN_SEARCH_COLUMNS), vec!["s4".to_string()]);
+        let without_provenance: Vec<&str> =
+            SPECIMEN_SEARCH_COLUMNS.iter().copied().filter(|c| *c != "provenance").collect();
+        assert!(hits(&without_provenance).is_empty(), "a hidden provenance must not be searchable");
+    }
+
     #[test]
     fn migration_054_backfills_rows_that_predate_the_index() {
         // The index is created on an existing database, so pre-existing rows
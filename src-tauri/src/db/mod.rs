@@ -1,13 +1,28 @@
+pub mod accession;
 pub mod analytics;
+pub mod backup;
 pub mod backend;
+pub mod contamination_sources;
+pub mod custom_fields;
 pub mod dashboard;
+pub mod export;
+pub mod import;
+pub mod initiation;
+pub mod lineage;
 pub mod fixtures;
 pub mod migrations;
 pub mod notifications;
 pub mod permissions;
 pub mod postgres;
 pub mod queries;
+pub mod saved_searches;
 pub mod sensors;
+pub mod specimen_merge;
+pub mod specimen_query;
+pub mod specimen_restore;
+pub mod specimens;
+pub mod stage_transitions;
+pub mod trials;
 pub mod sync;
 pub mod vocabulary;
 pub mod work_queue;
@@ -32,6 +47,8 @@ pub type DbResult<T> = Result<T, DbError>;
 
 pub struct Database {
     pub conn: Connection,
+    /// The file this database was opened from; `None` in memory.
+    pub path: Option<PathBuf>,
 }
 
 impl Database {
@@ -40,21 +57,35 @@ impl Database {
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent).ok();
         }
-        let conn = Connection::open(&db_path)?;
+        Self::open(&db_path)
+    }
+
+    /// Open the database file at `path` with the app's connection settings.
+    /// `stelo-cli --db` uses this to work on a database other than the
+    /// default one.
+    pub fn open(path: &std::path::Path) -> DbResult<Self> {
+        let conn = Connection::open(path)?;
         conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON; PRAGMA busy_timeout=5000;")?;
-        Ok(Database { conn })
+        Ok(Database { conn, path: Some(path.to_path_buf()) })
     }
 
     pub fn new_in_memory() -> DbResult<Self> {
         let conn = Connection::open_in_memory()?;
         conn.execute_batch("PRAGMA foreign_keys=ON;")?;
-        Ok(Database { conn })
+        Ok(Database { conn, path: None })
     }
 
     pub fn db_path() -> PathBuf {
         Self::default_path()
     }
 
+    /// A file kept next to this database, such as `totp.key`, so that
+    /// `stelo-cli --db` reads the one that belongs to the database it opened.
+    /// An in-memory database uses the default location.
+    pub fn beside(&self, name: &str) -> PathBuf {
+        self.path.clone().unwrap_or_else(Self::default_path).with_file_name(name)
+    }
+
     fn default_path() -> PathBuf {
         let mut path = dirs_next().unwrap_or_else(|| PathBuf::from("."));
         path.push("stelo_ptc.db");
@@ -26,11 +26,13 @@
 //! than a silent gap.
 //!
 //! Sending real email requires a configured SMTP server and is exercised via
-//! `commands::notifications::send_test_email`; it cannot be unit-tested
+//! `commands::notifications::send_test_email`; delivery cannot be unit-tested
 //! without a live mail server, matching the same limitation already
-//! documented for WP-50's PostgreSQL connector.
+//! documented for WP-50's PostgreSQL connector. A server that cannot be
+//! reached is an `AppError::external("smtp", …)`, and that path is tested.
 
 use super::DbResult;
+use crate::error::AppError;
 use crate::models::notifications::{
     NotificationCandidate, NotificationPreference, SetSmtpConfigRequest, SmtpConfig,
 };
@@ -52,7 +54,7 @@ pub fn severity_meets_threshold(candidate_severity: &str, min_severity: &str) ->
 /// Builds one notification candidate per open Work Queue item. `urgency` on
 /// `WorkQueueItem` already uses the same three-value vocabulary
 /// ("critical"/"high"/"normal") as notification severity, so no mapping is needed.
-pub fn compute_due_notifications(conn: &Connection) -> Result<Vec<NotificationCandidate>, String> {
+pub fn compute_due_notifications(conn: &Connection) -> Result<Vec<NotificationCandidate>, AppError> {
     let items = crate::db::work_queue::compute_work_queue_items(conn)?;
     let candidates = items
         .into_iter()
@@ -209,34 +211,36 @@ fn get_smtp_config_internal(conn: &Connection) -> DbResult<SmtpConfigInternal> {
 
 /// Sends one email via the configured SMTP server. Requires `host`,
 /// `from_address`, `username`, and `password` to all be set — returns a clear
-/// error naming the missing field otherwise. This cannot be exercised in a
-/// unit test without a live SMTP server; see the module doc comment.
-pub fn send_email(conn: &Connection, to: &str, subject: &str, body: &str) -> Result<(), String> {
-    let config = get_smtp_config_internal(conn).map_err(|e| e.to_string())?;
+/// error naming the missing field otherwise. A server that refuses or drops
+/// the message is an `external_service` error for `"smtp"`.
+pub fn send_email(conn: &Connection, to: &str, subject: &str, body: &str) -> Result<(), AppError> {
+    let config = get_smtp_config_internal(conn)?;
 
-    let host = config.host.filter(|h| !h.is_empty()).ok_or("SMTP host is not configured")?;
-    let from = config.from_address.filter(|f| !f.is_empty()).ok_or("SMTP from address is not configured")?;
-    let username = config.username.filter(|u| !u.is_empty()).ok_or("SMTP username is not configured")?;
-    let password = config.password.filter(|p| !p.is_empty()).ok_or("SMTP password is not configured")?;
+    let missing = |what: &str| AppError::invalid(format!("SMTP {} is not configured", what));
+    let host = config.host.filter(|h| !h.is_empty()).ok_or_else(|| missing("host"))?;
+    let from = config.from_address.filter(|f| !f.is_empty()).ok_or_else(|| missing("from address"))?;
+    let username = config.username.filter(|u| !u.is_empty()).ok_or_else(|| missing("username"))?;
+    let password = config.password.filter(|p| !p.is_empty()).ok_or_else(|| missing("password"))?;
 
     let email = lettre::Message::builder()
-        .from(from.parse().map_err(|e| format!("Invalid from address: {}", e))?)
-        .to(to.parse().map_err(|e| format!("Invalid recipient address: {}", e))?)
+        .from(from.parse().map_err(|e| AppError::invalid(format!("Invalid from address: {}", e)))?)
+        .to(to.parse().map_err(|e| AppError::validation("to_address", format!("Invalid recipient address: {}", e)))?)
         .subject(subject)
         .body(body.to_string())
-        .map_err(|e| format!("Failed to build email: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Failed to build email: {}", e)))?;
 
     let creds = lettre::transport::smtp::authentication::Credentials::new(username, password);
 
     let mailer_builder = if config.use_tls {
         lettre::SmtpTransport::starttls_relay(&host)
-            .map_err(|e| format!("Failed to configure SMTP transport: {}", e))?
+            .map_err(|e| AppError::external("smtp", format!("Failed to configure SMTP transport: {}", e)))?
     } else {
         lettre::SmtpTransport::builder_dangerous(&host)
     };
     let mailer = mailer_builder.port(config.port).credentials(creds).build();
 
-    lettre::Transport::send(&mailer, &email).map_err(|e| format!("Failed to send email: {}", e))?;
+    lettre::Transport::send(&mailer, &email)
+        .map_err(|e| AppError::external("smtp", format!("Failed to send email: {}", e)))?;
     Ok(())
 }
 
@@ -486,4 +490,30 @@ mod tests {
             .unwrap();
         assert_eq!(stored, candidate.body);
     }
+
+    #[test]
+    fn an_unreachable_smtp_server_is_an_external_service_error() {
+        let conn = migrated_db();
+        set_smtp_config(
+            &conn,
+            &SetSmtpConfigRequest {
+                host: Some("127.0.0.1".to_string()),
+                port: 1,
+                username: Some("lab".to_string()),
+                password: Some("secret".to_string()),
+                from_address: Some("lab@example.com".to_string()),
+                use_tls: false,
+            },
+        )
+        .unwrap();
+
+        let err = send_email(&conn, "qa@example.com", "subject", "body").unwrap_err();
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["code"], "external_service");
+        assert_eq!(json["params"]["service"], "smtp");
+        assert_eq!(crate::api::status_for(&err), 502);
+
+        let err = send_email(&conn, "not an address", "subject", "body").unwrap_err();
+        assert_eq!(serde_json::to_value(&err).unwrap()["params"]["field"], "to_address");
+    }
 }
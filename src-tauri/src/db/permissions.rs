@@ -1,19 +1,20 @@
-//! WP-55 — Field-level permissions foundation.
+//! WP-55 — Field-level permissions; WP-87 — declarative masking.
 //!
-//! Masking is applied only in the read path, per-entity, by the command
-//! layer calling `mask_optional_field` after loading a row (Rust has no
-//! runtime reflection, so per-entity masking calls are the idiomatic
-//! approach here — matching how every other cross-cutting concern in this
-//! codebase, e.g. audit logging, is applied explicitly at each call site
-//! rather than through a generic interceptor).
+//! [`MASKABLE_FIELDS`] declares every `(entity, field)` a role can be denied,
+//! and masking is applied once, when a command's response is serialized: a
+//! read command returns [`Masked<T>`] (built with [`mask_for_role`]) instead
+//! of `T`, and `Masked` serializes `T`, replaces each hidden field's value
+//! with [`RESTRICTED_MARKER`] and emits the result. Which entity a type
+//! belongs to is declared once per type via [`Maskable`], so a new command
+//! returning a `Specimen` cannot forget to mask one field and remember
+//! another. The test `every_command_returning_a_maskable_entity_masks_it`
+//! scans the command layer and fails the build if a command returns a
+//! maskable type without wrapping it in `Masked`.
 //!
-//! Currently wired into two representative, explicitly-sensitive fields —
-//! `strains.genomic_fingerprint` and `breeding_programs.goal` /
-//! `target_traits` — matching the packet's own acceptance-criteria examples.
-//! Extending masking to additional fields/entities is a mechanical follow-up
-//! (add a seed row + one call to `FieldPermissionSet::mask_optional_field` at
-//! the relevant read site) and is intentionally left as future work rather
-//! than a sweeping rewrite of every read command in the codebase.
+//! Exports (`db::export`) run their rows through the same
+//! [`FieldPermissionSet::mask_object`]. Passports (`passport::store`) drop the
+//! hidden provenance note from the signed document instead of signing the
+//! marker. The audit log is never masked: it always stores the full value.
 //!
 //! **Write-path guard, mandatory wherever a masked field is also writable:**
 //! a read command can return the [`RESTRICTED_MARKER`] placeholder in place
@@ -26,10 +27,20 @@
 //! unconditionally wrote `genomic_fingerprint` on every call, and
 //! `StrainManager.svelte` pre-filled its form from the (possibly masked)
 //! current value — see the regression tests below and in `commands::strains`.
+//! Numeric fields (`cost_per_batch`, `cost_per_unit`) cannot carry the marker
+//! on write: a request with a string there fails to deserialize.
 
 use super::DbResult;
+use crate::error::AppError;
+use crate::models::breeding::BreedingProgram;
+use crate::models::inventory::InventoryItem;
+use crate::models::media::MediaBatch;
 use crate::models::permissions::FieldPermission;
+use crate::models::specimen::{PaginatedResponse, Specimen};
+use crate::models::strain::Strain;
 use rusqlite::{params, Connection};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
 use std::collections::HashMap;
 
 /// Returned in place of a real value when a field is masked. Chosen instead
@@ -38,32 +49,40 @@ use std::collections::HashMap;
 /// `Option<String>` was already `None` for an unrelated reason.
 pub const RESTRICTED_MARKER: &str = "[RESTRICTED]";
 
-/// Canonical registry of every field the read path actually masks at a call
-/// site (WP-55). This is the single source of truth that ties together three
-/// things which would otherwise drift silently:
-///   1. the migration seed (`field_permissions` default rows, migration 036),
-///   2. the `apply_field_permissions` mask calls in the command layer
-///      (`commands::strains`, `commands::breeding`), and
+/// Canonical registry of every field a role can be denied (WP-55, WP-87).
+/// Field names are the keys of the entity's serialized JSON. This one list
+/// ties together:
+///   1. the migration seed (`field_permissions` default rows, migrations 036
+///      and 065),
+///   2. what [`FieldPermissionSet::mask_object`] replaces on every response
+///      and export row of that entity, and
 ///   3. what the admin permissions editor is allowed to configure.
 ///
-/// A pair listed here MUST have a corresponding `mask_optional_field` call on
-/// **every** read path that returns it. A pair NOT listed here is never masked,
-/// even if a `field_permissions` row somehow exists for it — so we refuse to
-/// create such a row (see [`set_field_permission`]) rather than let an admin
-/// believe they hid a field that the read path still returns in full. The test
+/// A pair NOT listed here is never masked, even if a `field_permissions` row
+/// somehow exists for it — so we refuse to create such a row (see
+/// [`set_field_permission`]) rather than let an admin believe they hid a
+/// field that the read path still returns in full. The test
 /// `maskable_fields_registry_matches_migration_seed` fails the build if this
-/// list and the migration seed ever disagree, catching "added a seed row but
-/// forgot the call site" (and the reverse) before it ships.
+/// list and the migration seed ever disagree, and
+/// `every_maskable_field_is_a_key_of_its_entity` if a name does not match a
+/// serialized key (a typo would otherwise mask nothing).
 ///
-/// This is the deliberate hardening of the call-site masking model (WP-55
-/// strategy decision, v1.40.2): keep per-call-site masking — the idiomatic
-/// choice given Rust has no runtime reflection and the masked surface is
-/// intentionally tiny — but pin the set of masked fields to one auditable
-/// constant so the model cannot drift out of sync unnoticed.
+/// `compliance_record.permit_number` is deliberately absent: compliance
+/// records are the permit register itself, and the regulatory bundles that
+/// read specimen permits need `compliance.export` — grant that capability
+/// only to roles that may see permits.
 pub const MASKABLE_FIELDS: &[(&str, &str)] = &[
     ("strain", "genomic_fingerprint"),
     ("breeding_program", "goal"),
     ("breeding_program", "target_traits"),
+    ("specimen", "provenance"),
+    ("specimen", "source_plant"),
+    ("specimen", "permit_number"),
+    ("specimen", "ip_notes"),
+    ("media_batch", "supplier_info"),
+    ("media_batch", "cost_per_batch"),
+    ("inventory_item", "supplier"),
+    ("inventory_item", "cost_per_unit"),
 ];
 
 /// Whether `(entity_type, field_name)` is a field the read path actually masks
@@ -121,14 +140,14 @@ pub fn mask_optional_field(
 /// backend guarantee that a masked read result can never be round-tripped
 /// back into the database as if it were real data — it holds regardless of
 /// what any frontend does or fails to do.
-pub fn reject_if_restricted_marker(value: Option<&str>, field_label: &str) -> Result<(), String> {
+pub fn reject_if_restricted_marker(value: Option<&str>, field_label: &str) -> Result<(), AppError> {
     if value == Some(RESTRICTED_MARKER) {
-        Err(format!(
+        Err(AppError::invalid(format!(
             "'{}' cannot be saved as \"{}\" — that placeholder is shown when a field is \
              restricted for your role; it is never real data. Leave the field blank to keep \
              the existing value unchanged, or ask an admin to grant visibility before editing it.",
             field_label, RESTRICTED_MARKER
-        ))
+        )))
     } else {
         Ok(())
     }
@@ -138,6 +157,7 @@ pub fn reject_if_restricted_marker(value: Option<&str>, field_label: &str) -> Re
 /// in memory — the fix for the N+1 pattern `mask_optional_field` has when
 /// called once per field per row across a list of N records. Load one of
 /// these per request (not per row) and reuse it for every row.
+#[derive(Debug)]
 pub struct FieldPermissionSet {
     /// `(entity_type, field_name) -> visible`. Absent entries default to
     /// visible, matching `is_field_visible`'s permissive-default behavior.
@@ -170,6 +190,31 @@ impl FieldPermissionSet {
             .unwrap_or(true)
     }
 
+    /// Attaches these rules to a response; see [`Masked`].
+    pub fn masked<T: Maskable>(self, value: T) -> Masked<T> {
+        Masked { value, perms: self }
+    }
+
+    /// Replaces every hidden, non-null field of `entity_type` in a serialized
+    /// object with [`RESTRICTED_MARKER`]. A null stays null (nothing to hide)
+    /// and the key is never removed, matching [`mask_optional_field`]. A value
+    /// that is not a JSON object is left alone.
+    pub fn mask_object(&self, entity_type: &str, value: &mut Value) {
+        let Some(object) = value.as_object_mut() else {
+            return;
+        };
+        for (entity, field) in MASKABLE_FIELDS {
+            if *entity != entity_type || self.is_visible(entity, field) {
+                continue;
+            }
+            if let Some(v) = object.get_mut(*field) {
+                if !v.is_null() {
+                    *v = Value::String(RESTRICTED_MARKER.to_string());
+                }
+            }
+        }
+    }
+
     /// In-memory equivalent of [`mask_optional_field`] — no query per call.
     pub fn mask_optional_field(&self, entity_type: &str, field_name: &str, value: Option<String>) -> Option<String> {
         if value.is_none() {
@@ -183,6 +228,99 @@ impl FieldPermissionSet {
     }
 }
 
+/// A response type whose serialized form carries maskable fields (WP-87).
+pub trait Maskable: Serialize {
+    /// Masks `value`, the serialized form of `Self`, in place.
+    fn mask_json(perms: &FieldPermissionSet, value: &mut Value);
+}
+
+macro_rules! maskable_entity {
+    ($($ty:ty => $entity:literal),* $(,)?) => {
+        $(impl Maskable for $ty {
+            fn mask_json(perms: &FieldPermissionSet, value: &mut Value) {
+                perms.mask_object($entity, value);
+            }
+        })*
+    };
+}
+
+maskable_entity! {
+    Specimen => "specimen",
+    Strain => "strain",
+    BreedingProgram => "breeding_program",
+    InventoryItem => "inventory_item",
+}
+
+impl Maskable for MediaBatch {
+    fn mask_json(perms: &FieldPermissionSet, value: &mut Value) {
+        perms.mask_object("media_batch", value);
+        // Each hormone line is a draw from an inventory item and copies that
+        // item's supplier, so the inventory rules apply to it.
+        if let Some(Value::Array(hormones)) = value.get_mut("hormones") {
+            for hormone in hormones {
+                perms.mask_object("inventory_item", hormone);
+            }
+        }
+    }
+}
+
+impl<T: Maskable> Maskable for Vec<T> {
+    fn mask_json(perms: &FieldPermissionSet, value: &mut Value) {
+        if let Value::Array(items) = value {
+            for item in items {
+                T::mask_json(perms, item);
+            }
+        }
+    }
+}
+
+impl<T: Maskable> Maskable for Option<T> {
+    fn mask_json(perms: &FieldPermissionSet, value: &mut Value) {
+        if !value.is_null() {
+            T::mask_json(perms, value);
+        }
+    }
+}
+
+impl<T: Maskable> Maskable for PaginatedResponse<T> {
+    fn mask_json(perms: &FieldPermissionSet, value: &mut Value) {
+        if let Some(items) = value.get_mut("items") {
+            Vec::<T>::mask_json(perms, items);
+        }
+    }
+}
+
+/// A command response with the caller's field rules attached. Masking happens
+/// in `serialize`, so every field of `T` listed in [`MASKABLE_FIELDS`] is
+/// masked on the way out whatever path produced the value.
+#[derive(Debug)]
+pub struct Masked<T> {
+    value: T,
+    perms: FieldPermissionSet,
+}
+
+impl<T> Masked<T> {
+    /// The unmasked value, for callers inside the backend.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: Maskable> Serialize for Masked<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut value = serde_json::to_value(&self.value).map_err(serde::ser::Error::custom)?;
+        T::mask_json(&self.perms, &mut value);
+        value.serialize(serializer)
+    }
+}
+
+/// Wraps `value` with `role`'s field rules — the one call a read command
+/// makes before returning a maskable entity.
+pub fn mask_for_role<T: Maskable>(conn: &Connection, role: &str, value: T) -> Result<Masked<T>, AppError> {
+    let perms = FieldPermissionSet::load(conn, role)?;
+    Ok(perms.masked(value))
+}
+
 pub fn list_field_permissions(conn: &Connection) -> DbResult<Vec<FieldPermission>> {
     let mut stmt = conn.prepare(
         "SELECT id, role, entity_type, field_name, visible \
@@ -220,7 +358,7 @@ pub fn set_field_permission(
     // Without this, an admin could toggle "hide" on an arbitrary field and get
     // a persisted `field_permissions` row that the read path never consults —
     // a false sense of security (the field is still returned in full). Only the
-    // fields wired into a call-site mask (see `MASKABLE_FIELDS`) may be
+    // fields listed in `MASKABLE_FIELDS` may be
     // configured; everything else is rejected loudly instead of silently
     // no-op'd.
     if !is_maskable_field(entity_type, field_name) {
@@ -253,17 +391,6 @@ pub fn set_field_permission(
     Ok(())
 }
 
-/// Pure, independently-testable admin gate for the permissions editor,
-/// mirroring the `check_profile_change_allowed` pattern (WP-26) of keeping
-/// authorization logic as a plain function the command layer calls into.
-pub fn validate_admin_role(role: &str) -> Result<(), String> {
-    if role == "admin" {
-        Ok(())
-    } else {
-        Err("Only admins can manage field permissions".to_string())
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -314,11 +441,11 @@ mod tests {
 
     #[test]
     fn maskable_fields_registry_matches_migration_seed() {
-        // Tripwire for the call-site masking model (WP-55): the fields the
-        // migration seeds into `field_permissions` must be exactly the fields
-        // the code masks at a call site (`MASKABLE_FIELDS`). If someone seeds a
-        // new field without wiring a mask call (or removes a mask without
-        // updating the seed), this fails — the two can never drift silently.
+        // Tripwire (WP-55): the fields the migrations seed into
+        // `field_permissions` must be exactly the fields the code masks
+        // (`MASKABLE_FIELDS`). If someone seeds a new field without adding it
+        // to the registry (or the reverse), this fails — the two can never
+        // drift silently.
         let conn = migrated_db();
         let mut seeded: Vec<(String, String)> = conn
             .prepare("SELECT DISTINCT entity_type, field_name FROM field_permissions")
@@ -366,6 +493,8 @@ mod tests {
         assert!(is_maskable_field("strain", "genomic_fingerprint"));
         assert!(is_maskable_field("breeding_program", "goal"));
         assert!(is_maskable_field("breeding_program", "target_traits"));
+        assert!(is_maskable_field("specimen", "ip_notes"));
+        assert!(is_maskable_field("inventory_item", "cost_per_unit"));
         assert!(!is_maskable_field("strain", "name"));
         assert!(!is_maskable_field("specimen", "notes"));
     }
@@ -414,18 +543,11 @@ mod tests {
     fn list_field_permissions_returns_seeded_rows() {
         let conn = migrated_db();
         let perms = list_field_permissions(&conn).unwrap();
-        // 4 roles x 3 fields (genomic_fingerprint, goal, target_traits) seeded by migration 036.
-        assert_eq!(perms.len(), 12);
+        // 4 roles x 3 fields seeded by migration 036, plus 4 x 8 by migration 065.
+        assert_eq!(perms.len(), 4 * MASKABLE_FIELDS.len());
         assert!(perms.iter().all(|p| p.visible));
     }
 
-    #[test]
-    fn validate_admin_role_accepts_admin_rejects_others() {
-        assert!(validate_admin_role("admin").is_ok());
-        assert!(validate_admin_role("supervisor").is_err());
-        assert!(validate_admin_role("tech").is_err());
-        assert!(validate_admin_role("guest").is_err());
-    }
 
     #[test]
     fn masking_never_reaches_audit_log_writes() {
@@ -461,13 +583,150 @@ mod tests {
         assert_eq!(stored, "ATCG-SENSITIVE-VALUE", "audit log must always capture the full, unmasked value");
     }
 
+    // ── Declarative masking (WP-87) ───────────────────────────────────────────
+
+    /// The field names serde knows for `T`, read by a deserializer that stops
+    /// at `deserialize_struct`. These are the keys `T` serializes under.
+    fn field_names<T: serde::de::DeserializeOwned>() -> &'static [&'static str] {
+        use serde::de::{value::Error, Error as _, Visitor};
+
+        struct Probe<'a>(&'a mut &'static [&'static str]);
+        impl<'de> serde::Deserializer<'de> for Probe<'_> {
+            type Error = Error;
+            fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+                Err(Error::custom("not a struct"))
+            }
+            fn deserialize_struct<V: Visitor<'de>>(
+                self,
+                _: &'static str,
+                fields: &'static [&'static str],
+                _: V,
+            ) -> Result<V::Value, Error> {
+                *self.0 = fields;
+                Err(Error::custom("probed"))
+            }
+            serde::forward_to_deserialize_any! {
+                bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+                byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map enum
+                identifier ignored_any
+            }
+        }
+
+        let mut fields: &'static [&'static str] = &[];
+        let _ = T::deserialize(Probe(&mut fields));
+        fields
+    }
+
+    #[test]
+    fn every_maskable_field_is_a_key_of_its_entity() {
+        let entities: [(&str, &[&str]); 5] = [
+            ("specimen", field_names::<Specimen>()),
+            ("strain", field_names::<Strain>()),
+            ("breeding_program", field_names::<BreedingProgram>()),
+            ("media_batch", field_names::<MediaBatch>()),
+            ("inventory_item", field_names::<InventoryItem>()),
+        ];
+        for (entity, field) in MASKABLE_FIELDS {
+            let (_, keys) = entities
+                .iter()
+                .find(|(e, _)| e == entity)
+                .unwrap_or_else(|| panic!("{entity} has no Maskable type registered in this test"));
+            assert!(keys.contains(field), "{entity}.{field} is not a serialized key, so it would never be masked");
+        }
+    }
+
+    #[test]
+    fn every_command_returning_a_maskable_entity_masks_it() {
+        // Tripwire for the central model: a command that returns one of these
+        // types bare would serialize it unmasked. Scans every
+        // `#[tauri::command]` signature in the command layer.
+        const MASKABLE_TYPES: [&str; 5] = ["Specimen", "Strain", "BreedingProgram", "MediaBatch", "InventoryItem"];
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/commands");
+        let mut masked = 0;
+        let mut unmasked = Vec::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let src = std::fs::read_to_string(&path).unwrap();
+            for chunk in src.split("#[tauri::command]").skip(1) {
+                let signature = &chunk[..chunk.find('{').unwrap()];
+                let returns = signature.rsplit_once("->").map(|(_, r)| r).unwrap_or("");
+                let names_entity = returns
+                    .split(|c: char| !c.is_alphanumeric() && c != '_')
+                    .any(|word| MASKABLE_TYPES.contains(&word));
+                if !names_entity {
+                    continue;
+                }
+                if returns.contains("Masked<") {
+                    masked += 1;
+                } else {
+                    let name = signature.split("fn ").nth(1).and_then(|s| s.split('(').next()).unwrap_or("?");
+                    unmasked.push(format!("{}::{}", path.file_name().unwrap().to_string_lossy(), name));
+                }
+            }
+        }
+        assert!(unmasked.is_empty(), "these commands return a maskable entity without Masked<…>: {unmasked:?}");
+        assert!(masked >= 20, "the scan found only {masked} masked commands — is it still reading the command layer?");
+    }
+
+    #[test]
+    fn masked_replaces_hidden_fields_in_nested_responses() {
+        let conn = migrated_db();
+        set_field_permission(&conn, "tech", "inventory_item", "supplier", false).unwrap();
+        set_field_permission(&conn, "tech", "inventory_item", "cost_per_unit", false).unwrap();
+        let perms = FieldPermissionSet::load(&conn, "tech").unwrap();
+
+        let mut page = serde_json::json!({
+            "items": [
+                { "supplier": "Sigma", "cost_per_unit": 12.5, "catalog_number": "S-1" },
+                { "supplier": null, "cost_per_unit": null, "catalog_number": "S-2" },
+            ],
+            "total": 2,
+        });
+        PaginatedResponse::<InventoryItem>::mask_json(&perms, &mut page);
+        assert_eq!(page["items"][0]["supplier"], RESTRICTED_MARKER);
+        assert_eq!(page["items"][0]["cost_per_unit"], RESTRICTED_MARKER, "numbers are masked too");
+        assert_eq!(page["items"][0]["catalog_number"], "S-1");
+        assert!(page["items"][1]["supplier"].is_null(), "nothing to hide stays null");
+
+        // A media batch's hormone lines follow the inventory rules.
+        let mut batch = serde_json::json!({
+            "supplier_info": "Duchefa",
+            "hormones": [{ "hormone_name": "BAP", "supplier": "Sigma" }],
+        });
+        MediaBatch::mask_json(&perms, &mut batch);
+        assert_eq!(batch["supplier_info"], "Duchefa", "media rules are separate");
+        assert_eq!(batch["hormones"][0]["supplier"], RESTRICTED_MARKER);
+    }
+
+    #[test]
+    fn masked_serializes_through_the_rules_and_keeps_the_value_intact() {
+        #[derive(Serialize)]
+        struct Row {
+            provenance: Option<String>,
+        }
+        impl Maskable for Row {
+            fn mask_json(perms: &FieldPermissionSet, value: &mut Value) {
+                perms.mask_object("specimen", value);
+            }
+        }
+        let conn = migrated_db();
+        set_field_permission(&conn, "guest", "specimen", "provenance", false).unwrap();
+        let row = || Row { provenance: Some("Field site 7".to_string()) };
+
+        let hidden = mask_for_role(&conn, "guest", vec![row()]).unwrap();
+        assert_eq!(serde_json::to_string(&hidden).unwrap(), r#"[{"provenance":"[RESTRICTED]"}]"#);
+        assert_eq!(hidden.into_inner()[0].provenance.as_deref(), Some("Field site 7"));
+        let shown = mask_for_role(&conn, "supervisor", Some(row())).unwrap();
+        assert_eq!(serde_json::to_string(&shown).unwrap(), r#"{"provenance":"Field site 7"}"#);
+    }
+
     // ── reject_if_restricted_marker (write-path corruption guard) ────────────
 
     #[test]
     fn reject_if_restricted_marker_rejects_the_exact_marker() {
         let result = reject_if_restricted_marker(Some(RESTRICTED_MARKER), "Genomic fingerprint");
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Genomic fingerprint"));
+        assert!(result.unwrap_err().message().contains("Genomic fingerprint"));
     }
 
     #[test]
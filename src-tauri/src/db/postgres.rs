@@ -91,24 +91,27 @@ pub fn split_sql_statements(sql: &str) -> Vec<&str> {
 mod live {
     use super::{split_sql_statements, BOOTSTRAP_SCHEMA_SQL};
     use crate::db::backend::validate_connection_string;
+    use crate::error::AppError;
     use sqlx::postgres::PgPoolOptions;
     use std::time::Duration;
 
     /// Opens a short-lived connection pool, verifies it with `SELECT 1`, and
     /// closes it. Used by the Settings UI's "Test Connection" action.
-    pub async fn test_connection(connection_string: &str) -> Result<String, String> {
+    pub async fn test_connection(connection_string: &str) -> Result<String, AppError> {
         validate_connection_string(connection_string)?;
         let pool = PgPoolOptions::new()
             .max_connections(1)
             .acquire_timeout(Duration::from_secs(5))
             .connect(connection_string)
             .await
-            .map_err(|e| format!("Failed to connect to PostgreSQL: {}", e))?;
+            .map_err(|e| AppError::external("postgres", format!("Failed to connect to PostgreSQL: {}", e)))?;
 
         let result = sqlx::query("SELECT 1")
             .execute(&pool)
             .await
-            .map_err(|e| format!("Connection succeeded but the health-check query failed: {}", e));
+            .map_err(|e| {
+                AppError::external("postgres", format!("Connection succeeded but the health-check query failed: {}", e))
+            });
 
         pool.close().await;
         result?;
@@ -118,19 +121,19 @@ mod live {
     /// Connects and creates the foundation schema (see `BOOTSTRAP_SCHEMA_SQL`).
     /// Returns the names of the tables created/verified. Safe to call repeatedly
     /// — every statement is `CREATE TABLE IF NOT EXISTS`.
-    pub async fn bootstrap_schema(connection_string: &str) -> Result<Vec<String>, String> {
+    pub async fn bootstrap_schema(connection_string: &str) -> Result<Vec<String>, AppError> {
         validate_connection_string(connection_string)?;
         let pool = PgPoolOptions::new()
             .max_connections(1)
             .acquire_timeout(Duration::from_secs(5))
             .connect(connection_string)
             .await
-            .map_err(|e| format!("Failed to connect to PostgreSQL: {}", e))?;
+            .map_err(|e| AppError::external("postgres", format!("Failed to connect to PostgreSQL: {}", e)))?;
 
         for statement in split_sql_statements(BOOTSTRAP_SCHEMA_SQL) {
             if let Err(e) = sqlx::query(statement).execute(&pool).await {
                 pool.close().await;
-                return Err(format!("Failed to run bootstrap statement: {}", e));
+                return Err(AppError::external("postgres", format!("Failed to run bootstrap statement: {}", e)));
             }
         }
         pool.close().await;
@@ -147,16 +150,18 @@ mod live {
 
 #[cfg(not(feature = "postgres"))]
 mod live {
+    use crate::error::AppError;
+
     const NOT_COMPILED_MSG: &str =
         "This build was not compiled with PostgreSQL support. Rebuild with \
          `--features postgres` to enable PostgreSQL connectivity.";
 
-    pub async fn test_connection(_connection_string: &str) -> Result<String, String> {
-        Err(NOT_COMPILED_MSG.to_string())
+    pub async fn test_connection(_connection_string: &str) -> Result<String, AppError> {
+        Err(AppError::invalid(NOT_COMPILED_MSG))
     }
 
-    pub async fn bootstrap_schema(_connection_string: &str) -> Result<Vec<String>, String> {
-        Err(NOT_COMPILED_MSG.to_string())
+    pub async fn bootstrap_schema(_connection_string: &str) -> Result<Vec<String>, AppError> {
+        Err(AppError::invalid(NOT_COMPILED_MSG))
     }
 }
 
@@ -204,7 +209,7 @@ mod tests {
     async fn test_connection_without_feature_returns_clear_error() {
         let result = test_connection("postgres://localhost/db").await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("--features postgres"));
+        assert!(result.unwrap_err().message().contains("--features postgres"));
     }
 
     #[cfg(not(feature = "postgres"))]
@@ -212,6 +217,6 @@ mod tests {
     async fn bootstrap_schema_without_feature_returns_clear_error() {
         let result = bootstrap_schema("postgres://localhost/db").await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("--features postgres"));
+        assert!(result.unwrap_err().message().contains("--features postgres"));
     }
 }
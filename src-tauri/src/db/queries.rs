@@ -10,6 +10,7 @@ use crate::models::strain::{
     GenerationalStats, HybridizationEventRecord, PedigreeEdge, PedigreeExport, PedigreeNode,
     SpecimenSummary, StrainSpecimenTree, StrainSummary, SuggestGenerationLabelResponse,
 };
+use crate::models::audit::{CreateCheckpointResult, VerifyChainResult};
 use crate::models::compliance::MycoplasmaStatus;
 use crate::models::compliance::ComplianceFlag;
 use crate::models::cryo::{CreateFrozenVialRequest, FrozenVial, ListFrozenVialsParams};
@@ -18,6 +19,7 @@ use crate::models::breeding::{
     BreedingProgram, BreedingRecord, CreateBreedingProgramRequest,
     CreateBreedingRecordRequest, GenerationalSummary,
 };
+use crate::error::AppError;
 
 /// Zero-hash used as prev_hash when a lineage has no prior entry.
 pub const ZERO_HASH: &str =
@@ -304,6 +306,216 @@ pub fn auto_checkpoint_lineages(
     Ok(created_ids)
 }
 
+/// Walk one lineage's audit chain and check, for every row:
+///   1. The stored entry_hash matches the recomputed hash.
+///   2. The row's prev_hash matches the previous row's entry_hash.
+///
+/// Reports the chain_seq of the first break detected, if any. Shared by the
+/// `verify_audit_lineage` command and `stelo-cli verify audit`.
+pub fn verify_audit_lineage(conn: &Connection, lineage_id: String) -> Result<VerifyChainResult, AppError> {
+    struct ChainRow {
+        chain_seq: i64,
+        user_id: Option<String>,
+        entity_type: String,
+        action: String,
+        entity_id: Option<String>,
+        created_at: String,
+        details: Option<String>,
+        prev_hash: String,
+        entry_hash: String,
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT chain_seq, user_id, entity_type, action, entity_id, created_at, details, prev_hash, entry_hash \
+         FROM audit_log \
+         WHERE lineage_id = ?1 AND entry_hash IS NOT NULL \
+         ORDER BY chain_seq ASC",
+    )?;
+
+    let rows: Vec<ChainRow> = stmt.query_map(rusqlite::params![lineage_id], |r| {
+        Ok(ChainRow {
+            chain_seq: r.get(0)?,
+            user_id: r.get(1)?,
+            entity_type: r.get(2)?,
+            action: r.get(3)?,
+            entity_id: r.get(4)?,
+            created_at: r.get(5)?,
+            details: r.get(6)?,
+            prev_hash: r.get(7)?,
+            entry_hash: r.get(8)?,
+        })
+    })?
+      // Strict: dropping a row here would shorten the chain, and the
+      // gap/hash checks would then report it as TAMPERING. A mapping
+      // failure must not masquerade as tamper evidence.
+      .collect::<rusqlite::Result<Vec<_>>>()
+      .map_err(|e| format!("Failed to read audit rows to verify the audit lineage: {}", e))?;
+
+    if rows.is_empty() {
+        return Ok(VerifyChainResult {
+            lineage_id,
+            ok: true,
+            checked: 0,
+            first_break_seq: None,
+            message: "No chained entries found for this lineage.".to_string(),
+        });
+    }
+
+    // For root lineages the first row's prev_hash is ZERO_HASH; for fork lineages
+    // (split specimens) it is the parent's last entry_hash. Either way, we anchor
+    // verification at the first row's own claimed prev_hash — we cannot independently
+    // verify the anchor without walking the parent lineage, but we verify every
+    // subsequent link and every entry_hash within this lineage.
+    //
+    // Previous (buggy) code used ZERO_HASH as the fixed anchor, which always
+    // reported "Chain broken at seq 1" for any forked lineage.
+    let mut prev_entry_hash = rows[0].prev_hash.clone();
+    // `idx` is the number of entries fully verified before the current row, which is
+    // the correct "checked" count on a break. (Deriving it from `chain_seq - 1`
+    // underflowed to usize::MAX when the break was on a genesis seq-0 entry.)
+    for (idx, row) in rows.iter().enumerate() {
+        // Verify the link: this row's prev_hash must equal the previous row's entry_hash
+        // (or the anchor for the very first row, which trivially passes).
+        if row.prev_hash != prev_entry_hash {
+            return Ok(VerifyChainResult {
+                lineage_id,
+                ok: false,
+                checked: idx,
+                first_break_seq: Some(row.chain_seq),
+                message: format!(
+                    "Chain broken at seq {} — prev_hash does not match the preceding entry's hash.",
+                    row.chain_seq
+                ),
+            });
+        }
+
+        // Verify the hash of this row's content.
+        let canonical = audit_canonical_bytes(
+            &lineage_id,
+            row.chain_seq,
+            &row.created_at,
+            row.user_id.as_deref().unwrap_or(""),
+            &row.entity_type,
+            row.entity_id.as_deref().unwrap_or(""),
+            &row.action,
+            row.details.as_deref().unwrap_or(""),
+        );
+        let computed = compute_entry_hash(&canonical, &row.prev_hash);
+        if computed != row.entry_hash {
+            return Ok(VerifyChainResult {
+                lineage_id,
+                ok: false,
+                checked: idx,
+                first_break_seq: Some(row.chain_seq),
+                message: format!(
+                    "Tamper detected at seq {} — stored hash does not match recomputed hash.",
+                    row.chain_seq
+                ),
+            });
+        }
+
+        prev_entry_hash = row.entry_hash.clone();
+    }
+
+    let checked = rows.len();
+    Ok(VerifyChainResult {
+        lineage_id,
+        ok: true,
+        checked,
+        first_break_seq: None,
+        message: format!(
+            "All {} entries verified — chain is intact.",
+            checked
+        ),
+    })
+}
+
+/// Create a Merkle checkpoint over a contiguous seq range of one lineage's
+/// audit chain. Omitted bounds default to the lineage's first and last
+/// chained entries. Shared by `create_audit_checkpoint` and
+/// `stelo-cli checkpoint`; the caller checks `audit.checkpoint`.
+pub fn create_audit_checkpoint(
+    conn: &Connection,
+    lineage_id: &str,
+    start_seq: Option<i64>,
+    end_seq: Option<i64>,
+    user_id: &str,
+) -> Result<CreateCheckpointResult, AppError> {
+    let actual_start: i64 = if let Some(s) = start_seq {
+        s
+    } else {
+        conn.query_row(
+            "SELECT MIN(chain_seq) FROM audit_log WHERE lineage_id = ?1 AND entry_hash IS NOT NULL",
+            rusqlite::params![lineage_id],
+            |r| r.get::<_, Option<i64>>(0),
+        )?
+        .ok_or_else(|| AppError::invalid(format!("No chained entries found in lineage '{}'.", lineage_id)))?
+    };
+
+    let actual_end: i64 = if let Some(e) = end_seq {
+        e
+    } else {
+        conn.query_row(
+            "SELECT MAX(chain_seq) FROM audit_log WHERE lineage_id = ?1 AND entry_hash IS NOT NULL",
+            rusqlite::params![lineage_id],
+            |r| r.get::<_, Option<i64>>(0),
+        )?
+        .ok_or_else(|| AppError::invalid(format!("No chained entries found in lineage '{}'.", lineage_id)))?
+    };
+
+    if actual_start > actual_end {
+        return Err(AppError::validation(
+            "start_seq",
+            format!("start_seq ({}) must be ≤ end_seq ({}).", actual_start, actual_end),
+        ));
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT entry_hash FROM audit_log \
+         WHERE lineage_id = ?1 AND chain_seq >= ?2 AND chain_seq <= ?3 AND entry_hash IS NOT NULL \
+         ORDER BY chain_seq ASC",
+    )?;
+
+    let hashes: Vec<String> = stmt
+        .query_map(rusqlite::params![lineage_id, actual_start, actual_end], |r| r.get::<_, String>(0))?
+        // Strict: dropping a row here would shorten the chain, and the
+        // gap/hash checks would then report it as TAMPERING. A mapping
+        // failure must not masquerade as tamper evidence.
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| AppError::internal(format!("Failed to read audit rows to build the checkpoint: {}", e)))?;
+
+    if hashes.is_empty() {
+        return Err(AppError::invalid(format!(
+            "No chained entries found in lineage '{}' for seq range [{}, {}].",
+            lineage_id, actual_start, actual_end
+        )));
+    }
+
+    let entry_count = hashes.len() as i64;
+    let merkle_root = build_merkle_root(&hashes);
+    let checkpoint_id = uuid::Uuid::new_v4().to_string();
+    let created_at = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+
+    conn.execute(
+        "INSERT INTO audit_checkpoints \
+         (id, lineage_id, start_seq, end_seq, entry_count, merkle_root, created_at, created_by, is_auto) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0)",
+        rusqlite::params![
+            &checkpoint_id, lineage_id, actual_start, actual_end,
+            entry_count, &merkle_root, &created_at, user_id
+        ],
+    )?;
+
+    Ok(CreateCheckpointResult {
+        checkpoint_id,
+        lineage_id: lineage_id.to_string(),
+        start_seq: actual_start,
+        end_seq: actual_end,
+        entry_count,
+        merkle_root,
+    })
+}
+
 /// SHA-256(canonical_bytes || prev_hash_utf8), returned as lowercase hex.
 pub fn compute_entry_hash(canonical: &[u8], prev_hash: &str) -> String {
     let mut hasher = Sha256::new();
@@ -367,6 +579,34 @@ pub fn generate_accession_number(conn: &Connection, species_code: &str, date: &s
     Ok(format!("{}-{:03}", prefix, seq))
 }
 
+/// `count` consecutive accession numbers starting at the one
+/// `generate_accession_number` would hand out next (WP-94). Fails rather than
+/// skipping over a number that is already taken, so a batch's range has no gaps.
+pub fn generate_accession_range(conn: &Connection, species_code: &str, date: &str, count: usize) -> DbResult<Vec<String>> {
+    let first = generate_accession_number(conn, species_code, date)?;
+    let prefix = format!("{}-{}", date, species_code);
+    let start: i64 = first[prefix.len() + 1..]
+        .parse()
+        .map_err(|_| DbError::Constraint(format!("Unexpected accession number '{}'", first)))?;
+    let mut range = Vec::with_capacity(count);
+    for seq in start..start + count as i64 {
+        let candidate = format!("{}-{:03}", prefix, seq);
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM specimens WHERE accession_number = ?1)",
+            params![&candidate],
+            |r| r.get(0),
+        )?;
+        if taken {
+            return Err(DbError::Constraint(format!(
+                "Accession number {} is already in use, so {} consecutive numbers from {} are not free",
+                candidate, count, first
+            )));
+        }
+        range.push(candidate);
+    }
+    Ok(range)
+}
+
 /// Log an audit entry that continues the entity's own lineage chain.
 /// The lineage_id is entity_id (or "system" when entity_id is None).
 /// All existing call sites use this function without change.
@@ -442,6 +682,7 @@ pub fn log_audit_at_seq_zero(
             lineage_id, ZERO_HASH, entry_hash
         ],
     )?;
+    crate::signed_ledger::sign_audited_mutation(conn, user_id, action, entity_type, entity_id, new_value, details, &entry_hash);
     Ok(())
 }
 
@@ -538,6 +779,11 @@ fn log_audit_impl(
             lineage_id, next_seq, prev_hash, entry_hash
         ],
     )?;
+    // WP-79: every audited mutation is also signed into the ledger (best-effort).
+    crate::signed_ledger::sign_audited_mutation(
+        conn, entry.user_id, entry.action, entry.entity_type, entry.entity_id,
+        entry.new_value, entry.details, &entry_hash,
+    );
     Ok(())
 }
 
@@ -627,6 +873,7 @@ pub fn log_audit_strain_genesis(
             lineage_id, prev_hash, entry_hash
         ],
     )?;
+    crate::signed_ledger::sign_audited_mutation(conn, user_id, action, entity_type, entity_id, new_value, details, &entry_hash);
     Ok(())
 }
 
@@ -688,6 +935,7 @@ pub fn log_audit_taxon_genesis(
             lineage_id, prev_hash, entry_hash
         ],
     )?;
+    crate::signed_ledger::sign_audited_mutation(conn, user_id, action, entity_type, entity_id, new_value, details, &entry_hash);
     Ok(())
 }
 
@@ -739,6 +987,7 @@ pub fn log_audit_species_genesis(
             lineage_id, prev_hash, entry_hash
         ],
     )?;
+    crate::signed_ledger::sign_audited_mutation(conn, user_id, action, entity_type, entity_id, new_value, details, &entry_hash);
     Ok(())
 }
 
@@ -776,7 +1025,7 @@ pub fn validate_strain_status_transition(
     next: &str,
     confirmation_basis: Option<&str>,
     genomic_fingerprint: Option<&str>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     fn level(s: &str) -> i32 {
         match s {
             "unverified" => 0,
@@ -789,32 +1038,34 @@ pub fn validate_strain_status_transition(
 
     let next_level = level(next);
     if next_level < 0 {
-        return Err(format!("Unknown strain status: '{}'", next));
+        return Err(AppError::validation("status", format!("Unknown strain status: '{}'", next)));
     }
     let current_level = level(current);
 
     if current_level >= 2 && next_level < current_level {
-        return Err(format!(
+        return Err(AppError::invalid(format!(
             "Cannot downgrade strain status from '{}' to '{}'",
             current, next
-        ));
+        )));
     }
 
     if next == "confirmed_manual" {
         match confirmation_basis.map(str::trim) {
             Some(b) if !b.is_empty() => {}
-            _ => return Err(
-                "Status 'confirmed_manual' requires a non-empty confirmation_basis".to_string()
-            ),
+            _ => return Err(AppError::validation(
+                "confirmation_basis",
+                "Status 'confirmed_manual' requires a non-empty confirmation_basis",
+            )),
         }
     }
 
     if next == "confirmed_genomic" {
         match genomic_fingerprint.map(str::trim) {
             Some(fp) if !fp.is_empty() => {}
-            _ => return Err(
-                "Status 'confirmed_genomic' requires a non-null genomic_fingerprint".to_string()
-            ),
+            _ => return Err(AppError::validation(
+                "genomic_fingerprint",
+                "Status 'confirmed_genomic' requires a non-null genomic_fingerprint",
+            )),
         }
     }
 
@@ -840,7 +1091,7 @@ pub fn apply_strain_status_update(
     claimed_at: Option<&str>,
     confirmation_basis: Option<&str>,
     genomic_fingerprint: Option<&str>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     validate_strain_status_transition(
         current_status,
         next_status,
@@ -866,7 +1117,7 @@ pub fn apply_strain_status_update(
             id
         ],
     )
-    .map_err(|e| format!("Failed to update status: {}", e))?;
+    .map_err(|e| AppError::internal(format!("Failed to update status: {}", e)))?;
 
     Ok(())
 }
@@ -877,19 +1128,22 @@ pub fn apply_strain_status_update(
 pub fn check_profile_change_allowed(
     specimen_count: i64,
     confirmation: Option<&str>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     if specimen_count == 0 {
         return Ok(());
     }
     match confirmation.map(str::trim) {
         Some("CHANGE PROFILE") => Ok(()),
-        _ => Err(format!(
-            "This lab has {} specimen{}. Switching the active lab profile changes which \
-             lab you are viewing — existing cultures keep the lab they were created in \
-             and will be hidden until you switch back. Nothing is deleted or relabelled. \
-             To confirm the change, type exactly: CHANGE PROFILE",
-            specimen_count,
-            if specimen_count == 1 { "" } else { "s" }
+        _ => Err(AppError::validation(
+            "confirmation",
+            format!(
+                "This lab has {} specimen{}. Switching the active lab profile changes which \
+                 lab you are viewing — existing cultures keep the lab they were created in \
+                 and will be hidden until you switch back. Nothing is deleted or relabelled. \
+                 To confirm the change, type exactly: CHANGE PROFILE",
+                specimen_count,
+                if specimen_count == 1 { "" } else { "s" }
+            ),
         )),
     }
 }
@@ -924,6 +1178,17 @@ pub fn fts_match_query(raw: &str) -> Option<String> {
     Some(format!("\"{}\"", trimmed.replace('"', "\"\"")))
 }
 
+/// The `specimens` text columns free-text search matches; all of them are in
+/// the `specimens_fts` index.
+pub const SPECIMEN_SEARCH_COLUMNS: &[&str] = &["accession_number", "notes", "location", "provenance", "source_plant"];
+
+/// Restricts an FTS5 match expression from [`fts_match_query`] to `columns`.
+/// WP-87: search drops the columns a role cannot see, since which rows match
+/// would otherwise reveal what the hidden value contains.
+pub fn fts_restrict_columns(columns: &[&str], fts_query: &str) -> String {
+    format!("{{{}}} : {}", columns.join(" "), fts_query)
+}
+
 /// Paginated query helper
 pub struct PaginationParams {
     pub page: u32,
@@ -2532,9 +2797,7 @@ pub fn thaw_frozen_vial(
         |row| row.get(0),
     ).map_err(|_| DbError::Constraint("Species not found".into()))?;
 
-    let accession = generate_accession_number(conn, &species_code, thaw_date)?;
     let specimen_id = uuid::Uuid::new_v4().to_string();
-    let qr_data = format!("STELO:{}", accession);
 
     // Resolve generation and root from the source specimen, if present.
     let (parent_gen, parent_root): (i32, Option<String>) = if let Some(ref src_id) = vial.specimen_id {
@@ -2569,6 +2832,11 @@ pub fn thaw_frozen_vial(
 
     let tx = conn.unchecked_transaction()
         .map_err(|e| DbError::Constraint(format!("Transaction start failed: {}", e)))?;
+    // Numbered by the recovered culture's lab and species template (WP-95).
+    let accession = crate::db::accession::allocate(&tx, &child_lab_profile, &vial.species_id, &species_code, thaw_date, 1)
+        .map_err(|e| DbError::Constraint(e.to_string()))?
+        .remove(0);
+    let qr_data = format!("STELO:{}", accession);
 
     // Decrement vial inventory.
     tx.execute(
@@ -3243,6 +3511,19 @@ pub fn reanchor_taxon_chain(
         ],
     )?;
 
+    // WP-79: the genesis entries above bypass `log_audit*`, so sign the
+    // re-anchor once, explicitly, as a single ledger event.
+    crate::signed_ledger::try_append_signed_event(
+        &tx,
+        performed_by,
+        crate::signed_ledger::lifecycle::TAXON_CHAIN_REANCHORED,
+        "taxon",
+        Some(taxon_id),
+        &crate::signed_ledger::lifecycle::reanchor(
+            taxon_id, &event_id, reason, [affected_taxa, affected_species, affected_strains, affected_specimens],
+        ),
+    );
+
     tx.commit()?;
 
     Ok(ReanchorResult {
@@ -3395,13 +3676,16 @@ mod wp64_reanchor_tests {
 
     #[test]
     fn admin_role_required_gate_matches_the_predicate_used_by_the_command() {
-        // commands::taxa::reanchor_taxon_chain gates on `user.role.is_admin()`;
-        // this proves that exact predicate accepts admin and rejects every
-        // other role, since the pure db function itself has no role concept.
-        assert!(UserRole::Admin.is_admin());
-        assert!(!UserRole::Supervisor.is_admin());
-        assert!(!UserRole::Tech.is_admin());
-        assert!(!UserRole::Guest.is_admin());
+        // commands::taxa::reanchor_taxon_chain requires `taxonomy.reanchor`;
+        // this proves only admin holds it, since the pure db function itself
+        // has no role concept.
+        use crate::auth::roles::{has_capability, Capability};
+        let conn = seeded_db();
+        let cap = Capability::TaxonomyReanchor;
+        assert!(has_capability(&conn, &UserRole::Admin, cap).unwrap());
+        assert!(!has_capability(&conn, &UserRole::Supervisor, cap).unwrap());
+        assert!(!has_capability(&conn, &UserRole::Tech, cap).unwrap());
+        assert!(!has_capability(&conn, &UserRole::Guest, cap).unwrap());
     }
 
     #[test]
@@ -3423,15 +3707,15 @@ mod wp64_reanchor_tests {
     fn new_reanchor_lineage_verifies_cleanly() {
         let conn = seeded_db();
         let result = reanchor_taxon_chain(&conn, "g1", "admin-1", "Reclassified per updated APG taxonomy source").unwrap();
+        // (user_id, action, entity_type, entity_id, details, created_at, prev_hash, entry_hash)
+        type GenesisRow = (Option<String>, String, String, Option<String>, Option<String>, String, String, String);
 
         for lineage in [
             format!("g1#reanchor-{}", result.reanchor_event_id),
             format!("sp1#reanchor-{}", result.reanchor_event_id),
             format!("st1#reanchor-{}", result.reanchor_event_id),
         ] {
-            let (user_id, action, entity_type, entity_id, details, created_at, prev_hash, entry_hash): (
-                Option<String>, String, String, Option<String>, Option<String>, String, String, String,
-            ) = conn
+            let (user_id, action, entity_type, entity_id, details, created_at, prev_hash, entry_hash): GenesisRow = conn
                 .query_row(
                     "SELECT user_id, action, entity_type, entity_id, details, created_at, prev_hash, entry_hash \
                      FROM audit_log WHERE lineage_id = ?1 AND chain_seq = 0",
@@ -3710,7 +3994,7 @@ mod tests {
     #[test]
     fn merkle_single_leaf_returns_itself() {
         let leaf = "abc123".repeat(10);
-        assert_eq!(build_merkle_root(&[leaf.clone()]), leaf);
+        assert_eq!(build_merkle_root(std::slice::from_ref(&leaf)), leaf);
     }
 
     #[test]
@@ -4160,14 +4444,14 @@ mod tests {
     #[test]
     fn profile_change_with_specimens_blocked_without_confirmation() {
         let err = check_profile_change_allowed(5, None).unwrap_err();
-        assert!(err.contains("5 specimens"), "error should report count: {err}");
-        assert!(err.contains("CHANGE PROFILE"), "error should name the phrase: {err}");
+        assert!(err.message().contains("5 specimens"), "error should report count: {err}");
+        assert!(err.message().contains("CHANGE PROFILE"), "error should name the phrase: {err}");
     }
 
     #[test]
     fn profile_change_with_specimens_blocked_on_wrong_confirmation() {
         let err = check_profile_change_allowed(3, Some("yes")).unwrap_err();
-        assert!(err.contains("CHANGE PROFILE"), "error should name the phrase: {err}");
+        assert!(err.message().contains("CHANGE PROFILE"), "error should name the phrase: {err}");
     }
 
     #[test]
@@ -4184,7 +4468,7 @@ mod tests {
     #[test]
     fn profile_change_singular_specimen_grammar() {
         let err = check_profile_change_allowed(1, None).unwrap_err();
-        assert!(err.contains("1 specimen."), "should use singular 'specimen': {err}");
+        assert!(err.message().contains("1 specimen."), "should use singular 'specimen': {err}");
     }
 
     // ── WP-28: strain / status-machine / hash-chain tests ─────────────────────
@@ -4361,7 +4645,7 @@ mod tests {
     fn strain_status_confirmed_manual_rejected_without_basis() {
         let err = validate_strain_status_transition("claimed", "confirmed_manual", None, None)
             .unwrap_err();
-        assert!(err.contains("confirmation_basis"), "error must mention confirmation_basis: {err}");
+        assert!(err.message().contains("confirmation_basis"), "error must mention confirmation_basis: {err}");
     }
 
     #[test]
@@ -4381,7 +4665,7 @@ mod tests {
         let err = validate_strain_status_transition(
             "confirmed_genomic", "confirmed_manual", Some("basis"), None,
         ).unwrap_err();
-        assert!(err.contains("downgrade"),
+        assert!(err.message().contains("downgrade"),
             "error must mention downgrade: {err}");
     }
 
@@ -5744,6 +6028,22 @@ mod tests {
                 created_at TEXT NOT NULL DEFAULT (datetime('now')),
                 updated_at TEXT NOT NULL DEFAULT (datetime('now'))
             );
+            CREATE TABLE accession_templates (
+                id TEXT PRIMARY KEY,
+                lab_profile TEXT NOT NULL,
+                species_id TEXT,
+                pattern TEXT NOT NULL,
+                sequence_reset TEXT NOT NULL DEFAULT 'never',
+                split_suffix TEXT NOT NULL DEFAULT 'letter',
+                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                updated_by TEXT
+            );
+            CREATE TABLE accession_sequences (
+                template_id TEXT NOT NULL,
+                period TEXT NOT NULL,
+                last_value INTEGER NOT NULL,
+                PRIMARY KEY (template_id, period)
+            );
             CREATE TABLE audit_log (
                 id TEXT PRIMARY KEY,
                 user_id TEXT,
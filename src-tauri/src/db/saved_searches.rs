@@ -0,0 +1,209 @@
+// WP-97: saved specimen filters. A saved search is a named filter
+// expression (`db::specimen_query`) for one lab profile. Its owner may
+// share it with the lab; everyone in the lab can then run it, in the
+// specimen list, the work queue or an export, but only the owner can
+// change or delete it.
+use crate::db::specimen_query;
+use crate::error::AppError;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+pub const MAX_NAME_LEN: usize = 80;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub filter: String,
+    pub shared: bool,
+    pub lab_profile: String,
+    pub owner_id: String,
+    pub owner_name: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SaveSavedSearchRequest {
+    /// Set to update one of the caller's searches; absent to create one.
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    pub filter: String,
+    #[serde(default)]
+    pub shared: bool,
+}
+
+const SELECT: &str = "SELECT ss.id, ss.name, ss.filter, ss.shared, ss.lab_profile, ss.owner_id, u.display_name,
+                             ss.created_at, ss.updated_at
+                      FROM saved_searches ss LEFT JOIN users u ON u.id = ss.owner_id";
+
+fn row_to_search(row: &rusqlite::Row) -> rusqlite::Result<SavedSearch> {
+    Ok(SavedSearch {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        filter: row.get(2)?,
+        shared: row.get::<_, i64>(3)? != 0,
+        lab_profile: row.get(4)?,
+        owner_id: row.get(5)?,
+        owner_name: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+/// The caller's own searches and those shared in `profile`, by name.
+pub fn list(conn: &Connection, user_id: &str, profile: &str) -> Result<Vec<SavedSearch>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE ss.lab_profile = ?1 AND (ss.owner_id = ?2 OR ss.shared = 1) ORDER BY ss.name COLLATE NOCASE, ss.id",
+        SELECT
+    ))?;
+    let searches = stmt.query_map(params![profile, user_id], row_to_search)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(searches)
+}
+
+/// A search the caller may run: their own or a shared one in `profile`.
+pub fn get_visible(conn: &Connection, user_id: &str, profile: &str, id: &str) -> Result<SavedSearch, AppError> {
+    conn.query_row(
+        &format!("{} WHERE ss.id = ?1 AND ss.lab_profile = ?2 AND (ss.owner_id = ?3 OR ss.shared = 1)", SELECT),
+        params![id, profile, user_id],
+        row_to_search,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::not_found("saved_search", "Saved search not found").with_id(id))
+}
+
+/// One of the caller's own searches; a shared search of someone else's is
+/// `forbidden`, anything else `not_found`.
+fn get_owned(conn: &Connection, user_id: &str, profile: &str, id: &str) -> Result<SavedSearch, AppError> {
+    let search = get_visible(conn, user_id, profile, id)?;
+    if search.owner_id != user_id {
+        return Err(AppError::forbidden(format!("Only {} can change '{}'", search.owner_name.as_deref().unwrap_or("its owner"), search.name)));
+    }
+    Ok(search)
+}
+
+/// Creates or updates one of the caller's searches in `profile`. The filter
+/// must compile for the caller's `role`, so a saved search never hides a
+/// syntax error until someone runs it.
+pub fn save(
+    conn: &Connection,
+    user_id: &str,
+    role: &str,
+    profile: &str,
+    req: &SaveSavedSearchRequest,
+    today: NaiveDate,
+) -> Result<SavedSearch, AppError> {
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::validation("name", format!("A name is 1–{} characters", MAX_NAME_LEN)));
+    }
+    let filter = req.filter.trim();
+    specimen_query::compile(conn, role, profile, filter, today)?;
+
+    let id = match &req.id {
+        Some(id) => {
+            get_owned(conn, user_id, profile, id)?;
+            conn.execute(
+                "UPDATE saved_searches SET name = ?1, filter = ?2, shared = ?3, updated_at = datetime('now') WHERE id = ?4",
+                params![name, filter, req.shared, id],
+            )
+            .map_err(|e| duplicate_name(e, name))?;
+            id.clone()
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO saved_searches (id, owner_id, lab_profile, name, filter, shared) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![id, user_id, profile, name, filter, req.shared],
+            )
+            .map_err(|e| duplicate_name(e, name))?;
+            id
+        }
+    };
+    get_visible(conn, user_id, profile, &id)
+}
+
+fn duplicate_name(e: rusqlite::Error, name: &str) -> AppError {
+    match e {
+        rusqlite::Error::SqliteFailure(f, _) if f.code == rusqlite::ErrorCode::ConstraintViolation => {
+            AppError::conflict(format!("You already have a saved search named '{}'", name))
+        }
+        other => other.into(),
+    }
+}
+
+/// Deletes one of the caller's searches and returns it.
+pub fn delete(conn: &Connection, user_id: &str, profile: &str, id: &str) -> Result<SavedSearch, AppError> {
+    let search = get_owned(conn, user_id, profile, id)?;
+    conn.execute("DELETE FROM saved_searches WHERE id = ?1", params![id])?;
+    Ok(search)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::{run_all, seed_defaults};
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 6, 30).unwrap()
+    }
+
+    fn search_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        seed_defaults(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES
+                 ('ann', 'ann', 'x', 'Ann', 'tech'), ('bo', 'bo', 'x', 'Bo', 'tech');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn req(id: Option<&str>, name: &str, filter: &str, shared: bool) -> SaveSavedSearchRequest {
+        SaveSavedSearchRequest { id: id.map(str::to_string), name: name.to_string(), filter: filter.to_string(), shared }
+    }
+
+    const PTC: &str = "plant_tissue_culture";
+
+    #[test]
+    fn private_searches_stay_private_and_shared_ones_are_read_only() {
+        let conn = search_db();
+        let mine = save(&conn, "ann", "tech", PTC, &req(None, "Stale", "last_passage_date <= today - 40d", false), today()).unwrap();
+        let shared = save(&conn, "ann", "tech", PTC, &req(None, "Room B", "location contains 'room b'", true), today()).unwrap();
+        assert_eq!(shared.owner_name.as_deref(), Some("Ann"));
+
+        let names = |user: &str| list(&conn, user, PTC).unwrap().into_iter().map(|s| s.name).collect::<Vec<_>>();
+        assert_eq!(names("ann"), ["Room B", "Stale"]);
+        assert_eq!(names("bo"), ["Room B"]);
+        assert!(list(&conn, "ann", "mycology").unwrap().is_empty(), "searches belong to their lab");
+
+        assert_eq!(get_visible(&conn, "bo", PTC, &mine.id).unwrap_err().code(), "not_found");
+        let err = save(&conn, "bo", "tech", PTC, &req(Some(&shared.id), "Mine now", "generation > 1", true), today()).unwrap_err();
+        assert_eq!(err.code(), "forbidden");
+        assert_eq!(delete(&conn, "bo", PTC, &shared.id).unwrap_err().code(), "forbidden");
+
+        let renamed = save(&conn, "ann", "tech", PTC, &req(Some(&shared.id), "Bench B", "location contains 'room b'", false), today()).unwrap();
+        assert_eq!((renamed.name.as_str(), renamed.shared), ("Bench B", false));
+        assert!(names("bo").is_empty());
+        delete(&conn, "ann", PTC, &mine.id).unwrap();
+        assert_eq!(names("ann"), ["Bench B"]);
+    }
+
+    #[test]
+    fn names_and_filters_are_checked() {
+        let conn = search_db();
+        save(&conn, "ann", "tech", PTC, &req(None, "Stale", "generation > 1", false), today()).unwrap();
+        let err = save(&conn, "ann", "tech", PTC, &req(None, " Stale ", "generation > 2", false), today()).unwrap_err();
+        assert_eq!(err.code(), "conflict");
+        // Another user may use the same name.
+        save(&conn, "bo", "tech", PTC, &req(None, "Stale", "generation > 2", false), today()).unwrap();
+
+        let err = save(&conn, "ann", "tech", PTC, &req(None, " ", "generation > 1", false), today()).unwrap_err();
+        assert!(matches!(err, AppError::Validation { field: Some("name"), .. }));
+        let err = save(&conn, "ann", "tech", PTC, &req(None, "Bad", "generation >", false), today()).unwrap_err();
+        assert!(matches!(err, AppError::Validation { field: Some("filter"), .. }));
+    }
+}
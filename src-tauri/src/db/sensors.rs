@@ -35,6 +35,7 @@
 //! only caller permitted to set a non-`manual` source.
 
 use super::DbResult;
+use crate::error::AppError;
 use crate::models::sensors::{CreateEnvironmentalReadingRequest, EnvironmentalAlert, EnvironmentalReading, ParsedReading};
 use rusqlite::{params, Connection};
 
@@ -46,14 +47,13 @@ const KNOWN_SOURCES: &[&str] = &["manual", "usb_serial", "bluetooth", "mqtt"];
 /// clear, specific error here rather than a raw SQLite constraint-violation
 /// message. This is input validation only — see the module doc comment's
 /// "Trust gap in `source`" note for what it does *not* verify.
-pub fn validate_source(source: &str) -> Result<(), String> {
+pub fn validate_source(source: &str) -> Result<(), AppError> {
     if KNOWN_SOURCES.contains(&source) {
         Ok(())
     } else {
-        Err(format!(
-            "Unknown reading source '{}'. Must be one of: {}",
-            source,
-            KNOWN_SOURCES.join(", ")
+        Err(AppError::validation(
+            "source",
+            format!("Unknown reading source '{}'. Must be one of: {}", source, KNOWN_SOURCES.join(", ")),
         ))
     }
 }
@@ -65,17 +65,14 @@ pub fn validate_source(source: &str) -> Result<(), String> {
 /// `{"temp_c": 24.5, "humidity_pct": 61.2}`). Unknown keys are skipped rather
 /// than rejecting the whole payload — a firmware update that adds a new field
 /// should not break ingestion of the fields SteloPTC already understands.
-pub fn parse_sensor_payload(raw: &str) -> Result<Vec<ParsedReading>, String> {
+pub fn parse_sensor_payload(raw: &str) -> Result<Vec<ParsedReading>, AppError> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
-        return Err("Sensor payload is empty".to_string());
+        return Err(AppError::validation("raw_payload", "Sensor payload is empty"));
     }
 
-    if trimmed.starts_with('{') {
-        parse_json_payload(trimmed)
-    } else {
-        parse_kv_payload(trimmed)
-    }
+    let parsed = if trimmed.starts_with('{') { parse_json_payload(trimmed) } else { parse_kv_payload(trimmed) };
+    parsed.map_err(|e| AppError::validation("raw_payload", e))
 }
 
 fn parse_kv_payload(payload: &str) -> Result<Vec<ParsedReading>, String> {
@@ -126,9 +123,9 @@ fn parse_json_payload(payload: &str) -> Result<Vec<ParsedReading>, String> {
 /// Sanity-range validation per reading type. Deliberately generous — this
 /// catches transport garbage (a misread byte producing e.g. `humidity_pct =
 /// 6512.0`), not precise scientific bounds.
-pub fn validate_reading_value(reading_type: &str, value: f64) -> Result<(), String> {
+pub fn validate_reading_value(reading_type: &str, value: f64) -> Result<(), AppError> {
     if !value.is_finite() {
-        return Err(format!("Reading value for '{}' is not a finite number", reading_type));
+        return Err(AppError::validation("value", format!("Reading value for '{}' is not a finite number", reading_type)));
     }
     let (min, max): (f64, f64) = match reading_type {
         "temp_c" => (-40.0, 100.0),
@@ -137,12 +134,12 @@ pub fn validate_reading_value(reading_type: &str, value: f64) -> Result<(), Stri
         "light_lux" => (0.0, 200_000.0),
         "ph" => (0.0, 14.0),
         "custom" => return Ok(()),
-        other => return Err(format!("Unknown reading_type '{}'", other)),
+        other => return Err(AppError::validation("reading_type", format!("Unknown reading_type '{}'", other))),
     };
     if value < min || value > max {
-        return Err(format!(
-            "Reading value {} for '{}' is outside the plausible range [{}, {}]",
-            value, reading_type, min, max
+        return Err(AppError::validation(
+            "value",
+            format!("Reading value {} for '{}' is outside the plausible range [{}, {}]", value, reading_type, min, max),
         ));
     }
     Ok(())
@@ -156,9 +153,9 @@ pub fn create_environmental_reading(
     conn: &Connection,
     req: &CreateEnvironmentalReadingRequest,
     created_by: Option<&str>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     if req.specimen_id.is_none() && req.subculture_id.is_none() {
-        return Err("A reading must be linked to a specimen or a subculture".to_string());
+        return Err(AppError::validation("specimen_id", "A reading must be linked to a specimen or a subculture"));
     }
     validate_reading_value(&req.reading_type, req.value)?;
 
@@ -182,8 +179,7 @@ pub fn create_environmental_reading(
             req.notes,
             created_by,
         ],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(id)
 }
 
@@ -381,8 +377,9 @@ mod tests {
     #[test]
     fn validate_source_rejects_unknown_value() {
         let err = validate_source("wifi_sensor").unwrap_err();
-        assert!(err.contains("wifi_sensor"), "error should name the rejected value: {err}");
-        assert!(err.contains("manual"), "error should list the known values: {err}");
+        assert!(err.message().contains("wifi_sensor"), "error should name the rejected value: {err}");
+        assert!(err.message().contains("manual"), "error should list the known values: {err}");
+        assert_eq!(err.params()["field"], "source");
     }
 
     #[test]
@@ -465,7 +462,7 @@ mod tests {
         };
         let err = create_environmental_reading(&conn, &req, None).unwrap_err();
         // A clear validation error, not a raw SQLite CHECK-constraint message.
-        assert!(err.contains("wifi_sensor"), "error should name the rejected value: {err}");
+        assert!(err.message().contains("wifi_sensor"), "error should name the rejected value: {err}");
         assert_eq!(list_environmental_readings(&conn, Some("sp-src-2"), 10).unwrap().len(), 0);
     }
 
@@ -0,0 +1,424 @@
+// WP-99: merging duplicate specimens. An XLSX import or a double entry can
+// leave the same culture on two records. A merge keeps one (the survivor),
+// moves the other's history onto it, and leaves the other as a tombstone:
+// archived, with `merged_into` pointing at the survivor, so its id and
+// accession number still resolve. Nothing is deleted, and both audit
+// lineages record the merge (see `commands::specimens::merge_specimens`).
+//
+// What moves: passages, attachments, reminders, environmental readings,
+// frozen vials, fruiting records, compliance records and waivers, tags and
+// child specimens. What stays on the tombstone: its audit lineage, signed
+// events, electronic signatures, issued passports, AI suggestions and trial
+// assignments (WP-102), which are records *about* that row and must keep
+// pointing at it.
+use crate::error::AppError;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Longest chain of tombstones followed before giving up; a merge flattens
+/// earlier redirects, so real chains have length one.
+const MAX_REDIRECTS: usize = 32;
+/// Deepest parent chain walked when checking that neither record descends
+/// from the other.
+const MAX_ANCESTRY: usize = 1_000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MergeSpecimensRequest {
+    /// The record that stays.
+    pub survivor_id: String,
+    /// The duplicate that becomes a tombstone.
+    pub retired_id: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// How many rows each table moved to the survivor.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MergeMoved {
+    pub subcultures: usize,
+    pub attachments: usize,
+    pub reminders: usize,
+    pub environmental_readings: usize,
+    pub frozen_vials: usize,
+    pub fruiting_records: usize,
+    pub compliance_records: usize,
+    pub compliance_waivers: usize,
+    pub tags: usize,
+    pub children: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpecimenMerge {
+    pub id: String,
+    pub survivor_id: String,
+    pub survivor_accession: String,
+    pub retired_id: String,
+    pub retired_accession: String,
+    pub reason: Option<String>,
+    pub moved: MergeMoved,
+    pub merged_by: Option<String>,
+    pub merged_at: String,
+}
+
+const SELECT: &str = "SELECT m.id, m.survivor_id, s.accession_number, m.retired_id, r.accession_number,
+                             m.reason, m.moved, m.merged_by, m.merged_at
+                      FROM specimen_merges m
+                      JOIN specimens s ON s.id = m.survivor_id
+                      JOIN specimens r ON r.id = m.retired_id";
+
+fn row_to_merge(row: &rusqlite::Row) -> rusqlite::Result<SpecimenMerge> {
+    let moved: String = row.get(6)?;
+    Ok(SpecimenMerge {
+        id: row.get(0)?,
+        survivor_id: row.get(1)?,
+        survivor_accession: row.get(2)?,
+        retired_id: row.get(3)?,
+        retired_accession: row.get(4)?,
+        reason: row.get(5)?,
+        moved: serde_json::from_str(&moved).unwrap_or_default(),
+        merged_by: row.get(7)?,
+        merged_at: row.get(8)?,
+    })
+}
+
+/// The merges that folded other records into `survivor_id`, newest first.
+pub fn list_for(conn: &Connection, survivor_id: &str) -> Result<Vec<SpecimenMerge>, AppError> {
+    let mut stmt = conn.prepare(&format!("{} WHERE m.survivor_id = ?1 ORDER BY m.merged_at DESC, m.id", SELECT))?;
+    let merges = stmt.query_map(params![survivor_id], row_to_merge)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(merges)
+}
+
+/// The record a lookup of `id` should show: `id` itself, or for a tombstone
+/// the specimen it was merged into.
+pub fn resolve(conn: &Connection, id: &str) -> Result<String, AppError> {
+    let mut current = id.to_string();
+    for _ in 0..MAX_REDIRECTS {
+        let next: Option<String> = conn
+            .query_row("SELECT merged_into FROM specimens WHERE id = ?1", params![current], |r| r.get(0))
+            .optional()?
+            .ok_or_else(|| AppError::not_found("specimen", "Specimen not found").with_id(id))?;
+        match next {
+            Some(next) => current = next,
+            None => return Ok(current),
+        }
+    }
+    Err(AppError::internal(format!("Specimen {} redirects more than {} times", id, MAX_REDIRECTS)))
+}
+
+struct Side {
+    accession: String,
+    species_id: String,
+    lab_profile: String,
+    merged_into: Option<String>,
+    root_specimen_id: Option<String>,
+    contamination_flag: bool,
+    contamination_notes: Option<String>,
+}
+
+fn side(conn: &Connection, field: &'static str, id: &str) -> Result<Side, AppError> {
+    conn.query_row(
+        "SELECT accession_number, species_id, lab_profile, merged_into, root_specimen_id,
+                contamination_flag, contamination_notes
+         FROM specimens WHERE id = ?1",
+        params![id],
+        |r| {
+            Ok(Side {
+                accession: r.get(0)?,
+                species_id: r.get(1)?,
+                lab_profile: r.get(2)?,
+                merged_into: r.get(3)?,
+                root_specimen_id: r.get(4)?,
+                contamination_flag: r.get::<_, i64>(5)? != 0,
+                contamination_notes: r.get(6)?,
+            })
+        },
+    )
+    .optional()?
+    .ok_or_else(|| AppError::validation(field, "Specimen not found"))
+}
+
+/// Whether `ancestor` is on the parent chain of `id`.
+fn descends_from(conn: &Connection, id: &str, ancestor: &str) -> Result<bool, AppError> {
+    let mut current = id.to_string();
+    for _ in 0..MAX_ANCESTRY {
+        let parent: Option<String> = conn
+            .query_row("SELECT parent_specimen_id FROM specimens WHERE id = ?1", params![current], |r| r.get(0))
+            .optional()?
+            .flatten();
+        match parent {
+            Some(p) if p == ancestor => return Ok(true),
+            Some(p) => current = p,
+            None => return Ok(false),
+        }
+    }
+    Ok(false)
+}
+
+/// Merges `req.retired_id` into `req.survivor_id`, both in `profile`. Run it
+/// inside a transaction: it writes to a dozen tables and the caller commits
+/// or rolls back as one.
+///
+/// The survivor keeps its own fields. The one exception is contamination: a
+/// flag on the duplicate carries over, so a merge never clears it. When
+/// passages move, the survivor's passages are renumbered 1…n by date and
+/// `subculture_count` set to n.
+pub fn merge(
+    conn: &Connection,
+    user_id: &str,
+    profile: &str,
+    req: &MergeSpecimensRequest,
+) -> Result<SpecimenMerge, AppError> {
+    if req.survivor_id == req.retired_id {
+        return Err(AppError::validation("retired_id", "Choose two different specimens"));
+    }
+    let survivor = side(conn, "survivor_id", &req.survivor_id)?;
+    let retired = side(conn, "retired_id", &req.retired_id)?;
+    for (field, s) in [("survivor_id", &survivor), ("retired_id", &retired)] {
+        if s.lab_profile != profile {
+            return Err(AppError::validation(field, format!("{} belongs to another lab", s.accession)));
+        }
+        if let Some(into) = &s.merged_into {
+            let into = side(conn, field, into).map(|s| s.accession).unwrap_or_else(|_| into.clone());
+            return Err(AppError::conflict(format!("{} was already merged into {}", s.accession, into)));
+        }
+    }
+    if survivor.species_id != retired.species_id {
+        return Err(AppError::validation(
+            "retired_id",
+            format!("{} and {} are different species, so they are not duplicates", survivor.accession, retired.accession),
+        ));
+    }
+    if descends_from(conn, &req.survivor_id, &req.retired_id)? || descends_from(conn, &req.retired_id, &req.survivor_id)? {
+        return Err(AppError::validation(
+            "retired_id",
+            format!("{} and {} are parent and descendant, not duplicates", survivor.accession, retired.accession),
+        ));
+    }
+    let reason = req.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+
+    let (to, from) = (req.survivor_id.as_str(), req.retired_id.as_str());
+    let move_rows = |table: &str| conn.execute(&format!("UPDATE {} SET specimen_id = ?1 WHERE specimen_id = ?2", table), params![to, from]);
+    let mut moved = MergeMoved {
+        subcultures: move_rows("subcultures")?,
+        reminders: move_rows("reminders")?,
+        environmental_readings: move_rows("environmental_readings")?,
+        frozen_vials: move_rows("frozen_vials")?,
+        fruiting_records: move_rows("fruiting_records")?,
+        compliance_records: move_rows("compliance_records")?,
+        compliance_waivers: move_rows("compliance_flag_waivers")?,
+        attachments: conn.execute(
+            "UPDATE attachments SET entity_id = ?1 WHERE entity_type = 'specimen' AND entity_id = ?2",
+            params![to, from],
+        )?,
+        ..MergeMoved::default()
+    };
+    moved.tags = conn.execute(
+        "INSERT OR IGNORE INTO specimen_tags (specimen_id, tag_id) SELECT ?1, tag_id FROM specimen_tags WHERE specimen_id = ?2",
+        params![to, from],
+    )?;
+    conn.execute("DELETE FROM specimen_tags WHERE specimen_id = ?1", params![from])?;
+
+    // Children join the survivor's lineage, and every descendant takes the
+    // survivor's root. Descendants are found before the children move.
+    let survivor_root = survivor.root_specimen_id.clone().unwrap_or_else(|| to.to_string());
+    conn.execute(
+        "WITH RECURSIVE d(id) AS (
+             SELECT id FROM specimens WHERE parent_specimen_id = ?1
+             UNION SELECT s.id FROM specimens s JOIN d ON s.parent_specimen_id = d.id
+         )
+         UPDATE specimens SET root_specimen_id = ?2 WHERE id IN (SELECT id FROM d)",
+        params![from, survivor_root],
+    )?;
+    moved.children = conn.execute(
+        "UPDATE specimens SET parent_specimen_id = ?1, updated_at = datetime('now') WHERE parent_specimen_id = ?2",
+        params![to, from],
+    )?;
+
+    if moved.subcultures > 0 {
+        conn.execute(
+            "UPDATE subcultures SET passage_number = (
+                 SELECT COUNT(*) FROM subcultures o
+                 WHERE o.specimen_id = subcultures.specimen_id
+                   AND (o.date, o.created_at, o.id) <= (subcultures.date, subcultures.created_at, subcultures.id)
+             )
+             WHERE specimen_id = ?1",
+            params![to],
+        )?;
+        conn.execute(
+            "UPDATE specimens SET subculture_count = (SELECT COUNT(*) FROM subcultures WHERE specimen_id = ?1) WHERE id = ?1",
+            params![to],
+        )?;
+    }
+    if retired.contamination_flag && !survivor.contamination_flag {
+        conn.execute(
+            "UPDATE specimens SET contamination_flag = 1, contamination_notes = COALESCE(contamination_notes, ?2) WHERE id = ?1",
+            params![to, retired.contamination_notes],
+        )?;
+    }
+    conn.execute("UPDATE specimens SET updated_at = datetime('now') WHERE id = ?1", params![to])?;
+
+    // The tombstone, and any earlier tombstones that pointed at it.
+    conn.execute(
+        "UPDATE specimens SET is_archived = 1, archived_at = COALESCE(archived_at, datetime('now')),
+                merged_into = ?1, updated_at = datetime('now')
+         WHERE id = ?2",
+        params![to, from],
+    )?;
+    conn.execute("UPDATE specimens SET merged_into = ?1 WHERE merged_into = ?2", params![to, from])?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO specimen_merges (id, survivor_id, retired_id, reason, moved, merged_by) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id, to, from, reason, serde_json::to_string(&moved)?, user_id],
+    )?;
+    conn.query_row(&format!("{} WHERE m.id = ?1", SELECT), params![id], row_to_merge)
+        .map_err(AppError::from)
+}
+
+/// A one-line account of what moved, for audit details.
+pub fn describe(moved: &MergeMoved) -> String {
+    let parts: Vec<String> = [
+        (moved.subcultures, "passages"),
+        (moved.attachments, "attachments"),
+        (moved.reminders, "reminders"),
+        (moved.environmental_readings, "readings"),
+        (moved.frozen_vials, "vial lots"),
+        (moved.fruiting_records, "fruiting records"),
+        (moved.compliance_records, "compliance records"),
+        (moved.compliance_waivers, "compliance waivers"),
+        (moved.tags, "tags"),
+        (moved.children, "children"),
+    ]
+    .iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, what)| format!("{} {}", n, what))
+    .collect();
+    if parts.is_empty() {
+        "nothing to move".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::{run_all, seed_defaults};
+
+    const PTC: &str = "plant_tissue_culture";
+
+    fn merge_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        seed_defaults(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'ann', 'x', 'Ann', 'admin');
+             INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT'), ('sp2', 'Malus', 'domestica', 'MAL');
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, subculture_count) VALUES
+                 ('root', 'CIT-001', 'sp', 'shoot', '2026-01-01', 0),
+                 ('keep', 'CIT-002', 'sp', 'shoot', '2026-02-01', 2),
+                 ('dup', 'CIT-002-X', 'sp', 'shoot', '2026-02-01', 1),
+                 ('apple', 'MAL-001', 'sp2', 'shoot', '2026-02-01', 0);
+             UPDATE specimens SET parent_specimen_id = 'root', root_specimen_id = 'root' WHERE id = 'keep';
+             UPDATE specimens SET contamination_flag = 1, contamination_notes = 'Bacterial halo' WHERE id = 'dup';
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, parent_specimen_id, root_specimen_id) VALUES
+                 ('child', 'CIT-002-X-A', 'sp', 'shoot', '2026-03-01', 'dup', 'dup'),
+                 ('grandchild', 'CIT-002-X-A-A', 'sp', 'shoot', '2026-04-01', 'child', 'dup');
+             INSERT INTO subcultures (id, specimen_id, passage_number, date, created_at) VALUES
+                 ('p1', 'keep', 1, '2026-02-10', '2026-02-10 09:00:00'),
+                 ('p2', 'keep', 2, '2026-03-10', '2026-03-10 09:00:00'),
+                 ('q1', 'dup', 1, '2026-02-20', '2026-02-20 09:00:00');
+             INSERT INTO reminders (id, specimen_id, title, reminder_type, due_date) VALUES ('r1', 'dup', 'Check', 'custom', '2026-05-01');
+             INSERT INTO attachments (id, entity_type, entity_id, file_name, file_path) VALUES ('a1', 'specimen', 'dup', 'x.jpg', '/x.jpg');
+             INSERT INTO environmental_readings (id, specimen_id, reading_type, value) VALUES ('e1', 'dup', 'ph', 5.7);
+             INSERT INTO frozen_vials (id, specimen_id, species_id, freeze_date, freeze_medium) VALUES ('v1', 'dup', 'sp', '2026-03-01', 'DMSO');
+             INSERT INTO tags (id, name, category) VALUES ('t1', 'elite', 'general'), ('t2', 'virus-free', 'general');
+             INSERT INTO specimen_tags (specimen_id, tag_id) VALUES ('keep', 't1'), ('dup', 't1'), ('dup', 't2');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn req(survivor: &str, retired: &str) -> MergeSpecimensRequest {
+        MergeSpecimensRequest { survivor_id: survivor.into(), retired_id: retired.into(), reason: Some(" Imported twice ".into()) }
+    }
+
+    fn col(conn: &Connection, sql: &str) -> Option<String> {
+        conn.query_row(sql, [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn merge_moves_the_history_and_leaves_a_redirecting_tombstone() {
+        let conn = merge_db();
+        let merge = merge(&conn, "u1", PTC, &req("keep", "dup")).unwrap();
+        assert_eq!((merge.survivor_accession.as_str(), merge.retired_accession.as_str()), ("CIT-002", "CIT-002-X"));
+        assert_eq!(merge.reason.as_deref(), Some("Imported twice"));
+        assert_eq!(
+            merge.moved,
+            MergeMoved {
+                subcultures: 1, attachments: 1, reminders: 1, environmental_readings: 1, frozen_vials: 1,
+                tags: 1, children: 1, ..MergeMoved::default()
+            }
+        );
+        assert_eq!(describe(&merge.moved), "1 passages, 1 attachments, 1 reminders, 1 readings, 1 vial lots, 1 tags, 1 children");
+
+        // Passages interleave by date and are renumbered.
+        let order: Vec<(String, i64)> = conn
+            .prepare("SELECT id, passage_number FROM subcultures WHERE specimen_id = 'keep' ORDER BY passage_number")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(order, [("p1".to_string(), 1), ("q1".to_string(), 2), ("p2".to_string(), 3)]);
+        assert_eq!(col(&conn, "SELECT CAST(subculture_count AS TEXT) FROM specimens WHERE id = 'keep'").as_deref(), Some("3"));
+        assert_eq!(col(&conn, "SELECT contamination_notes FROM specimens WHERE id = 'keep'").as_deref(), Some("Bacterial halo"));
+
+        // Children join the survivor's lineage, down to the grandchildren.
+        assert_eq!(col(&conn, "SELECT parent_specimen_id FROM specimens WHERE id = 'child'").as_deref(), Some("keep"));
+        assert_eq!(col(&conn, "SELECT root_specimen_id FROM specimens WHERE id = 'grandchild'").as_deref(), Some("root"));
+        let tags: i64 = conn.query_row("SELECT COUNT(*) FROM specimen_tags WHERE specimen_id = 'keep'", [], |r| r.get(0)).unwrap();
+        assert_eq!(tags, 2);
+
+        // The tombstone stays, archived, and lookups land on the survivor.
+        assert_eq!(col(&conn, "SELECT merged_into FROM specimens WHERE id = 'dup' AND is_archived = 1").as_deref(), Some("keep"));
+        assert_eq!(resolve(&conn, "dup").unwrap(), "keep");
+        assert_eq!(resolve(&conn, "keep").unwrap(), "keep");
+        assert_eq!(resolve(&conn, "nope").unwrap_err().code(), "not_found");
+        assert_eq!(list_for(&conn, "keep").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn a_tombstone_cannot_be_merged_again_and_redirects_follow_later_merges() {
+        let conn = merge_db();
+        merge(&conn, "u1", PTC, &req("keep", "dup")).unwrap();
+        let err = merge(&conn, "u1", PTC, &req("root", "dup")).unwrap_err();
+        assert_eq!(err.code(), "conflict");
+        assert!(err.message().contains("already merged into CIT-002"), "{}", err.message());
+        assert_eq!(merge(&conn, "u1", PTC, &req("dup", "root")).unwrap_err().code(), "conflict");
+
+        // The child is now a separate duplicate of the survivor; merging the
+        // survivor away re-points the old tombstone too.
+        conn.execute("UPDATE specimens SET parent_specimen_id = NULL WHERE id = 'child'", []).unwrap();
+        merge(&conn, "u1", PTC, &req("child", "keep")).unwrap();
+        assert_eq!(col(&conn, "SELECT merged_into FROM specimens WHERE id = 'dup'").as_deref(), Some("child"));
+        assert_eq!(resolve(&conn, "dup").unwrap(), "child");
+    }
+
+    #[test]
+    fn only_true_duplicates_merge() {
+        let conn = merge_db();
+        let field = |e: AppError| match e {
+            AppError::Validation { field, .. } => field,
+            other => panic!("expected a validation error, got {:?}", other),
+        };
+        assert_eq!(field(merge(&conn, "u1", PTC, &req("keep", "keep")).unwrap_err()), Some("retired_id"));
+        assert_eq!(field(merge(&conn, "u1", PTC, &req("keep", "apple")).unwrap_err()), Some("retired_id"));
+        assert_eq!(field(merge(&conn, "u1", PTC, &req("gone", "dup")).unwrap_err()), Some("survivor_id"));
+        assert_eq!(field(merge(&conn, "u1", PTC, &req("dup", "grandchild")).unwrap_err()), Some("retired_id"));
+        assert_eq!(field(merge(&conn, "u1", PTC, &req("root", "keep")).unwrap_err()), Some("retired_id"));
+        assert_eq!(field(merge(&conn, "u1", "mycology", &req("keep", "dup")).unwrap_err()), Some("survivor_id"));
+        let merges: i64 = conn.query_row("SELECT COUNT(*) FROM specimen_merges", [], |r| r.get(0)).unwrap();
+        assert_eq!(merges, 0);
+    }
+}
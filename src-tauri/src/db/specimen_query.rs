@@ -0,0 +1,913 @@
+// WP-97: the specimen filter language. A filter such as
+//
+//   genus = "Citrus" and generation >= 5
+//     and any(subcultures where contamination_flag and date >= today - 30d)
+//     and location contains "Room B" and last_passage_date <= today - 40d
+//
+// is parsed into SQL over `specimens s`. Field names resolve against a fixed
+// catalogue (plus the lab's custom fields), so no identifier from the text
+// ever reaches the SQL; every literal is a bound parameter. Search, the work
+// queue, bulk selection and exports all take the same text, so a saved
+// search means the same thing everywhere. See docs/specimen-queries.md.
+use crate::db::custom_fields::{self, CustomFieldEntity, CustomFieldType};
+use crate::db::permissions::FieldPermissionSet;
+use crate::error::AppError;
+use chrono::{Months, NaiveDate};
+use rusqlite::types::{ToSql, Value};
+use rusqlite::Connection;
+
+/// Longest filter accepted, in characters.
+pub const MAX_FILTER_LEN: usize = 2000;
+const MAX_DEPTH: usize = 32;
+const MAX_PREDICATES: usize = 100;
+const MAX_IN_VALUES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ty {
+    Text,
+    Number,
+    Date,
+    Bool,
+}
+
+impl Ty {
+    fn name(self) -> &'static str {
+        match self {
+            Ty::Text => "text",
+            Ty::Number => "a number",
+            Ty::Date => "a date",
+            Ty::Bool => "true/false",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Specimen,
+    Subculture,
+}
+
+/// A built-in field: its name in the language, its type, and the SQL it
+/// reads. Specimen SQL is over alias `s`, subculture SQL over `sc`. The SQL
+/// is fixed text: nothing from the filter is spliced into it.
+struct FieldDef {
+    name: &'static str,
+    ty: Ty,
+    sql: &'static str,
+    /// The `(entity, field)` a role may be denied (WP-87), if maskable.
+    masked_as: Option<(&'static str, &'static str)>,
+}
+
+const fn f(name: &'static str, ty: Ty, sql: &'static str) -> FieldDef {
+    FieldDef { name, ty, sql, masked_as: None }
+}
+
+const SPECIMEN_FIELDS: &[FieldDef] = &[
+    f("accession_number", Ty::Text, "s.accession_number"),
+    f("species", Ty::Text, "(SELECT genus || ' ' || species_name FROM species WHERE id = s.species_id)"),
+    f("genus", Ty::Text, "(SELECT genus FROM species WHERE id = s.species_id)"),
+    f("species_code", Ty::Text, "(SELECT species_code FROM species WHERE id = s.species_id)"),
+    f("project", Ty::Text, "(SELECT name FROM projects WHERE id = s.project_id)"),
+    f("stage", Ty::Text, "s.stage"),
+    f("location", Ty::Text, "s.location"),
+    f("propagation_method", Ty::Text, "s.propagation_method"),
+    f("acclimatization_status", Ty::Text, "s.acclimatization_status"),
+    f("health_status", Ty::Text, "s.health_status"),
+    f("disease_status", Ty::Text, "s.disease_status"),
+    f("origin_type", Ty::Text, "s.origin_type"),
+    f("employee_id", Ty::Text, "s.employee_id"),
+    f("notes", Ty::Text, "s.notes"),
+    FieldDef { name: "provenance", ty: Ty::Text, sql: "s.provenance", masked_as: Some(("specimen", "provenance")) },
+    FieldDef { name: "source_plant", ty: Ty::Text, sql: "s.source_plant", masked_as: Some(("specimen", "source_plant")) },
+    f("generation", Ty::Number, "s.generation"),
+    f("subculture_count", Ty::Number, "s.subculture_count"),
+    f("cumulative_pdl", Ty::Number, "s.cumulative_pdl"),
+    f("initiation_date", Ty::Date, "date(s.initiation_date)"),
+    f("created_at", Ty::Date, "date(s.created_at)"),
+    f("updated_at", Ty::Date, "date(s.updated_at)"),
+    f("archived_at", Ty::Date, "date(s.archived_at)"),
+    f("quarantine_release_date", Ty::Date, "date(s.quarantine_release_date)"),
+    f("permit_expiry", Ty::Date, "date(s.permit_expiry)"),
+    // The latest passage, or the initiation date for a specimen never
+    // passaged, so "not passaged for 40 days" includes new cultures.
+    f(
+        "last_passage_date",
+        Ty::Date,
+        "COALESCE((SELECT MAX(date(date)) FROM subcultures WHERE specimen_id = s.id), date(s.initiation_date))",
+    ),
+    f("quarantine", Ty::Bool, "s.quarantine_flag"),
+    f("contamination_flag", Ty::Bool, "s.contamination_flag"),
+    f("best_performer", Ty::Bool, "s.is_best_performer"),
+    f("ip_flag", Ty::Bool, "s.ip_flag"),
+    f("archived", Ty::Bool, "s.is_archived"),
+    f("strain.name", Ty::Text, "(SELECT name FROM strains WHERE id = s.strain_id)"),
+    f("strain.code", Ty::Text, "(SELECT code FROM strains WHERE id = s.strain_id)"),
+    f("strain.status", Ty::Text, "(SELECT status FROM strains WHERE id = s.strain_id)"),
+    f("strain.type", Ty::Text, "(SELECT strain_type FROM strains WHERE id = s.strain_id)"),
+    f("strain.hybrid", Ty::Bool, "(SELECT is_hybrid FROM strains WHERE id = s.strain_id)"),
+];
+
+const SUBCULTURE_FIELDS: &[FieldDef] = &[
+    f("date", Ty::Date, "date(sc.date)"),
+    f("passage_number", Ty::Number, "sc.passage_number"),
+    f("event_type", Ty::Text, "sc.event_type"),
+    f("vessel_type", Ty::Text, "sc.vessel_type"),
+    f("light_cycle", Ty::Text, "sc.light_cycle"),
+    f("location_to", Ty::Text, "sc.location_to"),
+    f("health_status", Ty::Text, "sc.health_status"),
+    f("employee_id", Ty::Text, "sc.employee_id"),
+    f("contaminant_type", Ty::Text, "sc.contaminant_type"),
+    f("experimental_treatment", Ty::Text, "sc.experimental_treatment"),
+    f("media_batch", Ty::Text, "(SELECT batch_id FROM media_batches WHERE id = sc.media_batch_id)"),
+    f("contamination_flag", Ty::Bool, "sc.contamination_flag"),
+    f("ph", Ty::Number, "sc.ph"),
+    f("temperature_c", Ty::Number, "sc.temperature_c"),
+    f("colonization_pct", Ty::Number, "sc.colonization_pct"),
+    f("seed_cell_count", Ty::Number, "sc.seed_cell_count"),
+    f("harvest_cell_count", Ty::Number, "sc.harvest_cell_count"),
+    f("split_ratio", Ty::Number, "sc.split_ratio"),
+    f("pdl_gained", Ty::Number, "sc.pdl_gained"),
+    f("doubling_time_hours", Ty::Number, "sc.doubling_time_hours"),
+];
+
+/// Field names of the language, for the editor's help and autocompletion.
+pub fn field_names() -> (Vec<&'static str>, Vec<&'static str>) {
+    (SPECIMEN_FIELDS.iter().map(|f| f.name).collect(), SUBCULTURE_FIELDS.iter().map(|f| f.name).collect())
+}
+
+// ── Lexer ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Word(String),
+    Str(String),
+    Num(f64),
+    /// A length of time, in whole units: `30d`, `2w`, `6m`, `1y`.
+    Span(u32, char),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+    Plus,
+    Minus,
+    End,
+}
+
+fn at(pos: usize, msg: impl std::fmt::Display) -> AppError {
+    AppError::validation("filter", format!("At character {}: {}", pos + 1, msg))
+}
+
+fn lex(text: &str) -> Result<Vec<(Tok, usize)>, AppError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let tok = if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            Tok::Word(chars[start..i].iter().collect::<String>().to_ascii_lowercase())
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            let unit = chars.get(i).copied().filter(|u| matches!(u, 'd' | 'w' | 'm' | 'y'));
+            let unit_ends = !chars.get(i + 1).is_some_and(|n| n.is_ascii_alphanumeric() || *n == '_');
+            match unit {
+                Some(u) if unit_ends => {
+                    i += 1;
+                    let n = digits.parse::<u32>().map_err(|_| at(start, "a length of time is a whole number, e.g. 30d"))?;
+                    Tok::Span(n, u)
+                }
+                _ => Tok::Num(digits.parse().map_err(|_| at(start, format!("'{}' is not a number", digits)))?),
+            }
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            let mut s = String::new();
+            loop {
+                match chars.get(i) {
+                    None => return Err(at(start, "text is missing its closing quote")),
+                    Some('\\') if i + 1 < chars.len() => {
+                        s.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(&q) if q == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&ch) => {
+                        s.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            Tok::Str(s)
+        } else {
+            let next = chars.get(i + 1).copied();
+            let (tok, len) = match (c, next) {
+                ('<', Some('=')) => (Tok::Op("<="), 2),
+                ('>', Some('=')) => (Tok::Op(">="), 2),
+                ('!', Some('=')) | ('<', Some('>')) => (Tok::Op("!="), 2),
+                ('=', Some('=')) => (Tok::Op("="), 2),
+                ('=', _) => (Tok::Op("="), 1),
+                ('<', _) => (Tok::Op("<"), 1),
+                ('>', _) => (Tok::Op(">"), 1),
+                ('≤', _) => (Tok::Op("<="), 1),
+                ('≥', _) => (Tok::Op(">="), 1),
+                ('≠', _) => (Tok::Op("!="), 1),
+                ('(', _) => (Tok::LParen, 1),
+                (')', _) => (Tok::RParen, 1),
+                (',', _) => (Tok::Comma, 1),
+                ('+', _) => (Tok::Plus, 1),
+                ('-', _) => (Tok::Minus, 1),
+                _ => return Err(at(start, format!("unexpected '{}'", c))),
+            };
+            i += len;
+            tok
+        };
+        out.push((tok, start));
+    }
+    out.push((Tok::End, chars.len()));
+    Ok(out)
+}
+
+// ── Parser / compiler ────────────────────────────────────────────────────────
+
+/// A typed SQL expression with its parameters, in order of appearance.
+struct Expr {
+    sql: String,
+    params: Vec<Value>,
+    ty: Ty,
+    label: String,
+}
+
+struct CustomDef {
+    key: String,
+    label: String,
+    ty: Ty,
+}
+
+struct Parser<'a> {
+    toks: Vec<(Tok, usize)>,
+    pos: usize,
+    today: NaiveDate,
+    perms: &'a FieldPermissionSet,
+    specimen_custom: Vec<CustomDef>,
+    subculture_custom: Vec<CustomDef>,
+    predicates: usize,
+}
+
+/// A compiled filter: one SQL condition over `specimens s` with bare `?`
+/// placeholders, and the values they bind.
+#[derive(Debug, Clone)]
+pub struct SpecimenFilter {
+    sql: String,
+    params: Vec<Value>,
+}
+
+/// Parses and type-checks `text` for the lab `profile`. Fields `role` may
+/// not see are refused, so a filter cannot probe a masked value. `today`
+/// anchors `today` in date arithmetic.
+pub fn compile(
+    conn: &Connection,
+    role: &str,
+    profile: &str,
+    text: &str,
+    today: NaiveDate,
+) -> Result<SpecimenFilter, AppError> {
+    if text.chars().count() > MAX_FILTER_LEN {
+        return Err(AppError::validation("filter", format!("A filter is at most {} characters", MAX_FILTER_LEN)));
+    }
+    let perms = FieldPermissionSet::load(conn, role)?;
+    let custom = |entity| -> Result<Vec<CustomDef>, AppError> {
+        Ok(custom_fields::list(conn, profile, Some(entity), true)?
+            .into_iter()
+            .map(|f| CustomDef {
+                key: f.key,
+                label: f.label,
+                ty: match f.field_type {
+                    CustomFieldType::Number => Ty::Number,
+                    CustomFieldType::Date => Ty::Date,
+                    CustomFieldType::Boolean => Ty::Bool,
+                    CustomFieldType::Text | CustomFieldType::Enum => Ty::Text,
+                },
+            })
+            .collect())
+    };
+    let mut parser = Parser {
+        toks: lex(text)?,
+        pos: 0,
+        today,
+        perms: &perms,
+        specimen_custom: custom(CustomFieldEntity::Specimen)?,
+        subculture_custom: custom(CustomFieldEntity::Subculture)?,
+        predicates: 0,
+    };
+    if parser.peek() == &Tok::End {
+        return Err(AppError::validation("filter", "The filter is empty"));
+    }
+    let cond = parser.or_expr(Scope::Specimen, 0)?;
+    if parser.peek() != &Tok::End {
+        return Err(parser.unexpected("'and', 'or' or the end of the filter"));
+    }
+    Ok(SpecimenFilter { sql: cond.sql, params: cond.params })
+}
+
+/// [`compile`] for the active lab profile and today's date.
+pub fn compile_for_active_lab(conn: &Connection, role: &str, text: &str) -> Result<SpecimenFilter, AppError> {
+    let profile = crate::db::vocabulary::active_profile(conn);
+    compile(conn, role, &profile, text, chrono::Local::now().date_naive())
+}
+
+impl SpecimenFilter {
+    /// Appends the condition to `conditions`, numbering its placeholders
+    /// after those already in `bind_values` (the `?N` style of
+    /// `db::specimens::search`).
+    pub fn push_condition(&self, conditions: &mut Vec<String>, bind_values: &mut Vec<Box<dyn ToSql>>) {
+        let mut sql = String::with_capacity(self.sql.len() + 16);
+        for c in self.sql.chars() {
+            if c == '?' {
+                bind_values.push(Box::new(Value::Null));
+                sql.push_str(&format!("?{}", bind_values.len()));
+            } else {
+                sql.push(c);
+            }
+        }
+        let first = bind_values.len() - self.params.len();
+        for (slot, value) in bind_values[first..].iter_mut().zip(&self.params) {
+            *slot = Box::new(value.clone());
+        }
+        conditions.push(format!("({})", sql));
+    }
+}
+
+/// IDs of the active lab's unarchived specimens matching `filter`, oldest
+/// accession first. Bulk actions and the work queue select with this.
+pub fn matching_ids(conn: &Connection, filter: &SpecimenFilter) -> Result<Vec<String>, AppError> {
+    let mut conditions = vec!["s.lab_profile = ?1".to_string(), "s.is_archived = 0".to_string()];
+    let mut binds: Vec<Box<dyn ToSql>> = vec![Box::new(crate::db::vocabulary::active_profile(conn))];
+    filter.push_condition(&mut conditions, &mut binds);
+    let sql = format!("SELECT s.id FROM specimens s WHERE {} ORDER BY s.accession_number", conditions.join(" AND "));
+    let refs: Vec<&dyn ToSql> = binds.iter().map(|b| b.as_ref()).collect();
+    let mut stmt = conn.prepare(&sql)?;
+    let ids = stmt.query_map(refs.as_slice(), |r| r.get(0))?.collect::<Result<Vec<String>, _>>()?;
+    Ok(ids)
+}
+
+const AND_OR_NOT: &[&str] = &["and", "or", "not"];
+
+impl Parser<'_> {
+    fn peek(&self) -> &Tok {
+        &self.toks[self.pos].0
+    }
+
+    fn here(&self) -> usize {
+        self.toks[self.pos].1
+    }
+
+    fn bump(&mut self) -> Tok {
+        let tok = self.toks[self.pos].0.clone();
+        if tok != Tok::End {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn is_word(&self, w: &str) -> bool {
+        matches!(self.peek(), Tok::Word(x) if x == w)
+    }
+
+    fn eat_word(&mut self, w: &str) -> bool {
+        let hit = self.is_word(w);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn expect(&mut self, tok: Tok, what: &str) -> Result<(), AppError> {
+        if self.peek() == &tok {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected(what))
+        }
+    }
+
+    fn unexpected(&self, wanted: &str) -> AppError {
+        let found = match self.peek() {
+            Tok::End => "the end of the filter".to_string(),
+            Tok::Word(w) => format!("'{}'", w),
+            Tok::Str(s) => format!("\"{}\"", s),
+            Tok::Num(n) => format!("{}", n),
+            Tok::Span(n, u) => format!("{}{}", n, u),
+            Tok::Op(o) => format!("'{}'", o),
+            Tok::LParen => "'('".to_string(),
+            Tok::RParen => "')'".to_string(),
+            Tok::Comma => "','".to_string(),
+            Tok::Plus => "'+'".to_string(),
+            Tok::Minus => "'-'".to_string(),
+        };
+        at(self.here(), format!("expected {}, found {}", wanted, found))
+    }
+
+    fn or_expr(&mut self, scope: Scope, depth: usize) -> Result<Expr, AppError> {
+        self.join(scope, depth, "or", Self::and_expr)
+    }
+
+    fn and_expr(&mut self, scope: Scope, depth: usize) -> Result<Expr, AppError> {
+        self.join(scope, depth, "and", Self::unary)
+    }
+
+    fn join(
+        &mut self,
+        scope: Scope,
+        depth: usize,
+        word: &str,
+        next: fn(&mut Self, Scope, usize) -> Result<Expr, AppError>,
+    ) -> Result<Expr, AppError> {
+        let mut expr = next(self, scope, depth)?;
+        while self.eat_word(word) {
+            let rhs = next(self, scope, depth)?;
+            expr.sql = format!("({} {} {})", expr.sql, word.to_uppercase(), rhs.sql);
+            expr.params.extend(rhs.params);
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self, scope: Scope, depth: usize) -> Result<Expr, AppError> {
+        if depth > MAX_DEPTH {
+            return Err(at(self.here(), format!("a filter nests at most {} levels deep", MAX_DEPTH)));
+        }
+        if self.eat_word("not") {
+            let inner = self.unary(scope, depth + 1)?;
+            return Ok(Expr { sql: format!("(NOT {})", inner.sql), ..inner });
+        }
+        if self.peek() == &Tok::LParen {
+            self.pos += 1;
+            let inner = self.or_expr(scope, depth + 1)?;
+            self.expect(Tok::RParen, "')'")?;
+            return Ok(inner);
+        }
+        self.predicates += 1;
+        if self.predicates > MAX_PREDICATES {
+            return Err(at(self.here(), format!("a filter has at most {} conditions", MAX_PREDICATES)));
+        }
+        self.predicate(scope, depth)
+    }
+
+    /// One condition. Every condition is made two-valued with COALESCE, so a
+    /// missing value never makes `not (…)` drop a record.
+    fn predicate(&mut self, scope: Scope, depth: usize) -> Result<Expr, AppError> {
+        if scope == Scope::Specimen && self.is_word("any") {
+            self.pos += 1;
+            self.expect(Tok::LParen, "'(' after any")?;
+            let (cond, params) = self.subculture_source(depth)?;
+            self.expect(Tok::RParen, "')'")?;
+            return Ok(Expr {
+                sql: format!("EXISTS (SELECT 1 FROM subcultures sc WHERE sc.specimen_id = s.id{})", cond),
+                params,
+                ty: Ty::Bool,
+                label: "any(…)".to_string(),
+            });
+        }
+
+        let lhs = self.operand(scope, depth)?;
+        let negated = self.is_word("not") && {
+            let next = self.toks.get(self.pos + 1).map(|t| &t.0);
+            matches!(next, Some(Tok::Word(w)) if w == "in" || w == "contains" || w == "starts_with")
+        };
+        if negated {
+            self.pos += 1;
+        }
+        let wrap = |sql: String, params: Vec<Value>| {
+            let sql = format!("COALESCE({}, 0)", sql);
+            Ok(Expr { sql: if negated { format!("(NOT {})", sql) } else { sql }, params, ty: Ty::Bool, label: String::new() })
+        };
+
+        match self.peek().clone() {
+            Tok::Op(op) => {
+                self.pos += 1;
+                let rhs = self.value(lhs.ty, &lhs.label)?;
+                if lhs.ty == Ty::Bool && !matches!(op, "=" | "!=") {
+                    return Err(at(self.here(), format!("{} is true/false; compare it with = or !=", lhs.label)));
+                }
+                let (lhs_sql, rhs_sql) = if lhs.ty == Ty::Text {
+                    (format!("LOWER({})", lhs.sql), "LOWER(?)")
+                } else if lhs.ty == Ty::Bool {
+                    (format!("COALESCE({}, 0)", lhs.sql), "?")
+                } else {
+                    (lhs.sql, "?")
+                };
+                let mut params = lhs.params;
+                params.push(rhs);
+                wrap(format!("{} {} {}", lhs_sql, op, rhs_sql), params)
+            }
+            Tok::Word(w) if w == "in" => {
+                self.pos += 1;
+                if lhs.ty == Ty::Bool {
+                    return Err(at(self.here(), format!("{} is true/false; compare it with =", lhs.label)));
+                }
+                self.expect(Tok::LParen, "'(' after in")?;
+                let mut params = lhs.params;
+                let mut marks = Vec::new();
+                loop {
+                    params.push(self.value(lhs.ty, &lhs.label)?);
+                    marks.push(if lhs.ty == Ty::Text { "LOWER(?)" } else { "?" });
+                    if marks.len() > MAX_IN_VALUES {
+                        return Err(at(self.here(), format!("a list has at most {} values", MAX_IN_VALUES)));
+                    }
+                    if self.peek() == &Tok::Comma {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                self.expect(Tok::RParen, "',' or ')'")?;
+                let lhs_sql = if lhs.ty == Ty::Text { format!("LOWER({})", lhs.sql) } else { lhs.sql };
+                wrap(format!("{} IN ({})", lhs_sql, marks.join(", ")), params)
+            }
+            Tok::Word(w) if w == "contains" || w == "starts_with" => {
+                self.pos += 1;
+                if lhs.ty != Ty::Text {
+                    return Err(at(self.here(), format!("{} only applies to text; {} is {}", w, lhs.label, lhs.ty.name())));
+                }
+                let Tok::Str(needle) = self.bump() else {
+                    self.pos -= 1;
+                    return Err(self.unexpected("quoted text"));
+                };
+                let mut params = lhs.params;
+                params.push(Value::Text(needle.to_lowercase()));
+                let sql = if w == "contains" {
+                    format!("instr(LOWER({}), ?) > 0", lhs.sql)
+                } else {
+                    format!("instr(LOWER({}), ?) = 1", lhs.sql)
+                };
+                wrap(sql, params)
+            }
+            Tok::Word(w) if w == "is" => {
+                self.pos += 1;
+                let not = self.eat_word("not");
+                if !self.eat_word("null") {
+                    return Err(self.unexpected("'null'"));
+                }
+                let sql = format!("({} IS {}NULL)", lhs.sql, if not { "NOT " } else { "" });
+                Ok(Expr { sql, params: lhs.params, ty: Ty::Bool, label: String::new() })
+            }
+            _ if lhs.ty == Ty::Bool => wrap(format!("{} = 1", lhs.sql), lhs.params),
+            _ => Err(self.unexpected(&format!("a comparison after {}", lhs.label))),
+        }
+    }
+
+    /// `subcultures [where <condition>]`, the inside of `any(…)` and `count(…)`.
+    /// Returns the extra ` AND (…)` for the subquery.
+    fn subculture_source(&mut self, depth: usize) -> Result<(String, Vec<Value>), AppError> {
+        if !self.eat_word("subcultures") {
+            return Err(self.unexpected("'subcultures'"));
+        }
+        self.subculture_where(depth)
+    }
+
+    fn subculture_where(&mut self, depth: usize) -> Result<(String, Vec<Value>), AppError> {
+        if self.eat_word("where") {
+            let cond = self.or_expr(Scope::Subculture, depth + 1)?;
+            Ok((format!(" AND {}", cond.sql), cond.params))
+        } else {
+            Ok((String::new(), Vec::new()))
+        }
+    }
+
+    fn operand(&mut self, scope: Scope, depth: usize) -> Result<Expr, AppError> {
+        let start = self.here();
+        let Tok::Word(name) = self.peek().clone() else {
+            return Err(self.unexpected("a field name"));
+        };
+        if AND_OR_NOT.contains(&name.as_str()) {
+            return Err(self.unexpected("a field name"));
+        }
+        self.pos += 1;
+
+        let aggregate = matches!(name.as_str(), "count" | "min" | "max" | "avg" | "sum") && self.peek() == &Tok::LParen;
+        if aggregate {
+            if scope == Scope::Subculture {
+                return Err(at(start, "aggregates cannot be nested inside subcultures where …"));
+            }
+            self.pos += 1;
+            let expr = if name == "count" {
+                let (cond, params) = self.subculture_source(depth)?;
+                Expr {
+                    sql: format!("(SELECT COUNT(*) FROM subcultures sc WHERE sc.specimen_id = s.id{})", cond),
+                    params,
+                    ty: Ty::Number,
+                    label: "count(…)".to_string(),
+                }
+            } else {
+                let field_at = self.here();
+                let Tok::Word(path) = self.bump() else {
+                    self.pos -= 1;
+                    return Err(self.unexpected("subcultures.<field>"));
+                };
+                let Some(field) = path.strip_prefix("subcultures.") else {
+                    return Err(at(field_at, format!("{}(…) takes a passage field, e.g. {}(subcultures.ph)", name, name)));
+                };
+                let inner = self.field(Scope::Subculture, field, field_at)?;
+                let allowed = match name.as_str() {
+                    "min" | "max" => matches!(inner.ty, Ty::Number | Ty::Date),
+                    _ => inner.ty == Ty::Number,
+                };
+                if !allowed {
+                    return Err(at(field_at, format!("{}(…) does not apply to {}, which is {}", name, inner.label, inner.ty.name())));
+                }
+                let (cond, params) = self.subculture_where(depth)?;
+                let mut all = inner.params;
+                all.extend(params);
+                Expr {
+                    sql: format!(
+                        "(SELECT {}({}) FROM subcultures sc WHERE sc.specimen_id = s.id{})",
+                        name.to_uppercase(),
+                        inner.sql,
+                        cond
+                    ),
+                    params: all,
+                    ty: inner.ty,
+                    label: format!("{}(subcultures.{})", name, field),
+                }
+            };
+            self.expect(Tok::RParen, "')'")?;
+            return Ok(expr);
+        }
+        self.field(scope, &name, start)
+    }
+
+    fn field(&self, scope: Scope, name: &str, start: usize) -> Result<Expr, AppError> {
+        let (fields, custom, entity) = match scope {
+            Scope::Specimen => (SPECIMEN_FIELDS, &self.specimen_custom, "specimen"),
+            Scope::Subculture => (SUBCULTURE_FIELDS, &self.subculture_custom, "passage"),
+        };
+        if let Some(key) = name.strip_prefix("custom.") {
+            let def = custom
+                .iter()
+                .find(|d| d.key == key)
+                .ok_or_else(|| at(start, format!("'{}' is not a custom {} field in this lab", key, entity)))?;
+            let alias = if scope == Scope::Specimen { "s" } else { "sc" };
+            return Ok(Expr {
+                sql: format!("json_extract({}.custom_fields, ?)", alias),
+                params: vec![Value::Text(format!("$.{}", def.key))],
+                ty: def.ty,
+                label: def.label.clone(),
+            });
+        }
+        let def = fields.iter().find(|d| d.name == name).ok_or_else(|| {
+            let hint = if scope == Scope::Specimen && name.starts_with("subcultures.") {
+                "; passage fields are used inside any(subcultures where …) or an aggregate"
+            } else {
+                ""
+            };
+            at(start, format!("'{}' is not a {} field{}", name, entity, hint))
+        })?;
+        if let Some((e, field)) = def.masked_as {
+            if !self.perms.is_visible(e, field) {
+                return Err(at(start, format!("your role cannot see {}, so it cannot filter on it", def.name)));
+            }
+        }
+        Ok(Expr { sql: def.sql.to_string(), params: Vec::new(), ty: def.ty, label: def.name.to_string() })
+    }
+
+    /// A literal of type `ty`, bound as a parameter. Dates accept `today`,
+    /// `'YYYY-MM-DD'` and either one plus or minus a length of time.
+    fn value(&mut self, ty: Ty, label: &str) -> Result<Value, AppError> {
+        let start = self.here();
+        let wrong = |found: &str| at(start, format!("{} is {}, not {}", label, ty.name(), found));
+        match (ty, self.bump()) {
+            (Ty::Text, Tok::Str(s)) => Ok(Value::Text(s)),
+            (Ty::Text, Tok::Word(w)) if !matches!(w.as_str(), "true" | "false" | "today") => Err(at(
+                start,
+                format!("put text in quotes: \"{}\"", w),
+            )),
+            (Ty::Number, Tok::Num(n)) => Ok(Value::Real(n)),
+            (Ty::Number, Tok::Minus) => match self.bump() {
+                Tok::Num(n) => Ok(Value::Real(-n)),
+                _ => Err(wrong("that")),
+            },
+            (Ty::Bool, Tok::Word(w)) if w == "true" || w == "false" => Ok(Value::Integer((w == "true") as i64)),
+            (Ty::Date, Tok::Word(w)) if w == "today" => self.date_offset(self.today).map(date_value),
+            (Ty::Date, Tok::Str(s)) => {
+                let d = NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+                    .map_err(|_| at(start, format!("'{}' is not a date; write it as \"YYYY-MM-DD\"", s)))?;
+                self.date_offset(d).map(date_value)
+            }
+            (Ty::Date, Tok::Num(_)) => Err(at(start, "write a date in quotes, \"YYYY-MM-DD\", or use today")),
+            (_, Tok::Str(_)) => Err(wrong("text")),
+            (_, Tok::Num(_)) => Err(wrong("a number")),
+            (_, Tok::Word(w)) if w == "true" || w == "false" => Err(wrong("true/false")),
+            (_, Tok::Word(w)) if w == "today" => Err(wrong("a date")),
+            _ => {
+                self.pos -= 1;
+                Err(self.unexpected(&format!("a value for {}", label)))
+            }
+        }
+    }
+
+    /// `<date> [+|- <span>]`.
+    fn date_offset(&mut self, base: NaiveDate) -> Result<NaiveDate, AppError> {
+        let sign = match self.peek() {
+            Tok::Plus => 1,
+            Tok::Minus => -1,
+            _ => return Ok(base),
+        };
+        self.pos += 1;
+        let start = self.here();
+        let Tok::Span(n, unit) = self.bump() else {
+            self.pos -= 1;
+            return Err(self.unexpected("a length of time such as 30d, 2w, 6m or 1y"));
+        };
+        let shifted = match unit {
+            'd' | 'w' => {
+                let days = chrono::Duration::days(n as i64 * if unit == 'w' { 7 } else { 1 });
+                if sign > 0 { base.checked_add_signed(days) } else { base.checked_sub_signed(days) }
+            }
+            _ => {
+                let months = Months::new(if unit == 'y' { n.saturating_mul(12) } else { n });
+                if sign > 0 { base.checked_add_months(months) } else { base.checked_sub_months(months) }
+            }
+        };
+        shifted.ok_or_else(|| at(start, "that date is out of range"))
+    }
+}
+
+fn date_value(d: NaiveDate) -> Value {
+    Value::Text(d.format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::{run_all, seed_defaults};
+    use rusqlite::params;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 6, 30).unwrap()
+    }
+
+    /// Two species, four specimens in the plant profile and one in
+    /// mycology, with passages dated relative to `today()`.
+    fn query_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        seed_defaults(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES
+                 ('cit', 'Citrus', 'sinensis', 'CIT'), ('vit', 'Vitis', 'vinifera', 'VIT');
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, location, generation, lab_profile) VALUES
+                 ('a', 'A-1', 'cit', 'explant', '2026-01-10', 'Room B / Rack 2', 6, 'plant_tissue_culture'),
+                 ('b', 'B-1', 'cit', 'multiplication', '2026-03-01', 'Room A', 5, 'plant_tissue_culture'),
+                 ('c', 'C-1', 'vit', 'explant', '2026-06-20', 'Room B', 1, 'plant_tissue_culture'),
+                 ('d', 'D-1', 'cit', 'explant', '2026-01-10', NULL, 8, 'plant_tissue_culture'),
+                 ('m', 'M-1', 'cit', 'explant', '2026-01-10', 'Room B', 9, 'mycology');
+             INSERT INTO subcultures (id, specimen_id, passage_number, date, ph, contamination_flag) VALUES
+                 ('a1', 'a', 1, '2026-04-01', 5.6, 0),
+                 ('a2', 'a', 2, '2026-05-10', 5.8, 1),
+                 ('b1', 'b', 1, '2026-06-25', 5.7, 1),
+                 ('d1', 'd', 1, '2026-05-01', 6.1, 1),
+                 ('d2', 'd', 2, '2026-05-02', 5.9, 0);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn ids(conn: &Connection, text: &str) -> Vec<String> {
+        let filter = compile(conn, "admin", "plant_tissue_culture", text, today()).unwrap_or_else(|e| panic!("{}: {}", text, e));
+        matching_ids(conn, &filter).unwrap()
+    }
+
+    fn error(conn: &Connection, text: &str) -> String {
+        compile(conn, "admin", "plant_tissue_culture", text, today()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn the_worked_example_selects_the_stale_contaminated_citrus() {
+        let conn = query_db();
+        // a is citrus, generation 6, contaminated on 2026-05-10 (within 60
+        // days, not 30) and last passaged 51 days ago.
+        let text = "genus = \"citrus\" and generation ≥ 5 \
+                    and any(subcultures where contamination_flag and date >= today - 60d) \
+                    and location contains 'room b' and last_passage_date <= today - 40d";
+        assert_eq!(ids(&conn, text), ["a"]);
+        assert_eq!(ids(&conn, "any(subcultures where contamination_flag and date >= today - 30d)"), ["b"]);
+    }
+
+    #[test]
+    fn boolean_logic_lists_and_text_operators() {
+        let conn = query_db();
+        assert_eq!(ids(&conn, "stage = 'explant' and not (genus = 'Vitis' or generation > 7)"), ["a"]);
+        assert_eq!(ids(&conn, "stage in ('MULTIPLICATION', 'rooting') or accession_number starts_with 'c'"), ["b", "c"]);
+        assert_eq!(ids(&conn, "species_code not in ('CIT')"), ["c"]);
+        assert_eq!(ids(&conn, "location is null"), ["d"]);
+        // Two-valued: a missing location is simply "does not contain".
+        assert_eq!(ids(&conn, "not (location contains 'room')"), ["d"]);
+        assert_eq!(ids(&conn, "location not contains 'room b'"), ["b", "d"]);
+    }
+
+    #[test]
+    fn dates_and_aggregates() {
+        let conn = query_db();
+        assert_eq!(ids(&conn, "initiation_date = '2026-01-10'"), ["a", "d"]);
+        assert_eq!(ids(&conn, "initiation_date > '2026-06-30' - 2w"), ["c"]);
+        assert_eq!(ids(&conn, "initiation_date < today - 5m"), ["a", "d"]);
+        // Never passaged: last_passage_date falls back to initiation.
+        assert_eq!(ids(&conn, "last_passage_date >= today - 10d"), ["b", "c"]);
+        assert_eq!(ids(&conn, "count(subcultures) = 0"), ["c"]);
+        assert_eq!(ids(&conn, "count(subcultures where contamination_flag) >= 1 and max(subcultures.date) < '2026-06-01'"), ["a", "d"]);
+        assert_eq!(ids(&conn, "avg(subcultures.ph) > 5.75"), ["d"]);
+        assert_eq!(ids(&conn, "min(subcultures.ph where passage_number = 2) < 5.85"), ["a"]);
+    }
+
+    #[test]
+    fn custom_and_strain_fields_resolve() {
+        let conn = query_db();
+        let req = serde_json::from_value(serde_json::json!({
+            "entity": "specimen", "key": "ploidy", "label": "Ploidy", "type": "number"
+        }))
+        .unwrap();
+        custom_fields::save(&conn, "u1", "plant_tissue_culture", &req).unwrap();
+        conn.execute("UPDATE specimens SET custom_fields = '{\"ploidy\":4.0}' WHERE id = 'b'", []).unwrap();
+        conn.execute(
+            "INSERT INTO strains (id, species_id, name, code) VALUES ('st', 'cit', 'Valencia', 'VAL')",
+            [],
+        )
+        .unwrap();
+        conn.execute("UPDATE specimens SET strain_id = 'st' WHERE id = 'd'", []).unwrap();
+        assert_eq!(ids(&conn, "custom.ploidy >= 3"), ["b"]);
+        assert_eq!(ids(&conn, "strain.code = 'val'"), ["d"]);
+        assert!(error(&conn, "custom.colour = 'red'").contains("not a custom specimen field"));
+    }
+
+    #[test]
+    fn only_the_active_lab_and_unarchived_specimens_match() {
+        let conn = query_db();
+        assert_eq!(ids(&conn, "generation >= 9"), Vec::<String>::new());
+        conn.execute("UPDATE specimens SET is_archived = 1 WHERE id = 'a'", []).unwrap();
+        assert_eq!(ids(&conn, "generation >= 5"), ["b", "d"]);
+    }
+
+    #[test]
+    fn literals_are_bound_never_spliced() {
+        let conn = query_db();
+        assert_eq!(ids(&conn, "location = \"x') OR 1=1 --\""), Vec::<String>::new());
+        let filter = compile(&conn, "admin", "plant_tissue_culture", "notes contains \"'; DROP TABLE specimens; --\"", today()).unwrap();
+        assert!(!filter.sql.contains("DROP"));
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let conn = query_db();
+        assert!(error(&conn, "colour = 'red'").contains("At character 1: 'colour' is not a specimen field"));
+        assert!(error(&conn, "generation >= 'five'").contains("generation is a number, not text"));
+        assert!(error(&conn, "stage = explant").contains("put text in quotes"));
+        assert!(error(&conn, "initiation_date > today - 30").contains("a length of time"));
+        assert!(error(&conn, "stage = 'explant' and").contains("expected a field name, found the end"));
+        assert!(error(&conn, "(stage = 'explant'").contains("expected ')'"));
+        assert!(error(&conn, "subcultures.ph > 5").contains("inside any(subcultures where"));
+        assert!(error(&conn, "any(subcultures where count(subcultures) > 1)").contains("cannot be nested"));
+        assert!(error(&conn, "sum(subcultures.date) > 1").contains("does not apply"));
+        assert!(error(&conn, "quarantine > true").contains("compare it with = or !="));
+        assert!(error(&conn, "location = 'x").contains("closing quote"));
+        assert!(error(&conn, "").contains("empty"));
+        let deep = format!("{}stage = 'x'{}", "(".repeat(40), ")".repeat(40));
+        assert!(error(&conn, &deep).contains("nests at most"));
+    }
+
+    #[test]
+    fn masked_fields_cannot_be_filtered() {
+        let conn = query_db();
+        conn.execute(
+            "INSERT OR REPLACE INTO field_permissions (id, role, entity_type, field_name, visible) \
+             VALUES ('fp', 'guest', 'specimen', 'provenance', 0)",
+            params![],
+        )
+        .unwrap();
+        let err = compile(&conn, "guest", "plant_tissue_culture", "provenance contains 'wild'", today()).unwrap_err();
+        assert!(err.to_string().contains("cannot see provenance"));
+        assert!(compile(&conn, "admin", "plant_tissue_culture", "provenance contains 'wild'", today()).is_ok());
+    }
+
+    #[test]
+    fn placeholders_follow_existing_binds() {
+        let conn = query_db();
+        let filter = compile(&conn, "admin", "plant_tissue_culture", "stage = 'explant' and custom.x is null", today());
+        assert!(filter.is_err());
+        let filter = compile(&conn, "admin", "plant_tissue_culture", "stage in ('a', 'b') and generation > 1", today()).unwrap();
+        let mut conditions = vec!["s.lab_profile = ?1".to_string()];
+        let mut binds: Vec<Box<dyn ToSql>> = vec![Box::new("p")];
+        filter.push_condition(&mut conditions, &mut binds);
+        assert_eq!(binds.len(), 4);
+        assert!(conditions[1].contains("?2") && conditions[1].contains("?4") && !conditions[1].contains("?5"));
+    }
+}
@@ -0,0 +1,552 @@
+// WP-100: undoing archives from the audit trail. `delete_specimen` and
+// `bulk_archive_specimens` archive rather than delete, and each archive audit
+// entry now carries a snapshot of the specimen row, its passages and its
+// children in `old_value`. The entry's hashed `details` end with the
+// snapshot's SHA-256, so the lineage hash chain vouches for the snapshot even
+// though `old_value` itself is not hashed.
+//
+// A restore un-archives a specimen whose last lifecycle entry is an archive.
+// Where rows have gone missing since (a specimen deleted outside the app, or
+// some of its passages), they are re-inserted from the snapshot, but only when
+// the lineage verifies and the digest matches. Deaths, splits and merges are
+// not undone: each is its own recorded fact, not an archive.
+use crate::error::AppError;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+const SNAPSHOT_VERSION: u32 = 1;
+/// Marks the snapshot digest inside an archive entry's `details`.
+const DIGEST_MARKER: &str = "snapshot sha256:";
+/// Most rows `list_restorable` returns, newest archive first.
+const LIST_LIMIT: i64 = 500;
+/// The audit actions that end or resume a specimen's active life. The newest
+/// one decides whether an archived specimen can be restored.
+const LIFECYCLE_ACTIONS: &str = "'archive', 'death', 'split', 'merge', 'restore'";
+
+/// A specimen's state just before it was archived, as stored in the archive
+/// entry's `old_value`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveSnapshot {
+    pub version: u32,
+    /// Shared by every specimen of one bulk archive, so the batch can be
+    /// restored together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<String>,
+    pub specimen: Map<String, Value>,
+    pub subcultures: Vec<Map<String, Value>>,
+    /// Ids of the specimens whose parent this was.
+    pub children: Vec<String>,
+}
+
+/// A snapshot ready to be written to an archive audit entry.
+pub struct Snapshot {
+    pub json: String,
+    pub digest: String,
+}
+
+impl Snapshot {
+    /// `details` for the archive entry: `base` plus the digest the chain hashes.
+    pub fn details(&self, base: &str) -> String {
+        format!("{}; {}{}", base, DIGEST_MARKER, self.digest)
+    }
+}
+
+/// An archived specimen, or a deleted one the audit trail can bring back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestorableSpecimen {
+    pub specimen_id: String,
+    pub accession_number: String,
+    /// The specimen row no longer exists; restoring re-inserts it.
+    pub deleted: bool,
+    /// Whether the lineage verifies and the snapshot matches its digest.
+    /// Checked only for deleted specimens, which cannot come back without it.
+    pub proven: Option<bool>,
+    pub archive_entry_id: String,
+    pub archived_at: String,
+    pub archived_by: Option<String>,
+    pub batch: Option<String>,
+    pub details: Option<String>,
+}
+
+/// What one restore did.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoredSpecimen {
+    pub specimen_id: String,
+    pub accession_number: String,
+    /// The archive audit entry the restore undid.
+    pub source_entry_id: String,
+    pub archived_at: String,
+    /// The specimen row was re-inserted from the snapshot.
+    pub reinserted: bool,
+    pub subcultures_reinserted: usize,
+    pub children_relinked: usize,
+}
+
+/// Snapshots specimen `id` for its archive entry; `None` if there is no such
+/// row. Take it before the archiving UPDATE, so it records the prior state.
+pub fn take(conn: &Connection, id: &str, batch: Option<&str>) -> Result<Option<Snapshot>, AppError> {
+    let Some(specimen) = select_maps(conn, "SELECT * FROM specimens WHERE id = ?1", id)?.pop() else {
+        return Ok(None);
+    };
+    let snapshot = ArchiveSnapshot {
+        version: SNAPSHOT_VERSION,
+        batch: batch.map(str::to_string),
+        specimen,
+        subcultures: select_maps(conn, "SELECT * FROM subcultures WHERE specimen_id = ?1 ORDER BY passage_number, id", id)?,
+        children: conn
+            .prepare("SELECT id FROM specimens WHERE parent_specimen_id = ?1 ORDER BY id")?
+            .query_map(params![id], |r| r.get(0))?
+            .collect::<Result<_, _>>()?,
+    };
+    let json = serde_json::to_string(&snapshot)?;
+    Ok(Some(Snapshot { digest: digest(&json), json }))
+}
+
+fn digest(json: &str) -> String {
+    format!("{:x}", Sha256::digest(json.as_bytes()))
+}
+
+fn select_maps(conn: &Connection, sql: &str, id: &str) -> Result<Vec<Map<String, Value>>, AppError> {
+    let mut stmt = conn.prepare(sql)?;
+    let names: Vec<String> = stmt.column_names().into_iter().map(str::to_string).collect();
+    let rows = stmt
+        .query_map(params![id], |row| {
+            let mut map = Map::new();
+            for (i, name) in names.iter().enumerate() {
+                let value = match row.get_ref(i)? {
+                    ValueRef::Null => Value::Null,
+                    ValueRef::Integer(n) => Value::from(n),
+                    ValueRef::Real(f) => Value::from(f),
+                    ValueRef::Text(t) => Value::from(String::from_utf8_lossy(t).into_owned()),
+                    // Neither table has blob columns.
+                    ValueRef::Blob(_) => continue,
+                };
+                map.insert(name.clone(), value);
+            }
+            Ok(map)
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
+/// Inserts `row` into `table`, keeping only the columns the table still has.
+fn insert_map(conn: &Connection, table: &str, row: &Map<String, Value>) -> rusqlite::Result<()> {
+    let columns: Vec<String> = conn
+        .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))?
+        .query_map([], |r| r.get(0))?
+        .collect::<Result<_, _>>()?;
+    let (names, values): (Vec<&String>, Vec<SqlValue>) = columns
+        .iter()
+        .filter_map(|c| row.get(c).map(|v| (c, to_sql(v))))
+        .unzip();
+    let placeholders: Vec<String> = (1..=names.len()).map(|i| format!("?{}", i)).collect();
+    let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+    conn.execute(
+        &format!("INSERT INTO {} ({}) VALUES ({})", table, names.join(", "), placeholders.join(", ")),
+        rusqlite::params_from_iter(values),
+    )?;
+    Ok(())
+}
+
+fn to_sql(v: &Value) -> SqlValue {
+    match v {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(i64::from(*b)),
+        Value::Number(n) => n.as_i64().map(SqlValue::Integer).unwrap_or_else(|| SqlValue::Real(n.as_f64().unwrap_or_default())),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// The newest lifecycle audit entry of a specimen.
+struct LifecycleEntry {
+    id: String,
+    action: String,
+    old_value: Option<String>,
+    details: Option<String>,
+    created_at: String,
+}
+
+fn latest_lifecycle(conn: &Connection, id: &str) -> Result<Option<LifecycleEntry>, AppError> {
+    conn.query_row(
+        &format!(
+            "SELECT id, action, old_value, details, created_at FROM audit_log
+             WHERE entity_type = 'specimen' AND entity_id = ?1 AND action IN ({})
+             ORDER BY COALESCE(chain_seq, 0) DESC, created_at DESC LIMIT 1",
+            LIFECYCLE_ACTIONS
+        ),
+        params![id],
+        |r| {
+            Ok(LifecycleEntry {
+                id: r.get(0)?,
+                action: r.get(1)?,
+                old_value: r.get(2)?,
+                details: r.get(3)?,
+                created_at: r.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+impl LifecycleEntry {
+    fn snapshot(&self) -> Option<ArchiveSnapshot> {
+        serde_json::from_str(self.old_value.as_deref()?).ok()
+    }
+
+    /// The snapshot, if the lineage verifies and it matches the digest in the
+    /// hashed details.
+    fn proven_snapshot(&self, conn: &Connection, specimen_id: &str) -> Option<ArchiveSnapshot> {
+        let json = self.old_value.as_deref()?;
+        let details = self.details.as_deref()?;
+        let claimed = &details[details.rfind(DIGEST_MARKER)? + DIGEST_MARKER.len()..];
+        if claimed != digest(json) {
+            return None;
+        }
+        let verified = crate::db::queries::verify_audit_lineage(conn, specimen_id.to_string()).ok()?;
+        if !verified.ok {
+            return None;
+        }
+        self.snapshot()
+    }
+}
+
+/// Archived specimens of `profile` whose last lifecycle entry is an archive,
+/// and deleted ones whose archive snapshot names `profile`. Newest first.
+pub fn list_restorable(conn: &Connection, profile: &str) -> Result<Vec<RestorableSpecimen>, AppError> {
+    let latest = format!(
+        "(SELECT l.id FROM audit_log l WHERE l.entity_type = 'specimen' AND l.entity_id = a.entity_id
+           AND l.action IN ({}) ORDER BY COALESCE(l.chain_seq, 0) DESC, l.created_at DESC LIMIT 1)",
+        LIFECYCLE_ACTIONS
+    );
+    let batch = "CASE WHEN json_valid(a.old_value) THEN json_extract(a.old_value, '$.batch') END";
+    let mut rows = conn
+        .prepare(&format!(
+            "SELECT s.id, s.accession_number, a.id, a.created_at, u.username, {batch}, a.details
+             FROM specimens s
+             JOIN audit_log a ON a.entity_type = 'specimen' AND a.entity_id = s.id AND a.action = 'archive'
+             LEFT JOIN users u ON u.id = a.user_id
+             WHERE s.is_archived = 1 AND s.merged_into IS NULL AND s.lab_profile = ?1 AND a.id = {latest}
+             ORDER BY a.created_at DESC LIMIT ?2"
+        ))?
+        .query_map(params![profile, LIST_LIMIT], |r| {
+            Ok(RestorableSpecimen {
+                specimen_id: r.get(0)?,
+                accession_number: r.get(1)?,
+                deleted: false,
+                proven: None,
+                archive_entry_id: r.get(2)?,
+                archived_at: r.get(3)?,
+                archived_by: r.get(4)?,
+                batch: r.get(5)?,
+                details: r.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut deleted = conn
+        .prepare(&format!(
+            "SELECT a.entity_id, json_extract(a.old_value, '$.specimen.accession_number'), a.id, a.created_at,
+                    u.username, {batch}, a.details
+             FROM audit_log a
+             LEFT JOIN users u ON u.id = a.user_id
+             WHERE a.entity_type = 'specimen' AND a.action = 'archive' AND json_valid(a.old_value)
+               AND json_extract(a.old_value, '$.specimen.lab_profile') = ?1
+               AND NOT EXISTS (SELECT 1 FROM specimens s WHERE s.id = a.entity_id)
+               AND a.id = {latest}
+             ORDER BY a.created_at DESC LIMIT ?2"
+        ))?
+        .query_map(params![profile, LIST_LIMIT], |r| {
+            Ok(RestorableSpecimen {
+                specimen_id: r.get(0)?,
+                accession_number: r.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                deleted: true,
+                proven: None,
+                archive_entry_id: r.get(2)?,
+                archived_at: r.get(3)?,
+                archived_by: r.get(4)?,
+                batch: r.get(5)?,
+                details: r.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for d in &mut deleted {
+        let proven = latest_lifecycle(conn, &d.specimen_id)?.and_then(|e| e.proven_snapshot(conn, &d.specimen_id)).is_some();
+        d.proven = Some(proven);
+    }
+
+    rows.extend(deleted);
+    rows.sort_by(|a, b| b.archived_at.cmp(&a.archived_at));
+    rows.truncate(LIST_LIMIT as usize);
+    Ok(rows)
+}
+
+/// Restores each of `ids` in `profile`. Run it inside a transaction: one
+/// refusal is an error naming the specimen, and the caller rolls back the lot.
+pub fn restore(conn: &Connection, profile: &str, ids: &[String]) -> Result<Vec<RestoredSpecimen>, AppError> {
+    let mut seen = std::collections::HashSet::new();
+    ids.iter()
+        .filter(|id| seen.insert(id.as_str()))
+        .map(|id| restore_one(conn, profile, id))
+        .collect()
+}
+
+fn restore_one(conn: &Connection, profile: &str, id: &str) -> Result<RestoredSpecimen, AppError> {
+    let not_found = || AppError::not_found("specimen", "Specimen not found").with_id(id);
+    let entry = latest_lifecycle(conn, id)?;
+    let row: Option<(String, bool, Option<String>, String)> = conn
+        .query_row(
+            "SELECT accession_number, is_archived, merged_into, lab_profile FROM specimens WHERE id = ?1",
+            params![id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )
+        .optional()?;
+
+    let (accession, snapshot, reinserted) = match row {
+        Some((accession, archived, merged_into, lab)) => {
+            if lab != profile {
+                return Err(not_found());
+            }
+            if let Some(into) = merged_into {
+                let into: String = conn
+                    .query_row("SELECT accession_number FROM specimens WHERE id = ?1", params![into], |r| r.get(0))
+                    .unwrap_or(into);
+                return Err(AppError::conflict(format!("{} was merged into {}; a merge cannot be undone", accession, into)));
+            }
+            if !archived {
+                return Err(AppError::validation("ids", format!("{} is not archived", accession)));
+            }
+            let by = match entry.as_ref().map(|e| e.action.as_str()) {
+                Some("archive") => None,
+                Some("death") => Some("a death record".to_string()),
+                Some("split") => Some("a split".to_string()),
+                Some("merge") => Some("a merge".to_string()),
+                // Archived again after its last restore without an entry.
+                Some("restore") => {
+                    Some("something after its last restore, with no archive entry in the audit trail".to_string())
+                }
+                Some(other) => Some(format!("an unrecognised '{}' entry", other)),
+                None => Some("something with no archive entry in the audit trail".to_string()),
+            };
+            if let Some(by) = by {
+                return Err(AppError::validation(
+                    "ids",
+                    format!("{} was archived by {}; only an archive can be undone", accession, by),
+                ));
+            }
+            // An archive from before snapshots, or one the chain cannot vouch
+            // for, still un-archives; it just re-inserts nothing.
+            let snapshot = entry.as_ref().and_then(|e| e.proven_snapshot(conn, id));
+            (accession, snapshot, false)
+        }
+        None => {
+            let entry = entry.as_ref().filter(|e| e.action == "archive").ok_or_else(not_found)?;
+            let snapshot = entry.snapshot().ok_or_else(not_found)?;
+            if snapshot.specimen.get("lab_profile").and_then(Value::as_str) != Some(profile) {
+                return Err(not_found());
+            }
+            let accession = snapshot.specimen.get("accession_number").and_then(Value::as_str).unwrap_or(id).to_string();
+            let snapshot = entry.proven_snapshot(conn, id).ok_or_else(|| {
+                AppError::validation(
+                    "ids",
+                    format!("{} was deleted, and its audit chain does not verify the archived copy, so it cannot be restored", accession),
+                )
+            })?;
+            let mut specimen = snapshot.specimen.clone();
+            specimen.insert("is_archived".into(), Value::from(0));
+            specimen.insert("archived_at".into(), Value::Null);
+            insert_map(conn, "specimens", &specimen)
+                .map_err(|e| AppError::validation("ids", format!("{} cannot be re-inserted: {}", accession, e)))?;
+            (accession, Some(snapshot), true)
+        }
+    };
+    let entry = entry.ok_or_else(not_found)?;
+
+    let mut subcultures_reinserted = 0;
+    let mut children_relinked = 0;
+    if let Some(snapshot) = &snapshot {
+        for passage in &snapshot.subcultures {
+            let Some(pid) = passage.get("id").and_then(Value::as_str) else { continue };
+            let exists: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM subcultures WHERE id = ?1)", params![pid], |r| r.get(0))?;
+            if !exists {
+                insert_map(conn, "subcultures", passage)
+                    .map_err(|e| AppError::validation("ids", format!("A passage of {} cannot be re-inserted: {}", accession, e)))?;
+                subcultures_reinserted += 1;
+            }
+        }
+        for child in &snapshot.children {
+            children_relinked += conn.execute(
+                "UPDATE specimens SET parent_specimen_id = ?1, updated_at = datetime('now')
+                 WHERE id = ?2 AND parent_specimen_id IS NULL",
+                params![id, child],
+            )?;
+        }
+        if subcultures_reinserted > 0 && !reinserted {
+            let count = snapshot.specimen.get("subculture_count").and_then(Value::as_i64).unwrap_or(0);
+            conn.execute(
+                "UPDATE specimens SET subculture_count = MAX(subculture_count, ?2) WHERE id = ?1",
+                params![id, count],
+            )?;
+        }
+    }
+    conn.execute(
+        "UPDATE specimens SET is_archived = 0, archived_at = NULL, updated_at = datetime('now') WHERE id = ?1",
+        params![id],
+    )?;
+
+    Ok(RestoredSpecimen {
+        specimen_id: id.to_string(),
+        accession_number: accession,
+        source_entry_id: entry.id,
+        archived_at: entry.created_at,
+        reinserted,
+        subcultures_reinserted,
+        children_relinked,
+    })
+}
+
+/// A one-line account of a restore, for its audit details.
+pub fn describe(r: &RestoredSpecimen) -> String {
+    let mut parts = vec![format!("Specimen restored, undoing the archive of {}", r.archived_at)];
+    if r.reinserted {
+        parts.push("re-inserted from the archive snapshot".to_string());
+    }
+    if r.subcultures_reinserted > 0 {
+        parts.push(format!("{} passages re-inserted", r.subcultures_reinserted));
+    }
+    if r.children_relinked > 0 {
+        parts.push(format!("{} children relinked", r.children_relinked));
+    }
+    parts.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::{run_all, seed_defaults};
+    use crate::db::queries::log_audit;
+
+    const PTC: &str = "plant_tissue_culture";
+
+    fn restore_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        seed_defaults(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES ('u1', 'ann', 'x', 'Ann', 'admin');
+             INSERT INTO species (id, genus, species_name, species_code) VALUES ('sp', 'Citrus', 'sinensis', 'CIT');
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, subculture_count) VALUES
+                 ('a', 'CIT-001', 'sp', 'shoot', '2026-01-01', 2),
+                 ('b', 'CIT-002', 'sp', 'shoot', '2026-01-01', 0);
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, parent_specimen_id, root_specimen_id) VALUES
+                 ('kid', 'CIT-001-A', 'sp', 'shoot', '2026-03-01', 'a', 'a');
+             INSERT INTO subcultures (id, specimen_id, passage_number, date) VALUES
+                 ('p1', 'a', 1, '2026-02-01'), ('p2', 'a', 2, '2026-03-01');",
+        )
+        .unwrap();
+        log_audit(&conn, Some("u1"), "create", "specimen", Some("a"), None, Some("CIT-001"), Some("Specimen created")).unwrap();
+        conn
+    }
+
+    /// What the archive commands do: snapshot, archive, then audit.
+    fn archive(conn: &Connection, id: &str, batch: Option<&str>) {
+        let snap = take(conn, id, batch).unwrap().unwrap();
+        conn.execute("UPDATE specimens SET is_archived = 1, archived_at = datetime('now') WHERE id = ?1", params![id]).unwrap();
+        log_audit(conn, Some("u1"), "archive", "specimen", Some(id), Some(&snap.json), None, Some(&snap.details("Bulk archived"))).unwrap();
+    }
+
+    fn archived(conn: &Connection, id: &str) -> bool {
+        conn.query_row("SELECT is_archived FROM specimens WHERE id = ?1", params![id], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn a_bulk_archive_is_listed_by_batch_and_restored() {
+        let conn = restore_db();
+        archive(&conn, "a", Some("batch-1"));
+        archive(&conn, "b", Some("batch-1"));
+        let listed = list_restorable(&conn, PTC).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed.iter().all(|r| r.batch.as_deref() == Some("batch-1") && !r.deleted));
+        assert_eq!(listed[0].archived_by.as_deref(), Some("ann"));
+        assert!(list_restorable(&conn, "mycology").unwrap().is_empty());
+
+        let done = restore(&conn, PTC, &["a".into(), "a".into()]).unwrap();
+        assert_eq!(done.len(), 1);
+        assert!(!archived(&conn, "a") && archived(&conn, "b"));
+        assert_eq!((done[0].subcultures_reinserted, done[0].reinserted), (0, false));
+        assert!(describe(&done[0]).starts_with("Specimen restored, undoing the archive of "));
+
+        // Restored: no longer listed, and a second restore is refused.
+        log_audit(&conn, Some("u1"), "restore", "specimen", Some("a"), None, None, Some("Specimen restored")).unwrap();
+        assert_eq!(list_restorable(&conn, PTC).unwrap().len(), 1);
+        assert_eq!(restore(&conn, PTC, &["a".into()]).unwrap_err().code(), "validation");
+
+        // Archived again behind the trail's back: the restore is the latest
+        // lifecycle entry, and the refusal says so rather than blaming a merge.
+        conn.execute("UPDATE specimens SET is_archived = 1 WHERE id = 'a'", []).unwrap();
+        let err = restore(&conn, PTC, &["a".into()]).unwrap_err();
+        assert!(err.message().contains("after its last restore"), "{}", err);
+        assert!(!err.message().contains("merge"), "{}", err);
+    }
+
+    #[test]
+    fn a_deleted_specimen_comes_back_with_its_passages_and_children() {
+        let conn = restore_db();
+        archive(&conn, "a", None);
+        conn.execute("UPDATE specimens SET parent_specimen_id = NULL, root_specimen_id = NULL WHERE id = 'kid'", []).unwrap();
+        conn.execute("DELETE FROM subcultures WHERE specimen_id = 'a'", []).unwrap();
+        conn.execute("DELETE FROM specimens WHERE id = 'a'", []).unwrap();
+
+        let listed = list_restorable(&conn, PTC).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!((listed[0].accession_number.as_str(), listed[0].deleted, listed[0].proven), ("CIT-001", true, Some(true)));
+
+        let done = restore(&conn, PTC, &["a".into()]).unwrap().remove(0);
+        assert_eq!((done.reinserted, done.subcultures_reinserted, done.children_relinked), (true, 2, 1));
+        assert!(!archived(&conn, "a"));
+        let kid: String = conn.query_row("SELECT parent_specimen_id FROM specimens WHERE id = 'kid'", [], |r| r.get(0)).unwrap();
+        assert_eq!(kid, "a");
+        let count: i64 = conn.query_row("SELECT subculture_count FROM specimens WHERE id = 'a'", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn a_snapshot_the_chain_does_not_vouch_for_is_not_reinserted() {
+        let conn = restore_db();
+        archive(&conn, "a", None);
+        conn.execute("DELETE FROM specimens WHERE id = 'kid'", []).unwrap();
+        conn.execute("DELETE FROM subcultures WHERE specimen_id = 'a'", []).unwrap();
+        conn.execute("DELETE FROM specimens WHERE id = 'a'", []).unwrap();
+        conn.execute(
+            "UPDATE audit_log SET old_value = replace(old_value, 'CIT-001', 'CIT-999') WHERE action = 'archive'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(list_restorable(&conn, PTC).unwrap()[0].proven, Some(false));
+        let err = restore(&conn, PTC, &["a".into()]).unwrap_err();
+        assert_eq!(err.code(), "validation");
+        assert!(err.message().contains("does not verify"), "{}", err.message());
+        let rows: i64 = conn.query_row("SELECT COUNT(*) FROM specimens WHERE id = 'a'", [], |r| r.get(0)).unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[test]
+    fn deaths_merges_and_live_specimens_are_not_restored() {
+        let conn = restore_db();
+        conn.execute("UPDATE specimens SET is_archived = 1 WHERE id = 'a'", []).unwrap();
+        log_audit(&conn, Some("u1"), "death", "specimen", Some("a"), None, None, Some("Specimen marked dead")).unwrap();
+        let err = restore(&conn, PTC, &["a".into()]).unwrap_err();
+        assert!(err.message().contains("a death record"), "{}", err.message());
+        assert!(list_restorable(&conn, PTC).unwrap().is_empty());
+
+        conn.execute("UPDATE specimens SET is_archived = 1, merged_into = 'a' WHERE id = 'b'", []).unwrap();
+        assert_eq!(restore(&conn, PTC, &["b".into()]).unwrap_err().code(), "conflict");
+        assert_eq!(restore(&conn, PTC, &["kid".into()]).unwrap_err().code(), "validation");
+        assert_eq!(restore(&conn, "mycology", &["kid".into()]).unwrap_err().code(), "not_found");
+        assert_eq!(restore(&conn, PTC, &["gone".into()]).unwrap_err().code(), "not_found");
+    }
+}
@@ -0,0 +1,437 @@
+// WP-98: the stage state machine. Each lab profile lists the stage changes it
+// allows (`from_stage` → `to_stage`), each optionally requiring fields to be
+// filled in on the specimen, e.g. plantlet → acclimatized needs the
+// acclimatization date and survival count. A profile with no transitions at
+// all is unrestricted, so a plugin profile keeps working until it ships (or an
+// admin writes) its own.
+//
+// The check runs after the write, inside the caller's transaction: the
+// specimen as stored is what must satisfy the rule, whatever mix of request
+// values, stored values and custom-field patches produced it. The caller
+// rolls back on error.
+use crate::db::custom_fields::{self, CustomFieldEntity};
+use crate::error::AppError;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Specimen columns a transition may require, with their labels. A request
+/// names a key from this list; only these fixed names reach the SQL.
+const REQUIRABLE: &[(&str, &str)] = &[
+    ("location", "Location"),
+    ("location_details", "Location details"),
+    ("propagation_method", "Propagation method"),
+    ("acclimatization_status", "Acclimatization status"),
+    ("health_status", "Health status"),
+    ("disease_status", "Disease status"),
+    ("quarantine_release_date", "Quarantine release date"),
+    ("permit_number", "Permit number"),
+    ("permit_expiry", "Permit expiry"),
+    ("environmental_notes", "Environmental notes"),
+    ("notes", "Notes"),
+    ("origin_type", "Origin type"),
+    ("biosafety_level", "Biosafety level"),
+    ("custom_stage", "Custom stage"),
+];
+
+const MAX_REQUIRED: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageTransition {
+    pub lab_profile: String,
+    pub from_stage: String,
+    pub to_stage: String,
+    /// Keys from [`requirable_fields`] or `custom.<key>`.
+    pub required_fields: Vec<String>,
+    pub source_plugin: Option<String>,
+    pub updated_at: String,
+    pub updated_by: Option<String>,
+}
+
+/// A transition as sent by the settings screen or a plugin manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveStageTransitionRequest {
+    /// Defaults to the active profile (or, in a manifest, the plugin's).
+    #[serde(default)]
+    pub lab_profile: Option<String>,
+    pub from_stage: String,
+    pub to_stage: String,
+    #[serde(default)]
+    pub required_fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RequirableField {
+    pub key: &'static str,
+    pub label: &'static str,
+}
+
+/// The built-in specimen fields a transition can require. Custom specimen
+/// fields are `custom.<key>`.
+pub fn requirable_fields() -> Vec<RequirableField> {
+    REQUIRABLE.iter().map(|&(key, label)| RequirableField { key, label }).collect()
+}
+
+const SELECT: &str = "SELECT lab_profile, from_stage, to_stage, required_fields, source_plugin, updated_at, updated_by
+                      FROM stage_transitions";
+
+fn row_to_transition(row: &rusqlite::Row) -> rusqlite::Result<StageTransition> {
+    let required: String = row.get(3)?;
+    Ok(StageTransition {
+        lab_profile: row.get(0)?,
+        from_stage: row.get(1)?,
+        to_stage: row.get(2)?,
+        required_fields: serde_json::from_str(&required).unwrap_or_default(),
+        source_plugin: row.get(4)?,
+        updated_at: row.get(5)?,
+        updated_by: row.get(6)?,
+    })
+}
+
+/// The profile's transitions, in stage order.
+pub fn list(conn: &Connection, profile: &str) -> Result<Vec<StageTransition>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "{} t WHERE t.lab_profile = ?1
+         ORDER BY (SELECT sort_order FROM stages WHERE profile = t.lab_profile AND code = t.from_stage),
+                  (SELECT sort_order FROM stages WHERE profile = t.lab_profile AND code = t.to_stage)",
+        SELECT
+    ))?;
+    let rows = stmt.query_map(params![profile], row_to_transition)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+fn get(conn: &Connection, profile: &str, from: &str, to: &str) -> Result<Option<StageTransition>, AppError> {
+    Ok(conn
+        .query_row(
+            &format!("{} WHERE lab_profile = ?1 AND from_stage = ?2 AND to_stage = ?3", SELECT),
+            params![profile, from, to],
+            row_to_transition,
+        )
+        .optional()?)
+}
+
+/// Whether the profile restricts stage changes at all.
+pub fn is_restricted(conn: &Connection, profile: &str) -> Result<bool, AppError> {
+    Ok(conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM stage_transitions WHERE lab_profile = ?1)",
+        params![profile],
+        |r| r.get(0),
+    )?)
+}
+
+/// `(label, is_terminal)` of a stage of `profile`, if it has one.
+fn stage(conn: &Connection, profile: &str, code: &str) -> Result<Option<(String, bool)>, AppError> {
+    Ok(conn
+        .query_row(
+            "SELECT label, is_terminal FROM stages WHERE profile = ?1 AND code = ?2",
+            params![profile, code],
+            |r| Ok((r.get(0)?, r.get::<_, i64>(1)? != 0)),
+        )
+        .optional()?)
+}
+
+fn stage_label(conn: &Connection, profile: &str, code: &str) -> Result<String, AppError> {
+    Ok(stage(conn, profile, code)?.map(|(label, _)| label).unwrap_or_else(|| code.to_string()))
+}
+
+/// Checks a transition on its own, without the database. Shared by
+/// [`save`] and plugin manifest validation.
+pub fn check_definition(req: &SaveStageTransitionRequest) -> Result<(), AppError> {
+    if req.from_stage.trim().is_empty() {
+        return Err(AppError::validation("from_stage", "Choose the stage the transition leaves"));
+    }
+    if req.to_stage.trim().is_empty() {
+        return Err(AppError::validation("to_stage", "Choose the stage the transition enters"));
+    }
+    if req.from_stage == req.to_stage {
+        return Err(AppError::validation("to_stage", "A transition goes to a different stage"));
+    }
+    if req.required_fields.len() > MAX_REQUIRED {
+        return Err(AppError::validation("required_fields", format!("At most {} required fields", MAX_REQUIRED)));
+    }
+    let mut seen = std::collections::HashSet::new();
+    for key in &req.required_fields {
+        let known = match key.strip_prefix("custom.") {
+            Some(custom) => custom_fields::is_key(custom),
+            None => REQUIRABLE.iter().any(|(k, _)| k == key),
+        };
+        if !known {
+            return Err(AppError::validation(
+                "required_fields",
+                format!("'{}' cannot be required; use a specimen field or custom.<key>", key),
+            ));
+        }
+        if !seen.insert(key) {
+            return Err(AppError::validation("required_fields", format!("'{}' is listed twice", key)));
+        }
+    }
+    Ok(())
+}
+
+/// The database half of the checks: both stages exist in `profile`, the
+/// source is not terminal, and every custom field required is defined.
+fn check_against(conn: &Connection, profile: &str, req: &SaveStageTransitionRequest) -> Result<(), AppError> {
+    check_definition(req)?;
+    match stage(conn, profile, &req.from_stage)? {
+        None => {
+            return Err(AppError::validation("from_stage", format!("'{}' is not a stage of {}", req.from_stage, profile)));
+        }
+        Some((label, true)) => {
+            return Err(AppError::validation("from_stage", format!("{} is a terminal stage; nothing leaves it", label)));
+        }
+        Some(_) => {}
+    }
+    if stage(conn, profile, &req.to_stage)?.is_none() {
+        return Err(AppError::validation("to_stage", format!("'{}' is not a stage of {}", req.to_stage, profile)));
+    }
+    let custom = custom_fields::list(conn, profile, Some(CustomFieldEntity::Specimen), false)?;
+    for key in req.required_fields.iter().filter_map(|k| k.strip_prefix("custom.")) {
+        if !custom.iter().any(|f| f.key == key) {
+            return Err(AppError::validation(
+                "required_fields",
+                format!("'{}' is not a custom specimen field of {}", key, profile),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Allows a transition in `profile`, or changes the fields an existing one
+/// requires.
+pub fn save(
+    conn: &Connection,
+    user_id: &str,
+    profile: &str,
+    req: &SaveStageTransitionRequest,
+) -> Result<StageTransition, AppError> {
+    let profile = req.lab_profile.as_deref().unwrap_or(profile);
+    check_against(conn, profile, req)?;
+    conn.execute(
+        "INSERT INTO stage_transitions (lab_profile, from_stage, to_stage, required_fields, updated_by)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (lab_profile, from_stage, to_stage) DO UPDATE SET
+             required_fields = excluded.required_fields,
+             updated_at = datetime('now'),
+             updated_by = excluded.updated_by",
+        params![
+            profile, req.from_stage, req.to_stage,
+            serde_json::to_string(&req.required_fields)?, user_id,
+        ],
+    )?;
+    get(conn, profile, &req.from_stage, &req.to_stage)?
+        .ok_or_else(|| AppError::internal("The saved transition could not be read back"))
+}
+
+/// Forbids a transition again and returns it. Removing a profile's last
+/// transition leaves the profile unrestricted.
+pub fn delete(conn: &Connection, profile: &str, from: &str, to: &str) -> Result<StageTransition, AppError> {
+    let existing = get(conn, profile, from, to)?
+        .ok_or_else(|| AppError::not_found("stage_transition", format!("{} → {} is not a transition of {}", from, to, profile)))?;
+    conn.execute(
+        "DELETE FROM stage_transitions WHERE lab_profile = ?1 AND from_stage = ?2 AND to_stage = ?3",
+        params![profile, from, to],
+    )?;
+    Ok(existing)
+}
+
+/// Adds a plugin's transitions. Like the vocabulary seed this only adds: a
+/// transition the profile already has is left as it is.
+pub fn seed(conn: &Connection, plugin: &str, profile: &str, transitions: &[SaveStageTransitionRequest]) -> Result<usize, AppError> {
+    let mut added = 0;
+    for req in transitions {
+        let profile = req.lab_profile.as_deref().unwrap_or(profile);
+        check_against(conn, profile, req)?;
+        added += conn.execute(
+            "INSERT OR IGNORE INTO stage_transitions (lab_profile, from_stage, to_stage, required_fields, source_plugin)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![profile, req.from_stage, req.to_stage, serde_json::to_string(&req.required_fields)?, plugin],
+        )?;
+    }
+    Ok(added)
+}
+
+/// Checks the stage change a write has just made to `specimen_id`, from
+/// `from_stage` to whatever is now stored. A change to the same stage, or in
+/// an unrestricted profile, always passes. Otherwise the transition must be
+/// allowed and its required fields filled in; the error is a `validation`
+/// error on `stage` naming the specimen.
+pub fn enforce(conn: &Connection, profile: &str, specimen_id: &str, from_stage: &str) -> Result<(), AppError> {
+    let (to_stage, accession, custom): (String, String, Option<String>) = conn
+        .query_row(
+            "SELECT stage, accession_number, custom_fields FROM specimens WHERE id = ?1",
+            params![specimen_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("specimen", "Specimen not found").with_id(specimen_id))?;
+    if to_stage == from_stage || !is_restricted(conn, profile)? {
+        return Ok(());
+    }
+    let from_label = stage_label(conn, profile, from_stage)?;
+    let to_label = stage_label(conn, profile, &to_stage)?;
+
+    let Some(transition) = get(conn, profile, from_stage, &to_stage)? else {
+        let mut stmt = conn.prepare(
+            "SELECT st.label FROM stage_transitions t
+             JOIN stages st ON st.profile = t.lab_profile AND st.code = t.to_stage
+             WHERE t.lab_profile = ?1 AND t.from_stage = ?2 ORDER BY st.sort_order",
+        )?;
+        let next = stmt.query_map(params![profile, from_stage], |r| r.get::<_, String>(0))?.collect::<Result<Vec<_>, _>>()?;
+        let hint = if next.is_empty() {
+            format!("Nothing follows {} in this lab.", from_label)
+        } else {
+            format!("From {} it can move to {}.", from_label, next.join(", "))
+        };
+        return Err(AppError::validation(
+            "stage",
+            format!("{} cannot move from {} to {}. {}", accession, from_label, to_label, hint),
+        ));
+    };
+
+    let values = custom_fields::from_column(custom.as_deref());
+    let definitions = custom_fields::list(conn, profile, Some(CustomFieldEntity::Specimen), true)?;
+    let mut missing = Vec::new();
+    for key in &transition.required_fields {
+        if let Some(custom_key) = key.strip_prefix("custom.") {
+            if values.get(custom_key).is_none_or(|v| v.is_null()) {
+                let label = definitions.iter().find(|f| f.key == custom_key).map(|f| f.label.clone());
+                missing.push(label.unwrap_or_else(|| custom_key.to_string()));
+            }
+        } else if let Some(&(column, label)) = REQUIRABLE.iter().find(|(k, _)| k == key) {
+            let value: Option<String> = conn.query_row(
+                &format!("SELECT CAST({} AS TEXT) FROM specimens WHERE id = ?1", column),
+                params![specimen_id],
+                |r| r.get(0),
+            )?;
+            if value.as_deref().is_none_or(|v| v.trim().is_empty()) {
+                missing.push(label.to_string());
+            }
+        }
+    }
+    if !missing.is_empty() {
+        return Err(AppError::validation(
+            "stage",
+            format!("Moving {} to {} needs {}", accession, to_label, join_and(&missing)),
+        ));
+    }
+    Ok(())
+}
+
+fn join_and(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::{run_all, seed_defaults};
+
+    const PTC: &str = "plant_tissue_culture";
+
+    fn machine_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        seed_defaults(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO species (id, genus, species_name, species_code) VALUES ('cit', 'Citrus', 'sinensis', 'CIT');
+             INSERT INTO specimens (id, accession_number, species_id, stage, initiation_date, lab_profile) VALUES
+                 ('r', 'R-1', 'cit', 'root', '2026-01-10', 'plant_tissue_culture'),
+                 ('p', 'P-1', 'cit', 'plantlet', '2026-01-10', 'plant_tissue_culture');",
+        )
+        .unwrap();
+        conn
+    }
+
+    /// What a command does: write the new stage, then enforce.
+    fn move_to(conn: &Connection, id: &str, to: &str) -> Result<(), AppError> {
+        let from: String = conn.query_row("SELECT stage FROM specimens WHERE id = ?1", [id], |r| r.get(0)).unwrap();
+        conn.execute("UPDATE specimens SET stage = ?1 WHERE id = ?2", params![to, id]).unwrap();
+        let result = enforce(conn, PTC, id, &from);
+        if result.is_err() {
+            conn.execute("UPDATE specimens SET stage = ?1 WHERE id = ?2", params![from, id]).unwrap();
+        }
+        result
+    }
+
+    fn req(from: &str, to: &str, required: &[&str]) -> SaveStageTransitionRequest {
+        SaveStageTransitionRequest {
+            lab_profile: None,
+            from_stage: from.to_string(),
+            to_stage: to.to_string(),
+            required_fields: required.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn seeded_machine_refuses_going_backwards() {
+        let conn = machine_db();
+        let err = move_to(&conn, "r", "explant").unwrap_err();
+        assert_eq!(err.code(), "validation");
+        assert!(err.message().starts_with("R-1 cannot move from Root to Explant."), "{}", err.message());
+        assert!(err.message().contains("Root Meristem, Plantlet"), "{}", err.message());
+        move_to(&conn, "r", "plantlet").unwrap();
+        move_to(&conn, "r", "plantlet").unwrap();
+    }
+
+    #[test]
+    fn acclimatization_needs_its_date_and_survival_count() {
+        let conn = machine_db();
+        let err = move_to(&conn, "p", "acclimatized").unwrap_err();
+        assert_eq!(err.message(), "Moving P-1 to Acclimatized needs Acclimatization date and Survival count");
+        conn.execute(
+            "UPDATE specimens SET custom_fields = '{\"acclimatization_date\":\"2026-06-01\",\"survival_count\":18.0}' WHERE id = 'p'",
+            [],
+        )
+        .unwrap();
+        move_to(&conn, "p", "acclimatized").unwrap();
+    }
+
+    #[test]
+    fn admins_edit_the_machine_and_an_empty_profile_is_unrestricted() {
+        let conn = machine_db();
+        save(&conn, "admin", PTC, &req("root", "explant", &["notes", "location"])).unwrap();
+        let err = move_to(&conn, "r", "explant").unwrap_err();
+        assert_eq!(err.message(), "Moving R-1 to Explant needs Notes and Location");
+        conn.execute("UPDATE specimens SET notes = 'Re-initiated', location = 'Room B' WHERE id = 'r'", []).unwrap();
+        move_to(&conn, "r", "explant").unwrap();
+
+        assert_eq!(delete(&conn, PTC, "root", "explant").unwrap().updated_by.as_deref(), Some("admin"));
+        assert_eq!(delete(&conn, PTC, "root", "explant").unwrap_err().code(), "not_found");
+
+        conn.execute("DELETE FROM stage_transitions WHERE lab_profile = ?1", [PTC]).unwrap();
+        assert!(!is_restricted(&conn, PTC).unwrap());
+        move_to(&conn, "r", "stock").unwrap();
+    }
+
+    #[test]
+    fn definitions_are_checked() {
+        let conn = machine_db();
+        let field = |r: &SaveStageTransitionRequest| match save(&conn, "admin", PTC, r).unwrap_err() {
+            AppError::Validation { field, .. } => field,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(field(&req("root", "root", &[])), Some("to_stage"));
+        assert_eq!(field(&req("petal", "root", &[])), Some("from_stage"));
+        assert_eq!(field(&req("archived", "root", &[])), Some("from_stage"));
+        assert_eq!(field(&req("root", "petal", &[])), Some("to_stage"));
+        assert_eq!(field(&req("root", "stock", &["password_hash"])), Some("required_fields"));
+        assert_eq!(field(&req("root", "stock", &["notes", "notes"])), Some("required_fields"));
+        assert_eq!(field(&req("root", "stock", &["custom.ploidy"])), Some("required_fields"));
+    }
+
+    #[test]
+    fn plugin_seed_adds_but_never_overwrites() {
+        let conn = machine_db();
+        save(&conn, "admin", PTC, &req("root", "stock", &["notes"])).unwrap();
+        let added = seed(&conn, "orchids", PTC, &[req("root", "stock", &[]), req("acclimatized", "shoot", &[])]).unwrap();
+        assert_eq!(added, 1);
+        let rows = list(&conn, PTC).unwrap();
+        let kept = rows.iter().find(|t| t.from_stage == "root" && t.to_stage == "stock").unwrap();
+        assert_eq!((kept.required_fields.as_slice(), kept.source_plugin.as_deref()), (&["notes".to_string()][..], None));
+        let added = rows.iter().find(|t| t.from_stage == "acclimatized" && t.to_stage == "shoot").unwrap();
+        assert_eq!(added.source_plugin.as_deref(), Some("orchids"));
+    }
+}
@@ -1,5 +1,7 @@
 use rusqlite::Connection;
 
+use crate::error::AppError;
+
 /// Returns the active lab profile from app_config, defaulting to 'plant_tissue_culture'
 /// if the row is missing or the query fails.
 pub fn active_profile(conn: &Connection) -> String {
@@ -56,13 +58,13 @@ pub fn active_lab_sql(alias: &str) -> String {
 ///
 /// Reads the stamped `specimens.lab_profile` column — never re-derives it from
 /// the stage code, which is ambiguous across profiles (see migration 053).
-pub fn specimen_lab_profile(conn: &Connection, specimen_id: &str) -> Result<String, String> {
+pub fn specimen_lab_profile(conn: &Connection, specimen_id: &str) -> Result<String, AppError> {
     conn.query_row(
         "SELECT lab_profile FROM specimens WHERE id = ?1",
         rusqlite::params![specimen_id],
         |r| r.get(0),
     )
-    .map_err(|_| "Specimen not found".to_string())
+    .map_err(|_| AppError::not_found("specimen", "Specimen not found").with_id(specimen_id))
 }
 
 /// Guard: refuse to read or mutate a specimen that belongs to a different lab
@@ -74,17 +76,17 @@ pub fn specimen_lab_profile(conn: &Connection, specimen_id: &str) -> Result<Stri
 /// under one profile (from a QR scan, a bookmark, a stale UI, or a crafted IPC
 /// call) would otherwise still resolve after switching profiles. Every
 /// by-ID specimen command routes through here, so the block is default-deny.
-pub fn require_active_lab_profile(conn: &Connection, specimen_id: &str) -> Result<(), String> {
+pub fn require_active_lab_profile(conn: &Connection, specimen_id: &str) -> Result<(), AppError> {
     let active = active_profile(conn);
     let owner = specimen_lab_profile(conn, specimen_id)?;
     if owner == active {
         Ok(())
     } else {
-        Err(format!(
+        Err(AppError::invalid(format!(
             "This specimen belongs to the {} lab, but the {} lab is currently active. \
              Switch the active lab profile in Settings to work with it.",
             owner, active
-        ))
+        )))
     }
 }
 
@@ -108,11 +110,11 @@ pub fn stage_is_selectable(conn: &Connection, profile: &str, code: &str) -> bool
 /// exact same profile-scoped rule (previously only bulk-update validated, letting a
 /// stale cross-profile stage — e.g. an `explant` stage on a mycology specimen — be
 /// written straight to the DB on create).
-pub fn require_selectable_stage(conn: &Connection, profile: &str, code: &str) -> Result<(), String> {
+pub fn require_selectable_stage(conn: &Connection, profile: &str, code: &str) -> Result<(), AppError> {
     if stage_is_selectable(conn, profile, code) {
         Ok(())
     } else {
-        Err(format!("'{}' is not a valid or selectable stage", code))
+        Err(AppError::validation("stage", format!("'{}' is not a valid or selectable stage", code)))
     }
 }
 
@@ -237,7 +239,7 @@ mod tests {
         // 'adherent' belongs to cell_culture; rejecting it under plant_tissue_culture is
         // exactly the create-path hole this guard closes.
         let err = require_selectable_stage(&conn, "plant_tissue_culture", "adherent").unwrap_err();
-        assert!(err.contains("adherent"), "error should name the rejected code: {err}");
+        assert!(err.message().contains("adherent"), "error should name the rejected code: {err}");
     }
 
     #[test]
@@ -5,6 +5,8 @@
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 
+use crate::error::AppError;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WorkQueueItem {
     pub specimen_id: String,
@@ -37,7 +39,7 @@ fn days_ago(date_str: &str, today_str: &str) -> Option<i64> {
 }
 
 
-pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>, String> {
+pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>, AppError> {
     // Every query below is scoped to the active lab: the work queue tells an
     // operator what to physically go and do, so listing another lab's cultures
     // sends them to a bench they do not work at.
@@ -56,8 +58,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
              WHERE s.is_archived = 0 {lab}
                AND s.quarantine_flag = 1
                AND (s.quarantine_release_date IS NULL OR s.quarantine_release_date <= ?1)",
-        ))
-        .map_err(|e| e.to_string())?;
+        ))?;
 
         let rows = stmt
             .query_map(params![today], |row| {
@@ -68,8 +69,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
                     row.get::<_, Option<String>>("species_name")?,
                     row.get::<_, Option<String>>("quarantine_release_date")?,
                 ))
-            })
-            .map_err(|e| e.to_string())?;
+            })?;
 
         for row in rows.filter_map(|r| r.ok()) {
             let (specimen_id, accession_number, location, species_name, release_date) = row;
@@ -105,8 +105,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
                    )
              WHERE s.is_archived = 0 {lab}
                AND sc.contamination_flag = 1",
-        ))
-        .map_err(|e| e.to_string())?;
+        ))?;
 
         let rows = stmt
             .query_map(params![], |row| {
@@ -117,8 +116,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
                     row.get::<_, Option<String>>("species_name")?,
                     row.get::<_, String>("last_date")?,
                 ))
-            })
-            .map_err(|e| e.to_string())?;
+            })?;
 
         for row in rows.filter_map(|r| r.ok()) {
             let (specimen_id, accession_number, location, species_name, last_date) = row;
@@ -149,8 +147,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
              LEFT JOIN species sp ON s.species_id = sp.id
              WHERE s.is_archived = 0 {lab}
                AND s.subculture_count = 0",
-        ))
-        .map_err(|e| e.to_string())?;
+        ))?;
 
         let rows = stmt
             .query_map(params![], |row| {
@@ -160,8 +157,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
                     row.get::<_, Option<String>>("location")?,
                     row.get::<_, Option<String>>("species_name")?,
                 ))
-            })
-            .map_err(|e| e.to_string())?;
+            })?;
 
         for row in rows.filter_map(|r| r.ok()) {
             let (specimen_id, accession_number, location, species_name) = row;
@@ -194,8 +190,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
                AND sp.default_subculture_interval_days > 0
              GROUP BY s.id
              HAVING last_subculture_date IS NOT NULL",
-        ))
-        .map_err(|e| e.to_string())?;
+        ))?;
 
         let rows = stmt
             .query_map(params![], |row| {
@@ -207,8 +202,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
                     row.get::<_, i64>("default_subculture_interval_days")?,
                     row.get::<_, String>("last_subculture_date")?,
                 ))
-            })
-            .map_err(|e| e.to_string())?;
+            })?;
 
         for row in rows.filter_map(|r| r.ok()) {
             let (specimen_id, accession_number, location, species_name, interval, last_date) = row;
@@ -266,8 +260,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
              WHERE s.is_archived = 0 {lab}
                AND mb.expiration_date IS NOT NULL
                AND mb.expiration_date < ?1",
-        ))
-        .map_err(|e| e.to_string())?;
+        ))?;
 
         let rows = stmt
             .query_map(params![today], |row| {
@@ -279,8 +272,7 @@ pub fn compute_work_queue_items(conn: &Connection) -> Result<Vec<WorkQueueItem>,
                     row.get::<_, String>("expiration_date")?,
                     row.get::<_, String>("media_name")?,
                 ))
-            })
-            .map_err(|e| e.to_string())?;
+            })?;
 
         for row in rows.filter_map(|r| r.ok()) {
             let (specimen_id, accession_number, location, species_name, expiry, media_name) = row;
@@ -0,0 +1,352 @@
+// WP-93: the typed error every command returns.
+//
+// Commands used to return `Result<_, String>`, so the frontend and API
+// clients could only react to an error by matching its English wording —
+// `clearAuth()` ran when the text contained "Session expired", the local API
+// picked its status code from message prefixes, and nothing could be
+// translated. An `AppError` carries a stable machine `code`, the parameters a
+// client needs to word or act on it (which field, which capability, which
+// record) and the English `message` as a fallback. It serializes as
+//
+//     { "code": "validation", "message": "…", "params": { "field": "name" } }
+//
+// The codes are a public contract (docs/error-codes.md): add new ones, never
+// rename one.
+//
+// The modules commands call return it too; only low-level parsers (hex, the
+// SPV reader, HTTP framing) still return `String`, and their callers map
+// those errors to the variant they mean. `From<String>` remains for
+// whatever slips through: it keeps the auth and "not found" wording rules
+// the local API used before (`AppError::from_message`) and treats anything
+// else as `Internal`, since an untyped message is not a refusal a client can
+// act on. `From<AppError> for String` lets typed errors flow the other way,
+// into the CLI.
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    /// No usable credential: an unknown, expired or revoked session or token,
+    /// a wrong password, or a sign-in still waiting for its second factor.
+    Unauthenticated { message: String, reason: Option<&'static str> },
+    /// Signed in but not allowed: a capability the role (or token scope)
+    /// lacks, or a gate such as a mandated password change.
+    Forbidden {
+        message: String,
+        capability: Option<String>,
+        reason: Option<&'static str>,
+    },
+    /// The record the request names does not exist (in this lab).
+    NotFound {
+        message: String,
+        entity: Option<&'static str>,
+        id: Option<String>,
+    },
+    /// The request itself is wrong: a missing or malformed value, or an
+    /// operation the record's current state does not allow.
+    Validation { message: String, field: Option<&'static str> },
+    /// The request clashes with existing data: a duplicate name, a stale edit.
+    Conflict { message: String },
+    /// Stored data failed a tamper-evidence or consistency check.
+    Integrity { message: String },
+    /// A service outside the app (SMTP, a cloud target, a registry, an AI
+    /// provider, LDAP) failed or refused.
+    ExternalService { message: String, service: &'static str },
+    /// Anything else: a database or filesystem failure the caller cannot fix.
+    Internal { message: String },
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// `reason` values for [`AppError::Unauthenticated`] and
+/// [`AppError::Forbidden`].
+pub mod reason {
+    pub const SESSION_EXPIRED: &str = "session_expired";
+    pub const INVALID_CREDENTIALS: &str = "invalid_credentials";
+    pub const MFA_REQUIRED: &str = "mfa_required";
+    pub const PASSWORD_CHANGE_REQUIRED: &str = "password_change_required";
+    pub const MFA_ENROLLMENT_REQUIRED: &str = "mfa_enrollment_required";
+    pub const TOKEN_SCOPE: &str = "token_scope";
+}
+
+impl AppError {
+    pub fn unauthenticated(reason: &'static str, message: impl Into<String>) -> Self {
+        AppError::Unauthenticated { message: message.into(), reason: Some(reason) }
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        AppError::Forbidden { message: message.into(), capability: None, reason: None }
+    }
+
+    /// Refused for want of `capability` (a capability key, or `read` for a
+    /// token's read scope).
+    pub fn missing_capability(capability: &str, message: impl Into<String>) -> Self {
+        AppError::Forbidden { message: message.into(), capability: Some(capability.to_string()), reason: None }
+    }
+
+    /// Refused until the caller clears a gate; `reason` is one of the
+    /// [`reason`] constants.
+    pub fn gated(reason: &'static str, message: impl Into<String>) -> Self {
+        AppError::Forbidden { message: message.into(), capability: None, reason: Some(reason) }
+    }
+
+    pub fn not_found(entity: &'static str, message: impl Into<String>) -> Self {
+        AppError::NotFound { message: message.into(), entity: Some(entity), id: None }
+    }
+
+    pub fn validation(field: &'static str, message: impl Into<String>) -> Self {
+        AppError::Validation { message: message.into(), field: Some(field) }
+    }
+
+    /// A validation error that is not about one field.
+    pub fn invalid(message: impl Into<String>) -> Self {
+        AppError::Validation { message: message.into(), field: None }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::Conflict { message: message.into() }
+    }
+
+    pub fn integrity(message: impl Into<String>) -> Self {
+        AppError::Integrity { message: message.into() }
+    }
+
+    pub fn external(service: &'static str, message: impl Into<String>) -> Self {
+        AppError::ExternalService { message: message.into(), service }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal { message: message.into() }
+    }
+
+    /// Attach the id of the missing record to a `NotFound`; other variants
+    /// are returned unchanged.
+    pub fn with_id(self, id: impl Into<String>) -> Self {
+        match self {
+            AppError::NotFound { message, entity, .. } => AppError::NotFound { message, entity, id: Some(id.into()) },
+            other => other,
+        }
+    }
+
+    /// The stable machine code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Unauthenticated { .. } => "unauthenticated",
+            AppError::Forbidden { .. } => "forbidden",
+            AppError::NotFound { .. } => "not_found",
+            AppError::Validation { .. } => "validation",
+            AppError::Conflict { .. } => "conflict",
+            AppError::Integrity { .. } => "integrity",
+            AppError::ExternalService { .. } => "external_service",
+            AppError::Internal { .. } => "internal",
+        }
+    }
+
+    /// The English message, for logs and for clients without a translation.
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Unauthenticated { message, .. }
+            | AppError::Forbidden { message, .. }
+            | AppError::NotFound { message, .. }
+            | AppError::Validation { message, .. }
+            | AppError::Conflict { message }
+            | AppError::Integrity { message }
+            | AppError::ExternalService { message, .. }
+            | AppError::Internal { message } => message,
+        }
+    }
+
+    /// The code's parameters; absent ones are omitted.
+    pub fn params(&self) -> Map<String, Value> {
+        let mut params = Map::new();
+        let mut put = |key: &str, value: Option<&str>| {
+            if let Some(v) = value {
+                params.insert(key.to_string(), Value::String(v.to_string()));
+            }
+        };
+        match self {
+            AppError::Unauthenticated { reason, .. } => put("reason", *reason),
+            AppError::Forbidden { capability, reason, .. } => {
+                put("capability", capability.as_deref());
+                put("reason", *reason);
+            }
+            AppError::NotFound { entity, id, .. } => {
+                put("entity", *entity);
+                put("id", id.as_deref());
+            }
+            AppError::Validation { field, .. } => put("field", *field),
+            AppError::ExternalService { service, .. } => put("service", Some(service)),
+            AppError::Conflict { .. } | AppError::Integrity { .. } | AppError::Internal { .. } => {}
+        }
+        params
+    }
+
+    /// Classify an untyped error message. The wording rules are the ones the
+    /// local API used to pick a status code (WP-90); what matches none of
+    /// them is `Internal` — a refusal the user can fix is constructed as one.
+    pub fn from_message(message: String) -> Self {
+        const UNAUTHENTICATED: [(&str, &str); 4] = [
+            ("Session expired", reason::SESSION_EXPIRED),
+            ("Missing bearer token", reason::SESSION_EXPIRED),
+            ("Invalid username or password", reason::INVALID_CREDENTIALS),
+            ("Two-factor verification required", reason::MFA_REQUIRED),
+        ];
+        const GATES: [(&str, &str); 2] = [
+            ("A password change is required", reason::PASSWORD_CHANGE_REQUIRED),
+            ("Two-factor enrollment is required", reason::MFA_ENROLLMENT_REQUIRED),
+        ];
+        if let Some((_, r)) = UNAUTHENTICATED.iter().find(|(p, _)| message.starts_with(p)) {
+            return AppError::unauthenticated(r, message);
+        }
+        if let Some((_, r)) = GATES.iter().find(|(p, _)| message.starts_with(p)) {
+            return AppError::gated(r, message);
+        }
+        if message.starts_with("Insufficient permissions") || message.starts_with("Only ") {
+            return AppError::forbidden(message);
+        }
+        if message.to_lowercase().contains("not found") {
+            return AppError::NotFound { message, entity: None, id: None };
+        }
+        AppError::internal(message)
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AppError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", self.message())?;
+        s.serialize_field("params", &self.params())?;
+        s.end()
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::from_message(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::from_message(message.to_string())
+    }
+}
+
+impl From<AppError> for String {
+    fn from(e: AppError) -> Self {
+        match e {
+            AppError::Unauthenticated { message, .. }
+            | AppError::Forbidden { message, .. }
+            | AppError::NotFound { message, .. }
+            | AppError::Validation { message, .. }
+            | AppError::Conflict { message }
+            | AppError::Integrity { message }
+            | AppError::ExternalService { message, .. }
+            | AppError::Internal { message } => message,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        match &e {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound {
+                message: "Record not found".to_string(),
+                entity: None,
+                id: None,
+            },
+            rusqlite::Error::SqliteFailure(f, _)
+                if f.code == rusqlite::ErrorCode::ConstraintViolation && e.to_string().contains("UNIQUE") =>
+            {
+                AppError::conflict(format!("Database error: {}", e))
+            }
+            _ => AppError::internal(format!("Database error: {}", e)),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::internal(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::internal(e.to_string())
+    }
+}
+
+impl From<crate::db::DbError> for AppError {
+    fn from(e: crate::db::DbError) -> Self {
+        match e {
+            crate::db::DbError::Sqlite(inner) => inner.into(),
+            crate::db::DbError::Constraint(_) => AppError::conflict(e.to_string()),
+            other => AppError::internal(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_message_and_only_the_params_it_has() {
+        let e = AppError::validation("name", "Name is required");
+        assert_eq!(
+            serde_json::to_value(&e).unwrap(),
+            serde_json::json!({ "code": "validation", "message": "Name is required", "params": { "field": "name" } })
+        );
+        let e = AppError::not_found("specimen", "Specimen not found").with_id("s1");
+        assert_eq!(serde_json::to_value(&e).unwrap()["params"], serde_json::json!({ "entity": "specimen", "id": "s1" }));
+        assert_eq!(serde_json::to_value(AppError::conflict("taken")).unwrap()["params"], serde_json::json!({}));
+        assert_eq!(e.to_string(), "Specimen not found");
+        assert_eq!(String::from(e), "Specimen not found");
+    }
+
+    #[test]
+    fn legacy_messages_are_classified_by_their_wording() {
+        let cases = [
+            ("Session expired or invalid", "unauthenticated"),
+            ("Invalid username or password", "unauthenticated"),
+            ("Two-factor verification required.", "unauthenticated"),
+            ("A password change is required before continuing.", "forbidden"),
+            ("Two-factor enrollment is required before continuing.", "forbidden"),
+            ("Insufficient permissions — your role does not include \"x\" (x).", "forbidden"),
+            ("Only admins can grant the admin role", "forbidden"),
+            ("Specimen not found", "not_found"),
+            ("Failed to open backup file: denied", "internal"),
+            ("Name cannot be empty", "internal"),
+        ];
+        for (message, code) in cases {
+            assert_eq!(AppError::from(message).code(), code, "{}", message);
+        }
+        assert_eq!(
+            AppError::from("Two-factor enrollment is required before continuing.").params()["reason"],
+            reason::MFA_ENROLLMENT_REQUIRED
+        );
+    }
+
+    #[test]
+    fn sqlite_errors_map_to_not_found_conflict_or_internal() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (name TEXT UNIQUE); INSERT INTO t VALUES ('a');").unwrap();
+        let missing = conn.query_row("SELECT name FROM t WHERE name = 'b'", [], |r| r.get::<_, String>(0)).unwrap_err();
+        assert_eq!(AppError::from(missing).code(), "not_found");
+        let duplicate = conn.execute("INSERT INTO t VALUES ('a')", []).unwrap_err();
+        assert_eq!(AppError::from(duplicate).code(), "conflict");
+        let broken = conn.execute("INSERT INTO nowhere VALUES (1)", []).unwrap_err();
+        assert_eq!(AppError::from(broken).code(), "internal");
+    }
+}
@@ -16,6 +16,8 @@
 use rusqlite::Connection;
 use serde::Serialize;
 
+use crate::error::AppError;
+
 /// One failed integrity check.
 #[derive(Debug, Clone, Serialize)]
 pub struct IntegrityIssue {
@@ -53,17 +55,16 @@ struct OrphanCheck {
     example_sql: &'static str,
 }
 
-fn run_orphan_check(conn: &Connection, spec: &OrphanCheck) -> Result<Option<IntegrityIssue>, String> {
+fn run_orphan_check(conn: &Connection, spec: &OrphanCheck) -> Result<Option<IntegrityIssue>, AppError> {
     let count: i64 = conn
         .query_row(spec.count_sql, [], |r| r.get(0))
         .map_err(|e| format!("integrity check '{}' failed: {}", spec.check, e))?;
     if count == 0 {
         return Ok(None);
     }
-    let mut stmt = conn.prepare(spec.example_sql).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(spec.example_sql)?;
     let examples: Vec<String> = stmt
-        .query_map([], |r| r.get::<_, String>(0))
-        .map_err(|e| e.to_string())?
+        .query_map([], |r| r.get::<_, String>(0))?
         .filter_map(|r| r.ok())
         .collect();
     Ok(Some(IntegrityIssue {
@@ -151,7 +152,7 @@ const ORPHAN_CHECKS: &[OrphanCheck] = &[
 /// entries has genesis `chain_seq = 0` and max `chain_seq = N - 1`, so
 /// `COUNT(*) = MAX(chain_seq) + 1`. Any mismatch means a history row was
 /// removed — precisely the tamper the audit chain exists to make detectable.
-fn run_chain_gap_check(conn: &Connection) -> Result<Option<IntegrityIssue>, String> {
+fn run_chain_gap_check(conn: &Connection) -> Result<Option<IntegrityIssue>, AppError> {
     const COUNT_SQL: &str = "SELECT COUNT(*) FROM (\
         SELECT lineage_id FROM audit_log WHERE entry_hash IS NOT NULL \
         GROUP BY lineage_id HAVING COUNT(*) <> MAX(chain_seq) + 1)";
@@ -163,10 +164,9 @@ fn run_chain_gap_check(conn: &Connection) -> Result<Option<IntegrityIssue>, Stri
     if count == 0 {
         return Ok(None);
     }
-    let mut stmt = conn.prepare(EXAMPLE_SQL).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(EXAMPLE_SQL)?;
     let examples: Vec<String> = stmt
-        .query_map([], |r| r.get::<_, String>(0))
-        .map_err(|e| e.to_string())?
+        .query_map([], |r| r.get::<_, String>(0))?
         .filter_map(|r| r.ok())
         .collect();
     Ok(Some(IntegrityIssue {
@@ -206,7 +206,7 @@ fn run_chain_gap_check(conn: &Connection) -> Result<Option<IntegrityIssue>, Stri
 ///
 /// Search returning wrong rows is a provenance problem in a regulated lab, so
 /// this is `critical`.
-fn run_search_index_check(conn: &Connection) -> Result<Option<IntegrityIssue>, String> {
+fn run_search_index_check(conn: &Connection) -> Result<Option<IntegrityIssue>, AppError> {
     // The table only exists from migration 054 onward; a database mid-upgrade
     // is not an integrity failure.
     let exists: i64 = conn
@@ -259,7 +259,7 @@ fn run_search_index_check(conn: &Connection) -> Result<Option<IntegrityIssue>, S
 
 /// Run every integrity check and return the aggregated report, issues sorted
 /// most-severe first.
-pub fn run_integrity_check(conn: &Connection) -> Result<IntegrityReport, String> {
+pub fn run_integrity_check(conn: &Connection) -> Result<IntegrityReport, AppError> {
     let mut issues: Vec<IntegrityIssue> = Vec::new();
     for spec in ORPHAN_CHECKS {
         if let Some(issue) = run_orphan_check(conn, spec)? {
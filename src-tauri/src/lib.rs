@@ -1,11 +1,14 @@
 pub mod ai;
 pub mod anchoring;
+pub mod api;
 pub mod auth;
+pub mod cli;
 pub mod cloud;
 pub mod compliance_export;
 pub mod compliance_rules;
 pub mod coordination;
 pub mod db;
+pub mod error;
 pub mod integrity;
 pub mod models;
 pub mod monitoring;
@@ -14,6 +17,7 @@ pub mod plugins;
 pub mod reg_submission;
 pub mod registry;
 pub mod signed_ledger;
+pub mod stats;
 
 #[cfg(feature = "tauri-commands")]
 pub mod commands;
@@ -26,14 +30,13 @@ pub struct AppState {
     // WP-63: in-memory materialized dashboard cache (never persisted — see
     // db::dashboard for the TTL/invalidation logic).
     pub dashboard_cache: Mutex<Option<db::dashboard::DashboardCacheEntry>>,
-    /// Failed-login counters backing the brute-force lockout. In-memory and
-    /// per-process by design (see `auth::LoginThrottle`) — deliberately not
-    /// persisted, so a failed guess never causes a database write.
-    pub login_throttle: auth::LoginThrottle,
     /// Set when the real database could not be opened and the app fell back to
     /// an in-memory one. Holds the message the UI must show before the user
     /// enters anything — see `run()`.
     pub degraded_reason: Option<String>,
+    /// WP-90: the local REST API while it runs. Replaced by
+    /// `commands::api::set_api_config`; dropping it stops the listener.
+    pub api_server: Mutex<Option<api::server::ApiServer>>,
 }
 
 impl AppState {
@@ -94,8 +97,8 @@ pub fn run() {
     let state = AppState {
         db: Mutex::new(db),
         dashboard_cache: Mutex::new(None),
-        login_throttle: auth::LoginThrottle::default(),
         degraded_reason,
+        api_server: Mutex::new(None),
     };
 
     tauri::Builder::default()
@@ -113,13 +116,73 @@ pub fn run() {
             commands::auth::update_user_role,
             commands::auth::change_password,
             commands::auth::logout,
+            commands::auth::verify_login_mfa,
+            commands::auth::get_mfa_status,
+            commands::auth::begin_totp_enrollment,
+            commands::auth::confirm_totp_enrollment,
+            commands::auth::disable_totp,
+            commands::auth::regenerate_recovery_codes,
+            commands::auth::list_mfa_policy,
+            commands::auth::set_mfa_policy,
+            commands::auth::list_sessions,
+            commands::auth::revoke_session,
+            commands::auth::revoke_user_sessions,
+            commands::auth::set_user_active,
+            commands::auth::list_session_policy,
+            commands::auth::set_session_policy,
+            commands::auth::get_auth_policy,
+            commands::auth::set_auth_policy,
+            commands::auth::list_locked_accounts,
+            commands::auth::unlock_account,
+            commands::auth::reset_user_totp,
+            // WP-90: local REST API
+            commands::api::get_api_status,
+            commands::api::set_api_config,
+            commands::api_tokens::list_api_tokens,
+            commands::api_tokens::create_service_account,
+            commands::api_tokens::create_api_token,
+            commands::api_tokens::revoke_api_token,
+            commands::accession::list_accession_templates,
+            commands::accession::save_accession_template,
+            commands::accession::delete_accession_template,
+            commands::accession::preview_accession_template,
+            commands::custom_fields::list_custom_fields,
+            commands::custom_fields::save_custom_field,
+            commands::custom_fields::retire_custom_field,
+            // WP-97: specimen filters and saved searches
+            commands::saved_searches::list_saved_searches,
+            commands::saved_searches::save_saved_search,
+            commands::saved_searches::delete_saved_search,
+            commands::saved_searches::specimen_filter_fields,
+            // WP-98: stage transition rules
+            commands::stage_transitions::list_stage_transitions,
+            commands::stage_transitions::save_stage_transition,
+            commands::stage_transitions::delete_stage_transition,
+            // WP-86: custom roles and capabilities
+            commands::auth::set_user_access_expiry,
+            commands::roles::get_my_capabilities,
+            commands::roles::list_capabilities,
+            commands::roles::list_roles,
+            commands::roles::create_role,
+            commands::roles::update_role,
+            commands::roles::delete_role,
+            // WP-85: LDAP / Active Directory authentication
+            commands::directory::get_ldap_config,
+            commands::directory::set_ldap_config,
+            commands::directory::test_ldap_connection,
+            commands::directory::list_ldap_group_mappings,
+            commands::directory::set_ldap_group_mapping,
+            commands::directory::delete_ldap_group_mapping,
+            commands::directory::sync_directory_accounts,
             // Specimens
             commands::specimens::list_specimens,
             commands::specimens::get_specimen,
             commands::specimens::create_specimen,
+            commands::specimens::initiate_specimen_batch,
             commands::specimens::update_specimen,
             commands::specimens::delete_specimen,
             commands::specimens::search_specimens,
+            commands::specimens::list_matching_specimen_ids,
             commands::specimens::get_specimen_stats,
             commands::specimens::bulk_archive_specimens,
             commands::specimens::bulk_update_location,
@@ -127,6 +190,24 @@ pub fn run() {
             commands::specimens::split_specimen,
             commands::specimens::preview_split_accessions,
             commands::specimens::get_specimen_family,
+            // WP-99: merging duplicate specimens
+            commands::specimens::merge_specimens,
+            commands::specimens::list_specimen_merges,
+            // WP-100: restoring archived and deleted specimens
+            commands::specimens::list_restorable_specimens,
+            commands::specimens::restore_specimens,
+            // WP-101: lineage graph exports and metrics
+            commands::lineage::get_lineage_metrics,
+            commands::lineage::export_lineage,
+            // WP-102: treatment trials
+            commands::trials::list_trials,
+            commands::trials::get_trial,
+            commands::trials::save_trial,
+            commands::trials::delete_trial,
+            commands::trials::assign_trial_specimens,
+            commands::trials::set_trial_status,
+            commands::trials::record_trial_observations,
+            commands::trials::get_trial_analysis,
             // Media
             commands::media::list_media,
             commands::media::get_media_batch,
@@ -332,6 +413,8 @@ pub fn run() {
             commands::analytics::get_strain_performance,
             commands::analytics::get_cryo_utilization,
             commands::analytics::get_technician_activity,
+            // WP-103: contamination root-cause analysis
+            commands::analytics::get_contamination_sources,
             commands::analytics::get_analytics_kpi_summary,
             commands::analytics::get_analytics_panel_config,
             commands::analytics::set_analytics_panel_config,
@@ -377,11 +460,25 @@ pub fn run() {
             commands::anchoring::record_checkpoint_anchor,
             commands::anchoring::verify_checkpoint_anchor,
             commands::anchoring::list_checkpoint_anchors,
+            commands::anchoring::get_anchor_node_config,
+            commands::anchoring::set_anchor_node_config,
+            commands::anchoring::test_anchor_node,
+            commands::anchoring::broadcast_checkpoint_anchor,
+            commands::anchoring::poll_checkpoint_anchors,
+            commands::anchoring::capture_anchor_spv_proof,
+            commands::anchoring::verify_anchor_spv_proof,
             // Signed-event ledger — Trust Layer Phase 3 (WP-67)
             commands::signed_events::get_user_signing_public_key,
             commands::signed_events::record_signed_event,
             commands::signed_events::list_signed_events,
             commands::signed_events::verify_signed_event_ledger,
+            commands::signed_events::list_signable_actions,
+            commands::signed_events::sign_record,
+            commands::signed_events::list_electronic_signatures,
+            commands::signed_events::list_pending_witness,
+            commands::signed_events::countersign_event,
+            commands::signed_events::list_witness_policies,
+            commands::signed_events::save_witness_policy,
             // Regulatory submission pipeline (WP-68)
             commands::reg_submission::evaluate_submission_readiness,
             commands::reg_submission::create_submission,
@@ -424,6 +521,9 @@ pub fn run() {
             db.seed_defaults().map_err(|e| format!("Seed error: {}", e))?;
             drop(db);
 
+            // WP-90: the local REST API, when an admin has switched it on.
+            commands::api::start_configured(app.handle());
+
             // WP-52: background scheduler. Sleeps for the configured interval
             // (default 15 minutes, `notification_check_interval_minutes` in
             // app_settings) before each check, so restarting the app during
@@ -460,13 +560,35 @@ pub fn run() {
                     // Bind the guard: inlining `state.db()` into the call would
                     // create a temporary that outlives `state`.
                     let db = state.db();
-                    match commands::reg_submission::monitor(&db.conn) {
+                    match commands::reg_submission::monitor(&db.conn, None) {
                         Ok(r) if r.auto_generated > 0 => {
                             eprintln!("Submission monitor: auto-generated {} package(s).", r.auto_generated);
                         }
                         Ok(_) => {}
                         Err(e) => eprintln!("Submission monitor failed: {}", e),
                     }
+
+                    // WP-82: poll the lab node for anchor confirmations, when
+                    // node broadcasting is enabled. `connect` errors when it is
+                    // not, which is the normal case and not worth logging.
+                    if let Ok(rpc) = anchoring::node_rpc::connect(&db.conn) {
+                        if let Err(e) = commands::anchoring::poll_node_anchors(&db.conn, &rpc, None) {
+                            eprintln!("Anchor confirmation poll failed: {}", e);
+                        }
+                    }
+
+                    // WP-85: deactivate accounts disabled or removed in the
+                    // directory, when directory login is enabled.
+                    if let Ok(Some(dir)) = auth::ldap::configured_directory(&db.conn) {
+                        let default_role = dir.config().default_role.clone();
+                        match auth::ldap::sync_accounts(&db.conn, &dir, default_role.as_deref(), None) {
+                            Ok(r) if r.deactivated > 0 => {
+                                eprintln!("Directory sync: deactivated {} account(s).", r.deactivated);
+                            }
+                            Ok(_) => {}
+                            Err(e) => eprintln!("Directory sync failed: {}", e),
+                        }
+                    }
                 }
             });
 
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::anchoring::spv::AnchorSpvProof;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEntry {
     pub id: String,
@@ -107,6 +109,10 @@ pub struct PortableMerkleProof {
     pub checkpoint: ProofCheckpointMeta,
     /// Ordered by chain_seq ascending.
     pub entries: Vec<ProofEntry>,
+    /// WP-83: SPV proofs for each confirmed on-chain anchor of this checkpoint.
+    /// Absent from proofs of unanchored checkpoints and from older exports.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anchors: Vec<AnchorSpvProof>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -119,6 +125,9 @@ pub struct VerifyProofResult {
     pub failure_reason: Option<String>,
     /// chain_seq of the first entry where a failure was detected.
     pub failed_seq: Option<i64>,
+    /// WP-83: number of on-chain anchors whose SPV proof verified.
+    #[serde(default)]
+    pub anchors_verified: i64,
 }
 
 /// Configuration for automatic checkpoint creation.
@@ -1,3 +1,4 @@
+use crate::db::custom_fields::CustomValues;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,9 +68,14 @@ pub struct Specimen {
     /// admin switches the active profile. Reads are scoped to the active
     /// profile, so this is what keeps the three lab types from commingling.
     pub lab_profile: String,
+    /// WP-96: values of the lab's custom specimen fields, keyed by field key.
+    pub custom_fields: CustomValues,
+    /// WP-99: set on the tombstone of a specimen merged into another; the id
+    /// of the record that now holds its history.
+    pub merged_into: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct CreateSpecimenRequest {
     pub species_id: String,
     pub project_id: Option<String>,
@@ -96,6 +102,9 @@ pub struct CreateSpecimenRequest {
     pub strain_id: Option<String>,
     // ── WP-42 ────────────────────────────────────────────────────────────────
     pub origin_type: Option<String>,
+    // ── WP-96 ────────────────────────────────────────────────────────────────
+    #[serde(default)]
+    pub custom_fields: CustomValues,
 }
 
 #[derive(Debug, Deserialize)]
@@ -121,9 +130,13 @@ pub struct UpdateSpecimenRequest {
     // ── WP-42 ────────────────────────────────────────────────────────────────
     pub origin_type: Option<String>,
     pub is_best_performer: Option<bool>,
+    // ── WP-96 ────────────────────────────────────────────────────────────────
+    /// Custom values to set; a null value clears that field, and fields not
+    /// named are left as they are.
+    pub custom_fields: Option<CustomValues>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct SpecimenSearchParams {
     pub query: Option<String>,
     pub species_id: Option<String>,
@@ -139,6 +152,13 @@ pub struct SpecimenSearchParams {
     /// taxonomy navigator's strain quick-panel relies on this; without the
     /// filter the search silently returned every specimen in the lab.
     pub strain_id: Option<String>,
+    /// WP-96: filters on custom fields, keyed by field key
+    /// (see `db::custom_fields::push_search_conditions`).
+    #[serde(default)]
+    pub custom_fields: CustomValues,
+    /// WP-97: a filter expression (see `db::specimen_query`), ANDed with
+    /// the fixed filters above.
+    pub filter: Option<String>,
 }
 
 // WP-63: Clone is needed so a computed snapshot can be stored in the
@@ -236,3 +256,64 @@ pub struct SplitChildResult {
     pub id: String,
     pub accession_number: String,
 }
+
+// ── WP-94: batch initiation ──────────────────────────────────────────────────
+
+/// Wells of a culture plate, filled row by row: A1, A2, … then B1, ….
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct PlateLayout {
+    pub rows: u32,
+    pub columns: u32,
+}
+
+/// Fields one specimen of a batch sets differently from the template.
+/// `position` is 1-based, in accession order. Species, stage and initiation
+/// date are not overridable: they define the batch's accession range.
+#[derive(Debug, Default, Deserialize)]
+pub struct BatchRowOverride {
+    pub position: u32,
+    pub location: Option<String>,
+    pub location_details: Option<String>,
+    pub provenance: Option<String>,
+    pub source_plant: Option<String>,
+    pub health_status: Option<String>,
+    pub environmental_notes: Option<String>,
+    pub notes: Option<String>,
+    pub employee_id: Option<String>,
+}
+
+/// Request payload for initiating many specimens from one template. Exactly one
+/// of `count` and `plate` sizes the batch.
+#[derive(Debug, Deserialize)]
+pub struct InitiateBatchRequest {
+    pub template: CreateSpecimenRequest,
+    pub count: Option<u32>,
+    pub plate: Option<PlateLayout>,
+    #[serde(default)]
+    pub overrides: Vec<BatchRowOverride>,
+}
+
+/// One printable label of an initiated batch.
+#[derive(Debug, Serialize)]
+pub struct BatchLabel {
+    pub position: u32,
+    /// Plate well, e.g. `B3`, when the batch was laid out on a plate.
+    pub well: Option<String>,
+    pub specimen_id: String,
+    pub accession_number: String,
+    pub qr_code_data: String,
+    pub species_code: String,
+    pub stage: String,
+    pub initiation_date: String,
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InitiationBatch {
+    /// Recorded in each specimen's genesis audit entry, so the batch can be
+    /// traced back from any one of its members.
+    pub batch_id: String,
+    pub first_accession: String,
+    pub last_accession: String,
+    pub labels: Vec<BatchLabel>,
+}
@@ -1,3 +1,4 @@
+use crate::db::custom_fields::CustomValues;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +55,10 @@ pub struct Subculture {
     pub colonization_pct: Option<f64>,
     /// Categorical contaminant label (e.g. "trich", "wet_rot", "cobweb"). Set when contamination_flag is true.
     pub contaminant_type: Option<String>,
+    /// WP-96: values of the lab's custom subculture fields, keyed by field key.
+    pub custom_fields: CustomValues,
+    /// WP-103: the laminar-flow hood or workstation the passage was done at.
+    pub hood: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -92,6 +97,12 @@ pub struct CreateSubcultureRequest {
     // ── WP-41: mycology colonization & contaminant tracking ─────────────────
     pub colonization_pct: Option<f64>,
     pub contaminant_type: Option<String>,
+    // ── WP-96 ────────────────────────────────────────────────────────────────
+    #[serde(default)]
+    pub custom_fields: CustomValues,
+    // ── WP-103 ───────────────────────────────────────────────────────────────
+    #[serde(default)]
+    pub hood: Option<String>,
 }
 
 /// Payload for the "Record Death & Archive" terminal event.
@@ -116,6 +127,9 @@ pub struct UpdateSubcultureRequest {
     // ── WP-41: mycology colonization & contaminant tracking ─────────────────
     pub colonization_pct: Option<f64>,
     pub contaminant_type: Option<String>,
+    // ── WP-96 ────────────────────────────────────────────────────────────────
+    /// Custom values to set; a null value clears that field.
+    pub custom_fields: Option<CustomValues>,
 }
 
 /// Lab-wide contamination statistics.
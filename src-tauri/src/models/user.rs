@@ -13,15 +13,32 @@ pub struct User {
     pub must_change_password: bool,
     pub created_at: String,
     pub updated_at: String,
+    /// WP-85: `local` (bcrypt password) or `ldap` (directory bind).
+    pub auth_source: String,
+    /// WP-86: when set, the account cannot sign in or use a session after this
+    /// UTC time (`YYYY-MM-DD HH:MM:SS`).
+    pub access_expires_at: Option<String>,
+    /// WP-92: set when the caller authenticated with an API token rather than
+    /// a session; `auth::require_capability` then checks the token's scope too.
+    #[serde(skip)]
+    pub token_scope: Option<crate::auth::api_tokens::TokenScope>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
+/// A user's role: one of the four built-in roles or, since WP-86, an
+/// admin-defined role from the `roles` table.
+///
+/// What a role may do is decided by its capabilities (`auth::roles`), checked
+/// through `auth::require_capability` — never by matching on the variant. The
+/// built-ins keep variants because a few identity rules name them: the local
+/// admin break-glass login and the last-admin guard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub enum UserRole {
     Admin,
     Supervisor,
     Tech,
     Guest,
+    Custom(String),
 }
 
 impl UserRole {
@@ -31,22 +48,19 @@ impl UserRole {
             UserRole::Supervisor => "supervisor",
             UserRole::Tech => "tech",
             UserRole::Guest => "guest",
+            UserRole::Custom(name) => name,
         }
     }
 
-    pub fn can_write(&self) -> bool {
-        matches!(self, UserRole::Admin | UserRole::Supervisor | UserRole::Tech)
-    }
-
-    pub fn can_manage(&self) -> bool {
-        matches!(self, UserRole::Admin | UserRole::Supervisor)
-    }
-
+    /// The built-in admin role, which holds every capability.
     pub fn is_admin(&self) -> bool {
         matches!(self, UserRole::Admin)
     }
 }
 
+/// Parses any well-formed role name: 2–32 lowercase ASCII letters, digits,
+/// `_` or `-`, starting with a letter. Whether a custom name exists is a
+/// database question — see `auth::roles::existing_role`.
 impl std::str::FromStr for UserRole {
     type Err = ();
 
@@ -56,11 +70,34 @@ impl std::str::FromStr for UserRole {
             "supervisor" => Ok(UserRole::Supervisor),
             "tech" => Ok(UserRole::Tech),
             "guest" => Ok(UserRole::Guest),
-            _ => Err(()),
+            _ => {
+                let well_formed = (2..=32).contains(&s.len())
+                    && s.starts_with(|c: char| c.is_ascii_lowercase())
+                    && s.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+                if well_formed {
+                    Ok(UserRole::Custom(s.to_string()))
+                } else {
+                    Err(())
+                }
+            }
         }
     }
 }
 
+impl From<UserRole> for String {
+    fn from(role: UserRole) -> String {
+        role.as_str().to_string()
+    }
+}
+
+impl TryFrom<String> for UserRole {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse().map_err(|_| format!("Invalid role '{}'", s))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserPublic {
     pub id: String,
@@ -69,6 +106,10 @@ pub struct UserPublic {
     pub email: Option<String>,
     pub role: String,
     pub is_active: bool,
+    /// WP-85: `local` or `ldap`.
+    pub auth_source: String,
+    /// WP-86: end of a time-boxed account's access, if any.
+    pub access_expires_at: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -91,4 +132,8 @@ pub struct LoginResponse {
     pub token: String,
     pub user: UserPublic,
     pub must_change_password: bool,
+    /// WP-84: the token is pending until `verify_login_mfa` accepts a code.
+    pub mfa_required: bool,
+    /// WP-84: the user's role requires 2FA and they have not enrolled yet.
+    pub mfa_enrollment_required: bool,
 }
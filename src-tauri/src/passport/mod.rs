@@ -37,6 +37,7 @@ use sha2::{Digest, Sha256};
 
 use crate::compliance_export::signing;
 use crate::db::queries::{build_merkle_root, compute_entry_hash};
+use crate::error::AppError;
 
 pub mod store;
 
@@ -220,7 +221,7 @@ pub fn assemble_and_sign(
     provenance: Vec<PassportAuditEntry>,
     merkle_anchor: Option<PassportMerkleAnchor>,
     private_key_b64: &str,
-) -> Result<SpecimenPassport, String> {
+) -> Result<SpecimenPassport, AppError> {
     let mut passport = SpecimenPassport {
         format: PASSPORT_FORMAT.to_string(),
         version: PASSPORT_VERSION.to_string(),
@@ -435,8 +436,8 @@ pub fn verify_passport(p: &SpecimenPassport) -> PassportVerification {
 }
 
 /// Parse a passport from JSON, returning a clear error on malformed input.
-pub fn parse_passport(json: &str) -> Result<SpecimenPassport, String> {
-    serde_json::from_str(json).map_err(|e| format!("Invalid passport JSON: {}", e))
+pub fn parse_passport(json: &str) -> Result<SpecimenPassport, AppError> {
+    serde_json::from_str(json).map_err(|e| AppError::validation("passport_json", format!("Invalid passport JSON: {}", e)))
 }
 
 #[cfg(test)]
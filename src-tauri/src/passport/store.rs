@@ -12,6 +12,7 @@ use super::{
 };
 use crate::compliance_export::load_or_create_lab_signing_key;
 use crate::db::queries::{audit_canonical_bytes, build_merkle_root, log_audit};
+use crate::error::AppError;
 
 /// Default issuer lab name used until an operator sets one in Settings.
 pub const DEFAULT_LAB_NAME: &str = "Unnamed SteloPTC Lab";
@@ -56,29 +57,28 @@ pub fn read_lab_name(conn: &Connection) -> String {
 }
 
 /// Persist this lab's issuer name.
-pub fn set_lab_name(conn: &Connection, name: &str) -> Result<(), String> {
+pub fn set_lab_name(conn: &Connection, name: &str) -> Result<(), AppError> {
     let trimmed = name.trim();
     if trimmed.is_empty() {
-        return Err("Lab name cannot be empty.".to_string());
+        return Err(AppError::validation("name", "Lab name cannot be empty."));
     }
     conn.execute(
         "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES ('lab_name', ?1, ?2)",
         params![trimmed, now_iso()],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(())
 }
 
 /// This lab's public issuer identity — the name plus the lab-wide Ed25519 public
 /// key (the same WP-60 export key), generating the key on first use. An operator
 /// shares this out-of-band so partner labs can verify the passports it issues.
-pub fn get_lab_identity(conn: &Connection) -> Result<IssuerIdentity, String> {
+pub fn get_lab_identity(conn: &Connection) -> Result<IssuerIdentity, AppError> {
     let (public_key, _) = load_or_create_lab_signing_key(conn)?;
     Ok(IssuerIdentity { lab_name: read_lab_name(conn), public_key })
 }
 
 /// The full signing keypair for issuing (identity + private key).
-fn load_signing_identity(conn: &Connection) -> Result<(IssuerIdentity, String), String> {
+fn load_signing_identity(conn: &Connection) -> Result<(IssuerIdentity, String), AppError> {
     let (public_key, private_key) = load_or_create_lab_signing_key(conn)?;
     Ok((IssuerIdentity { lab_name: read_lab_name(conn), public_key }, private_key))
 }
@@ -87,7 +87,7 @@ fn load_signing_identity(conn: &Connection) -> Result<(IssuerIdentity, String),
 /// audit-log row for the specimen's lineage, in ascending `chain_seq`, in the
 /// exact shape a verifier needs to recompute each hash. Mirrors
 /// `commands::audit::export_audit_proof`'s entry construction.
-pub fn gather_provenance(conn: &Connection, specimen_id: &str) -> Result<Vec<PassportAuditEntry>, String> {
+pub fn gather_provenance(conn: &Connection, specimen_id: &str) -> Result<Vec<PassportAuditEntry>, AppError> {
     struct Row {
         chain_seq: i64,
         user_id: Option<String>,
@@ -105,8 +105,7 @@ pub fn gather_provenance(conn: &Connection, specimen_id: &str) -> Result<Vec<Pas
              FROM audit_log \
              WHERE lineage_id = ?1 AND entry_hash IS NOT NULL \
              ORDER BY chain_seq ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let rows: Vec<Row> = stmt
         .query_map(params![specimen_id], |r| {
             Ok(Row {
@@ -120,8 +119,7 @@ pub fn gather_provenance(conn: &Connection, specimen_id: &str) -> Result<Vec<Pas
                 prev_hash: r.get(7)?,
                 entry_hash: r.get(8)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -182,7 +180,7 @@ fn gather_merkle_anchor(conn: &Connection, specimen_id: &str, entries: &[Passpor
 }
 
 /// Build the specimen identity subset for a passport from the `specimens` table.
-fn load_passport_specimen(conn: &Connection, specimen_id: &str) -> Result<PassportSpecimen, String> {
+fn load_passport_specimen(conn: &Connection, specimen_id: &str) -> Result<PassportSpecimen, AppError> {
     conn.query_row(
         "SELECT s.id, s.accession_number, \
                 sp.genus || ' ' || sp.species_name AS scientific_name, \
@@ -204,19 +202,32 @@ fn load_passport_specimen(conn: &Connection, specimen_id: &str) -> Result<Passpo
             })
         },
     )
-    .map_err(|_| format!("Specimen '{}' not found.", specimen_id))
+    .map_err(|_| AppError::not_found("specimen", format!("Specimen '{}' not found.", specimen_id)).with_id(specimen_id))
 }
 
 /// Issue a signed passport for a local specimen, record it (direction `issued`),
 /// and return the full document.
-pub fn issue_passport(conn: &Connection, specimen_id: &str, created_by: Option<&str>) -> Result<SpecimenPassport, String> {
+///
+/// WP-87: when the issuer's role cannot see the specimen's provenance, the
+/// note is left out of the document rather than replaced by the restricted
+/// marker — the passport is signed and the receiving lab keeps it, so the
+/// placeholder would become someone else's data.
+pub fn issue_passport(
+    conn: &Connection,
+    specimen_id: &str,
+    created_by: Option<&str>,
+    provenance_visible: bool,
+) -> Result<SpecimenPassport, AppError> {
     let (issuer, private_key) = load_signing_identity(conn)?;
-    let specimen = load_passport_specimen(conn, specimen_id)?;
+    let mut specimen = load_passport_specimen(conn, specimen_id)?;
+    if !provenance_visible {
+        specimen.provenance_note = None;
+    }
     let provenance = gather_provenance(conn, specimen_id)?;
     if provenance.is_empty() {
-        return Err(
-            "This specimen has no hashed provenance entries to attest — cannot issue a passport.".to_string(),
-        );
+        return Err(AppError::invalid(
+            "This specimen has no hashed provenance entries to attest — cannot issue a passport.",
+        ));
     }
     let anchor = gather_merkle_anchor(conn, specimen_id, &provenance);
 
@@ -230,7 +241,7 @@ pub fn issue_passport(conn: &Connection, specimen_id: &str, created_by: Option<&
         &private_key,
     )?;
 
-    let passport_json = serde_json::to_string_pretty(&passport).map_err(|e| e.to_string())?;
+    let passport_json = serde_json::to_string_pretty(&passport)?;
     conn.execute(
         "INSERT INTO specimen_passports \
          (id, passport_id, direction, specimen_id, issuer_lab, issuer_public_key, subject_accession, \
@@ -250,14 +261,13 @@ pub fn issue_passport(conn: &Connection, specimen_id: &str, created_by: Option<&
             created_by,
             passport.issued_at,
         ],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     Ok(passport)
 }
 
 /// Verify a passport JSON with no side effects — pure verification, no import.
-pub fn verify_passport_json(json: &str) -> Result<PassportVerification, String> {
+pub fn verify_passport_json(json: &str) -> Result<PassportVerification, AppError> {
     let passport = parse_passport(json)?;
     Ok(verify_passport(&passport))
 }
@@ -265,11 +275,11 @@ pub fn verify_passport_json(json: &str) -> Result<PassportVerification, String>
 /// Import a received passport: verify it, refuse an invalid or duplicate one, then
 /// fold it into this lab's own audit chain (a `passport_imported` entry that
 /// commits to the passport's content hash) and record it (direction `imported`).
-pub fn import_passport(conn: &Connection, json: &str, imported_by: Option<&str>) -> Result<ImportPassportResult, String> {
+pub fn import_passport(conn: &Connection, json: &str, imported_by: Option<&str>) -> Result<ImportPassportResult, AppError> {
     let passport = parse_passport(json)?;
     let verification = verify_passport(&passport);
     if !verification.verified {
-        return Err(format!("Refusing to import an unverifiable passport: {}", verification.message));
+        return Err(AppError::integrity(format!("Refusing to import an unverifiable passport: {}", verification.message)));
     }
 
     // Do not import the same passport twice.
@@ -278,19 +288,18 @@ pub fn import_passport(conn: &Connection, json: &str, imported_by: Option<&str>)
             "SELECT COUNT(*) FROM specimen_passports WHERE direction = 'imported' AND passport_id = ?1",
             params![passport.passport_id],
             |r| r.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     if already > 0 {
-        return Err(format!(
+        return Err(AppError::conflict(format!(
             "Passport '{}' (accession {}) has already been imported.",
             passport.passport_id, passport.specimen.accession_number
-        ));
+        )));
     }
 
     // Both writes below run inside a single transaction: without it, a failure of
     // the register INSERT would leave a committed `passport_imported` audit-chain
     // entry attesting an import that never landed in the register.
-    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction()?;
 
     // Fold the import into this lab's own tamper-evident audit chain. The entry's
     // entity_id is the passport id (so it starts its own single-entry lineage);
@@ -310,8 +319,7 @@ pub fn import_passport(conn: &Connection, json: &str, imported_by: Option<&str>)
         None,
         Some(&passport.content_hash),
         Some(&details),
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     // Recover the id of the audit row we just wrote for the durable link.
     let audit_entry_id: Option<String> = tx
@@ -343,10 +351,9 @@ pub fn import_passport(conn: &Connection, json: &str, imported_by: Option<&str>)
             imported_by,
             now_iso(),
         ],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
-    tx.commit().map_err(|e| e.to_string())?;
+    tx.commit()?;
 
     Ok(ImportPassportResult {
         imported: true,
@@ -358,7 +365,7 @@ pub fn import_passport(conn: &Connection, json: &str, imported_by: Option<&str>)
 }
 
 /// List passport register rows, newest first, optionally filtered by direction.
-pub fn list_passports(conn: &Connection, direction: Option<&str>) -> Result<Vec<PassportRecord>, String> {
+pub fn list_passports(conn: &Connection, direction: Option<&str>) -> Result<Vec<PassportRecord>, AppError> {
     let cols = "id, passport_id, direction, specimen_id, issuer_lab, issuer_public_key, subject_accession, \
                 subject_scientific_name, content_hash, entry_count, verified, created_at";
     let map = |r: &rusqlite::Row| -> rusqlite::Result<PassportRecord> {
@@ -383,22 +390,18 @@ pub fn list_passports(conn: &Connection, direction: Option<&str>) -> Result<Vec<
                 .prepare(&format!(
                     "SELECT {} FROM specimen_passports WHERE direction = ?1 ORDER BY created_at DESC",
                     cols
-                ))
-                .map_err(|e| e.to_string())?;
+                ))?;
             let rows = stmt
-                .query_map(params![dir], map)
-                .map_err(|e| e.to_string())?
+                .query_map(params![dir], map)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
         }
         None => {
             let mut stmt = conn
-                .prepare(&format!("SELECT {} FROM specimen_passports ORDER BY created_at DESC", cols))
-                .map_err(|e| e.to_string())?;
+                .prepare(&format!("SELECT {} FROM specimen_passports ORDER BY created_at DESC", cols))?;
             let rows = stmt
-                .query_map([], map)
-                .map_err(|e| e.to_string())?
+                .query_map([], map)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
@@ -407,13 +410,27 @@ pub fn list_passports(conn: &Connection, direction: Option<&str>) -> Result<Vec<
 }
 
 /// Fetch the full stored passport JSON for one register row (for re-export).
-pub fn get_passport_json(conn: &Connection, row_id: &str) -> Result<String, String> {
-    conn.query_row(
-        "SELECT passport_json FROM specimen_passports WHERE id = ?1",
-        params![row_id],
-        |r| r.get(0),
-    )
-    .map_err(|_| format!("Passport record '{}' not found.", row_id))
+///
+/// A stored passport is signed, so it cannot be masked: when it carries a
+/// provenance note and the caller's role cannot see provenance (WP-87), it is
+/// refused instead.
+pub fn get_passport_json(conn: &Connection, row_id: &str, provenance_visible: bool) -> Result<String, AppError> {
+    let json: String = conn
+        .query_row(
+            "SELECT passport_json FROM specimen_passports WHERE id = ?1",
+            params![row_id],
+            |r| r.get(0),
+        )
+        .map_err(|_| AppError::not_found("specimen_passport", format!("Passport record '{}' not found.", row_id)).with_id(row_id))?;
+    if !provenance_visible {
+        let document: serde_json::Value = serde_json::from_str(&json)?;
+        if document["specimen"]["provenance_note"].as_str().is_some_and(|note| !note.is_empty()) {
+            return Err(AppError::forbidden(
+                "This passport includes the specimen's provenance, which is restricted for your role.",
+            ));
+        }
+    }
+    Ok(json)
 }
 
 #[cfg(test)]
@@ -458,7 +475,7 @@ mod tests {
     fn issue_then_verify_round_trips() {
         let conn = test_db();
         seed_specimen(&conn, "spec1", "2026-01-01-CIT-SIN-001");
-        let passport = issue_passport(&conn, "spec1", Some("u1")).unwrap();
+        let passport = issue_passport(&conn, "spec1", Some("u1"), true).unwrap();
         assert_eq!(passport.specimen.accession_number, "2026-01-01-CIT-SIN-001");
         assert_eq!(passport.specimen.scientific_name.as_deref(), Some("Citrus sinensis"));
         assert!(!passport.provenance.is_empty());
@@ -479,7 +496,7 @@ mod tests {
             [],
         )
         .unwrap();
-        assert!(issue_passport(&conn, "bare", Some("u1")).is_err());
+        assert!(issue_passport(&conn, "bare", Some("u1"), true).is_err());
     }
 
     #[test]
@@ -487,7 +504,7 @@ mod tests {
         // "Origin lab" issues; a fresh "receiving lab" DB imports the JSON.
         let origin = test_db();
         seed_specimen(&origin, "spec1", "2026-01-01-CIT-SIN-001");
-        let passport = issue_passport(&origin, "spec1", Some("u1")).unwrap();
+        let passport = issue_passport(&origin, "spec1", Some("u1"), true).unwrap();
         let json = serde_json::to_string_pretty(&passport).unwrap();
 
         let receiver = test_db();
@@ -512,7 +529,7 @@ mod tests {
     fn duplicate_import_is_rejected() {
         let origin = test_db();
         seed_specimen(&origin, "spec1", "2026-01-01-CIT-SIN-001");
-        let json = serde_json::to_string_pretty(&issue_passport(&origin, "spec1", Some("u1")).unwrap()).unwrap();
+        let json = serde_json::to_string_pretty(&issue_passport(&origin, "spec1", Some("u1"), true).unwrap()).unwrap();
 
         let receiver = test_db();
         assert!(import_passport(&receiver, &json, Some("u1")).is_ok());
@@ -523,7 +540,7 @@ mod tests {
     fn import_rejects_tampered_passport() {
         let origin = test_db();
         seed_specimen(&origin, "spec1", "2026-01-01-CIT-SIN-001");
-        let mut passport = issue_passport(&origin, "spec1", Some("u1")).unwrap();
+        let mut passport = issue_passport(&origin, "spec1", Some("u1"), true).unwrap();
         passport.specimen.accession_number = "FORGED".to_string(); // breaks content hash
         let json = serde_json::to_string_pretty(&passport).unwrap();
 
@@ -540,7 +557,7 @@ mod tests {
         set_lab_name(&conn, "  Green Thumb Labs  ").unwrap();
         assert_eq!(read_lab_name(&conn), "Green Thumb Labs");
         seed_specimen(&conn, "spec1", "2026-01-01-CIT-SIN-001");
-        let passport = issue_passport(&conn, "spec1", Some("u1")).unwrap();
+        let passport = issue_passport(&conn, "spec1", Some("u1"), true).unwrap();
         assert_eq!(passport.issuer.lab_name, "Green Thumb Labs");
         assert!(verify_passport(&passport).verified);
     }
@@ -565,7 +582,7 @@ mod tests {
             params![entries.first().unwrap().chain_seq, entries.last().unwrap().chain_seq, entries.len() as i64, root],
         )
         .unwrap();
-        let passport = issue_passport(&conn, "spec1", Some("u1")).unwrap();
+        let passport = issue_passport(&conn, "spec1", Some("u1"), true).unwrap();
         let anchor = passport.merkle_anchor.as_ref().expect("anchor should be attached");
         assert_eq!(anchor.checkpoint_id, "cp1");
         assert_eq!(anchor.anchored_txid.as_deref(), Some("txid-xyz"));
@@ -583,7 +600,7 @@ mod tests {
             params![ZERO_HASH],
         )
         .unwrap();
-        let passport = issue_passport(&conn, "spec1", Some("u1")).unwrap();
+        let passport = issue_passport(&conn, "spec1", Some("u1"), true).unwrap();
         assert!(passport.merkle_anchor.is_none());
     }
 
@@ -591,13 +608,32 @@ mod tests {
     fn get_passport_json_returns_stored_document() {
         let conn = test_db();
         seed_specimen(&conn, "spec1", "2026-01-01-CIT-SIN-001");
-        issue_passport(&conn, "spec1", Some("u1")).unwrap();
+        issue_passport(&conn, "spec1", Some("u1"), true).unwrap();
         let row = &list_passports(&conn, Some("issued")).unwrap()[0];
-        let json = get_passport_json(&conn, &row.id).unwrap();
+        let json = get_passport_json(&conn, &row.id, true).unwrap();
         let parsed = parse_passport(&json).unwrap();
         assert!(verify_passport(&parsed).verified);
     }
 
+    #[test]
+    fn hidden_provenance_is_left_out_of_a_passport_and_its_re_export() {
+        let conn = test_db();
+        seed_specimen(&conn, "spec1", "2026-01-01-CIT-SIN-001");
+        conn.execute("UPDATE specimens SET provenance = 'Wild collection, site 7' WHERE id = 'spec1'", [])
+            .unwrap();
+
+        let hidden = issue_passport(&conn, "spec1", Some("u1"), false).unwrap();
+        assert_eq!(hidden.specimen.provenance_note, None, "the marker must never be signed into a passport");
+        assert!(verify_passport(&hidden).verified);
+
+        let full = issue_passport(&conn, "spec1", Some("u1"), true).unwrap();
+        assert_eq!(full.specimen.provenance_note.as_deref(), Some("Wild collection, site 7"));
+        let rows = list_passports(&conn, Some("issued")).unwrap();
+        let row_of = |pid: &str| rows.iter().find(|r| r.passport_id == pid).unwrap().id.clone();
+        assert!(get_passport_json(&conn, &row_of(&full.passport_id), false).is_err());
+        assert!(get_passport_json(&conn, &row_of(&hidden.passport_id), false).is_ok());
+    }
+
     // Guards that gather_provenance produces canonical strings a verifier
     // recomputes identically (regression against a serialization drift).
     #[test]
@@ -69,6 +69,23 @@ pub fn apply_vocabulary_seed(conn: &Connection, manifest: &PluginManifest) -> Db
     Ok(applied)
 }
 
+/// Defines the manifest's custom fields (WP-96). Like the vocabulary seed
+/// this only adds: a field the profile already has, from the lab or another
+/// plugin, is left as it is.
+pub fn apply_custom_fields(conn: &Connection, manifest: &PluginManifest) -> DbResult<usize> {
+    let profile = manifest.profile.as_deref().unwrap_or_default();
+    crate::db::custom_fields::seed(conn, &manifest.name, profile, &manifest.custom_fields)
+        .map_err(|e| crate::db::DbError::Constraint(e.to_string()))
+}
+
+/// Adds the manifest's stage transitions (WP-98). Runs after the vocabulary
+/// seed, so the plugin's own stages exist; like it, this only adds.
+pub fn apply_stage_transitions(conn: &Connection, manifest: &PluginManifest) -> DbResult<usize> {
+    let profile = manifest.profile.as_deref().unwrap_or_default();
+    crate::db::stage_transitions::seed(conn, &manifest.name, profile, &manifest.stage_transitions)
+        .map_err(|e| crate::db::DbError::Constraint(e.to_string()))
+}
+
 /// Registers a validated plugin in `installed_plugins`. Idempotent by
 /// `plugin_name` (the table's `UNIQUE` constraint) — installing the same
 /// plugin twice updates its stored manifest/version rather than erroring.
@@ -156,6 +173,8 @@ mod tests {
             dashboard_panels: vec![],
             compliance_rules: vec![],
             report_templates: vec![],
+            custom_fields: vec![],
+            stage_transitions: vec![],
         };
         let result = apply_vocabulary_seed(&conn, &malicious);
         assert!(result.is_err(), "seeding a non-whitelisted table must be refused");
@@ -180,6 +199,65 @@ mod tests {
         assert_eq!(ptc_stage_count_before, ptc_stage_count_after, "seeding a new profile must not touch existing profiles' vocabulary");
     }
 
+    #[test]
+    fn custom_fields_are_added_once_and_never_overwritten() {
+        let conn = plugin_test_db();
+        let json = serde_json::json!({
+            "name": "Algae Culture", "version": "1.0.0", "profile": "algae_culture",
+            "custom_fields": [
+                { "entity": "specimen", "key": "cell_density", "label": "Cell density", "type": "number", "unit": "cells/mL" },
+                { "entity": "subculture", "key": "light_regime", "label": "Light regime", "type": "enum",
+                  "options": [{ "code": "continuous", "label": "Continuous" }, { "code": "day_16h", "label": "16:8" }] }
+            ]
+        })
+        .to_string();
+        let manifest = validate_manifest(&json).unwrap();
+        assert_eq!(apply_custom_fields(&conn, &manifest).unwrap(), 2);
+        conn.execute("UPDATE custom_fields SET label = 'Density' WHERE field_key = 'cell_density'", []).unwrap();
+        assert_eq!(apply_custom_fields(&conn, &manifest).unwrap(), 0);
+
+        let (label, plugin): (String, String) = conn
+            .query_row(
+                "SELECT label, source_plugin FROM custom_fields WHERE lab_profile = 'algae_culture' AND field_key = 'cell_density'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((label.as_str(), plugin.as_str()), ("Density", "Algae Culture"));
+        let options: i64 = conn.query_row("SELECT COUNT(*) FROM custom_field_options", [], |r| r.get(0)).unwrap();
+        assert_eq!(options, 2);
+    }
+
+    #[test]
+    fn stage_transitions_seed_the_plugin_profile_after_its_stages() {
+        let conn = plugin_test_db();
+        let json = serde_json::json!({
+            "name": "Algae Culture", "version": "1.0.0", "profile": "algae_culture",
+            "vocabulary_seed": [
+                { "table": "stages", "code": "inoculum", "label": "Inoculum", "sort_order": 1 },
+                { "table": "stages", "code": "bloom", "label": "Bloom", "sort_order": 2, "is_terminal": true }
+            ],
+            "stage_transitions": [{ "from_stage": "inoculum", "to_stage": "bloom" }]
+        })
+        .to_string();
+        let manifest = validate_manifest(&json).unwrap();
+        apply_vocabulary_seed(&conn, &manifest).unwrap();
+        assert_eq!(apply_stage_transitions(&conn, &manifest).unwrap(), 1);
+        assert_eq!(apply_stage_transitions(&conn, &manifest).unwrap(), 0);
+        let plugin: String = conn
+            .query_row("SELECT source_plugin FROM stage_transitions WHERE lab_profile = 'algae_culture'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(plugin, "Algae Culture");
+
+        let backwards = serde_json::json!({
+            "name": "Algae Culture", "version": "1.0.1", "profile": "algae_culture",
+            "stage_transitions": [{ "from_stage": "bloom", "to_stage": "inoculum" }]
+        })
+        .to_string();
+        let err = apply_stage_transitions(&conn, &validate_manifest(&backwards).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Bloom"), "a terminal stage has no way out: {}", err);
+    }
+
     #[test]
     fn seeding_twice_is_idempotent() {
         let conn = plugin_test_db();
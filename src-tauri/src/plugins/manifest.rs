@@ -5,6 +5,8 @@
 // plugin author's manifest looks like everything else in the system).
 use serde::{Deserialize, Serialize};
 
+use crate::error::AppError;
+
 /// Vocabulary tables a plugin is allowed to seed. Deliberately a closed
 /// whitelist (not "any table name from the manifest") so a malicious or
 /// buggy manifest can never target an arbitrary table via string
@@ -74,6 +76,14 @@ pub struct PluginManifest {
     pub compliance_rules: Vec<ComplianceRuleDescriptor>,
     #[serde(default)]
     pub report_templates: Vec<ReportTemplateDescriptor>,
+    /// WP-96: custom specimen/subculture fields, defined for each row's
+    /// `lab_profile` or else for `profile`.
+    #[serde(default)]
+    pub custom_fields: Vec<crate::db::custom_fields::SaveCustomFieldRequest>,
+    /// WP-98: allowed stage changes, for each row's `lab_profile` or else
+    /// for `profile`. Stages the manifest seeds itself can be used.
+    #[serde(default)]
+    pub stage_transitions: Vec<crate::db::stage_transitions::SaveStageTransitionRequest>,
 }
 
 /// Validates a manifest's structural and semantic requirements: non-empty
@@ -81,30 +91,58 @@ pub struct PluginManifest {
 /// and no duplicate `(table, code)` pairs within the manifest itself (a
 /// plugin author's own typo, not a cross-plugin isolation concern — that's
 /// handled separately by `INSERT OR IGNORE` at seed time).
-pub fn validate_manifest(json: &str) -> Result<PluginManifest, String> {
-    let manifest: PluginManifest = serde_json::from_str(json).map_err(|e| format!("Invalid manifest JSON: {}", e))?;
+pub fn validate_manifest(json: &str) -> Result<PluginManifest, AppError> {
+    let bad = |message: String| AppError::validation("manifest_json", message);
+    let manifest: PluginManifest = serde_json::from_str(json).map_err(|e| bad(format!("Invalid manifest JSON: {}", e)))?;
 
     if manifest.name.trim().is_empty() {
-        return Err("Manifest 'name' is required".to_string());
+        return Err(bad("Manifest 'name' is required".to_string()));
     }
     if manifest.version.trim().is_empty() {
-        return Err("Manifest 'version' is required".to_string());
+        return Err(bad("Manifest 'version' is required".to_string()));
     }
 
     let mut seen = std::collections::HashSet::new();
     for row in &manifest.vocabulary_seed {
         if !SEEDABLE_VOCAB_TABLES.contains(&row.table.as_str()) {
-            return Err(format!(
+            return Err(bad(format!(
                 "Manifest references unknown vocabulary table '{}'. Allowed: {}",
                 row.table,
                 SEEDABLE_VOCAB_TABLES.join(", ")
-            ));
+            )));
         }
         if row.code.trim().is_empty() || row.label.trim().is_empty() {
-            return Err(format!("Vocabulary seed row for table '{}' is missing code/label", row.table));
+            return Err(bad(format!("Vocabulary seed row for table '{}' is missing code/label", row.table)));
         }
         if !seen.insert((row.table.clone(), row.code.clone())) {
-            return Err(format!("Duplicate vocabulary seed row: {}.{}", row.table, row.code));
+            return Err(bad(format!("Duplicate vocabulary seed row: {}.{}", row.table, row.code)));
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    for field in &manifest.custom_fields {
+        crate::db::custom_fields::check_definition(field)
+            .map_err(|e| bad(format!("Custom field '{}': {}", field.key, e.message())))?;
+        let Some(profile) = field.lab_profile.as_ref().or(manifest.profile.as_ref()) else {
+            return Err(bad(format!("Custom field '{}' needs a lab_profile, as the manifest declares no profile", field.key)));
+        };
+        if !seen.insert((profile.clone(), field.entity, field.key.clone())) {
+            return Err(bad(format!("Duplicate custom field: {}", field.key)));
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    for t in &manifest.stage_transitions {
+        crate::db::stage_transitions::check_definition(t)
+            .map_err(|e| bad(format!("Stage transition {} → {}: {}", t.from_stage, t.to_stage, e.message())))?;
+        let Some(profile) = t.lab_profile.as_ref().or(manifest.profile.as_ref()) else {
+            return Err(bad(format!(
+                "Stage transition {} → {} needs a lab_profile, as the manifest declares no profile",
+                t.from_stage, t.to_stage
+            )));
+        };
+        if !seen.insert((profile.clone(), t.from_stage.clone(), t.to_stage.clone())) {
+            return Err(bad(format!("Duplicate stage transition: {} → {}", t.from_stage, t.to_stage)));
         }
     }
 
@@ -152,7 +190,7 @@ mod tests {
         })
         .to_string();
         let err = validate_manifest(&json).unwrap_err();
-        assert!(err.contains("unknown vocabulary table"));
+        assert!(err.message().contains("unknown vocabulary table"));
     }
 
     #[test]
@@ -168,6 +206,48 @@ mod tests {
         assert!(validate_manifest(&json).is_err());
     }
 
+    #[test]
+    fn custom_fields_are_checked_and_need_a_profile() {
+        let field = serde_json::json!({ "entity": "specimen", "key": "cell_density", "label": "Cell density", "type": "number", "unit": "cells/mL" });
+        let with = |profile: serde_json::Value, fields: Vec<serde_json::Value>| {
+            serde_json::json!({ "name": "Algae", "version": "1.0.0", "profile": profile, "custom_fields": fields }).to_string()
+        };
+
+        let manifest = validate_manifest(&with("algae_culture".into(), vec![field.clone()])).unwrap();
+        assert_eq!(manifest.custom_fields[0].key, "cell_density");
+
+        let err = validate_manifest(&with(serde_json::Value::Null, vec![field.clone()])).unwrap_err();
+        assert!(err.message().contains("needs a lab_profile"), "{}", err);
+        let mut scoped = field.clone();
+        scoped["lab_profile"] = "plant_tissue_culture".into();
+        assert!(validate_manifest(&with(serde_json::Value::Null, vec![scoped])).is_ok());
+
+        let err = validate_manifest(&with("algae_culture".into(), vec![field.clone(), field.clone()])).unwrap_err();
+        assert!(err.message().contains("Duplicate custom field"), "{}", err);
+        let mut bad = field;
+        bad["key"] = "Cell Density".into();
+        let err = validate_manifest(&with("algae_culture".into(), vec![bad])).unwrap_err();
+        assert!(err.message().contains("not a valid key"), "{}", err);
+    }
+
+    #[test]
+    fn stage_transitions_are_checked_and_need_a_profile() {
+        let with = |profile: serde_json::Value, transitions: serde_json::Value| {
+            serde_json::json!({ "name": "Algae", "version": "1.0.0", "profile": profile, "stage_transitions": transitions }).to_string()
+        };
+        let step = serde_json::json!({ "from_stage": "inoculum", "to_stage": "bloom", "required_fields": ["location"] });
+
+        let manifest = validate_manifest(&with("algae_culture".into(), serde_json::json!([step]))).unwrap();
+        assert_eq!(manifest.stage_transitions[0].required_fields, ["location"]);
+
+        let err = validate_manifest(&with(serde_json::Value::Null, serde_json::json!([step]))).unwrap_err();
+        assert!(err.message().contains("needs a lab_profile"), "{}", err);
+        let err = validate_manifest(&with("algae_culture".into(), serde_json::json!([step, step]))).unwrap_err();
+        assert!(err.message().contains("Duplicate stage transition"), "{}", err);
+        let err = validate_manifest(&with("algae_culture".into(), serde_json::json!([{ "from_stage": "bloom", "to_stage": "bloom" }]))).unwrap_err();
+        assert!(err.message().starts_with("Stage transition bloom → bloom"), "{}", err);
+    }
+
     #[test]
     fn malformed_json_is_rejected() {
         assert!(validate_manifest("not json").is_err());
@@ -26,6 +26,7 @@ use serde::Serialize;
 
 use crate::compliance_export::bundle;
 use crate::db::queries;
+use crate::error::AppError;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SubmissionKind {
@@ -35,12 +36,12 @@ pub enum SubmissionKind {
 }
 
 impl SubmissionKind {
-    pub fn from_code(s: &str) -> Result<Self, String> {
+    pub fn from_code(s: &str) -> Result<Self, AppError> {
         match s {
             "part11" => Ok(SubmissionKind::Part11),
             "usda" => Ok(SubmissionKind::Usda),
             "cites" => Ok(SubmissionKind::Cites),
-            other => Err(format!("Unknown submission kind '{}' (expected part11 | usda | cites)", other)),
+            other => Err(AppError::validation("kind", format!("Unknown submission kind '{}' (expected part11 | usda | cites)", other))),
         }
     }
     pub fn as_str(&self) -> &'static str {
@@ -100,7 +101,7 @@ fn scope_str<'a>(scope: &'a serde_json::Value, key: &str) -> &'a str {
 
 /// Evaluate whether a submission of `kind` over `scope` meets its preconditions.
 /// Pure and read-only — used both for a pre-flight preview and by the pipeline.
-pub fn evaluate_readiness(conn: &Connection, kind: SubmissionKind, scope: &serde_json::Value) -> Result<Readiness, String> {
+pub fn evaluate_readiness(conn: &Connection, kind: SubmissionKind, scope: &serde_json::Value) -> Result<Readiness, AppError> {
     let checks = match kind {
         SubmissionKind::Part11 => part11_checks(conn, scope)?,
         SubmissionKind::Usda => usda_checks(conn, scope)?,
@@ -115,7 +116,7 @@ pub fn evaluate_readiness(conn: &Connection, kind: SubmissionKind, scope: &serde
     })
 }
 
-fn part11_checks(conn: &Connection, scope: &serde_json::Value) -> Result<Vec<ReadinessCheck>, String> {
+fn part11_checks(conn: &Connection, scope: &serde_json::Value) -> Result<Vec<ReadinessCheck>, AppError> {
     let from = scope_str(scope, "from_date");
     let to = scope_str(scope, "to_date");
     let mut checks = Vec::new();
@@ -157,7 +158,7 @@ fn part11_checks(conn: &Connection, scope: &serde_json::Value) -> Result<Vec<Rea
     Ok(checks)
 }
 
-fn usda_checks(conn: &Connection, scope: &serde_json::Value) -> Result<Vec<ReadinessCheck>, String> {
+fn usda_checks(conn: &Connection, scope: &serde_json::Value) -> Result<Vec<ReadinessCheck>, AppError> {
     let ids: Vec<String> = scope
         .get("specimen_ids")
         .and_then(|v| v.as_array())
@@ -213,7 +214,7 @@ fn usda_checks(conn: &Connection, scope: &serde_json::Value) -> Result<Vec<Readi
     Ok(checks)
 }
 
-fn cites_checks(conn: &Connection, scope: &serde_json::Value) -> Result<Vec<ReadinessCheck>, String> {
+fn cites_checks(conn: &Connection, scope: &serde_json::Value) -> Result<Vec<ReadinessCheck>, AppError> {
     let root = scope_str(scope, "root_specimen_id");
     let appendix = scope_str(scope, "cites_appendix");
     let mut checks = Vec::new();
@@ -265,22 +266,20 @@ fn map_submission(r: &rusqlite::Row) -> rusqlite::Result<Submission> {
     })
 }
 
-pub fn get_submission(conn: &Connection, id: &str) -> Result<Submission, String> {
+pub fn get_submission(conn: &Connection, id: &str) -> Result<Submission, AppError> {
     conn.query_row(
         &format!("SELECT {} FROM regulatory_submissions WHERE id = ?1", SUB_COLS),
         params![id],
         map_submission,
     )
-    .map_err(|_| format!("Submission '{}' not found", id))
+    .map_err(|_| AppError::not_found("regulatory_submission", format!("Submission '{}' not found", id)).with_id(id))
 }
 
-pub fn list_submissions(conn: &Connection) -> Result<Vec<Submission>, String> {
+pub fn list_submissions(conn: &Connection) -> Result<Vec<Submission>, AppError> {
     let mut stmt = conn
-        .prepare(&format!("SELECT {} FROM regulatory_submissions ORDER BY created_at DESC", SUB_COLS))
-        .map_err(|e| e.to_string())?;
+        .prepare(&format!("SELECT {} FROM regulatory_submissions ORDER BY created_at DESC", SUB_COLS))?;
     let rows = stmt
-        .query_map([], map_submission)
-        .map_err(|e| e.to_string())?
+        .query_map([], map_submission)?
         .filter_map(|r| r.ok())
         .collect();
     Ok(rows)
@@ -295,12 +294,12 @@ pub fn create_submission(
     scope: &serde_json::Value,
     auto_generate: bool,
     user_id: &str,
-) -> Result<Submission, String> {
+) -> Result<Submission, AppError> {
     let kind = SubmissionKind::from_code(kind_str)?;
     let readiness = evaluate_readiness(conn, kind, scope)?;
     let status = if readiness.ready { "ready" } else { "blocked" };
-    let readiness_json = serde_json::to_string(&readiness).map_err(|e| e.to_string())?;
-    let scope_json = serde_json::to_string(scope).map_err(|e| e.to_string())?;
+    let readiness_json = serde_json::to_string(&readiness)?;
+    let scope_json = serde_json::to_string(scope)?;
 
     let id = uuid::Uuid::new_v4().to_string();
     let now = now_iso();
@@ -309,8 +308,7 @@ pub fn create_submission(
          (id, kind, title, scope, status, readiness, auto_generate, created_by, created_at, updated_at) \
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
         params![id, kind.as_str(), title, scope_json, status, readiness_json, auto_generate as i64, user_id, now],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     get_submission(conn, &id)
 }
 
@@ -318,56 +316,53 @@ pub fn create_submission(
 /// still in the `ready`/`blocked` states are re-evaluated; once a package is
 /// `generated`/`submitted`/`acknowledged` the status is left alone so the record
 /// of what was produced is never silently unwound.
-pub fn reevaluate_submission(conn: &Connection, id: &str) -> Result<Submission, String> {
+pub fn reevaluate_submission(conn: &Connection, id: &str) -> Result<Submission, AppError> {
     let sub = get_submission(conn, id)?;
     if sub.status != "ready" && sub.status != "blocked" && sub.status != "draft" {
         return Ok(sub);
     }
     let kind = SubmissionKind::from_code(&sub.kind)?;
-    let scope: serde_json::Value = serde_json::from_str(&sub.scope).map_err(|e| e.to_string())?;
+    let scope: serde_json::Value = serde_json::from_str(&sub.scope)?;
     let readiness = evaluate_readiness(conn, kind, &scope)?;
     let status = if readiness.ready { "ready" } else { "blocked" };
-    let readiness_json = serde_json::to_string(&readiness).map_err(|e| e.to_string())?;
+    let readiness_json = serde_json::to_string(&readiness)?;
     conn.execute(
         "UPDATE regulatory_submissions SET status = ?1, readiness = ?2, updated_at = ?3 WHERE id = ?4",
         params![status, readiness_json, now_iso(), id],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     get_submission(conn, id)
 }
 
 /// Record that the signed package was produced (called by the command layer after
 /// it assembles + signs + writes the bundle). Advances `ready` → `generated`.
-pub fn attach_package(conn: &Connection, id: &str, package_path: &str, signature: &str) -> Result<Submission, String> {
+pub fn attach_package(conn: &Connection, id: &str, package_path: &str, signature: &str) -> Result<Submission, AppError> {
     let sub = get_submission(conn, id)?;
     if sub.status != "ready" {
-        return Err(format!("Submission must be 'ready' to generate a package (currently '{}')", sub.status));
+        return Err(AppError::invalid(format!("Submission must be 'ready' to generate a package (currently '{}')", sub.status)));
     }
     conn.execute(
         "UPDATE regulatory_submissions SET status = 'generated', package_path = ?1, package_signature = ?2, updated_at = ?3 WHERE id = ?4",
         params![package_path, signature, now_iso(), id],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     get_submission(conn, id)
 }
 
 /// Record that the operator submitted the generated package through the official
 /// channel and got back a reference. Advances `generated` → `submitted`.
-pub fn mark_submitted(conn: &Connection, id: &str, reference: &str) -> Result<Submission, String> {
+pub fn mark_submitted(conn: &Connection, id: &str, reference: &str) -> Result<Submission, AppError> {
     let sub = get_submission(conn, id)?;
     if sub.status != "generated" {
-        return Err(format!("Only a 'generated' submission can be marked submitted (currently '{}')", sub.status));
+        return Err(AppError::invalid(format!("Only a 'generated' submission can be marked submitted (currently '{}')", sub.status)));
     }
     let reference = reference.trim();
     if reference.is_empty() {
-        return Err("A submission reference (e.g. the portal confirmation number) is required".to_string());
+        return Err(AppError::validation("reference", "A submission reference (e.g. the portal confirmation number) is required"));
     }
     let now = now_iso();
     conn.execute(
         "UPDATE regulatory_submissions SET status = 'submitted', submission_reference = ?1, submitted_at = ?2, updated_at = ?2 WHERE id = ?3",
         params![reference, now, id],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     get_submission(conn, id)
 }
 
@@ -503,8 +498,8 @@ mod tests {
         seed_specimen(&conn, "spec1", None);
         let scope = serde_json::json!({ "specimen_ids": ["spec1"] });
         let sub = create_submission(&conn, "usda", "Test", &scope, false, "user1").unwrap();
-        // Not generated yet.
-        assert!(mark_submitted(&conn, &sub.id, "REF").is_err());
+        // Not generated yet: a state refusal, not a permission one.
+        assert_eq!(mark_submitted(&conn, &sub.id, "REF").unwrap_err().code(), "validation");
         attach_package(&conn, &sub.id, "/tmp/x.zip", "s").unwrap();
         // Empty reference rejected.
         assert!(mark_submitted(&conn, &sub.id, "  ").is_err());
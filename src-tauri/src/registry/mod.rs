@@ -40,6 +40,7 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::compliance_export::signing;
+use crate::error::AppError;
 // The self-attested issuer identity (lab name + Ed25519 public key) is the same
 // concept as WP-70's passport issuer — reuse it rather than defining a twin.
 pub use crate::passport::IssuerIdentity;
@@ -216,7 +217,7 @@ pub fn assemble_and_sign(
     issuer: IssuerIdentity,
     mut records: Vec<RegistryRecord>,
     private_key_b64: &str,
-) -> Result<TaxonomyRegistry, String> {
+) -> Result<TaxonomyRegistry, AppError> {
     // Deterministic order → byte-stable re-exports.
     records.sort_by(|a, b| a.source_key.cmp(&b.source_key));
     for r in &mut records {
@@ -394,8 +395,8 @@ pub fn verify_registry(reg: &TaxonomyRegistry) -> RegistryVerification {
 }
 
 /// Parse a registry from JSON, returning a clear error on malformed input.
-pub fn parse_registry(json: &str) -> Result<TaxonomyRegistry, String> {
-    serde_json::from_str(json).map_err(|e| format!("Invalid registry JSON: {}", e))
+pub fn parse_registry(json: &str) -> Result<TaxonomyRegistry, AppError> {
+    serde_json::from_str(json).map_err(|e| AppError::validation("registry_json", format!("Invalid registry JSON: {}", e)))
 }
 
 #[cfg(test)]
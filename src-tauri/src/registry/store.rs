@@ -18,6 +18,7 @@ use super::{
     RegistryVerification, TaxonomyRegistry, RECORD_SPECIES, RECORD_STRAIN, RECORD_TAXON,
 };
 use crate::db::queries::log_audit;
+use crate::error::AppError;
 use crate::passport::store::{get_lab_identity, read_lab_name};
 
 fn now_iso() -> String {
@@ -96,7 +97,7 @@ pub struct RegistryImportResult {
 /// This lab's public issuer identity (name + Ed25519 public key) — the same WP-60
 /// lab key the passport uses. Shared out-of-band so partner labs can verify the
 /// registries this lab exports.
-pub fn get_identity(conn: &Connection) -> Result<IssuerIdentity, String> {
+pub fn get_identity(conn: &Connection) -> Result<IssuerIdentity, AppError> {
     get_lab_identity(conn)
 }
 
@@ -115,7 +116,7 @@ fn split_scientific(sci: &str) -> (String, String) {
 /// are filled by `assemble_and_sign`). Never exports a strain's genomic
 /// fingerprint (it may be access-restricted, and a foreign lab must re-confirm
 /// identity anyway); `status` is carried for information only.
-pub fn gather_records(conn: &Connection, lab_name: &str) -> Result<Vec<RegistryRecord>, String> {
+pub fn gather_records(conn: &Connection, lab_name: &str) -> Result<Vec<RegistryRecord>, AppError> {
     let mut records: Vec<RegistryRecord> = Vec::new();
 
     // Taxa (kingdom..genus). parent_name via a self-join for context.
@@ -124,13 +125,11 @@ pub fn gather_records(conn: &Connection, lab_name: &str) -> Result<Vec<RegistryR
             "SELECT t.rank, t.name, p.name \
              FROM taxa t LEFT JOIN taxa p ON t.parent_id = p.id \
              ORDER BY t.rank, t.name",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let taxa = stmt
         .query_map([], |r| {
             Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<String>>(2)?))
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect::<Vec<_>>();
     for (rank, name, parent_name) in taxa {
@@ -152,8 +151,7 @@ pub fn gather_records(conn: &Connection, lab_name: &str) -> Result<Vec<RegistryR
 
     // Species.
     let mut stmt = conn
-        .prepare("SELECT genus, species_name, species_code, common_name FROM species ORDER BY genus, species_name")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT genus, species_name, species_code, common_name FROM species ORDER BY genus, species_name")?;
     let species = stmt
         .query_map([], |r| {
             Ok((
@@ -162,8 +160,7 @@ pub fn gather_records(conn: &Connection, lab_name: &str) -> Result<Vec<RegistryR
                 r.get::<_, String>(2)?,
                 r.get::<_, Option<String>>(3)?,
             ))
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect::<Vec<_>>();
     for (genus, species_name, code, common_name) in species {
@@ -191,8 +188,7 @@ pub fn gather_records(conn: &Connection, lab_name: &str) -> Result<Vec<RegistryR
              FROM strains s JOIN species sp ON s.species_id = sp.id \
              WHERE s.is_archived = 0 \
              ORDER BY sp.genus, sp.species_name, s.code",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let strains = stmt
         .query_map([], |r| {
             Ok((
@@ -203,8 +199,7 @@ pub fn gather_records(conn: &Connection, lab_name: &str) -> Result<Vec<RegistryR
                 r.get::<_, String>(4)?,
                 r.get::<_, String>(5)?,
             ))
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect::<Vec<_>>();
     for (genus, species_name, code, name, strain_type, status) in strains {
@@ -230,7 +225,7 @@ pub fn gather_records(conn: &Connection, lab_name: &str) -> Result<Vec<RegistryR
 
 /// Export a signed taxonomy registry for this lab, record it (direction
 /// `issued`), and return the full document.
-pub fn export_registry(conn: &Connection, created_by: Option<&str>) -> Result<TaxonomyRegistry, String> {
+pub fn export_registry(conn: &Connection, created_by: Option<&str>) -> Result<TaxonomyRegistry, AppError> {
     let (public_key, private_key) = crate::compliance_export::load_or_create_lab_signing_key(conn)?;
     let lab_name = read_lab_name(conn);
     let issuer = IssuerIdentity { lab_name: lab_name.clone(), public_key };
@@ -245,7 +240,7 @@ pub fn export_registry(conn: &Connection, created_by: Option<&str>) -> Result<Ta
     )?;
 
     let (taxa, species, strains) = kind_counts(&registry);
-    let registry_json = serde_json::to_string_pretty(&registry).map_err(|e| e.to_string())?;
+    let registry_json = serde_json::to_string_pretty(&registry)?;
     conn.execute(
         "INSERT INTO taxonomy_registries \
          (id, registry_id, direction, issuer_lab, issuer_public_key, content_hash, record_count, \
@@ -265,8 +260,7 @@ pub fn export_registry(conn: &Connection, created_by: Option<&str>) -> Result<Ta
             created_by,
             registry.issued_at,
         ],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     Ok(registry)
 }
@@ -287,7 +281,7 @@ fn kind_counts(reg: &TaxonomyRegistry) -> (i64, i64, i64) {
 }
 
 /// Verify a registry JSON with no side effects — pure verification, no import.
-pub fn verify_registry_json(json: &str) -> Result<RegistryVerification, String> {
+pub fn verify_registry_json(json: &str) -> Result<RegistryVerification, AppError> {
     let registry = parse_registry(json)?;
     Ok(verify_registry(&registry))
 }
@@ -406,7 +400,7 @@ fn plan_for(conn: &Connection, r: &RegistryRecord) -> RecordPlan {
 
 /// Preview an import: verify the registry and compute a per-record reconciliation
 /// plan against the local database. No side effects.
-pub fn preview_import(conn: &Connection, json: &str) -> Result<RegistryImportPreview, String> {
+pub fn preview_import(conn: &Connection, json: &str) -> Result<RegistryImportPreview, AppError> {
     let registry = parse_registry(json)?;
     let verification = verify_registry(&registry);
     let records = if verification.verified {
@@ -468,7 +462,7 @@ fn unique_strain_code(conn: &Connection, species_id: &str, base: &str) -> String
 
 /// Insert a taxon reference record. `local_override` distinguishes an accepted
 /// shared taxon (0) from a forked, lab-curated divergent copy (1).
-fn insert_taxon(conn: &Connection, rank: &str, name: &str, parent_name: Option<&str>, local_override: i64) -> Result<String, String> {
+fn insert_taxon(conn: &Connection, rank: &str, name: &str, parent_name: Option<&str>, local_override: i64) -> Result<String, AppError> {
     // Best-effort parent resolution by name (parents are usually a higher rank).
     let parent_id: Option<String> = parent_name.and_then(|pn| {
         conn.query_row(
@@ -499,19 +493,17 @@ fn insert_taxon(conn: &Connection, rank: &str, name: &str, parent_name: Option<&
     conn.execute(
         "INSERT INTO taxa (id, rank, name, parent_id, local_override, taxon_path) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         params![id, rank, name, parent_id, local_override, path],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(id)
 }
 
-fn insert_species(conn: &Connection, genus: &str, species_name: &str, code: &str, common_name: Option<&str>) -> Result<String, String> {
+fn insert_species(conn: &Connection, genus: &str, species_name: &str, code: &str, common_name: Option<&str>) -> Result<String, AppError> {
     let id = uuid::Uuid::new_v4().to_string();
     let final_code = unique_species_code(conn, code);
     conn.execute(
         "INSERT INTO species (id, genus, species_name, common_name, species_code) VALUES (?1, ?2, ?3, ?4, ?5)",
         params![id, genus, species_name, common_name, final_code],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(id)
 }
 
@@ -526,7 +518,7 @@ fn insert_strain(
     strain_type: &str,
     origin_lab: &str,
     claimed_status: &str,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let id = uuid::Uuid::new_v4().to_string();
     let final_code = unique_strain_code(conn, species_id, code);
     let basis = format!(
@@ -537,8 +529,7 @@ fn insert_strain(
         "INSERT INTO strains (id, species_id, name, code, strain_type, status, is_hybrid, confirmation_basis) \
          VALUES (?1, ?2, ?3, ?4, ?5, 'unverified', 0, ?6)",
         params![id, species_id, name, final_code, strain_type, basis],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(id)
 }
 
@@ -548,7 +539,7 @@ fn apply_record(
     r: &RegistryRecord,
     plan: &RecordPlan,
     disposition: &str,
-) -> Result<AppliedRecord, String> {
+) -> Result<AppliedRecord, AppError> {
     let mut local_record_id: Option<String> = None;
     let action_taken: String;
 
@@ -650,7 +641,7 @@ fn apply_record(
             }
         }
         other => {
-            return Err(format!("Unknown disposition '{}'.", other));
+            return Err(AppError::validation("dispositions", format!("Unknown disposition '{}'.", other)));
         }
     }
 
@@ -673,11 +664,11 @@ pub fn import_registry(
     json: &str,
     decisions: &[RecordDecision],
     imported_by: Option<&str>,
-) -> Result<RegistryImportResult, String> {
+) -> Result<RegistryImportResult, AppError> {
     let registry = parse_registry(json)?;
     let verification = verify_registry(&registry);
     if !verification.verified {
-        return Err(format!("Refusing to import an unverifiable registry: {}", verification.message));
+        return Err(AppError::integrity(format!("Refusing to import an unverifiable registry: {}", verification.message)));
     }
 
     // Do not import the same registry twice.
@@ -686,10 +677,9 @@ pub fn import_registry(
             "SELECT COUNT(*) FROM taxonomy_registries WHERE direction = 'imported' AND registry_id = ?1",
             params![registry.registry_id],
             |r| r.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     if already > 0 {
-        return Err(format!("Registry '{}' has already been imported.", registry.registry_id));
+        return Err(AppError::conflict(format!("Registry '{}' has already been imported.", registry.registry_id)));
     }
 
     // All writes below run inside a single transaction so a mid-batch failure
@@ -698,7 +688,7 @@ pub fn import_registry(
     // Without it, the audit entry + `taxonomy_registries` register row would be
     // committed before the failing record, and the duplicate-import guard above
     // would then reject every retry — a permanently partial, unrecoverable import.
-    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction()?;
 
     // Fold the import into this lab's own tamper-evident audit chain.
     let details = format!(
@@ -716,8 +706,7 @@ pub fn import_registry(
         None,
         Some(&registry.content_hash),
         Some(&details),
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     let audit_entry_id: Option<String> = tx
         .query_row(
@@ -750,8 +739,7 @@ pub fn import_registry(
             imported_by,
             now_iso(),
         ],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     // Apply each record. Species are applied before strains within the sorted
     // order (source_key `species|…` sorts before `strain|…`), so a strain whose
@@ -789,12 +777,11 @@ pub fn import_registry(
                 outcome.action_taken,
                 outcome.local_record_id,
             ],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
         applied.push(outcome);
     }
 
-    tx.commit().map_err(|e| e.to_string())?;
+    tx.commit()?;
 
     Ok(RegistryImportResult {
         imported: true,
@@ -813,7 +800,7 @@ pub fn import_registry(
 // ── Register queries ─────────────────────────────────────────────────────────
 
 /// List registry register rows, newest first, optionally filtered by direction.
-pub fn list_registries(conn: &Connection, direction: Option<&str>) -> Result<Vec<RegistryRecordRow>, String> {
+pub fn list_registries(conn: &Connection, direction: Option<&str>) -> Result<Vec<RegistryRecordRow>, AppError> {
     let cols = "id, registry_id, direction, issuer_lab, issuer_public_key, content_hash, record_count, \
                 taxon_count, species_count, strain_count, verified, created_at";
     let map = |r: &rusqlite::Row| -> rusqlite::Result<RegistryRecordRow> {
@@ -838,22 +825,18 @@ pub fn list_registries(conn: &Connection, direction: Option<&str>) -> Result<Vec
                 .prepare(&format!(
                     "SELECT {} FROM taxonomy_registries WHERE direction = ?1 ORDER BY created_at DESC",
                     cols
-                ))
-                .map_err(|e| e.to_string())?;
+                ))?;
             let rows = stmt
-                .query_map(params![dir], map)
-                .map_err(|e| e.to_string())?
+                .query_map(params![dir], map)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
         }
         None => {
             let mut stmt = conn
-                .prepare(&format!("SELECT {} FROM taxonomy_registries ORDER BY created_at DESC", cols))
-                .map_err(|e| e.to_string())?;
+                .prepare(&format!("SELECT {} FROM taxonomy_registries ORDER BY created_at DESC", cols))?;
             let rows = stmt
-                .query_map([], map)
-                .map_err(|e| e.to_string())?
+                .query_map([], map)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
@@ -862,23 +845,22 @@ pub fn list_registries(conn: &Connection, direction: Option<&str>) -> Result<Vec
 }
 
 /// Fetch the full stored registry JSON for one register row (for re-export).
-pub fn get_registry_json(conn: &Connection, row_id: &str) -> Result<String, String> {
+pub fn get_registry_json(conn: &Connection, row_id: &str) -> Result<String, AppError> {
     conn.query_row(
         "SELECT registry_json FROM taxonomy_registries WHERE id = ?1",
         params![row_id],
         |r| r.get(0),
     )
-    .map_err(|_| format!("Registry record '{}' not found.", row_id))
+    .map_err(|_| AppError::not_found("taxonomy_registry", format!("Registry record '{}' not found.", row_id)).with_id(row_id))
 }
 
 /// Fetch the recorded per-record dispositions for one imported registry row.
-pub fn list_dispositions(conn: &Connection, registry_row_id: &str) -> Result<Vec<AppliedRecord>, String> {
+pub fn list_dispositions(conn: &Connection, registry_row_id: &str) -> Result<Vec<AppliedRecord>, AppError> {
     let mut stmt = conn
         .prepare(
             "SELECT source_key, record_type, local_status, disposition, action_taken, local_record_id \
              FROM registry_record_dispositions WHERE registry_row_id = ?1 ORDER BY source_key",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     let rows = stmt
         .query_map(params![registry_row_id], |r| {
             Ok(AppliedRecord {
@@ -889,8 +871,7 @@ pub fn list_dispositions(conn: &Connection, registry_row_id: &str) -> Result<Vec
                 action_taken: r.get(4)?,
                 local_record_id: r.get(5)?,
             })
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .filter_map(|r| r.ok())
         .collect();
     Ok(rows)
@@ -0,0 +1,440 @@
+//! WP-80: 21 CFR Part 11 electronic signatures.
+//!
+//! The ledger already attributes every mutation to a user's key, but a Part 11
+//! signature needs two more things at the moment of signing. §11.200 requires
+//! the signer to re-enter their credentials. §11.50 requires the signed record
+//! to show the printed name, the date and time, and the meaning of the
+//! signature ("authored", "reviewed", "approved").
+//!
+//! The ceremony has two steps, and the types keep them in order:
+//!
+//! 1. [`reauthenticate`] checks the re-entered password against the session's
+//!    own account and confirms the meaning is allowed for the action. It returns
+//!    a [`VerifiedCeremony`]. That type has private fields, so this function is
+//!    the only way to get one.
+//! 2. [`record_signature`] consumes the `VerifiedCeremony` and appends an
+//!    `electronic_signature` event to the signed ledger. The event's payload
+//!    contains the printed name, timestamp, meaning and reason. The signature is
+//!    then indexed in `electronic_signatures` for the Part 11 bundle.
+//!
+//! Commands verify before they act and record after the action succeeds, both
+//! inside the action's transaction. An action therefore never commits without
+//! its signature, and a wrong password never causes a write.
+
+use std::str::FromStr;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::db::queries;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::user::User;
+
+/// Event type of the ledger entry that carries an electronic signature.
+pub const ELECTRONIC_SIGNATURE: &str = "electronic_signature";
+
+/// The statement bound into every signature payload, so the signed bytes say
+/// what the signer agreed a signature means (§11.100(c)).
+pub const SIGNATURE_STATEMENT: &str = "I confirm this electronic signature is the legally binding equivalent \
+     of my handwritten signature, applied with the meaning stated.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureMeaning {
+    Authored,
+    Reviewed,
+    Approved,
+}
+
+impl SignatureMeaning {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureMeaning::Authored => "authored",
+            SignatureMeaning::Reviewed => "reviewed",
+            SignatureMeaning::Approved => "approved",
+        }
+    }
+}
+
+impl FromStr for SignatureMeaning {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "authored" => Ok(SignatureMeaning::Authored),
+            "reviewed" => Ok(SignatureMeaning::Reviewed),
+            "approved" => Ok(SignatureMeaning::Approved),
+            other => Err(format!("Unknown signature meaning '{}'", other)),
+        }
+    }
+}
+
+/// An action that can be electronically signed, and the meanings a signature on
+/// it may carry.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SignableAction {
+    pub action: &'static str,
+    pub label: &'static str,
+    pub entity_type: &'static str,
+    /// Whether the owning command refuses to run without a signature.
+    pub required: bool,
+    pub meanings: &'static [SignatureMeaning],
+}
+
+pub const STRAIN_CONFIRMATION: &str = "strain_confirmation";
+pub const WAIVER_APPROVAL: &str = "waiver_approval";
+pub const SUBMISSION_GENERATION: &str = "submission_generation";
+pub const PASSPORT_ISSUE: &str = "passport_issue";
+pub const RECORD_REVIEW: &str = "record_review";
+pub const WITNESS_COUNTERSIGNATURE: &str = "witness_countersignature";
+
+use SignatureMeaning::{Approved, Authored, Reviewed};
+
+/// Every signable action. The `required` ones are the critical actions whose
+/// commands demand a ceremony (WP-81 added the witness countersignature);
+/// `record_review` is the ad-hoc review or approval signature any record can
+/// receive through `sign_record`.
+pub const SIGNABLE_ACTIONS: &[SignableAction] = &[
+    SignableAction { action: STRAIN_CONFIRMATION, label: "Strain identity confirmation", entity_type: "strain", required: true, meanings: &[Approved, Reviewed] },
+    SignableAction { action: WAIVER_APPROVAL, label: "Compliance waiver approval", entity_type: "compliance_flag_waiver", required: true, meanings: &[Approved] },
+    SignableAction { action: SUBMISSION_GENERATION, label: "Regulatory submission generation", entity_type: "regulatory_submission", required: true, meanings: &[Authored, Approved] },
+    SignableAction { action: PASSPORT_ISSUE, label: "Specimen passport issue", entity_type: "specimen_passport", required: true, meanings: &[Authored, Approved] },
+    SignableAction { action: WITNESS_COUNTERSIGNATURE, label: "Witness countersignature", entity_type: "signed_event", required: true, meanings: &[Reviewed, Approved] },
+    SignableAction { action: RECORD_REVIEW, label: "Record review", entity_type: "*", required: false, meanings: &[Authored, Reviewed, Approved] },
+];
+
+pub fn signable_action(action: &str) -> Option<&'static SignableAction> {
+    SIGNABLE_ACTIONS.iter().find(|a| a.action == action)
+}
+
+/// What the client submits alongside a critical action. Deliberately not
+/// `Debug`, so the password cannot end up in a log line.
+#[derive(Deserialize)]
+pub struct SignatureCeremony {
+    pub password: String,
+    pub meaning: SignatureMeaning,
+    pub reason: Option<String>,
+}
+
+/// Proof that the ceremony passed for one action. Only [`reauthenticate`] can
+/// build one, and [`record_signature`] consumes it.
+pub struct VerifiedCeremony {
+    action: &'static str,
+    meaning: SignatureMeaning,
+    reason: Option<String>,
+}
+
+impl VerifiedCeremony {
+    pub fn action(&self) -> &'static str {
+        self.action
+    }
+
+    pub fn meaning(&self) -> SignatureMeaning {
+        self.meaning
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ElectronicSignature {
+    pub id: String,
+    pub signed_event_id: String,
+    pub user_id: String,
+    pub printed_name: String,
+    pub meaning: String,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub reason: Option<String>,
+    pub signed_at: String,
+}
+
+/// The action's spec, if `meaning` is one it allows. Runs before any password
+/// check, so a wrong choice in the dialog is not counted as a failed attempt.
+pub fn check_meaning(action: &str, meaning: SignatureMeaning) -> Result<&'static SignableAction, AppError> {
+    let spec = signable_action(action)
+        .ok_or_else(|| AppError::validation("action", format!("'{}' is not a signable action", action)))?;
+    if !spec.meanings.contains(&meaning) {
+        return Err(AppError::validation("meaning", format!(
+            "A '{}' signature is not valid for {} — allowed: {}",
+            meaning.as_str(),
+            spec.label.to_lowercase(),
+            spec.meanings.iter().map(|m| m.as_str()).collect::<Vec<_>>().join(", "),
+        )));
+    }
+    Ok(spec)
+}
+
+/// Step one of the ceremony: check the meaning is allowed for `action`, then
+/// re-verify `user`'s password with the same bcrypt path as login. The
+/// password is checked against the session's own account. A correct password
+/// for some other account does not count.
+pub fn reauthenticate(
+    db: &Database,
+    user: &User,
+    ceremony: &SignatureCeremony,
+    action: &str,
+) -> Result<VerifiedCeremony, AppError> {
+    let spec = check_meaning(action, ceremony.meaning)?;
+    // Not `Unauthenticated`: the session is still good, only the signature
+    // password is wrong.
+    let wrong_password = || AppError::validation("password", "Signature re-authentication failed — the password is incorrect.");
+    let verified = crate::auth::authenticate(db, &user.username, &ceremony.password).map_err(|_| wrong_password())?;
+    if verified.id != user.id {
+        return Err(wrong_password());
+    }
+    let reason = ceremony.reason.as_deref().map(str::trim).filter(|r| !r.is_empty()).map(str::to_string);
+    Ok(VerifiedCeremony { action: spec.action, meaning: ceremony.meaning, reason })
+}
+
+/// The signed payload. Field names are part of the record; add fields, never
+/// rename them.
+fn signature_payload(
+    user: &User,
+    verified: &VerifiedCeremony,
+    entity_type: &str,
+    entity_id: &str,
+    signed_at: &str,
+) -> String {
+    json!({
+        "printed_name": user.display_name,
+        "username": user.username,
+        "signer_role": user.role.as_str(),
+        "meaning": verified.meaning.as_str(),
+        "action": verified.action,
+        "entity_type": entity_type,
+        "entity_id": entity_id,
+        "reason": verified.reason,
+        "signed_at": signed_at,
+        "statement": SIGNATURE_STATEMENT,
+    })
+    .to_string()
+}
+
+/// Step two: append the `electronic_signature` ledger event and index it.
+/// Unlike the best-effort mutation hook, this propagates every error: the
+/// caller runs it inside the action's transaction, so a failure here rolls the
+/// action back instead of leaving it unsigned.
+pub fn record_signature(
+    conn: &Connection,
+    user: &User,
+    verified: VerifiedCeremony,
+    entity_type: &str,
+    entity_id: &str,
+) -> Result<ElectronicSignature, AppError> {
+    let signed_at = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+    let payload = signature_payload(user, &verified, entity_type, entity_id, &signed_at);
+    let event = super::append_signed_event(conn, &user.id, ELECTRONIC_SIGNATURE, entity_type, Some(entity_id), &payload)?;
+
+    let sig = ElectronicSignature {
+        id: uuid::Uuid::new_v4().to_string(),
+        signed_event_id: event.id,
+        user_id: user.id.clone(),
+        printed_name: user.display_name.clone(),
+        meaning: verified.meaning.as_str().to_string(),
+        action: verified.action.to_string(),
+        entity_type: entity_type.to_string(),
+        entity_id: entity_id.to_string(),
+        reason: verified.reason,
+        signed_at,
+    };
+    conn.execute(
+        "INSERT INTO electronic_signatures \
+         (id, signed_event_id, user_id, printed_name, meaning, action, entity_type, entity_id, reason, signed_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            sig.id, sig.signed_event_id, sig.user_id, sig.printed_name, sig.meaning,
+            sig.action, sig.entity_type, sig.entity_id, sig.reason, sig.signed_at
+        ],
+    )
+    .map_err(|e| format!("Failed to record electronic signature: {}", e))?;
+
+    queries::log_audit(
+        conn,
+        Some(&user.id),
+        "sign",
+        "electronic_signature",
+        Some(&sig.id),
+        None,
+        Some(&sig.meaning),
+        Some(&format!(
+            "{} signed {} {} as '{}' ({})",
+            sig.printed_name, sig.entity_type, sig.entity_id, sig.meaning, sig.action
+        )),
+    )
+    .map_err(|e| format!("Failed to write audit entry: {}", e))?;
+    Ok(sig)
+}
+
+const SIGNATURE_COLUMNS: &str =
+    "id, signed_event_id, user_id, printed_name, meaning, action, entity_type, entity_id, reason, signed_at";
+
+fn map_signature(r: &rusqlite::Row) -> rusqlite::Result<ElectronicSignature> {
+    Ok(ElectronicSignature {
+        id: r.get(0)?,
+        signed_event_id: r.get(1)?,
+        user_id: r.get(2)?,
+        printed_name: r.get(3)?,
+        meaning: r.get(4)?,
+        action: r.get(5)?,
+        entity_type: r.get(6)?,
+        entity_id: r.get(7)?,
+        reason: r.get(8)?,
+        signed_at: r.get(9)?,
+    })
+}
+
+/// Signatures on one record, oldest first.
+pub fn list_signatures_for(conn: &Connection, entity_type: &str, entity_id: &str) -> Result<Vec<ElectronicSignature>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM electronic_signatures WHERE entity_type = ?1 AND entity_id = ?2 ORDER BY signed_at ASC",
+            SIGNATURE_COLUMNS
+        ))?;
+    let rows = stmt
+        .query_map(params![entity_type, entity_id], map_signature)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// Signatures whose date falls in `[from, to]` (inclusive, `YYYY-MM-DD`),
+/// oldest first. Used by the Part 11 bundle.
+pub fn list_signatures_in_range(conn: &Connection, from: &str, to: &str) -> Result<Vec<ElectronicSignature>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM electronic_signatures WHERE date(signed_at) >= ?1 AND date(signed_at) <= ?2 ORDER BY signed_at ASC",
+            SIGNATURE_COLUMNS
+        ))?;
+    let rows = stmt
+        .query_map(params![from, to], map_signature)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read an electronic signature: {}", e))?;
+    Ok(rows)
+}
+
+/// Check one indexed signature against its ledger event. The event must exist,
+/// be signed by the same user, and carry a payload whose name, meaning, action,
+/// entity and timestamp match the index row. This proves the index has not been
+/// edited. The ledger's own hash and signature checks are done by
+/// `verify_ledger`.
+pub fn signature_matches_ledger(conn: &Connection, sig: &ElectronicSignature) -> Result<(), String> {
+    let (event_type, user_id, entity_id, payload): (String, Option<String>, Option<String>, String) = conn
+        .query_row(
+            "SELECT event_type, user_id, entity_id, payload FROM signed_events WHERE id = ?1",
+            params![sig.signed_event_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )
+        .map_err(|_| "the ledger event it points to is missing".to_string())?;
+    if event_type != ELECTRONIC_SIGNATURE {
+        return Err(format!("the ledger event is a '{}', not an electronic signature", event_type));
+    }
+    if user_id.as_deref() != Some(sig.user_id.as_str()) || entity_id.as_deref() != Some(sig.entity_id.as_str()) {
+        return Err("the ledger event is attributed to a different user or record".to_string());
+    }
+    let p: serde_json::Value = serde_json::from_str(&payload).map_err(|_| "the ledger payload is not valid JSON".to_string())?;
+    let fields = [
+        ("printed_name", sig.printed_name.as_str()),
+        ("meaning", sig.meaning.as_str()),
+        ("action", sig.action.as_str()),
+        ("entity_type", sig.entity_type.as_str()),
+        ("signed_at", sig.signed_at.as_str()),
+    ];
+    for (key, expected) in fields {
+        if p.get(key).and_then(|v| v.as_str()) != Some(expected) {
+            return Err(format!("'{}' differs from the signed payload", key));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer_db() -> (Database, User) {
+        let db = Database::new_in_memory().unwrap();
+        db.run_migrations().unwrap();
+        let hash = bcrypt::hash("correct horse battery", 4).unwrap();
+        db.conn
+            .execute(
+                "INSERT INTO users (id, username, password_hash, display_name, role) \
+                 VALUES ('u1', 'qa1', ?1, 'Quinn Avery', 'supervisor'), \
+                        ('u2', 'qa2', ?1, 'Other Person', 'supervisor')",
+                params![hash],
+            )
+            .unwrap();
+        let user = crate::auth::authenticate(&db, "qa1", "correct horse battery").unwrap();
+        (db, user)
+    }
+
+    fn ceremony(password: &str, meaning: SignatureMeaning) -> SignatureCeremony {
+        SignatureCeremony { password: password.to_string(), meaning, reason: Some("  Batch release  ".to_string()) }
+    }
+
+    #[test]
+    fn wrong_password_is_rejected_before_anything_is_written() {
+        let (db, user) = signer_db();
+        let err = reauthenticate(&db, &user, &ceremony("nope", Approved), WAIVER_APPROVAL).err().unwrap();
+        assert!(err.message().contains("re-authentication failed"), "{err}");
+        assert_eq!(err.params()["field"], "password", "a wrong signature password must not end the session");
+        let n: i64 = db.conn.query_row("SELECT COUNT(*) FROM signed_events", [], |r| r.get(0)).unwrap();
+        assert_eq!(n, 0);
+    }
+
+    #[test]
+    fn meaning_must_be_allowed_for_the_action() {
+        let (db, user) = signer_db();
+        let err = reauthenticate(&db, &user, &ceremony("correct horse battery", Authored), WAIVER_APPROVAL).err().unwrap();
+        assert!(err.message().contains("not valid"), "{err}");
+        assert!(reauthenticate(&db, &user, &ceremony("correct horse battery", Reviewed), "delete_everything").is_err());
+    }
+
+    #[test]
+    fn deactivated_signer_cannot_reauthenticate() {
+        let (db, user) = signer_db();
+        db.conn.execute("UPDATE users SET is_active = 0 WHERE id = 'u1'", []).unwrap();
+        assert!(reauthenticate(&db, &user, &ceremony("correct horse battery", Approved), WAIVER_APPROVAL).is_err());
+    }
+
+    #[test]
+    fn recorded_signature_binds_name_meaning_and_time_into_the_ledger() {
+        let (db, user) = signer_db();
+        let verified = reauthenticate(&db, &user, &ceremony("correct horse battery", Approved), WAIVER_APPROVAL).unwrap();
+        let sig = record_signature(&db.conn, &user, verified, "compliance_flag_waiver", "spec1").unwrap();
+        assert_eq!(sig.printed_name, "Quinn Avery");
+        assert_eq!(sig.meaning, "approved");
+        assert_eq!(sig.reason.as_deref(), Some("Batch release"));
+
+        let events = super::super::list_signed_events(&db.conn, Some("spec1"), 10).unwrap();
+        let event = events.iter().find(|e| e.event_type == ELECTRONIC_SIGNATURE).unwrap();
+        let payload: serde_json::Value = serde_json::from_str(&event.payload).unwrap();
+        assert_eq!(payload["printed_name"], "Quinn Avery");
+        assert_eq!(payload["meaning"], "approved");
+        assert_eq!(payload["signed_at"], sig.signed_at.as_str());
+        assert_eq!(payload["statement"], SIGNATURE_STATEMENT);
+        assert!(super::super::verify_ledger(&db.conn).unwrap().verified);
+        signature_matches_ledger(&db.conn, &sig).unwrap();
+        assert_eq!(list_signatures_for(&db.conn, "compliance_flag_waiver", "spec1").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn an_edited_index_row_no_longer_matches_the_ledger() {
+        let (db, user) = signer_db();
+        let verified = reauthenticate(&db, &user, &ceremony("correct horse battery", Reviewed), RECORD_REVIEW).unwrap();
+        let sig = record_signature(&db.conn, &user, verified, "specimen", "spec1").unwrap();
+        db.conn.execute("UPDATE electronic_signatures SET meaning = 'approved'", []).unwrap();
+        let edited = list_signatures_in_range(&db.conn, "2000-01-01", "2999-12-31").unwrap().remove(0);
+        assert_eq!(edited.id, sig.id);
+        let err = signature_matches_ledger(&db.conn, &edited).err().unwrap();
+        assert!(err.contains("meaning"), "{err}");
+    }
+
+    #[test]
+    fn meanings_round_trip() {
+        for m in [Authored, Reviewed, Approved] {
+            assert_eq!(m.as_str().parse::<SignatureMeaning>().unwrap(), m);
+        }
+        assert!("witnessed".parse::<SignatureMeaning>().is_err());
+    }
+}
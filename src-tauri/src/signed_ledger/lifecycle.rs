@@ -14,6 +14,20 @@
 //! Call sites use `super::try_append_signed_event(conn, user_id, EVENT_TYPE,
 //! "specimen", Some(id), &payload)` — best-effort, so a ledger hiccup never fails
 //! the primary mutation (mirrors `log_audit(...).ok()`).
+//!
+//! WP-79 closes the rest of the gap. Every other mutation is signed by the
+//! central audit hook (`super::sign_audited_mutation`, called from each
+//! `queries::log_audit*` insert path): the `(entity_type, action)` of the audit
+//! entry is looked up in [`MUTATIONS`] and, when it maps to an event type, a
+//! signed event carrying the audit entry's hash is appended in the same
+//! statement sequence. The specimen lifecycle events above stay explicit
+//! because their payloads carry more than the audit row does (passage number,
+//! split children) — those pairs are listed in [`EXPLICITLY_SIGNED`] so the hook
+//! never signs them twice. Audit actions that are not record mutations (logins,
+//! exports, the ledger's own `sign_event`) are listed in [`NOT_SIGNED`]. The
+//! tests at the bottom of this file scan the source tree and fail if an audited
+//! action falls outside all three, or if a `#[tauri::command]` writes a lab
+//! table without reaching an audit or signing call.
 
 use serde_json::json;
 
@@ -24,6 +38,8 @@ pub const SPECIMEN_DIED: &str = "specimen_died";
 pub const SPECIMEN_SPLIT: &str = "specimen_split";
 pub const SPECIMEN_STATUS_CHANGED: &str = "specimen_status_changed";
 pub const SPECIMEN_ARCHIVED: &str = "specimen_archived";
+pub const SPECIMEN_MERGED: &str = "specimen_merged";
+pub const SPECIMEN_RESTORED: &str = "specimen_restored";
 
 /// Every lifecycle event type, for validation and the ledger-filter UI.
 pub const ALL: &[&str] = &[
@@ -33,8 +49,368 @@ pub const ALL: &[&str] = &[
     SPECIMEN_SPLIT,
     SPECIMEN_STATUS_CHANGED,
     SPECIMEN_ARCHIVED,
+    SPECIMEN_MERGED,
+    SPECIMEN_RESTORED,
+];
+
+// ── Audited mutation event types (WP-79) ─────────────────────────────────────
+// Signed automatically by the audit hook; see `MUTATIONS` for the audit pair
+// each one is derived from.
+pub const SPECIMEN_UPDATED: &str = "specimen_updated";
+pub const SPECIMEN_RELOCATED: &str = "specimen_relocated";
+pub const SUBCULTURE_UPDATED: &str = "subculture_updated";
+pub const MEDIA_BATCH_CREATED: &str = "media_batch_created";
+pub const MEDIA_BATCH_UPDATED: &str = "media_batch_updated";
+pub const MEDIA_BATCH_DELETED: &str = "media_batch_deleted";
+pub const INVENTORY_ITEM_CREATED: &str = "inventory_item_created";
+pub const INVENTORY_ITEM_UPDATED: &str = "inventory_item_updated";
+pub const INVENTORY_ITEM_DELETED: &str = "inventory_item_deleted";
+pub const INVENTORY_STOCK_ADJUSTED: &str = "inventory_stock_adjusted";
+pub const INVENTORY_STOCK_CONSUMED: &str = "inventory_stock_consumed";
+pub const PREPARED_SOLUTION_CREATED: &str = "prepared_solution_created";
+pub const PREPARED_SOLUTION_UPDATED: &str = "prepared_solution_updated";
+pub const PREPARED_SOLUTION_DELETED: &str = "prepared_solution_deleted";
+pub const STRAIN_CREATED: &str = "strain_created";
+pub const STRAIN_UPDATED: &str = "strain_updated";
+pub const STRAIN_ARCHIVED: &str = "strain_archived";
+pub const STRAIN_STATUS_CHANGED: &str = "strain_status_changed";
+pub const STRAIN_HYBRIDIZED: &str = "strain_hybridized";
+pub const STRAIN_CROSS_SPECIES_OVERRIDE: &str = "strain_cross_species_override";
+pub const VIAL_FROZEN: &str = "vial_frozen";
+pub const VIAL_THAWED: &str = "vial_thawed";
+pub const VIAL_DISCARDED: &str = "vial_discarded";
+pub const COMPLIANCE_RECORD_CREATED: &str = "compliance_record_created";
+pub const COMPLIANCE_RECORD_UPDATED: &str = "compliance_record_updated";
+pub const COMPLIANCE_FLAG_WAIVED: &str = "compliance_flag_waived";
+pub const COMPLIANCE_WAIVER_REVOKED: &str = "compliance_waiver_revoked";
+pub const LOCATION_CREATED: &str = "location_created";
+pub const LOCATION_UPDATED: &str = "location_updated";
+pub const LOCATION_DELETED: &str = "location_deleted";
+pub const REMINDER_CREATED: &str = "reminder_created";
+pub const REMINDER_UPDATED: &str = "reminder_updated";
+pub const REMINDER_SNOOZED: &str = "reminder_snoozed";
+pub const REMINDER_DISMISSED: &str = "reminder_dismissed";
+pub const SPECIES_CREATED: &str = "species_created";
+pub const SPECIES_UPDATED: &str = "species_updated";
+pub const TAXON_CREATED: &str = "taxon_created";
+pub const TAXON_UPDATED: &str = "taxon_updated";
+pub const TAXONOMY_NCBI_IMPORTED: &str = "taxonomy_ncbi_imported";
+pub const TAXON_NCBI_SYNCED: &str = "taxon_ncbi_synced";
+pub const TAXON_NCBI_CONFLICT_RESOLVED: &str = "taxon_ncbi_conflict_resolved";
+pub const ATTACHMENT_ADDED: &str = "attachment_added";
+pub const ATTACHMENT_DELETED: &str = "attachment_deleted";
+pub const FRUITING_RECORDED: &str = "fruiting_recorded";
+pub const ENVIRONMENTAL_READING_RECORDED: &str = "environmental_reading_recorded";
+pub const BREEDING_PROGRAM_CREATED: &str = "breeding_program_created";
+pub const BREEDING_RECORD_ADDED: &str = "breeding_record_added";
+pub const TRIAL_CREATED: &str = "trial_created";
+pub const TRIAL_CHANGED: &str = "trial_changed";
+pub const TRIAL_DELETED: &str = "trial_deleted";
+pub const TRIAL_ASSIGNED: &str = "trial_assigned";
+pub const TRIAL_STARTED: &str = "trial_started";
+pub const TRIAL_CLOSED: &str = "trial_closed";
+pub const TRIAL_REOPENED: &str = "trial_reopened";
+pub const TRIAL_OBSERVATIONS_RECORDED: &str = "trial_observations_recorded";
+pub const AI_SUGGESTION_REJECTED: &str = "ai_suggestion_rejected";
+pub const WORKBOOK_IMPORTED: &str = "workbook_imported";
+pub const PASSPORT_ISSUED: &str = "passport_issued";
+pub const PASSPORT_IMPORTED: &str = "passport_imported";
+pub const TAXONOMY_REGISTRY_IMPORTED: &str = "taxonomy_registry_imported";
+pub const COORDINATION_BUNDLE_IMPORTED: &str = "coordination_bundle_imported";
+pub const SUBMISSION_CREATED: &str = "submission_created";
+pub const SUBMISSION_GENERATED: &str = "submission_generated";
+pub const SUBMISSION_SUBMITTED: &str = "submission_submitted";
+pub const ANCHOR_PREPARED: &str = "anchor_prepared";
+pub const ANCHOR_SUBMITTED: &str = "anchor_submitted";
+pub const ANCHOR_CONFIRMED: &str = "anchor_confirmed";
+pub const ANCHOR_SPV_PROOF_CAPTURED: &str = "anchor_spv_proof_captured";
+pub const SYNC_BATCH_APPLIED: &str = "sync_batch_applied";
+pub const SYNC_CONFLICT_RESOLVED: &str = "sync_conflict_resolved";
+pub const USER_CREATED: &str = "user_created";
+pub const USER_ROLE_CHANGED: &str = "user_role_changed";
+pub const PASSWORD_CHANGED: &str = "password_changed";
+pub const TOTP_ENROLLED: &str = "totp_enrolled";
+pub const TOTP_DISABLED: &str = "totp_disabled";
+pub const TOTP_RESET: &str = "totp_reset";
+pub const RECOVERY_CODES_REGENERATED: &str = "recovery_codes_regenerated";
+pub const MFA_POLICY_CHANGED: &str = "mfa_policy_changed";
+pub const DIRECTORY_USER_PROVISIONED: &str = "directory_user_provisioned";
+pub const DIRECTORY_USER_LINKED: &str = "directory_user_linked";
+pub const DIRECTORY_USER_SYNCED: &str = "directory_user_synced";
+pub const DIRECTORY_USER_DEACTIVATED: &str = "directory_user_deactivated";
+pub const LDAP_CONFIG_CHANGED: &str = "ldap_config_changed";
+pub const LDAP_GROUP_ROLE_CHANGED: &str = "ldap_group_role_changed";
+pub const LDAP_GROUP_ROLE_REMOVED: &str = "ldap_group_role_removed";
+pub const FIELD_PERMISSION_CHANGED: &str = "field_permission_changed";
+pub const USER_ACCESS_EXPIRY_CHANGED: &str = "user_access_expiry_changed";
+pub const USER_DEACTIVATED: &str = "user_deactivated";
+pub const USER_REACTIVATED: &str = "user_reactivated";
+pub const USER_SESSIONS_REVOKED: &str = "user_sessions_revoked";
+pub const SESSION_REVOKED: &str = "session_revoked";
+pub const SESSION_POLICY_CHANGED: &str = "session_policy_changed";
+pub const AUTH_POLICY_CHANGED: &str = "auth_policy_changed";
+pub const ACCOUNT_UNLOCKED: &str = "account_unlocked";
+pub const API_CONFIG_CHANGED: &str = "api_config_changed";
+pub const API_TOKEN_ISSUED: &str = "api_token_issued";
+pub const API_TOKEN_REVOKED: &str = "api_token_revoked";
+pub const ROLE_CREATED: &str = "role_created";
+pub const ROLE_CHANGED: &str = "role_changed";
+pub const ROLE_DELETED: &str = "role_deleted";
+pub const SETTINGS_CHANGED: &str = "settings_changed";
+pub const LAB_PROFILE_CHANGED: &str = "lab_profile_changed";
+pub const ACCESSION_TEMPLATE_CHANGED: &str = "accession_template_changed";
+pub const ACCESSION_TEMPLATE_DELETED: &str = "accession_template_deleted";
+pub const CUSTOM_FIELD_CHANGED: &str = "custom_field_changed";
+pub const CUSTOM_FIELD_RETIRED: &str = "custom_field_retired";
+pub const STAGE_TRANSITION_CHANGED: &str = "stage_transition_changed";
+pub const STAGE_TRANSITION_REMOVED: &str = "stage_transition_removed";
+pub const SMTP_CONFIG_CHANGED: &str = "smtp_config_changed";
+pub const PLUGIN_INSTALLED: &str = "plugin_installed";
+pub const PLUGIN_UNINSTALLED: &str = "plugin_uninstalled";
+pub const BACKUP_TARGET_CREATED: &str = "backup_target_created";
+pub const BACKUP_TARGET_DELETED: &str = "backup_target_deleted";
+pub const BACKUP_RESTORED: &str = "backup_restored";
+pub const CLOUD_BACKUP_RESTORED: &str = "cloud_backup_restored";
+pub const DEMO_DATA_LOADED: &str = "demo_data_loaded";
+pub const DATABASE_RESET: &str = "database_reset";
+pub const TAXON_MAPPED: &str = "taxon_mapped";
+pub const SYNC_PEER_REGISTERED: &str = "sync_peer_registered";
+pub const CLOUD_SYNC_RECONCILED: &str = "cloud_sync_reconciled";
+pub const ANCHOR_NODE_CONFIG_CHANGED: &str = "anchor_node_config_changed";
+pub const WITNESS_POLICY_CREATED: &str = "witness_policy_created";
+pub const WITNESS_POLICY_UPDATED: &str = "witness_policy_updated";
+/// Signed explicitly by `queries::reanchor_taxon_chain`, which writes its
+/// genesis entries without going through `log_audit*`.
+pub const TAXON_CHAIN_REANCHORED: &str = "taxon_chain_reanchored";
+
+/// One audited mutation the hook signs: an audit entry with this
+/// `(entity_type, action)` produces a signed event of `event_type`.
+pub struct Mutation {
+    pub entity_type: &'static str,
+    pub action: &'static str,
+    pub event_type: &'static str,
+}
+
+const fn m(entity_type: &'static str, action: &'static str, event_type: &'static str) -> Mutation {
+    Mutation { entity_type, action, event_type }
+}
+
+/// The audit-pair → signed-event vocabulary applied by `super::sign_audited_mutation`.
+pub const MUTATIONS: &[Mutation] = &[
+    m("specimen", "create", SPECIMEN_CREATED),
+    m("specimen", "update", SPECIMEN_UPDATED),
+    m("specimen", "relocate", SPECIMEN_RELOCATED),
+    m("subculture", "update", SUBCULTURE_UPDATED),
+    m("media_batch", "create", MEDIA_BATCH_CREATED),
+    m("media_batch", "update", MEDIA_BATCH_UPDATED),
+    m("media_batch", "delete", MEDIA_BATCH_DELETED),
+    m("inventory_item", "create", INVENTORY_ITEM_CREATED),
+    m("inventory_item", "update", INVENTORY_ITEM_UPDATED),
+    m("inventory_item", "delete", INVENTORY_ITEM_DELETED),
+    m("inventory_item", "adjust_stock", INVENTORY_STOCK_ADJUSTED),
+    m("inventory_item", "consume", INVENTORY_STOCK_CONSUMED),
+    m("prepared_solution", "create", PREPARED_SOLUTION_CREATED),
+    m("prepared_solution", "update", PREPARED_SOLUTION_UPDATED),
+    m("prepared_solution", "delete", PREPARED_SOLUTION_DELETED),
+    m("strain", "create", STRAIN_CREATED),
+    m("strain", "update", STRAIN_UPDATED),
+    m("strain", "archive", STRAIN_ARCHIVED),
+    m("strain", "status_change", STRAIN_STATUS_CHANGED),
+    m("strain", "hybridize", STRAIN_HYBRIDIZED),
+    m("strain", "cross_species_override", STRAIN_CROSS_SPECIES_OVERRIDE),
+    m("frozen_vial", "create", VIAL_FROZEN),
+    m("frozen_vial", "thaw", VIAL_THAWED),
+    m("frozen_vial", "discard", VIAL_DISCARDED),
+    m("compliance", "create", COMPLIANCE_RECORD_CREATED),
+    m("compliance", "update", COMPLIANCE_RECORD_UPDATED),
+    m("compliance_flag", "waive", COMPLIANCE_FLAG_WAIVED),
+    m("compliance_flag", "revoke", COMPLIANCE_WAIVER_REVOKED),
+    m("location", "create", LOCATION_CREATED),
+    m("location", "update", LOCATION_UPDATED),
+    m("location", "delete", LOCATION_DELETED),
+    m("reminder", "create", REMINDER_CREATED),
+    m("reminder", "update", REMINDER_UPDATED),
+    m("reminder", "snooze", REMINDER_SNOOZED),
+    m("reminder", "dismiss", REMINDER_DISMISSED),
+    m("species", "create", SPECIES_CREATED),
+    m("species", "update", SPECIES_UPDATED),
+    m("taxon", "create", TAXON_CREATED),
+    m("taxon", "update", TAXON_UPDATED),
+    m("taxonomy", "ncbi_import", TAXONOMY_NCBI_IMPORTED),
+    m("taxon", "ncbi_sync", TAXON_NCBI_SYNCED),
+    m("taxon", "ncbi_resolve", TAXON_NCBI_CONFLICT_RESOLVED),
+    m("taxon", "map", TAXON_MAPPED),
+    m("attachment", "create", ATTACHMENT_ADDED),
+    m("attachment", "delete", ATTACHMENT_DELETED),
+    m("fruiting_record", "create", FRUITING_RECORDED),
+    m("environmental_reading", "create", ENVIRONMENTAL_READING_RECORDED),
+    m("breeding_program", "create", BREEDING_PROGRAM_CREATED),
+    m("breeding_record", "create", BREEDING_RECORD_ADDED),
+    m("trial", "create", TRIAL_CREATED),
+    m("trial", "update", TRIAL_CHANGED),
+    m("trial", "delete", TRIAL_DELETED),
+    m("trial", "assign", TRIAL_ASSIGNED),
+    m("trial", "start", TRIAL_STARTED),
+    m("trial", "close", TRIAL_CLOSED),
+    m("trial", "reopen", TRIAL_REOPENED),
+    m("trial", "record", TRIAL_OBSERVATIONS_RECORDED),
+    m("ai_suggestion", "reject", AI_SUGGESTION_REJECTED),
+    m("workbook", "import", WORKBOOK_IMPORTED),
+    m("specimen_passport", "issue", PASSPORT_ISSUED),
+    m("specimen_passport", "import", PASSPORT_IMPORTED),
+    m("taxonomy_registry", "import", TAXONOMY_REGISTRY_IMPORTED),
+    m("breeding_coordination", "import", COORDINATION_BUNDLE_IMPORTED),
+    m("regulatory_submission", "create", SUBMISSION_CREATED),
+    m("regulatory_submission", "generate", SUBMISSION_GENERATED),
+    m("regulatory_submission", "submit", SUBMISSION_SUBMITTED),
+    m("checkpoint_anchor", "anchor_prepared", ANCHOR_PREPARED),
+    m("checkpoint_anchor", "anchor_submitted", ANCHOR_SUBMITTED),
+    m("checkpoint_anchor", "anchor_confirmed", ANCHOR_CONFIRMED),
+    m("checkpoint_anchor", "spv_proof_captured", ANCHOR_SPV_PROOF_CAPTURED),
+    m("sync_batch", "sync_submit", SYNC_BATCH_APPLIED),
+    m("sync_conflict", "sync_conflict_resolve", SYNC_CONFLICT_RESOLVED),
+    m("sync_peer", "register", SYNC_PEER_REGISTERED),
+    m("cloud_sync", "reconcile", CLOUD_SYNC_RECONCILED),
+    m("user", "create", USER_CREATED),
+    m("user", "update_role", USER_ROLE_CHANGED),
+    m("user", "change_password", PASSWORD_CHANGED),
+    m("user", "totp_enrolled", TOTP_ENROLLED),
+    m("user", "totp_disabled", TOTP_DISABLED),
+    m("user", "totp_reset", TOTP_RESET),
+    m("user", "recovery_codes_regenerated", RECOVERY_CODES_REGENERATED),
+    m("mfa_policy", "update", MFA_POLICY_CHANGED),
+    m("user", "ldap_provisioned", DIRECTORY_USER_PROVISIONED),
+    m("user", "ldap_linked", DIRECTORY_USER_LINKED),
+    m("user", "directory_sync", DIRECTORY_USER_SYNCED),
+    m("user", "directory_disabled", DIRECTORY_USER_DEACTIVATED),
+    m("ldap_config", "update", LDAP_CONFIG_CHANGED),
+    m("ldap_group_role", "update", LDAP_GROUP_ROLE_CHANGED),
+    m("ldap_group_role", "delete", LDAP_GROUP_ROLE_REMOVED),
+    m("field_permission", "update", FIELD_PERMISSION_CHANGED),
+    m("user", "access_expiry", USER_ACCESS_EXPIRY_CHANGED),
+    m("user", "deactivate", USER_DEACTIVATED),
+    m("user", "reactivate", USER_REACTIVATED),
+    m("user", "sessions_revoked", USER_SESSIONS_REVOKED),
+    m("session", "revoke", SESSION_REVOKED),
+    m("session_policy", "update", SESSION_POLICY_CHANGED),
+    m("auth_policy", "update", AUTH_POLICY_CHANGED),
+    m("user", "account_unlocked", ACCOUNT_UNLOCKED),
+    m("role", "create", ROLE_CREATED),
+    m("role", "update", ROLE_CHANGED),
+    m("role", "delete", ROLE_DELETED),
+    m("app_settings", "update", SETTINGS_CHANGED),
+    m("app_config", "update", LAB_PROFILE_CHANGED),
+    m("accession_template", "save", ACCESSION_TEMPLATE_CHANGED),
+    m("accession_template", "delete", ACCESSION_TEMPLATE_DELETED),
+    m("custom_field", "save", CUSTOM_FIELD_CHANGED),
+    m("custom_field", "retire", CUSTOM_FIELD_RETIRED),
+    m("stage_transition", "save", STAGE_TRANSITION_CHANGED),
+    m("stage_transition", "delete", STAGE_TRANSITION_REMOVED),
+    m("smtp_config", "update", SMTP_CONFIG_CHANGED),
+    m("anchor_node_config", "update", ANCHOR_NODE_CONFIG_CHANGED),
+    m("api_config", "update", API_CONFIG_CHANGED),
+    m("api_token", "create", API_TOKEN_ISSUED),
+    m("api_token", "revoke", API_TOKEN_REVOKED),
+    m("witness_policy", "create", WITNESS_POLICY_CREATED),
+    m("witness_policy", "update", WITNESS_POLICY_UPDATED),
+    m("plugin", "create", PLUGIN_INSTALLED),
+    m("plugin", "delete", PLUGIN_UNINSTALLED),
+    m("backup_target", "create", BACKUP_TARGET_CREATED),
+    m("backup_target", "delete", BACKUP_TARGET_DELETED),
+    m("backup", "restore", BACKUP_RESTORED),
+    m("cloud_backup", "restore", CLOUD_BACKUP_RESTORED),
+    m("demo_data", "create", DEMO_DATA_LOADED),
+    m("database", "reset", DATABASE_RESET),
 ];
 
+/// Audit pairs signed at their call site with a lifecycle payload richer than
+/// the audit row (see the builders below). The hook skips them so each fact is
+/// signed exactly once.
+pub const EXPLICITLY_SIGNED: &[(&str, &str)] = &[
+    ("specimen", "subcultured"),
+    ("specimen", "death"),
+    ("specimen", "split"),
+    ("specimen", "archive"),
+    ("specimen", "merge"),
+    ("specimen", "restore"),
+];
+
+/// Audit actions that are deliberately not signed, with the reason. None of
+/// them changes a lab record: they are authentication attempts, read-only
+/// exports, notes attached to another entry, or the ledger's own bookkeeping.
+pub const NOT_SIGNED: &[(&str, &str, &str)] = &[
+    ("user", "login", "session event, not a record mutation"),
+    ("user", "login_failed", "failed authentication, no acting user"),
+    ("user", "login_blocked", "failed authentication, no acting user"),
+    ("user", "account_locked", "automatic lock after failed sign-ins, no acting user"),
+    ("user", "password_expired", "policy-driven forced change; the change itself is signed as change_password"),
+    ("user", "change_password_denied", "rejected attempt, nothing changed"),
+    ("user", "login_password_verified", "first half of a two-factor login, a session event"),
+    ("user", "mfa_failed", "failed authentication, nothing changed"),
+    ("signed_event", "sign_event", "the audit note of a signature — signing it would recurse"),
+    ("electronic_signature", "sign", "the audit note of a Part 11 signature, which is itself the signed event"),
+    ("electronic_signature", "reauth_failed", "rejected signature ceremony, nothing changed"),
+    ("strain", "used_as_parent", "parent-side note of a hybridization already signed as strain_hybridized"),
+    ("checkpoint_anchor", "anchor_verify_failed", "a failed check, no state change"),
+    ("notification", "notify", "outbound notification, not a record mutation"),
+    ("backup", "create", "read-only copy of the database"),
+    ("cloud_backup", "create", "read-only encrypted copy of the database"),
+    ("compliance_bundle", "export", "read-only export"),
+    ("taxonomy_registry", "export", "read-only export"),
+    ("breeding_coordination", "export", "read-only export"),
+    ("saved_search", "save", "a user's saved filter, not a lab record"),
+    ("saved_search", "delete", "a user's saved filter, not a lab record"),
+];
+
+/// The signed event type for an audit entry, or `None` when the hook must not
+/// sign it (explicitly signed, deliberately unsigned, or unknown).
+pub fn event_for_audit(entity_type: &str, action: &str) -> Option<&'static str> {
+    MUTATIONS
+        .iter()
+        .find(|mu| mu.entity_type == entity_type && mu.action == action)
+        .map(|mu| mu.event_type)
+}
+
+/// Payload for a hook-signed mutation. `audit_entry_hash` binds the signed
+/// event to the audit entry it was derived from, so a verifier can check the
+/// two chains against each other.
+pub fn mutation(
+    event_type: &str,
+    entity_type: &str,
+    entity_id: Option<&str>,
+    action: &str,
+    new_value: Option<&str>,
+    details: Option<&str>,
+    audit_entry_hash: &str,
+) -> String {
+    json!({
+        "event": event_type,
+        "entity_type": entity_type,
+        "entity_id": entity_id,
+        "action": action,
+        "new_value": new_value,
+        "details": details,
+        "audit_entry_hash": audit_entry_hash,
+    })
+    .to_string()
+}
+
+/// A taxonomic re-anchor (WP-64): the reclassified taxon, the `reanchor_events`
+/// id, the reason, and the affected taxa/species/strains/specimens counts.
+pub fn reanchor(taxon_id: &str, reanchor_event_id: &str, reason: &str, affected: [i64; 4]) -> String {
+    json!({
+        "event": TAXON_CHAIN_REANCHORED,
+        "taxon_id": taxon_id,
+        "reanchor_event_id": reanchor_event_id,
+        "reason": reason,
+        "affected_taxa": affected[0],
+        "affected_species": affected[1],
+        "affected_strains": affected[2],
+        "affected_specimens": affected[3],
+    })
+    .to_string()
+}
+
 /// A passage/subculture event. `event_type` distinguishes a `death` subculture
 /// from a normal passage at the caller; this returns the matching signed
 /// event-type alongside the payload so the two never drift apart.
@@ -83,6 +459,34 @@ pub fn archived(specimen_id: &str) -> String {
     .to_string()
 }
 
+/// A duplicate specimen merged into a survivor (WP-99), with the row counts
+/// moved. Signed once, against the survivor.
+pub fn merged(survivor_id: &str, retired_id: &str, retired_accession: &str, moved: &serde_json::Value) -> String {
+    json!({
+        "event": SPECIMEN_MERGED,
+        "specimen_id": survivor_id,
+        "retired_id": retired_id,
+        "retired_accession": retired_accession,
+        "moved": moved,
+    })
+    .to_string()
+}
+
+/// A specimen brought back from an archive (WP-100). `source_entry_id` is the
+/// archive audit entry whose snapshot was used; `reinserted` is true when the
+/// specimen row itself had been deleted.
+pub fn restored(specimen_id: &str, source_entry_id: &str, reinserted: bool, subcultures: usize, children: usize) -> String {
+    json!({
+        "event": SPECIMEN_RESTORED,
+        "specimen_id": specimen_id,
+        "source_entry_id": source_entry_id,
+        "reinserted": reinserted,
+        "subcultures_reinserted": subcultures,
+        "children_relinked": children,
+    })
+    .to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -142,6 +546,23 @@ mod tests {
         assert_eq!(v["specimen_id"], "s9");
     }
 
+    #[test]
+    fn merged_payload_names_both_records() {
+        let v: serde_json::Value =
+            serde_json::from_str(&merged("keep", "dup", "CIT-002-X", &json!({ "subcultures": 3 }))).unwrap();
+        assert_eq!(v["event"], SPECIMEN_MERGED);
+        assert_eq!((v["specimen_id"].as_str(), v["retired_id"].as_str()), (Some("keep"), Some("dup")));
+        assert_eq!(v["moved"]["subcultures"], 3);
+    }
+
+    #[test]
+    fn restored_payload_names_the_source_entry() {
+        let v: serde_json::Value = serde_json::from_str(&restored("s9", "a1", true, 4, 1)).unwrap();
+        assert_eq!(v["event"], SPECIMEN_RESTORED);
+        assert_eq!((v["source_entry_id"].as_str(), v["reinserted"].as_bool()), (Some("a1"), Some(true)));
+        assert_eq!((v["subcultures_reinserted"].as_u64(), v["children_relinked"].as_u64()), (Some(4), Some(1)));
+    }
+
     /// `record_specimen_death` appends two events — the death and the archival it
     /// causes — because they are two separate facts a verifier may need to check.
     /// This pins the pair a call site must emit so the two can't drift apart.
@@ -176,13 +597,15 @@ mod tests {
         }
 
         let built: Vec<String> = vec![
-            // `specimen_created` is built inline at its call site, not here.
-            SPECIMEN_CREATED.to_string(),
+            // `specimen_created` is signed by the audit hook (WP-79).
+            event_of(&mutation(SPECIMEN_CREATED, "specimen", Some("s"), "create", None, None, "h")),
             event_of(&passage("s", 1, "passage").1),
             event_of(&passage("s", 1, "death").1),
             event_of(&split("s", &["001A".to_string()])),
             event_of(&status_change("s", "f", None, "t")),
             event_of(&archived("s")),
+            event_of(&merged("s", "d", "001-X", &json!({}))),
+            event_of(&restored("s", "a", false, 0, 0)),
         ];
         for t in ALL {
             assert!(
@@ -191,4 +614,507 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn mutation_vocabulary_is_unambiguous() {
+        let mut types = std::collections::HashSet::new();
+        let mut pairs = std::collections::HashSet::new();
+        for mu in MUTATIONS {
+            assert!(types.insert(mu.event_type), "event type {} mapped twice", mu.event_type);
+            assert!(pairs.insert((mu.entity_type, mu.action)), "pair {}/{} mapped twice", mu.entity_type, mu.action);
+        }
+        for (entity_type, action) in EXPLICITLY_SIGNED {
+            assert!(pairs.insert((entity_type, action)), "{entity_type}/{action} is both hook- and explicitly signed");
+        }
+        for (entity_type, action, _) in NOT_SIGNED {
+            assert!(pairs.insert((entity_type, action)), "{entity_type}/{action} is listed as signed and unsigned");
+        }
+    }
+
+    #[test]
+    fn event_for_audit_skips_explicit_and_unsigned_pairs() {
+        assert_eq!(event_for_audit("frozen_vial", "thaw"), Some(VIAL_THAWED));
+        assert_eq!(event_for_audit("specimen", "create"), Some(SPECIMEN_CREATED));
+        assert_eq!(event_for_audit("specimen", "split"), None);
+        assert_eq!(event_for_audit("user", "login"), None);
+        assert_eq!(event_for_audit("no_such_entity", "create"), None);
+    }
+
+    #[test]
+    fn mutation_payload_binds_the_audit_entry_hash() {
+        let payload = mutation(STRAIN_STATUS_CHANGED, "strain", Some("st1"), "status_change", None, Some("claimed → confirmed_manual"), "abc123");
+        let v: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(v["event"], STRAIN_STATUS_CHANGED);
+        assert_eq!(v["entity_id"], "st1");
+        assert_eq!(v["action"], "status_change");
+        assert_eq!(v["audit_entry_hash"], "abc123");
+        assert!(v["new_value"].is_null());
+    }
+
+    // ── WP-79 coverage tripwires ─────────────────────────────────────────────
+    //
+    // A plain text scan of the `log_audit*` call sites, so that adding an audit
+    // action without deciding how it is signed fails `cargo test`; a scan of
+    // the `#[tauri::command]`s, so that a command writing without an audit
+    // entry or signed event fails too; and a run of the hook itself for every
+    // pair the vocabulary maps.
+
+    /// Tables whose writes are bookkeeping rather than lab records, with the
+    /// reason. Writing only to these does not make a command "mutating".
+    const BOOKKEEPING_TABLES: &[(&str, &str)] = &[
+        ("sessions", "login sessions and expiry pruning"),
+        ("user_totp", "authenticator secret and replay step; enrollment changes are audited"),
+        ("user_recovery_codes", "hashed recovery codes; issuing them is audited, spending one is part of a login"),
+        ("error_logs", "application error log"),
+        ("audit_log", "the audit chain itself"),
+        ("signed_events", "the ledger itself"),
+        ("user_signing_keys", "ledger key material"),
+        ("signing_keys", "lab export key, generated on first use"),
+        ("audit_checkpoints", "Merkle roots over the audit log, self-verifying"),
+        ("specimens_fts", "full-text index maintenance"),
+        ("ai_suggestions", "pending model output; only an approval touches a record, and that is audited"),
+        ("qr_scans", "scanner history"),
+        ("notification_preferences", "each user's own alert settings"),
+    ];
+
+    /// Commands that write lab-facing state without an audit entry or signed
+    /// event, with the reason. Keep this short: a new entry needs the same
+    /// justification a reviewer would ask for.
+    const UNSIGNED_COMMANDS: &[(&str, &str)] = &[
+        ("reevaluate_submission", "refreshes a submission's derived readiness status from already-signed compliance records"),
+    ];
+
+    /// `log_audit*` calls whose entity type or action is a runtime value, keyed
+    /// by file, with every pair the call can produce.
+    const DYNAMIC_AUDIT_CALLS: &[(&str, &[(&str, &str)])] = &[
+        ("commands/ai.rs", &[("specimen", "update"), ("subculture", "update")]),
+    ];
+
+    /// Every `.rs` file under `src`, relative to it, with its top-level
+    /// `#[cfg(test)]` modules cut out (they run from the attribute to the next
+    /// `}` in column 0).
+    fn sources() -> Vec<(String, String)> {
+        fn walk(dir: &std::path::Path, out: &mut Vec<std::path::PathBuf>) {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    walk(&path, out);
+                } else if path.extension().is_some_and(|e| e == "rs") {
+                    out.push(path);
+                }
+            }
+        }
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut paths = Vec::new();
+        walk(&root, &mut paths);
+        paths
+            .into_iter()
+            .map(|path| {
+                let mut kept = String::new();
+                let mut in_tests = false;
+                let src = std::fs::read_to_string(&path).unwrap();
+                let mut lines = src.lines().peekable();
+                while let Some(line) = lines.next() {
+                    if line == "#[cfg(test)]" && lines.peek().is_some_and(|l| l.starts_with("mod ")) {
+                        in_tests = true;
+                    } else if in_tests && line == "}" {
+                        in_tests = false;
+                    } else if !in_tests {
+                        kept.push_str(line);
+                        kept.push('\n');
+                    }
+                }
+                let rel = path.strip_prefix(&root).unwrap().display().to_string().replace('\\', "/");
+                (rel, kept)
+            })
+            .collect()
+    }
+
+    /// The top-level arguments of each `log_audit*(` call in `src`, skipping
+    /// the helpers' own definitions, their shared insert path and comment lines.
+    fn audit_calls(src: &str) -> Vec<Vec<&str>> {
+        let mut out = Vec::new();
+        for (at, _) in src.match_indices("log_audit") {
+            let line = &src[src[..at].rfind('\n').map_or(0, |n| n + 1)..at];
+            let rest = &src[at..];
+            let Some(open) = rest.find('(') else { continue };
+            let name = &rest[..open];
+            if line.trim_start().starts_with("//")
+                || line.trim_end().ends_with("fn")
+                || name == "log_audit_impl"
+                || !name.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_')
+            {
+                continue;
+            }
+            let (mut args, mut depth, mut start, mut in_str, mut escaped) = (Vec::new(), 0, open + 1, false, false);
+            for (i, c) in rest.bytes().enumerate().skip(open) {
+                match c {
+                    _ if escaped => escaped = false,
+                    b'\\' if in_str => escaped = true,
+                    b'"' => in_str = !in_str,
+                    _ if in_str => {}
+                    b'(' | b'[' | b'{' => depth += 1,
+                    b',' if depth == 1 => {
+                        args.push(rest[start..i].trim());
+                        start = i + 1;
+                    }
+                    b')' | b']' | b'}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            args.push(rest[start..i].trim());
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            out.push(args);
+        }
+        out
+    }
+
+    /// The string literals in an argument: the literal itself, or each arm of
+    /// an `if`/`match` over literals.
+    fn literals(arg: &str) -> Vec<String> {
+        arg.split('"').skip(1).step_by(2).map(str::to_string).collect()
+    }
+
+    /// `(file, entity_type, action)` for every pair a non-test `log_audit*`
+    /// call can write. A call with a runtime-valued argument must be listed in
+    /// `DYNAMIC_AUDIT_CALLS`, otherwise it is reported with an empty pair so the
+    /// test can point at it.
+    fn audited_pairs() -> Vec<(String, String, String)> {
+        let mut out = Vec::new();
+        for (path, src) in sources() {
+            for args in audit_calls(&src) {
+                let (actions, entities) = (
+                    args.get(2).map(|a| literals(a)).unwrap_or_default(),
+                    args.get(3).map(|a| literals(a)).unwrap_or_default(),
+                );
+                if entities.is_empty() || actions.is_empty() {
+                    match DYNAMIC_AUDIT_CALLS.iter().find(|(file, _)| *file == path) {
+                        Some((_, pairs)) => {
+                            out.extend(pairs.iter().map(|(e, a)| (path.clone(), e.to_string(), a.to_string())))
+                        }
+                        None => out.push((path.clone(), String::new(), args.join(", "))),
+                    }
+                    continue;
+                }
+                for e in &entities {
+                    for a in &actions {
+                        out.push((path.clone(), e.clone(), a.clone()));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn every_audited_action_has_a_signing_decision() {
+        let pairs = audited_pairs();
+        assert!(pairs.len() > 50, "source scan found too few audit calls — is the scan broken?");
+        let decided = |e: &str, a: &str| {
+            MUTATIONS.iter().any(|mu| mu.entity_type == e && mu.action == a)
+                || EXPLICITLY_SIGNED.contains(&(e, a))
+                || NOT_SIGNED.iter().any(|(ne, na, _)| *ne == e && *na == a)
+        };
+        let mut missing = Vec::new();
+        for (path, e, a) in &pairs {
+            if e.is_empty() {
+                missing.push(format!("{path}: log_audit({a}) — runtime-valued entity_type/action, list it in DYNAMIC_AUDIT_CALLS"));
+            } else if !decided(e, a) {
+                missing.push(format!("{path}: {e}/{a}"));
+            }
+        }
+        assert!(
+            missing.is_empty(),
+            "audit actions with no signing decision — add them to lifecycle::MUTATIONS, \
+             EXPLICITLY_SIGNED or NOT_SIGNED:\n{}",
+            missing.join("\n"),
+        );
+    }
+
+    #[test]
+    fn every_mapped_pair_is_still_audited_somewhere() {
+        let pairs = audited_pairs();
+        for mu in MUTATIONS {
+            assert!(
+                pairs.iter().any(|(_, e, a)| e == mu.entity_type && a == mu.action),
+                "{}/{} is mapped to {} but no code writes that audit entry any more",
+                mu.entity_type, mu.action, mu.event_type,
+            );
+        }
+    }
+
+    /// `src` with comments and the insides of string and char literals blanked
+    /// to spaces, byte for byte, so braces and parentheses can be matched on
+    /// it; and the byte ranges of the string literals' contents in `src`.
+    fn mask(src: &str) -> (String, Vec<std::ops::Range<usize>>) {
+        let b = src.as_bytes();
+        let mut out = b.to_vec();
+        let mut literals = Vec::new();
+        let ident = |c: u8| c.is_ascii_alphanumeric() || c == b'_';
+        let blank = |out: &mut Vec<u8>, r: std::ops::Range<usize>| r.for_each(|k| out[k] = b' ');
+        let mut i = 0;
+        while i < b.len() {
+            let rest = &b[i..];
+            if rest.starts_with(b"//") {
+                let end = rest.iter().position(|&c| c == b'\n').map_or(b.len(), |n| i + n);
+                blank(&mut out, i..end);
+                i = end;
+            } else if rest.starts_with(b"/*") {
+                let end = src[i + 2..].find("*/").map_or(b.len(), |n| i + n + 4);
+                blank(&mut out, i..end);
+                i = end;
+            } else if b[i] == b'r' && (i == 0 || !ident(b[i - 1])) && matches!(b.get(i + 1), Some(b'"' | b'#')) {
+                let hashes = rest[1..].iter().take_while(|&&c| c == b'#').count();
+                if rest.get(1 + hashes) != Some(&b'"') {
+                    i += 1;
+                    continue;
+                }
+                let close = format!("\"{}", "#".repeat(hashes));
+                let start = i + 2 + hashes;
+                let end = src[start..].find(&close).map_or(b.len(), |n| start + n);
+                literals.push(start..end);
+                blank(&mut out, start..end);
+                i = end + close.len();
+            } else if b[i] == b'"' {
+                let start = i + 1;
+                let mut k = start;
+                while k < b.len() && b[k] != b'"' {
+                    k += if b[k] == b'\\' { 2 } else { 1 };
+                }
+                literals.push(start..k.min(b.len()));
+                blank(&mut out, start..k.min(b.len()));
+                i = k + 1;
+            } else if b[i] == b'\'' {
+                // A char literal, or a lifetime (which has no closing quote).
+                let len = if b.get(i + 1) == Some(&b'\\') {
+                    src[i + 3..].find('\'').map(|n| n + 2)
+                } else {
+                    src[i + 1..].chars().next().map(char::len_utf8).filter(|&n| b.get(i + 1 + n) == Some(&b'\''))
+                };
+                match len {
+                    Some(n) => {
+                        blank(&mut out, i + 1..i + 1 + n);
+                        i += n + 2;
+                    }
+                    None => i += 1,
+                }
+            } else {
+                i += 1;
+            }
+        }
+        (String::from_utf8(out).unwrap(), literals)
+    }
+
+    /// A `fn` with a body: its name, whether it is a `#[tauri::command]`, the
+    /// functions it calls (by name) and the tables its SQL writes.
+    struct Function {
+        name: String,
+        is_command: bool,
+        calls: Vec<String>,
+        writes: Vec<String>,
+    }
+
+    fn functions(src: &str) -> Vec<Function> {
+        let (code, literals) = mask(src);
+        let ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let mut out = Vec::new();
+        for (at, _) in code.match_indices("fn ") {
+            if code[..at].chars().next_back().is_some_and(ident) {
+                continue;
+            }
+            let name: String = code[at + 3..].chars().take_while(|&c| ident(c)).collect();
+            let Some(open) = code[at..].find(['{', ';']).map(|n| at + n) else { continue };
+            if name.is_empty() || code.as_bytes()[open] == b';' {
+                continue;
+            }
+            let mut depth = 0;
+            let close = code[open..]
+                .char_indices()
+                .find(|&(_, c)| {
+                    depth += match c {
+                        '{' => 1,
+                        '}' => -1,
+                        _ => 0,
+                    };
+                    depth == 0
+                })
+                .map_or(code.len(), |(n, _)| open + n);
+            let body = &code[open..close];
+
+            // Attributes sit on the lines above; comments are already blanked.
+            let is_command = code[..at]
+                .lines()
+                .rev()
+                .skip(1)
+                .map(str::trim)
+                .take_while(|l| l.is_empty() || l.starts_with("#["))
+                .any(|l| l.contains("tauri::command"));
+            // Free and path calls only: a `.method(` is most often rusqlite's
+            // (`conn.execute`), and resolving it by name to the crate's own
+            // `fn execute` would make every write look signed.
+            let calls = body
+                .match_indices('(')
+                .filter_map(|(k, _)| {
+                    let name: String = body[..k].chars().rev().take_while(|&c| ident(c)).collect();
+                    let before = body[..k - name.len()].trim_end();
+                    (!name.is_empty() && !before.ends_with('.')).then(|| name.chars().rev().collect())
+                })
+                .collect();
+            let writes = literals
+                .iter()
+                .filter(|r| r.start > open && r.end < close)
+                .filter_map(|r| {
+                    let words: Vec<String> = src[r.clone()].split_whitespace().take(6).map(str::to_ascii_lowercase).collect();
+                    let table = match words.first()?.as_str() {
+                        "insert" | "replace" => words.iter().position(|w| w == "into").and_then(|p| words.get(p + 1)),
+                        "update" => words.get(1).filter(|_| words.get(2).is_some_and(|w| w == "set")),
+                        "delete" => words.get(2).filter(|_| words[1] == "from"),
+                        _ => None,
+                    }?;
+                    Some(table.trim_matches(|c: char| !ident(c)).to_string())
+                })
+                .collect();
+            out.push(Function { name, is_command, calls, writes });
+        }
+        out
+    }
+
+    fn is_signing_call(name: &str) -> bool {
+        name.starts_with("log_audit") || name == "append_signed_event" || name == "try_append_signed_event"
+    }
+
+    /// Fails when a `#[tauri::command]` writes to the database — directly or
+    /// through any function it calls — without reaching an audit or signing
+    /// call. The call graph is by name, so a collision only ever adds edges.
+    #[test]
+    fn every_writing_command_signs_or_audits() {
+        use std::collections::{HashMap, HashSet};
+        let functions: Vec<(String, Function)> =
+            sources().into_iter().flat_map(|(path, src)| functions(&src).into_iter().map(move |f| (path.clone(), f))).collect();
+        let bookkeeping = |t: &String| BOOKKEEPING_TABLES.iter().any(|(b, _)| b == t);
+        let mut writes: HashSet<&str> =
+            functions.iter().filter(|(_, f)| f.writes.iter().any(|t| !bookkeeping(t))).map(|(_, f)| f.name.as_str()).collect();
+        let mut signs: HashSet<&str> =
+            functions.iter().filter(|(_, f)| f.calls.iter().any(|c| is_signing_call(c))).map(|(_, f)| f.name.as_str()).collect();
+        let mut calls: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (_, f) in &functions {
+            calls.entry(&f.name).or_default().extend(f.calls.iter().map(String::as_str));
+        }
+        loop {
+            let before = (writes.len(), signs.len());
+            for (name, callees) in &calls {
+                if callees.iter().any(|c| writes.contains(c)) {
+                    writes.insert(name);
+                }
+                if callees.iter().any(|c| signs.contains(c)) {
+                    signs.insert(name);
+                }
+            }
+            if (writes.len(), signs.len()) == before {
+                break;
+            }
+        }
+
+        let commands: Vec<&(String, Function)> = functions.iter().filter(|(_, f)| f.is_command).collect();
+        assert!(commands.len() > 100, "source scan found only {} commands — is the scan broken?", commands.len());
+        let offenders: Vec<String> = commands
+            .iter()
+            .filter(|(_, f)| writes.contains(f.name.as_str()) && !signs.contains(f.name.as_str()))
+            .filter(|(_, f)| !UNSIGNED_COMMANDS.iter().any(|(n, _)| *n == f.name))
+            .map(|(path, f)| format!("{path}: {}", f.name))
+            .collect();
+        assert!(
+            offenders.is_empty(),
+            "#[tauri::command]s that write without an audit entry or signed event — audit the write, \
+             or list the command in UNSIGNED_COMMANDS with the reason:\n{}",
+            offenders.join("\n"),
+        );
+    }
+
+    #[test]
+    fn the_command_scan_sees_through_comments_and_literals() {
+        let src = r##"
+            // fn commented_out() { "INSERT INTO specimens" }
+            #[tauri::command]
+            pub fn cmd(c: char) -> Result<(), String> {
+                let _ = ('"', '{', "a } brace", r#"raw "quoted" }"#);
+                helper(&conn)?;
+                conn.execute_batch("")?;
+                Ok(())
+            }
+            fn helper<'a>(conn: &'a Connection) {
+                conn.execute("UPDATE specimens SET x = 1", []);
+            }
+            fn declared_only();
+        "##;
+        let fns = functions(src);
+        let names: Vec<&str> = fns.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["cmd", "helper"]);
+        assert!(fns[0].is_command && !fns[1].is_command);
+        assert_eq!(fns[0].calls, ["helper", "Ok"], "method calls are not followed");
+        assert!(fns[0].writes.is_empty());
+        assert_eq!(fns[1].writes, ["specimens"]);
+    }
+
+    #[test]
+    fn explicitly_signed_pairs_are_signed_in_their_files() {
+        for (path, src) in sources() {
+            let logs_explicit = audit_calls(&src).iter().any(|args| match (args.get(3), args.get(2)) {
+                (Some(e), Some(a)) => EXPLICITLY_SIGNED.iter().any(|(xe, xa)| {
+                    literals(e).iter().any(|l| l == xe) && literals(a).iter().any(|l| l == xa)
+                }),
+                _ => false,
+            });
+            assert!(
+                !logs_explicit || src.contains("try_append_signed_event("),
+                "{path} logs an explicitly-signed audit pair but appends no signed event",
+            );
+        }
+    }
+
+    /// Writes every audit pair through `queries::log_audit` and checks what the
+    /// hook appended: one event of the mapped type bound to the new audit row
+    /// for each mapped pair, nothing for the rest. A witness policy's follow-up
+    /// `witness_required` entry is the policy's, not the hook's.
+    #[test]
+    fn the_audit_hook_signs_each_mapped_pair_once() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_all(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO users (id, username, password_hash, display_name, role) \
+             VALUES ('user1', 'u1', 'x', 'User One', 'tech')",
+            [],
+        )
+        .unwrap();
+        let events = |id: &str| -> Vec<(String, String)> {
+            let mut stmt = conn
+                .prepare("SELECT event_type, payload FROM signed_events WHERE entity_id = ?1 AND event_type != ?2")
+                .unwrap();
+            stmt.query_map([id, super::super::witness::WITNESS_REQUIRED], |r| Ok((r.get(0)?, r.get(1)?))).unwrap().map(|r| r.unwrap()).collect()
+        };
+
+        for (k, mu) in MUTATIONS.iter().enumerate() {
+            let id = format!("m{k}");
+            crate::db::queries::log_audit(&conn, Some("user1"), mu.action, mu.entity_type, Some(&id), None, None, None).unwrap();
+            let entry_hash: String = conn
+                .query_row("SELECT entry_hash FROM audit_log WHERE entity_id = ?1", [&id], |r| r.get(0))
+                .unwrap();
+            let signed = events(&id);
+            assert_eq!(signed.len(), 1, "{}/{} appended {} signed events", mu.entity_type, mu.action, signed.len());
+            assert_eq!(signed[0].0, mu.event_type);
+            let payload: serde_json::Value = serde_json::from_str(&signed[0].1).unwrap();
+            assert_eq!(payload["audit_entry_hash"], entry_hash.as_str(), "{}/{}", mu.entity_type, mu.action);
+        }
+
+        let unsigned = EXPLICITLY_SIGNED.iter().copied().chain(NOT_SIGNED.iter().map(|(e, a, _)| (*e, *a)));
+        for (k, (entity_type, action)) in unsigned.enumerate() {
+            let id = format!("u{k}");
+            crate::db::queries::log_audit(&conn, Some("user1"), action, entity_type, Some(&id), None, None, None).unwrap();
+            assert!(events(&id).is_empty(), "the hook signed {entity_type}/{action}");
+        }
+    }
 }
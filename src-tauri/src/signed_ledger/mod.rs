@@ -21,14 +21,21 @@
 // automatically gets a signed genesis transaction. Extending automatic signing
 // to every one of the ~30 mutation commands is incremental follow-up work; the
 // foundation here forecloses nothing.
+//
+// WP-79 finished that follow-up: `sign_audited_mutation` is called from every
+// audit-log insert path in `db::queries`, so any audited mutation is signed
+// automatically using the vocabulary in `lifecycle::MUTATIONS`.
 
 use rusqlite::{params, Connection};
 use serde::Serialize;
 
 use crate::compliance_export::signing;
 use crate::db::queries::{compute_entry_hash, ZERO_HASH};
+use crate::error::AppError;
 
+pub mod esignature;
 pub mod lifecycle;
+pub mod witness;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct SignedEvent {
@@ -54,6 +61,11 @@ pub struct LedgerVerification {
     /// `seq` of the first entry that failed a check (hash, linkage, or signature).
     pub first_break_seq: Option<i64>,
     pub message: String,
+    /// WP-81: events whose required witnesses have not all countersigned. Not an
+    /// integrity failure, so `verified` stays true, but a reviewer must see them.
+    /// Empty when the chain itself is broken, because the report would rest on
+    /// untrusted rows.
+    pub pending_witness: Vec<witness::WitnessStatus>,
 }
 
 fn now_iso() -> String {
@@ -87,7 +99,7 @@ pub fn canonical_event_bytes(
 /// on first use. Returns `(public_key_b64, private_key_b64)`. Distinct from the
 /// single lab-wide WP-60 export key: signed ledger events are attributed to the
 /// *individual* who authorized them.
-pub fn load_or_create_user_signing_key(conn: &Connection, user_id: &str) -> Result<(String, String), String> {
+pub fn load_or_create_user_signing_key(conn: &Connection, user_id: &str) -> Result<(String, String), AppError> {
     let existing: Option<(String, String)> = conn
         .query_row(
             "SELECT public_key_b64, private_key_b64 FROM user_signing_keys WHERE user_id = ?1",
@@ -102,8 +114,7 @@ pub fn load_or_create_user_signing_key(conn: &Connection, user_id: &str) -> Resu
     conn.execute(
         "INSERT INTO user_signing_keys (user_id, public_key_b64, private_key_b64, created_at) VALUES (?1, ?2, ?3, ?4)",
         params![user_id, keypair.public_key_b64, keypair.private_key_b64, now_iso()],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok((keypair.public_key_b64, keypair.private_key_b64))
 }
 
@@ -127,12 +138,11 @@ pub fn append_signed_event(
     entity_type: &str,
     entity_id: Option<&str>,
     payload: &str,
-) -> Result<SignedEvent, String> {
+) -> Result<SignedEvent, AppError> {
     let (public_key, private_key) = load_or_create_user_signing_key(conn, user_id)?;
 
     let next_seq: i64 = conn
-        .query_row("SELECT COALESCE(MAX(seq), -1) + 1 FROM signed_events", [], |r| r.get(0))
-        .map_err(|e| e.to_string())?;
+        .query_row("SELECT COALESCE(MAX(seq), -1) + 1 FROM signed_events", [], |r| r.get(0))?;
 
     let prev_hash: String = conn
         .query_row(
@@ -160,10 +170,9 @@ pub fn append_signed_event(
             id, next_seq, event_type, entity_type, entity_id, user_id, payload,
             prev_hash, event_hash, signature, public_key, created_at
         ],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
-    Ok(SignedEvent {
+    let event = SignedEvent {
         id,
         seq: next_seq,
         event_type: event_type.to_string(),
@@ -176,7 +185,11 @@ pub fn append_signed_event(
         signature,
         public_key,
         created_at,
-    })
+    };
+    // WP-81: an event a witness policy covers is followed by its signed
+    // `witness_required` entry, whichever path appended it.
+    witness::require_witnesses_if_policy(conn, &event)?;
+    Ok(event)
 }
 
 /// A best-effort wrapper for wiring into existing command flows: never returns an
@@ -193,8 +206,35 @@ pub fn try_append_signed_event(
     let _ = append_signed_event(conn, user_id, event_type, entity_type, entity_id, payload);
 }
 
+/// WP-79: the central mutation hook. Called by every `queries::log_audit*` insert
+/// path right after the audit row is written, it signs the mutation as the
+/// event type `lifecycle::event_for_audit` maps the audit pair to, embedding the
+/// audit entry's hash in the payload so the two chains cross-reference.
+///
+/// Best-effort like [`try_append_signed_event`]. Entries with no acting user
+/// (system jobs, failed logins) are skipped, as are pairs the vocabulary does
+/// not map — those are signed explicitly at the call site or deliberately
+/// unsigned, see `lifecycle::EXPLICITLY_SIGNED` / `lifecycle::NOT_SIGNED`.
+#[allow(clippy::too_many_arguments)]
+pub fn sign_audited_mutation(
+    conn: &Connection,
+    user_id: Option<&str>,
+    action: &str,
+    entity_type: &str,
+    entity_id: Option<&str>,
+    new_value: Option<&str>,
+    details: Option<&str>,
+    audit_entry_hash: &str,
+) {
+    let (Some(uid), Some(event_type)) = (user_id, lifecycle::event_for_audit(entity_type, action)) else {
+        return;
+    };
+    let payload = lifecycle::mutation(event_type, entity_type, entity_id, action, new_value, details, audit_entry_hash);
+    try_append_signed_event(conn, uid, event_type, entity_type, entity_id, &payload);
+}
+
 /// List signed events, newest first, optionally scoped to one entity.
-pub fn list_signed_events(conn: &Connection, entity_id: Option<&str>, limit: i64) -> Result<Vec<SignedEvent>, String> {
+pub fn list_signed_events(conn: &Connection, entity_id: Option<&str>, limit: i64) -> Result<Vec<SignedEvent>, AppError> {
     let lim = limit.clamp(1, 1000);
     match entity_id {
         Some(eid) => {
@@ -202,11 +242,9 @@ pub fn list_signed_events(conn: &Connection, entity_id: Option<&str>, limit: i64
                 .prepare(
                     "SELECT id, seq, event_type, entity_type, entity_id, user_id, payload, prev_hash, event_hash, signature, public_key, created_at \
                      FROM signed_events WHERE entity_id = ?1 ORDER BY seq DESC LIMIT ?2",
-                )
-                .map_err(|e| e.to_string())?;
+                )?;
             let rows = stmt
-                .query_map(params![eid, lim], map_event)
-                .map_err(|e| e.to_string())?
+                .query_map(params![eid, lim], map_event)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
@@ -216,11 +254,9 @@ pub fn list_signed_events(conn: &Connection, entity_id: Option<&str>, limit: i64
                 .prepare(
                     "SELECT id, seq, event_type, entity_type, entity_id, user_id, payload, prev_hash, event_hash, signature, public_key, created_at \
                      FROM signed_events ORDER BY seq DESC LIMIT ?1",
-                )
-                .map_err(|e| e.to_string())?;
+                )?;
             let rows = stmt
-                .query_map(params![lim], map_event)
-                .map_err(|e| e.to_string())?
+                .query_map(params![lim], map_event)?
                 .filter_map(|r| r.ok())
                 .collect();
             Ok(rows)
@@ -250,13 +286,12 @@ fn map_event(r: &rusqlite::Row) -> rusqlite::Result<SignedEvent> {
 /// entry's hash (and the first entry links to `ZERO_HASH`), confirm `seq` is
 /// gapless (a gap means a deletion), and verify the Ed25519 signature against the
 /// entry's public key. Returns the first break, if any.
-pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
+pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, AppError> {
     let mut stmt = conn
         .prepare(
             "SELECT seq, event_type, entity_type, entity_id, user_id, payload, prev_hash, event_hash, signature, public_key, created_at \
              FROM signed_events ORDER BY seq ASC",
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
     #[allow(clippy::type_complexity)]
     let rows: Vec<(i64, String, String, Option<String>, Option<String>, String, String, String, String, String, String)> = stmt
@@ -265,8 +300,7 @@ pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
                 r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?,
                 r.get(6)?, r.get(7)?, r.get(8)?, r.get(9)?, r.get(10)?,
             ))
-        })
-        .map_err(|e| e.to_string())?
+        })?
         .collect::<rusqlite::Result<Vec<_>>>()
         // Strict: a row dropped here would shorten the sequence, and the
         // gapless-seq check below would then report it as a DELETED event.
@@ -286,6 +320,7 @@ pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
                 total_events: total,
                 signatures_valid,
                 first_break_seq: Some(*seq),
+                pending_witness: Vec::new(),
                 message: format!("Ledger sequence gap — expected seq {}, found {} (an entry was removed).", expected_seq, seq),
             });
         }
@@ -296,6 +331,7 @@ pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
                 total_events: total,
                 signatures_valid,
                 first_break_seq: Some(*seq),
+                pending_witness: Vec::new(),
                 message: format!("Broken chain linkage at seq {} — prev_hash does not match the previous entry.", seq),
             });
         }
@@ -311,6 +347,7 @@ pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
                 total_events: total,
                 signatures_valid,
                 first_break_seq: Some(*seq),
+                pending_witness: Vec::new(),
                 message: format!("Content tampering at seq {} — recomputed hash does not match the stored hash.", seq),
             });
         }
@@ -322,6 +359,7 @@ pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
                 total_events: total,
                 signatures_valid,
                 first_break_seq: Some(*seq),
+                pending_witness: Vec::new(),
                 message: format!("Invalid signature at seq {} — the entry was not signed by the stated key.", seq),
             });
         }
@@ -342,6 +380,7 @@ pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
                         total_events: total,
                         signatures_valid,
                         first_break_seq: Some(*seq),
+                        pending_witness: Vec::new(),
                         message: format!("Signing key mismatch at seq {} — the entry's key differs from the user's registered key.", seq),
                     });
                 }
@@ -351,6 +390,7 @@ pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
                         total_events: total,
                         signatures_valid,
                         first_break_seq: Some(*seq),
+                        pending_witness: Vec::new(),
                         message: format!("Missing registered key at seq {} — user '{}' has no registered signing key to verify against (the key row was removed).", seq, uid),
                     });
                 }
@@ -360,6 +400,7 @@ pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
         expected_prev = event_hash.clone();
     }
 
+    let pending_witness = witness::pending_witness(conn)?;
     Ok(LedgerVerification {
         verified: true,
         total_events: total,
@@ -367,9 +408,16 @@ pub fn verify_ledger(conn: &Connection) -> Result<LedgerVerification, String> {
         first_break_seq: None,
         message: if total == 0 {
             "Ledger is empty — nothing to verify.".to_string()
+        } else if !pending_witness.is_empty() {
+            format!(
+                "Ledger verified — {} signed events, all hashes and signatures valid; {} event(s) still awaiting required witnesses.",
+                total,
+                pending_witness.len()
+            )
         } else {
             format!("Ledger verified — {} signed events, all hashes and signatures valid.", total)
         },
+        pending_witness,
     })
 }
 
@@ -526,4 +574,48 @@ mod tests {
         assert_eq!(list_signed_events(&conn, Some("spec1"), 100).unwrap().len(), 1);
         assert_eq!(list_signed_events(&conn, None, 100).unwrap().len(), 2);
     }
+
+    #[test]
+    fn audited_mutation_is_signed_with_the_audit_entry_hash() {
+        let conn = test_db();
+        crate::db::queries::log_audit(
+            &conn, Some("user1"), "thaw", "frozen_vial", Some("vial1"), None, None, Some("Thawed"),
+        )
+        .unwrap();
+        let audit_hash: String = conn
+            .query_row("SELECT entry_hash FROM audit_log WHERE entity_id = 'vial1'", [], |r| r.get(0))
+            .unwrap();
+
+        // The seeded WP-81 thaw policy adds a witness requirement after it.
+        let events = list_signed_events(&conn, Some("vial1"), 10).unwrap();
+        let thawed: Vec<_> = events.iter().filter(|e| e.event_type == lifecycle::VIAL_THAWED).collect();
+        assert_eq!(thawed.len(), 1);
+        assert_eq!(thawed[0].user_id.as_deref(), Some("user1"));
+        let payload: serde_json::Value = serde_json::from_str(&thawed[0].payload).unwrap();
+        assert_eq!(payload["audit_entry_hash"], audit_hash);
+        assert!(events.iter().any(|e| e.event_type == witness::WITNESS_REQUIRED));
+        assert!(verify_ledger(&conn).unwrap().verified);
+    }
+
+    #[test]
+    fn hook_skips_unmapped_explicit_and_anonymous_entries() {
+        let conn = test_db();
+        // Deliberately unsigned, explicitly signed at the call site, and no acting user.
+        crate::db::queries::log_audit(&conn, Some("user1"), "login", "user", Some("user1"), None, None, None).unwrap();
+        crate::db::queries::log_audit(&conn, Some("user1"), "split", "specimen", Some("s1"), None, None, None).unwrap();
+        crate::db::queries::log_audit(&conn, None, "thaw", "frozen_vial", Some("v1"), None, None, None).unwrap();
+        assert!(list_signed_events(&conn, None, 100).unwrap().is_empty());
+    }
+
+    #[test]
+    fn hook_signs_genesis_entries_too() {
+        let conn = test_db();
+        crate::db::queries::log_audit_taxon_genesis(
+            &conn, Some("user2"), "create", "taxon", Some("tx1"), None, Some("Plantae"), None, None,
+        )
+        .unwrap();
+        let events = list_signed_events(&conn, Some("tx1"), 10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, lifecycle::TAXON_CREATED);
+    }
 }
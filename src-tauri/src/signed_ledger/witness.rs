@@ -0,0 +1,504 @@
+//! WP-81: supervisor countersignatures (witnessed events).
+//!
+//! Some SOPs need a second person to witness an operation, such as splitting a
+//! stock culture, thawing a banked vial, or confirming a strain by hand. The
+//! admin-editable `witness_policies` table lists those operations by ledger
+//! event type, optionally narrowed to one payload value. Each policy sets how
+//! many witnesses are needed and which roles may witness.
+//!
+//! Both halves of a witness requirement live in the ledger, so they are covered
+//! by the same hash chain and signature checks as everything else:
+//!
+//! - When [`super::append_signed_event`] appends an event that matches an
+//!   enabled policy, it appends a `witness_required` event right after it. That
+//!   event is signed by the same user. Its payload names the witnessed event,
+//!   that event's hash, and a snapshot of the policy. Later policy edits
+//!   therefore never change what an earlier event required.
+//! - A witness countersigns by applying a WP-80 electronic signature with the
+//!   action `witness_countersignature` to the `signed_event` record. That
+//!   signature is another signed ledger event, carrying the witness's printed
+//!   name, role and meaning.
+//!
+//! [`witness_report`] re-derives every requirement's status from the ledger.
+//! The `electronic_signatures` index is not used. A countersignature counts
+//! only when it comes after the requirement, is by someone other than the
+//! original signer, is by a role the snapshot allows, and is not a duplicate
+//! from the same person. `verify_ledger` includes the unsatisfied requirements
+//! in its report.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::esignature::{ELECTRONIC_SIGNATURE, WITNESS_COUNTERSIGNATURE};
+use super::SignedEvent;
+use crate::error::AppError;
+use crate::models::user::User;
+
+/// Event type of the ledger entry that records a witness requirement.
+pub const WITNESS_REQUIRED: &str = "witness_required";
+
+/// Upper bound on witnesses per event. More than this is an SOP problem, not a
+/// signature one.
+pub const MAX_WITNESSES: i64 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WitnessPolicy {
+    pub id: String,
+    pub event_type: String,
+    /// Optional payload field the policy is narrowed to, e.g. `new_value`.
+    pub match_field: Option<String>,
+    pub match_value: Option<String>,
+    pub label: String,
+    pub required_count: i64,
+    pub allowed_roles: Vec<String>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Countersignature {
+    pub signed_event_id: String,
+    pub user_id: String,
+    pub printed_name: String,
+    pub role: String,
+    pub meaning: String,
+    pub signed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WitnessStatus {
+    pub requirement_event_id: String,
+    pub event_id: String,
+    pub event_seq: Option<i64>,
+    pub event_type: String,
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub signed_by: Option<String>,
+    pub label: String,
+    pub required_count: i64,
+    pub allowed_roles: Vec<String>,
+    pub witnesses: Vec<Countersignature>,
+    /// Countersignatures that do not count, with the reason.
+    pub rejected: Vec<String>,
+    pub satisfied: bool,
+}
+
+fn roles_from_column(s: &str) -> Vec<String> {
+    s.split(',').map(str::trim).filter(|r| !r.is_empty()).map(str::to_string).collect()
+}
+
+fn map_policy(r: &rusqlite::Row) -> rusqlite::Result<WitnessPolicy> {
+    Ok(WitnessPolicy {
+        id: r.get(0)?,
+        event_type: r.get(1)?,
+        match_field: r.get(2)?,
+        match_value: r.get(3)?,
+        label: r.get(4)?,
+        required_count: r.get(5)?,
+        allowed_roles: roles_from_column(&r.get::<_, String>(6)?),
+        enabled: r.get::<_, i64>(7)? != 0,
+    })
+}
+
+const POLICY_COLUMNS: &str =
+    "id, event_type, match_field, match_value, label, required_count, allowed_roles, enabled";
+
+pub fn list_policies(conn: &Connection) -> Result<Vec<WitnessPolicy>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM witness_policies ORDER BY label ASC", POLICY_COLUMNS))?;
+    let rows = stmt
+        .query_map([], map_policy)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// Validate a policy before it is saved. The event type must be a real ledger
+/// vocabulary type. Allowing `witness_required` or `electronic_signature` would
+/// let a requirement trigger another requirement.
+pub fn validate_policy(conn: &Connection, p: &WitnessPolicy) -> Result<(), AppError> {
+    let known = super::lifecycle::ALL.contains(&p.event_type.as_str())
+        || super::lifecycle::MUTATIONS.iter().any(|mu| mu.event_type == p.event_type)
+        || p.event_type == super::lifecycle::TAXON_CHAIN_REANCHORED;
+    if !known {
+        return Err(AppError::validation("event_type", format!("'{}' is not a signed ledger event type", p.event_type)));
+    }
+    if p.label.trim().is_empty() {
+        return Err(AppError::validation("label", "A witness policy needs a label"));
+    }
+    if !(1..=MAX_WITNESSES).contains(&p.required_count) {
+        return Err(AppError::validation(
+            "required_count",
+            format!("Required witnesses must be between 1 and {}", MAX_WITNESSES),
+        ));
+    }
+    if p.allowed_roles.is_empty() {
+        return Err(AppError::validation("allowed_roles", "At least one witness role is required"));
+    }
+    for role in &p.allowed_roles {
+        crate::auth::roles::existing_role(conn, role)?;
+    }
+    if p.match_field.is_some() != p.match_value.is_some() {
+        return Err(AppError::validation("match_value", "A payload match needs both a field and a value"));
+    }
+    Ok(())
+}
+
+/// Insert or update a policy. Returns whether it was newly created.
+pub fn upsert_policy(conn: &Connection, p: &WitnessPolicy) -> Result<bool, AppError> {
+    validate_policy(conn, p)?;
+    let existed: bool = conn
+        .query_row("SELECT COUNT(*) FROM witness_policies WHERE id = ?1", params![p.id], |r| r.get::<_, i64>(0))?
+        > 0;
+    conn.execute(
+        "INSERT INTO witness_policies (id, event_type, match_field, match_value, label, required_count, allowed_roles, enabled, updated_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, datetime('now')) \
+         ON CONFLICT(id) DO UPDATE SET event_type = excluded.event_type, match_field = excluded.match_field, \
+             match_value = excluded.match_value, label = excluded.label, required_count = excluded.required_count, \
+             allowed_roles = excluded.allowed_roles, enabled = excluded.enabled, updated_at = excluded.updated_at",
+        params![
+            p.id, p.event_type, p.match_field, p.match_value, p.label.trim(), p.required_count,
+            p.allowed_roles.join(","), p.enabled as i64
+        ],
+    )
+    .map_err(|e| AppError::internal(format!("Failed to save witness policy: {}", e)))?;
+    Ok(!existed)
+}
+
+fn policy_matches(policy: &WitnessPolicy, payload: &serde_json::Value) -> bool {
+    match (&policy.match_field, &policy.match_value) {
+        (Some(field), Some(value)) => payload.get(field).and_then(|v| v.as_str()) == Some(value.as_str()),
+        _ => true,
+    }
+}
+
+/// Called by `append_signed_event` after every append. If an enabled policy
+/// matches the event, this appends the `witness_required` ledger entry. When
+/// several policies match, the strictest one wins: the most witnesses, then
+/// the policy id as a tie-break so the choice is deterministic.
+pub(super) fn require_witnesses_if_policy(conn: &Connection, event: &SignedEvent) -> Result<(), AppError> {
+    if event.event_type == WITNESS_REQUIRED || event.event_type == ELECTRONIC_SIGNATURE {
+        return Ok(());
+    }
+    let Some(user_id) = event.user_id.as_deref() else {
+        return Ok(());
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM witness_policies WHERE event_type = ?1 AND enabled = 1 \
+             ORDER BY required_count DESC, id ASC",
+            POLICY_COLUMNS
+        ))?;
+    let policies = stmt
+        .query_map(params![event.event_type], map_policy)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if policies.is_empty() {
+        return Ok(());
+    }
+    let payload: serde_json::Value = serde_json::from_str(&event.payload).unwrap_or(serde_json::Value::Null);
+    let Some(policy) = policies.iter().find(|p| policy_matches(p, &payload)) else {
+        return Ok(());
+    };
+    let requirement = json!({
+        "event": WITNESS_REQUIRED,
+        "witnessed_event_id": event.id,
+        "witnessed_event_hash": event.event_hash,
+        "witnessed_event_type": event.event_type,
+        "policy_id": policy.id,
+        "label": policy.label,
+        "required_count": policy.required_count,
+        "allowed_roles": policy.allowed_roles,
+    })
+    .to_string();
+    super::append_signed_event(
+        conn, user_id, WITNESS_REQUIRED, &event.entity_type, event.entity_id.as_deref(), &requirement,
+    )?;
+    Ok(())
+}
+
+struct LedgerRow {
+    id: String,
+    seq: i64,
+    entity_type: String,
+    entity_id: Option<String>,
+    user_id: Option<String>,
+    payload: serde_json::Value,
+    event_hash: String,
+}
+
+fn ledger_rows(conn: &Connection, sql: &str, p: &[&dyn rusqlite::ToSql]) -> Result<Vec<LedgerRow>, AppError> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map(p, |r| {
+            Ok(LedgerRow {
+                id: r.get(0)?,
+                seq: r.get(1)?,
+                entity_type: r.get(2)?,
+                entity_id: r.get(3)?,
+                user_id: r.get(4)?,
+                payload: serde_json::from_str(&r.get::<_, String>(5)?).unwrap_or(serde_json::Value::Null),
+                event_hash: r.get(6)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read the signed event ledger: {}", e))?;
+    Ok(rows)
+}
+
+const LEDGER_COLUMNS: &str = "id, seq, entity_type, entity_id, user_id, payload, event_hash";
+
+/// The status of every witness requirement in the ledger, oldest first, derived
+/// from ledger payloads alone.
+pub fn witness_report(conn: &Connection) -> Result<Vec<WitnessStatus>, AppError> {
+    let requirements = ledger_rows(
+        conn,
+        &format!("SELECT {} FROM signed_events WHERE event_type = ?1 ORDER BY seq ASC", LEDGER_COLUMNS),
+        &[&WITNESS_REQUIRED],
+    )?;
+    if requirements.is_empty() {
+        return Ok(Vec::new());
+    }
+    let countersigs = ledger_rows(
+        conn,
+        &format!(
+            "SELECT {} FROM signed_events WHERE event_type = ?1 AND entity_type = 'signed_event' ORDER BY seq ASC",
+            LEDGER_COLUMNS
+        ),
+        &[&ELECTRONIC_SIGNATURE],
+    )?;
+
+    let mut report = Vec::with_capacity(requirements.len());
+    for req in requirements {
+        let p = &req.payload;
+        let text = |k: &str| p.get(k).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let event_id = text("witnessed_event_id");
+        let required_count = p.get("required_count").and_then(|v| v.as_i64()).unwrap_or(1);
+        let allowed_roles: Vec<String> = p
+            .get("allowed_roles")
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|r| r.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        let witnessed = ledger_rows(
+            conn,
+            &format!("SELECT {} FROM signed_events WHERE id = ?1", LEDGER_COLUMNS),
+            &[&event_id],
+        )?
+        .pop();
+        let mut rejected = Vec::new();
+        let signed_by = witnessed.as_ref().and_then(|w| w.user_id.clone());
+        match &witnessed {
+            None => rejected.push("the witnessed event is missing from the ledger".to_string()),
+            Some(w) if w.event_hash != text("witnessed_event_hash") => {
+                rejected.push("the witnessed event's hash differs from the one the requirement names".to_string())
+            }
+            _ => {}
+        }
+
+        let mut witnesses: Vec<Countersignature> = Vec::new();
+        for cs in countersigs.iter().filter(|c| c.entity_id.as_deref() == Some(event_id.as_str())) {
+            let cp = &cs.payload;
+            let field = |k: &str| cp.get(k).and_then(|v| v.as_str()).unwrap_or("").to_string();
+            if field("action") != WITNESS_COUNTERSIGNATURE {
+                continue;
+            }
+            let who = cs.user_id.clone().unwrap_or_default();
+            let name = field("printed_name");
+            let role = field("signer_role");
+            if cs.seq < req.seq {
+                rejected.push(format!("{} signed before the requirement was recorded", name));
+            } else if Some(&who) == signed_by.as_ref() {
+                rejected.push(format!("{} signed the event and cannot also witness it", name));
+            } else if !allowed_roles.contains(&role) {
+                rejected.push(format!("{} signed as '{}', which is not a permitted witness role", name, role));
+            } else if witnesses.iter().any(|w| w.user_id == who) {
+                rejected.push(format!("{} countersigned more than once; counted once", name));
+            } else {
+                witnesses.push(Countersignature {
+                    signed_event_id: cs.id.clone(),
+                    user_id: who,
+                    printed_name: name,
+                    role,
+                    meaning: field("meaning"),
+                    signed_at: field("signed_at"),
+                });
+            }
+        }
+
+        let satisfied = witnessed.is_some() && witnesses.len() as i64 >= required_count;
+        report.push(WitnessStatus {
+            requirement_event_id: req.id,
+            event_id,
+            event_seq: witnessed.as_ref().map(|w| w.seq),
+            event_type: text("witnessed_event_type"),
+            entity_type: req.entity_type,
+            entity_id: req.entity_id,
+            signed_by,
+            label: text("label"),
+            required_count,
+            allowed_roles,
+            witnesses,
+            rejected,
+            satisfied,
+        });
+    }
+    Ok(report)
+}
+
+/// Requirements still waiting for witnesses.
+pub fn pending_witness(conn: &Connection) -> Result<Vec<WitnessStatus>, AppError> {
+    Ok(witness_report(conn)?.into_iter().filter(|s| !s.satisfied).collect())
+}
+
+/// Check `witness` may countersign `event_id` before asking for their password.
+/// This applies the same rules [`witness_report`] enforces, so a countersignature
+/// the UI accepts always counts.
+pub fn check_can_countersign(conn: &Connection, witness: &User, event_id: &str) -> Result<WitnessStatus, AppError> {
+    let status = witness_report(conn)?
+        .into_iter()
+        .find(|s| s.event_id == event_id)
+        .ok_or_else(|| AppError::invalid("That event does not require a witness"))?;
+    if status.satisfied {
+        return Err(AppError::conflict("That event already has its required witnesses"));
+    }
+    if status.signed_by.as_deref() == Some(witness.id.as_str()) {
+        return Err(AppError::forbidden("You cannot witness an event you signed yourself"));
+    }
+    if !status.allowed_roles.iter().any(|r| r == witness.role.as_str()) {
+        return Err(AppError::forbidden(format!(
+            "Witnessing '{}' requires one of these roles: {}",
+            status.label,
+            status.allowed_roles.join(", ")
+        )));
+    }
+    if status.witnesses.iter().any(|w| w.user_id == witness.id) {
+        return Err(AppError::conflict("You have already witnessed this event"));
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_all;
+    use crate::signed_ledger::{append_signed_event, lifecycle, verify_ledger};
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_all(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO users (id, username, password_hash, display_name, role) VALUES
+                ('tech1', 't1', 'x', 'Tess Tech', 'tech'),
+                ('sup1', 's1', 'x', 'Sam Super', 'supervisor'),
+                ('sup2', 's2', 'x', 'Sky Super', 'supervisor');",
+        )
+        .unwrap();
+        conn
+    }
+
+    /// Append a countersignature the way `record_signature` does, without the
+    /// password ceremony (covered in `esignature`).
+    fn countersign(conn: &Connection, user_id: &str, name: &str, role: &str, event_id: &str) {
+        let payload = json!({
+            "printed_name": name, "signer_role": role, "meaning": "reviewed",
+            "action": WITNESS_COUNTERSIGNATURE, "entity_type": "signed_event",
+            "entity_id": event_id, "signed_at": "2026-10-18T10:00:00.000Z",
+        })
+        .to_string();
+        append_signed_event(conn, user_id, ELECTRONIC_SIGNATURE, "signed_event", Some(event_id), &payload).unwrap();
+    }
+
+    #[test]
+    fn a_split_is_pending_witness_until_a_supervisor_countersigns() {
+        let conn = test_db();
+        let split = append_signed_event(&conn, "tech1", lifecycle::SPECIMEN_SPLIT, "specimen", Some("spec1"), "{}").unwrap();
+        let pending = pending_witness(&conn).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].event_id, split.id);
+        assert_eq!(pending[0].signed_by.as_deref(), Some("tech1"));
+
+        let v = verify_ledger(&conn).unwrap();
+        assert!(v.verified, "{}", v.message);
+        assert_eq!(v.pending_witness.len(), 1);
+
+        countersign(&conn, "sup1", "Sam Super", "supervisor", &split.id);
+        assert!(pending_witness(&conn).unwrap().is_empty());
+        let v = verify_ledger(&conn).unwrap();
+        assert!(v.verified, "{}", v.message);
+        assert!(v.pending_witness.is_empty());
+    }
+
+    #[test]
+    fn self_witness_wrong_role_and_duplicates_do_not_count() {
+        let conn = test_db();
+        conn.execute("UPDATE witness_policies SET required_count = 2 WHERE event_type = ?1", [lifecycle::SPECIMEN_SPLIT])
+            .unwrap();
+        let split = append_signed_event(&conn, "sup1", lifecycle::SPECIMEN_SPLIT, "specimen", Some("spec1"), "{}").unwrap();
+        countersign(&conn, "sup1", "Sam Super", "supervisor", &split.id);
+        countersign(&conn, "tech1", "Tess Tech", "tech", &split.id);
+        countersign(&conn, "sup2", "Sky Super", "supervisor", &split.id);
+        countersign(&conn, "sup2", "Sky Super", "supervisor", &split.id);
+
+        let status = witness_report(&conn).unwrap().remove(0);
+        assert_eq!(status.witnesses.len(), 1);
+        assert_eq!(status.rejected.len(), 3, "{:?}", status.rejected);
+        assert!(!status.satisfied);
+    }
+
+    #[test]
+    fn payload_match_narrows_a_policy_to_one_value() {
+        let conn = test_db();
+        let claimed = lifecycle::mutation(lifecycle::STRAIN_STATUS_CHANGED, "strain", Some("st1"), "status_change", Some("claimed"), None, "h");
+        append_signed_event(&conn, "tech1", lifecycle::STRAIN_STATUS_CHANGED, "strain", Some("st1"), &claimed).unwrap();
+        assert!(witness_report(&conn).unwrap().is_empty());
+
+        let confirmed = lifecycle::mutation(lifecycle::STRAIN_STATUS_CHANGED, "strain", Some("st1"), "status_change", Some("confirmed_manual"), None, "h");
+        append_signed_event(&conn, "tech1", lifecycle::STRAIN_STATUS_CHANGED, "strain", Some("st1"), &confirmed).unwrap();
+        assert_eq!(pending_witness(&conn).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn the_requirement_is_a_snapshot_of_the_policy() {
+        let conn = test_db();
+        let split = append_signed_event(&conn, "tech1", lifecycle::SPECIMEN_SPLIT, "specimen", Some("spec1"), "{}").unwrap();
+        // Disabling the policy afterwards does not excuse the earlier event.
+        conn.execute("UPDATE witness_policies SET enabled = 0", []).unwrap();
+        assert_eq!(pending_witness(&conn).unwrap()[0].event_id, split.id);
+        append_signed_event(&conn, "tech1", lifecycle::SPECIMEN_SPLIT, "specimen", Some("spec2"), "{}").unwrap();
+        assert_eq!(pending_witness(&conn).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn check_can_countersign_applies_the_report_rules() {
+        let conn = test_db();
+        let split = append_signed_event(&conn, "tech1", lifecycle::SPECIMEN_SPLIT, "specimen", Some("spec1"), "{}").unwrap();
+        let user = |id: &str, role: crate::models::user::UserRole| User {
+            id: id.to_string(), username: id.to_string(), password_hash: String::new(),
+            display_name: id.to_string(), email: None, role, is_active: true,
+            must_change_password: false, created_at: String::new(), updated_at: String::new(),
+            auth_source: "local".into(),
+            access_expires_at: None, token_scope: None,
+        };
+        use crate::models::user::UserRole;
+        assert!(check_can_countersign(&conn, &user("tech1", UserRole::Tech), &split.id).is_err());
+        assert!(check_can_countersign(&conn, &user("sup1", UserRole::Supervisor), &split.id).is_ok());
+        assert!(check_can_countersign(&conn, &user("sup1", UserRole::Supervisor), "no-such-event").is_err());
+    }
+
+    #[test]
+    fn policies_reject_unknown_event_types_and_roles() {
+        let conn = test_db();
+        let mut p = WitnessPolicy {
+            id: "p".into(), event_type: lifecycle::VIAL_THAWED.into(), match_field: None, match_value: None,
+            label: "Thaw".into(), required_count: 1, allowed_roles: vec!["supervisor".into()], enabled: true,
+        };
+        assert!(validate_policy(&conn, &p).is_ok());
+        p.event_type = WITNESS_REQUIRED.into();
+        assert!(validate_policy(&conn, &p).is_err(), "a requirement must not be able to require itself");
+        p.event_type = lifecycle::VIAL_THAWED.into();
+        p.allowed_roles = vec!["wizard".into()];
+        assert!(validate_policy(&conn, &p).is_err());
+        p.allowed_roles = vec!["admin".into()];
+        p.required_count = 0;
+        assert!(validate_policy(&conn, &p).is_err());
+    }
+}
@@ -0,0 +1,541 @@
+// Small statistics toolkit for the analyses the app runs itself: summaries,
+// one- and two-way ANOVA, Tukey HSD, odds ratios and Fisher's exact test on
+// 2 × 2 tables, and the distributions behind their p-values. Pure functions
+// over `f64`, no I/O.
+//
+// Accuracy targets are those of a lab report, not a statistics package:
+// p-values are good to about four decimal places.
+use serde::Serialize;
+
+/// n, mean, sample SD, standard error, min and max of a sample.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    pub n: usize,
+    pub mean: f64,
+    /// `None` below two observations.
+    pub sd: Option<f64>,
+    pub se: Option<f64>,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// `None` for an empty sample.
+pub fn summarize(xs: &[f64]) -> Option<Summary> {
+    if xs.is_empty() {
+        return None;
+    }
+    let n = xs.len();
+    let mean = xs.iter().sum::<f64>() / n as f64;
+    let sd = (n > 1).then(|| (xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt());
+    Some(Summary {
+        n,
+        mean,
+        sd,
+        se: sd.map(|sd| sd / (n as f64).sqrt()),
+        min: xs.iter().copied().fold(f64::INFINITY, f64::min),
+        max: xs.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+    })
+}
+
+// ── Distributions ───────────────────────────────────────────────────────────
+
+/// ln Γ(x) for x > 0 (Lanczos, g = 7).
+pub fn ln_gamma(x: f64) -> f64 {
+    const C: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection keeps the series in its accurate range.
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = C[1..].iter().enumerate().fold(C[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// The regularized incomplete beta function I_x(a, b).
+pub fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges fast on this side of the mean.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// Lentz's method for the continued fraction of I_x(a, b).
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    d = 1.0 / if d.abs() < TINY { TINY } else { d };
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        for num in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + num * d;
+            d = 1.0 / if d.abs() < TINY { TINY } else { d };
+            c = 1.0 + num / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-14 {
+            break;
+        }
+    }
+    h
+}
+
+/// P(F > f) for an F distribution with `d1` and `d2` degrees of freedom.
+pub fn f_sf(f: f64, d1: f64, d2: f64) -> f64 {
+    if f.is_nan() || f <= 0.0 {
+        return 1.0;
+    }
+    incomplete_beta(d2 / 2.0, d1 / 2.0, d2 / (d2 + d1 * f)).clamp(0.0, 1.0)
+}
+
+/// The complementary error function, to about 1e-7 relative.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.265_512_23
+            + t * (1.000_023_68
+                + t * (0.374_091_96
+                    + t * (0.096_784_18
+                        + t * (-0.186_288_06
+                            + t * (0.278_868_07
+                                + t * (-1.135_203_98 + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+/// The standard normal CDF.
+pub fn normal_cdf(z: f64) -> f64 {
+    0.5 * erfc(-z / std::f64::consts::SQRT_2)
+}
+
+/// The `p` quantile of the standard normal, by bisection.
+pub fn normal_quantile(p: f64) -> f64 {
+    let (mut lo, mut hi) = (-40.0, 40.0);
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if normal_cdf(mid) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+/// Composite Simpson's rule over `[lo, hi]` with `n` (even) intervals.
+fn simpson(lo: f64, hi: f64, n: usize, f: impl Fn(f64) -> f64) -> f64 {
+    let h = (hi - lo) / n as f64;
+    let inner: f64 = (1..n).map(|i| f(lo + i as f64 * h) * if i % 2 == 1 { 4.0 } else { 2.0 }).sum();
+    (f(lo) + inner + f(hi)) * h / 3.0
+}
+
+/// P(range of `k` standard normals ≤ w).
+fn normal_range_cdf(w: f64, k: f64) -> f64 {
+    if w <= 0.0 {
+        return 0.0;
+    }
+    let density = |z: f64| (-0.5 * z * z).exp() / (2.0 * std::f64::consts::PI).sqrt();
+    let p = k * simpson(-8.0, 8.0, 200, |z| density(z) * (normal_cdf(z) - normal_cdf(z - w)).max(0.0).powf(k - 1.0));
+    p.clamp(0.0, 1.0)
+}
+
+/// P(Q ≤ q) for the studentized range of `k` means with `df` error degrees of
+/// freedom: the normal range integrated over the distribution of s/σ.
+pub fn studentized_range_cdf(q: f64, k: f64, df: f64) -> f64 {
+    if q <= 0.0 {
+        return 0.0;
+    }
+    if df > 25_000.0 {
+        return normal_range_cdf(q, k);
+    }
+    // s/σ is chi(df)/√df: centred on 1, with spread about 1/√(2·df).
+    let ln_norm = (df / 2.0) * df.ln() - ln_gamma(df / 2.0) - (df / 2.0 - 1.0) * 2f64.ln();
+    let density = |s: f64| {
+        if s <= 0.0 {
+            return if df == 1.0 { (2.0 / std::f64::consts::PI).sqrt() } else { 0.0 };
+        }
+        (ln_norm + (df - 1.0) * s.ln() - df * s * s / 2.0).exp()
+    };
+    let spread = 10.0 / (2.0 * df).sqrt();
+    let (lo, hi) = ((1.0 - spread).max(0.0), 1.0 + spread.max(1.0));
+    simpson(lo, hi, 200, |s| density(s) * normal_range_cdf(q * s, k)).clamp(0.0, 1.0)
+}
+
+/// The `p` quantile of the studentized range, by bisection.
+pub fn studentized_range_quantile(p: f64, k: f64, df: f64) -> f64 {
+    let (mut lo, mut hi) = (0.0, 100.0);
+    for _ in 0..60 {
+        let mid = (lo + hi) / 2.0;
+        if studentized_range_cdf(mid, k, df) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+        if hi - lo < 1e-6 {
+            break;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+// ── ANOVA ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnovaRow {
+    pub term: String,
+    pub df: usize,
+    pub ss: f64,
+    /// `None` on the total row.
+    pub ms: Option<f64>,
+    /// Only on effect rows, and only when there are residual degrees of freedom.
+    pub f: Option<f64>,
+    pub p: Option<f64>,
+}
+
+/// Effect rows, then `Residual` and `Total`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnovaTable {
+    pub rows: Vec<AnovaRow>,
+}
+
+impl AnovaTable {
+    pub fn residual(&self) -> Option<&AnovaRow> {
+        self.rows.iter().find(|r| r.term == "Residual")
+    }
+
+    fn build(effects: Vec<(String, usize, f64)>, residual_df: usize, residual_ss: f64, total_ss: f64) -> AnovaTable {
+        let mse = (residual_df > 0).then(|| residual_ss / residual_df as f64);
+        let mut rows: Vec<AnovaRow> = effects
+            .into_iter()
+            .map(|(term, df, ss)| {
+                let ms = ss.max(0.0) / df as f64;
+                let f = mse.filter(|m| *m > 0.0).map(|m| ms / m);
+                AnovaRow { term, df, ss: ss.max(0.0), ms: Some(ms), f, p: f.map(|f| f_sf(f, df as f64, residual_df as f64)) }
+            })
+            .collect();
+        let total_df = rows.iter().map(|r| r.df).sum::<usize>() + residual_df;
+        rows.push(AnovaRow { term: "Residual".into(), df: residual_df, ss: residual_ss, ms: mse, f: None, p: None });
+        rows.push(AnovaRow { term: "Total".into(), df: total_df, ss: total_ss, ms: None, f: None, p: None });
+        AnovaTable { rows }
+    }
+}
+
+fn sum_sq_about_mean(xs: impl Iterator<Item = f64> + Clone) -> f64 {
+    let (n, sum) = xs.clone().fold((0usize, 0.0), |(n, s), x| (n + 1, s + x));
+    if n == 0 {
+        return 0.0;
+    }
+    let mean = sum / n as f64;
+    xs.map(|x| (x - mean).powi(2)).sum()
+}
+
+/// The residual sum of squares after fitting a mean per `key` group.
+fn within_groups_ss<K: Ord>(obs: &[(usize, usize, f64)], key: impl Fn(&(usize, usize, f64)) -> K) -> f64 {
+    let mut groups: std::collections::BTreeMap<K, Vec<f64>> = Default::default();
+    for o in obs {
+        groups.entry(key(o)).or_default().push(o.2);
+    }
+    groups.values().map(|g| sum_sq_about_mean(g.iter().copied())).sum()
+}
+
+/// One-way ANOVA over `groups`, ignoring empty ones. `None` unless at least
+/// two groups have data.
+pub fn one_way_anova(term: &str, groups: &[Vec<f64>]) -> Option<AnovaTable> {
+    let groups: Vec<&Vec<f64>> = groups.iter().filter(|g| !g.is_empty()).collect();
+    if groups.len() < 2 {
+        return None;
+    }
+    let n: usize = groups.iter().map(|g| g.len()).sum();
+    let total_ss = sum_sq_about_mean(groups.iter().flat_map(|g| g.iter().copied()));
+    let within: f64 = groups.iter().map(|g| sum_sq_about_mean(g.iter().copied())).sum();
+    Some(AnovaTable::build(vec![(term.to_string(), groups.len() - 1, total_ss - within)], n - groups.len(), within, total_ss))
+}
+
+/// Two-way ANOVA with interaction over `(level of A, level of B, value)`.
+/// Sums of squares are type II, so an unbalanced design is handled: each main
+/// effect is tested after the other, the interaction after both. The additive
+/// model is fitted by backfitting. `None` unless both factors have two levels
+/// with data.
+pub fn two_way_anova(a_term: &str, b_term: &str, obs: &[(usize, usize, f64)]) -> Option<AnovaTable> {
+    use std::collections::{BTreeMap, BTreeSet};
+    let a_levels: BTreeSet<usize> = obs.iter().map(|o| o.0).collect();
+    let b_levels: BTreeSet<usize> = obs.iter().map(|o| o.1).collect();
+    if a_levels.len() < 2 || b_levels.len() < 2 {
+        return None;
+    }
+    let cells: BTreeSet<(usize, usize)> = obs.iter().map(|o| (o.0, o.1)).collect();
+    let n = obs.len();
+
+    let rss_a = within_groups_ss(obs, |o| o.0);
+    let rss_b = within_groups_ss(obs, |o| o.1);
+    let rss_cells = within_groups_ss(obs, |o| (o.0, o.1));
+    let total_ss = sum_sq_about_mean(obs.iter().map(|o| o.2));
+
+    // Additive model y = α_a + β_b by alternating conditional means.
+    let mut alpha: BTreeMap<usize, f64> = a_levels.iter().map(|&a| (a, 0.0)).collect();
+    let mut beta: BTreeMap<usize, f64> = b_levels.iter().map(|&b| (b, 0.0)).collect();
+    let scale = total_ss.max(1.0);
+    for _ in 0..1_000 {
+        let mut change: f64 = 0.0;
+        for (&a, slot) in alpha.iter_mut() {
+            let resid: Vec<f64> = obs.iter().filter(|o| o.0 == a).map(|o| o.2 - beta[&o.1]).collect();
+            let next = resid.iter().sum::<f64>() / resid.len() as f64;
+            change = change.max((next - *slot).abs());
+            *slot = next;
+        }
+        for (&b, slot) in beta.iter_mut() {
+            let resid: Vec<f64> = obs.iter().filter(|o| o.1 == b).map(|o| o.2 - alpha[&o.0]).collect();
+            let next = resid.iter().sum::<f64>() / resid.len() as f64;
+            change = change.max((next - *slot).abs());
+            *slot = next;
+        }
+        if change * change < 1e-20 * scale {
+            break;
+        }
+    }
+    let rss_additive: f64 = obs.iter().map(|o| (o.2 - alpha[&o.0] - beta[&o.1]).powi(2)).sum();
+
+    let (a, b) = (a_levels.len(), b_levels.len());
+    let mut effects = vec![
+        (a_term.to_string(), a - 1, rss_b - rss_additive),
+        (b_term.to_string(), b - 1, rss_a - rss_additive),
+    ];
+    // Empty cells cost interaction degrees of freedom (connected designs).
+    let interaction_df = (cells.len() + 1).saturating_sub(a + b);
+    if interaction_df > 0 {
+        effects.push((format!("{} × {}", a_term, b_term), interaction_df, rss_additive - rss_cells));
+    }
+    Some(AnovaTable::build(effects, n - cells.len(), rss_cells, total_ss))
+}
+
+// ── Tukey HSD ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TukeyComparison {
+    /// Group indices as passed in.
+    pub a: usize,
+    pub b: usize,
+    /// mean(a) − mean(b).
+    pub diff: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub q: f64,
+    pub p: f64,
+}
+
+/// Tukey–Kramer pairwise comparisons of every pair of non-empty `groups`,
+/// using the residual mean square `mse` on `df` degrees of freedom, with
+/// simultaneous intervals at `confidence`.
+pub fn tukey_hsd(groups: &[Vec<f64>], mse: f64, df: usize, confidence: f64) -> Vec<TukeyComparison> {
+    let present: Vec<(usize, f64, usize)> = groups
+        .iter()
+        .enumerate()
+        .filter(|(_, g)| !g.is_empty())
+        .map(|(i, g)| (i, g.iter().sum::<f64>() / g.len() as f64, g.len()))
+        .collect();
+    if present.len() < 2 || df == 0 || mse.is_nan() || mse <= 0.0 {
+        return Vec::new();
+    }
+    let k = present.len() as f64;
+    let critical = studentized_range_quantile(confidence, k, df as f64);
+    let mut out = Vec::new();
+    for (i, &(a, mean_a, n_a)) in present.iter().enumerate() {
+        for &(b, mean_b, n_b) in &present[i + 1..] {
+            let se = (mse / 2.0 * (1.0 / n_a as f64 + 1.0 / n_b as f64)).sqrt();
+            let diff = mean_a - mean_b;
+            let q = diff.abs() / se;
+            out.push(TukeyComparison {
+                a,
+                b,
+                diff,
+                lower: diff - critical * se,
+                upper: diff + critical * se,
+                q,
+                p: (1.0 - studentized_range_cdf(q, k, df as f64)).clamp(0.0, 1.0),
+            });
+        }
+    }
+    out
+}
+
+// ── 2 × 2 tables ────────────────────────────────────────────────────────────
+//
+// Cells are `a` exposed cases, `b` exposed non-cases, `c` unexposed cases and
+// `d` unexposed non-cases.
+
+/// An odds ratio with its confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct OddsRatio {
+    pub odds_ratio: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// The odds ratio (a·d)/(b·c) with Woolf's logit interval at `confidence`.
+/// A zero cell adds 0.5 to every cell (Haldane–Anscombe), so the ratio and
+/// its interval stay finite.
+pub fn odds_ratio(a: u64, b: u64, c: u64, d: u64, confidence: f64) -> OddsRatio {
+    let shift = if a == 0 || b == 0 || c == 0 || d == 0 { 0.5 } else { 0.0 };
+    let [a, b, c, d] = [a, b, c, d].map(|x| x as f64 + shift);
+    let ln_or = (a * d / (b * c)).ln();
+    let half = normal_quantile(0.5 + confidence / 2.0) * (1.0 / a + 1.0 / b + 1.0 / c + 1.0 / d).sqrt();
+    OddsRatio { odds_ratio: ln_or.exp(), lower: (ln_or - half).exp(), upper: (ln_or + half).exp() }
+}
+
+/// Two-sided p-value of Fisher's exact test: the probability, with the
+/// margins fixed, of every table no more likely than the one observed.
+pub fn fisher_exact(a: u64, b: u64, c: u64, d: u64) -> f64 {
+    let ln_fact = |n: u64| ln_gamma(n as f64 + 1.0);
+    let (row1, col1, n) = (a + b, a + c, a + b + c + d);
+    let row2 = n - row1;
+    let ln_fixed = ln_fact(row1) + ln_fact(row2) + ln_fact(col1) + ln_fact(n - col1) - ln_fact(n);
+    let ln_p = |x: u64| ln_fixed - ln_fact(x) - ln_fact(row1 - x) - ln_fact(col1 - x) - ln_fact(row2 + x - col1);
+    let observed = ln_p(a);
+    let lo = col1.saturating_sub(row2);
+    let hi = row1.min(col1);
+    let p: f64 = (lo..=hi)
+        .map(ln_p)
+        .filter(|&l| l <= observed + 1e-7)
+        .map(f64::exp)
+        .sum();
+    p.min(1.0)
+}
+
+/// Benjamini–Hochberg adjusted p-values (q-values), in the order given.
+pub fn benjamini_hochberg(p: &[f64]) -> Vec<f64> {
+    let m = p.len();
+    let mut order: Vec<usize> = (0..m).collect();
+    order.sort_by(|&i, &j| p[j].total_cmp(&p[i]));
+    let mut q = vec![0.0; m];
+    let mut running: f64 = 1.0;
+    for (k, &i) in order.iter().enumerate() {
+        let rank = (m - k) as f64;
+        running = running.min(p[i] * m as f64 / rank);
+        q[i] = running;
+    }
+    q
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() <= tol
+    }
+
+    #[test]
+    fn f_and_studentized_range_match_published_tables() {
+        // F(2, ν) has a closed form: P(F > f) = (1 + 2f/ν)^(−ν/2).
+        assert!(close(f_sf(27.0, 2.0, 6.0), 0.001, 1e-9));
+        // t(4) = 3 is F(1, 4) = 9, two-sided p 0.0400.
+        assert!(close(f_sf(9.0, 1.0, 4.0), 0.039_94, 1e-4));
+        assert!(close(f_sf(4.256_5, 2.0, 9.0), 0.05, 1e-4));
+        // q(0.95; k, ν) from Tukey tables.
+        for (k, df, q) in [(3.0, 6.0, 4.339), (3.0, 10.0, 3.877), (4.0, 20.0, 3.958), (2.0, 1e9, 2.772)] {
+            let got = studentized_range_quantile(0.95, k, df);
+            assert!(close(got, q, 0.005), "q(0.95; {k}, {df}) = {got}, expected {q}");
+        }
+        assert!(close(normal_cdf(1.959_964), 0.975, 1e-6));
+        assert!(close(ln_gamma(5.0), 24f64.ln(), 1e-12));
+    }
+
+    #[test]
+    fn one_way_anova_and_tukey_on_three_groups() {
+        let groups = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0], vec![7.0, 8.0, 9.0], vec![]];
+        let t = one_way_anova("Treatment", &groups).unwrap();
+        assert_eq!((t.rows[0].df, t.rows[0].ss, t.rows[0].f), (2, 54.0, Some(27.0)));
+        assert!(close(t.rows[0].p.unwrap(), 0.001, 1e-9));
+        let resid = t.residual().unwrap();
+        assert_eq!((resid.df, resid.ss, resid.ms), (6, 6.0, Some(1.0)));
+        assert_eq!((t.rows[2].df, t.rows[2].ss), (8, 60.0));
+
+        let pairs = tukey_hsd(&groups, 1.0, 6, 0.95);
+        assert_eq!(pairs.iter().map(|c| (c.a, c.b)).collect::<Vec<_>>(), [(0, 1), (0, 2), (1, 2)]);
+        // Adjacent groups differ by 3 (q = 5.196): between the 5% and 1% critical values.
+        assert_eq!(pairs[0].diff, -3.0);
+        assert!(pairs[0].p > 0.01 && pairs[0].p < 0.05, "p = {}", pairs[0].p);
+        assert!(pairs[1].p < 0.01);
+        assert!(pairs[0].upper < 0.0 && pairs[0].lower < pairs[0].upper);
+    }
+
+    #[test]
+    fn two_way_anova_matches_the_balanced_textbook_decomposition() {
+        // 2 × 2 with two replicates; cell means 2, 6, 3, 11.
+        let obs = [
+            (0, 0, 1.0), (0, 0, 3.0), (0, 1, 5.0), (0, 1, 7.0),
+            (1, 0, 2.0), (1, 0, 4.0), (1, 1, 10.0), (1, 1, 12.0),
+        ];
+        let t = two_way_anova("BAP", "NAA", &obs).unwrap();
+        let ss: Vec<(String, usize, f64)> = t.rows.iter().map(|r| (r.term.clone(), r.df, (r.ss * 1e6).round() / 1e6)).collect();
+        assert_eq!(
+            ss,
+            [
+                ("BAP".into(), 1, 18.0),
+                ("NAA".into(), 1, 72.0),
+                ("BAP × NAA".into(), 1, 8.0),
+                ("Residual".into(), 4, 8.0),
+                ("Total".into(), 7, 106.0),
+            ]
+        );
+        assert!(close(t.rows[0].f.unwrap(), 9.0, 1e-9) && close(t.rows[2].f.unwrap(), 4.0, 1e-9));
+        assert!(close(t.rows[0].p.unwrap(), 0.039_94, 1e-4));
+
+        // Unbalanced: dropping one observation still partitions sensibly.
+        let t = two_way_anova("BAP", "NAA", &obs[1..]).unwrap();
+        assert_eq!(t.residual().unwrap().df, 3);
+        assert!(t.rows.iter().all(|r| r.ss >= 0.0));
+        assert!(two_way_anova("BAP", "NAA", &[(0, 0, 1.0), (0, 1, 2.0)]).is_none());
+    }
+
+    #[test]
+    fn two_by_two_tables() {
+        // Fisher's tea-tasting table.
+        assert!(close(fisher_exact(3, 1, 1, 3), 0.485_714, 1e-6));
+        assert!(close(fisher_exact(10, 0, 0, 10), 1.0825e-5, 1e-8));
+        assert!(close(fisher_exact(0, 5, 0, 5), 1.0, 1e-12));
+
+        let or = odds_ratio(10, 20, 5, 40, 0.95);
+        assert!(close(or.odds_ratio, 4.0, 1e-12));
+        assert!(close(or.lower, 1.205, 1e-3) && close(or.upper, 13.28, 1e-2), "{or:?}");
+        let zero = odds_ratio(4, 0, 1, 5, 0.95);
+        assert!(zero.odds_ratio.is_finite() && zero.lower > 1.0);
+        assert!(close(normal_quantile(0.975), 1.959_964, 1e-6));
+
+        let q = benjamini_hochberg(&[0.01, 0.04, 0.03, 0.5]);
+        let want = [0.04, 0.04 * 4.0 / 3.0, 0.04 * 4.0 / 3.0, 0.5];
+        assert!(q.iter().zip(want).all(|(a, b)| close(*a, b, 1e-12)), "{q:?}");
+    }
+}
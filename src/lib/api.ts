@@ -1,10 +1,14 @@
 import { invoke } from '@tauri-apps/api/core';
-import { token, clearAuth } from './stores/auth';
+import { token, clearAuth, mustEnrollMfa } from './stores/auth';
 import { get } from 'svelte/store';
+import { isTauri } from './isTauri';
+import { AppError, toAppError } from './errors';
+
+export { AppError, invalidField, type ErrorCode } from './errors';
 
 function getToken(): string {
   const t = get(token);
-  if (!t) throw new Error('Not authenticated');
+  if (!t) throw new AppError('unauthenticated', 'Not authenticated');
   return t;
 }
 
@@ -12,24 +16,368 @@ async function call<T>(command: string, args: Record<string, unknown> = {}): Pro
   try {
     return await invoke<T>(command, { token: getToken(), ...args });
   } catch (e: unknown) {
-    const msg = typeof e === 'string' ? e : (e instanceof Error ? e.message : 'Unknown error');
-    if (msg.includes('Session expired or invalid') || msg.includes('Session expired')) {
+    // WP-93: react to the error's code, not its wording.
+    const err = toAppError(e);
+    if (err.code === 'unauthenticated') {
       clearAuth();
     }
-    throw new Error(msg);
+    // An admin can require 2FA for the user's role mid-session (WP-84).
+    if (err.code === 'forbidden' && err.params.reason === 'mfa_enrollment_required') {
+      mustEnrollMfa.set(true);
+    }
+    throw err;
   }
 }
 
 // Auth (login doesn't need token)
+export interface LoginResult {
+  token: string;
+  user: any;
+  must_change_password: boolean;
+  /** WP-84: the token is pending until `verifyLoginMfa` accepts a code. */
+  mfa_required: boolean;
+  /** WP-84: the user's role requires 2FA and they have not enrolled. */
+  mfa_enrollment_required: boolean;
+}
+
+/**
+ * WP-88: how this client describes itself in the admin session list, e.g.
+ * "Windows · SteloPTC desktop" or "Android · Chrome".
+ */
+function deviceLabel(): string {
+  const ua = typeof navigator === 'undefined' ? '' : navigator.userAgent;
+  const os = /Android/.test(ua) ? 'Android'
+    : /iPad|iPhone/.test(ua) ? 'iOS'
+    : /Windows/.test(ua) ? 'Windows'
+    : /Mac OS X/.test(ua) ? 'macOS'
+    : /Linux/.test(ua) ? 'Linux'
+    : 'Unknown OS';
+  const client = isTauri() ? 'SteloPTC desktop'
+    : /Edg\//.test(ua) ? 'Edge'
+    : /Firefox\//.test(ua) ? 'Firefox'
+    : /Chrome\//.test(ua) ? 'Chrome'
+    : /Safari\//.test(ua) ? 'Safari'
+    : 'browser';
+  return `${os} · ${client}`;
+}
+
 export async function login(username: string, password: string) {
   try {
-    return await invoke<{ token: string; user: any; must_change_password: boolean }>('login', { username, password });
+    return await invoke<LoginResult>('login', { username, password, device: deviceLabel() });
   } catch (e: unknown) {
-    const msg = typeof e === 'string' ? e : (e instanceof Error ? e.message : 'Login failed');
-    throw new Error(msg);
+    throw toAppError(e, 'Login failed');
   }
 }
 
+/**
+ * Second step of a two-factor login. Takes the pending token explicitly: it is
+ * not put in the auth store until this succeeds.
+ */
+export async function verifyLoginMfa(pendingToken: string, code: string) {
+  try {
+    return await invoke<LoginResult>('verify_login_mfa', { token: pendingToken, code });
+  } catch (e: unknown) {
+    throw toAppError(e, 'Verification failed');
+  }
+}
+
+// Two-factor authentication (WP-84)
+export interface MfaStatus {
+  enrolled: boolean;
+  required_by_policy: boolean;
+  enrollment_required: boolean;
+  recovery_codes_remaining: number;
+}
+
+export interface TotpEnrollment {
+  secret: string;
+  provisioning_uri: string;
+}
+
+export interface MfaPolicy {
+  role: string;
+  required: boolean;
+}
+
+export async function getMfaStatus() {
+  return call<MfaStatus>('get_mfa_status');
+}
+
+export async function beginTotpEnrollment() {
+  return call<TotpEnrollment>('begin_totp_enrollment');
+}
+
+/** Returns the recovery codes — shown once, never retrievable again. */
+export async function confirmTotpEnrollment(code: string) {
+  return call<string[]>('confirm_totp_enrollment', { code });
+}
+
+export async function disableTotp(code: string) {
+  return call<void>('disable_totp', { code });
+}
+
+export async function regenerateRecoveryCodes(code: string) {
+  return call<string[]>('regenerate_recovery_codes', { code });
+}
+
+export async function listMfaPolicy() {
+  return call<MfaPolicy[]>('list_mfa_policy');
+}
+
+export async function setMfaPolicy(role: string, required: boolean) {
+  return call<void>('set_mfa_policy', { role, required });
+}
+
+export async function resetUserTotp(userId: string) {
+  return call<void>('reset_user_totp', { userId });
+}
+
+// Sessions and account deactivation (WP-88)
+export interface SessionInfo {
+  id: string;
+  user_id: string;
+  username: string;
+  display_name: string;
+  role: string;
+  device: string | null;
+  created_at: string;
+  last_seen_at: string | null;
+  expires_at: string;
+  mfa_pending: boolean;
+  /** The session this client is using. */
+  current: boolean;
+}
+
+/** Limits in minutes and hours; `null` means no limit of that kind. */
+export interface SessionPolicy {
+  role: string;
+  idle_minutes: number | null;
+  absolute_hours: number | null;
+}
+
+export async function listSessions() {
+  return call<SessionInfo[]>('list_sessions');
+}
+
+export async function revokeSession(sessionId: string) {
+  return call<void>('revoke_session', { sessionId });
+}
+
+/** Returns how many sessions ended. */
+export async function revokeUserSessions(userId: string) {
+  return call<number>('revoke_user_sessions', { userId });
+}
+
+export async function setUserActive(userId: string, active: boolean) {
+  return call<void>('set_user_active', { userId, active });
+}
+
+export async function listSessionPolicy() {
+  return call<SessionPolicy[]>('list_session_policy');
+}
+
+export async function setSessionPolicy(role: string, idleMinutes: number | null, absoluteHours: number | null) {
+  return call<void>('set_session_policy', { role, idleMinutes, absoluteHours });
+}
+
+// Lockout and password policy (WP-89)
+export interface AuthPolicy {
+  lockout_threshold: number;
+  lockout_minutes: number;
+  password_min_length: number;
+  password_history: number;
+  password_max_age_days: number | null;
+  breach_check: boolean;
+}
+
+export interface AuthPolicyView extends AuthPolicy {
+  /** Folder the breached-password list is read from. */
+  breach_list_path: string;
+  breach_list_present: boolean;
+}
+
+export interface LockedAccount {
+  username: string;
+  /** Null when no account has this name. */
+  user_id: string | null;
+  failures: number;
+  last_failure_at: string;
+  locked_until: string;
+}
+
+export async function getAuthPolicy() {
+  return call<AuthPolicyView>('get_auth_policy');
+}
+
+export async function setAuthPolicy(policy: AuthPolicy) {
+  return call<AuthPolicyView>('set_auth_policy', { policy });
+}
+
+export async function listLockedAccounts() {
+  return call<LockedAccount[]>('list_locked_accounts');
+}
+
+export async function unlockAccount(username: string) {
+  return call<void>('unlock_account', { username });
+}
+
+// Local REST API (WP-90)
+export interface ApiConfig {
+  enabled: boolean;
+  /** `loopback` (this computer only) or `lan`. */
+  bind: 'loopback' | 'lan';
+  port: number;
+  allowed_origins: string[];
+}
+
+export interface ApiStatus extends ApiConfig {
+  /** `host:port` while the server runs. */
+  listening_on: string | null;
+}
+
+export async function getApiStatus() {
+  return call<ApiStatus>('get_api_status');
+}
+
+export async function setApiConfig(config: ApiConfig) {
+  return call<ApiStatus>('set_api_config', { config });
+}
+
+// Service accounts and API tokens (WP-92)
+export interface ApiTokenInfo {
+  id: string;
+  name: string;
+  user_id: string;
+  username: string;
+  display_name: string;
+  role: string;
+  /** `read` and capability keys. */
+  scopes: string[];
+  /** The token's first characters, to tell tokens apart. */
+  hint: string;
+  created_by: string | null;
+  created_at: string;
+  expires_at: string | null;
+  last_used_at: string | null;
+  revoked_at: string | null;
+  expired: boolean;
+}
+
+export interface CreatedApiToken {
+  /** Shown once; only a digest is stored. */
+  token: string;
+  info: ApiTokenInfo;
+}
+
+export async function listApiTokens() {
+  return call<ApiTokenInfo[]>('list_api_tokens');
+}
+
+/** Returns the new account's id. */
+export async function createServiceAccount(request: { username: string; display_name: string; role: string }) {
+  return call<string>('create_service_account', { request });
+}
+
+/** `expires_in_days: null` for a token that does not expire. */
+export async function createApiToken(request: {
+  service_account_id: string;
+  name: string;
+  scopes: string[];
+  expires_in_days: number | null;
+}) {
+  return call<CreatedApiToken>('create_api_token', { request });
+}
+
+export async function revokeApiToken(tokenId: string) {
+  return call<void>('revoke_api_token', { tokenId });
+}
+
+// Directory (LDAP / Active Directory) authentication (WP-85)
+export interface LdapConfig {
+  enabled: boolean;
+  url: string | null;
+  starttls: boolean;
+  bind_dn: string | null;
+  bind_password_set: boolean;
+  user_base_dn: string | null;
+  user_filter: string;
+  group_base_dn: string | null;
+  display_name_attribute: string;
+  email_attribute: string;
+  default_role: string | null;
+  timeout_secs: number;
+}
+
+/** `bind_password: null` keeps the stored service password. */
+export interface SetLdapConfigRequest {
+  enabled: boolean;
+  url: string;
+  starttls: boolean;
+  bind_dn: string | null;
+  bind_password: string | null;
+  user_base_dn: string;
+  user_filter: string;
+  group_base_dn: string | null;
+  display_name_attribute: string;
+  email_attribute: string;
+  default_role: string | null;
+  timeout_secs: number;
+}
+
+export interface DirectoryEntry {
+  dn: string;
+  display_name: string | null;
+  email: string | null;
+  groups: string[];
+  disabled: boolean;
+}
+
+export interface LdapTestResult {
+  message: string;
+  entry: DirectoryEntry | null;
+  mapped_role: string | null;
+}
+
+export interface LdapGroupMapping {
+  group_dn: string;
+  role: string;
+}
+
+export interface DirectorySyncReport {
+  checked: number;
+  updated: number;
+  deactivated: number;
+  reactivated: number;
+}
+
+export async function getLdapConfig() {
+  return call<LdapConfig>('get_ldap_config');
+}
+
+export async function setLdapConfig(request: SetLdapConfigRequest) {
+  return call<LdapConfig>('set_ldap_config', { request });
+}
+
+export async function testLdapConnection(username?: string) {
+  return call<LdapTestResult>('test_ldap_connection', { username: username || null });
+}
+
+export async function listLdapGroupMappings() {
+  return call<LdapGroupMapping[]>('list_ldap_group_mappings');
+}
+
+export async function setLdapGroupMapping(groupDn: string, role: string) {
+  return call<void>('set_ldap_group_mapping', { groupDn, role });
+}
+
+export async function deleteLdapGroupMapping(groupDn: string) {
+  return call<void>('delete_ldap_group_mapping', { groupDn });
+}
+
+export async function syncDirectoryAccounts() {
+  return call<DirectorySyncReport>('sync_directory_accounts');
+}
+
 /**
  * Change the signed-in user's password.
  *
@@ -65,6 +413,60 @@ export async function updateUserRole(userId: string, newRole: string) {
   return call<void>('update_user_role', { userId, newRole });
 }
 
+/** WP-86: `expiresOn` is a YYYY-MM-DD date (access ends at the end of that day, UTC), or null for no limit. */
+export async function setUserAccessExpiry(userId: string, expiresOn: string | null) {
+  return call<void>('set_user_access_expiry', { userId, expiresOn });
+}
+
+// Roles and capabilities (WP-86)
+export interface CapabilityInfo {
+  key: string;
+  group: string;
+  label: string;
+  /** The least privileged built-in role that held it before custom roles. */
+  baseline: 'tech' | 'supervisor' | 'admin';
+}
+
+export interface RoleSummary {
+  name: string;
+  label: string;
+  description: string | null;
+  builtin: boolean;
+  capabilities: string[];
+  user_count: number;
+}
+
+export interface SaveRoleRequest {
+  name: string;
+  label: string;
+  description: string | null;
+  capabilities: string[];
+}
+
+export async function getMyCapabilities() {
+  return call<string[]>('get_my_capabilities');
+}
+
+export async function listCapabilities() {
+  return call<CapabilityInfo[]>('list_capabilities');
+}
+
+export async function listRoles() {
+  return call<RoleSummary[]>('list_roles');
+}
+
+export async function createRole(request: SaveRoleRequest) {
+  return call<void>('create_role', { request });
+}
+
+export async function updateRole(request: SaveRoleRequest) {
+  return call<void>('update_role', { request });
+}
+
+export async function deleteRole(name: string) {
+  return call<void>('delete_role', { name });
+}
+
 // Specimens
 export async function listSpecimens(page = 1, perPage = 50) {
   return call<any>('list_specimens', { page, perPage });
@@ -78,6 +480,217 @@ export async function createSpecimen(request: any) {
   return call<any>('create_specimen', { request });
 }
 
+// WP-94: batch initiation. Species, stage and date come from the template;
+// `position` in an override is 1-based, in accession order.
+export interface BatchRowOverride {
+  position: number;
+  location?: string;
+  location_details?: string;
+  provenance?: string;
+  source_plant?: string;
+  health_status?: string;
+  environmental_notes?: string;
+  notes?: string;
+  employee_id?: string;
+}
+
+export interface BatchLabel {
+  position: number;
+  well: string | null;
+  specimen_id: string;
+  accession_number: string;
+  qr_code_data: string;
+  species_code: string;
+  stage: string;
+  initiation_date: string;
+  location: string | null;
+}
+
+export interface InitiationBatch {
+  batch_id: string;
+  first_accession: string;
+  last_accession: string;
+  labels: BatchLabel[];
+}
+
+export async function initiateSpecimenBatch(request: {
+  template: any;
+  count?: number;
+  plate?: { rows: number; columns: number };
+  overrides?: BatchRowOverride[];
+}) {
+  return call<InitiationBatch>('initiate_specimen_batch', { request });
+}
+
+// Accession number templates (WP-95)
+export type SequenceReset = 'never' | 'yearly' | 'monthly' | 'daily';
+export type SplitSuffix = 'letter' | 'number' | 'dot_number';
+
+export interface AccessionTemplate {
+  id: string;
+  lab_profile: string;
+  /** `null` for the profile-wide template. */
+  species_id: string | null;
+  species_code: string | null;
+  pattern: string;
+  sequence_reset: SequenceReset;
+  split_suffix: SplitSuffix;
+  updated_at: string;
+  updated_by: string | null;
+}
+
+export interface AccessionPreview {
+  first: string;
+  second: string;
+  split_child: string;
+}
+
+export async function listAccessionTemplates() {
+  return call<AccessionTemplate[]>('list_accession_templates');
+}
+
+/** `lab_profile` defaults to the active profile. */
+export async function saveAccessionTemplate(request: {
+  lab_profile?: string;
+  species_id: string | null;
+  pattern: string;
+  sequence_reset: SequenceReset;
+  split_suffix: SplitSuffix;
+}) {
+  return call<AccessionTemplate>('save_accession_template', { request });
+}
+
+export async function deleteAccessionTemplate(id: string) {
+  return call<void>('delete_accession_template', { id });
+}
+
+export async function previewAccessionTemplate(
+  pattern: string, sequenceReset: SequenceReset, splitSuffix: SplitSuffix, speciesCode: string, date: string,
+) {
+  return call<AccessionPreview>('preview_accession_template', { pattern, sequenceReset, splitSuffix, speciesCode, date });
+}
+
+// Custom fields per lab profile (WP-96)
+export type CustomFieldEntity = 'specimen' | 'subculture';
+export type CustomFieldType = 'text' | 'number' | 'date' | 'enum' | 'boolean';
+/** Keyed by field key. Numbers are numbers, dates `YYYY-MM-DD`, enums the option code. */
+export type CustomValues = Record<string, string | number | boolean | null>;
+
+export interface CustomFieldOption {
+  code: string;
+  label: string;
+}
+
+export interface CustomField {
+  id: string;
+  lab_profile: string;
+  entity: CustomFieldEntity;
+  key: string;
+  label: string;
+  field_type: CustomFieldType;
+  unit: string | null;
+  required: boolean;
+  sort_order: number;
+  options: CustomFieldOption[];
+  /** The plugin that shipped the field, if any. */
+  source_plugin: string | null;
+  retired: boolean;
+  updated_at: string;
+  updated_by: string | null;
+}
+
+export async function listCustomFields(entity?: CustomFieldEntity, includeRetired = false) {
+  return call<CustomField[]>('list_custom_fields', { entity: entity ?? null, includeRetired });
+}
+
+/** `lab_profile` defaults to the active profile. Saving an existing key updates it. */
+export async function saveCustomField(request: {
+  lab_profile?: string;
+  entity: CustomFieldEntity;
+  key: string;
+  label: string;
+  type: CustomFieldType;
+  unit?: string | null;
+  required: boolean;
+  sort_order: number;
+  options: CustomFieldOption[];
+}) {
+  return call<CustomField>('save_custom_field', { request });
+}
+
+export async function retireCustomField(id: string) {
+  return call<CustomField>('retire_custom_field', { id });
+}
+
+// Stage transition rules per lab profile (WP-98)
+export interface StageTransition {
+  lab_profile: string;
+  from_stage: string;
+  to_stage: string;
+  /** Specimen field keys, or `custom.<key>`, that must be filled in to make the move. */
+  required_fields: string[];
+  source_plugin: string | null;
+  updated_at: string;
+  updated_by: string | null;
+}
+
+export interface StageMachine {
+  lab_profile: string;
+  /** Empty means the profile does not restrict stage changes. */
+  transitions: StageTransition[];
+  requirable_fields: { key: string; label: string }[];
+}
+
+export async function listStageTransitions() {
+  return call<StageMachine>('list_stage_transitions');
+}
+
+/** Allows `from_stage` → `to_stage` in the active profile, or updates its required fields. */
+export async function saveStageTransition(request: { from_stage: string; to_stage: string; required_fields: string[] }) {
+  return call<StageTransition>('save_stage_transition', { request });
+}
+
+export async function deleteStageTransition(fromStage: string, toStage: string) {
+  return call<void>('delete_stage_transition', { fromStage, toStage });
+}
+
+/** The stages a specimen in `from` may move to: every stage when the profile is unrestricted. */
+export function allowedTargets(machine: StageMachine | null, from: string, stages: StageEntry[]): StageEntry[] {
+  if (!machine || machine.transitions.length === 0) return stages;
+  const to = new Set(machine.transitions.filter((t) => t.from_stage === from).map((t) => t.to_stage));
+  return stages.filter((s) => s.code === from || to.has(s.code));
+}
+
+// Specimen filters and saved searches (WP-97)
+export interface SavedSearch {
+  id: string;
+  name: string;
+  filter: string;
+  shared: boolean;
+  lab_profile: string;
+  owner_id: string;
+  owner_name: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export async function listSavedSearches() {
+  return call<SavedSearch[]>('list_saved_searches');
+}
+
+/** Pass `id` to change one of your own searches. */
+export async function saveSavedSearch(request: { id?: string; name: string; filter: string; shared: boolean }) {
+  return call<SavedSearch>('save_saved_search', { request });
+}
+
+export async function deleteSavedSearch(id: string) {
+  return call<void>('delete_saved_search', { id });
+}
+
+export async function specimenFilterFields() {
+  return call<{ specimen: string[]; subculture: string[] }>('specimen_filter_fields');
+}
+
 export async function updateSpecimen(request: any) {
   return call<any>('update_specimen', { request });
 }
@@ -90,6 +703,36 @@ export async function searchSpecimens(paramsInput: any) {
   return call<any>('search_specimens', { paramsInput });
 }
 
+// Merging duplicate specimens (WP-99)
+export interface SpecimenMerge {
+  id: string;
+  survivor_id: string;
+  survivor_accession: string;
+  retired_id: string;
+  retired_accession: string;
+  reason: string | null;
+  /** Rows moved to the survivor, per kind. */
+  moved: Record<string, number>;
+  merged_by: string | null;
+  merged_at: string;
+}
+
+/** Moves `retiredId`'s history onto `survivorId` and leaves `retiredId` as a redirecting tombstone. */
+export async function mergeSpecimens(survivorId: string, retiredId: string, reason?: string) {
+  return call<SpecimenMerge>('merge_specimens', {
+    request: { survivor_id: survivorId, retired_id: retiredId, reason: reason || null },
+  });
+}
+
+export async function listSpecimenMerges(id: string) {
+  return call<SpecimenMerge[]>('list_specimen_merges', { id });
+}
+
+/** Ids of every active-lab specimen the filter matches, for bulk actions. */
+export async function listMatchingSpecimenIds(filter: string) {
+  return call<string[]>('list_matching_specimen_ids', { filter });
+}
+
 export async function getSpecimenStats() {
   return call<any>('get_specimen_stats');
 }
@@ -98,10 +741,83 @@ export async function getSpecimenFamily(id: string) {
   return call<any[]>('get_specimen_family', { id });
 }
 
+// Lineage graph exports and metrics (WP-101)
+export type LineageFormat = 'graphml' | 'dot' | 'newick';
+
+export interface LineageMetrics {
+  root_id: string;
+  root_accession: string;
+  nodes: number;
+  edges: number;
+  /** Deepest generation below the root. */
+  depth: number;
+  leaves: number;
+  alive: number;
+  branching: { internal_nodes: number; mean_children: number; max_children: number };
+  generations: {
+    generation: number;
+    total: number;
+    alive: number;
+    split: number;
+    dead: number;
+    archived: number;
+    /** Share of the generation still alive or split onward. */
+    survival_rate: number;
+  }[];
+  /** Days from initiation to death or archive; null when nothing was lost. */
+  time_to_loss: { lost: number; mean_days: number; median_days: number; min_days: number; max_days: number } | null;
+  /** The root's children, each with the size of its subtree and how much of it lives. */
+  branches: { id: string; accession_number: string; nodes: number; living: number }[];
+}
+
+export async function getLineageMetrics(id: string) {
+  return call<LineageMetrics>('get_lineage_metrics', { id });
+}
+
+/** The whole lineage of `id` as GraphML, DOT or Newick text. */
+export async function exportLineage(id: string, format: LineageFormat) {
+  return call<string>('export_lineage', { id, format });
+}
+
 export async function bulkArchiveSpecimens(ids: string[]) {
   return call<number>('bulk_archive_specimens', { ids });
 }
 
+// Restoring archived and deleted specimens (WP-100)
+export interface RestorableSpecimen {
+  specimen_id: string;
+  accession_number: string;
+  /** The row is gone; restoring re-inserts it from the archive snapshot. */
+  deleted: boolean;
+  /** For deleted specimens: whether the audit chain proves the snapshot. */
+  proven: boolean | null;
+  archive_entry_id: string;
+  archived_at: string;
+  archived_by: string | null;
+  /** Shared by the specimens of one bulk archive. */
+  batch: string | null;
+  details: string | null;
+}
+
+export interface RestoredSpecimen {
+  specimen_id: string;
+  accession_number: string;
+  source_entry_id: string;
+  archived_at: string;
+  reinserted: boolean;
+  subcultures_reinserted: number;
+  children_relinked: number;
+}
+
+export async function listRestorableSpecimens() {
+  return call<RestorableSpecimen[]>('list_restorable_specimens');
+}
+
+/** All or nothing: one specimen that cannot be restored fails the lot. */
+export async function restoreSpecimens(ids: string[]) {
+  return call<RestoredSpecimen[]>('restore_specimens', { ids });
+}
+
 export async function bulkUpdateLocation(ids: string[], location: string) {
   return call<number>('bulk_update_location', { ids, location });
 }
@@ -261,8 +977,10 @@ export async function listComplianceRules() {
 }
 
 // WP-77: compliance flag waivers.
-export async function waiveComplianceFlag(flagType: string, specimenId: string, reason: string, expiresAt?: string) {
-  return call<void>('waive_compliance_flag', { flagType, specimenId, reason, expiresAt: expiresAt ?? null });
+export async function waiveComplianceFlag(
+  flagType: string, specimenId: string, reason: string, expiresAt: string | undefined, signature: SignatureCeremony,
+) {
+  return call<void>('waive_compliance_flag', { flagType, specimenId, reason, expiresAt: expiresAt ?? null, signature });
 }
 export async function listComplianceWaivers() {
   return call<any[]>('list_compliance_waivers');
@@ -359,12 +1077,13 @@ export async function importXlsx(payload: {
 }
 
 // Export
-export async function exportSpecimensCsv() {
-  return call<string>('export_specimens_csv');
+/** `filter` is a specimen filter expression (WP-97); omit it to export everything. */
+export async function exportSpecimensCsv(filter?: string) {
+  return call<string>('export_specimens_csv', { filter: filter || null });
 }
 
-export async function exportSpecimensJson() {
-  return call<string>('export_specimens_json');
+export async function exportSpecimensJson(filter?: string) {
+  return call<string>('export_specimens_json', { filter: filter || null });
 }
 
 // Inventory
@@ -535,8 +1254,8 @@ export async function deleteAttachment(id: string) {
 }
 
 // Work Queue
-export async function getWorkQueue() {
-  return call<any[]>('get_work_queue');
+export async function getWorkQueue(filter?: string) {
+  return call<any[]>('get_work_queue', { filter: filter || null });
 }
 
 // Strains (WP-28)
@@ -577,8 +1296,8 @@ export async function updateStrainStatus(request: {
   claimed_at?: string;
   confirmation_basis?: string;
   genomic_fingerprint?: string;
-}) {
-  return call<any>('update_strain_status', { request });
+}, signature?: SignatureCeremony) {
+  return call<any>('update_strain_status', { request, signature: signature ?? null });
 }
 
 export async function createHybridizationEvent(request: {
@@ -1120,6 +1839,167 @@ export async function getGenerationalSummary(programId: string) {
   return call<GenerationalSummary[]>('get_generational_summary', { programId });
 }
 
+// Treatment trials (WP-102)
+export type TrialStatus = 'draft' | 'running' | 'closed';
+export type OutcomeKind = 'number' | 'boolean';
+
+export interface TrialFactor {
+  name: string;
+  unit?: string | null;
+  levels: string[];
+}
+
+export interface TrialOutcome {
+  key: string;
+  label: string;
+  unit?: string | null;
+  kind: OutcomeKind;
+}
+
+export interface Trial {
+  id: string;
+  lab_profile: string;
+  name: string;
+  objective: string | null;
+  factors: TrialFactor[];
+  outcomes: TrialOutcome[];
+  replicates: number;
+  status: TrialStatus;
+  /** The seed of the current assignment, if any. */
+  seed: number | null;
+  /** The full factorial, first factor varying slowest. */
+  treatments: { index: number; label: string; levels: string[] }[];
+  /** Specimens assigned so far. */
+  units: number;
+  created_by: string | null;
+  created_at: string;
+  updated_at: string;
+  started_at: string | null;
+  closed_at: string | null;
+}
+
+export interface TrialUnit {
+  id: string;
+  specimen_id: string;
+  accession_number: string;
+  is_archived: boolean;
+  treatment: number;
+  replicate: number;
+  /** The specimen's current passage. */
+  passage: number;
+}
+
+export interface TrialObservation {
+  unit_id: string;
+  outcome: string;
+  passage: number;
+  value: number;
+  recorded_by: string | null;
+  recorded_at: string;
+}
+
+export interface TrialDetail {
+  trial: Trial;
+  units: TrialUnit[];
+  observations: TrialObservation[];
+}
+
+export interface Summary {
+  n: number;
+  mean: number;
+  sd: number | null;
+  se: number | null;
+  min: number;
+  max: number;
+}
+
+export interface AnovaRow {
+  term: string;
+  df: number;
+  ss: number;
+  ms: number | null;
+  f: number | null;
+  p: number | null;
+}
+
+export interface TukeyRow {
+  a: number;
+  b: number;
+  a_label: string;
+  b_label: string;
+  diff: number;
+  lower: number;
+  upper: number;
+  q: number;
+  p: number;
+  significant: boolean;
+}
+
+export interface OutcomeAnalysis {
+  outcome: string;
+  label: string;
+  kind: OutcomeKind;
+  n: number;
+  treatments: { treatment: number; label: string; summary: Summary | null }[];
+  one_way: { rows: AnovaRow[] } | null;
+  /** Only for trials with exactly two factors. */
+  two_way: { rows: AnovaRow[] } | null;
+  tukey: TukeyRow[];
+}
+
+export interface TrialAnalysis {
+  trial_id: string;
+  /** The passage analysed; null means each specimen's latest value. */
+  passage: number | null;
+  confidence: number;
+  outcomes: OutcomeAnalysis[];
+}
+
+export async function listTrials() {
+  return call<Trial[]>('list_trials');
+}
+
+export async function getTrial(id: string) {
+  return call<TrialDetail>('get_trial', { id });
+}
+
+/** Create a trial, or edit a draft. Redesigning a draft clears its assignment. */
+export async function saveTrial(request: {
+  id?: string;
+  name: string;
+  objective?: string | null;
+  factors: TrialFactor[];
+  outcomes: TrialOutcome[];
+  replicates: number;
+}) {
+  return call<{ trial: Trial; assignment_cleared: boolean }>('save_trial', { request });
+}
+
+export async function deleteTrial(id: string) {
+  return call<void>('delete_trial', { id });
+}
+
+/** Needs exactly treatments × replicates specimens. A seed reproduces a layout. */
+export async function assignTrialSpecimens(id: string, specimenIds: string[], seed?: number | null) {
+  return call<{ units: TrialUnit[]; seed: number }>('assign_trial_specimens', { id, specimenIds, seed });
+}
+
+export async function setTrialStatus(id: string, status: TrialStatus) {
+  return call<Trial>('set_trial_status', { id, status });
+}
+
+/** Values are recorded at each specimen's current passage. */
+export async function recordTrialObservations(
+  id: string,
+  observations: { specimen_id: string; outcome: string; value: number }[],
+) {
+  return call<number>('record_trial_observations', { id, observations });
+}
+
+export async function getTrialAnalysis(id: string, passage?: number | null) {
+  return call<TrialAnalysis>('get_trial_analysis', { id, passage });
+}
+
 // WP-49: Provisional taxa & Darwin Core export
 
 export interface TaxonMapping {
@@ -1316,9 +2196,22 @@ export async function listSyncPeers() {
 /** Sentinel value a masked field is replaced with. Matches db::permissions::RESTRICTED_MARKER. */
 export const RESTRICTED_MARKER = '[RESTRICTED]';
 
+/** WP-87: display text for a field that may be masked for the current role. */
+export function maskedText(value: unknown, empty = '—'): string {
+  if (value === RESTRICTED_MARKER) return '🔒 Restricted';
+  return value == null || value === '' ? empty : String(value);
+}
+
+/** WP-87: edit-form value for a field that may be masked. A masked field starts
+ *  blank, and a blank field leaves the stored value unchanged on save. */
+export function editableValue(value: unknown): string {
+  return value == null || value === RESTRICTED_MARKER ? '' : String(value);
+}
+
 export interface FieldPermission {
   id: string;
-  role: 'admin' | 'supervisor' | 'tech' | 'guest';
+  /** A built-in or custom role name (WP-86). */
+  role: string;
   entity_type: string;
   field_name: string;
   visible: boolean;
@@ -1511,6 +2404,63 @@ export async function getTechnicianActivity(timeRange: AnalyticsTimeRange) {
   );
 }
 
+// WP-103: contamination root-cause analysis.
+export type ContaminationFactor = 'media_batch' | 'technician' | 'location' | 'vessel_type' | 'hood' | 'date';
+export type ContaminationAttribution = 'passage' | 'previous';
+
+export interface ContaminationCase {
+  subculture_id: string;
+  specimen_id: string;
+  accession_number: string;
+  date: string;
+  contaminant_type: string | null;
+}
+
+export interface ContaminationLevelOdds {
+  factor: ContaminationFactor;
+  level: string;
+  label: string;
+  passages: number;
+  contaminated: number;
+  rate_pct: number;
+  expected: number;
+  odds: { odds_ratio: number; lower: number; upper: number } | null;
+  p: number | null;
+  q: number | null;
+  significant: boolean;
+  cases: ContaminationCase[];
+}
+
+export interface ContaminationSources {
+  since: string | null;
+  attribution: ContaminationAttribution;
+  contaminant_type: string | null;
+  contaminant_types: string[];
+  passages: number;
+  contaminated: number;
+  rate_pct: number;
+  confidence: number;
+  factors: Array<{
+    factor: ContaminationFactor;
+    label: string;
+    recorded: number;
+    unrecorded: number;
+    levels: ContaminationLevelOdds[];
+  }>;
+  ranked: ContaminationLevelOdds[];
+  withheld: ContaminationFactor[];
+}
+
+export async function getContaminationSources(
+  timeRange: AnalyticsTimeRange,
+  contaminantType?: string,
+  attribution?: ContaminationAttribution,
+) {
+  return call<ContaminationSources>('get_contamination_sources', {
+    timeRange, contaminantType: contaminantType || null, attribution: attribution ?? null,
+  });
+}
+
 export async function getAnalyticsKpiSummary() {
   return call<{
     total_active_specimens: number; passages_this_week: number; contamination_rate_this_month_pct: number;
@@ -1793,6 +2743,10 @@ export interface CheckpointAnchor {
   created_by: string | null;
   created_at: string;
   updated_at: string;
+  broadcast_via: 'manual' | 'node_rpc';
+  confirmations: number | null;
+  block_hash: string | null;
+  last_polled_at: string | null;
 }
 
 export interface AnchorPayloadPreview {
@@ -1833,6 +2787,86 @@ export async function listCheckpointAnchors(checkpointId?: string) {
   return call<CheckpointAnchor[]>('list_checkpoint_anchors', { checkpointId });
 }
 
+// ── WP-82: broadcast through the lab's own node ─────────────────────────────
+
+export interface AnchorNodeConfig {
+  enabled: boolean;
+  rpc_url: string | null;
+  username: string | null;
+  password_set: boolean;
+  wallet: string | null;
+  min_confirmations: number;
+}
+
+export interface AnchorNodeInfo {
+  chain: string;
+  blocks: number;
+  wallet_balance: number | null;
+}
+
+export interface AnchorPollResult {
+  anchor_id: string;
+  txid: string | null;
+  confirmations: number | null;
+  block_hash: string | null;
+  confirmed: boolean;
+  message: string;
+}
+
+export async function getAnchorNodeConfig() {
+  return call<AnchorNodeConfig>('get_anchor_node_config');
+}
+
+/** `password` undefined keeps the stored password. */
+export async function setAnchorNodeConfig(request: {
+  enabled: boolean;
+  rpc_url: string;
+  username?: string | null;
+  password?: string | null;
+  wallet?: string | null;
+  min_confirmations: number;
+}) {
+  return call<AnchorNodeConfig>('set_anchor_node_config', { request });
+}
+
+export async function testAnchorNode() {
+  return call<AnchorNodeInfo>('test_anchor_node');
+}
+
+export async function broadcastCheckpointAnchor(anchorId: string) {
+  return call<CheckpointAnchor>('broadcast_checkpoint_anchor', { anchorId });
+}
+
+export async function pollCheckpointAnchors() {
+  return call<AnchorPollResult[]>('poll_checkpoint_anchors');
+}
+
+// WP-83: SPV inclusion proofs for confirmed anchors.
+export interface SpvVerification {
+  anchor_id: string;
+  ok: boolean;
+  txid_ok: boolean;
+  anchored_root: string | null;
+  root_ok: boolean;
+  merkle_ok: boolean;
+  header_hash_ok: boolean;
+  pow_ok: boolean;
+  /** The header's bits are no easier than the chain's mainnet minimum. */
+  work_ok: boolean;
+  pow_algorithm: 'sha256d' | 'scrypt';
+  auxpow: boolean;
+  bits: string;
+  message: string;
+}
+
+export async function captureAnchorSpvProof(anchorId: string) {
+  return call<SpvVerification>('capture_anchor_spv_proof', { anchorId });
+}
+
+export async function verifyAnchorSpvProof(anchorId: string) {
+  return call<SpvVerification>('verify_anchor_spv_proof', { anchorId });
+}
+
 // ── WP-67: Trust Layer Phase 3 — signed-event ledger ─────────────────────────
 
 export interface SignedEvent {
@@ -1856,6 +2890,7 @@ export interface LedgerVerification {
   signatures_valid: number;
   first_break_seq: number | null;
   message: string;
+  pending_witness: WitnessStatus[];
 }
 
 export async function getUserSigningPublicKey() {
@@ -1876,6 +2911,103 @@ export async function verifySignedEventLedger() {
   return call<LedgerVerification>('verify_signed_event_ledger');
 }
 
+// ── WP-80: 21 CFR Part 11 electronic signatures ─────────────────────────────
+
+export type SignatureMeaning = 'authored' | 'reviewed' | 'approved';
+
+export interface SignatureCeremony {
+  password: string;
+  meaning: SignatureMeaning;
+  reason?: string | null;
+}
+
+export interface SignableAction {
+  action: string;
+  label: string;
+  entity_type: string;
+  required: boolean;
+  meanings: SignatureMeaning[];
+}
+
+export interface ElectronicSignature {
+  id: string;
+  signed_event_id: string;
+  user_id: string;
+  printed_name: string;
+  meaning: SignatureMeaning;
+  action: string;
+  entity_type: string;
+  entity_id: string;
+  reason: string | null;
+  signed_at: string;
+}
+
+export async function listSignableActions() {
+  return call<SignableAction[]>('list_signable_actions');
+}
+
+export async function signRecord(entityType: string, entityId: string, signature: SignatureCeremony) {
+  return call<ElectronicSignature>('sign_record', { entityType, entityId, signature });
+}
+
+export async function listElectronicSignatures(entityType: string, entityId: string) {
+  return call<ElectronicSignature[]>('list_electronic_signatures', { entityType, entityId });
+}
+
+// ── WP-81: supervisor countersignatures ──────────────────────────────────────
+
+export interface WitnessPolicy {
+  id: string;
+  event_type: string;
+  match_field: string | null;
+  match_value: string | null;
+  label: string;
+  required_count: number;
+  allowed_roles: string[];
+  enabled: boolean;
+}
+
+export interface Countersignature {
+  signed_event_id: string;
+  user_id: string;
+  printed_name: string;
+  role: string;
+  meaning: SignatureMeaning;
+  signed_at: string;
+}
+
+export interface WitnessStatus {
+  requirement_event_id: string;
+  event_id: string;
+  event_seq: number | null;
+  event_type: string;
+  entity_type: string;
+  entity_id: string | null;
+  signed_by: string | null;
+  label: string;
+  required_count: number;
+  allowed_roles: string[];
+  witnesses: Countersignature[];
+  rejected: string[];
+  satisfied: boolean;
+}
+
+export async function listPendingWitness() {
+  return call<WitnessStatus[]>('list_pending_witness');
+}
+
+export async function countersignEvent(eventId: string, signature: SignatureCeremony) {
+  return call<ElectronicSignature>('countersign_event', { eventId, signature });
+}
+
+export async function listWitnessPolicies() {
+  return call<WitnessPolicy[]>('list_witness_policies');
+}
+
+export async function saveWitnessPolicy(policy: WitnessPolicy) {
+  return call<WitnessPolicy>('save_witness_policy', { policy });
+}
+
 // ── WP-68: Regulatory submission pipeline ────────────────────────────────────
 
 export interface ReadinessCheck {
@@ -1930,8 +3062,8 @@ export async function reevaluateSubmission(submissionId: string) {
   return call<RegulatorySubmission>('reevaluate_submission', { submissionId });
 }
 
-export async function generateSubmissionPackage(submissionId: string) {
-  return call<RegulatorySubmission>('generate_submission_package', { submissionId });
+export async function generateSubmissionPackage(submissionId: string, signature: SignatureCeremony) {
+  return call<RegulatorySubmission>('generate_submission_package', { submissionId, signature });
 }
 
 export async function markSubmissionSubmitted(submissionId: string, reference: string) {
@@ -2040,8 +3172,8 @@ export async function setLabName(name: string) {
   return call<void>('set_lab_name', { name });
 }
 
-export async function issueSpecimenPassport(specimenId: string) {
-  return call<SpecimenPassport>('issue_specimen_passport', { specimenId });
+export async function issueSpecimenPassport(specimenId: string, signature: SignatureCeremony) {
+  return call<SpecimenPassport>('issue_specimen_passport', { specimenId, signature });
 }
 
 export async function verifySpecimenPassport(passportJson: string) {
@@ -0,0 +1,25 @@
+import { describe, it, expect } from 'vitest';
+import { AppError, toAppError, invalidField } from './errors';
+
+describe('errors', () => {
+  it('reads the serialized command error', () => {
+    const e = toAppError({ code: 'validation', message: 'Name is required', params: { field: 'name' } });
+    expect(e).toBeInstanceOf(AppError);
+    expect(e).toBeInstanceOf(Error);
+    expect([e.code, e.message, e.params]).toEqual(['validation', 'Name is required', { field: 'name' }]);
+    expect(invalidField(e)).toBe('name');
+  });
+
+  it('reads the local API body, whose message is under `error`', () => {
+    const e = toAppError({ error: 'Session expired or invalid', code: 'unauthenticated', params: { reason: 'session_expired' } });
+    expect([e.code, e.message, e.params.reason]).toEqual(['unauthenticated', 'Session expired or invalid', 'session_expired']);
+    expect(invalidField(e)).toBeNull();
+  });
+
+  it('treats anything without a known code as internal', () => {
+    expect(toAppError('boom')).toMatchObject({ code: 'internal', message: 'boom' });
+    expect(toAppError(new Error('offline'))).toMatchObject({ code: 'internal', message: 'offline' });
+    expect(toAppError({ code: 'teapot', message: 'x' }, 'Login failed')).toMatchObject({ code: 'internal', message: 'Login failed' });
+    expect(toAppError(undefined, 'Login failed').message).toBe('Login failed');
+  });
+});
@@ -0,0 +1,59 @@
+// WP-93: the typed error every command rejects with.
+//
+// A command's `Err` arrives as `{ code, message, params }` (from the local API,
+// as `{ error, code, params }`). The code is stable and the params say which
+// field, capability or record it is about, so screens can react to an error
+// and word it themselves instead of matching on the English message, which is
+// kept as the fallback text. See docs/error-codes.md.
+
+export type ErrorCode =
+  | 'unauthenticated'
+  | 'forbidden'
+  | 'not_found'
+  | 'validation'
+  | 'conflict'
+  | 'integrity'
+  | 'external_service'
+  | 'internal';
+
+const CODES: readonly string[] = [
+  'unauthenticated', 'forbidden', 'not_found', 'validation',
+  'conflict', 'integrity', 'external_service', 'internal',
+];
+
+export class AppError extends Error {
+  readonly code: ErrorCode;
+  /** e.g. `field`, `capability`, `reason`, `entity`, `id`, `service`. */
+  readonly params: Record<string, string>;
+
+  constructor(code: ErrorCode, message: string, params: Record<string, string> = {}) {
+    super(message);
+    this.name = 'AppError';
+    this.code = code;
+    this.params = params;
+  }
+}
+
+function isCode(value: unknown): value is ErrorCode {
+  return typeof value === 'string' && CODES.includes(value);
+}
+
+/**
+ * Whatever a command or request rejected with, as an `AppError`. A bare
+ * string or `Error` (a network failure, a plugin) becomes `internal`.
+ */
+export function toAppError(e: unknown, fallback = 'Unknown error'): AppError {
+  if (e instanceof AppError) return e;
+  if (e && typeof e === 'object' && isCode((e as any).code)) {
+    const { code, message, error, params } = e as any;
+    return new AppError(code, String(message ?? error ?? fallback), params ?? {});
+  }
+  if (typeof e === 'string') return new AppError('internal', e);
+  if (e instanceof Error) return new AppError('internal', e.message);
+  return new AppError('internal', fallback);
+}
+
+/** The field a `validation` error names, for highlighting it in a form. */
+export function invalidField(e: unknown): string | null {
+  return e instanceof AppError && e.code === 'validation' ? e.params.field ?? null : null;
+}
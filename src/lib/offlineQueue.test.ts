@@ -1,5 +1,5 @@
 import { describe, it, expect, vi } from 'vitest';
-import { enqueue, replayInOrder, type QueuedMutation } from './offlineQueue';
+import { enqueue, replayInOrder, httpInvoker, type QueuedMutation } from './offlineQueue';
 
 describe('offlineQueue', () => {
   it('enqueue appends a mutation preserving FIFO order', () => {
@@ -56,4 +56,32 @@ describe('offlineQueue', () => {
     expect(invoke).not.toHaveBeenCalled();
     expect(result).toEqual({ succeededIds: [], remaining: [], firstError: null });
   });
+
+  it('httpInvoker posts the queued args to the local API with a bearer token', async () => {
+    const fetchImpl = vi.fn(async () => new Response(JSON.stringify({ id: 's1' }), { status: 201 }));
+    const invoke = httpInvoker('http://lab-pc:8470/', 'tok', fetchImpl as unknown as typeof fetch);
+
+    expect(await invoke('create_specimen', { request: { stage: 'initiation' }, token: 'stale' })).toEqual({ id: 's1' });
+    const [url, init] = fetchImpl.mock.calls[0] as unknown as [string, RequestInit];
+    expect(url).toBe('http://lab-pc:8470/api/v1/invoke/create_specimen');
+    expect((init.headers as Record<string, string>).Authorization).toBe('Bearer tok');
+    expect(JSON.parse(init.body as string)).toEqual({ request: { stage: 'initiation' } });
+  });
+
+  it('httpInvoker turns an API error into a thrown Error so replay stops there', async () => {
+    const fetchImpl = vi.fn(async () => new Response(JSON.stringify({ error: 'Specimen not found' }), { status: 404 }));
+    const invoke = httpInvoker('http://lab-pc:8470', 'tok', fetchImpl as unknown as typeof fetch);
+    await expect(invoke('update_specimen', {})).rejects.toThrow('Specimen not found');
+  });
+
+  it('httpInvoker keeps the error code and params the API sends', async () => {
+    const body = { error: 'Specimen not found', code: 'not_found', params: { entity: 'specimen', id: 's9' } };
+    const fetchImpl = vi.fn(async () => new Response(JSON.stringify(body), { status: 404 }));
+    const invoke = httpInvoker('http://lab-pc:8470', 'tok', fetchImpl as unknown as typeof fetch);
+    await expect(invoke('update_specimen', {})).rejects.toMatchObject({
+      message: 'Specimen not found',
+      code: 'not_found',
+      params: { entity: 'specimen', id: 's9' },
+    });
+  });
 });
@@ -11,15 +11,15 @@
 // `enqueueMutation`/`getQueuedMutations`/etc. wrappers below are the actual
 // IndexedDB-backed persistence used by the running app.
 //
-// Honest scope note: replaying a mutation still calls the same Tauri
-// `invoke()` used everywhere else in this app. In a pure browser PWA
-// context (no Tauri runtime), there is currently no remote HTTP endpoint
-// for `invoke()` to reach — SteloPTC's entire command layer is Tauri IPC
-// only today. This queue is therefore a tested, ready-to-wire mechanism;
-// making offline mutations actually reach a live backend from a
-// browser-only PWA install requires a remote API layer that does not exist
-// yet (the same "foundation now, transport later" pattern already used for
-// WP-50's PostgreSQL connector and WP-59's S3/SFTP targets).
+// Replaying needs a backend to reach. In the desktop app that is the same
+// Tauri `invoke()` used everywhere else. A browser-only PWA install has no
+// Tauri runtime; since WP-90 it replays through the desktop app's local REST
+// API instead, with `httpInvoker` below, which calls
+// `POST /api/v1/invoke/{command}` with the queued arguments unchanged. Only
+// the commands the API routes are accepted; anything else fails the replay
+// at that mutation, as any other error would.
+
+import { toAppError } from './errors';
 
 export interface QueuedMutation {
   id: number;
@@ -73,6 +73,35 @@ export async function replayInOrder(
   return { succeededIds, remaining: [], firstError: null };
 }
 
+/**
+ * WP-90: an `invoke` for `replayInOrder` that calls the local REST API at
+ * `baseUrl` (e.g. `http://192.168.1.20:8470`). The token goes in the
+ * Authorization header; a `token` in the queued args is dropped. A non-2xx
+ * reply throws with the API's `error` text.
+ */
+export function httpInvoker(
+  baseUrl: string,
+  token: string,
+  fetchImpl: typeof fetch = fetch,
+): (command: string, args: Record<string, unknown>) => Promise<unknown> {
+  const base = baseUrl.replace(/\/+$/, '');
+  return async (command, args) => {
+    const { token: _dropped, ...rest } = args;
+    const response = await fetchImpl(`${base}/api/v1/invoke/${encodeURIComponent(command)}`, {
+      method: 'POST',
+      headers: { Authorization: `Bearer ${token}`, 'Content-Type': 'application/json' },
+      body: JSON.stringify(rest),
+    });
+    if (response.status === 204) return null;
+    const body = await response.json().catch(() => null);
+    if (!response.ok) {
+      // WP-93: the body is `{ error, code, params }`.
+      throw body?.code ? toAppError(body) : new Error(body?.error ?? `HTTP ${response.status}`);
+    }
+    return body;
+  };
+}
+
 // ── IndexedDB-backed persistence (used by the running app) ──────────────────
 
 const DB_NAME = 'stelo_offline_queue';
@@ -1,6 +1,6 @@
 import { writable } from 'svelte/store';
 
-export type View = 'dashboard' | 'specimens' | 'specimen-detail' | 'media' | 'reminders' | 'compliance' | 'species' | 'inventory' | 'users' | 'audit' | 'error-log' | 'export' | 'import' | 'settings' | 'work-queue' | 'taxonomy' | 'ncbi-sync' | 'cryo' | 'breeding' | 'provisional-taxa' | 'analytics' | 'lab-map' | 'fruiting';
+export type View = 'dashboard' | 'specimens' | 'specimen-detail' | 'media' | 'reminders' | 'compliance' | 'species' | 'inventory' | 'users' | 'audit' | 'error-log' | 'export' | 'import' | 'settings' | 'work-queue' | 'taxonomy' | 'ncbi-sync' | 'cryo' | 'breeding' | 'provisional-taxa' | 'analytics' | 'trials' | 'lab-map' | 'fruiting';
 
 export const currentView = writable<View>('dashboard');
 export const selectedSpecimenId = writable<string | null>(null);
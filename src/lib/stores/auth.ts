@@ -7,6 +7,10 @@ export interface User {
   email: string | null;
   role: string;
   is_active: boolean;
+  /** WP-85: 'ldap' for accounts that sign in through the directory. */
+  auth_source?: string;
+  /** WP-86: end of a time-boxed account's access (UTC), if any. */
+  access_expires_at?: string | null;
 }
 
 function getStoredToken(): string | null {
@@ -24,6 +28,13 @@ export const isLoggedIn = derived(token, ($token) => $token !== null);
 export const initializing = writable<boolean>(getStoredToken() !== null);
 // Set to true after login when the backend signals must_change_password
 export const mustChangePassword = writable<boolean>(false);
+// Set when the user's role requires two-factor authentication and they have
+// not enrolled yet (WP-84); the app shows only the enrollment screen.
+export const mustEnrollMfa = writable<boolean>(false);
+// Capability keys held by the user's role (WP-86), loaded after sign-in. The
+// backend enforces them; the UI only uses them to hide what would be refused.
+export const capabilities = writable<string[]>([]);
+export const can = derived(capabilities, ($caps) => (key: string) => $caps.includes(key));
 
 token.subscribe((value) => {
   try {
@@ -37,10 +48,11 @@ token.subscribe((value) => {
   }
 });
 
-export function setAuth(newToken: string, user: User, forceChange = false) {
+export function setAuth(newToken: string, user: User, forceChange = false, enrollMfa = false) {
   token.set(newToken);
   currentUser.set(user);
   mustChangePassword.set(forceChange);
+  mustEnrollMfa.set(enrollMfa);
   initializing.set(false);
 }
 
@@ -48,5 +60,7 @@ export function clearAuth() {
   token.set(null);
   currentUser.set(null);
   mustChangePassword.set(false);
+  mustEnrollMfa.set(false);
+  capabilities.set([]);
   initializing.set(false);
 }
@@ -0,0 +1,19 @@
+import { writable } from 'svelte/store';
+import type { SignatureCeremony } from '../api';
+
+// WP-80: a single Part 11 signature dialog, mounted once in App.svelte. A
+// component that runs a critical action awaits `requestSignature(action)`; the
+// dialog resolves it with the ceremony, or null if the user cancels.
+export interface SignatureRequest {
+  action: string;
+  subject: string;
+  resolve: (ceremony: SignatureCeremony | null) => void;
+}
+
+export const signatureRequest = writable<SignatureRequest | null>(null);
+
+export function requestSignature(action: string, subject: string): Promise<SignatureCeremony | null> {
+  return new Promise((resolve) => {
+    signatureRequest.set({ action, subject, resolve });
+  });
+}